@@ -97,6 +97,7 @@ const EMPTY: NodeTags = NodeTags {
 pub struct Annotations {
     pub tags: HashMap<NodeQId, NodeTags>,
     pub profile_summary: Option<crate::profile::ProfileSummary>,
+    pub memory_plan: Option<tract_core::model::memory::MemoryPlan>,
 }
 
 impl Annotations {
@@ -266,6 +267,11 @@ impl Annotations {
             }
             Ok(())
         }
-        extract_costs_rec(self, model, &[], 1.into())
+        extract_costs_rec(self, model, &[], 1.into())?;
+        if let Some(model) = model.downcast_ref::<TypedModel>() {
+            let plan = SimplePlan::new(model)?;
+            self.memory_plan = Some(tract_core::model::memory::MemoryPlan::new(&plan)?);
+        }
+        Ok(())
     }
 }
@@ -45,6 +45,7 @@ mod optimize_check;
 mod params;
 mod profile;
 mod run;
+mod save;
 mod stream_check;
 mod tensor;
 mod terminal;
@@ -86,10 +87,10 @@ fn main() {
     (@arg model: +takes_value "Sets the model to use")
 
     (@arg format: -f +takes_value
-     "Hint the model format ('kaldi', 'onnx' or 'tf') instead of guess from extension.")
+     "Hint the model format ('kaldi', 'onnx', 'tf' or 'tract') instead of guess from extension.")
 
     (@arg input: -i --input +takes_value +multiple number_of_values(1)
-     "Set input shape and type (@file.pb or @file.npz:thing.npy or 3x4xi32).")
+     "Set input shape and type (@file.pb or @file.npz:thing.npy or 3x4xi32 or Nx3xTxf32 or N,max_len,f32).")
 
     (@arg const_input: --("const-input") +takes_value +multiple number_of_values(1)
      "Treat input as a Const (by name), retaining its value.")
@@ -118,6 +119,12 @@ fn main() {
     (@arg tf_initializer_output_node: --("tf-initializer-output-node") +takes_value +multiple number_of_values(1)
      "Set an initializer node")
 
+    (@arg tf_saved_model_tag: --("tf-saved-model-tag") +takes_value +multiple number_of_values(1)
+     "Select the meta graph of a SavedModel directory by tag (default: serve)")
+
+    (@arg tf_signature: --("tf-signature") +takes_value
+     "Use a SavedModel signature for inputs and outputs (default: serving_default)")
+
     (@arg output_node: --("output-node") +takes_value +multiple number_of_values(1)
      "Override output nodes name (auto-detects otherwise).")
 
@@ -238,6 +245,12 @@ fn main() {
         .long_about("Run the graph")
         .arg(Arg::with_name("dump").long("dump").help("Show output"))
         .arg(Arg::with_name("steps").long("steps").help("Show all inputs and outputs"))
+        .arg(
+            Arg::with_name("threads")
+                .takes_value(true)
+                .long("threads")
+                .help("Evaluate independent nodes concurrently on this many threads"),
+        )
         .arg(
             Arg::with_name("assert-output-bundle")
                 .takes_value(true)
@@ -269,6 +282,11 @@ fn main() {
         .long_about("Compare output of streamed and regular exec");
     app = app.subcommand(output_options(stream_check));
 
+    let save = clap::SubCommand::with_name("save")
+        .long_about("Saves the decluttered model in tract native format (reload it with -f tract).")
+        .arg(Arg::with_name("path").takes_value(true).required(true).help("Output file"));
+    app = app.subcommand(save);
+
     let matches = app.get_matches();
 
     let probe = if matches.is_present("readings") {
@@ -378,7 +396,20 @@ fn handle(matches: clap::ArgMatches, probe: Option<&Probe>) -> CliResult<()> {
         #[cfg(feature = "onnx")]
         {
             let onnx = tract_onnx::onnx();
-            let names = onnx.op_register.0.keys().sorted().into_iter().join(", ");
+            let names = onnx
+                .op_register
+                .0
+                .keys()
+                .map(|(domain, op)| {
+                    if domain.is_empty() {
+                        op.to_string()
+                    } else {
+                        format!("{}.{}", domain, op)
+                    }
+                })
+                .sorted()
+                .into_iter()
+                .join(", ");
             println!("Onnx:\n");
             println!("{}", names);
             println!("\n");
@@ -435,7 +466,12 @@ fn handle(matches: clap::ArgMatches, probe: Option<&Probe>) -> CliResult<()> {
             display_params_from_clap(&matches, m)?,
         ),
 
-        ("run", Some(m)) => run::handle(&params, m.is_present("dump"), m.is_present("steps")),
+        ("run", Some(m)) => run::handle(
+            &params,
+            m.is_present("dump"),
+            m.is_present("steps"),
+            m.value_of("threads").map(|s| s.parse::<usize>()).transpose()?,
+        ),
 
         ("optimize-check", Some(m)) => {
             optimize_check::handle(&params, display_params_from_clap(&matches, m)?)
@@ -445,6 +481,8 @@ fn handle(matches: clap::ArgMatches, probe: Option<&Probe>) -> CliResult<()> {
             stream_check::handle(&params, &display_params_from_clap(&matches, m)?)
         }
 
+        ("save", Some(m)) => save::handle(&params, m.value_of("path").unwrap()),
+
         ("", None) => dump::handle(
             &params,
             &display_params_from_clap(&matches, &clap::ArgMatches::default())?,
@@ -80,6 +80,14 @@ impl Parameters {
         Ok((filename, onnx_tc))
     }
 
+    fn format<'a>(matches: &'a clap::ArgMatches, filename: &std::path::Path) -> &'a str {
+        matches.value_of("format").unwrap_or(match filename.extension().and_then(|s| s.to_str()) {
+            Some("onnx") => "onnx",
+            Some("tract") => "tract",
+            _ => "tf",
+        })
+    }
+
     fn load_model(
         matches: &clap::ArgMatches,
         probe: Option<&Probe>,
@@ -88,13 +96,7 @@ impl Parameters {
         let need_graph =
             matches.is_present("proto") || matches.subcommand_name() == Some("compare-pbdir");
 
-        let format = matches.value_of("format").unwrap_or(
-            if filename.extension().and_then(|s| s.to_str()) == Some("onnx") {
-                "onnx"
-            } else {
-                "tf"
-            },
-        );
+        let format = Self::format(matches, filename);
         let triplet = match format {
             #[cfg(feature = "kaldi")]
             "kaldi" => {
@@ -118,7 +120,7 @@ impl Parameters {
                 info_usage("loaded framework (onnx)", probe);
                 let graph = onnx.proto_model_for_path(&filename)?;
                 info_usage("proto model loaded", probe);
-                let parsed = onnx.parse(&graph)?;
+                let parsed = onnx.parse(&graph, filename.parent())?;
                 if need_graph {
                     (SomeGraphDef::Onnx(graph, parsed.clone()), parsed.model, Option::<TfExt>::None)
                 } else {
@@ -129,12 +131,30 @@ impl Parameters {
             "tf" => {
                 let tf = tract_tensorflow::tensorflow();
                 info_usage("loaded framework (tf)", probe);
-                let mut graph = tf.proto_model_for_path(&filename)?;
-                info_usage("proto model loaded", probe);
-                if matches.is_present("determinize") {
-                    tract_tensorflow::Tensorflow::determinize(&mut graph)?;
-                }
-                let mut model_and_ext = tf.parse_graph(&graph)?;
+                let (graph, mut model_and_ext) = if filename.is_dir() {
+                    let tags = matches
+                        .values_of("tf_saved_model_tag")
+                        .map(|tags| tags.collect())
+                        .unwrap_or(vec!["serve"]);
+                    let mut saved = tf.open_saved_model_dir(&filename, &tags)?;
+                    info_usage("proto model loaded", probe);
+                    if matches.is_present("determinize") {
+                        if let Some(graph) = saved.meta_graph.graph_def.as_mut() {
+                            tract_tensorflow::Tensorflow::determinize(graph)?;
+                        }
+                    }
+                    let model_and_ext =
+                        tf.model_for_saved_model(&saved, matches.value_of("tf_signature"))?;
+                    (saved.meta_graph.graph_def.unwrap_or_default(), model_and_ext)
+                } else {
+                    let mut graph = tf.proto_model_for_path(&filename)?;
+                    info_usage("proto model loaded", probe);
+                    if matches.is_present("determinize") {
+                        tract_tensorflow::Tensorflow::determinize(&mut graph)?;
+                    }
+                    let model_and_ext = tf.parse_graph(&graph)?;
+                    (graph, model_and_ext)
+                };
                 model_and_ext.1.initializing_nodes = matches
                     .values_of("tf_initializer_output_node")
                     .map(|values| {
@@ -345,10 +365,13 @@ impl Parameters {
         Ok(input_values)
     }
 
+    /// Runs the preprocessing passes, starting either from an inference
+    /// model or from a typed one (as loaded from a tract file).
     fn pipeline(
         matches: &clap::ArgMatches,
         probe: Option<&readings_probe::Probe>,
-        raw_model: InferenceModel,
+        raw_model: Option<InferenceModel>,
+        typed_model: Option<TypedModel>,
         tf_model_extensions: Option<TfExt>,
     ) -> Result<(Arc<dyn Model>, Option<Arc<TypedModel>>, Option<Arc<PulsedModel>>), ModelError>
     {
@@ -358,8 +381,8 @@ impl Parameters {
         let concretize_stream_dim: Option<usize> =
             matches.value_of("concretize_stream_dim").map(|s| s.parse()).transpose()?;
 
-        let mut inference_model: Option<Arc<InferenceModel>> = Some(Arc::new(raw_model));
-        let mut typed_model: Option<Arc<TypedModel>> = None;
+        let mut inference_model: Option<Arc<InferenceModel>> = raw_model.map(Arc::new);
+        let mut typed_model: Option<Arc<TypedModel>> = typed_model.map(Arc::new);
         let mut pulsed_model: Option<Arc<PulsedModel>> = None;
 
         let stop_at = matches.value_of("pass").unwrap_or(if matches.is_present("optimize") {
@@ -397,15 +420,17 @@ impl Parameters {
             };
         };
 
-        stage!("load", inference_model -> inference_model, |m:InferenceModel| TractResult::Ok(m));
-        stage!("analyse", inference_model -> inference_model, 
-               |mut m:InferenceModel| { m.analyse(matches.is_present("analyse_fail_fast"))?; TractResult::Ok(m) });
-        if let Some(ext) = tf_model_extensions {
-            #[cfg(feature = "tf")]
-            stage!("tf-preproc", inference_model -> inference_model, |m:InferenceModel| ext.preproc(m));
+        if inference_model.is_some() {
+            stage!("load", inference_model -> inference_model, |m:InferenceModel| TractResult::Ok(m));
+            stage!("analyse", inference_model -> inference_model, 
+                   |mut m:InferenceModel| { m.analyse(matches.is_present("analyse_fail_fast"))?; TractResult::Ok(m) });
+            if let Some(ext) = tf_model_extensions {
+                #[cfg(feature = "tf")]
+                stage!("tf-preproc", inference_model -> inference_model, |m:InferenceModel| ext.preproc(m));
+            }
+            stage!("incorporate", inference_model -> inference_model, |m:InferenceModel| { m.incorporate()});
+            stage!("type", inference_model -> typed_model, |m:InferenceModel| m.into_typed());
         }
-        stage!("incorporate", inference_model -> inference_model, |m:InferenceModel| { m.incorporate()});
-        stage!("type", inference_model -> typed_model, |m:InferenceModel| m.into_typed());
         stage!("declutter", typed_model -> typed_model, |m:TypedModel| m.declutter());
         if let Some(dim) = concretize_stream_dim {
             stage!("concretize-stream-dim", typed_model -> typed_model, |m:TypedModel| m.concretize_stream_dim(dim) );
@@ -427,6 +452,9 @@ impl Parameters {
         probe: Option<&Probe>,
     ) -> Result<Parameters, ModelError> {
         let (filename, onnx_tc) = Self::disco_model(matches)?;
+        if Self::format(matches, &filename) == "tract" {
+            return Self::from_tract_file(matches, probe, &filename);
+        }
         let (mut graph, mut raw_model, tf_model_extensions) =
             Self::load_model(matches, probe, &filename)?;
 
@@ -511,7 +539,7 @@ impl Parameters {
             raw_model = raw_model.eliminate_dead_branches()?;
         }
 
-        Self::pipeline(matches, probe, raw_model, tf_model_extensions).map(
+        Self::pipeline(matches, probe, Some(raw_model), None, tf_model_extensions).map(
             |(tract_model, decluttered_model, pulsed_model)| {
                 info!("Model ready");
                 info_usage("model ready", probe);
@@ -531,6 +559,56 @@ impl Parameters {
     }
 }
 
+impl Parameters {
+    /// Loads a model saved in tract native format. It is already typed, so
+    /// the options tweaking the inference model do not apply, except for
+    /// input values from --input-bundle.
+    fn from_tract_file(
+        matches: &clap::ArgMatches,
+        probe: Option<&Probe>,
+        filename: &std::path::Path,
+    ) -> Result<Parameters, ModelError> {
+        let model = crate::save::registry().load_model(filename)?;
+        info!("Model {:?} loaded", filename);
+        info_usage("model loaded", probe);
+        let output_names: Vec<String> =
+            model.output_outlets()?.iter().map(|o| model.node(o.node).name.to_string()).collect();
+        let assertions = Assertions::from_clap(matches, &output_names)?;
+        let mut input_values = vec![None; model.input_outlets()?.len()];
+        if let Some(bundle) = matches.values_of("input_bundle") {
+            for input in bundle {
+                let mut npz = ndarray_npy::NpzReader::new(std::fs::File::open(input)?)?;
+                for (ix, outlet) in model.input_outlets()?.iter().enumerate() {
+                    let name = format!("{}.npy", model.node(outlet.node).name);
+                    if let Ok(t) = tensor::for_npz(&mut npz, &name) {
+                        input_values[ix] = Some(t.into_arc_tensor());
+                    }
+                }
+            }
+        }
+        Self::pipeline(matches, probe, None, Some(model), None).map(
+            |(tract_model, decluttered_model, pulsed_model)| {
+                info!("Model ready");
+                info_usage("model ready", probe);
+                Parameters {
+                    analyse_error: None,
+                    graph: SomeGraphDef::NoGraphDef,
+                    decluttered_model,
+                    pulsed_model,
+                    tract_model,
+                    #[cfg(feature = "conform")]
+                    tf_model: None,
+                    #[cfg(not(feature = "conform"))]
+                    tf_model: (),
+                    input_values,
+                    assertions,
+                    machine_friendly: matches.is_present("machine_friendly"),
+                }
+            },
+        )
+    }
+}
+
 pub struct BenchLimits {
     pub max_iters: usize,
     pub max_time: std::time::Duration,
@@ -2,11 +2,16 @@ use crate::errors::*;
 use crate::{Model, Parameters};
 use tract_hir::internal::*;
 
-pub fn handle(params: &Parameters, dump: bool, steps: bool) -> CliResult<()> {
+pub fn handle(
+    params: &Parameters,
+    dump: bool,
+    steps: bool,
+    threads: Option<usize>,
+) -> CliResult<()> {
     let outputs = if let Some(pulse) = params.tract_model.downcast_ref::<PulsedModel>() {
         run_pulse_t(pulse, &params)?
     } else {
-        dispatch_model!(params.tract_model, |m| run_regular(m, &params, steps))?
+        dispatch_model!(params.tract_model, |m| run_regular(m, &params, steps, threads))?
     };
 
     if dump {
@@ -31,6 +36,7 @@ fn run_regular(
     tract: &dyn Model,
     params: &Parameters,
     steps: bool,
+    threads: Option<usize>,
 ) -> CliResult<TVec<Arc<Tensor>>> {
     let mut inputs: TVec<Tensor> = tvec!();
     for (ix, input) in tract.input_outlets().iter().enumerate() {
@@ -44,19 +50,40 @@ fn run_regular(
     dispatch_model!(tract, |m| {
         let plan = SimplePlan::new(m)?;
         let mut state = SimpleState::new(plan)?;
-        Ok(state.run_plan_with_eval(inputs, |session_state, state, node, input| {
-            if steps {
-                eprintln!("{}: <{:?}", node, input);
-            }
-            let r = tract_core::plan::eval(session_state, state, node, input);
-            if steps {
-                eprintln!("{}: >{:?}", node, r);
-            }
-            r
-        })?)
+        if let Some(threads) = threads {
+            let pool = tract_core::rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .map_err(|e| format!("{}", e))?;
+            return Ok(state.run_in_pool_with_eval(inputs, &pool, |s, st, n, i| {
+                eval_step(steps, s, st, n, i)
+            })?);
+        }
+        Ok(state.run_plan_with_eval(inputs, |s, st, n, i| eval_step(steps, s, st, n, i))?)
     })
 }
 
+fn eval_step<F, O>(
+    steps: bool,
+    session_state: &mut SessionState,
+    state: Option<&mut (dyn OpState + 'static)>,
+    node: &BaseNode<F, O>,
+    input: TVec<Arc<Tensor>>,
+) -> TractResult<TVec<Arc<Tensor>>>
+where
+    F: Fact + Hash + Clone + 'static,
+    O: std::fmt::Debug + std::fmt::Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + Hash + 'static,
+{
+    if steps {
+        eprintln!("{}: <{:?}", node, input);
+    }
+    let r = tract_core::plan::eval(session_state, state, node, input);
+    if steps {
+        eprintln!("{}: >{:?}", node, r);
+    }
+    r
+}
+
 fn run_pulse_t(model: &PulsedModel, params: &Parameters) -> CliResult<TVec<Arc<Tensor>>> {
     let input_fact = model.input_fact(0)?;
     let output_fact = model.output_fact(0)?;
@@ -0,0 +1,25 @@
+use tract_core::internal::*;
+use tract_core::ser::Registry;
+
+use crate::{CliResult, Parameters};
+
+/// A registry knowing about the TypedOps of all the enabled frontends.
+pub fn registry() -> Registry {
+    #[allow(unused_mut)]
+    let mut registry = tract_hir::ser::registry();
+    #[cfg(feature = "onnx")]
+    tract_onnx::ser::register_onnx_ops(&mut registry);
+    #[cfg(feature = "tf")]
+    tract_tensorflow::ser::register_tensorflow_ops(&mut registry);
+    registry
+}
+
+pub fn handle(params: &Parameters, path: &str) -> CliResult<()> {
+    let model = params
+        .tract_model
+        .downcast_ref::<TypedModel>()
+        .ok_or("Final model is not Typed. (using --pass ?)")?;
+    registry().save_model(model, path)?;
+    info!("Model saved to {}", path);
+    Ok(())
+}
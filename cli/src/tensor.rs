@@ -10,17 +10,25 @@ pub fn parse_spec(size: &str) -> CliResult<InferenceFact> {
     if size.len() == 0 {
         return Ok(InferenceFact::default());
     }
-    let splits = size.split("x").collect::<Vec<_>>();
+    // Dimensions are separated by 'x' (3x224x224xf32), or by ',' when a symbol
+    // name contains an 'x' (N,max_len,f32).
+    let separator = if size.contains(',') { ',' } else { 'x' };
+    let splits = size.split(separator).map(|s| s.trim()).collect::<Vec<_>>();
 
     if splits.len() < 1 {
-        bail!("The <size> argument should be formatted as {size}x{...}x{type}.");
+        bail!("The <size> argument should be formatted as {size}x{...}x{type} or {size},{...},{type}.");
     }
 
-    let last = splits.last().unwrap();
-    let (datum_type, shape) = if last.ends_with("S") || last.parse::<i32>().is_ok() {
+    // Dimensions are integers or expressions of symbols (S, N, 2T+1...), so the
+    // last token is a type when it looks like one (f32, i8...).
+    let last = splits.last().unwrap().to_lowercase();
+    let looks_like_type = last.len() > 1
+        && ["f", "i", "u"].iter().any(|p| last.starts_with(p))
+        && last[1..].chars().all(|c| c.is_ascii_digit());
+    let (datum_type, shape) = if !looks_like_type {
         (None, &*splits)
     } else {
-        let datum_type = match splits.last().unwrap().to_lowercase().as_str() {
+        let datum_type = match &*last {
             "f64" => DatumType::F64,
             "f32" => DatumType::F32,
             "i32" => DatumType::I32,
@@ -174,16 +182,20 @@ pub fn make_inputs_for_model(model: &dyn Model) -> CliResult<TVec<Tensor>> {
 pub fn tensor_for_fact(fact: &TypedFact, streaming_dim: Option<usize>) -> CliResult<Tensor> {
     if let Some(value) = &fact.konst {
         Ok(value.clone().into_tensor())
-    } else if fact.shape.stream_info.is_some() {
-        if let Some(dim) = streaming_dim {
-            let shape =
-                fact.shape.iter().map(|d| d.eval(dim as _).unwrap() as usize).collect::<TVec<_>>();
-            Ok(random(&shape, fact.datum_type))
-        } else {
-            bail!("random tensor requires a streaming dim")
-        }
+    } else if let Some(shape) = fact.shape.as_finite() {
+        Ok(random(shape, fact.datum_type))
+    } else if fact.shape.stream_info().is_some() && streaming_dim.is_none() {
+        bail!("random tensor requires a streaming dim")
     } else {
-        Ok(random(&fact.shape.as_finite().unwrap(), fact.datum_type))
+        let values =
+            SymbolValues::default().with(Symbol::stream(), streaming_dim.unwrap_or(0) as _);
+        let shape = fact
+            .shape
+            .iter()
+            .map(|d| Ok(d.eval_with(&values)? as usize))
+            .collect::<TractResult<TVec<_>>>()
+            .chain_err(|| format!("random tensor requires a concrete shape, got {:?}", fact))?;
+        Ok(random(&shape, fact.datum_type))
     }
 }
 
@@ -218,3 +230,28 @@ pub fn random(sizes: &[usize], datum_type: DatumType) -> Tensor {
         _ => panic!("Can generate random tensor for {:?}", datum_type),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_spec_with_x() {
+        let fact = parse_spec("1xNx3xf32").unwrap();
+        assert_eq!(fact.datum_type.concretize(), Some(DatumType::F32));
+        assert_eq!(fact.shape.concretize(), Some(tvec!(1.to_dim(), TDim::sym("N"), 3.to_dim())));
+    }
+
+    #[test]
+    fn parse_spec_with_commas() {
+        let fact = parse_spec("N, max_len, 2*seq.len+1, i8").unwrap();
+        assert_eq!(fact.datum_type.concretize(), Some(DatumType::I8));
+        assert_eq!(
+            fact.shape.concretize(),
+            Some(tvec!(TDim::sym("N"), TDim::sym("max_len"), TDim::sym("seq.len") * 2 + 1))
+        );
+        let fact = parse_spec("1,_,3").unwrap();
+        assert_eq!(fact.datum_type.concretize(), None);
+        assert_eq!(fact.shape.concretize(), None);
+    }
+}
@@ -293,6 +293,21 @@ pub fn render_summaries(
         for (c, i) in &total.cost {
             println!(" * {:?}: {}", c, render_tdim(i));
         }
+        if let Some(memory) = &annotations.memory_plan {
+            println!("{}", White.bold().paint("Memory summary"));
+            println!(" * Peak: {} bytes", render_big_integer(memory.peak as i64));
+            println!(
+                " * Arena: {} bytes for {} tensors",
+                render_big_integer(memory.arena_size as i64),
+                memory.tensors.len()
+            );
+            if memory.unplanned.len() > 0 {
+                println!(
+                    " * Not planned (symbolic shape or non-plain type): {} tensors",
+                    memory.unplanned.len()
+                );
+            }
+        }
     }
 
     if options.profile {
@@ -72,7 +72,11 @@ impl DatumType {
         } else if self.is_float() {
             [F16, F32, F64].iter().filter(|s| s.size_of() >= self.size_of()).copied().collect()
         } else if self.is_signed() {
-            [I8, I16, I32, I64, TDim].iter().filter(|s| s.size_of() >= self.size_of()).copied().collect()
+            [I8, I16, I32, I64, TDim]
+                .iter()
+                .filter(|s| s.size_of() >= self.size_of())
+                .copied()
+                .collect()
         } else {
             [U8, U16, U32, U64].iter().filter(|s| s.size_of() >= self.size_of()).copied().collect()
         }
@@ -146,6 +150,11 @@ impl DatumType {
         self.is_signed() || self.is_unsigned()
     }
 
+    /// Plain values, with no heap allocated content (unlike String, TDim, Blob).
+    pub fn is_copy(&self) -> bool {
+        *self == DatumType::Bool || self.is_integer() || self.is_float()
+    }
+
     pub fn size_of(&self) -> usize {
         match self {
             DatumType::Bool => std::mem::size_of::<bool>(),
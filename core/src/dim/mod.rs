@@ -2,8 +2,11 @@
 use std::fmt;
 use std::ops;
 
+mod parse;
+mod sym;
 mod tree;
 
+pub use self::sym::{Symbol, SymbolValues};
 pub use self::tree::TDim;
 use crate::{TractError, TractResult};
 
@@ -12,7 +15,7 @@ use crate::{TractError, TractResult};
 /// Implemented by:
 ///
 /// * `usize` for regular dimensions
-/// * `TDim` supporting regular and streaming dimensions
+/// * `TDim` supporting regular, streaming and other symbolic dimensions
 pub trait DimLike:
     Clone
     + Default
@@ -104,7 +107,7 @@ impl DimLike for TDim {
     }
 
     fn concretize_stream_dim(&self, stream_dim: usize) -> Self {
-        self.eval(stream_dim as _).unwrap().to_dim()
+        self.substitute(&SymbolValues::default().with(Symbol::stream(), stream_dim as _))
     }
 }
 
@@ -196,4 +199,18 @@ mod tests {
     fn div_sym_sym_rem() {
         assert!((TDim::s() + 1).maybe_div(&(TDim::s() * 4)).is_err());
     }
+
+    #[test]
+    fn div_two_symbols() {
+        let n = TDim::sym('N');
+        let t = TDim::sym('T');
+        assert_eq!((n.clone() * 6 + t.clone() * 6).maybe_div(&(n.clone() + &t)).unwrap(), (6.into(), 1));
+        assert!((n.clone() + &t).maybe_div(&n).is_err());
+    }
+
+    #[test]
+    fn concretize_stream_keeps_other_symbols() {
+        let d = TDim::s() * 2 + TDim::sym('N');
+        assert_eq!(d.concretize_stream_dim(3), TDim::sym('N') + 6);
+    }
 }
@@ -0,0 +1,188 @@
+//! Parsing of dimension expressions like `N`, `2S+1` or `(T-3)/2`.
+//!
+//! expr   := term (('+'|'-') term)*
+//! term   := factor ('*' factor | '/' integer)*
+//! factor := integer [ident] | ident | '(' expr ')' | '-' factor
+//! ident  := (letter | '_') (letter | digit | '_' | '.')*
+//!
+//! Symbols named after nodes contain dots (`loop.iters`), so '.' is part of
+//! identifiers and never a multiplication.
+use super::{DimLike, TDim};
+use crate::TractResult;
+use std::iter::Peekable;
+use std::str::Chars;
+
+pub(super) fn parse_tdim(s: &str) -> TractResult<TDim> {
+    let mut chars = s.chars().peekable();
+    let dim = expr(&mut chars).map_err(|e| format!("Invalid dimension {:?}: {}", s, e))?;
+    skip_spaces(&mut chars);
+    if let Some(c) = chars.next() {
+        bail!("Invalid dimension {:?}: unexpected {:?}", s, c)
+    }
+    Ok(dim)
+}
+
+fn skip_spaces(chars: &mut Peekable<Chars>) {
+    while chars.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+        chars.next();
+    }
+}
+
+fn next_if(chars: &mut Peekable<Chars>, expected: &[char]) -> Option<char> {
+    skip_spaces(chars);
+    match chars.peek() {
+        Some(c) if expected.contains(c) => chars.next(),
+        _ => None,
+    }
+}
+
+fn expr(chars: &mut Peekable<Chars>) -> TractResult<TDim> {
+    let mut dim = term(chars)?;
+    while let Some(op) = next_if(chars, &['+', '-']) {
+        let rhs = term(chars)?;
+        dim = if op == '+' { dim + rhs } else { dim - rhs };
+    }
+    Ok(dim)
+}
+
+fn term(chars: &mut Peekable<Chars>) -> TractResult<TDim> {
+    let mut dim = factor(chars)?;
+    while let Some(op) = next_if(chars, &['*', '/']) {
+        if op == '/' {
+            skip_spaces(chars);
+            let q = integer(chars)?;
+            if q <= 0 {
+                bail!("division by {}", q)
+            }
+            dim = dim / q as u32;
+        } else {
+            dim = dim.maybe_mul(&factor(chars)?)?;
+        }
+    }
+    Ok(dim)
+}
+
+fn factor(chars: &mut Peekable<Chars>) -> TractResult<TDim> {
+    skip_spaces(chars);
+    match chars.peek().cloned() {
+        Some('-') => {
+            chars.next();
+            Ok(-factor(chars)?)
+        }
+        Some('(') => {
+            chars.next();
+            let dim = expr(chars)?;
+            if next_if(chars, &[')']).is_none() {
+                bail!("expected ')'")
+            }
+            Ok(dim)
+        }
+        Some(c) if c.is_ascii_digit() => {
+            let value = integer(chars)?;
+            if chars.peek().map(|&c| is_ident_start(c)).unwrap_or(false) {
+                Ok(TDim::sym(ident(chars)) * value)
+            } else {
+                Ok(value.into())
+            }
+        }
+        Some(c) if is_ident_start(c) => Ok(TDim::sym(ident(chars))),
+        Some(c) => bail!("unexpected {:?}", c),
+        None => bail!("unexpected end of expression"),
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn ident(chars: &mut Peekable<Chars>) -> String {
+    let mut name = String::new();
+    while let Some(&c) = chars.peek() {
+        if c.is_alphanumeric() || c == '_' || c == '.' {
+            name.push(c);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    name
+}
+
+fn integer(chars: &mut Peekable<Chars>) -> TractResult<i32> {
+    let mut digits = String::new();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    Ok(digits.parse::<i32>().map_err(|e| format!("{:?}: {}", digits, e))?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn p(s: &str) -> TDim {
+        parse_tdim(s).unwrap()
+    }
+
+    #[test]
+    fn parse_int() {
+        assert_eq!(p("12"), 12.into());
+        assert_eq!(p("-3"), (-3).into());
+    }
+
+    #[test]
+    fn parse_stream() {
+        assert_eq!(p("S"), TDim::s());
+        assert_eq!(p("3S"), TDim::s() * 3);
+        assert_eq!(p("3*S"), TDim::s() * 3);
+    }
+
+    #[test]
+    fn parse_named() {
+        assert_eq!(p("N"), TDim::sym("N"));
+        assert_eq!(p("batch_size"), TDim::sym("batch_size"));
+        assert_eq!(p("2*N + T - 1"), TDim::sym('N') * 2 + TDim::sym('T') - 1);
+    }
+
+    #[test]
+    fn parse_dotted_names() {
+        assert_eq!(p("loop.iters"), TDim::sym("loop.iters"));
+        assert_eq!(p("2loop.iters"), TDim::sym("loop.iters") * 2);
+        assert_eq!(p("3 * if_1.0.2 + 1"), TDim::sym("if_1.0.2") * 3 + 1);
+        assert_eq!(p("(node.count-1)/2"), (TDim::sym("node.count") - 1) / 2);
+    }
+
+    #[test]
+    fn parse_div() {
+        assert_eq!(p("(S+1)/2"), (TDim::s() + 1) / 2);
+        assert_eq!(p("(3-T)/2"), (TDim::from(3) - TDim::sym('T')) / 2);
+    }
+
+    #[test]
+    fn parse_display_roundtrip() {
+        for d in &[
+            TDim::s() * -2 + 1,
+            (TDim::sym('T') + 5) / 3,
+            TDim::sym('N') * 4 + TDim::sym('T') - 7,
+            TDim::sym("node.count") * 3 + TDim::sym("loop.iters"),
+        ] {
+            assert_eq!(&p(&d.to_string()), d);
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse_tdim("N*T").is_err());
+        assert!(parse_tdim("N+").is_err());
+        assert!(parse_tdim("(N").is_err());
+        assert!(parse_tdim("N/0").is_err());
+        assert!(parse_tdim("N?").is_err());
+        assert!(parse_tdim("3.S").is_err());
+        assert!(parse_tdim(".N").is_err());
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// A named variable in dimension expressions.
+///
+/// `S` is reserved for the streaming dimension. Other names (`N`, `T`,
+/// `batch`...) can be used for any dimension only known at runtime.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    pub fn new(name: &str) -> Symbol {
+        Symbol(name.into())
+    }
+
+    /// The streaming symbol, `S`.
+    pub fn stream() -> Symbol {
+        Symbol::from('S')
+    }
+
+    pub fn as_str(&self) -> &str {
+        &*self.0
+    }
+}
+
+impl From<char> for Symbol {
+    fn from(c: char) -> Symbol {
+        Symbol::new(c.encode_utf8(&mut [0u8; 4]))
+    }
+}
+
+impl<'a> From<&'a str> for Symbol {
+    fn from(s: &'a str) -> Symbol {
+        Symbol::new(s)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(s: String) -> Symbol {
+        Symbol(s.into())
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.0)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.0)
+    }
+}
+
+/// Values given to symbols for evaluating dimension expressions.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SymbolValues(HashMap<Symbol, i32>);
+
+impl SymbolValues {
+    pub fn with(mut self, symbol: impl Into<Symbol>, value: i32) -> SymbolValues {
+        self.insert(symbol, value);
+        self
+    }
+
+    pub fn insert(&mut self, symbol: impl Into<Symbol>, value: i32) {
+        self.0.insert(symbol.into(), value);
+    }
+
+    pub fn get(&self, symbol: &Symbol) -> Option<i32> {
+        self.0.get(symbol).cloned()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Symbol, i32)> {
+        self.0.iter().map(|(s, v)| (s, *v))
+    }
+}
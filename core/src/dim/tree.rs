@@ -1,14 +1,15 @@
+use super::sym::{Symbol, SymbolValues};
 use crate::prelude::TractResult;
 use itertools::Itertools;
 use num_traits::{AsPrimitive, Zero};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::{fmt, ops};
 
 macro_rules! b( ($e:expr) => { Box::new($e) } );
 
 #[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Hash, Debug)]
 pub enum TDim {
-    Sym(char),
+    Sym(Symbol),
     Val(i32),
     Add(Vec<TDim>),
     Mul(i32, Box<TDim>),
@@ -23,7 +24,8 @@ impl fmt::Display for TDim {
             Sym(it) => write!(fmt, "{}", it),
             Val(it) => write!(fmt, "{}", it),
             Add(it) => write!(fmt, "{}", it.iter().map(|x| format!("{}", x)).join("+")),
-            Mul(a, b) => write!(fmt, "{}.{}", a, b),
+            Mul(a, b) if matches!(**b, Add(_)) => write!(fmt, "{}*({})", a, b),
+            Mul(a, b) => write!(fmt, "{}*{}", a, b),
             Div(a, b) => write!(fmt, "({})/{}", a, b),
         }
     }
@@ -36,7 +38,12 @@ impl TDim {
 
     /// The special value S, for streaming.
     pub fn s() -> TDim {
-        TDim::Sym('S')
+        TDim::Sym(Symbol::stream())
+    }
+
+    /// A named symbol, like `N` for a dynamic batch size.
+    pub fn sym(name: impl Into<Symbol>) -> TDim {
+        TDim::Sym(name.into())
     }
 
     /// The special value S, for streaming.
@@ -44,26 +51,38 @@ impl TDim {
         Self::s()
     }
 
-    /// Try to convert the value to an integer, if it does not contains S.
+    /// Try to convert the value to an integer, if it does not contains any symbol.
     pub fn as_const(&self) -> Option<i32> {
         self.to_integer().ok()
     }
 
+    /// Does the expression depend on the streaming symbol S.
     pub fn is_stream(&self) -> bool {
-        self.to_integer().is_err()
+        self.symbols().contains(&Symbol::stream())
+    }
+
+    /// Symbols appearing in the expression.
+    pub fn symbols(&self) -> HashSet<Symbol> {
+        match self {
+            Sym(s) => std::iter::once(s.clone()).collect(),
+            Val(_) => HashSet::new(),
+            Add(terms) => terms.iter().flat_map(|t| t.symbols()).collect(),
+            Mul(_, a) | Div(a, _) => a.symbols(),
+        }
     }
 
     pub fn to_integer(&self) -> TractResult<i32> {
-        self.eval_with(&hashmap!())
+        self.eval_with(&SymbolValues::default())
     }
 
     pub fn eval(&self, s: i32) -> Option<i32> {
-        self.eval_with(&hashmap!('S' => s)).ok()
+        self.eval_with(&SymbolValues::default().with(Symbol::stream(), s)).ok()
     }
 
-    fn eval_with(&self, values: &HashMap<char, i32>) -> TractResult<i32> {
+    /// Evaluate the expression, failing if a symbol has no value.
+    pub fn eval_with(&self, values: &SymbolValues) -> TractResult<i32> {
         Ok(match self {
-            Sym(v) => *values.get(v).ok_or(format!("Unresolved value {:?}", v))?,
+            Sym(v) => values.get(v).ok_or(format!("Unresolved value {:?}", v))?,
             Val(v) => *v,
             Add(terms) => terms.iter().try_fold(0i32, |acc, it| -> TractResult<i32> {
                 Ok(acc + it.eval_with(values)?)
@@ -73,6 +92,17 @@ impl TDim {
         })
     }
 
+    /// Replace the symbols with a value in `values`, keeping the other ones.
+    pub fn substitute(&self, values: &SymbolValues) -> TDim {
+        match self {
+            Sym(v) => values.get(v).map(Val).unwrap_or_else(|| self.clone()),
+            Val(_) => self.clone(),
+            Add(terms) => Add(terms.iter().map(|t| t.substitute(values)).collect()).reduce(),
+            Mul(p, a) => Mul(*p, b!(a.substitute(values))).reduce(),
+            Div(a, q) => Div(b!(a.substitute(values)), *q).reduce(),
+        }
+    }
+
     pub fn reduce(self) -> TDim {
         self.simplify()
             .wiggle()
@@ -225,7 +255,14 @@ impl TDim {
                 } else if let Mul(-1, a) = a {
                     Mul(-1, b!(Div(a, q)))
                 } else if let Add(mut terms) = a {
-                    if terms.iter().any(|t| t == &Mul(-1, b!(Sym('S')))) {
+                    if terms.iter().any(|t| {
+                        if let Mul(-1, s) = t {
+                            if let Sym(_) = &**s {
+                                return true;
+                            }
+                        }
+                        false
+                    }) {
                         Mul(
                             -1,
                             b!(Div(
@@ -333,7 +370,7 @@ impl TDim {
                 Add(terms) => terms
                     .iter()
                     .map(slope_rec)
-                    .fold((1, 1), |a, b| ((a.0 * b.1 + a.1 * b.0), (b.1 * a.1))),
+                    .fold((0, 1), |a, b| ((a.0 * b.1 + a.1 * b.0), (b.1 * a.1))),
                 Mul(p, a) => {
                     let (n, d) = slope_rec(a);
                     (p * n, d)
@@ -530,18 +567,16 @@ impl<I: AsPrimitive<u32>> ops::Rem<I> for TDim {
     }
 }
 
+impl From<Symbol> for TDim {
+    fn from(s: Symbol) -> TDim {
+        TDim::Sym(s)
+    }
+}
+
 impl std::str::FromStr for TDim {
-    type Err = std::num::ParseIntError;
-    fn from_str(s: &str) -> Result<TDim, Self::Err> {
-        if s == "S" {
-            Ok(TDim::s())
-        } else if s.ends_with("S") {
-            let number: String = s.chars().take_while(|c| c.is_digit(10)).collect();
-            let number: i32 = number.parse::<i32>().map(|i| i.into())?;
-            Ok(TDim::s() * number)
-        } else {
-            s.parse::<i32>().map(|i| i.into())
-        }
+    type Err = crate::errors::TractError;
+    fn from_str(s: &str) -> TractResult<TDim> {
+        super::parse::parse_tdim(s)
     }
 }
 
@@ -551,7 +586,9 @@ mod tests {
 
     macro_rules! b( ($e:expr) => { Box::new($e) } );
 
-    const S: TDim = TDim::Sym('S');
+    fn s() -> TDim {
+        TDim::s()
+    }
 
     fn neg(a: &TDim) -> TDim {
         mul(-1, a)
@@ -571,73 +608,107 @@ mod tests {
 
     #[test]
     fn reduce_add() {
-        assert_eq!(add(&Sym('S'), &neg(&Sym('S'))).reduce(), Val(0))
+        assert_eq!(add(&s(), &neg(&s())).reduce(), Val(0))
     }
 
     #[test]
     fn reduce_neg_mul() {
-        assert_eq!(neg(&mul(2, &Sym('S'))).reduce(), mul(-2, &Sym('S')))
+        assert_eq!(neg(&mul(2, &s())).reduce(), mul(-2, &s()))
     }
 
     #[test]
     fn reduce_cplx_ex_2() {
         assert_eq!(
-            add(
-                &add(&Val(-4), &mul(-2, &div(&Sym('S'), 4))),
-                &mul(-2, &mul(-1, &div(&Sym('S'), 4)))
-            )
-            .reduce(),
+            add(&add(&Val(-4), &mul(-2, &div(&s(), 4))), &mul(-2, &mul(-1, &div(&s(), 4))))
+                .reduce(),
             Val(-4)
         )
     }
 
     #[test]
     fn reduce_cplx_ex_3() {
-        assert_eq!(div(&Mul(1, b!(Mul(4, b!(Sym('S'))))), 4).reduce(), Sym('S'))
+        assert_eq!(div(&Mul(1, b!(Mul(4, b!(s())))), 4).reduce(), s())
     }
 
     #[test]
     fn reduce_cplx_ex_4() {
         // (S+1)/2 + (1-S)/2 == 1
         assert_eq!(
-            add(&div(&add(&S, &Val(1)), 2), &div(&add(&neg(&S), &Val(1)), 2)).reduce(),
+            add(&div(&add(&s(), &Val(1)), 2), &div(&add(&neg(&s()), &Val(1)), 2)).reduce(),
             1.into()
         );
     }
 
     #[test]
     fn reduce_mul_mul_1() {
-        assert_eq!(mul(3, &mul(2, &Sym('S'))).reduce(), mul(6, &Sym('S')))
+        assert_eq!(mul(3, &mul(2, &s())).reduce(), mul(6, &s()))
     }
 
     #[test]
     fn reduce_mul_mul_2() {
-        assert_eq!(mul(-2, &mul(-1, &Sym('S'))).reduce(), mul(2, &Sym('S')))
+        assert_eq!(mul(-2, &mul(-1, &s())).reduce(), mul(2, &s()))
     }
 
     #[test]
     fn reduce_mul_div_1() {
-        assert_eq!(mul(2, &div(&mul(-1, &Sym('S')), 3)).reduce(), mul(-2, &div(&Sym('S'), 3)))
+        assert_eq!(mul(2, &div(&mul(-1, &s()), 3)).reduce(), mul(-2, &div(&s(), 3)))
     }
 
     #[test]
     fn const_and_add() {
         let e: TDim = 2i32.into();
-        assert_eq!(e.eval_with(&hashmap! {}).unwrap(), 2);
+        assert_eq!(e.to_integer().unwrap(), 2);
         let e: TDim = TDim::from(2) + 3;
-        assert_eq!(e.eval_with(&hashmap! {}).unwrap(), 5);
+        assert_eq!(e.to_integer().unwrap(), 5);
         let e: TDim = TDim::from(2) - 3;
-        assert_eq!(e.eval_with(&hashmap! {}).unwrap(), -1);
+        assert_eq!(e.to_integer().unwrap(), -1);
         let e: TDim = -TDim::from(2);
-        assert_eq!(e.eval_with(&hashmap! {}).unwrap(), -2);
+        assert_eq!(e.to_integer().unwrap(), -2);
     }
 
     #[test]
     fn substitution() {
-        let e = TDim::Sym('x');
-        assert_eq!(e.eval_with(&hashmap! {'x' => 2}).unwrap(), 2);
-        let e = TDim::Sym('x') + 3;
-        assert_eq!(e.eval_with(&hashmap! {'x' => 2}).unwrap(), 5);
+        let e = TDim::sym('x');
+        assert_eq!(e.eval_with(&SymbolValues::default().with('x', 2)).unwrap(), 2);
+        let e = TDim::sym('x') + 3;
+        assert_eq!(e.eval_with(&SymbolValues::default().with('x', 2)).unwrap(), 5);
+    }
+
+    #[test]
+    fn eval_two_symbols() {
+        let e = TDim::sym('N') * 3 + TDim::sym('T') - 1;
+        let values = SymbolValues::default().with('N', 2).with('T', 5);
+        assert_eq!(e.eval_with(&values).unwrap(), 10);
+        assert!(e.eval_with(&SymbolValues::default().with('N', 2)).is_err());
+    }
+
+    #[test]
+    fn partial_substitution() {
+        let e = TDim::sym('N') * 3 + TDim::sym('T') - 1;
+        let values = SymbolValues::default().with('N', 2);
+        assert_eq!(e.substitute(&values), TDim::sym('T') + 5);
+    }
+
+    #[test]
+    fn reduce_two_symbols() {
+        let n = TDim::sym('N');
+        let t = TDim::sym('T');
+        assert_eq!(n.clone() + &t - &n, t);
+        assert_eq!((n.clone() * 2 + &t) - &t - &n, n);
+    }
+
+    #[test]
+    fn reduce_neg_div_other_symbol() {
+        // (1-T)/2 + (T+1)/2 == 1
+        let t = TDim::sym('T');
+        assert_eq!((TDim::from(1) - &t) / 2 + (t + 1) / 2, 1.into());
+    }
+
+    #[test]
+    fn is_stream() {
+        assert!((TDim::s() + 1).is_stream());
+        assert!(!TDim::sym('N').is_stream());
+        assert!(!TDim::from(12).is_stream());
     }
 
     #[test]
@@ -668,69 +739,69 @@ mod tests {
 
     #[test]
     fn reduce_div_bug_0() {
-        let e1: TDim = (TDim::Sym('S') + 23) / 2 - 1;
-        let e2: TDim = (TDim::Sym('S') + 21) / 2;
+        let e1: TDim = (TDim::s() + 23) / 2 - 1;
+        let e2: TDim = (TDim::s() + 21) / 2;
         assert_eq!(e1, e2);
     }
 
     #[test]
     fn reduce_div_bug_1() {
-        let e1: TDim = (TDim::Sym('S') + -1) / 2;
-        let e2: TDim = (TDim::Sym('S') + 1) / 2 - 1;
+        let e1: TDim = (TDim::s() + -1) / 2;
+        let e2: TDim = (TDim::s() + 1) / 2 - 1;
         assert_eq!(e1, e2);
     }
 
     #[test]
     fn reduce_div_bug_2() {
-        let e1: TDim = ((TDim::Sym('S') + 1) / 2 + 1) / 2;
-        let e2: TDim = (TDim::Sym('S') + 3) / 4;
+        let e1: TDim = ((TDim::s() + 1) / 2 + 1) / 2;
+        let e2: TDim = (TDim::s() + 3) / 4;
         assert_eq!(e1, e2);
     }
 
     #[test]
     fn reduce_div_bug_3() {
-        let e1: TDim = (TDim::Sym('S') / 2) * -4;
-        let e2: TDim = (TDim::Sym('S') / 2) * -4 / 1;
+        let e1: TDim = (TDim::s() / 2) * -4;
+        let e2: TDim = (TDim::s() / 2) * -4 / 1;
         assert_eq!(e1, e2);
     }
 
     #[test]
     fn reduce_mul_div() {
-        let e: TDim = TDim::Sym('S') * 2 / 2;
-        assert_eq!(e, TDim::Sym('S'));
+        let e: TDim = TDim::s() * 2 / 2;
+        assert_eq!(e, TDim::s());
     }
 
     #[test]
     fn reduce_div_mul() {
-        let e: TDim = TDim::Sym('S') / 2 * 2;
-        assert_ne!(e, TDim::Sym('S'));
+        let e: TDim = TDim::s() / 2 * 2;
+        assert_ne!(e, TDim::s());
     }
 
     #[test]
     fn reduce_add_div() {
-        let e: TDim = TDim::Sym('S') / 2 + 1;
-        assert_eq!(e, ((TDim::Sym('S') + 2) / 2));
+        let e: TDim = TDim::s() / 2 + 1;
+        assert_eq!(e, ((TDim::s() + 2) / 2));
     }
 
     #[test]
     fn reduce_neg_mul_() {
-        let e: TDim = TDim::from(1) - TDim::Sym('S') * 2;
-        assert_eq!(e, TDim::from(1) + TDim::Sym('S') * -2);
+        let e: TDim = TDim::from(1) - TDim::s() * 2;
+        assert_eq!(e, TDim::from(1) + TDim::s() * -2);
     }
 
     #[test]
     fn reduce_add_rem_1() {
-        assert_eq!(((TDim::Sym('S') + 4) % 2), (TDim::Sym('S') % 2));
+        assert_eq!(((TDim::s() + 4) % 2), (TDim::s() % 2));
     }
 
     #[test]
     fn reduce_add_rem_2() {
-        assert_eq!(((TDim::Sym('S') - 4) % 2), (TDim::Sym('S') % 2));
+        assert_eq!(((TDim::s() - 4) % 2), (TDim::s() % 2));
     }
 
     #[test]
     fn reduce_rem_div() {
-        let e: TDim = TDim::Sym('S') % 2 / 2;
+        let e: TDim = TDim::s() % 2 / 2;
         assert_eq!(e, TDim::from(0));
     }
 
@@ -742,7 +813,7 @@ mod tests {
 
     #[test]
     fn conv2d_ex_2() {
-        let e = (TDim::Sym('S') - 3 + 1).div_ceil(1);
-        assert_eq!(e, TDim::Sym('S') + -2);
+        let e = (TDim::s() - 3 + 1).div_ceil(1);
+        assert_eq!(e, TDim::s() + -2);
     }
 }
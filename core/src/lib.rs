@@ -62,6 +62,7 @@ extern crate log;
 pub extern crate ndarray;
 extern crate num_integer;
 pub extern crate num_traits;
+pub extern crate rayon;
 #[macro_use]
 extern crate maplit;
 #[cfg(test)]
@@ -91,6 +92,7 @@ pub mod model;
 mod optim;
 pub mod plan;
 pub mod pulse;
+pub mod ser;
 pub mod tensor;
 
 pub use crate::errors::*;
@@ -99,7 +101,7 @@ pub use dyn_clone;
 /// This prelude is meant for code using tract.
 pub mod prelude {
     pub use crate::datum::{Blob, Datum, DatumType};
-    pub use crate::dim::TDim;
+    pub use crate::dim::{Symbol, SymbolValues, TDim};
     pub use crate::errors::*;
     pub use crate::model::*;
     pub use crate::plan::{SimplePlan, SimpleState};
@@ -116,7 +118,7 @@ pub mod prelude {
 
 /// This prelude is meant for code extending tract (like implementing new ops).
 pub mod internal {
-    pub use crate::dim::{DimLike, MaybeProduct, TDim, ToDim};
+    pub use crate::dim::{DimLike, MaybeProduct, Symbol, SymbolValues, TDim, ToDim};
     pub use crate::model::*;
     pub use crate::ops::change_axes::*;
     pub use crate::ops::element_wise::ElementWiseMiniOp;
@@ -31,125 +31,116 @@ pub struct StreamFact {
 
 /// Fully determined dimension of a tensor.
 ///
-/// Tensors in tract can have one streaming dimension. TDim generalize the
-/// regular tensor dimensions (usize) to arithmetic expressions of `S`, the
-/// (sometimes hypothetical) tensor length on the streaming axis.
+/// Tensors in tract can have one streaming dimension, and any number of other
+/// symbolic dimensions. TDim generalize the regular tensor dimensions (usize)
+/// to arithmetic expressions of symbols: `S`, the (sometimes hypothetical)
+/// tensor length on the streaming axis, or named symbols like `N` for
+/// dimensions only known at runtime (a dynamic batch size, for instance).
 #[derive(Clone, PartialEq, Hash)]
 pub struct ShapeFact {
-    shape: TVec<usize>,
-    /// Optional information for streaming tensors. None for regular tensors.
-    pub stream_info: Option<StreamFact>,
+    dims: TVec<TDim>,
+    /// The dimensions as integers, if none of them is symbolic.
+    concrete: Option<TVec<usize>>,
 }
 
 impl ShapeFact {
     /// Rank of the tensor.
     pub fn rank(&self) -> usize {
-        self.shape.len()
+        self.dims.len()
     }
 
     /// Extended dimension of the i-th axis.
     ///
-    /// The TDim will wrap a plain integer for regular (non-streaming) tensors.
+    /// The TDim will wrap a plain integer for regular (non-symbolic) dimensions.
     pub fn dim(&self, i: usize) -> TDim {
-        if let Some(ref stream) = self.stream_info {
-            if stream.axis == i {
-                return stream.len.clone();
-            }
-        }
-        self.shape[i].to_dim()
+        self.dims[i].clone()
     }
 
     /// Set the i-th axis dimension.
     pub fn set_dim(&mut self, i: usize, dim: TDim) -> TractResult<()> {
-        if let Some(ref stream) = self.stream_info {
-            if let Ok(int) = dim.to_integer() {
-                self.shape[i] = int as _;
-                if stream.axis == i {
-                    self.stream_info = None;
-                }
-            } else {
-                if stream.axis != i {
-                    bail!("Attempt at building a shape with two streaming dim")
-                } else {
-                    self.stream_info = Some(StreamFact { len: dim, axis: i })
-                }
-            }
-        } else {
-            if let Ok(int) = dim.to_integer() {
-                self.shape[i] = int as _;
-            } else {
-                self.shape[i] = 0;
-                self.stream_info = Some(StreamFact { len: dim, axis: i })
-            }
+        if dim.is_stream() && self.dims.iter().enumerate().any(|(ix, d)| ix != i && d.is_stream()) {
+            bail!("Attempt at building a shape with two streaming dim")
         }
+        self.dims[i] = dim;
+        self.compute_concrete();
         Ok(())
     }
 
     pub fn insert_axis(&mut self, axis: usize) -> TractResult<()> {
-        self.shape.insert(axis, 1);
-        if let Some(s) = self.stream_info.as_mut() {
-            if s.axis >= axis {
-                s.axis += 1;
-            }
+        self.dims.insert(axis, 1.to_dim());
+        if let Some(concrete) = self.concrete.as_mut() {
+            concrete.insert(axis, 1);
         }
         Ok(())
     }
 
     pub fn remove_axis(&mut self, axis: usize) -> TractResult<()> {
-        self.shape.remove(axis);
-        if let Some(s) = self.stream_info.as_mut() {
-            if s.axis > axis {
-                s.axis -= 1;
-            }
+        self.dims.remove(axis);
+        if let Some(concrete) = self.concrete.as_mut() {
+            concrete.remove(axis);
+        } else {
+            self.compute_concrete();
         }
         Ok(())
     }
 
-    /// Shape of the tensor, unless it is streaming.
+    /// Shape of the tensor, unless it has symbolic dimensions.
     pub fn as_finite(&self) -> Option<&[usize]> {
-        match self.stream_info {
-            None => Some(&*self.shape),
-            _ => None,
-        }
+        self.concrete.as_ref().map(|c| &**c)
+    }
+
+    /// Streaming axis and length, for streaming tensors.
+    pub fn stream_info(&self) -> Option<StreamFact> {
+        self.dims
+            .iter()
+            .enumerate()
+            .find(|(_ix, d)| d.is_stream())
+            .map(|(axis, len)| StreamFact { axis, len: len.clone() })
     }
 
     /// Iterator over dimension of the shape.
     pub fn iter<'a>(&'a self) -> impl Iterator<Item = TDim> + 'a {
-        self.shape.clone().into_iter().enumerate().map(move |(ix, d)| {
-            if let Some(ref info) = self.stream_info {
-                if ix == info.axis {
-                    return info.len.clone();
-                }
-            }
-            (d as i64).to_dim()
-        })
+        self.dims.iter().cloned()
     }
 
     /// Convert the shape to an array of extended dimensions.
     pub fn to_tvec(&self) -> TVec<TDim> {
-        self.iter().collect::<TVec<TDim>>()
+        self.dims.clone()
+    }
+
+    /// Check that a concrete shape is a possible value for this one.
+    pub fn matches(&self, shape: &[usize]) -> bool {
+        if let Some(concrete) = &self.concrete {
+            return &**concrete == shape;
+        }
+        self.rank() == shape.len()
+            && self.dims.iter().zip(shape.iter()).all(|(d, &s)| match d.to_integer() {
+                Ok(i) => i as usize == s,
+                Err(_) => true,
+            })
     }
 
     pub fn from_dims<T: AsRef<[TDim]> + std::fmt::Debug>(it: T) -> TractResult<ShapeFact> {
         let count = it.as_ref().iter().filter(|t| t.is_stream()).count();
         if count > 1 {
             bail!("Shape with two streaming dims are invalid: {:?}", it)
-        } else {
-            let stream_info = it
-                .as_ref()
-                .iter()
-                .enumerate()
-                .find(|(_ix, d)| d.is_stream())
-                .map(|(ix, d)| StreamFact { axis: ix, len: d.clone() });
-            Ok(ShapeFact {
-                shape: it
-                    .as_ref()
-                    .iter()
-                    .map(|t| t.to_integer().map(|i| i as usize).unwrap_or(0))
-                    .collect(),
-                stream_info,
-            })
         }
+        let mut shape = ShapeFact { dims: it.as_ref().into(), concrete: None };
+        shape.compute_concrete();
+        Ok(shape)
+    }
+
+    fn from_concrete(shape: &[usize]) -> ShapeFact {
+        ShapeFact { dims: shape.iter().map(|d| d.to_dim()).collect(), concrete: Some(shape.into()) }
+    }
+
+    fn compute_concrete(&mut self) {
+        self.concrete = self
+            .dims
+            .iter()
+            .map(|d| d.to_integer().map(|i| i as usize))
+            .collect::<TractResult<TVec<usize>>>()
+            .ok();
     }
 }
 
@@ -170,7 +161,7 @@ impl TryFrom<&[TDim]> for ShapeFact {
 impl TryFrom<&[usize]> for ShapeFact {
     type Error = TractError;
     fn try_from(it: &[usize]) -> TractResult<ShapeFact> {
-        Ok(ShapeFact { shape: it.into(), stream_info: None })
+        Ok(ShapeFact::from_concrete(it))
     }
 }
 
@@ -231,7 +222,7 @@ impl Fact for TypedFact {
     }
 
     fn matches(&self, t: &Tensor) -> TractResult<bool> {
-        Ok(self.datum_type == t.datum_type() && t.shape() == &*self.shape.shape)
+        Ok(self.datum_type == t.datum_type() && self.shape.matches(t.shape()))
     }
 
     fn same_as(&self, other: &dyn Fact) -> bool {
@@ -259,7 +250,7 @@ impl From<Arc<Tensor>> for TypedFact {
     fn from(t: Arc<Tensor>) -> TypedFact {
         TypedFact {
             datum_type: t.datum_type(),
-            shape: ShapeFact { shape: t.shape().into(), stream_info: None },
+            shape: ShapeFact::from_concrete(t.shape()),
             konst: Some(t),
         }
     }
@@ -0,0 +1,291 @@
+//! Conversion of f32 models to half precision (f16) compute.
+//!
+//! Constants and operator weights are stored in f16, and the tensors flowing
+//! through the network become f16, halving the memory footprint of the
+//! model. The interface of the model is preserved: f32 inputs are cast to f16
+//! right after the sources, and f32 outputs are cast back from f16.
+//!
+//! Operators that do not accept f16 inputs (or would not produce f16 outputs
+//! from them) are left running in f32, with casts wired around them.
+//!
+//! The pass expects a decluttered model, and is meant to run before
+//! `optimize`.
+use crate::internal::*;
+use crate::model::translator::Translate;
+use crate::ops::binary::UnaryOp;
+use crate::ops::cast::cast;
+use crate::ops::cnn::ConvUnary;
+use crate::ops::konst::Const;
+use crate::ops::matmul::MatMulUnary;
+use std::collections::HashMap;
+
+/// Convert the f32 tensors of a decluttered model to f16.
+pub fn half_precision(model: &TypedModel) -> TractResult<TypedModel> {
+    let (mut target, mapping) = HalfTranslator.translate_model_with_mappings(model)?;
+    target.inputs = model
+        .input_outlets()?
+        .iter()
+        .map(|i| {
+            let outlet = mapping[i];
+            if model.outlet_fact(*i)?.datum_type == f32::datum_type() {
+                Ok(target.node(outlet.node).inputs[0])
+            } else {
+                Ok(outlet)
+            }
+        })
+        .collect::<TractResult<_>>()?;
+    target.outputs = model
+        .output_outlets()?
+        .iter()
+        .map(|o| {
+            let outlet = mapping[o];
+            if model.outlet_fact(*o)?.datum_type == f32::datum_type() {
+                let name = format!("{}-as-f32", model.node(o.node).name);
+                Ok(target.wire_node(name, cast(f32::datum_type()), &[outlet])?[0])
+            } else {
+                Ok(outlet)
+            }
+        })
+        .collect::<TractResult<_>>()?;
+    Ok(target)
+}
+
+#[derive(Debug)]
+pub struct HalfTranslator;
+
+impl Translate<TypedFact, Box<dyn TypedOp>, TypedFact, Box<dyn TypedOp>> for HalfTranslator {
+    fn translate_node(
+        &self,
+        source: &TypedModel,
+        node: &TypedNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        if source.input_outlets()?.contains(&OutletId::new(node.id, 0)) {
+            let fact = node.outputs[0].fact.clone();
+            let is_f32 = fact.datum_type == f32::datum_type();
+            let wire = target.add_source(&*node.name, fact)?;
+            if is_f32 {
+                let name = format!("{}-as-f16", node.name);
+                return target.wire_node(name, cast(f16::datum_type()), &[wire]);
+            }
+            return Ok(tvec!(wire));
+        }
+        let inputs = node.inputs.iter().map(|i| mapping[i]).collect::<TVec<_>>();
+        if let Some(op) = half_op(&node.op)? {
+            let produces_f16 = |facts: &TVec<TypedFact>| {
+                node.outputs.iter().zip(facts.iter()).all(|(before, after)| {
+                    before.fact.datum_type != f32::datum_type()
+                        || after.datum_type == f16::datum_type()
+                })
+            };
+            let input_facts = inputs
+                .iter()
+                .map(|o| target.outlet_fact(*o).map(|f| f.clone()))
+                .collect::<TractResult<TVec<_>>>()?;
+            let input_facts = input_facts.iter().collect::<TVec<_>>();
+            if op.output_facts(&*input_facts).map(|f| produces_f16(&f)).unwrap_or(false) {
+                return target.wire_node(&*node.name, op, &*inputs);
+            }
+        }
+        // f16 not supported here: run the original operator in f32
+        let mut wires = tvec!();
+        for (ix, (i, input)) in node.inputs.iter().zip(inputs.iter()).enumerate() {
+            if source.outlet_fact(*i)?.datum_type == f32::datum_type() {
+                let name = format!("{}-input-{}-as-f32", node.name, ix);
+                wires.push(target.wire_node(name, cast(f32::datum_type()), &[*input])?[0]);
+            } else {
+                wires.push(*input);
+            }
+        }
+        let outputs = target.wire_node(&*node.name, node.op.clone(), &*wires)?;
+        outputs
+            .iter()
+            .enumerate()
+            .map(|(ix, o)| {
+                if target.outlet_fact(*o)?.datum_type == f32::datum_type() {
+                    let name = format!("{}-output-{}-as-f16", node.name, ix);
+                    Ok(target.wire_node(name, cast(f16::datum_type()), &[*o])?[0])
+                } else {
+                    Ok(*o)
+                }
+            })
+            .collect()
+    }
+}
+
+fn to_f16(t: &Arc<Tensor>) -> TractResult<Arc<Tensor>> {
+    if t.datum_type() == f32::datum_type() {
+        Ok(t.cast_to::<f16>()?.into_owned().into_arc_tensor())
+    } else {
+        Ok(t.clone())
+    }
+}
+
+/// The f16 version of `op`, if it has one.
+///
+/// Operators carrying f32 tensors get them converted. Others are kept as is,
+/// their output types are expected to follow their inputs.
+fn half_op(op: &Box<dyn TypedOp>) -> TractResult<Option<Box<dyn TypedOp>>> {
+    if let Some(k) = op.as_op().downcast_ref::<Const>() {
+        return Ok(Some(Box::new(Const::new(to_f16(&k.0)?))));
+    }
+    if let Some(conv) = op.as_op().downcast_ref::<ConvUnary>() {
+        if conv.q_params.is_some() {
+            return Ok(None);
+        }
+        let mut conv = conv.clone();
+        conv.kernel = to_f16(&conv.kernel)?;
+        conv.bias = conv.bias.as_ref().map(to_f16).transpose()?;
+        return Ok(Some(Box::new(conv)));
+    }
+    if let Some(mm) = op.as_op().downcast_ref::<MatMulUnary>() {
+        if mm.q_params.is_some() {
+            return Ok(None);
+        }
+        let mut mm = mm.clone();
+        mm.a = to_f16(&mm.a)?;
+        return Ok(Some(Box::new(mm)));
+    }
+    if let Some(unary) = op.as_op().downcast_ref::<UnaryOp>() {
+        let mut unary = unary.clone();
+        unary.a = to_f16(&unary.a)?;
+        return Ok(Some(Box::new(unary)));
+    }
+    Ok(Some(op.clone()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::cnn::{KernelFormat, PaddingSpec, PoolSpec};
+    use crate::ops::nn::DataFormat;
+
+    // operands are small multiples of powers of two, so f16 computations
+    // are exact
+    fn conv_relu_matmul() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let x = model
+            .add_source("x", TypedFact::dt_shape(f32::datum_type(), [1, 3, 8, 8].as_ref())?)?;
+        let kernel =
+            Tensor::from(tract_ndarray::Array4::from_shape_fn((4, 3, 3, 3), |(o, i, y, x)| {
+                ((o * 7 + i * 5 + y * 3 + x) % 5) as f32 / 4.0 - 0.5
+            }));
+        let conv = ConvUnary {
+            pool_spec: PoolSpec::new(
+                DataFormat::NCHW,
+                tvec!(3, 3),
+                PaddingSpec::Valid,
+                None,
+                None,
+                Some(4),
+            ),
+            kernel_fmt: KernelFormat::OIHW,
+            kernel: kernel.into_arc_tensor(),
+            group: 1,
+            bias: Some(rctensor1(&[0.5f32, -0.5, 1.0, 0.0])),
+            q_params: None,
+        };
+        let wire = model.wire_node("conv", conv, &[x])?[0];
+        let wire = model.wire_node(
+            "relu",
+            crate::ops::math::max::unary(tensor4(&[[[[0f32]]]]).into_arc_tensor()),
+            &[wire],
+        )?[0];
+        let wire = model.wire_node(
+            "reshape",
+            AxisOp::Reshape(2, tvec!(6.to_dim(), 6.to_dim()), tvec!(36.to_dim())),
+            &[wire],
+        )?[0];
+        let a = Tensor::from(tract_ndarray::Array2::from_shape_fn((5, 4), |(m, k)| {
+            ((m * 3 + k) % 3) as f32 - 1.0
+        }));
+        let wire = model.wire_node(
+            "mm",
+            MatMulUnary::new(a.into_arc_tensor(), false, false, false, None),
+            &[wire],
+        )?[0];
+        model.set_output_outlets(&[wire])?;
+        Ok(model)
+    }
+
+    // x + c, with an i32 input passed through as a second output
+    fn add_const_with_i32_passthrough() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", TypedFact::dt_shape(f32::datum_type(), [2].as_ref())?)?;
+        let y = model.add_source("y", TypedFact::dt_shape(i32::datum_type(), [3].as_ref())?)?;
+        let c = model.add_const("c", rctensor1(&[0.5f32, -1.5]))?;
+        let wire = model.wire_node("add", crate::ops::math::add::bin_typed(), &[x, c])?[0];
+        model.set_output_outlets(&[wire, y])?;
+        Ok(model)
+    }
+
+    fn run(model: TypedModel) -> TractResult<Arc<Tensor>> {
+        let input =
+            Tensor::from(tract_ndarray::Array4::from_shape_fn((1, 3, 8, 8), |(_, c, y, x)| {
+                ((c * 64 + y * 8 + x) * 37 % 7) as f32 / 2.0 - 1.5
+            }));
+        Ok(SimplePlan::new(model)?.run(tvec!(input))?.remove(0))
+    }
+
+    #[test]
+    fn half_model_computes_in_f16() {
+        let model = conv_relu_matmul().unwrap();
+        let half = half_precision(&model).unwrap();
+        assert_eq!(half.input_fact(0).unwrap().datum_type, f32::datum_type());
+        assert_eq!(half.output_fact(0).unwrap().datum_type, f32::datum_type());
+        for name in &["conv", "relu", "mm"] {
+            assert_eq!(
+                half.node_by_name(name).unwrap().outputs[0].fact.datum_type,
+                f16::datum_type()
+            );
+        }
+        let expected = run(model).unwrap();
+        assert_eq!(run(half.clone()).unwrap(), expected);
+        assert_eq!(run(half.declutter().unwrap().optimize().unwrap()).unwrap(), expected);
+    }
+
+    #[test]
+    fn const_is_converted_to_f16() {
+        let half = half_precision(&add_const_with_i32_passthrough().unwrap()).unwrap();
+        let c = half.node_by_name("c").unwrap();
+        assert_eq!(c.outputs[0].fact.datum_type, f16::datum_type());
+        assert_eq!(
+            c.op_as::<Const>().unwrap().0,
+            rctensor1(&[f16::from(0.5f32), f16::from(-1.5f32)])
+        );
+        let add = half.node_by_name("add").unwrap();
+        assert_eq!(add.outputs[0].fact.datum_type, f16::datum_type());
+    }
+
+    #[test]
+    fn f32_inputs_are_cast_to_f16() {
+        let half = half_precision(&add_const_with_i32_passthrough().unwrap()).unwrap();
+        assert_eq!(
+            half.input_fact(0).unwrap(),
+            &TypedFact::dt_shape(f32::datum_type(), [2].as_ref()).unwrap()
+        );
+        let cast = half.node_by_name("x-as-f16").unwrap();
+        assert_eq!(cast.inputs, vec!(half.input_outlets().unwrap()[0]));
+        assert_eq!(cast.outputs[0].fact.datum_type, f16::datum_type());
+        assert_eq!(half.node_by_name("add").unwrap().inputs[0], OutletId::new(cast.id, 0));
+        // other inputs are left alone
+        assert_eq!(half.input_fact(1).unwrap().datum_type, i32::datum_type());
+        assert!(half.node_by_name("y-as-f16").is_err());
+    }
+
+    #[test]
+    fn f32_outputs_are_cast_back_from_f16() {
+        let half = half_precision(&add_const_with_i32_passthrough().unwrap()).unwrap();
+        assert_eq!(
+            half.output_fact(0).unwrap(),
+            &TypedFact::dt_shape(f32::datum_type(), [2].as_ref()).unwrap()
+        );
+        let cast = half.node_by_name("add-as-f32").unwrap();
+        assert_eq!(half.output_outlets().unwrap()[0], OutletId::new(cast.id, 0));
+        assert_eq!(cast.inputs, vec!(OutletId::new(half.node_by_name("add").unwrap().id, 0)));
+        // other outputs are left alone
+        assert_eq!(half.output_outlets().unwrap()[1], half.input_outlets().unwrap()[1]);
+        assert_eq!(half.output_fact(1).unwrap().datum_type, i32::datum_type());
+    }
+}
@@ -0,0 +1,476 @@
+//! Static memory planning.
+//!
+//! Computes the lifetime of every tensor produced while running a
+//! `SimplePlan` (from the plan order and flush lists), and packs them in a
+//! single arena: tensors that are never alive at the same time share the same
+//! bytes. The output of an op computing in place (see
+//! `StatelessOp::in_place_input`) shares the bytes of the input it
+//! overwrites, when this input dies with it. This gives the peak memory
+//! needed by a run.
+//!
+//! A plan built `with_memory_plan` gives its states an `Arena`: a single
+//! allocation of `arena_size` bytes, in which the ops able to (see
+//! `StatelessOp::eval_into`) write their output at its planned offset.
+use crate::internal::*;
+use crate::plan::SimplePlan;
+use std::alloc;
+use std::borrow::Borrow;
+use std::fmt::{Debug, Display};
+
+/// Alignment of tensor offsets in the arena, in bytes.
+pub const ARENA_ALIGNMENT: usize = 64;
+
+/// Lifetime and placement of a tensor in the arena.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlannedTensor {
+    pub outlet: OutletId,
+    pub datum_type: DatumType,
+    pub shape: TVec<usize>,
+    /// Size in bytes.
+    pub size: usize,
+    /// Step (index in the plan order) computing the tensor.
+    pub birth: usize,
+    /// Last step during which the tensor is alive.
+    pub death: usize,
+    /// Offset in the arena, in bytes.
+    pub offset: usize,
+    /// Input overwritten by the op computing the tensor, if it is computed
+    /// in place.
+    pub in_place_of: Option<OutletId>,
+}
+
+impl PlannedTensor {
+    fn alive_with(&self, other: &PlannedTensor) -> bool {
+        self.birth <= other.death && other.birth <= self.death
+    }
+
+    fn padded_size(&self) -> usize {
+        (self.size + ARENA_ALIGNMENT - 1) / ARENA_ALIGNMENT * ARENA_ALIGNMENT
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemoryPlan {
+    pub tensors: Vec<PlannedTensor>,
+    /// Tensors which size is unknown before running (symbolic shapes), or
+    /// holding heap-allocated items (String, TDim, Blob).
+    pub unplanned: Vec<OutletId>,
+    /// Size of the arena, in bytes.
+    pub arena_size: usize,
+    /// Maximum of the total size of the tensors alive at the same step, in bytes.
+    pub peak: usize,
+}
+
+impl MemoryPlan {
+    pub fn new<F, O, M>(plan: &SimplePlan<F, O, M>) -> TractResult<MemoryPlan>
+    where
+        F: Fact + Hash + Clone + 'static,
+        O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash,
+        M: Borrow<Graph<F, O>> + Hash,
+    {
+        let model = plan.model();
+        let last_step = plan.order.len().saturating_sub(1);
+        let mut death = vec![last_step; model.nodes().len()];
+        for (step, flush) in plan.flush_lists.iter().enumerate() {
+            for &node in flush {
+                death[node] = step.min(last_step);
+            }
+        }
+        let sources = model.input_outlets()?;
+        let mut tensors: Vec<PlannedTensor> = vec![];
+        let mut unplanned = vec![];
+        for (birth, &n) in plan.order.iter().enumerate() {
+            let node = model.node(n);
+            for slot in 0..node.outputs.len() {
+                let outlet = OutletId::new(n, slot);
+                let fact = model.outlet_fact(outlet)?.to_typed_fact()?;
+                if fact.konst.is_some() {
+                    continue;
+                }
+                let dt = fact.datum_type;
+                let shape = match fact.shape.as_finite() {
+                    Some(shape) if dt.is_copy() => shape,
+                    _ => {
+                        unplanned.push(outlet);
+                        continue;
+                    }
+                };
+                let size = shape.iter().product::<usize>() * dt.size_of();
+                let mut in_place_of = None;
+                let single_output = node.outputs.len() == 1 && !plan.outputs.contains(&outlet);
+                if let Some(op) = node.op().as_stateless().filter(|_| single_output) {
+                    let facts = node
+                        .inputs
+                        .iter()
+                        .map(|i| model.outlet_fact(*i)?.to_typed_fact())
+                        .collect::<TractResult<TVec<_>>>()?;
+                    let facts = facts.iter().collect::<TVec<_>>();
+                    // the op can only overwrite an input nobody else will read
+                    in_place_of = op
+                        .in_place_input(&facts)
+                        .map(|ix| node.inputs[ix])
+                        .filter(|input| {
+                            node.inputs.iter().filter(|i| *i == input).count() == 1
+                                && !sources.contains(input)
+                                && !plan.outputs.contains(input)
+                        })
+                        .and_then(|input| {
+                            tensors.iter().find(|t| {
+                                t.outlet == input
+                                    && t.death == birth
+                                    && t.datum_type == dt
+                                    && t.size == size
+                            })
+                        })
+                        .map(|t| t.outlet);
+                }
+                tensors.push(PlannedTensor {
+                    outlet,
+                    datum_type: dt,
+                    shape: shape.into(),
+                    size,
+                    birth,
+                    death: death[n],
+                    offset: 0,
+                    in_place_of,
+                });
+            }
+        }
+        let peak = (0..plan.order.len())
+            .map(|step| {
+                // a tensor computed in place shares its first step bytes with its input
+                tensors
+                    .iter()
+                    .filter(|t| t.birth <= step && step <= t.death)
+                    .filter(|t| t.in_place_of.is_none() || t.birth != step)
+                    .map(|t| t.size)
+                    .sum::<usize>()
+            })
+            .max()
+            .unwrap_or(0);
+        let arena_size = assign_offsets(&mut tensors);
+        Ok(MemoryPlan { tensors, unplanned, arena_size, peak })
+    }
+
+    /// Offset of a tensor in the arena, if it was planned.
+    pub fn offset(&self, outlet: OutletId) -> Option<usize> {
+        self.tensors.iter().find(|t| t.outlet == outlet).map(|t| t.offset)
+    }
+}
+
+/// Greedy first-fit, biggest tensors first. A tensor computed in place gets
+/// the offset of its input. Returns the arena size.
+fn assign_offsets(tensors: &mut [PlannedTensor]) -> usize {
+    // chains of tensors computed in place of each other are placed as one
+    // tensor, alive from the first birth to the last death
+    let mut root: Vec<usize> = (0..tensors.len()).collect();
+    let mut chains = tensors.to_vec();
+    for ix in 0..tensors.len() {
+        if let Some(input) = tensors[ix].in_place_of {
+            let input = tensors.iter().position(|t| t.outlet == input).unwrap();
+            root[ix] = root[input];
+            chains[root[ix]].death = chains[root[ix]].death.max(tensors[ix].death);
+        }
+    }
+    let mut by_size: Vec<usize> = (0..chains.len()).filter(|&ix| root[ix] == ix).collect();
+    by_size.sort_by_key(|&ix| (std::cmp::Reverse(chains[ix].size), chains[ix].birth));
+    let mut placed: Vec<usize> = vec![];
+    let mut arena_size = 0;
+    for ix in by_size {
+        let mut conflicts: Vec<(usize, usize)> = placed
+            .iter()
+            .filter(|&&other| chains[other].alive_with(&chains[ix]))
+            .map(|&other| (chains[other].offset, chains[other].padded_size()))
+            .collect();
+        conflicts.sort();
+        let size = chains[ix].padded_size();
+        let mut offset = 0;
+        for (start, len) in conflicts {
+            if offset + size <= start {
+                break;
+            }
+            offset = offset.max(start + len);
+        }
+        chains[ix].offset = offset;
+        arena_size = arena_size.max(offset + size);
+        placed.push(ix);
+    }
+    for ix in 0..tensors.len() {
+        tensors[ix].offset = chains[root[ix]].offset;
+    }
+    arena_size
+}
+
+/// The single allocation of an arena.
+#[derive(Debug)]
+struct ArenaBuffer {
+    layout: alloc::Layout,
+    data: *mut u8,
+}
+
+// SAFETY: the buffer owns its memory, like a `Box<[u8]>`. `Arena` hands out
+// each byte to one live tensor at most.
+unsafe impl Send for ArenaBuffer {}
+unsafe impl Sync for ArenaBuffer {}
+
+impl Drop for ArenaBuffer {
+    fn drop(&mut self) {
+        if self.layout.size() > 0 {
+            unsafe { alloc::dealloc(self.data, self.layout) }
+        }
+    }
+}
+
+/// Bytes of an arena used by a tensor. The tensor keeps the slice, and the
+/// arena, alive.
+#[derive(Debug)]
+pub struct ArenaSlice {
+    _buffer: Arc<ArenaBuffer>,
+    offset: usize,
+    len: usize,
+}
+
+impl ArenaSlice {
+    fn overlaps(&self, offset: usize, len: usize) -> bool {
+        self.offset < offset + len && offset < self.offset + self.len
+    }
+}
+
+/// The arena of a state running a memory planned plan.
+///
+/// The output of a planned node is handed out as a slice of the arena at its
+/// planned offset, unless some of these bytes are still in use: an op can
+/// pass a tensor through, or keep it in its state, beyond its planned death.
+#[derive(Debug)]
+pub struct Arena {
+    buffer: Arc<ArenaBuffer>,
+    /// Datum type, shape and offset of the output of each node, if it goes
+    /// in the arena.
+    outputs: Vec<Option<(DatumType, TVec<usize>, usize)>>,
+    /// Slices handed out, possibly still in use.
+    slices: Vec<Arc<ArenaSlice>>,
+}
+
+impl Arena {
+    pub fn new<F, O, M>(plan: &SimplePlan<F, O, M>, memory: &MemoryPlan) -> TractResult<Arena>
+    where
+        F: Fact + Hash + Clone + 'static,
+        O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash,
+        M: Borrow<Graph<F, O>> + Hash,
+    {
+        let model = plan.model();
+        let sources = model.input_outlets()?;
+        let mut outputs = vec![None; model.nodes().len()];
+        for t in &memory.tensors {
+            let node = model.node(t.outlet.node);
+            // sources come from the caller, and outputs go back to it
+            if t.in_place_of.is_none()
+                && node.outputs.len() == 1
+                && node.op().as_stateless().is_some()
+                && !sources.contains(&t.outlet)
+                && !plan.outputs.contains(&t.outlet)
+            {
+                outputs[t.outlet.node] = Some((t.datum_type, t.shape.clone(), t.offset));
+            }
+        }
+        Ok(Arena::with_size(memory.arena_size, outputs))
+    }
+
+    fn with_size(size: usize, outputs: Vec<Option<(DatumType, TVec<usize>, usize)>>) -> Arena {
+        let layout = alloc::Layout::from_size_align(size, ARENA_ALIGNMENT).unwrap();
+        let data = if size > 0 {
+            let ptr = unsafe { alloc::alloc(layout) };
+            assert!(!ptr.is_null());
+            ptr
+        } else {
+            std::ptr::null_mut()
+        };
+        Arena { buffer: Arc::new(ArenaBuffer { layout, data }), outputs, slices: vec![] }
+    }
+
+    /// Size of the arena, in bytes.
+    pub fn size(&self) -> usize {
+        self.buffer.layout.size()
+    }
+
+    /// An uninitialized tensor in the arena for the output of `node`, if it
+    /// is planned and its bytes are free.
+    pub fn output(&mut self, node: usize) -> Option<Tensor> {
+        let (dt, shape, offset) = self.outputs.get(node)?.clone()?;
+        self.tensor(dt, &shape, offset)
+    }
+
+    fn tensor(&mut self, dt: DatumType, shape: &[usize], offset: usize) -> Option<Tensor> {
+        let len = shape.iter().product::<usize>() * dt.size_of();
+        self.slices.retain(|s| Arc::strong_count(s) > 1);
+        if len == 0
+            || offset + len > self.size()
+            || self.slices.iter().any(|s| s.overlaps(offset, len))
+        {
+            return None;
+        }
+        let slice = Arc::new(ArenaSlice { _buffer: self.buffer.clone(), offset, len });
+        self.slices.push(slice.clone());
+        unsafe {
+            let data = self.buffer.data.add(offset);
+            Tensor::uninitialized_in_arena(dt, shape, data, slice).ok()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math;
+
+    fn chain(len: usize, shape: &[TDim]) -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let mut wire = model.add_source("x", TypedFact::dt_shape(f32::datum_type(), shape)?)?;
+        for i in 0..len {
+            wire = model.wire_node(format!("a{}", i), math::add::bin_typed(), &[wire, wire])?[0];
+        }
+        model.set_output_outlets(&[wire])?;
+        Ok(model)
+    }
+
+    #[test]
+    fn chain_reuses_memory() {
+        let model = chain(3, &[16.to_dim()]).unwrap();
+        let plan = SimplePlan::new(&model).unwrap();
+        let memory = MemoryPlan::new(&plan).unwrap();
+        assert_eq!(memory.tensors.len(), 4);
+        assert!(memory.unplanned.is_empty());
+        assert_eq!(memory.peak, 2 * 64);
+        assert_eq!(memory.arena_size, 2 * 64);
+    }
+
+    #[test]
+    fn alive_tensors_do_not_overlap() {
+        let mut model = TypedModel::default();
+        let fact = TypedFact::dt_shape(f32::datum_type(), [10].as_ref()).unwrap();
+        let x = model.add_source("x", fact).unwrap();
+        let mut branches = tvec!();
+        for i in 0..5 {
+            let mut wire = x;
+            for j in 0..=i {
+                let name = format!("b{}.{}", i, j);
+                wire = model.wire_node(name, math::mul::bin_typed(), &[wire, wire]).unwrap()[0];
+            }
+            branches.push(wire);
+        }
+        let mut sum = branches[0];
+        for (i, b) in branches.iter().enumerate().skip(1) {
+            let name = format!("s{}", i);
+            sum = model.wire_node(name, math::add::bin_typed(), &[sum, *b]).unwrap()[0];
+        }
+        model.set_output_outlets(&[sum]).unwrap();
+        let plan = SimplePlan::new(&model).unwrap();
+        let memory = MemoryPlan::new(&plan).unwrap();
+        for a in &memory.tensors {
+            for b in &memory.tensors {
+                let in_place = a.in_place_of == Some(b.outlet) || b.in_place_of == Some(a.outlet);
+                if a.outlet != b.outlet && a.alive_with(b) && !in_place {
+                    assert!(
+                        a.offset + a.padded_size() <= b.offset
+                            || b.offset + b.padded_size() <= a.offset
+                    );
+                }
+            }
+        }
+        assert!(memory.peak <= memory.arena_size);
+        assert!(memory.arena_size < memory.tensors.iter().map(|t| t.padded_size()).sum());
+    }
+
+    #[test]
+    fn state_computes_in_arena() {
+        let model = chain(4, &[16.to_dim()]).unwrap();
+        let plan = SimplePlan::new(&model).unwrap().with_memory_plan().unwrap();
+        let memory = plan.memory_plan.as_ref().unwrap();
+        let mut state = SimpleState::new(&plan).unwrap();
+        for _ in 0..2 {
+            let mut in_arena = vec![];
+            let output = state
+                .run_plan_with_eval(
+                    tvec!(tensor1(&[1f32; 16])),
+                    |session, op_state, node, inputs| {
+                        let outputs = crate::plan::eval(session, op_state, node, inputs)?;
+                        if outputs[0].is_in_arena() {
+                            in_arena.push((node.id, outputs[0].as_ptr::<f32>()? as usize));
+                        }
+                        Ok(outputs)
+                    },
+                )
+                .unwrap()
+                .remove(0);
+            assert_eq!(output, rctensor1(&[16f32; 16]));
+            assert!(!output.is_in_arena());
+            // a0, a1 and a2, but not the output
+            assert_eq!(in_arena.len(), 3);
+            let base = state.arena.as_ref().unwrap().buffer.data as usize;
+            for (node, ptr) in in_arena {
+                assert_eq!(Some(ptr - base), memory.offset(OutletId::new(node, 0)));
+            }
+        }
+    }
+
+    #[test]
+    fn dead_inputs_are_overwritten_in_place() {
+        let mut model = TypedModel::default();
+        let fact = TypedFact::dt_shape(f32::datum_type(), [16].as_ref()).unwrap();
+        let x = model.add_source("x", fact).unwrap();
+        let m = model.wire_node("m", math::mul::bin_typed(), &[x, x]).unwrap();
+        let a = model.wire_node("a", math::add::bin_typed(), &[x, m[0]]).unwrap();
+        let n = model.wire_node("n", math::abs(), &a).unwrap();
+        let y = model.wire_node("y", math::mul::bin_typed(), &[n[0], n[0]]).unwrap();
+        model.set_output_outlets(&y).unwrap();
+        let plan = SimplePlan::new(&model).unwrap().with_memory_plan().unwrap();
+        let memory = plan.memory_plan.as_ref().unwrap();
+        let planned = |name: &str| {
+            memory.tensors.iter().find(|t| model.node(t.outlet.node).name == name).unwrap()
+        };
+        assert_eq!(planned("a").in_place_of, Some(planned("m").outlet));
+        assert_eq!(planned("n").in_place_of, Some(planned("a").outlet));
+        assert_eq!(planned("n").offset, planned("m").offset);
+        assert_eq!(memory.peak, 2 * 64);
+        let mut state = SimpleState::new(&plan).unwrap();
+        let mut computed = HashMap::new();
+        let y = state
+            .run_plan_with_eval(tvec!(tensor1(&[-3f32; 16])), |session, op_state, node, inputs| {
+                let outputs = crate::plan::eval(session, op_state, node, inputs)?;
+                let ptr = outputs[0].as_ptr::<f32>()? as usize;
+                computed.insert(node.name.clone(), (outputs[0].is_in_arena(), ptr));
+                Ok(outputs)
+            })
+            .unwrap();
+        assert_eq!(y[0], rctensor1(&[36f32; 16]));
+        assert!(computed["m"].0);
+        assert_eq!(computed["a"], computed["m"]);
+        assert_eq!(computed["n"], computed["m"]);
+    }
+
+    #[test]
+    fn busy_bytes_are_not_handed_out() {
+        let mut arena = Arena::with_size(128, vec![]);
+        let a = arena.tensor(f32::datum_type(), &[16], 0).unwrap();
+        assert!(arena.tensor(f32::datum_type(), &[16], 32).is_none());
+        let mut b = arena.tensor(f32::datum_type(), &[16], 64).unwrap();
+        std::mem::drop(a);
+        assert!(arena.tensor(f32::datum_type(), &[8], 32).is_some());
+        assert!(arena.tensor(f32::datum_type(), &[8], 96).is_none());
+        assert!(arena.tensor(f32::datum_type(), &[16], 128).is_none());
+        // the tensor keeps the arena memory alive
+        std::mem::drop(arena);
+        b.as_slice_mut::<f32>().unwrap().iter_mut().for_each(|x| *x = 1.0);
+        assert_eq!(b, tensor1(&[1f32; 16]));
+    }
+
+    #[test]
+    fn symbolic_shapes_are_not_planned() {
+        let model = chain(2, &[TDim::s()]).unwrap();
+        let plan = SimplePlan::new(&model).unwrap();
+        let memory = MemoryPlan::new(&plan).unwrap();
+        assert!(memory.tensors.is_empty());
+        assert_eq!(memory.unplanned.len(), 3);
+        assert_eq!(memory.arena_size, 0);
+    }
+}
@@ -40,8 +40,11 @@ use itertools::Itertools;
 
 mod fact;
 mod graph;
+pub mod half;
+pub mod memory;
 mod node;
 pub mod order;
+pub mod quantize;
 mod patch;
 pub mod translator;
 pub mod typed;
@@ -0,0 +1,322 @@
+//! Post-training static quantization.
+//!
+//! A `Calibration` runs representative inputs through a `SimplePlan` and
+//! records the range of every f32 tensor computed. It can then rewrite the
+//! float convolutions and matrix products of the model to i8: weights are
+//! quantized symmetrically, per tensor, and the calibrated input range gives
+//! the input scale and zero point. The quantized operator accumulates in
+//! i32, and its output is dequantized back to f32 (biases are added in
+//! float), so the rest of the network is left untouched.
+//!
+//! The pass expects a decluttered model: MatMul with a constant operand must
+//! have been turned into MatMulUnary.
+use crate::internal::*;
+use crate::ops::cnn::ConvUnary;
+use crate::ops::matmul::MatMulUnary;
+use crate::ops::quant::{quantize_linear_f32_i8, quantize_linear_i8, DequantizeLinearF32, QParams};
+use crate::plan::{SimplePlan, SimpleState};
+use std::borrow::Borrow;
+
+/// Scale and zero point mapping an f32 range to i8.
+///
+/// `x ~ (q - zero_point) * scale`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct I8Params {
+    pub scale: f32,
+    pub zero_point: i8,
+}
+
+impl I8Params {
+    /// Asymmetric parameters covering `[min, max]` (extended to include 0).
+    pub fn from_range(min: f32, max: f32) -> I8Params {
+        let (min, max) = (min.min(0.0), max.max(0.0));
+        let scale = if max > min { (max - min) / 255.0 } else { 1.0 };
+        let zero_point = (-128.0 - min / scale).round().max(-128.0).min(127.0) as i8;
+        I8Params { scale, zero_point }
+    }
+
+    /// Symmetric parameters (zero point is 0) covering `[-abs_max, abs_max]`.
+    pub fn symmetric(abs_max: f32) -> I8Params {
+        let scale = if abs_max > 0.0 { abs_max / 127.0 } else { 1.0 };
+        I8Params { scale, zero_point: 0 }
+    }
+
+    fn quantize(&self, x: f32) -> i8 {
+        quantize_linear_f32_i8(x, self.scale.recip(), self.zero_point as i32)
+    }
+}
+
+/// Value ranges of the f32 tensors of a model, observed on calibration data.
+#[derive(Clone, Debug, Default)]
+pub struct Calibration {
+    ranges: HashMap<OutletId, (f32, f32)>,
+}
+
+impl Calibration {
+    pub fn new() -> Calibration {
+        Calibration::default()
+    }
+
+    /// Run the plan on `inputs`, recording the range of all f32 outlets.
+    pub fn observe<M>(
+        &mut self,
+        plan: &SimplePlan<TypedFact, Box<dyn TypedOp>, M>,
+        inputs: TVec<Tensor>,
+    ) -> TractResult<TVec<Arc<Tensor>>>
+    where
+        M: Borrow<TypedModel> + Hash,
+    {
+        for (ix, input) in inputs.iter().enumerate() {
+            let outlet = plan.model().input_outlets()?[ix];
+            self.record(outlet, input)?;
+        }
+        let mut state = SimpleState::new(plan)?;
+        state.run_plan_with_eval(inputs, |session, op_state, node: &TypedNode, inputs| {
+            let outputs = crate::plan::eval(session, op_state, node, inputs)?;
+            for (slot, output) in outputs.iter().enumerate() {
+                self.record(OutletId::new(node.id, slot), output)?;
+            }
+            Ok(outputs)
+        })
+    }
+
+    fn record(&mut self, outlet: OutletId, t: &Tensor) -> TractResult<()> {
+        if t.datum_type() != f32::datum_type() {
+            return Ok(());
+        }
+        let range = self.ranges.entry(outlet).or_insert((std::f32::MAX, std::f32::MIN));
+        for &x in t.as_slice::<f32>()? {
+            if x.is_finite() {
+                range.0 = range.0.min(x);
+                range.1 = range.1.max(x);
+            }
+        }
+        Ok(())
+    }
+
+    /// Observed `(min, max)` for an outlet.
+    pub fn range(&self, outlet: OutletId) -> Option<(f32, f32)> {
+        self.ranges.get(&outlet).cloned().filter(|(min, max)| min <= max)
+    }
+
+    /// Rewrite calibrated f32 ConvUnary (without groups) and MatMulUnary
+    /// nodes to their i8 quantized forms.
+    pub fn quantize(&self, model: &TypedModel) -> TractResult<TypedModel> {
+        let mut quantized = model.clone();
+        for n in model.eval_order()? {
+            // ranges are known for the original outlets, but the wiring
+            // may have changed by the time we reach the node
+            let calibrated = model.node(n).inputs.get(0).and_then(|&i| self.range(i));
+            let node = quantized.node(n);
+            if let Some(patch) = self
+                .quantize_node(&quantized, node, calibrated)
+                .chain_err(|| format!("Quantizing {}", node))?
+            {
+                patch.apply(&mut quantized)?;
+            }
+        }
+        quantized.compact()
+    }
+
+    fn quantize_node(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+        calibrated: Option<(f32, f32)>,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        if node.inputs.len() != 1
+            || model.outlet_fact(node.inputs[0])?.datum_type != f32::datum_type()
+        {
+            return Ok(None);
+        }
+        let input = if let Some((min, max)) = calibrated {
+            I8Params::from_range(min, max)
+        } else {
+            return Ok(None);
+        };
+        let q_params =
+            QParams::new(i32::datum_type()).with_zero_point_b(&rctensor0(input.zero_point));
+        let (op, weights, bias): (Box<dyn TypedOp>, I8Params, Option<Tensor>) = if let Some(conv) =
+            node.op_as::<ConvUnary>()
+        {
+            if conv.q_params.is_some()
+                || conv.group != 1
+                || conv.kernel.datum_type() != f32::datum_type()
+            {
+                return Ok(None);
+            }
+            let (kernel, weights) = quantize_weights(&conv.kernel)?;
+            let bias = if let Some(bias) = &conv.bias {
+                let output_shape = &model.outlet_fact(OutletId::new(node.id, 0))?.shape;
+                let output_shape = conv.pool_spec.data_format.shape(output_shape.to_tvec())?;
+                let mut bias_shape = tvec!(1; output_shape.rank());
+                bias_shape[output_shape.c_axis()] = bias.len();
+                Some(bias.cast_to::<f32>()?.into_owned().into_shape(&bias_shape)?)
+            } else {
+                None
+            };
+            let op = ConvUnary {
+                kernel: kernel.into_arc_tensor(),
+                bias: None,
+                q_params: Some(q_params),
+                ..conv.clone()
+            };
+            (Box::new(op), weights, bias)
+        } else if let Some(mm) = node.op_as::<MatMulUnary>() {
+            if mm.q_params.is_some() || mm.a.datum_type() != f32::datum_type() {
+                return Ok(None);
+            }
+            let (a, weights) = quantize_weights(&mm.a)?;
+            let op = MatMulUnary { a: a.into_arc_tensor(), q_params: Some(q_params), ..mm.clone() };
+            (Box::new(op), weights, None)
+        } else {
+            return Ok(None);
+        };
+        let mut patch = TypedModelPatch::default();
+        let mut wire = patch.tap_model(model, node.inputs[0])?;
+        wire = patch.wire_node(
+            format!("{}.quant", node.name),
+            quantize_linear_i8(input.scale.recip(), input.zero_point),
+            &[wire],
+        )?[0];
+        wire = patch.wire_node(&*node.name, op, &[wire])?[0];
+        wire = patch.wire_node(
+            format!("{}.dequant", node.name),
+            DequantizeLinearF32::new(input.scale * weights.scale, 0),
+            &[wire],
+        )?[0];
+        if let Some(bias) = bias {
+            wire = patch.wire_node(
+                format!("{}.bias", node.name),
+                crate::ops::math::add::unary(bias.into_arc_tensor()),
+                &[wire],
+            )?[0];
+        }
+        patch.shunt_outside(model, OutletId::new(node.id, 0), wire)?;
+        Ok(Some(patch))
+    }
+}
+
+fn quantize_weights(weights: &Tensor) -> TractResult<(Tensor, I8Params)> {
+    let values = weights.as_slice::<f32>()?;
+    let params = I8Params::symmetric(values.iter().fold(0.0f32, |m, x| m.max(x.abs())));
+    let quantized: Vec<i8> = values.iter().map(|&x| params.quantize(x)).collect();
+    Ok((tensor1(&quantized).into_shape(weights.shape())?, params))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::cnn::{KernelFormat, PaddingSpec, PoolSpec};
+    use crate::ops::nn::DataFormat;
+
+    fn conv_relu_matmul() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let x = model
+            .add_source("x", TypedFact::dt_shape(f32::datum_type(), [1, 3, 8, 8].as_ref())?)?;
+        let kernel =
+            Tensor::from(tract_ndarray::Array4::from_shape_fn((4, 3, 3, 3), |(o, i, y, x)| {
+                ((o * 7 + i * 5 + y * 3 + x) % 11) as f32 / 5.0 - 1.0
+            }));
+        let conv = ConvUnary {
+            pool_spec: PoolSpec::new(
+                DataFormat::NCHW,
+                tvec!(3, 3),
+                PaddingSpec::Valid,
+                None,
+                None,
+                Some(4),
+            ),
+            kernel_fmt: KernelFormat::OIHW,
+            kernel: kernel.into_arc_tensor(),
+            group: 1,
+            bias: Some(rctensor1(&[0.5f32, -0.5, 1.0, 0.0])),
+            q_params: None,
+        };
+        let wire = model.wire_node("conv", conv, &[x])?[0];
+        let wire = model.wire_node(
+            "relu",
+            crate::ops::math::max::unary(tensor4(&[[[[0f32]]]]).into_arc_tensor()),
+            &[wire],
+        )?[0];
+        let wire = model.wire_node(
+            "reshape",
+            AxisOp::Reshape(2, tvec!(6.to_dim(), 6.to_dim()), tvec!(36.to_dim())),
+            &[wire],
+        )?[0];
+        let a = Tensor::from(tract_ndarray::Array2::from_shape_fn((5, 4), |(m, k)| {
+            ((m * 3 + k) % 7) as f32 / 3.0 - 1.0
+        }));
+        let wire = model.wire_node(
+            "mm",
+            MatMulUnary::new(a.into_arc_tensor(), false, false, false, None),
+            &[wire],
+        )?[0];
+        model.set_output_outlets(&[wire])?;
+        Ok(model)
+    }
+
+    fn input(seed: usize) -> Tensor {
+        Tensor::from(tract_ndarray::Array4::from_shape_fn((1, 3, 8, 8), |(_, c, y, x)| {
+            ((seed + c * 64 + y * 8 + x) * 37 % 101) as f32 / 50.0 - 1.0
+        }))
+    }
+
+    fn assert_close(found: &Tensor, expected: &Tensor) {
+        let found = found.as_slice::<f32>().unwrap();
+        let expected = expected.as_slice::<f32>().unwrap();
+        let range = expected.iter().fold(0f32, |m, x| m.max(x.abs()));
+        for (f, e) in found.iter().zip(expected.iter()) {
+            assert!((f - e).abs() <= range * 0.02, "{} != {}", f, e);
+        }
+    }
+
+    #[test]
+    fn i8_params_from_range() {
+        let p = I8Params::from_range(-1.0, 3.0);
+        assert_eq!(p.scale, 4.0 / 255.0);
+        assert_eq!(p.zero_point, -64);
+        assert_eq!(p.quantize(0.0), -64);
+        assert_eq!(I8Params::from_range(2.0, 3.0).zero_point, -128);
+        assert_eq!(I8Params::symmetric(0.0).scale, 1.0);
+    }
+
+    #[test]
+    fn quantized_model_is_close_to_float() {
+        let model = conv_relu_matmul().unwrap();
+        let plan = SimplePlan::new(&model).unwrap();
+        let mut calibration = Calibration::new();
+        for seed in 0..4 {
+            calibration.observe(&plan, tvec!(input(seed))).unwrap();
+        }
+        let quantized = calibration.quantize(&model).unwrap();
+        assert_eq!(
+            quantized.node_by_name("conv").unwrap().outputs[0].fact.datum_type,
+            i32::datum_type()
+        );
+        assert_eq!(
+            quantized.node_by_name("mm").unwrap().outputs[0].fact.datum_type,
+            i32::datum_type()
+        );
+        let expected = plan.run(tvec!(input(1))).unwrap().remove(0);
+        let found = SimplePlan::new(&quantized).unwrap().run(tvec!(input(1))).unwrap().remove(0);
+        assert_close(&found, &expected);
+        let optimized = quantized.declutter().unwrap().optimize().unwrap();
+        let found = SimplePlan::new(&optimized).unwrap().run(tvec!(input(1))).unwrap().remove(0);
+        assert_close(&found, &expected);
+    }
+
+    #[test]
+    fn uncalibrated_nodes_are_left_alone() {
+        let model = conv_relu_matmul().unwrap();
+        let quantized = Calibration::new().quantize(&model).unwrap();
+        assert_eq!(quantized.nodes().len(), model.nodes().len());
+        assert!(quantized
+            .node_by_name("conv")
+            .unwrap()
+            .op_as::<ConvUnary>()
+            .unwrap()
+            .q_params
+            .is_none());
+    }
+}
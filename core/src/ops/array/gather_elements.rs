@@ -0,0 +1,87 @@
+use crate::internal::*;
+use ndarray::*;
+
+/// Gather elements of data along an axis: the output has the shape of
+/// indices, and its elements come from data at the same coordinates, except
+/// along `axis`, where the coordinate is read from indices.
+#[derive(Debug, Clone, new, Hash)]
+pub struct GatherElements {
+    pub axis: usize,
+}
+
+tract_linalg::impl_dyn_hash!(GatherElements);
+
+impl GatherElements {
+    unsafe fn eval_t<T: Datum>(
+        &self,
+        data: &Tensor,
+        indices: &ArrayViewD<i64>,
+    ) -> TractResult<Tensor> {
+        let data_view = data.to_array_view_unchecked::<T>();
+        let len = data_view.shape()[self.axis] as i64;
+        let mut coords = IxDyn(&vec![0; data_view.ndim()]);
+        let mut output = Tensor::uninitialized_dt(data.datum_type(), indices.shape())?;
+        for (value, (ix, &index)) in
+            output.as_slice_mut_unchecked::<T>().iter_mut().zip(indices.indexed_iter())
+        {
+            let index = if index < 0 { index + len } else { index };
+            if index < 0 || index >= len {
+                bail!("GatherElements: index {} out of bounds for axis of length {}", index, len)
+            }
+            coords.slice_mut().copy_from_slice(ix.slice());
+            coords[self.axis] = index as usize;
+            *value = data_view[&coords].clone();
+        }
+        Ok(output)
+    }
+}
+
+impl Op for GatherElements {
+    fn name(&self) -> Cow<str> {
+        "GatherElements".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {}", self.axis)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for GatherElements {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (data, indices) = args_2!(inputs);
+        if data.rank() != indices.rank() {
+            bail!("GatherElements: data and indices must have the same rank")
+        }
+        let indices = indices.cast_to::<i64>()?;
+        let indices = indices.to_array_view::<i64>()?;
+        let output = unsafe {
+            dispatch_datum_by_size!(Self::eval_t(data.datum_type())(self, &data, &indices))?
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for GatherElements {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &*inputs[1].shape.to_tvec())?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gather_elements_axis_1() {
+        let op = GatherElements::new(1);
+        let data = rctensor2(&[[1, 2], [3, 4]]);
+        let indices = rctensor2(&[[0i64, 0], [1, -2]]);
+        assert_eq!(op.eval(tvec!(data, indices)).unwrap(), tvec!(rctensor2(&[[1, 1], [4, 3]])));
+    }
+}
@@ -1,27 +1,36 @@
-use tract_hir::internal::*;
-use tract_ndarray::prelude::*;
-
-use crate::model::ParsingContext;
-use crate::tfpb::tensorflow::NodeDef;
-
+use crate::internal::*;
+use ndarray::*;
+
+/// Gather slices of data from n-dimensional indices.
+///
+/// The last axis of indices holds coordinates in data, after the
+/// `batch_dims` leading axes that data and indices share. Negative
+/// coordinates count from the end of their axis.
 #[derive(Debug, Clone, new, Hash)]
-pub struct GatherNd {}
+pub struct GatherNd {
+    pub batch_dims: usize,
+}
 
 tract_linalg::impl_dyn_hash!(GatherNd);
 
-pub fn gather_nd(_ctx: &ParsingContext, _pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
-    Ok(Box::new(GatherNd::new()))
-}
-
 impl GatherNd {
-    fn compute_shape<D: DimLike>(
+    pub fn compute_shape<D: DimLike>(
         &self,
         data_shape: &[D],
         indices_shape: &[D],
     ) -> TractResult<TVec<D>> {
         let mut shape: TVec<D> = indices_shape.into();
-        let n = shape.pop().unwrap().to_integer()? as usize;
-        shape.extend(data_shape[n..].iter().cloned());
+        let n = shape.pop().ok_or("GatherNd: indices must have at least one axis")?;
+        let n = n.to_integer()? as usize;
+        if self.batch_dims + n > data_shape.len() {
+            bail!(
+                "GatherNd: {} batch axes and coordinates of length {} for data of rank {}",
+                self.batch_dims,
+                n,
+                data_shape.len()
+            )
+        }
+        shape.extend(data_shape[self.batch_dims + n..].iter().cloned());
         Ok(shape)
     }
 
@@ -29,22 +38,32 @@ impl GatherNd {
         &self,
         output: &mut Tensor,
         data: &Tensor,
-        indices: &ArrayViewD<i32>,
-    ) {
+        indices: &ArrayViewD<i64>,
+    ) -> TractResult<()> {
         let data = data.to_array_view_unchecked::<T>();
-        for prefix in tract_ndarray::indices(&indices.shape()[0..indices.ndim() - 1]) {
-            let mut dst = output.to_array_view_mut_unchecked();
+        let mut output = output.to_array_view_mut_unchecked::<T>();
+        for prefix in ndarray::indices(&indices.shape()[0..indices.ndim() - 1]) {
+            let mut dst = output.view_mut();
             let mut coords = indices.view();
-            for &x in prefix.slice().iter() {
+            let mut src = data.view();
+            for (axis, &x) in prefix.slice().iter().enumerate() {
                 dst.index_axis_inplace(Axis(0), x);
                 coords.index_axis_inplace(Axis(0), x);
+                if axis < self.batch_dims {
+                    src.index_axis_inplace(Axis(0), x);
+                }
             }
-            let mut src = data.view();
             for &x in coords.iter() {
-                src.index_axis_inplace(Axis(0), x as _);
+                let len = src.shape()[0] as i64;
+                let x = if x < 0 { x + len } else { x };
+                if x < 0 || x >= len {
+                    bail!("GatherNd: index {} out of bounds for axis of length {}", x, len)
+                }
+                src.index_axis_inplace(Axis(0), x as usize);
             }
             dst.assign(&src);
         }
+        Ok(())
     }
 }
 
@@ -53,16 +72,21 @@ impl Op for GatherNd {
         "GatherNd".into()
     }
 
-    op_tf!();
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("batch_dims: {}", self.batch_dims)])
+    }
+
+    op_core_mir!();
     op_as_typed_op!();
+    not_a_pulsed_op!();
 }
 
 impl StatelessOp for GatherNd {
     fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
         let (data, indices) = args_2!(inputs);
         let shape = self.compute_shape(&data.shape(), &indices.shape())?;
-        let indices = indices.cast_to::<i32>()?;
-        let indices = indices.to_array_view::<i32>()?;
+        let indices = indices.cast_to::<i64>()?;
+        let indices = indices.to_array_view::<i64>()?;
         unsafe {
             let mut output = Tensor::uninitialized_dt(data.datum_type(), &*shape)?;
             dispatch_datum_by_size!(Self::eval_t(data.datum_type())(
@@ -70,46 +94,12 @@ impl StatelessOp for GatherNd {
                 &mut output,
                 &data,
                 &indices
-            ));
+            ))?;
             Ok(tvec!(output.into_arc_tensor()))
         }
     }
 }
 
-impl InferenceRulesOp for GatherNd {
-    fn rules<'r, 'p: 'r, 's: 'r>(
-        &'s self,
-        s: &mut Solver<'r>,
-        inputs: &'p [TensorProxy],
-        outputs: &'p [TensorProxy],
-    ) -> InferenceResult {
-        check_input_arity(&inputs, 2)?;
-        check_output_arity(&outputs, 1)?;
-        s.equals(&outputs[0].datum_type, &inputs[0].datum_type)?;
-        s.given(&inputs[1].rank, move |s, indices_rank| {
-            let indices_rank = indices_rank as usize;
-            for i in 0..(indices_rank - 1) {
-                s.equals(&outputs[0].shape[i], &inputs[1].shape[i])?;
-            }
-            s.given_2(
-                &inputs[1].shape[indices_rank - 1],
-                &inputs[1].rank,
-                move |s, n, input_rank| {
-                    if let Ok(n) = n.to_integer() {
-                        for i in 0..(input_rank - n) as usize {
-                            s.equals(&outputs[0].shape[indices_rank - 1 + i], &inputs[1].shape[i])?;
-                        }
-                    }
-                    Ok(())
-                },
-            )
-        })
-    }
-
-    as_op!();
-    to_typed!();
-}
-
 impl TypedOp for GatherNd {
     as_op!();
 
@@ -123,29 +113,40 @@ impl TypedOp for GatherNd {
         model: &TypedModel,
         node: &TypedNode,
     ) -> TractResult<Option<TypedModelPatch>> {
+        if self.batch_dims > 0 {
+            return Ok(None);
+        }
         if let Some(indices) = &model.outlet_fact(node.inputs[1])?.konst {
+            let data_shape = model.outlet_fact(node.inputs[0])?.shape.to_tvec();
             if indices.rank() == 2 && indices.shape()[0] == 1 {
+                let indices = indices.cast_to::<i64>()?;
                 let mut patch = TypedModelPatch::default();
                 let mut wire = patch.tap_model(model, node.inputs[0])?;
-                for (axis, &i) in indices.cast_to::<i32>()?.as_slice::<i32>()?.iter().enumerate() {
+                for (axis, &i) in indices.as_slice::<i64>()?.iter().enumerate() {
+                    let i = if i < 0 {
+                        if let Ok(len) = data_shape[axis].to_integer() {
+                            i + len as i64
+                        } else {
+                            return Ok(None);
+                        }
+                    } else {
+                        i
+                    };
                     wire = patch.wire_node(
-                        format!("{}-slice-axis-{}", node.name, axis),
-                        tract_hir::ops::array::Slice::new(axis, i as usize, (i + 1) as usize),
+                        format!("{}.slice-axis-{}", node.name, axis),
+                        crate::ops::array::Slice::new(axis, i as usize, (i + 1) as usize),
                         &[wire],
                     )?[0];
                 }
                 for i in (0..indices.shape()[1]).rev() {
                     wire = patch.wire_node(
-                        format!("{}-remove_axis_{}", node.name, i),
-                        tract_hir::tract_core::ops::change_axes::AxisOp::Rm(i),
+                        format!("{}.remove_axis_{}", node.name, i),
+                        AxisOp::Rm(i),
                         &[wire],
                     )?[0];
                 }
-                wire = patch.wire_node(
-                    format!("{}-add_axis", node.name),
-                    tract_hir::tract_core::ops::change_axes::AxisOp::Add(0),
-                    &[wire],
-                )?[0];
+                wire =
+                    patch.wire_node(format!("{}.add_axis", node.name), AxisOp::Add(0), &[wire])?[0];
                 patch.shunt_outside(model, node.id.into(), wire)?;
                 return Ok(Some(patch));
             }
@@ -161,7 +162,7 @@ mod tests {
     // https://www.tensorflow.org/api_docs/python/tf/gather_nd
     #[test]
     fn simple_indexing() {
-        let g = GatherNd::new();
+        let g = GatherNd::new(0);
         assert_eq!(
             g.eval(tvec!(rctensor2(&[[1, 2], [3, 4]]), rctensor2(&[[0, 0], [1, 1]]))).unwrap(),
             tvec!(rctensor1(&[1, 4]))
@@ -170,7 +171,7 @@ mod tests {
 
     #[test]
     fn slice_indexing() {
-        let g = GatherNd::new();
+        let g = GatherNd::new(0);
         assert_eq!(
             g.eval(tvec!(rctensor2(&[[1, 2], [3, 4]]), rctensor2(&[[1], [0]]))).unwrap(),
             tvec!(rctensor2(&[[3, 4], [1, 2]]))
@@ -179,7 +180,7 @@ mod tests {
 
     #[test]
     fn tensor_3d_1() {
-        let g = GatherNd::new();
+        let g = GatherNd::new(0);
         let t = rctensor3(&[[[10, 20], [30, 40]], [[11, 21], [31, 41]]]);
         assert_eq!(
             g.eval(tvec!(t.clone(), rctensor2(&[[1]]))).unwrap(),
@@ -189,7 +190,7 @@ mod tests {
 
     #[test]
     fn tensor_3d_2() {
-        let g = GatherNd::new();
+        let g = GatherNd::new(0);
         let t = rctensor3(&[[[10, 20], [30, 40]], [[11, 21], [31, 41]]]);
         assert_eq!(
             g.eval(tvec!(t.clone(), rctensor2(&[[0, 1], [1, 0]]))).unwrap(),
@@ -199,11 +200,30 @@ mod tests {
 
     #[test]
     fn tensor_3d_3() {
-        let g = GatherNd::new();
+        let g = GatherNd::new(0);
         let t = rctensor3(&[[[10, 20], [30, 40]], [[11, 21], [31, 41]]]);
         assert_eq!(
-            g.eval(tvec!(t.clone(), rctensor2(&[[0, 0, 1], [1, 0, 1]]))).unwrap(),
+            g.eval(tvec!(t.clone(), rctensor2(&[[0, 0, 1], [1, 0, -1]]))).unwrap(),
             tvec!(rctensor1(&[20, 21]))
         );
     }
+
+    #[test]
+    fn batch_dims() {
+        let g = GatherNd::new(1);
+        let t = rctensor3(&[[[0, 1], [2, 3]], [[4, 5], [6, 7]]]);
+        assert_eq!(
+            g.eval(tvec!(t, rctensor2(&[[1], [0]]))).unwrap(),
+            tvec!(rctensor2(&[[2, 3], [4, 5]]))
+        );
+    }
+
+    #[test]
+    fn scalar_indices_are_rejected() {
+        let g = GatherNd::new(0);
+        assert!(g.eval(tvec!(rctensor1(&[1, 2]), rctensor0(0))).is_err());
+        let data = TypedFact::from(rctensor1(&[1, 2]));
+        let indices = TypedFact::dt_shape(i64::datum_type(), [0usize; 0].as_ref()).unwrap();
+        assert!(g.output_facts(&[&data, &indices]).is_err());
+    }
 }
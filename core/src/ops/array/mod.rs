@@ -2,15 +2,31 @@
 mod broadcast;
 pub(crate) mod concat;
 mod gather;
+mod gather_elements;
+mod gather_nd;
+mod non_zero;
+mod one_hot;
 mod pad;
 mod reshape;
+mod scatter_elements;
+mod scatter_nd;
 mod slice;
 mod tile;
+mod topk;
+mod unique;
 
 pub use self::broadcast::MultiBroadcastTo;
 pub use self::concat::{ConcatSlice, TypedConcat};
 pub use self::gather::Gather;
+pub use self::gather_elements::GatherElements;
+pub use self::gather_nd::GatherNd;
+pub use self::non_zero::NonZero;
+pub use self::one_hot::OneHot;
 pub use self::pad::{Pad, PadMode, PulsePad};
 pub use self::reshape::FiniteReshape;
+pub use self::scatter_elements::ScatterElements;
+pub use self::scatter_nd::{ScatterNd, ScatterReduction};
 pub use self::slice::Slice;
 pub use self::tile::Tile;
+pub use self::topk::TopK;
+pub use self::unique::Unique;
@@ -0,0 +1,74 @@
+use crate::internal::*;
+use ndarray::*;
+
+/// Indices of the non-zero elements, as a [rank, count] i64 tensor.
+///
+/// The number of non-zero elements is only known at runtime, so the output
+/// fact uses the `count` dimension.
+#[derive(Debug, Clone, new, Hash)]
+pub struct NonZero {
+    pub count: TDim,
+}
+
+tract_linalg::impl_dyn_hash!(NonZero);
+
+impl NonZero {
+    fn eval_t<T: Datum + PartialEq>(input: &Tensor) -> TractResult<Arc<Tensor>> {
+        let input = input.to_array_view::<T>()?;
+        let zero = T::default();
+        let coords: Vec<IxDyn> =
+            input.indexed_iter().filter(|(_, x)| **x != zero).map(|(coords, _)| coords).collect();
+        let output = Array2::from_shape_fn((input.ndim(), coords.len()), |(axis, ix)| {
+            coords[ix][axis] as i64
+        });
+        Ok(output.into_arc_tensor())
+    }
+}
+
+impl Op for NonZero {
+    fn name(&self) -> Cow<str> {
+        "NonZero".into()
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for NonZero {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let output = if input.datum_type() == bool::datum_type() {
+            Self::eval_t::<bool>(&input)?
+        } else {
+            dispatch_numbers!(Self::eval_t(input.datum_type())(&input))?
+        };
+        Ok(tvec!(output))
+    }
+}
+
+impl TypedOp for NonZero {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(TypedFact::dt_shape(
+            i64::datum_type(),
+            [inputs[0].rank().to_dim(), self.count.clone()].as_ref()
+        )?))
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_zero_coordinates() {
+        let op = NonZero::new(TDim::sym('n'));
+        let input = rctensor2(&[[1f32, 0., 2.], [0., 0., -3.]]);
+        let result = op.eval(tvec!(input)).unwrap();
+        assert_eq!(*result[0], tensor2(&[[0i64, 0, 1], [0, 2, 2]]));
+        let result = op.eval(tvec!(rctensor1(&[false, false]))).unwrap();
+        assert_eq!(result[0].shape(), &[1, 0]);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::internal::*;
+use ndarray::*;
+
+/// One-hot encoding of integer indices: a new axis of length `dim` is
+/// inserted at `axis`, holding `on` at the index position and `off`
+/// everywhere else. Indices out of 0..dim give an `off` row.
+#[derive(Debug, Clone, new, Hash)]
+pub struct OneHot {
+    pub axis: usize,
+    pub dim: usize,
+    pub off: Arc<Tensor>,
+    pub on: Arc<Tensor>,
+}
+
+tract_linalg::impl_dyn_hash!(OneHot);
+
+impl OneHot {
+    fn eval_t<T: Datum>(&self, indices: &ArrayViewD<i64>) -> TractResult<Tensor> {
+        let off = self.off.to_scalar::<T>()?;
+        let on = self.on.to_scalar::<T>()?;
+        let mut shape = indices.shape().to_vec();
+        shape.insert(self.axis, self.dim);
+        let mut output = ArrayD::from_elem(shape, off.clone());
+        let mut coords = vec![0; output.ndim()];
+        for (ix, &index) in indices.indexed_iter() {
+            if index < 0 || index >= self.dim as i64 {
+                continue;
+            }
+            coords[..self.axis].copy_from_slice(&ix.slice()[..self.axis]);
+            coords[self.axis] = index as usize;
+            coords[self.axis + 1..].copy_from_slice(&ix.slice()[self.axis..]);
+            output[&*coords] = on.clone();
+        }
+        Ok(output.into_tensor())
+    }
+}
+
+impl Op for OneHot {
+    fn name(&self) -> Cow<str> {
+        "OneHot".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {} dim: {}", self.axis, self.dim)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for OneHot {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let indices = args_1!(inputs);
+        let indices = indices.cast_to::<i64>()?;
+        let indices = indices.to_array_view::<i64>()?;
+        let output = dispatch_datum!(Self::eval_t(self.on.datum_type())(self, &indices))?;
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for OneHot {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if self.axis > inputs[0].rank() {
+            bail!("OneHot on axis {} for indices of rank {}", self.axis, inputs[0].rank())
+        }
+        let mut shape = inputs[0].shape.to_tvec();
+        shape.insert(self.axis, self.dim.to_dim());
+        Ok(tvec!(TypedFact::dt_shape(self.on.datum_type(), &*shape)?))
+    }
+
+    fn invariants(&self, model: &TypedModel, node: &TypedNode) -> TractResult<Invariants> {
+        let rank = model.outlet_fact(node.inputs[0])?.rank();
+        let axes = (0..rank)
+            .map(|axis| {
+                let output = if axis < self.axis { axis } else { axis + 1 };
+                AxisInfo {
+                    inputs: tvec!(Some(axis)),
+                    outputs: tvec!(Some(output)),
+                    period: 1,
+                    disposable: true,
+                }
+            })
+            .collect::<TVec<_>>();
+        Ok(axes.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_hot_last_axis() {
+        let op = OneHot::new(1, 3, rctensor0(0f32), rctensor0(1f32));
+        let output = op.eval(tvec!(rctensor1(&[0i32, 2, -1, 5]))).unwrap();
+        assert_eq!(
+            output,
+            tvec!(rctensor2(&[[1f32, 0., 0.], [0., 0., 1.], [0., 0., 0.], [0., 0., 0.]]))
+        );
+    }
+
+    #[test]
+    fn one_hot_first_axis() {
+        let op = OneHot::new(0, 2, rctensor0(-1i64), rctensor0(7i64));
+        let output = op.eval(tvec!(rctensor1(&[1i64, 0, 1]))).unwrap();
+        assert_eq!(output, tvec!(rctensor2(&[[-1i64, 7, -1], [7, -1, 7]])));
+    }
+}
@@ -6,6 +6,8 @@ use ndarray::*;
 pub enum PadMode {
     Constant(Arc<Tensor>),
     Reflect,
+    /// Reflect, including the edge values.
+    Symmetric,
     Edge,
 }
 
@@ -46,7 +48,7 @@ impl Pad {
             .collect();
         let slice_info = SliceInfo::<_, IxDyn>::new(slice_spec).unwrap();
         output.slice_mut(slice_info.as_ref()).assign(&input);
-        if self.mode == PadMode::Reflect || self.mode == PadMode::Edge {
+        if let PadMode::Reflect | PadMode::Symmetric | PadMode::Edge = self.mode {
             for (ax, &(bef, aft)) in self.pads.iter().enumerate() {
                 let axis = Axis(ax);
                 let dim = output.shape()[ax];
@@ -57,6 +59,7 @@ impl Pad {
                         let source_slice = match self.mode {
                             PadMode::Edge => 0,
                             PadMode::Reflect => bef - i,
+                            PadMode::Symmetric => bef - 1 - i,
                             _ => panic!(),
                         };
                         let source =
@@ -71,6 +74,7 @@ impl Pad {
                         let source_slice = match self.mode {
                             PadMode::Edge => dim - aft - 1,
                             PadMode::Reflect => dim - aft - 2 - i,
+                            PadMode::Symmetric => dim - aft - 1 - i,
                             _ => panic!(),
                         };
                         let source =
@@ -156,7 +160,7 @@ impl TypedOp for Pad {
                 }
             },
             PadMode::Edge => bail!("Edge padding mode needs pulse strictly bigger than left padding (pulse={} padding={})", pulse, before),
-            PadMode::Reflect => bail!("Reflect padding mode pulsing is not supported")
+            PadMode::Reflect | PadMode::Symmetric => bail!("{:?} padding mode pulsing is not supported", self.mode)
         };
         if extra_delay > 0 {
             input = target.wire_node(
@@ -385,3 +389,23 @@ impl PulsedOp for PulsePad {
     as_op!();
     pulsed_op_to_typed_op!();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pad(mode: PadMode) -> Arc<Tensor> {
+        let op = Pad::new(vec![(2, 2)], mode);
+        op.eval(tvec!(rctensor1(&[1, 2, 3]))).unwrap().remove(0)
+    }
+
+    #[test]
+    fn reflect() {
+        assert_eq!(pad(PadMode::Reflect), rctensor1(&[3, 2, 1, 2, 3, 2, 1]));
+    }
+
+    #[test]
+    fn symmetric() {
+        assert_eq!(pad(PadMode::Symmetric), rctensor1(&[2, 1, 1, 2, 3, 3, 2]));
+    }
+}
@@ -0,0 +1,142 @@
+use super::scatter_nd::ScatterReduction;
+use crate::internal::*;
+use ndarray::*;
+use std::ops::{Add, Mul};
+
+/// Scatter updates in a copy of data along an axis: each update goes to its
+/// own coordinates, except along `axis`, where the coordinate is read from
+/// indices. Indices and updates have the same shape.
+#[derive(Debug, Clone, new, Hash)]
+pub struct ScatterElements {
+    pub axis: usize,
+    pub reduction: ScatterReduction,
+}
+
+tract_linalg::impl_dyn_hash!(ScatterElements);
+
+impl ScatterElements {
+    fn eval_t<T: Datum>(
+        &self,
+        data: &Tensor,
+        indices: &ArrayViewD<i64>,
+        updates: &Tensor,
+        combine: impl Fn(&mut T, &T),
+    ) -> TractResult<Tensor> {
+        let mut output = data.clone();
+        let mut output_view = output.to_array_view_mut::<T>()?;
+        let updates = updates.to_array_view::<T>()?;
+        if updates.shape() != indices.shape() {
+            bail!(
+                "ScatterElements: updates of shape {:?} for indices of shape {:?}",
+                updates.shape(),
+                indices.shape()
+            )
+        }
+        let len = output_view.shape()[self.axis] as i64;
+        let mut coords = IxDyn(&vec![0; output_view.ndim()]);
+        for ((ix, &index), update) in indices.indexed_iter().zip(updates.iter()) {
+            let index = if index < 0 { index + len } else { index };
+            if index < 0 || index >= len {
+                bail!("ScatterElements: index {} out of bounds for axis of length {}", index, len)
+            }
+            coords.slice_mut().copy_from_slice(ix.slice());
+            coords[self.axis] = index as usize;
+            combine(&mut output_view[&coords], update);
+        }
+        Ok(output)
+    }
+
+    fn assign_t<T: Datum>(
+        &self,
+        data: &Tensor,
+        indices: &ArrayViewD<i64>,
+        updates: &Tensor,
+    ) -> TractResult<Tensor> {
+        self.eval_t::<T>(data, indices, updates, |d, s| *d = s.clone())
+    }
+
+    fn reduce_t<T>(
+        &self,
+        data: &Tensor,
+        indices: &ArrayViewD<i64>,
+        updates: &Tensor,
+    ) -> TractResult<Tensor>
+    where
+        T: Datum + PartialOrd + Add<Output = T> + Mul<Output = T>,
+    {
+        let reduction = self.reduction;
+        self.eval_t::<T>(data, indices, updates, |d, s| reduction.combine(d, s))
+    }
+}
+
+impl Op for ScatterElements {
+    fn name(&self) -> Cow<str> {
+        "ScatterElements".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {} reduction: {:?}", self.axis, self.reduction)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for ScatterElements {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (data, indices, updates) = args_3!(inputs);
+        if data.datum_type() != updates.datum_type() {
+            bail!(
+                "ScatterElements: data is {:?}, updates are {:?}",
+                data.datum_type(),
+                updates.datum_type()
+            )
+        }
+        let indices = indices.cast_to::<i64>()?;
+        let indices = indices.to_array_view::<i64>()?;
+        let output = if self.reduction == ScatterReduction::None {
+            dispatch_datum!(Self::assign_t(data.datum_type())(self, &data, &indices, &updates))?
+        } else {
+            dispatch_numbers!(Self::reduce_t(data.datum_type())(self, &data, &indices, &updates))?
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for ScatterElements {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &*inputs[0].shape.to_tvec())?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scatter_elements_axis_1() {
+        let op = ScatterElements::new(1, ScatterReduction::None);
+        let data = rctensor2(&[[1f32, 2., 3., 4., 5.]]);
+        let indices = rctensor2(&[[1i64, 3]]);
+        let updates = rctensor2(&[[1.1f32, 2.1]]);
+        assert_eq!(
+            op.eval(tvec!(data, indices, updates)).unwrap(),
+            tvec!(rctensor2(&[[1f32, 1.1, 3., 2.1, 5.]]))
+        );
+    }
+
+    #[test]
+    fn scatter_elements_max() {
+        let op = ScatterElements::new(0, ScatterReduction::Max);
+        let data = rctensor1(&[1i64, 5, 3]);
+        let indices = rctensor1(&[0i64, 1, 0]);
+        let updates = rctensor1(&[4i64, 2, 7]);
+        assert_eq!(
+            op.eval(tvec!(data, indices, updates)).unwrap(),
+            tvec!(rctensor1(&[7i64, 5, 3]))
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use crate::internal::*;
+use ndarray::*;
+use std::ops::{Add, Mul};
+
+/// How scattered updates combine with the values already in place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScatterReduction {
+    None,
+    Add,
+    Mul,
+    Max,
+    Min,
+}
+
+impl Default for ScatterReduction {
+    fn default() -> ScatterReduction {
+        ScatterReduction::None
+    }
+}
+
+impl ScatterReduction {
+    pub(super) fn combine<T>(self, current: &mut T, update: &T)
+    where
+        T: Datum + PartialOrd + Add<Output = T> + Mul<Output = T>,
+    {
+        use ScatterReduction::*;
+        match self {
+            None => *current = update.clone(),
+            Add => *current = current.clone() + update.clone(),
+            Mul => *current = current.clone() * update.clone(),
+            Max => {
+                if update > current {
+                    *current = update.clone()
+                }
+            }
+            Min => {
+                if update < current {
+                    *current = update.clone()
+                }
+            }
+        }
+    }
+}
+
+/// Scatter slices of updates in a copy of data, at the n-dimensional
+/// indices found along the last axis of indices.
+#[derive(Debug, Clone, new, Hash)]
+pub struct ScatterNd {
+    pub reduction: ScatterReduction,
+}
+
+tract_linalg::impl_dyn_hash!(ScatterNd);
+
+impl ScatterNd {
+    fn check_ranks(data_rank: usize, indices_rank: usize) -> TractResult<()> {
+        if indices_rank == 0 {
+            bail!("ScatterNd: indices must have at least one axis")
+        }
+        if data_rank == 0 {
+            bail!("ScatterNd: data must have at least one axis")
+        }
+        Ok(())
+    }
+
+    fn eval_t<T: Datum>(
+        &self,
+        data: &Tensor,
+        indices: &ArrayViewD<i64>,
+        updates: &Tensor,
+        combine: impl Fn(&mut T, &T),
+    ) -> TractResult<Tensor> {
+        let mut output = data.clone();
+        let mut output_view = output.to_array_view_mut::<T>()?;
+        let updates = updates.to_array_view::<T>()?;
+        for prefix in ndarray::indices(&indices.shape()[0..indices.ndim() - 1]) {
+            let mut coords = indices.view();
+            let mut src = updates.view();
+            for &x in prefix.slice().iter() {
+                coords.index_axis_inplace(Axis(0), x);
+                src.index_axis_inplace(Axis(0), x);
+            }
+            let mut dst = output_view.view_mut();
+            for &x in coords.iter() {
+                let len = dst.shape()[0] as i64;
+                let x = if x < 0 { x + len } else { x };
+                if x < 0 || x >= len {
+                    bail!("ScatterNd: index {} out of bounds for axis of length {}", x, len)
+                }
+                dst.index_axis_inplace(Axis(0), x as usize);
+            }
+            if dst.shape() != src.shape() {
+                bail!(
+                    "ScatterNd: updates slice of shape {:?} for data slice of shape {:?}",
+                    src.shape(),
+                    dst.shape()
+                )
+            }
+            Zip::from(&mut dst).and(&src).apply(|d, s| combine(d, s));
+        }
+        Ok(output)
+    }
+
+    fn assign_t<T: Datum>(
+        &self,
+        data: &Tensor,
+        indices: &ArrayViewD<i64>,
+        updates: &Tensor,
+    ) -> TractResult<Tensor> {
+        self.eval_t::<T>(data, indices, updates, |d, s| *d = s.clone())
+    }
+
+    fn reduce_t<T>(
+        &self,
+        data: &Tensor,
+        indices: &ArrayViewD<i64>,
+        updates: &Tensor,
+    ) -> TractResult<Tensor>
+    where
+        T: Datum + PartialOrd + Add<Output = T> + Mul<Output = T>,
+    {
+        let reduction = self.reduction;
+        self.eval_t::<T>(data, indices, updates, |d, s| reduction.combine(d, s))
+    }
+}
+
+impl Op for ScatterNd {
+    fn name(&self) -> Cow<str> {
+        "ScatterNd".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("reduction: {:?}", self.reduction)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for ScatterNd {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (data, indices, updates) = args_3!(inputs);
+        if data.datum_type() != updates.datum_type() {
+            bail!(
+                "ScatterNd: data is {:?}, updates are {:?}",
+                data.datum_type(),
+                updates.datum_type()
+            )
+        }
+        Self::check_ranks(data.rank(), indices.rank())?;
+        let indices = indices.cast_to::<i64>()?;
+        let indices = indices.to_array_view::<i64>()?;
+        let output = if self.reduction == ScatterReduction::None {
+            dispatch_datum!(Self::assign_t(data.datum_type())(self, &data, &indices, &updates))?
+        } else {
+            dispatch_numbers!(Self::reduce_t(data.datum_type())(self, &data, &indices, &updates))?
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for ScatterNd {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Self::check_ranks(inputs[0].rank(), inputs[1].rank())?;
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &*inputs[0].shape.to_tvec())?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scatter_rows() {
+        let op = ScatterNd::new(ScatterReduction::None);
+        let data = rctensor2(&[[1f32, 2.], [3., 4.], [5., 6.]]);
+        let indices = rctensor2(&[[2i64], [0]]);
+        let updates = rctensor2(&[[10f32, 20.], [30., 40.]]);
+        assert_eq!(
+            op.eval(tvec!(data, indices, updates)).unwrap(),
+            tvec!(rctensor2(&[[30f32, 40.], [3., 4.], [10., 20.]]))
+        );
+    }
+
+    #[test]
+    fn scatter_add_duplicates() {
+        let op = ScatterNd::new(ScatterReduction::Add);
+        let data = rctensor1(&[1i32, 2, 3]);
+        let indices = rctensor2(&[[1i64], [1], [-1]]);
+        let updates = rctensor1(&[10i32, 20, 30]);
+        assert_eq!(
+            op.eval(tvec!(data, indices, updates)).unwrap(),
+            tvec!(rctensor1(&[1i32, 32, 33]))
+        );
+    }
+
+    #[test]
+    fn scalar_indices_are_rejected() {
+        let op = ScatterNd::new(ScatterReduction::None);
+        let data = rctensor1(&[1i32, 2, 3]);
+        assert!(op.eval(tvec!(data.clone(), rctensor0(1i64), rctensor0(4i32))).is_err());
+        let data = TypedFact::from(data);
+        let indices = TypedFact::dt_shape(i64::datum_type(), [0usize; 0].as_ref()).unwrap();
+        let updates = TypedFact::dt_shape(i32::datum_type(), [0usize; 0].as_ref()).unwrap();
+        assert!(op.output_facts(&[&data, &indices, &updates]).is_err());
+    }
+}
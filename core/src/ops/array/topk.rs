@@ -0,0 +1,133 @@
+use crate::internal::*;
+use ndarray::*;
+use std::cmp::Ordering;
+
+/// The k largest (or smallest) values along an axis, and their indices.
+///
+/// Inputs are the data and k, a scalar or single-element tensor. When k is
+/// only known at runtime, `fallback_k` is used as the output dimension.
+/// Ties are broken by keeping the lowest index first.
+#[derive(Debug, Clone, new, Hash)]
+pub struct TopK {
+    pub axis: usize,
+    pub largest: bool,
+    pub fallback_k: TDim,
+}
+
+tract_linalg::impl_dyn_hash!(TopK);
+
+impl TopK {
+    fn k(k: &Tensor) -> TractResult<i64> {
+        if k.len() != 1 {
+            bail!("TopK: k must be a scalar or a single-element tensor, got shape {:?}", k.shape())
+        }
+        let k = k.cast_to::<i64>()?.as_slice::<i64>()?[0];
+        if k < 0 {
+            bail!("TopK: k must be positive, got {}", k)
+        }
+        Ok(k)
+    }
+
+    fn eval_t<T: Datum + PartialOrd>(
+        &self,
+        input: &Tensor,
+        k: usize,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = input.to_array_view::<T>()?;
+        let mut shape = input.shape().to_vec();
+        if k > shape[self.axis] {
+            bail!("TopK: k ({}) is greater than the axis length ({})", k, shape[self.axis])
+        }
+        shape[self.axis] = k;
+        let mut values = ArrayD::<T>::default(&*shape);
+        let mut indices = ArrayD::<i64>::zeros(&*shape);
+        for ((lane, mut values), mut indices) in input
+            .lanes(Axis(self.axis))
+            .into_iter()
+            .zip(values.lanes_mut(Axis(self.axis)))
+            .zip(indices.lanes_mut(Axis(self.axis)))
+        {
+            let mut sorted: Vec<(usize, &T)> = lane.iter().enumerate().collect();
+            if self.largest {
+                sorted.sort_by(|a, b| b.1.partial_cmp(a.1).unwrap_or(Ordering::Equal));
+            } else {
+                sorted.sort_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(Ordering::Equal));
+            }
+            for (ix, (index, value)) in sorted.into_iter().take(k).enumerate() {
+                values[ix] = value.clone();
+                indices[ix] = index as i64;
+            }
+        }
+        Ok(tvec!(values.into_arc_tensor(), indices.into_arc_tensor()))
+    }
+}
+
+impl Op for TopK {
+    fn name(&self) -> Cow<str> {
+        "TopK".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {} largest: {:?}", self.axis, self.largest)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for TopK {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let k = Self::k(&inputs[1])?;
+        let input = &inputs[0];
+        dispatch_numbers!(Self::eval_t(input.datum_type())(self, input, k as usize))
+    }
+}
+
+impl TypedOp for TopK {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let mut shape = inputs[0].shape.to_tvec();
+        shape[self.axis] = if let Some(k) = &inputs[1].konst {
+            Self::k(k)?.to_dim()
+        } else {
+            self.fallback_k.clone()
+        };
+        Ok(tvec!(
+            TypedFact::dt_shape(inputs[0].datum_type, &*shape)?,
+            TypedFact::dt_shape(i64::datum_type(), &*shape)?
+        ))
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn top_2_largest_and_smallest() {
+        let input = rctensor2(&[[1f32, 4., 3., 4.], [0., -1., 2., 5.]]);
+        let op = TopK::new(1, true, TDim::sym('k'));
+        let result = op.eval(tvec!(input.clone(), rctensor0(2i64))).unwrap();
+        assert_eq!(*result[0], tensor2(&[[4f32, 4.], [5., 2.]]));
+        assert_eq!(*result[1], tensor2(&[[1i64, 3], [3, 2]]));
+        let op = TopK::new(0, false, TDim::sym('k'));
+        let result = op.eval(tvec!(input, rctensor1(&[1i64]))).unwrap();
+        assert_eq!(*result[0], tensor2(&[[0f32, -1., 2., 4.]]));
+        assert_eq!(*result[1], tensor2(&[[1i64, 1, 1, 0]]));
+    }
+
+    #[test]
+    fn k_must_be_a_single_value() {
+        let op = TopK::new(0, true, TDim::sym('k'));
+        let input = TypedFact::dt_shape(f32::datum_type(), [4].as_ref()).unwrap();
+        for k in &[rctensor1(&[] as &[i64]), rctensor1(&[1i64, 2])] {
+            let k = TypedFact::from(k.clone());
+            assert!(op.output_facts(&[&input, &k]).is_err());
+        }
+        let k = TypedFact::from(rctensor0(3i64));
+        assert_eq!(op.output_facts(&[&input, &k]).unwrap()[0].shape.to_tvec(), tvec!(3.to_dim()));
+        assert!(op.eval(tvec!(rctensor1(&[1f32, 2.]), rctensor1(&[] as &[i64]))).is_err());
+    }
+}
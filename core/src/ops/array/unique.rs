@@ -0,0 +1,148 @@
+use crate::internal::*;
+use ndarray::*;
+use std::cmp::Ordering;
+
+/// Unique elements (or slices along `axis`) of the input.
+///
+/// Outputs are the unique values, the index of their first occurrence in
+/// the input, the index in the unique values of each input element (or
+/// slice), and the number of occurrences of each unique value. Without
+/// `sorted`, unique values come in order of first occurrence.
+///
+/// The number of unique values is only known at runtime, so the output facts
+/// use the `count` dimension.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Unique {
+    pub axis: Option<usize>,
+    pub sorted: bool,
+    pub count: TDim,
+}
+
+tract_linalg::impl_dyn_hash!(Unique);
+
+impl Unique {
+    fn eval_t<T: Datum + PartialOrd>(&self, input: &Tensor) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = input.to_array_view::<T>()?;
+        let (input, axis) = if let Some(axis) = self.axis {
+            (input, axis)
+        } else {
+            let len = input.len();
+            (input.into_shape(IxDyn(&[len]))?, 0)
+        };
+        let slices: Vec<_> = input.axis_iter(Axis(axis)).collect();
+        let cmp = |a: usize, b: usize| {
+            slices[a]
+                .iter()
+                .zip(slices[b].iter())
+                .map(|(x, y)| x.partial_cmp(y).unwrap_or(Ordering::Equal))
+                .find(|o| *o != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        };
+        let mut order: Vec<usize> = (0..slices.len()).collect();
+        // stable, so the first member of each group is its first occurrence
+        order.sort_by(|&a, &b| cmp(a, b));
+        let mut groups: Vec<Vec<usize>> = vec![];
+        for ix in order {
+            match groups.last_mut() {
+                Some(group) if cmp(group[0], ix) == Ordering::Equal => group.push(ix),
+                _ => groups.push(vec![ix]),
+            }
+        }
+        if !self.sorted {
+            groups.sort_by_key(|group| group[0]);
+        }
+        let firsts: Vec<usize> = groups.iter().map(|group| group[0]).collect();
+        let mut inverse = vec![0i64; slices.len()];
+        for (group_ix, group) in groups.iter().enumerate() {
+            for &ix in group {
+                inverse[ix] = group_ix as i64;
+            }
+        }
+        let mut shape = input.shape().to_vec();
+        shape[axis] = firsts.len();
+        let values = ArrayD::from_shape_fn(shape, |mut coords| {
+            coords[axis] = firsts[coords[axis]];
+            input[coords].clone()
+        });
+        let firsts: Vec<i64> = firsts.into_iter().map(|ix| ix as i64).collect();
+        let counts: Vec<i64> = groups.iter().map(|group| group.len() as i64).collect();
+        Ok(tvec!(
+            values.into_arc_tensor(),
+            rctensor1(&firsts),
+            rctensor1(&inverse),
+            rctensor1(&counts)
+        ))
+    }
+}
+
+impl Op for Unique {
+    fn name(&self) -> Cow<str> {
+        "Unique".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {:?} sorted: {:?}", self.axis, self.sorted)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for Unique {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        if input.datum_type() == String::datum_type() {
+            self.eval_t::<String>(&input)
+        } else {
+            dispatch_numbers!(Self::eval_t(input.datum_type())(self, &input))
+        }
+    }
+}
+
+impl TypedOp for Unique {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let (values, len) = if let Some(axis) = self.axis {
+            let mut shape = inputs[0].shape.to_tvec();
+            let len = std::mem::replace(&mut shape[axis], self.count.clone());
+            (shape, len)
+        } else {
+            let len = inputs[0].shape.iter().maybe_product()?;
+            (tvec!(self.count.clone()), len)
+        };
+        Ok(tvec!(
+            TypedFact::dt_shape(inputs[0].datum_type, &*values)?,
+            TypedFact::dt_shape(i64::datum_type(), [self.count.clone()].as_ref())?,
+            TypedFact::dt_shape(i64::datum_type(), [len].as_ref())?,
+            TypedFact::dt_shape(i64::datum_type(), [self.count.clone()].as_ref())?
+        ))
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_flat() {
+        let op = Unique::new(None, false, TDim::sym('n'));
+        let result = op.eval(tvec!(rctensor2(&[[2f32, 1.], [1., 3.]]))).unwrap();
+        assert_eq!(*result[0], tensor1(&[2f32, 1., 3.]));
+        assert_eq!(*result[1], tensor1(&[0i64, 1, 3]));
+        assert_eq!(*result[2], tensor1(&[0i64, 1, 1, 2]));
+        assert_eq!(*result[3], tensor1(&[1i64, 2, 1]));
+    }
+
+    #[test]
+    fn unique_sorted_rows() {
+        let op = Unique::new(Some(0), true, TDim::sym('n'));
+        let input = rctensor2(&[[1i32, 0], [1, 0], [0, 5]]);
+        let result = op.eval(tvec!(input)).unwrap();
+        assert_eq!(*result[0], tensor2(&[[0i32, 5], [1, 0]]));
+        assert_eq!(*result[1], tensor1(&[2i64, 0]));
+        assert_eq!(*result[2], tensor1(&[1i64, 1, 0]));
+        assert_eq!(*result[3], tensor1(&[1i64, 2]));
+    }
+}
@@ -29,11 +29,41 @@ pub trait BinMiniOp:
         self.eval_out_of_place(&mut c, a.as_ref(), b.as_ref())?;
         Ok(tvec!(c.into_arc_tensor()))
     }
+    /// Same as `eval_broadcast`, writing the result in `c`. Returns false if
+    /// `c` does not have the result type and shape.
+    fn eval_broadcast_into(&self, a: &Tensor, b: &Tensor, c: &mut Tensor) -> TractResult<bool> {
+        let c_shape = crate::broadcast::multi_broadcast(&[a.shape(), b.shape()])
+            .ok_or("Can not compute resulting shape")?;
+        let c_dt = self.result_datum_type(a.datum_type(), b.datum_type())?;
+        if c.datum_type() != c_dt || c.shape() != &*c_shape {
+            return Ok(false);
+        }
+        self.eval_out_of_place(c, a, b)?;
+        Ok(true)
+    }
+    /// Whether `eval_broadcast` computes in place in b for these facts.
+    fn in_place_in_b(&self, a: &TypedFact, b: &TypedFact) -> bool {
+        a.shape == b.shape
+            && a.datum_type == b.datum_type
+            && self.result_datum_type(a.datum_type, b.datum_type).ok() == Some(b.datum_type)
+    }
     fn eval_broadcast(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
-        let (a, b) = args_2!(inputs);
+        let (a, mut b) = args_2!(inputs);
         let c_shape = crate::broadcast::multi_broadcast(&[a.shape(), b.shape()])
             .ok_or("Can not compute resulting shape")?;
         let c_dt = self.result_datum_type(a.datum_type(), b.datum_type())?;
+        if a.shape() == b.shape() && a.datum_type() == c_dt && b.datum_type() == c_dt {
+            // b is not used anywhere else: write the result in place
+            b = match Arc::try_unwrap(b) {
+                Ok(mut b) => {
+                    if self.eval_in_place(a.as_ref(), &mut b).is_ok() {
+                        return Ok(tvec!(b.into_arc_tensor()));
+                    }
+                    b.into_arc_tensor()
+                }
+                Err(b) => b,
+            }
+        }
         let mut c = unsafe { Tensor::uninitialized_dt(c_dt, &*c_shape)? };
         self.eval_out_of_place(&mut c, a.as_ref(), b.as_ref())?;
         Ok(tvec!(c.into_arc_tensor()))
@@ -98,6 +128,18 @@ impl StatelessOp for TypedBinOp {
         debug_assert_eq!(inputs[0].rank(), inputs[1].rank());
         self.0.eval_broadcast(inputs)
     }
+
+    fn eval_into(&self, inputs: &[Arc<Tensor>], output: &mut Tensor) -> TractResult<bool> {
+        self.0.eval_broadcast_into(&inputs[0], &inputs[1], output)
+    }
+
+    fn in_place_input(&self, inputs: &[&TypedFact]) -> Option<usize> {
+        if self.0.in_place_in_b(inputs[0], inputs[1]) {
+            Some(1)
+        } else {
+            None
+        }
+    }
 }
 
 impl TypedOp for TypedBinOp {
@@ -287,9 +329,13 @@ impl Op for UnaryOp {
 }
 
 impl StatelessOp for UnaryOp {
-    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
         debug_assert_eq!(self.a.rank(), inputs[0].rank());
-        self.mini_op.eval_broadcast(tvec!(self.a.clone(), inputs[0].clone()))
+        self.mini_op.eval_broadcast(tvec!(self.a.clone(), args_1!(inputs)))
+    }
+
+    fn eval_into(&self, inputs: &[Arc<Tensor>], output: &mut Tensor) -> TractResult<bool> {
+        self.mini_op.eval_broadcast_into(&self.a, &inputs[0], output)
     }
 }
 
@@ -619,6 +665,10 @@ impl StatelessOp for MergeOpUnicast {
         self.0.eval_in_place(a.as_ref(), &mut b)?;
         Ok(tvec!(b.into_arc_tensor()))
     }
+
+    fn in_place_input(&self, _inputs: &[&TypedFact]) -> Option<usize> {
+        Some(1)
+    }
 }
 
 impl TypedOp for MergeOpUnicast {
@@ -7,6 +7,11 @@ use crate::ops::cnn::Patch;
 use crate::ops::nn::DataShape;
 
 use num_traits::Zero;
+use std::ops::Range;
+use tract_linalg::multithread::{current_tract_executor, split, Executor, Shared};
+
+/// Below this many packed items, im2col runs on one thread.
+const MIN_PARALLEL_PACKING: usize = 64 * 1024;
 
 #[derive(Debug, Clone, Educe)]
 #[educe(Hash)]
@@ -96,6 +101,16 @@ impl<T: Copy + Datum + Zero> Im2Col<T> {
             return Ok(packed);
         }
         let pad_value = *self.pad_value.to_scalar()?;
+        let pool = match current_tract_executor() {
+            Executor::MultiThread(pool)
+                if self.patcher != Patcher::Generic
+                    && self.ci_per_group > 1
+                    && self.k * self.n >= MIN_PARALLEL_PACKING =>
+            {
+                Some(pool)
+            }
+            _ => None,
+        };
         for i in 0..*self.input_shape.n_dim().unwrap_or(&1) {
             for g in 0..self.group {
                 let mut packed = packed.to_array_view_mut::<T>()?;
@@ -106,7 +121,22 @@ impl<T: Copy + Datum + Zero> Im2Col<T> {
                 } else {
                     input.view()
                 };
-                self.patcher.patch(self, &input, packed.as_slice_mut().unwrap(), g, pad_value);
+                let pack = packed.as_mut_ptr();
+                if let Some(pool) = &pool {
+                    // each chunk of input channels fills its own rows of the packed matrix
+                    let (input, pack) = (&input, Shared(pack));
+                    pool.scope(|s| {
+                        for cis in split(self.ci_per_group, pool.current_num_threads()) {
+                            s.spawn(move |_| unsafe {
+                                self.patcher.patch(self, input, pack.0, g, cis, pad_value)
+                            });
+                        }
+                    });
+                } else {
+                    unsafe {
+                        self.patcher.patch(self, &input, pack, g, 0..self.ci_per_group, pad_value)
+                    }
+                }
             }
         }
         Ok(packed)
@@ -148,7 +178,7 @@ impl<T: Copy + Datum + Zero> TypedOp for Im2Col<T> {
     }
 }
 
-#[derive(Copy, Clone, Debug, Hash)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq)]
 enum Patcher {
     Generic,
     Valid1d,
@@ -157,12 +187,16 @@ enum Patcher {
 }
 
 impl Patcher {
-    fn patch<'i, 'p, T: Copy + Datum + Zero>(
+    /// Fills the packed matrix rows for the `cis` input channels of group `g`.
+    ///
+    /// The generic patcher only supports the full channel range.
+    unsafe fn patch<'i, T: Copy + Datum + Zero>(
         &self,
         im2col: &'i Im2Col<T>,
         input: &'i ArrayViewD<'i, T>,
-        pack: &'p mut [T],
+        pack: *mut T,
         g: usize,
+        cis: Range<usize>,
         pad_value: T,
     ) {
         match self {
@@ -171,21 +205,28 @@ impl Patcher {
                 input.view().into_dimensionality().as_ref().unwrap(),
                 pack,
                 g,
+                cis,
             ),
             Patcher::Valid2d => Self::valid_2d(
                 im2col,
                 input.view().into_dimensionality().as_ref().unwrap(),
                 pack,
                 g,
+                cis,
             ),
             Patcher::Padded2d => Self::padded_2d(
                 im2col,
                 input.view().into_dimensionality().as_ref().unwrap(),
                 pack,
                 g,
+                cis,
                 pad_value,
             ),
-            _ => Self::generic(im2col, input, pack, g, pad_value),
+            _ => {
+                debug_assert_eq!(cis, 0..im2col.ci_per_group);
+                let pack = std::slice::from_raw_parts_mut(pack, im2col.b_pack.len());
+                Self::generic(im2col, input, pack, g, pad_value)
+            }
         }
     }
 
@@ -225,19 +266,21 @@ impl Patcher {
     }
 
     #[inline(never)]
-    fn valid_1d<'i, 'p, T: Copy + Datum + Zero>(
+    fn valid_1d<'i, T: Copy + Datum + Zero>(
         im2col: &'i Im2Col<T>,
         input: &'i ArrayView2<'i, T>,
-        pack: &'p mut [T],
+        pack: *mut T,
         g: usize,
+        cis: Range<usize>,
     ) {
         unsafe {
             let x_stride =
                 *im2col.input_shape.h_stride() as isize * im2col.patch.spec.strides[0] as isize;
             let c_stride = *im2col.input_shape.c_stride() as isize;
-            let mut writer = im2col.b_pack.write_packed_by_rows(pack);
+            let kernel_len = im2col.patch.standard_layout_data_field.len();
+            let mut writer = im2col.b_pack.write_packed_by_rows_from(pack, cis.start * kernel_len);
             let iptr = input.as_ptr();
-            for ci in (im2col.ci_per_group * g)..(im2col.ci_per_group * (g + 1)) {
+            for ci in (im2col.ci_per_group * g + cis.start)..(im2col.ci_per_group * g + cis.end) {
                 let iptr = iptr.offset(ci as isize * c_stride);
                 for koffset in &im2col.patch.standard_layout_data_field {
                     let iptr = iptr.offset(*koffset as isize);
@@ -250,11 +293,12 @@ impl Patcher {
     }
 
     #[inline(never)]
-    fn padded_2d<'i, 'p, T: Copy + Datum + Zero>(
+    fn padded_2d<'i, T: Copy + Datum + Zero>(
         im2col: &'i Im2Col<T>,
         input: &'i ArrayView3<'i, T>,
-        pack: &'p mut [T],
+        pack: *mut T,
         g: usize,
+        cis: Range<usize>,
         pad_value: T,
     ) {
         unsafe {
@@ -266,9 +310,9 @@ impl Patcher {
             let input_heigth = im2col.input_shape.hw_dims()[0] as isize;
             let input_width = im2col.input_shape.hw_dims()[1] as isize;
             let kernel_len = im2col.patch.standard_layout_data_field.len();
-            let mut writer = im2col.b_pack.write_packed_by_rows(pack);
+            let mut writer = im2col.b_pack.write_packed_by_rows_from(pack, cis.start * kernel_len);
             let iptr = input.as_ptr();
-            for ci in (im2col.ci_per_group * g)..(im2col.ci_per_group * (g + 1)) {
+            for ci in (im2col.ci_per_group * g + cis.start)..(im2col.ci_per_group * g + cis.end) {
                 let iptr = iptr.offset(ci as isize * c_stride_ptr);
                 for kitem in 0..kernel_len {
                     let dy = *im2col.patch.data_field.as_ptr().offset(kitem as isize * 2);
@@ -299,11 +343,12 @@ impl Patcher {
     }
 
     #[inline(never)]
-    fn valid_2d<'i, 'p, T: Copy + Datum + Zero>(
+    fn valid_2d<'i, T: Copy + Datum + Zero>(
         im2col: &'i Im2Col<T>,
         input: &'i ArrayView3<'i, T>,
-        pack: &'p mut [T],
+        pack: *mut T,
         g: usize,
+        cis: Range<usize>,
     ) {
         unsafe {
             let y_stride = im2col.patch.spec.strides[0] as isize;
@@ -311,9 +356,10 @@ impl Patcher {
             let y_stride_ptr = y_stride * *im2col.input_shape.h_stride() as isize;
             let x_stride_ptr = x_stride * *im2col.input_shape.w_stride() as isize;
             let c_stride_ptr = *im2col.input_shape.c_stride() as isize;
-            let mut writer = im2col.b_pack.write_packed_by_rows(pack);
+            let kernel_len = im2col.patch.standard_layout_data_field.len();
+            let mut writer = im2col.b_pack.write_packed_by_rows_from(pack, cis.start * kernel_len);
             let iptr = input.as_ptr();
-            for ci in (im2col.ci_per_group * g)..(im2col.ci_per_group * (g + 1)) {
+            for ci in (im2col.ci_per_group * g + cis.start)..(im2col.ci_per_group * g + cis.end) {
                 let iptr = iptr.offset(ci as isize * c_stride_ptr);
                 for koffset in &im2col.patch.standard_layout_data_field {
                     let iptr = iptr.offset(*koffset as isize);
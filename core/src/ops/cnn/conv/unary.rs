@@ -139,18 +139,25 @@ impl ConvUnary {
             return self.wire_as_im2col_pair_t(model, name, wire, direct, &|m, k, n| {
                 MMMWrapper::Plain((tract_linalg::ops().mmm_f32)(m, k, n))
             });
+        } else if (a, b) == (f64::datum_type(), f64::datum_type()) {
+            return self.wire_as_im2col_pair_t(model, name, wire, direct, &|m, k, n| {
+                MMMWrapper::Plain((tract_linalg::ops().mmm_f64)(m, k, n))
+            });
+        } else if (a, b) == (f16::datum_type(), f16::datum_type()) {
+            return self.wire_as_im2col_pair_t(model, name, wire, direct, &|m, k, n| {
+                MMMWrapper::Plain((tract_linalg::ops().mmm_f16)(m, k, n))
+            });
         } else if (a, b) == (u8::datum_type(), u8::datum_type()) {
             return self.wire_as_im2col_pair_t(model, name, wire, direct, &|m, k, n| {
                 MMMWrapper::Quant((tract_linalg::ops().qmmm_u8_i32)(m, k, n))
             });
         } else if (a, b) == (i8::datum_type(), i8::datum_type()) {
-            if let Some(q) = &self.q_params {
-                if q.c_datum_type == i8::datum_type() {
-                    return self.wire_as_im2col_pair_t(model, name, wire, direct, &|m, k, n| {
-                        MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i8)(m, k, n))
-                    });
-                }
-            } else {
+            let c = self.q_params.as_ref().map(|q| q.c_datum_type).unwrap_or(i32::datum_type());
+            if c == i8::datum_type() {
+                return self.wire_as_im2col_pair_t(model, name, wire, direct, &|m, k, n| {
+                    MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i8)(m, k, n))
+                });
+            } else if c == i32::datum_type() {
                 return self.wire_as_im2col_pair_t(model, name, wire, direct, &|m, k, n| {
                     MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i32)(m, k, n))
                 });
@@ -765,6 +772,94 @@ mod test {
     use crate::ops::cnn::PaddingSpec;
     use DataFormat::{HWC, NHWC};
 
+    #[test]
+    fn conv_multithread() {
+        use tract_linalg::multithread::Executor;
+        for padding in vec![PaddingSpec::Valid, PaddingSpec::SameUpper] {
+            let mut model = TypedModel::default();
+            let fact = TypedFact::dt_shape(f32::datum_type(), [1, 8, 34, 34].as_ref()).unwrap();
+            let x = model.add_source("x", fact).unwrap();
+            let kernel = Tensor::from(tract_ndarray::Array4::from_shape_fn(
+                (16, 8, 3, 3),
+                |(o, i, y, x)| (o + 2 * i + 3 * y + 5 * x) as f32 % 7. - 3.,
+            ));
+            let op = ConvUnary {
+                pool_spec: PoolSpec::new(
+                    DataFormat::NCHW,
+                    tvec!(3, 3),
+                    padding,
+                    None,
+                    None,
+                    Some(16),
+                ),
+                kernel_fmt: KernelFormat::OIHW,
+                kernel: kernel.into_arc_tensor(),
+                group: 1,
+                bias: None,
+                q_params: None,
+            };
+            let y = model.wire_node("conv", op, &[x]).unwrap();
+            model.set_output_outlets(&y).unwrap();
+            let model = model.declutter().unwrap().optimize().unwrap();
+            let input = Tensor::from(tract_ndarray::Array4::from_shape_fn(
+                (1, 8, 34, 34),
+                |(_, c, y, x)| ((c * 34 + y) * 34 + x) as f32 % 11. - 5.,
+            ));
+            let plan = SimplePlan::new(&model).unwrap();
+            let expected = plan.run(tvec!(input.clone())).unwrap();
+            let plan = plan.with_executor(Executor::multithread(3).unwrap());
+            let found = plan.run(tvec!(input)).unwrap();
+            found[0].close_enough(&expected[0], true).unwrap();
+        }
+    }
+
+    #[test]
+    fn conv_f64() {
+        fn run(dt: DatumType) -> Tensor {
+            let mut model = TypedModel::default();
+            let fact = TypedFact::dt_shape(dt, [1, 3, 10, 10].as_ref()).unwrap();
+            let x = model.add_source("x", fact).unwrap();
+            let kernel =
+                Tensor::from(tract_ndarray::Array4::from_shape_fn((4, 3, 3, 3), |(o, i, y, x)| {
+                    (o + 2 * i + 3 * y + 5 * x) as f32 % 7. - 3.
+                }));
+            let op = ConvUnary {
+                pool_spec: PoolSpec::new(
+                    DataFormat::NCHW,
+                    tvec!(3, 3),
+                    PaddingSpec::SameUpper,
+                    None,
+                    None,
+                    Some(4),
+                ),
+                kernel_fmt: KernelFormat::OIHW,
+                kernel: kernel.cast_to_dt(dt).unwrap().into_owned().into_arc_tensor(),
+                group: 1,
+                bias: Some(
+                    rctensor1(&[1f32, -1., 2., 0.])
+                        .cast_to_dt(dt)
+                        .unwrap()
+                        .into_owned()
+                        .into_arc_tensor(),
+                ),
+                q_params: None,
+            };
+            let y = model.wire_node("conv", op, &[x]).unwrap();
+            model.set_output_outlets(&y).unwrap();
+            let model = model.declutter().unwrap().optimize().unwrap();
+            let input = Tensor::from(tract_ndarray::Array4::from_shape_fn(
+                (1, 3, 10, 10),
+                |(_, c, y, x)| ((c * 10 + y) * 10 + x) as f32 % 11. - 5.,
+            ));
+            let input = input.cast_to_dt(dt).unwrap().into_owned();
+            let mut outputs = SimplePlan::new(&model).unwrap().run(tvec!(input)).unwrap();
+            outputs.remove(0).into_tensor()
+        }
+        let found = run(f64::datum_type());
+        assert_eq!(found.datum_type(), f64::datum_type());
+        assert_eq!(found.cast_to::<f32>().unwrap().into_owned(), run(f32::datum_type()));
+    }
+
     #[test]
     fn conv_vs_direct_arm_ml_kws_cnn_m_0() {
         let input = NHWC.from_n_c_hw(1, 1, &[49, 10]).unwrap();
@@ -0,0 +1,131 @@
+use crate::internal::*;
+use ndarray::prelude::*;
+
+use crate::ops::cnn::PoolSpec;
+use crate::ops::nn::DataFormat;
+
+use num_traits::Zero;
+use std::ops::AddAssign;
+
+/// Second half of a deconvolution: scatters the products computed by the
+/// matrix multiplication (one per output channel, kernel tap and input
+/// pixel) to their place in the output, crops the padding and adds the bias.
+///
+/// Input is expected as [N?, Cout * kernel surface, input surface], the group
+/// axis, if any, being folded with the channel axis.
+#[derive(Clone, Debug, new, Hash)]
+pub struct DeconvSum {
+    pub pool_spec: PoolSpec,
+    /// Shape of the deconvolution input.
+    pub input_shape: TVec<usize>,
+    pub adjustments: TVec<usize>,
+    pub bias: Option<Arc<Tensor>>,
+}
+
+tract_linalg::impl_dyn_hash!(DeconvSum);
+
+impl DeconvSum {
+    fn eval_t<T: Datum + Copy + Zero + AddAssign>(&self, input: &Tensor) -> TractResult<Tensor> {
+        let input_shape = self.pool_spec.data_format.shape(&*self.input_shape)?;
+        let output_shape =
+            super::output_shape(&self.pool_spec, &self.input_shape, &self.adjustments)?;
+        let output_shape = self.pool_spec.data_format.shape(output_shape)?;
+        let kernel_shape = &self.pool_spec.kernel_shape;
+        let computed = self.pool_spec.padding.compute_for_deconv(
+            input_shape.hw_dims(),
+            kernel_shape,
+            &self.pool_spec.dilations(),
+            &self.pool_spec.strides(),
+            &self.adjustments,
+        );
+        let n = *output_shape.n().unwrap_or(&1);
+        let co = *output_shape.c();
+        let kernel_surface = kernel_shape.iter().product::<usize>();
+        let input_surface = input_shape.hw_dims().iter().product::<usize>();
+        let output_surface = output_shape.hw_dims().iter().product::<usize>();
+        // output offset (in a NCHW layout) for each (kernel tap, input pixel) pair
+        let mut offsets: Vec<Option<usize>> = Vec::with_capacity(kernel_surface * input_surface);
+        for k in ndarray::indices(&**kernel_shape) {
+            for i in ndarray::indices(input_shape.hw_dims()) {
+                let mut offset = 0isize;
+                for axis in 0..kernel_shape.len() {
+                    let x = (i[axis] * self.pool_spec.stride(axis)
+                        + k[axis] * self.pool_spec.dilation(axis))
+                        as isize
+                        - computed[axis].pad_before as isize;
+                    let len = output_shape.hw_dims()[axis] as isize;
+                    if x < 0 || x >= len {
+                        offset = -1;
+                        break;
+                    }
+                    offset = offset * len + x;
+                }
+                offsets.push(if offset < 0 { None } else { Some(offset as usize) });
+            }
+        }
+        let input = input.as_slice::<T>()?;
+        let mut output = vec![T::zero(); n * co * output_surface];
+        let bias = self.bias.as_ref().map(|b| b.cast_to::<T>()).transpose()?;
+        let bias = bias.as_ref().map(|b| b.as_slice::<T>()).transpose()?;
+        for ni in 0..n {
+            for c in 0..co {
+                let input = &input[(ni * co + c) * kernel_surface * input_surface..]
+                    [..kernel_surface * input_surface];
+                let output = &mut output[(ni * co + c) * output_surface..][..output_surface];
+                for (x, offset) in input.iter().zip(offsets.iter()) {
+                    if let Some(offset) = offset {
+                        output[*offset] += *x;
+                    }
+                }
+                if let Some(bias) = bias {
+                    output.iter_mut().for_each(|o| *o += bias[c]);
+                }
+            }
+        }
+        let mut nchw_shape = tvec!(n, co);
+        nchw_shape.extend(output_shape.hw_dims().iter().cloned());
+        let mut output = ArrayD::from_shape_vec(&*nchw_shape, output)?;
+        if output_shape.fmt == DataFormat::NHWC || output_shape.fmt == DataFormat::HWC {
+            let mut permutation: Vec<usize> = vec![0];
+            permutation.extend(2..nchw_shape.len());
+            permutation.push(1);
+            output = output.permuted_axes(permutation);
+        }
+        if output_shape.n_axis().is_none() {
+            output = output.index_axis_move(Axis(0), 0);
+        }
+        Ok(output.as_standard_layout().into_owned().into_tensor())
+    }
+}
+
+impl Op for DeconvSum {
+    fn name(&self) -> Cow<str> {
+        "DeconvSum".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        let mut info = self.pool_spec.info();
+        info.push(format!("Adjustments: {:?}", self.adjustments));
+        Ok(info)
+    }
+
+    op_core_lir!();
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for DeconvSum {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let output = dispatch_numbers!(Self::eval_t(inputs[0].datum_type())(self, &inputs[0]))?;
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for DeconvSum {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let shape = super::output_shape(&self.pool_spec, &self.input_shape, &self.adjustments)?;
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &*shape)?))
+    }
+}
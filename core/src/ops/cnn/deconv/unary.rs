@@ -0,0 +1,305 @@
+use crate::internal::*;
+use crate::ops::cnn::{KernelFormat, PoolSpec};
+use crate::ops::matmul::MatMulUnary;
+use crate::ops::nn::DataFormat;
+
+use super::DeconvSum;
+
+/// Transposed convolution.
+///
+/// The kernel is given in the layout of the convolution this op is the
+/// transpose of: OIHW is [Cin, Cout/group, H, W] (as in ONNX ConvTranspose),
+/// HWIO is [H, W, Cout/group, Cin] (as in TF Conv2DBackpropInput).
+#[derive(Clone, Debug, new, Hash)]
+pub struct DeconvUnary {
+    pub pool_spec: PoolSpec,
+    pub kernel_fmt: KernelFormat,
+    pub kernel: Arc<Tensor>,
+    pub bias: Option<Arc<Tensor>>,
+    pub adjustments: TVec<usize>,
+    pub group: usize,
+}
+
+tract_linalg::impl_dyn_hash!(DeconvUnary);
+
+impl DeconvUnary {
+    fn input_channels(&self) -> usize {
+        match self.kernel_fmt {
+            KernelFormat::OIHW => self.kernel.shape()[0],
+            KernelFormat::HWIO => self.kernel.shape()[self.kernel.rank() - 1],
+        }
+    }
+
+    fn output_channels(&self) -> usize {
+        match self.kernel_fmt {
+            KernelFormat::OIHW => self.kernel.shape()[1] * self.group,
+            KernelFormat::HWIO => self.kernel.shape()[self.kernel.rank() - 2] * self.group,
+        }
+    }
+
+    /// Kernel as a [group?, Cout/group * kernel surface, Cin/group] tensor.
+    fn kernel_as_matmul_a<T: Datum>(&self) -> TractResult<Tensor> {
+        let kernel = self.kernel.to_array_view::<T>()?;
+        let spatial_rank = kernel.ndim() - 2;
+        let kernel = match self.kernel_fmt {
+            KernelFormat::OIHW => kernel,
+            KernelFormat::HWIO => {
+                let mut permutation = vec![spatial_rank + 1, spatial_rank];
+                permutation.extend(0..spatial_rank);
+                kernel.permuted_axes(permutation)
+            }
+        };
+        let ci = self.input_channels();
+        let m = kernel.len() / ci;
+        let kernel = kernel
+            .as_standard_layout()
+            .into_owned()
+            .into_shape((self.group, ci / self.group, m))?
+            .permuted_axes([0, 2, 1]);
+        let mut a = kernel.as_standard_layout().into_owned().into_dyn().into_tensor();
+        if self.group == 1 {
+            a = a.into_shape(&[m, ci])?;
+        }
+        Ok(a)
+    }
+
+    fn wire_with_deconv_sum(
+        &self,
+        name: &str,
+        target: &mut TypedModel,
+        input: OutletId,
+    ) -> TractResult<TVec<OutletId>> {
+        let input_shape = target
+            .outlet_fact(input)?
+            .shape
+            .as_finite()
+            .ok_or("Deconvolution needs a concrete input shape")?
+            .to_vec();
+        let shape = self.pool_spec.data_format.shape(&*input_shape)?;
+        let c_is_last = self.pool_spec.data_format == DataFormat::NHWC
+            || self.pool_spec.data_format == DataFormat::HWC;
+        let mut wire = input;
+        if shape.hw_rank() != 1 {
+            let geo_dim = shape.hw_dims().iter().product::<usize>();
+            wire = target.wire_node(
+                format!("{}.reshape_input", name),
+                AxisOp::Reshape(
+                    shape.h_axis(),
+                    shape.hw_dims().iter().map(|d| d.to_dim()).collect(),
+                    tvec!(geo_dim.to_dim()),
+                ),
+                &[wire],
+            )?[0];
+        }
+        if self.group != 1 {
+            let c_axis = if c_is_last { shape.h_axis() + 1 } else { shape.c_axis() };
+            wire = target.wire_node(
+                format!("{}.split_group", name),
+                AxisOp::Reshape(
+                    c_axis,
+                    tvec!(shape.c().to_dim()),
+                    tvec!(self.group.to_dim(), (shape.c() / self.group).to_dim()),
+                ),
+                &[wire],
+            )?[0];
+            if c_is_last {
+                wire = target.wire_node(
+                    format!("{}.group_first", name),
+                    AxisOp::Move(c_axis, shape.h_axis()),
+                    &[wire],
+                )?[0];
+            }
+        }
+        let dt = target.outlet_fact(input)?.datum_type;
+        let a = dispatch_datum!(Self::kernel_as_matmul_a(dt)(self))?;
+        wire = target.wire_node(
+            format!("{}.matmul", name),
+            MatMulUnary::new(a.into_arc_tensor(), false, c_is_last, false, None),
+            &[wire],
+        )?[0];
+        target.wire_node(
+            &*name,
+            DeconvSum::new(
+                self.pool_spec.clone(),
+                input_shape.into(),
+                self.adjustments.clone(),
+                self.bias.clone(),
+            ),
+            &[wire],
+        )
+    }
+}
+
+impl Op for DeconvUnary {
+    fn name(&self) -> Cow<str> {
+        "DeconvUnary".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        let mut info = self.pool_spec.info();
+        info.push(format!(
+            "Kernel shape, {:?}: {:?} (groups:{})",
+            self.kernel_fmt,
+            self.kernel.shape(),
+            self.group
+        ));
+        info.push(format!("Adjustments: {:?}", self.adjustments));
+        if let Some(b) = &self.bias {
+            info.push(format!("Bias: {:?}", b))
+        }
+        Ok(info)
+    }
+
+    fn validation(&self) -> Validation {
+        Validation::Rounding
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for DeconvUnary {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let mut model = TypedModel::default();
+        let dt = inputs[0].datum_type();
+        let wire = model.add_source("source", TypedFact::dt_shape(dt, inputs[0].shape())?)?;
+        let output = self.wire_with_deconv_sum("adhoc", &mut model, wire)?;
+        model.set_output_outlets(&*output)?;
+        let plan = SimplePlan::new(model)?;
+        plan.run(inputs.into_iter().map(|t| t.into_tensor()).collect())
+    }
+}
+
+impl TypedOp for DeconvUnary {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let x_fact = inputs[0];
+        let has_n = self.pool_spec.data_format == DataFormat::NCHW
+            || self.pool_spec.data_format == DataFormat::NHWC;
+        if x_fact.shape.rank() != self.kernel.rank() - !has_n as usize {
+            bail!("Deconvolution input and kernel ranks are inconsistent")
+        }
+        let output_shape =
+            super::output_shape(&self.pool_spec, &*x_fact.shape.to_tvec(), &self.adjustments)?;
+        Ok(tvec!(TypedFact::dt_shape(x_fact.datum_type, &*output_shape)?))
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let shape = self.pool_spec.data_format.shape(inputs[0].shape.to_tvec())?;
+        let n_input_points: TDim = shape.hw_dims().iter().maybe_product()?;
+        let kernel_surface = self.pool_spec.kernel_shape.iter().product::<usize>();
+        let one = 1.to_dim();
+        Ok(tvec!(
+            (
+                Cost::Params(inputs[0].datum_type),
+                (self.kernel.len() + self.bias.as_ref().map(|b| b.len()).unwrap_or(0)).to_dim()
+            ),
+            (
+                Cost::FMA(inputs[0].datum_type),
+                shape
+                    .n()
+                    .unwrap_or(&one)
+                    .maybe_mul(shape.c())?
+                    .maybe_mul(&(self.output_channels() / self.group).to_dim())?
+                    .maybe_mul(&n_input_points)?
+                    .maybe_mul(&kernel_surface.to_dim())?
+            )
+        ))
+    }
+
+    fn codegen(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        if model.outlet_fact(node.inputs[0])?.shape.as_finite().is_none() {
+            return Ok(None);
+        }
+        let mut patch = TypedModelPatch::default();
+        let wire = patch.tap_model(model, node.inputs[0])?;
+        let output = self.wire_with_deconv_sum(&node.name, &mut patch, wire)?;
+        patch.shunt_outside(model, OutletId::new(node.id, 0), output[0])?;
+        Ok(Some(patch))
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::cnn::PaddingSpec;
+
+    fn deconv(
+        data_format: DataFormat,
+        padding: PaddingSpec,
+        kernel: Tensor,
+        strides: usize,
+        group: usize,
+    ) -> DeconvUnary {
+        let kernel_shape: TVec<usize> = kernel.shape()[2..].into();
+        let co = kernel.shape()[1] * group;
+        let spatial_rank = kernel_shape.len();
+        let pool_spec = PoolSpec::new(
+            data_format,
+            kernel_shape,
+            padding,
+            None,
+            Some(tvec!(strides; spatial_rank)),
+            Some(co),
+        );
+        DeconvUnary::new(
+            pool_spec,
+            KernelFormat::OIHW,
+            kernel.into_arc_tensor(),
+            None,
+            tvec!(0; spatial_rank),
+            group,
+        )
+    }
+
+    #[test]
+    fn deconv_1d_stride_2() {
+        let op = deconv(DataFormat::CHW, PaddingSpec::Valid, tensor3(&[[[1f32, 2., 3.]]]), 2, 1);
+        let output = op.eval(tvec!(rctensor2(&[[1f32, 10.]]))).unwrap();
+        assert_eq!(output[0], rctensor2(&[[1f32, 2., 13., 20., 30.]]));
+    }
+
+    #[test]
+    fn deconv_2d_padded_nhwc_bias() {
+        let mut op = deconv(
+            DataFormat::NHWC,
+            PaddingSpec::Explicit(tvec!(1, 1), tvec!(1, 1), false),
+            tensor4(&[[[[1f32, 1., 1.], [1., 1., 1.], [1., 1., 1.]]]]),
+            1,
+            1,
+        );
+        op.bias = Some(rctensor1(&[0.5f32]));
+        let input = rctensor4(&[[[[1f32], [2.]], [[3.], [4.]]]]);
+        let output = op.eval(tvec!(input)).unwrap();
+        assert_eq!(output[0], rctensor4(&[[[[10.5f32], [10.5]], [[10.5], [10.5]]]]));
+    }
+
+    #[test]
+    fn deconv_grouped() {
+        let kernel = tensor3(&[[[1f32, 2.]], [[3., 4.]]]);
+        let op = deconv(DataFormat::NCHW, PaddingSpec::Valid, kernel, 1, 2);
+        let output = op.eval(tvec!(rctensor3(&[[[1f32], [10.]]]))).unwrap();
+        assert_eq!(output[0], rctensor3(&[[[1f32, 2.], [30., 40.]]]));
+    }
+
+    #[test]
+    fn deconv_hwio_matches_oihw() {
+        let oihw = ndarray::Array::range(0f32, 24., 1.).into_shape((2, 3, 2, 2)).unwrap();
+        let hwio = oihw.clone().permuted_axes([2, 3, 1, 0]).as_standard_layout().into_owned();
+        let mut op = deconv(DataFormat::NCHW, PaddingSpec::Valid, oihw.into_tensor(), 2, 1);
+        let input =
+            Tensor::from(ndarray::Array::range(0f32, 18., 1.).into_shape((1, 2, 3, 3)).unwrap())
+                .into_arc_tensor();
+        let expected = op.eval(tvec!(input.clone())).unwrap();
+        op.kernel_fmt = KernelFormat::HWIO;
+        op.kernel = hwio.into_arc_tensor();
+        let found = op.eval(tvec!(input)).unwrap();
+        assert_eq!(expected, found);
+    }
+}
@@ -1,5 +1,6 @@
 mod avgpool;
 pub mod conv;
+pub mod deconv;
 mod maxpool;
 mod padding;
 mod patch_axis;
@@ -8,6 +9,7 @@ pub mod pools;
 
 pub use self::avgpool::AvgPool;
 pub use self::conv::{ConvUnary, KernelFormat};
+pub use self::deconv::DeconvUnary;
 pub use self::maxpool::MaxPool;
 pub use self::padding::PaddingSpec;
 pub use self::patch_axis::PatchAxis;
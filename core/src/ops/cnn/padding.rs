@@ -81,6 +81,67 @@ impl PaddingSpec {
         }
     }
 
+    pub fn compute_for_deconv<D: DimLike>(
+        &self,
+        input_spatial_shape: &[D],
+        kernel_spatial_shape: &[usize],
+        dilations: &[usize],
+        strides: &[usize],
+        adjustments: &[usize],
+    ) -> TVec<ComputedPaddedDim<D>> {
+        (0..input_spatial_shape.len())
+            .map(|d| {
+                self.compute_one_for_deconv(
+                    d,
+                    &input_spatial_shape[d],
+                    kernel_spatial_shape[d],
+                    dilations[d],
+                    strides[d],
+                    adjustments[d],
+                )
+            })
+            .collect()
+    }
+
+    /// Compute the output dimension of a transposed convolution, along with
+    /// the padding of the equivalent forward convolution (which a deconv
+    /// crops out of its output).
+    pub fn compute_one_for_deconv<D: DimLike>(
+        &self,
+        axis: usize,
+        input: &D,
+        kernel: usize,
+        dilation: usize,
+        stride: usize,
+        adjustment: usize,
+    ) -> ComputedPaddedDim<D> {
+        let kernel_field = (kernel - 1) * dilation + 1;
+        match self {
+            PaddingSpec::Valid => {
+                let output = (input.clone() - 1) * stride + kernel_field + adjustment;
+                ComputedPaddedDim::new(output, 0.into(), 0.into())
+            }
+            PaddingSpec::Explicit(ref bef, ref aft, _) => {
+                let output = (input.clone() - 1) * stride + kernel_field + adjustment
+                    - bef[axis]
+                    - aft[axis];
+                ComputedPaddedDim::new(output, bef[axis].into(), aft[axis].into())
+            }
+            PaddingSpec::SameUpper | PaddingSpec::SameLower => {
+                let output = input.clone() * stride;
+                let pad = (kernel_field + adjustment).saturating_sub(stride);
+                let lower_pad = pad / 2;
+                let higher_pad = pad - lower_pad;
+                let (before, after) = if *self == PaddingSpec::SameUpper {
+                    (lower_pad, higher_pad)
+                } else {
+                    (higher_pad, lower_pad)
+                };
+                ComputedPaddedDim::new(output, before.into(), after.into())
+            }
+        }
+    }
+
     fn valid<D: DimLike>(
         input: &D,
         kernel: usize,
@@ -197,4 +258,37 @@ mod tests {
     fn same_upper() {
         assert_eq!(PaddingSpec::same(&7usize, 1usize, 1, 2, true), ComputedPaddedDim::new(4, 0, 0));
     }
+
+    #[test]
+    fn deconv_valid() {
+        assert_eq!(
+            PaddingSpec::Valid.compute_one_for_deconv(0, &3usize, 3, 1, 2, 0),
+            ComputedPaddedDim::new(7, 0, 0)
+        );
+        assert_eq!(
+            PaddingSpec::Valid.compute_one_for_deconv(0, &3usize, 3, 2, 1, 1),
+            ComputedPaddedDim::new(8, 0, 0)
+        );
+    }
+
+    #[test]
+    fn deconv_explicit() {
+        let padding = PaddingSpec::Explicit(tvec!(1), tvec!(2), false);
+        assert_eq!(
+            padding.compute_one_for_deconv(0, &3usize, 3, 1, 2, 1),
+            ComputedPaddedDim::new(5, 1, 2)
+        );
+    }
+
+    #[test]
+    fn deconv_same() {
+        assert_eq!(
+            PaddingSpec::SameUpper.compute_one_for_deconv(0, &3usize, 3, 1, 2, 0),
+            ComputedPaddedDim::new(6, 0, 1)
+        );
+        assert_eq!(
+            PaddingSpec::SameLower.compute_one_for_deconv(0, &3usize, 4, 1, 2, 0),
+            ComputedPaddedDim::new(6, 1, 1)
+        );
+    }
 }
@@ -0,0 +1,413 @@
+//! Conditional and loop operators running nested bodies.
+use crate::internal::*;
+
+/// Wire a copy of `body` in `target`, feeding its inputs with `inputs`.
+///
+/// Returns the outlets matching the body outputs.
+pub fn wire_body(
+    prefix: &str,
+    body: &TypedModel,
+    target: &mut TypedModel,
+    inputs: &[OutletId],
+) -> TractResult<TVec<OutletId>> {
+    let body_inputs = body.input_outlets()?;
+    if body_inputs.len() != inputs.len() {
+        bail!("Body expects {} inputs, got {}", body_inputs.len(), inputs.len())
+    }
+    let mut mapping: HashMap<OutletId, OutletId> =
+        body_inputs.iter().cloned().zip(inputs.iter().cloned()).collect();
+    for n in body.eval_order()? {
+        let node = body.node(n);
+        if body_inputs.contains(&OutletId::new(n, 0)) {
+            continue;
+        }
+        let node_inputs = node.inputs.iter().map(|i| mapping[i]).collect::<TVec<_>>();
+        let outputs = target.wire_node(
+            format!("{}.{}", prefix, node.name),
+            node.op.clone(),
+            &*node_inputs,
+        )?;
+        for (ix, o) in outputs.into_iter().enumerate() {
+            mapping.insert(OutletId::new(n, ix), o);
+        }
+    }
+    Ok(body.output_outlets()?.iter().map(|o| mapping[o]).collect())
+}
+
+/// Conditional evaluation of one of two bodies.
+///
+/// Input 0 is the boolean condition. Each body has its own input mapping,
+/// giving for each of its inputs the slot of the op input feeding it.
+///
+/// Branches must agree on the output types and ranks. Output dimensions they
+/// disagree on become symbols, named after `symbol_prefix`, the output and
+/// the axis.
+#[derive(Debug, Clone, new, Hash)]
+pub struct If {
+    pub then_body: TypedModel,
+    pub then_input_mapping: Vec<usize>,
+    pub else_body: TypedModel,
+    pub else_input_mapping: Vec<usize>,
+    pub symbol_prefix: String,
+}
+
+tract_linalg::impl_dyn_hash!(If);
+
+impl If {
+    fn branch(&self, cond: &Tensor) -> TractResult<(&TypedModel, &[usize])> {
+        if cond.cast_to_scalar::<bool>()? {
+            Ok((&self.then_body, &self.then_input_mapping))
+        } else {
+            Ok((&self.else_body, &self.else_input_mapping))
+        }
+    }
+}
+
+impl Op for If {
+    fn name(&self) -> Cow<str> {
+        "If".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![
+            format!("Then input mapping: {:?}", self.then_input_mapping),
+            format!("Else input mapping: {:?}", self.else_input_mapping),
+        ])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for If {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (body, mapping) = self.branch(&inputs[0])?;
+        let body_inputs = mapping.iter().map(|&slot| inputs[slot].clone().into_tensor()).collect();
+        SimplePlan::new(body)?.run(body_inputs)
+    }
+}
+
+impl TypedOp for If {
+    as_op!();
+
+    fn output_facts(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let then_outputs = self.then_body.output_outlets()?.len();
+        if then_outputs != self.else_body.output_outlets()?.len() {
+            bail!("If branches have different output counts")
+        }
+        (0..then_outputs)
+            .map(|ix| {
+                let then_fact = self.then_body.output_fact(ix)?;
+                let else_fact = self.else_body.output_fact(ix)?;
+                if then_fact.datum_type != else_fact.datum_type
+                    || then_fact.rank() != else_fact.rank()
+                {
+                    bail!(
+                        "If branches output #{} are incompatible: {:?} and {:?}",
+                        ix,
+                        then_fact,
+                        else_fact
+                    )
+                }
+                let shape = then_fact
+                    .shape
+                    .iter()
+                    .zip(else_fact.shape.iter())
+                    .enumerate()
+                    .map(|(axis, (t, e))| {
+                        if t == e {
+                            t
+                        } else {
+                            TDim::sym(format!("{}.{}.{}", self.symbol_prefix, ix, axis))
+                        }
+                    })
+                    .collect::<TVec<_>>();
+                Ok(TypedFact::dt_shape(then_fact.datum_type, &*shape)?)
+            })
+            .collect()
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        if let Some(cond) = &model.outlet_fact(node.inputs[0])?.konst {
+            let (body, mapping) = self.branch(cond)?;
+            let mut patch = TypedModelPatch::default();
+            let inputs = mapping
+                .iter()
+                .map(|&slot| patch.tap_model(model, node.inputs[slot]))
+                .collect::<TractResult<TVec<_>>>()?;
+            let outputs = wire_body(&node.name, body, &mut patch, &inputs)?;
+            for (ix, o) in outputs.into_iter().enumerate() {
+                let outlet = OutletId::new(node.id, ix);
+                if !patch.outlet_fact(o)?.same_as(model.outlet_fact(outlet)?) {
+                    // the branches disagree on this output shape
+                    return Ok(None);
+                }
+                patch.shunt_outside(model, outlet, o)?;
+            }
+            return Ok(Some(patch));
+        }
+        Ok(None)
+    }
+}
+
+/// Loop running a body while its condition holds, up to a maximum trip count.
+///
+/// Inputs are the maximum trip count (i64 scalar), the initial condition
+/// (bool scalar), the initial values of the `carried` loop-carried
+/// dependencies, then any number of extra inputs passed unchanged to each
+/// iteration.
+///
+/// The body takes the iteration number, the condition, the loop-carried
+/// values and the extra inputs. It returns the new condition, the new
+/// loop-carried values and any number of scan outputs, which are stacked on a
+/// new leading axis to make the op outputs following the final loop-carried
+/// values.
+///
+/// As the trip count is only known after running the loop, the leading
+/// dimension of the scan outputs is `iters`, usually a symbol. Loops with a
+/// known trip count should be translated to a Scan instead.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Loop {
+    pub body: TypedModel,
+    pub carried: usize,
+    pub iters: TDim,
+}
+
+tract_linalg::impl_dyn_hash!(Loop);
+
+impl Loop {
+    fn scan_outputs(&self) -> TractResult<usize> {
+        Ok(self.body.output_outlets()?.len() - 1 - self.carried)
+    }
+}
+
+impl Op for Loop {
+    fn name(&self) -> Cow<str> {
+        "Loop".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "Loop-carried values: {}, scan outputs: {}",
+            self.carried,
+            self.scan_outputs()?
+        )])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for Loop {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let plan = SimplePlan::new(&self.body)?;
+        let max_trip_count = inputs[0].cast_to_scalar::<i64>()?;
+        let mut cond = inputs[1].cast_to_scalar::<bool>()?;
+        let mut carried: TVec<Arc<Tensor>> = inputs[2..][..self.carried].into();
+        let extra = &inputs[2 + self.carried..];
+        let mut scans: TVec<Vec<Tensor>> = tvec!(vec!(); self.scan_outputs()?);
+        let mut iter = 0;
+        while iter < max_trip_count && cond {
+            let mut body_inputs: TVec<Tensor> = tvec!(tensor0(iter), tensor0(cond));
+            body_inputs.extend(carried.drain(..).map(|t| t.into_tensor()));
+            body_inputs.extend(extra.iter().map(|t| t.clone().into_tensor()));
+            let mut outputs = plan.run(body_inputs)?.into_iter();
+            cond = outputs.next().unwrap().cast_to_scalar::<bool>()?;
+            carried.extend((&mut outputs).take(self.carried));
+            for (scan, output) in scans.iter_mut().zip(outputs) {
+                let mut output = output.into_tensor();
+                output.insert_axis(0)?;
+                scan.push(output);
+            }
+            iter += 1;
+        }
+        for (ix, scan) in scans.into_iter().enumerate() {
+            let output = if scan.len() > 0 {
+                Tensor::stack_tensors(0, &scan)?
+            } else {
+                let fact = self.body.output_fact(1 + self.carried + ix)?;
+                let mut shape = tvec!(0);
+                shape.extend(
+                    fact.shape
+                        .as_finite()
+                        .ok_or("Loop scan output has no concrete shape")?
+                        .iter()
+                        .cloned(),
+                );
+                unsafe { Tensor::uninitialized_dt(fact.datum_type, &shape)? }
+            };
+            carried.push(output.into_arc_tensor());
+        }
+        Ok(carried)
+    }
+}
+
+impl TypedOp for Loop {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs.len() < 2 + self.carried {
+            bail!("Loop expects at least {} inputs, got {}", 2 + self.carried, inputs.len())
+        }
+        let mut facts = (0..self.carried)
+            .map(|ix| {
+                let fact = self.body.output_fact(1 + ix)?;
+                Ok(TypedFact::dt_shape(fact.datum_type, fact.shape.clone())?)
+            })
+            .collect::<TractResult<TVec<_>>>()?;
+        for ix in 0..self.scan_outputs()? {
+            let fact = self.body.output_fact(1 + self.carried + ix)?;
+            let mut shape = tvec!(self.iters.clone());
+            shape.extend(fact.shape.iter());
+            facts.push(TypedFact::dt_shape(fact.datum_type, &*shape)?);
+        }
+        Ok(facts)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math;
+
+    fn add_one_body() -> TypedModel {
+        let mut body = TypedModel::default();
+        let x = body
+            .add_source("x", TypedFact::dt_shape(f32::datum_type(), [2].as_ref()).unwrap())
+            .unwrap();
+        let one = body.add_const("one", rctensor1(&[1f32, 1.])).unwrap();
+        let y = body.wire_node("add", math::add::bin_typed(), &[x, one]).unwrap();
+        body.set_output_outlets(&y).unwrap();
+        body
+    }
+
+    fn identity_body() -> TypedModel {
+        let mut body = TypedModel::default();
+        let x = body
+            .add_source("x", TypedFact::dt_shape(f32::datum_type(), [2].as_ref()).unwrap())
+            .unwrap();
+        body.set_output_outlets(&[x]).unwrap();
+        body
+    }
+
+    fn if_model(cond: Option<bool>) -> TypedModel {
+        let mut model = TypedModel::default();
+        let cond = if let Some(cond) = cond {
+            // only known after constant propagation
+            let a = model.add_const("a", tensor0(0i64)).unwrap();
+            let b = model.add_const("b", tensor0(if cond { 1i64 } else { -1 })).unwrap();
+            model.wire_node("cond", crate::ops::logic::lesser::bin_typed(), &[a, b]).unwrap()[0]
+        } else {
+            model.add_source("cond", TypedFact::dt_shape(bool::datum_type(), ()).unwrap()).unwrap()
+        };
+        let x = model
+            .add_source("x", TypedFact::dt_shape(f32::datum_type(), [2].as_ref()).unwrap())
+            .unwrap();
+        let op = If::new(add_one_body(), vec![1], identity_body(), vec![1], "if".to_string());
+        let y = model.wire_node("if", op, &[cond, x]).unwrap();
+        model.set_output_outlets(&y).unwrap();
+        model
+    }
+
+    #[test]
+    fn if_eval() {
+        let model = if_model(None);
+        let plan = SimplePlan::new(model).unwrap();
+        let result = plan.run(tvec!(tensor0(true), tensor1(&[1f32, 2.]))).unwrap();
+        assert_eq!(result[0], rctensor1(&[2f32, 3.]));
+        let result = plan.run(tvec!(tensor0(false), tensor1(&[1f32, 2.]))).unwrap();
+        assert_eq!(result[0], rctensor1(&[1f32, 2.]));
+    }
+
+    #[test]
+    fn if_declutter_with_const_condition() {
+        for &(cond, expected) in &[(true, [2f32, 3.]), (false, [1., 2.])] {
+            let model = if_model(Some(cond)).declutter().unwrap();
+            assert!(model.nodes().iter().all(|n| !n.op_is::<If>()));
+            let result = SimplePlan::new(model).unwrap().run(tvec!(tensor1(&[1f32, 2.]))).unwrap();
+            assert_eq!(result[0], rctensor1(&expected));
+        }
+    }
+
+    #[test]
+    fn if_with_different_branch_shapes() {
+        let mut model = TypedModel::default();
+        let cond =
+            model.add_source("cond", TypedFact::dt_shape(bool::datum_type(), ()).unwrap()).unwrap();
+        let x = model
+            .add_source("x", TypedFact::dt_shape(f32::datum_type(), [2].as_ref()).unwrap())
+            .unwrap();
+        let mut else_body = TypedModel::default();
+        let zeros = else_body.add_const("zeros", rctensor1(&[0f32, 0., 0.])).unwrap();
+        else_body.set_output_outlets(&[zeros]).unwrap();
+        let op = If::new(identity_body(), vec![1], else_body, vec![], "if".to_string());
+        let y = model.wire_node("if", op, &[cond, x]).unwrap();
+        assert_eq!(model.outlet_fact(y[0]).unwrap().shape.dim(0), TDim::sym("if.0.0"));
+        model.set_output_outlets(&y).unwrap();
+        let plan = SimplePlan::new(model).unwrap();
+        let result = plan.run(tvec!(tensor0(true), tensor1(&[1f32, 2.]))).unwrap();
+        assert_eq!(result[0], rctensor1(&[1f32, 2.]));
+        let result = plan.run(tvec!(tensor0(false), tensor1(&[1f32, 2.]))).unwrap();
+        assert_eq!(result[0], rctensor1(&[0f32, 0., 0.]));
+    }
+
+    fn loop_body() -> TypedModel {
+        // body: (iter, cond, acc) -> (iter < 3, acc + 1, acc)
+        let mut body = TypedModel::default();
+        let iter =
+            body.add_source("iter", TypedFact::dt_shape(i64::datum_type(), ()).unwrap()).unwrap();
+        body.add_source("cond", TypedFact::dt_shape(bool::datum_type(), ()).unwrap()).unwrap();
+        let acc = body
+            .add_source("acc", TypedFact::dt_shape(f32::datum_type(), [2].as_ref()).unwrap())
+            .unwrap();
+        let three = body.add_const("three", tensor0(3i64)).unwrap();
+        let cond =
+            body.wire_node("lt", crate::ops::logic::lesser::bin_typed(), &[iter, three]).unwrap();
+        let one = body.add_const("one", rctensor1(&[1f32, 1.])).unwrap();
+        let next = body.wire_node("add", math::add::bin_typed(), &[acc, one]).unwrap();
+        body.set_output_outlets(&[cond[0], next[0], acc]).unwrap();
+        body
+    }
+
+    #[test]
+    fn loop_eval() {
+        let op = Loop::new(loop_body(), 1, TDim::sym("iters"));
+        let inputs = tvec!(rctensor0(10i64), rctensor0(true), rctensor1(&[0f32, 10.]));
+        let result = op.eval(inputs).unwrap();
+        assert_eq!(result[0], rctensor1(&[4f32, 14.]));
+        assert_eq!(result[1], rctensor2(&[[0f32, 10.], [1., 11.], [2., 12.], [3., 13.]]));
+        let inputs = tvec!(rctensor0(2i64), rctensor0(true), rctensor1(&[0f32, 10.]));
+        let result = op.eval(inputs).unwrap();
+        assert_eq!(result[0], rctensor1(&[2f32, 12.]));
+        assert_eq!(result[1], rctensor2(&[[0f32, 10.], [1., 11.]]));
+    }
+
+    #[test]
+    fn loop_scan_output_facts() {
+        let mut model = TypedModel::default();
+        let max = model.add_source("max", TypedFact::dt_shape(i64::datum_type(), ()).unwrap());
+        let cond = model.add_const("cond", tensor0(true)).unwrap();
+        let acc = model
+            .add_source("acc", TypedFact::dt_shape(f32::datum_type(), [2].as_ref()).unwrap())
+            .unwrap();
+        let op = Loop::new(loop_body(), 1, TDim::sym("iters"));
+        let outputs = model.wire_node("loop", op, &[max.unwrap(), cond, acc]).unwrap();
+        let scan_fact = model.outlet_fact(outputs[1]).unwrap();
+        assert_eq!(
+            scan_fact.shape.iter().collect::<TVec<_>>(),
+            tvec!(TDim::sym("iters"), 2.into())
+        );
+        model.set_output_outlets(&outputs).unwrap();
+        let result = SimplePlan::new(model)
+            .unwrap()
+            .run(tvec!(tensor0(10i64), tensor1(&[0f32, 10.])))
+            .unwrap();
+        assert_eq!(result[1].shape(), &[4, 2]);
+    }
+}
@@ -0,0 +1,532 @@
+use std::fmt;
+use std::ops::{Add, Mul};
+
+use crate::internal::*;
+use crate::ops::math::mul;
+use crate::ops::matmul::MatMul;
+use crate::ops::nn::{Reduce, Reducer};
+use ndarray::*;
+use num_traits::Zero;
+
+/// An Einstein summation expression, with one letter per axis.
+///
+/// Ellipsis are expanded at parsing, given the input ranks, so every axis has
+/// its own letter.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Expr {
+    pub inputs: TVec<TVec<char>>,
+    pub output: TVec<char>,
+}
+
+impl Expr {
+    /// Parse an expression like "bij,bjk->bik", or "ij,jk" for the implicit
+    /// form, where the output has the letters appearing only once, in
+    /// alphabetical order.
+    pub fn parse(expr: &str, ranks: &[usize]) -> TractResult<Expr> {
+        let expr: String = expr.chars().filter(|c| !c.is_whitespace()).collect();
+        let (inputs, output) = if let Some(arrow) = expr.find("->") {
+            (&expr[..arrow], Some(&expr[arrow + 2..]))
+        } else {
+            (&*expr, None)
+        };
+        let terms: TVec<&str> = inputs.split(',').collect();
+        if terms.len() != ranks.len() {
+            bail!("Einsum: {} has {} inputs, got {}", expr, terms.len(), ranks.len())
+        }
+        let mut ellipsis_rank = 0;
+        for (term, &rank) in terms.iter().zip(ranks.iter()) {
+            if term.contains("...") {
+                let letters = term.len() - 3;
+                if rank < letters {
+                    bail!("Einsum: {} does not match an input of rank {}", term, rank)
+                }
+                ellipsis_rank = ellipsis_rank.max(rank - letters);
+            }
+        }
+        if ellipsis_rank > 10 {
+            bail!("Einsum: ellipsis covers more than 10 axes in {}", expr)
+        }
+        // ellipsis axes get digits, which can not clash with letters
+        let ellipsis: TVec<char> =
+            (0..ellipsis_rank).map(|i| std::char::from_digit(i as u32, 10).unwrap()).collect();
+        let expand = |term: &str, rank: Option<usize>| -> TractResult<TVec<char>> {
+            let chunks: TVec<&str> = term.split("...").collect();
+            if chunks.len() > 2 {
+                bail!("Einsum: more than one ellipsis in {}", term)
+            }
+            if let Some(c) =
+                chunks.iter().flat_map(|c| c.chars()).find(|c| !c.is_ascii_alphabetic())
+            {
+                bail!("Einsum: invalid axis label {:?} in {}", c, term)
+            }
+            let mut axes: TVec<char> = chunks[0].chars().collect();
+            if chunks.len() == 2 {
+                let dims = rank.map(|r| r + 3 - term.len()).unwrap_or(ellipsis_rank);
+                axes.extend(ellipsis[ellipsis_rank - dims..].iter().cloned());
+                axes.extend(chunks[1].chars());
+            }
+            Ok(axes)
+        };
+        let inputs = terms
+            .iter()
+            .zip(ranks.iter())
+            .map(|(term, &rank)| {
+                let axes = expand(term, Some(rank))?;
+                if axes.len() != rank {
+                    bail!("Einsum: {} does not match an input of rank {}", term, rank)
+                }
+                Ok(axes)
+            })
+            .collect::<TractResult<TVec<_>>>()?;
+        let output = if let Some(output) = output {
+            let output = expand(output, None)?;
+            for c in &output {
+                if !inputs.iter().any(|i| i.contains(c)) {
+                    bail!("Einsum: output axis {:?} does not appear in inputs", c)
+                }
+            }
+            output
+        } else {
+            let mut letters: Vec<char> = inputs
+                .iter()
+                .flat_map(|i| i.iter())
+                .filter(|c| c.is_ascii_alphabetic())
+                .cloned()
+                .collect();
+            letters.sort();
+            let once =
+                letters.iter().filter(|c| letters.iter().filter(|d| d == c).count() == 1).cloned();
+            ellipsis.iter().cloned().chain(once).collect()
+        };
+        Ok(Expr { inputs, output })
+    }
+
+    /// Axes summed over: the ones not in the output.
+    pub fn summed(&self) -> TVec<char> {
+        let mut summed = tvec!();
+        for c in self.inputs.iter().flat_map(|i| i.iter()) {
+            if !self.output.contains(c) && !summed.contains(c) {
+                summed.push(*c);
+            }
+        }
+        summed
+    }
+
+    /// Size of every axis, broadcasting axes of size 1.
+    pub fn axis_dims<D: DimLike>(&self, shapes: &[&[D]]) -> TractResult<Vec<(char, D)>> {
+        let mut dims: Vec<(char, D)> = vec![];
+        for (axes, shape) in self.inputs.iter().zip(shapes.iter()) {
+            if axes.len() != shape.len() {
+                bail!("Einsum: axes {:?} for a shape of rank {}", axes, shape.len())
+            }
+            for (c, d) in axes.iter().zip(shape.iter()) {
+                if let Some(known) = dims.iter_mut().find(|(k, _)| k == c) {
+                    if known.1 == D::one() {
+                        known.1 = d.clone();
+                    } else if *d != D::one() && *d != known.1 {
+                        bail!("Einsum: inconsistent sizes for axis {:?}: {} and {}", c, known.1, d)
+                    }
+                } else {
+                    dims.push((*c, d.clone()));
+                }
+            }
+        }
+        Ok(dims)
+    }
+
+    pub fn output_shape<D: DimLike>(&self, shapes: &[&[D]]) -> TractResult<TVec<D>> {
+        let dims = self.axis_dims(shapes)?;
+        Ok(self
+            .output
+            .iter()
+            .map(|c| dims.iter().find(|(k, _)| k == c).unwrap().1.clone())
+            .collect())
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let inputs: Vec<String> = self.inputs.iter().map(|i| i.iter().collect()).collect();
+        write!(f, "{}->{}", inputs.join(","), self.output.iter().collect::<String>())
+    }
+}
+
+/// Einstein summation.
+///
+/// Evaluation is a plain loop over all axes. For f32, declutter lowers the
+/// expression into sums, axis moves, MatMul and Mul, one pair of inputs at a
+/// time.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Einsum {
+    pub expr: Expr,
+}
+
+tract_linalg::impl_dyn_hash!(Einsum);
+
+impl Einsum {
+    fn eval_t<T>(&self, inputs: &[Arc<Tensor>]) -> TractResult<Tensor>
+    where
+        T: Datum + Zero + Copy + Add<Output = T> + Mul<Output = T>,
+    {
+        let shapes: TVec<&[usize]> = inputs.iter().map(|t| t.shape()).collect();
+        let dims = self.expr.axis_dims(&shapes)?;
+        let summed = self.expr.summed();
+        let letters: TVec<char> = self.expr.output.iter().chain(summed.iter()).cloned().collect();
+        let dim = |c: &char| dims.iter().find(|(k, _)| k == c).unwrap().1;
+        let output_shape: TVec<usize> = self.expr.output.iter().map(dim).collect();
+        let summed_shape: TVec<usize> = summed.iter().map(dim).collect();
+        let views =
+            inputs.iter().map(|t| t.to_array_view::<T>()).collect::<TractResult<TVec<_>>>()?;
+        // position in letters of each input axis, or None if it is broadcast
+        let positions: TVec<TVec<Option<usize>>> = self
+            .expr
+            .inputs
+            .iter()
+            .zip(views.iter())
+            .map(|(axes, view)| {
+                axes.iter()
+                    .zip(view.shape())
+                    .map(|(c, &d)| if d == 1 { None } else { letters.iter().position(|l| l == c) })
+                    .collect()
+            })
+            .collect();
+        let mut coords: TVec<usize> = tvec!(0; letters.len());
+        let mut input_coords: TVec<IxDyn> =
+            views.iter().map(|v| IxDyn(&vec![0; v.ndim()])).collect();
+        let output = ArrayD::from_shape_fn(&*output_shape, |out| {
+            coords[0..output_shape.len()].copy_from_slice(out.slice());
+            let mut acc = T::zero();
+            for sum in ndarray::indices(&*summed_shape) {
+                coords[output_shape.len()..].copy_from_slice(sum.slice());
+                let mut product: Option<T> = None;
+                for ((view, pos), ic) in
+                    views.iter().zip(positions.iter()).zip(input_coords.iter_mut())
+                {
+                    for (axis, p) in pos.iter().enumerate() {
+                        ic[axis] = p.map(|p| coords[p]).unwrap_or(0);
+                    }
+                    let value = view[&*ic];
+                    product = Some(product.map(|p| p * value).unwrap_or(value));
+                }
+                acc = acc + product.unwrap();
+            }
+            acc
+        });
+        Ok(output.into_tensor())
+    }
+}
+
+impl Op for Einsum {
+    fn name(&self) -> Cow<str> {
+        "Einsum".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("{}", self.expr)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for Einsum {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let dt = inputs[0].datum_type();
+        if inputs.iter().any(|i| i.datum_type() != dt) {
+            bail!("Einsum: all inputs must have the same type")
+        }
+        let output = dispatch_numbers!(Self::eval_t(dt)(self, &*inputs))?;
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for Einsum {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let shapes: TVec<TVec<TDim>> = inputs.iter().map(|i| i.shape.to_tvec()).collect();
+        let shapes: TVec<&[TDim]> = shapes.iter().map(|s| &**s).collect();
+        let shape = self.expr.output_shape(&shapes)?;
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &*shape)?))
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        for input in &node.inputs {
+            if model.outlet_fact(*input)?.datum_type != f32::datum_type() {
+                return Ok(None);
+            }
+        }
+        // diagonals (an axis repeated in one input) stay in the loop
+        for axes in &self.expr.inputs {
+            if axes.iter().enumerate().any(|(ix, c)| axes[ix + 1..].contains(c)) {
+                return Ok(None);
+            }
+        }
+        let mut lowering =
+            Lowering { patch: TypedModelPatch::default(), name: &node.name, step: 0 };
+        let mut operands = tvec!();
+        for (input, axes) in node.inputs.iter().zip(self.expr.inputs.iter()) {
+            operands.push((lowering.patch.tap_model(model, *input)?, axes.clone()));
+        }
+        loop {
+            for ix in 0..operands.len() {
+                let needed: TVec<char> = self
+                    .expr
+                    .output
+                    .iter()
+                    .chain(
+                        operands
+                            .iter()
+                            .enumerate()
+                            .filter(|(other, _)| *other != ix)
+                            .flat_map(|(_, op)| op.1.iter()),
+                    )
+                    .cloned()
+                    .collect();
+                let (wire, axes) = &mut operands[ix];
+                *wire = lowering.sum(*wire, axes, &needed)?;
+            }
+            if operands.len() == 1 {
+                break;
+            }
+            let (a, a_axes) = operands.remove(0);
+            let (b, b_axes) = operands.remove(0);
+            let needed: TVec<char> = self
+                .expr
+                .output
+                .iter()
+                .chain(operands.iter().flat_map(|op| op.1.iter()))
+                .cloned()
+                .collect();
+            let contracted = if let Some(c) = lowering.contract(a, a_axes, b, b_axes, &needed)? {
+                c
+            } else {
+                return Ok(None);
+            };
+            operands.insert(0, contracted);
+        }
+        let (wire, mut axes) = operands.pop().unwrap();
+        let wire = lowering.permute(wire, &mut axes, &self.expr.output)?;
+        let mut patch = lowering.patch;
+        patch.shunt_outside(model, node.id.into(), wire)?;
+        Ok(Some(patch))
+    }
+}
+
+struct Lowering<'a> {
+    patch: TypedModelPatch,
+    name: &'a str,
+    step: usize,
+}
+
+impl<'a> Lowering<'a> {
+    fn wire(
+        &mut self,
+        what: &str,
+        op: impl Into<Box<dyn TypedOp>>,
+        inputs: &[OutletId],
+    ) -> TractResult<OutletId> {
+        self.step += 1;
+        Ok(self.patch.wire_node(format!("{}.{}-{}", self.name, what, self.step), op, inputs)?[0])
+    }
+
+    fn dim(&self, wire: OutletId, axis: usize) -> TractResult<TDim> {
+        Ok(self.patch.outlet_fact(wire)?.shape.dim(axis))
+    }
+
+    /// Sum and remove axes not in needed.
+    fn sum(
+        &mut self,
+        wire: OutletId,
+        axes: &mut TVec<char>,
+        needed: &[char],
+    ) -> TractResult<OutletId> {
+        let summed: TVec<usize> =
+            (0..axes.len()).filter(|&ix| !needed.contains(&axes[ix])).collect();
+        if summed.is_empty() {
+            return Ok(wire);
+        }
+        let mut wire = self.wire("sum", Reduce::new(summed.clone(), Reducer::Sum), &[wire])?;
+        for &ix in summed.iter().rev() {
+            wire = self.wire("rm", AxisOp::Rm(ix), &[wire])?;
+            axes.remove(ix);
+        }
+        Ok(wire)
+    }
+
+    /// Move axes around to match target, which must have the same axes.
+    fn permute(
+        &mut self,
+        mut wire: OutletId,
+        axes: &mut TVec<char>,
+        target: &[char],
+    ) -> TractResult<OutletId> {
+        for (ix, c) in target.iter().enumerate() {
+            let from = axes.iter().position(|a| a == c).unwrap();
+            if from != ix {
+                wire = self.wire("move", AxisOp::Move(from, ix), &[wire])?;
+                let c = axes.remove(from);
+                axes.insert(ix, c);
+            }
+        }
+        Ok(wire)
+    }
+
+    /// Merge `len` axes from `at` into one, or insert a unit axis if len is 0.
+    fn merge(&mut self, wire: OutletId, at: usize, len: usize) -> TractResult<OutletId> {
+        if len == 1 {
+            return Ok(wire);
+        }
+        if len == 0 {
+            return self.wire("add", AxisOp::Add(at), &[wire]);
+        }
+        let from = (at..at + len).map(|ax| self.dim(wire, ax)).collect::<TractResult<TVec<_>>>()?;
+        let merged = from.iter().maybe_product()?;
+        self.wire("merge", AxisOp::Reshape(at, from, tvec!(merged)), &[wire])
+    }
+
+    /// Split the axis at `at` into dims, or remove it if dims is empty.
+    fn split(&mut self, wire: OutletId, at: usize, dims: TVec<TDim>) -> TractResult<OutletId> {
+        match dims.len() {
+            1 => Ok(wire),
+            0 => self.wire("rm", AxisOp::Rm(at), &[wire]),
+            _ => {
+                let merged = self.dim(wire, at)?;
+                self.wire("split", AxisOp::Reshape(at, tvec!(merged), dims), &[wire])
+            }
+        }
+    }
+
+    /// Contract two operands, keeping the needed axes shared by both.
+    fn contract(
+        &mut self,
+        a: OutletId,
+        mut a_axes: TVec<char>,
+        b: OutletId,
+        mut b_axes: TVec<char>,
+        needed: &[char],
+    ) -> TractResult<Option<(OutletId, TVec<char>)>> {
+        let batch: TVec<char> =
+            a_axes.iter().filter(|c| b_axes.contains(c) && needed.contains(c)).cloned().collect();
+        let k: TVec<char> =
+            a_axes.iter().filter(|c| b_axes.contains(c) && !needed.contains(c)).cloned().collect();
+        let m: TVec<char> = a_axes.iter().filter(|c| !b_axes.contains(c)).cloned().collect();
+        let n: TVec<char> = b_axes.iter().filter(|c| !a_axes.contains(c)).cloned().collect();
+        let c_axes: TVec<char> = batch.iter().chain(m.iter()).chain(n.iter()).cloned().collect();
+        let a_target: TVec<char> = batch.iter().chain(m.iter()).chain(k.iter()).cloned().collect();
+        let b_target: TVec<char> = batch.iter().chain(n.iter()).chain(k.iter()).cloned().collect();
+        let mut a = self.permute(a, &mut a_axes, &a_target)?;
+        let mut b = self.permute(b, &mut b_axes, &b_target)?;
+        for ix in 0..k.len() {
+            let (a_dim, b_dim) = (
+                self.dim(a, batch.len() + m.len() + ix)?,
+                self.dim(b, batch.len() + n.len() + ix)?,
+            );
+            if a_dim != b_dim {
+                return Ok(None);
+            }
+        }
+        let c = if k.is_empty() {
+            // no contraction: broadcast to [batch, m, n] and multiply
+            for _ in 0..n.len() {
+                a = self.wire("add", AxisOp::Add(batch.len() + m.len()), &[a])?;
+            }
+            for _ in 0..m.len() {
+                b = self.wire("add", AxisOp::Add(batch.len()), &[b])?;
+            }
+            self.wire("mul", mul::bin_typed(), &[a, b])?
+        } else {
+            let m_dims = (0..m.len())
+                .map(|ix| self.dim(a, batch.len() + ix))
+                .collect::<TractResult<TVec<_>>>()?;
+            let n_dims = (0..n.len())
+                .map(|ix| self.dim(b, batch.len() + ix))
+                .collect::<TractResult<TVec<_>>>()?;
+            let a = self.merge(a, batch.len() + m.len(), k.len())?;
+            let a = self.merge(a, batch.len(), m.len())?;
+            let b = self.merge(b, batch.len() + n.len(), k.len())?;
+            let b = self.merge(b, batch.len(), n.len())?;
+            let c = self.wire("matmul", MatMul::default().with_b_trans(true), &[a, b])?;
+            let c = self.split(c, batch.len() + 1, n_dims)?;
+            self.split(c, batch.len(), m_dims)?
+        };
+        Ok(Some((c, c_axes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_implicit_and_ellipsis() {
+        let expr = Expr::parse("ij,jk", &[2, 2]).unwrap();
+        assert_eq!(expr.to_string(), "ij,jk->ik");
+        let expr = Expr::parse("...ij, ...jk -> ...ik", &[3, 2]).unwrap();
+        assert_eq!(expr.to_string(), "0ij,jk->0ik");
+        assert!(Expr::parse("ij->k", &[2]).is_err());
+    }
+
+    #[test]
+    fn eval_matmul_trace_and_outer() {
+        let a = rctensor2(&[[1f32, 2.], [3., 4.]]);
+        let b = rctensor2(&[[5f32, 6.], [7., 8.]]);
+        let op = Einsum::new(Expr::parse("ij,jk->ik", &[2, 2]).unwrap());
+        assert_eq!(
+            op.eval(tvec!(a.clone(), b.clone())).unwrap()[0],
+            rctensor2(&[[19f32, 22.], [43., 50.]])
+        );
+        let op = Einsum::new(Expr::parse("ii", &[2]).unwrap());
+        assert_eq!(op.eval(tvec!(a.clone())).unwrap()[0], rctensor0(5f32));
+        let op = Einsum::new(Expr::parse("i,j->ij", &[1, 1]).unwrap());
+        let outer = op.eval(tvec!(rctensor1(&[1i32, 2]), rctensor1(&[3i32, 4]))).unwrap();
+        assert_eq!(outer[0], rctensor2(&[[3i32, 4], [6, 8]]));
+    }
+
+    #[test]
+    fn declutter_matches_eval() {
+        let exprs = [
+            ("bhqd,bhkd->bhqk", vec![tvec!(2, 3, 4, 5), tvec!(2, 3, 6, 5)]),
+            ("ij,jk,kl->il", vec![tvec!(2, 3), tvec!(3, 4), tvec!(4, 5)]),
+            ("bij,j->bi", vec![tvec!(2, 3, 4), tvec!(4)]),
+            ("ijk->kj", vec![tvec!(2, 3, 4)]),
+            ("ab,cd->abcd", vec![tvec!(2, 3), tvec!(4, 5)]),
+            ("abc,cbd->ad", vec![tvec!(2, 3, 4), tvec!(4, 3, 5)]),
+        ];
+        for (expr, shapes) in exprs.iter() {
+            let ranks: Vec<usize> = shapes.iter().map(|s: &TVec<usize>| s.len()).collect();
+            let op = Einsum::new(Expr::parse(expr, &ranks).unwrap());
+            let mut model = TypedModel::default();
+            let mut inputs = tvec!();
+            let mut values = tvec!();
+            for (ix, shape) in shapes.iter().enumerate() {
+                let len = shape.iter().product::<usize>();
+                let t = tensor1(&(0..len).map(|i| i as f32 / 10.0).collect::<Vec<_>>())
+                    .into_shape(&shape)
+                    .unwrap();
+                inputs.push(
+                    model
+                        .add_source(
+                            format!("i{}", ix),
+                            TypedFact::dt_shape(f32::datum_type(), &**shape).unwrap(),
+                        )
+                        .unwrap(),
+                );
+                values.push(t);
+            }
+            let output = model.wire_node("einsum", op.clone(), &inputs).unwrap();
+            model.set_output_outlets(&output).unwrap();
+            let model = model.declutter().unwrap();
+            assert!(model.nodes().iter().all(|n| n.op_as::<Einsum>().is_none()), "{}", expr);
+            let expected =
+                op.eval(values.iter().cloned().map(|t| t.into_arc_tensor()).collect()).unwrap();
+            let found = SimplePlan::new(model).unwrap().run(values).unwrap();
+            found[0].close_enough(&expected[0], true).unwrap();
+        }
+    }
+}
@@ -96,6 +96,32 @@ impl StatelessOp for ElementWiseOp {
             Ok(tvec!(t.into_arc_tensor()))
         }
     }
+
+    fn eval_into(&self, inputs: &[Arc<Tensor>], output: &mut Tensor) -> TractResult<bool> {
+        fn copy<T: Datum + Copy>(from: &Tensor, to: &mut Tensor) {
+            unsafe { to.as_slice_mut_unchecked::<T>().copy_from_slice(from.as_slice_unchecked()) }
+        }
+        let input = &inputs[0];
+        let dt = input.datum_type();
+        if self.0.output_type(dt).is_some()
+            || !dt.is_copy()
+            || output.datum_type() != dt
+            || output.shape() != input.shape()
+        {
+            return Ok(false);
+        }
+        dispatch_copy_by_size!(copy(dt)(input, output));
+        self.0.eval_in_place(output)?;
+        Ok(true)
+    }
+
+    fn in_place_input(&self, inputs: &[&TypedFact]) -> Option<usize> {
+        if self.0.output_type(inputs[0].datum_type).is_none() {
+            Some(0)
+        } else {
+            None
+        }
+    }
 }
 
 impl TypedOp for ElementWiseOp {
@@ -67,17 +67,17 @@ bin_to_super_type!(rem, Rem,
                    [f32, i8, i16, i32, i64, u8, u16, u32, u64, f16, f64] => |c, a, b| *c = a.clone() % b);
 
 bin_to_super_type!(min, Min, flip:commute,
-                   [f32, f64] => |c,a,b| *c = a.min(*b),
+                   [f16, f32, f64] => |c,a,b| *c = a.min(*b),
                    [i8, i16, i32, i64, u8, u16, u32, u64] => |c, a, b| *c = *a.min(b));
 bin_to_super_type!(max, Max, flip:commute,
-                   [f32, f64] => |c,a,b| *c = a.max(*b),
+                   [f16, f32, f64] => |c,a,b| *c = a.max(*b),
                    [i8, i16, i32, i64, u8, u16, u32, u64] => |c, a, b| *c = *a.max(b));
 
 bin_to_super_type!(pow, Pow,
-                   [f32, f64] => |c,a,b| *c = a.powf(*b),
+                   [f16, f32, f64] => |c,a,b| *c = a.powf(*b),
                    [i32, i64] => |c,a,b| *c = a.pow(*b as u32));
 bin_to_super_type!(flipped_pow, FlippedPow,
-                   [f32, f64] => |c,a,b| *c = b.powf(*a),
+                   [f16, f32, f64] => |c,a,b| *c = b.powf(*a),
                    [i32, i64] => |c,a,b| *c = b.pow(*a as u32));
 
 bin_to_super_type!(shift_left, ShiftLeft,
@@ -222,11 +222,10 @@ element_wise!(abs, Abs, [i8, i16, i32, i64, f16, f32, i32] => |_, xs| {
     Ok(())
 });
 
-element_wise!(exp, Exp, [f16, f32, f64] => |_, xs| {
-    xs.iter_mut().for_each(|x| *x = x.exp());
-    Ok(())
-};
-validation: Validation::Rounding
+element_wise!(exp, Exp,
+ [f32] => |_, xs| { (tract_linalg::ops().exp_f32)().run(xs); Ok(()) },
+ [f16, f64] => |_, xs| { xs.iter_mut().for_each(|x| *x = x.exp()); Ok(()) };
+ validation: Validation::Rounding
 );
 
 element_wise!(ln, Ln, [f16, f32, f64] => |_, xs| {
@@ -385,6 +384,20 @@ mod tests {
         assert_eq!(a.dot(&b), arr2(&[[1., 0.], [3., 0.]]));
     }
 
+    #[test]
+    fn sub_in_place_when_b_is_not_shared() -> TractResult<()> {
+        let a = rctensor1(&[5f32, 7.]);
+        let b = rctensor1(&[1f32, 2.]);
+        let b_ptr = b.as_ptr::<f32>()?;
+        let c = sub::bin_typed().eval(tvec!(a.clone(), b.clone()))?;
+        assert_eq!(c[0], rctensor1(&[4f32, 5.]));
+        assert_eq!(b, rctensor1(&[1f32, 2.]));
+        let c = sub::bin_typed().eval(tvec!(a, b))?;
+        assert_eq!(c[0], rctensor1(&[4f32, 5.]));
+        assert_eq!(c[0].as_ptr::<f32>()?, b_ptr);
+        Ok(())
+    }
+
     #[test]
     fn mul_as_shift() -> TractResult<()> {
         let mut model = TypedModel::default();
@@ -38,11 +38,32 @@ fn eval(
                     MMMWrapper::Quant((tract_linalg::ops().qmmm_u8_u8)(m, k, n))
                 });
             }
+        } else if (a.datum_type(), b.datum_type(), q.c_datum_type)
+            == (u8::datum_type(), i8::datum_type(), i32::datum_type())
+        {
+            return eval_t(a, b, a_trans, b_trans, c_trans, q_params, &|m, k, n| {
+                MMMWrapper::Quant((tract_linalg::ops().qmmm_u8_i8)(m, k, n))
+            });
+        } else if (a.datum_type(), b.datum_type(), q.c_datum_type)
+            == (i8::datum_type(), u8::datum_type(), i32::datum_type())
+        {
+            // C = A.B is computed as the transposition of B'.A'
+            let mut q = q.clone();
+            std::mem::swap(&mut q.zero_point_a, &mut q.zero_point_b);
+            return eval(b, a, !b_trans, !a_trans, !c_trans, Some(&q));
         }
     } else if (a.datum_type(), b.datum_type()) == (f32::datum_type(), f32::datum_type()) {
         return eval_t(a, b, a_trans, b_trans, c_trans, q_params, &|m, k, n| {
             MMMWrapper::Plain((tract_linalg::ops().mmm_f32)(m, k, n))
         });
+    } else if (a.datum_type(), b.datum_type()) == (f64::datum_type(), f64::datum_type()) {
+        return eval_t(a, b, a_trans, b_trans, c_trans, q_params, &|m, k, n| {
+            MMMWrapper::Plain((tract_linalg::ops().mmm_f64)(m, k, n))
+        });
+    } else if (a.datum_type(), b.datum_type()) == (f16::datum_type(), f16::datum_type()) {
+        return eval_t(a, b, a_trans, b_trans, c_trans, q_params, &|m, k, n| {
+            MMMWrapper::Plain((tract_linalg::ops().mmm_f16)(m, k, n))
+        });
     }
     bail!(
         "Unsupported combination for MatMul eval (a: {:?}, b:{:?} q:{:?})",
@@ -410,11 +431,11 @@ impl TypedOp for MatMul {
 
 #[derive(Debug, Clone, new, Hash)]
 pub struct MatMulUnary {
-    a: Arc<Tensor>,
-    a_trans: bool,
-    b_trans: bool,
-    c_trans: bool,
-    q_params: Option<QParams>,
+    pub a: Arc<Tensor>,
+    pub a_trans: bool,
+    pub b_trans: bool,
+    pub c_trans: bool,
+    pub q_params: Option<QParams>,
 }
 
 tract_linalg::impl_dyn_hash!(MatMulUnary);
@@ -685,61 +706,88 @@ impl TypedOp for MatMulUnary {
     ) -> TractResult<Option<TypedModelPatch>> {
         let b = args_1!(model.node_input_facts(node.id)?);
         if let Some(b_shape) = b.shape.as_finite() {
-            let patch =
-                if (self.a.datum_type(), b.datum_type) == (f32::datum_type(), f32::datum_type()) {
-                    new_mat_mul_unary_finite(
-                        model,
-                        node,
-                        self.a.clone(),
-                        b_shape,
-                        self.a_trans,
-                        self.b_trans,
-                        self.c_trans,
-                        self.q_params.as_ref(),
-                        &|m, k, n| MMMWrapper::Plain((tract_linalg::ops().mmm_f32)(m, k, n)),
-                    )?
-                } else if (
-                    self.a.datum_type(),
-                    b.datum_type,
-                    self.q_params.as_ref().map(|q| q.c_datum_type),
-                ) == (i8::datum_type(), i8::datum_type(), Some(i8::datum_type()))
-                {
-                    new_mat_mul_unary_finite(
-                        model,
-                        node,
-                        self.a.clone(),
-                        b_shape,
-                        self.a_trans,
-                        self.b_trans,
-                        self.c_trans,
-                        self.q_params.as_ref(),
-                        &|m, k, n| MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i8)(m, k, n)),
-                    )?
-                } else if (
+            let patch = if (self.a.datum_type(), b.datum_type)
+                == (f32::datum_type(), f32::datum_type())
+            {
+                new_mat_mul_unary_finite(
+                    model,
+                    node,
+                    self.a.clone(),
+                    b_shape,
+                    self.a_trans,
+                    self.b_trans,
+                    self.c_trans,
+                    self.q_params.as_ref(),
+                    &|m, k, n| MMMWrapper::Plain((tract_linalg::ops().mmm_f32)(m, k, n)),
+                )?
+            } else if (self.a.datum_type(), b.datum_type) == (f64::datum_type(), f64::datum_type())
+            {
+                new_mat_mul_unary_finite(
+                    model,
+                    node,
+                    self.a.clone(),
+                    b_shape,
+                    self.a_trans,
+                    self.b_trans,
+                    self.c_trans,
+                    self.q_params.as_ref(),
+                    &|m, k, n| MMMWrapper::Plain((tract_linalg::ops().mmm_f64)(m, k, n)),
+                )?
+            } else if (self.a.datum_type(), b.datum_type) == (f16::datum_type(), f16::datum_type())
+            {
+                new_mat_mul_unary_finite(
+                    model,
+                    node,
+                    self.a.clone(),
+                    b_shape,
+                    self.a_trans,
+                    self.b_trans,
+                    self.c_trans,
+                    self.q_params.as_ref(),
+                    &|m, k, n| MMMWrapper::Plain((tract_linalg::ops().mmm_f16)(m, k, n)),
+                )?
+            } else if (
+                self.a.datum_type(),
+                b.datum_type,
+                self.q_params.as_ref().map(|q| q.c_datum_type),
+            ) == (i8::datum_type(), i8::datum_type(), Some(i8::datum_type()))
+            {
+                new_mat_mul_unary_finite(
+                    model,
+                    node,
+                    self.a.clone(),
+                    b_shape,
+                    self.a_trans,
+                    self.b_trans,
+                    self.c_trans,
+                    self.q_params.as_ref(),
+                    &|m, k, n| MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i8)(m, k, n)),
+                )?
+            } else if (
+                self.a.datum_type(),
+                b.datum_type,
+                self.q_params.as_ref().map(|q| q.c_datum_type),
+            ) == (i8::datum_type(), i8::datum_type(), Some(i32::datum_type()))
+            {
+                new_mat_mul_unary_finite(
+                    model,
+                    node,
+                    self.a.clone(),
+                    b_shape,
+                    self.a_trans,
+                    self.b_trans,
+                    self.c_trans,
+                    self.q_params.as_ref(),
+                    &|m, k, n| MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i32)(m, k, n)),
+                )?
+            } else {
+                bail!(
+                    "Unsupported combination for MatMul codegen (a: {:?}, b:{:?}, q: {:?})",
                     self.a.datum_type(),
                     b.datum_type,
-                    self.q_params.as_ref().map(|q| q.c_datum_type),
-                ) == (i8::datum_type(), i8::datum_type(), Some(i32::datum_type()))
-                {
-                    new_mat_mul_unary_finite(
-                        model,
-                        node,
-                        self.a.clone(),
-                        b_shape,
-                        self.a_trans,
-                        self.b_trans,
-                        self.c_trans,
-                        self.q_params.as_ref(),
-                        &|m, k, n| MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i32)(m, k, n)),
-                    )?
-                } else {
-                    bail!(
-                        "Unsupported combination for MatMul codegen (a: {:?}, b:{:?}, q: {:?})",
-                        self.a.datum_type(),
-                        b.datum_type,
-                        self.q_params
-                    );
-                };
+                    self.q_params
+                );
+            };
             return Ok(Some(patch));
         }
         Ok(None)
@@ -930,6 +978,64 @@ mod test {
         c.close_enough(&c_found, true).unwrap();
     }
 
+    #[test]
+    fn bin_f64() {
+        let a = rctensor2(&[[0f64, 1.0, 2.0], [3.0, 4.0, 5.0]]);
+        let b = rctensor2(&[[0f64], [1.0], [2.0]]);
+        let c = rctensor2(&[[5f64], [14.0]]);
+        let op = MatMul::default();
+        let c_found = op.eval(tvec!(a, b)).unwrap().pop().unwrap();
+        assert_eq!(c, c_found);
+    }
+
+    #[test]
+    fn unary_f64_optimized() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let wire =
+            model.add_source("b", TypedFact::dt_shape(f64::datum_type(), [3, 2].as_ref())?)?;
+        let a = rctensor2(&[[0f64, 1.0, 2.0], [3.0, 4.0, 5.0]]);
+        let wire = model.wire_node("m", MatMulUnary::new(a, false, false, false, None), &[wire])?;
+        model.set_output_outlets(&wire)?;
+        let b = tensor2(&[[0f64, 1.0], [1.0, 2.0], [2.0, 3.0]]);
+        let optimized = model.declutter()?.optimize()?;
+        assert!(optimized
+            .nodes()
+            .iter()
+            .any(|n| n.op_is::<lir::MatMatMulUnaryFinite<f64, f64, f64, f64>>()));
+        let c = optimized.into_runnable()?.run(tvec!(b))?.remove(0);
+        assert_eq!(*c, tensor2(&[[5f64, 8.0], [14.0, 26.0]]));
+        Ok(())
+    }
+
+    fn mixed_sign_q_params() -> QParams {
+        let mut q = QParams::new(i32::datum_type());
+        q.zero_point_a = Some(rctensor0(1u8));
+        q.zero_point_b = Some(rctensor1(&[-2i8, 3]));
+        q
+    }
+
+    #[test]
+    fn bin_u8_i8() {
+        let a = rctensor2(&[[0u8, 255, 2], [3, 4, 128]]);
+        let b = rctensor2(&[[-128i8, 1], [127, -2], [2, 3]]);
+        let c = rctensor2(&[[32896i32, -1268], [643, -19]]);
+        let op = MatMul::default().with_q_params(mixed_sign_q_params());
+        let c_found = op.eval(tvec!(a, b)).unwrap().pop().unwrap();
+        assert_eq!(c, c_found);
+    }
+
+    #[test]
+    fn bin_i8_u8() {
+        let a = rctensor2(&[[-128i8, 127, 2], [1, -2, 3]]);
+        let b = rctensor2(&[[0u8, 3], [255, 4], [2, 128]]);
+        let c = rctensor2(&[[32896i32, 643], [-1268, -19]]);
+        let mut q = mixed_sign_q_params();
+        std::mem::swap(&mut q.zero_point_a, &mut q.zero_point_b);
+        let op = MatMul::default().with_q_params(q);
+        let c_found = op.eval(tvec!(a, b)).unwrap().pop().unwrap();
+        assert_eq!(c, c_found);
+    }
+
     #[test]
     fn batch_input() -> TractResult<()> {
         crate::setup_test_logger();
@@ -18,8 +18,10 @@ pub mod array;
 pub mod cast;
 pub mod change_axes;
 pub mod cnn;
+pub mod control_flow;
 pub mod downsample;
 pub mod dummy;
+pub mod einsum;
 pub mod identity;
 pub mod konst;
 pub mod logic;
@@ -27,6 +29,7 @@ pub mod math;
 pub mod matmul;
 pub mod nn;
 pub mod quant;
+pub mod resize;
 pub mod scan;
 pub mod source;
 pub mod unimpl;
@@ -76,6 +79,23 @@ pub trait OpState: fmt::Debug + Send {
 
 pub trait StatelessOp: Op {
     fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>>;
+
+    /// Evaluate the op, writing its single output in `output`, an
+    /// uninitialized tensor of the output fact type and shape.
+    ///
+    /// Returns false, leaving `output` alone, if the op can not do it. `eval`
+    /// will be called instead.
+    #[allow(unused_variables)]
+    fn eval_into(&self, inputs: &[Arc<Tensor>], output: &mut Tensor) -> TractResult<bool> {
+        Ok(false)
+    }
+
+    /// The input `eval` overwrites with its output when it is not shared
+    /// with another consumer, if any.
+    #[allow(unused_variables)]
+    fn in_place_input(&self, inputs: &[&TypedFact]) -> Option<usize> {
+        None
+    }
 }
 
 pub trait StatefullOp {
@@ -14,10 +14,20 @@ impl ArgMaxMin {
     fn eval_t<T: Datum + PartialOrd>(&self, input: Arc<Tensor>) -> TractResult<Arc<Tensor>> {
         use std::cmp::Ordering;
         let array = input.to_array_view::<T>()?;
+        // max_by keeps the last of equal elements, so ties are broken
+        // toward the lowest index by ordering it higher
         let f: fn(&(usize, &T), &(usize, &T)) -> Ordering = if self.max {
-            |a, b| a.1.partial_cmp(&b.1).unwrap_or(a.0.cmp(&b.0))
+            |a, b| match a.1.partial_cmp(&b.1) {
+                Some(Ordering::Equal) => b.0.cmp(&a.0),
+                Some(o) => o,
+                None => a.0.cmp(&b.0),
+            }
         } else {
-            |a, b| b.1.partial_cmp(&a.1).unwrap_or(a.0.cmp(&b.0))
+            |a, b| match b.1.partial_cmp(&a.1) {
+                Some(Ordering::Equal) => b.0.cmp(&a.0),
+                Some(o) => o,
+                None => a.0.cmp(&b.0),
+            }
         };
         let mut values = array
             .map_axis(Axis(self.axis), |row| row.iter().enumerate().max_by(f).unwrap().0 as i64);
@@ -58,3 +68,17 @@ impl TypedOp for ArgMaxMin {
 
     as_op!();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ties_pick_first_index() {
+        let input = rctensor2(&[[1f32, 3., 3.], [2., 0., 0.]]);
+        let max = ArgMaxMin::new(true, 1, false).eval(tvec!(input.clone())).unwrap();
+        assert_eq!(max, tvec!(rctensor1(&[1i64, 0])));
+        let min = ArgMaxMin::new(false, 1, false).eval(tvec!(input)).unwrap();
+        assert_eq!(min, tvec!(rctensor1(&[0i64, 1])));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::internal::*;
+use num_traits::Float;
+
+/// Normalize to zero mean and unit variance over the axes from `axis` to the
+/// last one: (x - mean) / sqrt(var + epsilon).
+///
+/// This is the fused form of the Reduce and binary ops the normalization
+/// layers expand to, computing each group in two passes over contiguous
+/// memory.
+#[derive(Debug, Clone, new, Educe)]
+#[educe(Hash)]
+pub struct MeanVarNorm {
+    pub axis: usize,
+    #[educe(Hash(method = "hash_f32"))]
+    pub epsilon: f32,
+}
+
+tract_linalg::impl_dyn_hash!(MeanVarNorm);
+
+impl MeanVarNorm {
+    fn eval_t<T: Datum + Float>(&self, t: &mut Tensor) -> TractResult<()> {
+        let group = t.shape()[self.axis..].iter().product::<usize>();
+        if group == 0 {
+            return Ok(());
+        }
+        let n = T::from(group).unwrap();
+        let epsilon = T::from(self.epsilon).unwrap();
+        for xs in t.as_slice_mut::<T>()?.chunks_mut(group) {
+            let mean = xs.iter().fold(T::zero(), |acc, &x| acc + x) / n;
+            let var = xs.iter().fold(T::zero(), |acc, &x| acc + (x - mean) * (x - mean)) / n;
+            let rstd = (var + epsilon).sqrt().recip();
+            xs.iter_mut().for_each(|x| *x = (*x - mean) * rstd);
+        }
+        Ok(())
+    }
+}
+
+impl Op for MeanVarNorm {
+    fn name(&self) -> Cow<str> {
+        "MeanVarNorm".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {} epsilon: {}", self.axis, self.epsilon)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for MeanVarNorm {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let mut t = args_1!(inputs).into_tensor();
+        match t.datum_type() {
+            DatumType::F32 => self.eval_t::<f32>(&mut t)?,
+            DatumType::F64 => self.eval_t::<f64>(&mut t)?,
+            dt => bail!("MeanVarNorm does not support {:?}", dt),
+        }
+        Ok(tvec!(t.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for MeanVarNorm {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if self.axis > inputs[0].rank() {
+            bail!("MeanVarNorm on axis {} for rank {}", self.axis, inputs[0].rank())
+        }
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &*inputs[0].shape.to_tvec())?))
+    }
+
+    fn invariants(&self, _model: &TypedModel, _node: &TypedNode) -> TractResult<Invariants> {
+        Ok((0..self.axis).map(|axis| AxisInfo::simple(axis)).collect::<TVec<_>>().into())
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let count = inputs[0].shape.iter().maybe_product()?;
+        Ok(tvec!((Cost::FMA(inputs[0].datum_type), count * 4)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_rows() {
+        let op = MeanVarNorm::new(1, 0.0);
+        let output = op.eval(tvec!(rctensor2(&[[1f32, 3.], [2., 6.]]))).unwrap();
+        assert_eq!(*output[0], tensor2(&[[-1f32, 1.], [-1., 1.]]));
+    }
+}
@@ -1,9 +1,11 @@
 mod arg_max_min;
 mod data_formats;
+mod mean_var_norm;
 mod reduce;
 
 pub use self::arg_max_min::ArgMaxMin;
 pub use self::data_formats::{BaseDataShape, DataFormat, DataShape};
+pub use self::mean_var_norm::MeanVarNorm;
 pub use self::reduce::{Reduce, Reducer};
 
 pub use crate::internal::*;
@@ -11,6 +13,10 @@ pub use crate::internal::*;
 element_wise!(sigmoid, Sigmoid, [f32] => |_, xs| {
     (tract_linalg::ops().sigmoid_f32)().run(xs);
     Ok(())
+},
+[f16] => |_, xs| {
+    xs.iter_mut().for_each(|x| *x = f16::from(1.0) / (f16::from(1.0) + num_traits::Float::exp(-*x)));
+    Ok(())
 };
     cost: |dt| {tvec!((Cost::FMA(dt), 11), (Cost::Div(dt), 1))}
 );
@@ -150,8 +150,8 @@ fn info_quantize_linear_i8(q: &QuantizeLinearI8) -> TractResult<Vec<String>> {
 #[educe(Hash)]
 pub struct DequantizeLinearF32 {
     #[educe(Hash(method = "hash_f32"))]
-    scale: f32,
-    zero_point: i32,
+    pub scale: f32,
+    pub zero_point: i32,
 }
 
 impl DequantizeLinearF32 {
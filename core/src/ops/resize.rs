@@ -0,0 +1,485 @@
+//! Resizing of a tensor along one or several of its axes.
+//!
+//! This is modeled after ONNX Resize: for each axis, every output coordinate
+//! is mapped to a (fractional) input coordinate by the coordinate
+//! transformer, then the interpolator computes the output value from the
+//! input neighbourhood of this coordinate. Axes are processed one after
+//! the other, which is equivalent to n-linear or n-cubic interpolation.
+//!
+//! Besides the resized tensor, the op takes up to three optional inputs:
+//! the region of interest (only relevant for TfCropAndResize), the scales,
+//! and the output sizes. Either scales or sizes must be provided, and must
+//! be known at optimisation time.
+use crate::internal::*;
+use ndarray::*;
+use num_traits::Float;
+
+#[derive(Clone, Copy, Debug, PartialEq, Hash)]
+pub enum CoordTransformer {
+    HalfPixel,
+    AlignCorners,
+    Asymmetric,
+    TfHalfPixelForNn,
+    PytorchHalfPixel,
+    TfCropAndResize,
+}
+
+impl CoordTransformer {
+    /// Input coordinate corresponding to output coordinate `x_out`.
+    pub fn transform(
+        &self,
+        x_out: usize,
+        scale: f32,
+        len_in: usize,
+        len_out: usize,
+        roi: (f32, f32),
+    ) -> f32 {
+        let x_out = x_out as f32;
+        match self {
+            CoordTransformer::HalfPixel => (x_out + 0.5) / scale - 0.5,
+            CoordTransformer::AlignCorners => {
+                if len_out == 1 {
+                    0.0
+                } else {
+                    x_out * (len_in as f32 - 1.0) / (len_out as f32 - 1.0)
+                }
+            }
+            CoordTransformer::Asymmetric => x_out / scale,
+            CoordTransformer::TfHalfPixelForNn => (x_out + 0.5) / scale,
+            CoordTransformer::PytorchHalfPixel => {
+                if len_out > 1 {
+                    (x_out + 0.5) / scale - 0.5
+                } else {
+                    0.0
+                }
+            }
+            CoordTransformer::TfCropAndResize => {
+                let (start, end) = roi;
+                if len_out > 1 {
+                    start * (len_in as f32 - 1.0)
+                        + x_out * (end - start) * (len_in as f32 - 1.0) / (len_out as f32 - 1.0)
+                } else {
+                    0.5 * (start + end) * (len_in as f32 - 1.0)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Hash)]
+pub enum Interpolator {
+    Nearest,
+    Linear,
+    Cubic,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Hash)]
+pub enum Nearest {
+    RoundPreferFloor,
+    RoundPreferCeil,
+    Floor,
+    Ceil,
+}
+
+impl Nearest {
+    pub fn pick(&self, x: f32) -> f32 {
+        let half = x - x.floor() == 0.5;
+        match self {
+            Nearest::RoundPreferFloor if half => x.floor(),
+            Nearest::RoundPreferCeil if half => x.ceil(),
+            Nearest::RoundPreferFloor | Nearest::RoundPreferCeil => x.round(),
+            Nearest::Floor => x.floor(),
+            Nearest::Ceil => x.ceil(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, new, Educe)]
+#[educe(Hash)]
+pub struct Resize {
+    pub coord_transformer: CoordTransformer,
+    pub interpolator: Interpolator,
+    pub nearest: Nearest,
+    #[educe(Hash(method = "hash_f32"))]
+    pub cubic_coeff_a: f32,
+    pub exclude_outside: bool,
+    #[educe(Hash(method = "hash_f32"))]
+    pub extrapolation_value: f32,
+    pub optional_roi_input: Option<usize>,
+    pub optional_scales_input: Option<usize>,
+    pub optional_sizes_input: Option<usize>,
+}
+
+tract_linalg::impl_dyn_hash!(Resize);
+
+/// For each output coordinate along an axis, the input coordinates and
+/// weights to combine, or None if the extrapolation value must be used.
+#[derive(Debug)]
+struct AxisPlan {
+    len: usize,
+    taps: Vec<Option<TVec<(usize, f32)>>>,
+}
+
+impl Resize {
+    /// Scales and sizes inputs, if present and non empty.
+    ///
+    /// Both must have one value per input axis, and sizes can not be
+    /// negative.
+    fn scales_and_sizes<'a>(
+        &self,
+        inputs: &[Option<&'a Tensor>],
+        rank: usize,
+    ) -> TractResult<(Option<Cow<'a, Tensor>>, Option<Cow<'a, Tensor>>)> {
+        let get = |slot: Option<usize>| -> Option<&'a Tensor> {
+            slot.and_then(|ix| inputs.get(ix).cloned().flatten()).filter(|t| t.len() > 0)
+        };
+        let scales = get(self.optional_scales_input).map(|t| t.cast_to::<f32>()).transpose()?;
+        let sizes = get(self.optional_sizes_input).map(|t| t.cast_to::<i64>()).transpose()?;
+        if scales.is_none() && sizes.is_none() {
+            bail!("Resize needs either scales or sizes")
+        }
+        if let Some(scales) = &scales {
+            if scales.len() != rank {
+                bail!("Resize expects {} scales, got {:?}", rank, scales)
+            }
+        }
+        if let Some(sizes) = &sizes {
+            if sizes.len() != rank {
+                bail!("Resize expects {} sizes, got {:?}", rank, sizes)
+            }
+            if sizes.as_slice::<i64>()?.iter().any(|&s| s < 0) {
+                bail!("Resize sizes can not be negative, got {:?}", sizes)
+            }
+        }
+        Ok((scales, sizes))
+    }
+
+    fn taps(&self, x: f32, len_in: usize) -> TVec<(usize, f32)> {
+        let clamp = |x: isize| x.max(0).min(len_in as isize - 1) as usize;
+        let x0 = x.floor();
+        let ratio = x - x0;
+        let x0 = x0 as isize;
+        match self.interpolator {
+            Interpolator::Nearest => tvec!((clamp(self.nearest.pick(x) as isize), 1.0)),
+            Interpolator::Linear => tvec!((clamp(x0), 1.0 - ratio), (clamp(x0 + 1), ratio)),
+            Interpolator::Cubic => {
+                let a = self.cubic_coeff_a;
+                let coeffs = [
+                    ((a * (ratio + 1.0) - 5.0 * a) * (ratio + 1.0) + 8.0 * a) * (ratio + 1.0)
+                        - 4.0 * a,
+                    ((a + 2.0) * ratio - (a + 3.0)) * ratio * ratio + 1.0,
+                    ((a + 2.0) * (1.0 - ratio) - (a + 3.0)) * (1.0 - ratio) * (1.0 - ratio) + 1.0,
+                    ((a * (2.0 - ratio) - 5.0 * a) * (2.0 - ratio) + 8.0 * a) * (2.0 - ratio)
+                        - 4.0 * a,
+                ];
+                let mut taps: TVec<(isize, f32)> =
+                    coeffs.iter().enumerate().map(|(ix, &c)| (x0 - 1 + ix as isize, c)).collect();
+                if self.exclude_outside {
+                    taps.retain(|(x, _)| *x >= 0 && *x < len_in as isize);
+                    let sum: f32 = taps.iter().map(|t| t.1).sum();
+                    taps.iter_mut().for_each(|t| t.1 /= sum);
+                }
+                taps.into_iter().map(|(x, c)| (clamp(x), c)).collect()
+            }
+        }
+    }
+
+    fn plans(&self, inputs: &[Arc<Tensor>]) -> TractResult<Vec<Option<AxisPlan>>> {
+        let opt_inputs: TVec<Option<&Tensor>> = inputs.iter().map(|t| Some(&**t)).collect();
+        let (scales, sizes) = self.scales_and_sizes(&opt_inputs, inputs[0].rank())?;
+        let roi = self
+            .optional_roi_input
+            .and_then(|ix| inputs.get(ix))
+            .filter(|t| t.len() > 0)
+            .map(|t| t.cast_to::<f32>())
+            .transpose()?;
+        let input_shape = inputs[0].shape();
+        let rank = input_shape.len();
+        (0..rank)
+            .map(|axis| {
+                let len_in = input_shape[axis];
+                let (len_out, scale) = if let Some(sizes) = &sizes {
+                    let len_out = sizes.as_slice::<i64>()?[axis] as usize;
+                    (len_out, len_out as f32 / len_in as f32)
+                } else {
+                    let scale = scales.as_ref().unwrap().as_slice::<f32>()?[axis];
+                    ((len_in as f32 * scale).floor() as usize, scale)
+                };
+                if len_in == 0 && len_out > 0 {
+                    bail!(
+                        "Resize can not fill axis {} of length {} from an empty axis",
+                        axis,
+                        len_out
+                    )
+                }
+                if len_in == len_out && self.coord_transformer != CoordTransformer::TfCropAndResize
+                {
+                    return Ok(None);
+                }
+                let roi = if let Some(roi) = &roi {
+                    let roi = roi.as_slice::<f32>()?;
+                    (roi[axis], roi[rank + axis])
+                } else {
+                    (0.0, 1.0)
+                };
+                let taps = (0..len_out)
+                    .map(|x_out| {
+                        let x =
+                            self.coord_transformer.transform(x_out, scale, len_in, len_out, roi);
+                        if self.coord_transformer == CoordTransformer::TfCropAndResize
+                            && (x < 0.0 || x > len_in as f32 - 1.0)
+                        {
+                            None
+                        } else {
+                            Some(self.taps(x, len_in))
+                        }
+                    })
+                    .collect();
+                Ok(Some(AxisPlan { len: len_out, taps }))
+            })
+            .collect()
+    }
+
+    fn eval_nearest<T: Datum>(
+        &self,
+        input: &Tensor,
+        plans: &[Option<AxisPlan>],
+    ) -> TractResult<Tensor> {
+        let mut data = input.to_array_view::<T>()?.to_owned();
+        let extrapolation = if self.coord_transformer == CoordTransformer::TfCropAndResize {
+            tensor0(self.extrapolation_value).cast_to::<T>()?.to_scalar::<T>()?.clone()
+        } else {
+            T::default()
+        };
+        for (axis, plan) in plans.iter().enumerate() {
+            if let Some(plan) = plan {
+                let mut shape = data.shape().to_vec();
+                shape[axis] = plan.len;
+                data = ArrayD::from_shape_fn(shape, |mut coords| match &plan.taps[coords[axis]] {
+                    Some(taps) => {
+                        coords[axis] = taps[0].0;
+                        data[coords.slice()].clone()
+                    }
+                    None => extrapolation.clone(),
+                });
+            }
+        }
+        Ok(data.into_tensor())
+    }
+
+    fn eval_interpolate<T: Datum + Float>(
+        &self,
+        input: &Tensor,
+        plans: &[Option<AxisPlan>],
+    ) -> TractResult<Tensor> {
+        let mut data = input.to_array_view::<T>()?.to_owned();
+        let extrapolation = T::from(self.extrapolation_value).unwrap();
+        for (axis, plan) in plans.iter().enumerate() {
+            if let Some(plan) = plan {
+                let mut shape = data.shape().to_vec();
+                shape[axis] = plan.len;
+                data = ArrayD::from_shape_fn(shape, |mut coords| match &plan.taps[coords[axis]] {
+                    Some(taps) => taps.iter().fold(T::zero(), |acc, &(x, w)| {
+                        coords[axis] = x;
+                        acc + data[coords.slice()] * T::from(w).unwrap()
+                    }),
+                    None => extrapolation,
+                });
+            }
+        }
+        Ok(data.into_tensor())
+    }
+}
+
+impl Op for Resize {
+    fn name(&self) -> Cow<str> {
+        "Resize".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        let mut info = vec![format!("{:?} {:?}", self.interpolator, self.coord_transformer)];
+        match self.interpolator {
+            Interpolator::Nearest => info.push(format!("nearest: {:?}", self.nearest)),
+            Interpolator::Cubic => info.push(format!(
+                "cubic_coeff_a: {} exclude_outside: {}",
+                self.cubic_coeff_a, self.exclude_outside
+            )),
+            Interpolator::Linear => (),
+        }
+        Ok(info)
+    }
+
+    fn validation(&self) -> Validation {
+        if self.interpolator == Interpolator::Nearest {
+            Validation::Accurate
+        } else {
+            Validation::Rounding
+        }
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for Resize {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let plans = self.plans(&inputs)?;
+        let input = &inputs[0];
+        let output = match (self.interpolator, input.datum_type()) {
+            (Interpolator::Nearest, dt) => {
+                dispatch_datum!(Self::eval_nearest(dt)(self, input, &plans))?
+            }
+            (_, DatumType::F32) => self.eval_interpolate::<f32>(input, &plans)?,
+            (_, DatumType::F64) => self.eval_interpolate::<f64>(input, &plans)?,
+            (_, dt) => bail!("{:?} interpolation is not supported for {:?}", self.interpolator, dt),
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for Resize {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let konsts: TVec<Option<&Tensor>> = inputs.iter().map(|f| f.konst.as_deref()).collect();
+        let (scales, sizes) = self
+            .scales_and_sizes(&konsts, inputs[0].rank())
+            .chain_err(|| "Resize scales or sizes must be valid constants")?;
+        let mut fact = inputs[0].clone();
+        fact.konst = None;
+        for axis in 0..fact.rank() {
+            let dim = if let Some(sizes) = &sizes {
+                let len_out = sizes.as_slice::<i64>()?[axis] as usize;
+                if len_out > 0 && fact.shape.dim(axis) == 0.to_dim() {
+                    bail!(
+                        "Resize can not fill axis {} of length {} from an empty axis",
+                        axis,
+                        len_out
+                    )
+                }
+                len_out.to_dim()
+            } else {
+                let scale = scales.as_ref().unwrap().as_slice::<f32>()?[axis];
+                if scale == 1.0 {
+                    continue;
+                }
+                let len_in = fact.shape.dim(axis).to_integer()?;
+                ((len_in as f32 * scale).floor() as usize).to_dim()
+            };
+            fact.shape.set_dim(axis, dim)?;
+        }
+        Ok(tvec!(fact))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resize(interpolator: Interpolator, coord_transformer: CoordTransformer) -> Resize {
+        Resize::new(
+            coord_transformer,
+            interpolator,
+            Nearest::RoundPreferFloor,
+            -0.75,
+            false,
+            0.0,
+            None,
+            Some(1),
+            None,
+        )
+    }
+
+    fn run(op: Resize, input: Tensor, scales: &[f32]) -> Tensor {
+        let mut output = op.eval(tvec!(input.into_arc_tensor(), rctensor1(scales))).unwrap();
+        output.remove(0).into_tensor()
+    }
+
+    #[test]
+    fn downsample_nearest() {
+        let op = resize(Interpolator::Nearest, CoordTransformer::HalfPixel);
+        let input = tensor2(&[[1f32, 2., 3., 4.], [5., 6., 7., 8.]]);
+        assert_eq!(run(op, input, &[0.6, 0.6]), tensor2(&[[1f32, 3.]]));
+    }
+
+    #[test]
+    fn upsample_linear_half_pixel() {
+        let op = resize(Interpolator::Linear, CoordTransformer::HalfPixel);
+        let input = tensor2(&[[1f32, 2.], [3., 4.]]);
+        let expected = tensor2(&[
+            [1f32, 1.25, 1.75, 2.],
+            [1.5, 1.75, 2.25, 2.5],
+            [2.5, 2.75, 3.25, 3.5],
+            [3., 3.25, 3.75, 4.],
+        ]);
+        run(op, input, &[2., 2.]).close_enough(&expected, false).unwrap();
+    }
+
+    #[test]
+    fn upsample_linear_align_corners() {
+        let op = resize(Interpolator::Linear, CoordTransformer::AlignCorners);
+        let input = tensor2(&[[1f32, 2.], [3., 4.]]);
+        let expected = tensor2(&[
+            [1f32, 1.3333333, 1.6666667, 2.],
+            [1.6666666, 2., 2.3333333, 2.6666665],
+            [2.3333335, 2.6666665, 3., 3.3333335],
+            [3., 3.3333333, 3.6666667, 4.],
+        ]);
+        run(op, input, &[2., 2.]).close_enough(&expected, true).unwrap();
+    }
+
+    #[test]
+    fn upsample_cubic() {
+        let op = resize(Interpolator::Cubic, CoordTransformer::HalfPixel);
+        let input = tensor2(&[
+            [1f32, 2., 3., 4.],
+            [5., 6., 7., 8.],
+            [9., 10., 11., 12.],
+            [13., 14., 15., 16.],
+        ]);
+        let output = run(op, input, &[2., 2.]);
+        let first_row =
+            output.to_array_view::<f32>().unwrap().index_axis(Axis(0), 0).to_owned().into_tensor();
+        let expected = tensor1(&[
+            0.47265625f32,
+            0.76953125,
+            1.24609375,
+            1.875,
+            2.28125,
+            2.91015625,
+            3.38671875,
+            3.68359375,
+        ]);
+        first_row.close_enough(&expected, true).unwrap();
+    }
+
+    #[test]
+    fn reject_scales_of_wrong_length() {
+        let op = resize(Interpolator::Nearest, CoordTransformer::HalfPixel);
+        let input = tensor2(&[[1f32, 2.], [3., 4.]]).into_arc_tensor();
+        assert!(op.eval(tvec!(input, rctensor1(&[2f32]))).is_err());
+    }
+
+    #[test]
+    fn reject_negative_sizes() {
+        let mut op = resize(Interpolator::Nearest, CoordTransformer::HalfPixel);
+        op.optional_scales_input = None;
+        op.optional_sizes_input = Some(1);
+        let input = tensor2(&[[1f32, 2.], [3., 4.]]).into_arc_tensor();
+        assert!(op.eval(tvec!(input, rctensor1(&[2i64, -2]))).is_err());
+    }
+
+    #[test]
+    fn reject_sizes_from_empty_axis() {
+        let mut op = resize(Interpolator::Linear, CoordTransformer::HalfPixel);
+        op.optional_scales_input = None;
+        op.optional_sizes_input = Some(1);
+        let input = Tensor::from(tract_ndarray::Array2::<f32>::zeros((2, 0)));
+        let sizes = rctensor1(&[2i64, 3]);
+        let fact = TypedFact::dt_shape(f32::datum_type(), [2, 0].as_ref()).unwrap();
+        let sizes_fact = TypedFact::from(sizes.clone());
+        assert!(op.output_facts(&[&fact, &sizes_fact]).is_err());
+        assert!(op.eval(tvec!(input.into_arc_tensor(), sizes)).is_err());
+    }
+}
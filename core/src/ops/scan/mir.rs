@@ -216,6 +216,12 @@ impl Scan {
         for (inner_input_id, input) in self.body.input_outlets()?.iter().enumerate() {
             let source_node = self.body.node(input.node);
             if source_node.outputs[0].successors.len() == 0 {
+                // the iteration count comes from the scanned inputs, keep one
+                if let InputMapping::Scan { .. } = self.input_mapping[inner_input_id] {
+                    if self.input_mapping.iter().filter(|m| m.as_scan().is_some()).count() == 1 {
+                        continue;
+                    }
+                }
                 let mut new_inputs = node.inputs.clone();
                 let slot = match &self.input_mapping[inner_input_id] {
                     InputMapping::Full { slot } => Some(slot),
@@ -331,6 +337,7 @@ impl Scan {
                         let new_input_outer_fact = outside_patch.outlet_fact(new_input_wire)?;
                         let mut new_input_inner_fact = new_input_outer_fact.clone();
                         new_input_inner_fact.shape.set_dim(axis_after, chunk.clone())?;
+                        new_input_inner_fact.konst = None;
 
                         let mut new_body = self.body.clone();
                         let new_source_wire = new_body.add_source(
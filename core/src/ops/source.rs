@@ -16,7 +16,7 @@ impl OpState for SourceState {
 
 #[derive(Debug, Clone, new, Hash)]
 pub struct TypedSource {
-    fact: TypedFact,
+    pub fact: TypedFact,
 }
 
 tract_linalg::impl_dyn_hash!(TypedSource);
@@ -86,7 +86,7 @@ impl TypedOp for TypedSource {
         stream_dim: usize,
     ) -> TractResult<TVec<OutletId>> {
         let mut fact = self.fact.clone();
-        if let Some(info) = self.fact.shape.stream_info.as_ref() {
+        if let Some(info) = self.fact.shape.stream_info() {
             fact.shape
                 .set_dim(info.axis, fact.shape.dim(info.axis).concretize_stream_dim(stream_dim))?;
         }
@@ -3,14 +3,18 @@ use std::fmt::{Debug, Display};
 use std::marker::PhantomData;
 
 use crate::internal::*;
+use crate::model::memory::{Arena, MemoryPlan};
 use crate::model::order::eval_order_for_nodes;
 use crate::model::{Fact, Graph, OutletId};
+use tract_linalg::multithread::{current_tract_executor, multithread_tract_scope, Executor};
 
 #[derive(Debug, Default)]
 pub struct SessionState {
     pub inputs: HashMap<usize, Arc<Tensor>>,
     pub known_stream_len: Option<usize>,
     pub tensors: HashMap<String, Tensor>,
+    /// Where the node being evaluated can write its output, in the state arena.
+    pub arena_output: Option<Tensor>,
 }
 
 #[derive(Debug, Clone, Educe)]
@@ -25,6 +29,10 @@ where
     pub outputs: Vec<OutletId>,
     pub order: Vec<usize>,
     pub flush_lists: Vec<TVec<usize>>,
+    #[educe(Hash(ignore))]
+    pub executor: Option<Executor>,
+    #[educe(Hash(ignore))]
+    pub memory_plan: Option<MemoryPlan>,
     _casper: PhantomData<(F, O)>,
 }
 
@@ -78,6 +86,8 @@ where
             order,
             flush_lists,
             outputs: outputs.to_vec(),
+            executor: None,
+            memory_plan: None,
             _casper: PhantomData,
         })
     }
@@ -87,6 +97,33 @@ where
         state.run(inputs)
     }
 
+    /// Sets the intra-op executor (matrix products, im2col packing) used when
+    /// running this plan, overriding the one of the calling thread.
+    pub fn with_executor(self, executor: Executor) -> SimplePlan<F, O, M> {
+        SimplePlan { executor: Some(executor), ..self }
+    }
+
+    /// Plans the memory of this plan runs. States then compute the planned
+    /// tensors in their own arena.
+    pub fn with_memory_plan(self) -> TractResult<SimplePlan<F, O, M>> {
+        let memory_plan = MemoryPlan::new(&self)?;
+        Ok(SimplePlan { memory_plan: Some(memory_plan), ..self })
+    }
+
+    /// Run the plan once, evaluating independent nodes concurrently in `pool`.
+    pub fn run_in_pool(
+        &self,
+        inputs: TVec<Tensor>,
+        pool: &rayon::ThreadPool,
+    ) -> TractResult<TVec<Arc<Tensor>>>
+    where
+        F: Send + Sync,
+        O: Send + Sync,
+    {
+        let mut state = SimpleState::new(self)?;
+        state.run_in_pool(inputs, pool)
+    }
+
     pub fn model(&self) -> &Graph<F, O> {
         self.model.borrow()
     }
@@ -104,6 +141,7 @@ where
     pub states: Vec<Option<Box<dyn OpState>>>,
     pub session_state: SessionState,
     pub values: Vec<Option<TVec<Arc<Tensor>>>>,
+    pub arena: Option<Arena>,
     _phantom: PhantomData<(M, F, O)>,
 }
 
@@ -123,7 +161,16 @@ where
             .iter()
             .map(|n: &BaseNode<F, O>| n.op().state(&mut session, n.id))
             .collect::<TractResult<_>>()?;
-        Ok(SimpleState { plan, states, session_state: session, values, _phantom: PhantomData })
+        let arena =
+            plan.borrow().memory_plan.as_ref().map(|m| Arena::new(plan.borrow(), m)).transpose()?;
+        Ok(SimpleState {
+            plan,
+            states,
+            session_state: session,
+            values,
+            arena,
+            _phantom: PhantomData,
+        })
     }
 
     /// Reset wires state.
@@ -150,6 +197,23 @@ where
     }
 
     pub fn run_plan_with_eval<Eval>(
+        &mut self,
+        inputs: TVec<Tensor>,
+        eval: Eval,
+    ) -> TractResult<TVec<Arc<Tensor>>>
+    where
+        Eval: for<'a, 'b, 'c> FnMut(
+            &'a mut SessionState,
+            Option<&'b mut (dyn OpState + 'static)>,
+            &'c BaseNode<F, O>,
+            TVec<Arc<Tensor>>,
+        ) -> TractResult<TVec<Arc<Tensor>>>,
+    {
+        let executor = self.executor();
+        multithread_tract_scope(executor, || self.run_plan_with_eval_in_scope(inputs, eval))
+    }
+
+    fn run_plan_with_eval_in_scope<Eval>(
         &mut self,
         inputs: TVec<Tensor>,
         mut eval: Eval,
@@ -170,6 +234,7 @@ where
                 ref mut session_state,
                 ref mut states,
                 ref mut values,
+                ref mut arena,
                 ..
             } = self;
             let plan = plan.borrow();
@@ -193,68 +258,223 @@ where
                 }
 
                 if cfg!(debug_assertions) {
-                    let facts = model.node_input_facts(node.id)?;
-                    if facts.len() != inputs.len() {
-                        bail!(
-                            "Evaluating {}: expected {} inputs, got {}",
-                            node,
-                            facts.len(),
-                            inputs.len()
-                        );
-                    }
-                    for (ix, (v, f)) in inputs.iter().zip(facts.iter()).enumerate() {
-                        if !f.matches(v)? {
-                            bail!(
-                                "Evaluating {}: input {:?}, expected {:?}, got {:?}",
-                                node,
-                                ix,
-                                f,
-                                v
-                            );
-                        }
-                    }
+                    check_inputs(model, node, &inputs)?;
                 }
 
+                if let Some(arena) = arena.as_mut() {
+                    session_state.arena_output = arena.output(node.id);
+                }
                 let vs =
-                    eval(session_state, states[node.id].as_mut().map(|s| &mut **s), node, inputs)?;
+                    eval(session_state, states[node.id].as_mut().map(|s| &mut **s), node, inputs);
+                session_state.arena_output = None;
+                let vs = vs?;
 
                 if cfg!(debug_assertions) {
-                    let facts = model.node_output_facts(node.id)?;
-                    if facts.len() != vs.len() {
-                        bail!(
-                            "Evaluating {}: expected {} outputs, got {}",
-                            node,
-                            facts.len(),
-                            vs.len()
-                        );
-                    }
-                    for (ix, (v, f)) in vs.iter().zip(facts.iter()).enumerate() {
-                        if node.outputs[ix].successors.len() == 0 {
+                    check_outputs(model, node, &vs)?;
+                }
+
+                values[node.id] = Some(vs);
+            }
+            for output in &plan.outputs {
+                trace!("Extracting value {:?} ({})", output, model.node(output.node));
+                let value = values[output.node].as_ref().unwrap()[output.slot].clone();
+                // an op may have computed an output in place in the arena
+                if value.is_in_arena() {
+                    result.push(value.deep_clone().into_arc_tensor())
+                } else {
+                    result.push(value)
+                }
+            }
+        }
+        self.reset_wires()?;
+        Ok(result)
+    }
+
+    /// Run the plan, evaluating independent nodes concurrently in `pool`.
+    ///
+    /// Stateless nodes are dispatched to the pool as soon as all their inputs
+    /// are available. Nodes with an `OpState` need exclusive access to the
+    /// session state, so they are evaluated on the calling thread, one at a
+    /// time. As in `run`, intermediate values are released as soon as their
+    /// last consumer has been dispatched.
+    ///
+    /// When called from a worker thread of `pool` (a nested plan, like a Scan
+    /// or Loop body, evaluated by a node of an outer plan running in the same
+    /// pool), the plan is run sequentially on the calling thread.
+    pub fn run_in_pool(
+        &mut self,
+        inputs: TVec<Tensor>,
+        pool: &rayon::ThreadPool,
+    ) -> TractResult<TVec<Arc<Tensor>>>
+    where
+        F: Send + Sync,
+        O: Send + Sync,
+    {
+        self.run_in_pool_with_eval(inputs, pool, self::eval)
+    }
+
+    /// Same as `run_in_pool`, with a custom evaluation function.
+    ///
+    /// Stateless nodes are evaluated on the pool threads, with a scratch
+    /// session state. A panic in one of them is reported as an error.
+    pub fn run_in_pool_with_eval<Eval>(
+        &mut self,
+        inputs: TVec<Tensor>,
+        pool: &rayon::ThreadPool,
+        eval: Eval,
+    ) -> TractResult<TVec<Arc<Tensor>>>
+    where
+        F: Send + Sync,
+        O: Send + Sync,
+        Eval: for<'a, 'b, 'c> Fn(
+                &'a mut SessionState,
+                Option<&'b mut (dyn OpState + 'static)>,
+                &'c BaseNode<F, O>,
+                TVec<Arc<Tensor>>,
+            ) -> TractResult<TVec<Arc<Tensor>>>
+            + Sync,
+    {
+        // waiting for the spawned nodes from a worker of the pool could block
+        // the very thread they need (or all of them)
+        if pool.current_thread_index().is_some() {
+            return self.run_plan_with_eval(inputs, eval);
+        }
+        let result = self.run_in_pool_with_eval_and_keep_wires(inputs, pool, &eval);
+        // release the intermediate values even if the evaluation failed
+        self.reset_wires()?;
+        result
+    }
+
+    fn run_in_pool_with_eval_and_keep_wires<Eval>(
+        &mut self,
+        inputs: TVec<Tensor>,
+        pool: &rayon::ThreadPool,
+        eval: &Eval,
+    ) -> TractResult<TVec<Arc<Tensor>>>
+    where
+        F: Send + Sync,
+        O: Send + Sync,
+        Eval: for<'a, 'b, 'c> Fn(
+                &'a mut SessionState,
+                Option<&'b mut (dyn OpState + 'static)>,
+                &'c BaseNode<F, O>,
+                TVec<Arc<Tensor>>,
+            ) -> TractResult<TVec<Arc<Tensor>>>
+            + Sync,
+    {
+        let executor = self.executor();
+        let mut result = tvec!();
+        {
+            self.set_inputs(inputs)?;
+            let &mut SimpleState {
+                ref plan,
+                ref mut session_state,
+                ref mut states,
+                ref mut values,
+                ..
+            } = self;
+            let plan = plan.borrow();
+            let model = plan.model().borrow();
+            let nodes = model.nodes();
+            let mut missing_inputs = vec![0usize; nodes.len()];
+            let mut pending_consumers = vec![0usize; nodes.len()];
+            let mut successors: Vec<TVec<usize>> = vec![tvec!(); nodes.len()];
+            for &n in &plan.order {
+                for i in &nodes[n].inputs {
+                    missing_inputs[n] += 1;
+                    pending_consumers[i.node] += 1;
+                    successors[i.node].push(n);
+                }
+            }
+            for output in &plan.outputs {
+                // never released: the values are extracted at the end
+                pending_consumers[output.node] += 1;
+            }
+            let mut ready: std::collections::VecDeque<usize> =
+                plan.order.iter().cloned().filter(|&n| missing_inputs[n] == 0).collect();
+            let (tx, rx) = std::sync::mpsc::channel::<(usize, TractResult<TVec<Arc<Tensor>>>)>();
+            pool.in_place_scope(|scope| -> TractResult<()> {
+                let mut done = 0;
+                let mut running = 0;
+                while done < plan.order.len() {
+                    let (n, vs) = if let Some(n) = ready.pop_front() {
+                        let node = &nodes[n];
+                        trace!("Dispatching node {}", node);
+                        let mut inputs: TVec<Arc<Tensor>> = tvec![];
+                        for i in &node.inputs {
+                            let prec = values[i.node].as_ref().ok_or_else(|| {
+                                format!("Computing {}, precursor {} not done:", node, nodes[i.node])
+                            })?;
+                            inputs.push(prec[i.slot].clone());
+                        }
+                        for i in &node.inputs {
+                            pending_consumers[i.node] -= 1;
+                            if pending_consumers[i.node] == 0 {
+                                trace!("  Dispatched {} can now flush {}", node, nodes[i.node]);
+                                values[i.node] = None;
+                            }
+                        }
+                        if cfg!(debug_assertions) {
+                            check_inputs(model, node, &inputs)?;
+                        }
+                        if let Some(state) = states[n].as_mut() {
+                            let vs = multithread_tract_scope(executor.clone(), || {
+                                eval(session_state, Some(&mut **state), node, inputs)
+                            });
+                            (n, vs)
+                        } else {
+                            let tx = tx.clone();
+                            let executor = executor.clone();
+                            scope.spawn(move |_| {
+                                let vs = multithread_tract_scope(executor, || {
+                                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                                        eval(&mut SessionState::default(), None, node, inputs)
+                                    }))
+                                    .unwrap_or_else(|_| {
+                                        Err(format!("Panic evaluating {}", node).into())
+                                    })
+                                });
+                                // the receiver waits for exactly one message per spawned node
+                                let _ = tx.send((n, vs));
+                            });
+                            running += 1;
                             continue;
                         }
-                        if !f.matches(v)? {
-                            bail!(
-                                "Evaluating {}: output {:?}, expected {:?}, got {:?}",
-                                node,
-                                ix,
-                                f,
-                                v
-                            );
+                    } else if running > 0 {
+                        running -= 1;
+                        rx.recv().map_err(|e| format!("Worker failure: {}", e))?
+                    } else {
+                        bail!("No node ready to run, but plan is not done.")
+                    };
+                    let vs = vs?;
+                    if cfg!(debug_assertions) {
+                        check_outputs(model, &nodes[n], &vs)?;
+                    }
+                    values[n] = Some(vs);
+                    done += 1;
+                    for &succ in &successors[n] {
+                        missing_inputs[succ] -= 1;
+                        if missing_inputs[succ] == 0 {
+                            ready.push_back(succ);
                         }
                     }
                 }
-
-                values[node.id] = Some(vs);
-            }
+                Ok(())
+            })?;
             for output in &plan.outputs {
                 trace!("Extracting value {:?} ({})", output, model.node(output.node));
                 result.push(values[output.node].as_ref().unwrap()[output.slot].clone())
             }
         }
-        self.reset_wires()?;
         Ok(result)
     }
 
+    /// The intra-op executor for this state runs: the plan one if set, the
+    /// current thread one otherwise.
+    fn executor(&self) -> Executor {
+        self.plan().executor.clone().unwrap_or_else(current_tract_executor)
+    }
+
     pub fn set_inputs(&mut self, inputs: TVec<Tensor>) -> TractResult<()> {
         for (ix, t) in inputs.into_iter().enumerate() {
             self.set_input(ix, t)?
@@ -405,9 +625,170 @@ where
 {
     let r = match state {
         Some(ref mut state) => state.eval(session_state, node.op(), input),
-        None => node.op().as_stateless().expect("as_stateless").eval(input),
+        None => {
+            let op = node.op().as_stateless().expect("as_stateless");
+            match session_state.arena_output.take() {
+                Some(mut output) => match op.eval_into(&input, &mut output) {
+                    Ok(true) => Ok(tvec!(output.into_arc_tensor())),
+                    Ok(false) => op.eval(input),
+                    Err(e) => Err(e),
+                },
+                None => op.eval(input),
+            }
+        }
     }
     .chain_err(|| format!("Evaluating {}", node));
     // println!("{} {:?}", node, r);
     r
 }
+
+fn check_inputs<F, O>(
+    model: &Graph<F, O>,
+    node: &BaseNode<F, O>,
+    inputs: &[Arc<Tensor>],
+) -> TractResult<()>
+where
+    F: Fact + Hash + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash,
+{
+    let facts = model.node_input_facts(node.id)?;
+    if facts.len() != inputs.len() {
+        bail!("Evaluating {}: expected {} inputs, got {}", node, facts.len(), inputs.len());
+    }
+    for (ix, (v, f)) in inputs.iter().zip(facts.iter()).enumerate() {
+        if !f.matches(v)? {
+            bail!("Evaluating {}: input {:?}, expected {:?}, got {:?}", node, ix, f, v);
+        }
+    }
+    Ok(())
+}
+
+fn check_outputs<F, O>(
+    model: &Graph<F, O>,
+    node: &BaseNode<F, O>,
+    outputs: &[Arc<Tensor>],
+) -> TractResult<()>
+where
+    F: Fact + Hash + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash,
+{
+    let facts = model.node_output_facts(node.id)?;
+    if facts.len() != outputs.len() {
+        bail!("Evaluating {}: expected {} outputs, got {}", node, facts.len(), outputs.len());
+    }
+    for (ix, (v, f)) in outputs.iter().zip(facts.iter()).enumerate() {
+        if node.outputs[ix].successors.len() == 0 {
+            continue;
+        }
+        if !f.matches(v)? {
+            bail!("Evaluating {}: output {:?}, expected {:?}, got {:?}", node, ix, f, v);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math;
+
+    fn wide_model() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let fact = TypedFact::dt_shape(f32::datum_type(), [2].as_ref())?;
+        let x = model.add_source("x", fact)?;
+        let mut branches = tvec!();
+        for i in 0..8 {
+            let c = model.add_const(format!("c{}", i), rctensor1(&[i as f32, 1.]))?;
+            let b = model.wire_node(format!("b{}", i), math::add::bin_typed(), &[x, c])?[0];
+            let b = model.wire_node(format!("m{}", i), math::mul::bin_typed(), &[b, b])?[0];
+            branches.push(b);
+        }
+        let mut sum = branches[0];
+        for (i, b) in branches.iter().enumerate().skip(1) {
+            sum = model.wire_node(format!("s{}", i), math::add::bin_typed(), &[sum, *b])?[0];
+        }
+        model.set_output_outlets(&[sum, branches[3]])?;
+        Ok(model)
+    }
+
+    #[test]
+    fn run_in_pool_matches_sequential() {
+        let model = wide_model().unwrap();
+        let plan = SimplePlan::new(&model).unwrap();
+        let expected = plan.run(tvec!(tensor1(&[1f32, 2.]))).unwrap();
+        for &threads in &[1, 4] {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            let mut state = SimpleState::new(&plan).unwrap();
+            for _ in 0..2 {
+                let result = state.run_in_pool(tvec!(tensor1(&[1f32, 2.])), &pool).unwrap();
+                assert_eq!(result, expected);
+                assert!(state.values.iter().all(|v| v.is_none()));
+            }
+        }
+    }
+
+    #[test]
+    fn run_in_pool_reports_panics() {
+        let model = wide_model().unwrap();
+        let plan = SimplePlan::new(&model).unwrap();
+        let expected = plan.run(tvec!(tensor1(&[1f32, 2.]))).unwrap();
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        let mut state = SimpleState::new(&plan).unwrap();
+        let result = state.run_in_pool_with_eval(
+            tvec!(tensor1(&[1f32, 2.])),
+            &pool,
+            |session_state, op_state, node, inputs| {
+                if node.name == "m3" {
+                    panic!("Failing on purpose")
+                }
+                eval(session_state, op_state, node, inputs)
+            },
+        );
+        assert!(result.is_err());
+        assert!(state.values.iter().all(|v| v.is_none()));
+        let result = state.run_in_pool(tvec!(tensor1(&[1f32, 2.])), &pool).unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn run_in_pool_from_a_worker_of_the_same_pool() {
+        let model = wide_model().unwrap();
+        let plan = SimplePlan::new(&model).unwrap();
+        let expected = plan.run(tvec!(tensor1(&[1f32, 2.]))).unwrap();
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let mut state = SimpleState::new(&plan).unwrap();
+        let result = state
+            .run_in_pool_with_eval(
+                tvec!(tensor1(&[1f32, 2.])),
+                &pool,
+                |session_state, op_state, node, inputs| {
+                    if node.name == "m3" {
+                        // a nested plan, as a Scan or Loop body would run
+                        let mut nested = SimpleState::new(&plan)?;
+                        let found = nested.run_in_pool(tvec!(tensor1(&[1f32, 2.])), &pool)?;
+                        assert_eq!(found, expected);
+                    }
+                    eval(session_state, op_state, node, inputs)
+                },
+            )
+            .unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn run_with_two_symbolic_dims() {
+        let mut model = TypedModel::default();
+        let shape = tvec!(TDim::sym('N'), TDim::sym('T'), 2.to_dim());
+        let fact = TypedFact::dt_shape(f32::datum_type(), &*shape).unwrap();
+        let x = model.add_source("x", fact).unwrap();
+        let c = model.add_const("c", rctensor3(&[[[1f32, 2.]]])).unwrap();
+        let y = model.wire_node("y", math::add::bin_typed(), &[x, c]).unwrap()[0];
+        model.set_output_outlets(&[y]).unwrap();
+        assert_eq!(model.outlet_fact(y).unwrap().shape.to_tvec(), shape);
+        let plan = SimplePlan::new(model.declutter().unwrap()).unwrap();
+        for &(n, t) in &[(1, 3), (2, 5)] {
+            let result = plan.run(tvec!(Tensor::zero::<f32>(&[n, t, 2]).unwrap())).unwrap();
+            assert_eq!(result[0].shape(), &[n, t, 2]);
+        }
+    }
+}
@@ -60,10 +60,22 @@ impl PulsedFact {
     pub fn from_tensor_fact_pulse(tf: &TypedFact, pulse: usize) -> TractResult<PulsedFact> {
         let datum_type = tf.datum_type;
         let stream =
-            tf.shape.stream_info.as_ref().ok_or("Can not pulse a tensor with no streaming dim")?;
-        let shape =
-            tf.shape.iter().map(|d| d.to_integer().map(|d| d as usize).unwrap_or(pulse)).collect();
-        Ok(PulsedFact { datum_type, shape, axis: stream.axis, dim: stream.len.clone(), delay: 0 })
+            tf.shape.stream_info().ok_or("Can not pulse a tensor with no streaming dim")?;
+        let shape = tf
+            .shape
+            .iter()
+            .enumerate()
+            .map(
+                |(ix, d)| {
+                    if ix == stream.axis {
+                        Ok(pulse)
+                    } else {
+                        d.to_integer().map(|d| d as usize)
+                    }
+                },
+            )
+            .collect::<TractResult<_>>()?;
+        Ok(PulsedFact { datum_type, shape, axis: stream.axis, dim: stream.len, delay: 0 })
     }
 
     pub fn pulse(&self) -> usize {
@@ -84,7 +96,7 @@ impl PulsedFact {
 
     pub fn to_streaming_fact(&self) -> TypedFact {
         let mut info = self.to_pulse_fact();
-        info.shape.stream_info = Some(StreamFact { axis: self.axis, len: self.dim.clone() });
+        info.shape.set_dim(self.axis, self.dim.clone()).unwrap();
         info
     }
 }
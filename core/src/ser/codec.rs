@@ -0,0 +1,559 @@
+//! Binary encoding of the graph and tensor sections.
+//!
+//! Everything is little-endian. Integers are stored on 8 bytes, strings and
+//! lists are prefixed by their length. Tensors are collected in a table while
+//! the graph is encoded and are referred to by their index in this table.
+use super::{Attr, Attrs, Registry};
+use crate::internal::*;
+use std::convert::TryFrom;
+use std::io::Write;
+
+const ATTR_BOOL: u8 = 0;
+const ATTR_INT: u8 = 1;
+const ATTR_INTS: u8 = 2;
+const ATTR_FLOAT: u8 = 3;
+const ATTR_STRING: u8 = 4;
+const ATTR_DATUM_TYPE: u8 = 5;
+const ATTR_DIM: u8 = 6;
+const ATTR_DIMS: u8 = 7;
+const ATTR_TENSOR: u8 = 8;
+const ATTR_MODEL: u8 = 9;
+const ATTR_LIST: u8 = 10;
+const ATTR_ATTRS: u8 = 11;
+
+const DIM_SYM: u8 = 0;
+const DIM_VAL: u8 = 1;
+const DIM_ADD: u8 = 2;
+const DIM_MUL: u8 = 3;
+const DIM_DIV: u8 = 4;
+
+const DATUM_TYPES: &[DatumType] = &[
+    DatumType::Bool,
+    DatumType::U8,
+    DatumType::U16,
+    DatumType::U32,
+    DatumType::U64,
+    DatumType::I8,
+    DatumType::I16,
+    DatumType::I32,
+    DatumType::I64,
+    DatumType::F16,
+    DatumType::F32,
+    DatumType::F64,
+    DatumType::TDim,
+    DatumType::Blob,
+    DatumType::String,
+];
+
+fn datum_type_code(dt: DatumType) -> u8 {
+    DATUM_TYPES.iter().position(|d| *d == dt).unwrap() as u8
+}
+
+pub struct Encoder<'r> {
+    registry: &'r Registry,
+    buf: Vec<u8>,
+    tensors: Vec<Arc<Tensor>>,
+    tensor_ids: HashMap<*const Tensor, usize>,
+}
+
+impl<'r> Encoder<'r> {
+    pub fn new(registry: &'r Registry) -> Encoder<'r> {
+        Encoder { registry, buf: vec![], tensors: vec![], tensor_ids: HashMap::new() }
+    }
+
+    pub fn finish(self) -> (Vec<u8>, Vec<Arc<Tensor>>) {
+        (self.buf, self.tensors)
+    }
+
+    fn u8(&mut self, v: u8) {
+        self.buf.push(v)
+    }
+
+    fn u64(&mut self, v: u64) {
+        self.buf.extend_from_slice(&v.to_le_bytes())
+    }
+
+    fn i64(&mut self, v: i64) {
+        self.buf.extend_from_slice(&v.to_le_bytes())
+    }
+
+    fn len(&mut self, v: usize) {
+        self.u64(v as u64)
+    }
+
+    fn str(&mut self, s: &str) {
+        self.len(s.len());
+        self.buf.extend_from_slice(s.as_bytes())
+    }
+
+    fn dim(&mut self, d: &TDim) {
+        encode_dim(&mut self.buf, d)
+    }
+
+    fn tensor(&mut self, t: &Arc<Tensor>) {
+        let key = &**t as *const Tensor;
+        let id = if let Some(id) = self.tensor_ids.get(&key) {
+            *id
+        } else {
+            self.tensors.push(t.clone());
+            self.tensor_ids.insert(key, self.tensors.len() - 1);
+            self.tensors.len() - 1
+        };
+        self.len(id)
+    }
+
+    fn outlet(&mut self, outlet: OutletId) {
+        self.len(outlet.node);
+        self.len(outlet.slot);
+    }
+
+    fn fact(&mut self, fact: &TypedFact) {
+        self.u8(datum_type_code(fact.datum_type));
+        self.len(fact.shape.rank());
+        for d in fact.shape.iter() {
+            self.dim(&d);
+        }
+        if let Some(k) = &fact.konst {
+            self.u8(1);
+            self.tensor(k);
+        } else {
+            self.u8(0);
+        }
+    }
+
+    fn attr(&mut self, attr: &Attr) -> TractResult<()> {
+        match attr {
+            Attr::Bool(b) => {
+                self.u8(ATTR_BOOL);
+                self.u8(*b as u8);
+            }
+            Attr::Int(i) => {
+                self.u8(ATTR_INT);
+                self.i64(*i);
+            }
+            Attr::Ints(is) => {
+                self.u8(ATTR_INTS);
+                self.len(is.len());
+                is.iter().for_each(|i| self.i64(*i));
+            }
+            Attr::Float(f) => {
+                self.u8(ATTR_FLOAT);
+                self.buf.extend_from_slice(&f.to_bits().to_le_bytes());
+            }
+            Attr::String(s) => {
+                self.u8(ATTR_STRING);
+                self.str(s);
+            }
+            Attr::DatumType(dt) => {
+                self.u8(ATTR_DATUM_TYPE);
+                self.u8(datum_type_code(*dt));
+            }
+            Attr::Dim(d) => {
+                self.u8(ATTR_DIM);
+                self.dim(d);
+            }
+            Attr::Dims(ds) => {
+                self.u8(ATTR_DIMS);
+                self.len(ds.len());
+                ds.iter().for_each(|d| self.dim(d));
+            }
+            Attr::Tensor(t) => {
+                self.u8(ATTR_TENSOR);
+                self.tensor(t);
+            }
+            Attr::Model(m) => {
+                self.u8(ATTR_MODEL);
+                self.model(m)?;
+            }
+            Attr::List(l) => {
+                self.u8(ATTR_LIST);
+                self.len(l.len());
+                for a in l {
+                    self.attr(a)?;
+                }
+            }
+            Attr::Attrs(a) => {
+                self.u8(ATTR_ATTRS);
+                self.attrs(a)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn attrs(&mut self, attrs: &Attrs) -> TractResult<()> {
+        self.len(attrs.0.len());
+        for (name, attr) in &attrs.0 {
+            self.str(name);
+            self.attr(attr)?;
+        }
+        Ok(())
+    }
+
+    pub fn model(&mut self, model: &TypedModel) -> TractResult<()> {
+        self.len(model.nodes().len());
+        for node in model.nodes() {
+            let (op_name, attrs) = self
+                .registry
+                .dump_op(node.op.as_ref())
+                .chain_err(|| format!("Serializing node {}", node))?;
+            self.str(&node.name);
+            self.str(&op_name);
+            self.attrs(&attrs)?;
+            self.len(node.inputs.len());
+            for i in &node.inputs {
+                self.outlet(*i);
+            }
+            self.len(node.outputs.len());
+            for o in &node.outputs {
+                self.fact(&o.fact);
+            }
+        }
+        for outlets in &[model.input_outlets()?, model.output_outlets()?] {
+            self.len(outlets.len());
+            for o in outlets.iter() {
+                self.outlet(*o);
+            }
+        }
+        let mut labels: Vec<_> = model.outlet_labels.iter().collect();
+        labels.sort();
+        self.len(labels.len());
+        for (outlet, label) in labels {
+            self.outlet(*outlet);
+            self.str(label);
+        }
+        Ok(())
+    }
+}
+
+fn encode_dim(buf: &mut Vec<u8>, d: &TDim) {
+    match d {
+        TDim::Sym(s) => {
+            buf.push(DIM_SYM);
+            buf.extend_from_slice(&(s.as_str().len() as u64).to_le_bytes());
+            buf.extend_from_slice(s.as_str().as_bytes());
+        }
+        TDim::Val(v) => {
+            buf.push(DIM_VAL);
+            buf.extend_from_slice(&(*v as i64).to_le_bytes());
+        }
+        TDim::Add(terms) => {
+            buf.push(DIM_ADD);
+            buf.extend_from_slice(&(terms.len() as u64).to_le_bytes());
+            terms.iter().for_each(|t| encode_dim(buf, t));
+        }
+        TDim::Mul(p, a) => {
+            buf.push(DIM_MUL);
+            buf.extend_from_slice(&(*p as i64).to_le_bytes());
+            encode_dim(buf, a);
+        }
+        TDim::Div(a, q) => {
+            buf.push(DIM_DIV);
+            buf.extend_from_slice(&(*q as u64).to_le_bytes());
+            encode_dim(buf, a);
+        }
+    }
+}
+
+pub fn write_tensors<W: Write>(w: &mut W, tensors: &[Arc<Tensor>]) -> TractResult<()> {
+    w.write_all(&(tensors.len() as u64).to_le_bytes())?;
+    for t in tensors {
+        let mut buf = vec![datum_type_code(t.datum_type())];
+        buf.extend_from_slice(&(t.rank() as u64).to_le_bytes());
+        for d in t.shape() {
+            buf.extend_from_slice(&(*d as u64).to_le_bytes());
+        }
+        match t.datum_type() {
+            DatumType::String => {
+                for s in t.as_slice::<String>()? {
+                    buf.extend_from_slice(&(s.len() as u64).to_le_bytes());
+                    buf.extend_from_slice(s.as_bytes());
+                }
+            }
+            DatumType::Blob => {
+                for b in t.as_slice::<Blob>()? {
+                    buf.extend_from_slice(&(b.len() as u64).to_le_bytes());
+                    buf.extend_from_slice(b);
+                }
+            }
+            DatumType::TDim => {
+                for d in t.as_slice::<TDim>()? {
+                    encode_dim(&mut buf, d);
+                }
+            }
+            dt => {
+                if cfg!(target_endian = "big") && dt.size_of() > 1 {
+                    bail!("Serializing tensors is only supported on little-endian platforms")
+                }
+                let len = t.len() * dt.size_of();
+                buf.extend_from_slice(&(len as u64).to_le_bytes());
+                if len > 0 {
+                    let bytes = unsafe {
+                        std::slice::from_raw_parts(t.as_slice_unchecked::<u8>().as_ptr(), len)
+                    };
+                    buf.extend_from_slice(bytes);
+                }
+            }
+        }
+        w.write_all(&buf)?;
+    }
+    Ok(())
+}
+
+pub struct Decoder<'r, 'b> {
+    registry: &'r Registry,
+    buf: &'b [u8],
+    pos: usize,
+    tensors: Vec<Arc<Tensor>>,
+}
+
+impl<'r, 'b> Decoder<'r, 'b> {
+    pub fn new(registry: &'r Registry, buf: &'b [u8]) -> Decoder<'r, 'b> {
+        Decoder { registry, buf, pos: 0, tensors: vec![] }
+    }
+
+    fn bytes(&mut self, len: usize) -> TractResult<&'b [u8]> {
+        let end = match self.pos.checked_add(len) {
+            Some(end) if end <= self.buf.len() => end,
+            _ => bail!("Unexpected end of file"),
+        };
+        let slice = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> TractResult<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u64(&mut self) -> TractResult<u64> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.bytes(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn i64(&mut self) -> TractResult<i64> {
+        Ok(self.u64()? as i64)
+    }
+
+    fn i32(&mut self) -> TractResult<i32> {
+        let v = self.i64()?;
+        Ok(i32::try_from(v).map_err(|_| format!("Invalid dimension value {}", v))?)
+    }
+
+    fn len(&mut self) -> TractResult<usize> {
+        let len = self.u64()?;
+        Ok(usize::try_from(len).map_err(|_| format!("Invalid length {}", len))?)
+    }
+
+    /// A number of items about to be read. Each of them takes at least one
+    /// byte, so it can not exceed what is left of the input.
+    fn count(&mut self) -> TractResult<usize> {
+        let count = self.len()?;
+        self.check_count(count)?;
+        Ok(count)
+    }
+
+    fn check_count(&self, count: usize) -> TractResult<()> {
+        if count > self.buf.len() - self.pos {
+            bail!("Invalid item count {}: unexpected end of file", count)
+        }
+        Ok(())
+    }
+
+    fn str(&mut self) -> TractResult<String> {
+        let len = self.len()?;
+        Ok(std::str::from_utf8(self.bytes(len)?)?.to_string())
+    }
+
+    fn datum_type(&mut self) -> TractResult<DatumType> {
+        let code = self.u8()? as usize;
+        DATUM_TYPES.get(code).cloned().ok_or_else(|| format!("Invalid datum type {}", code).into())
+    }
+
+    fn dim(&mut self) -> TractResult<TDim> {
+        Ok(match self.u8()? {
+            DIM_SYM => TDim::sym(self.str()?),
+            DIM_VAL => TDim::Val(self.i32()?),
+            DIM_ADD => {
+                let len = self.count()?;
+                TDim::Add((0..len).map(|_| self.dim()).collect::<TractResult<_>>()?)
+            }
+            DIM_MUL => {
+                let p = self.i32()?;
+                TDim::Mul(p, Box::new(self.dim()?))
+            }
+            DIM_DIV => {
+                let q = self.u64()? as u32;
+                TDim::Div(Box::new(self.dim()?), q)
+            }
+            code => bail!("Invalid dimension code {}", code),
+        })
+    }
+
+    fn tensor(&mut self) -> TractResult<Arc<Tensor>> {
+        let id = self.len()?;
+        self.tensors.get(id).cloned().ok_or_else(|| format!("Invalid tensor id {}", id).into())
+    }
+
+    pub fn tensors(&mut self) -> TractResult<()> {
+        let count = self.count()?;
+        for _ in 0..count {
+            let dt = self.datum_type()?;
+            let rank = self.count()?;
+            let shape = (0..rank).map(|_| self.len()).collect::<TractResult<Vec<usize>>>()?;
+            let len = shape
+                .iter()
+                .try_fold(1usize, |acc, d| acc.checked_mul(*d))
+                .ok_or("Invalid tensor shape")?;
+            if dt == DatumType::String || dt == DatumType::Blob || dt == DatumType::TDim {
+                self.check_count(len)?;
+            }
+            let tensor = match dt {
+                DatumType::String => {
+                    let values = (0..len).map(|_| self.str()).collect::<TractResult<Vec<_>>>()?;
+                    tract_ndarray::ArrayD::from_shape_vec(&*shape, values)?.into_tensor()
+                }
+                DatumType::Blob => {
+                    let values = (0..len)
+                        .map(|_| {
+                            let len = self.len()?;
+                            Ok(Blob(self.bytes(len)?.to_vec()))
+                        })
+                        .collect::<TractResult<Vec<_>>>()?;
+                    tract_ndarray::ArrayD::from_shape_vec(&*shape, values)?.into_tensor()
+                }
+                DatumType::TDim => {
+                    let values = (0..len).map(|_| self.dim()).collect::<TractResult<Vec<_>>>()?;
+                    tract_ndarray::ArrayD::from_shape_vec(&*shape, values)?.into_tensor()
+                }
+                dt => {
+                    if cfg!(target_endian = "big") && dt.size_of() > 1 {
+                        bail!("Loading tensors is only supported on little-endian platforms")
+                    }
+                    let bytes_len = self.len()?;
+                    if Some(bytes_len) != len.checked_mul(dt.size_of()) {
+                        bail!("Inconsistent tensor size")
+                    }
+                    let bytes = self.bytes(bytes_len)?;
+                    if bytes_len == 0 {
+                        unsafe { Tensor::uninitialized_dt(dt, &shape)? }
+                    } else {
+                        unsafe { Tensor::from_raw_dt(dt, &shape, bytes)? }
+                    }
+                }
+            };
+            self.tensors.push(tensor.into_arc_tensor());
+        }
+        Ok(())
+    }
+
+    fn outlet(&mut self) -> TractResult<OutletId> {
+        Ok(OutletId::new(self.len()?, self.len()?))
+    }
+
+    fn outlets(&mut self) -> TractResult<Vec<OutletId>> {
+        let len = self.count()?;
+        (0..len).map(|_| self.outlet()).collect()
+    }
+
+    fn fact(&mut self) -> TractResult<TypedFact> {
+        let datum_type = self.datum_type()?;
+        let rank = self.count()?;
+        let dims = (0..rank).map(|_| self.dim()).collect::<TractResult<TVec<_>>>()?;
+        let konst = if self.u8()? != 0 { Some(self.tensor()?) } else { None };
+        Ok(TypedFact { datum_type, shape: ShapeFact::from_dims(dims)?, konst })
+    }
+
+    fn attr(&mut self) -> TractResult<Attr> {
+        Ok(match self.u8()? {
+            ATTR_BOOL => Attr::Bool(self.u8()? != 0),
+            ATTR_INT => Attr::Int(self.i64()?),
+            ATTR_INTS => {
+                let len = self.count()?;
+                Attr::Ints((0..len).map(|_| self.i64()).collect::<TractResult<_>>()?)
+            }
+            ATTR_FLOAT => {
+                let mut bytes = [0u8; 4];
+                bytes.copy_from_slice(self.bytes(4)?);
+                Attr::Float(f32::from_bits(u32::from_le_bytes(bytes)))
+            }
+            ATTR_STRING => Attr::String(self.str()?),
+            ATTR_DATUM_TYPE => Attr::DatumType(self.datum_type()?),
+            ATTR_DIM => Attr::Dim(self.dim()?),
+            ATTR_DIMS => {
+                let len = self.count()?;
+                Attr::Dims((0..len).map(|_| self.dim()).collect::<TractResult<_>>()?)
+            }
+            ATTR_TENSOR => Attr::Tensor(self.tensor()?),
+            ATTR_MODEL => Attr::Model(self.model()?),
+            ATTR_LIST => {
+                let len = self.count()?;
+                Attr::List((0..len).map(|_| self.attr()).collect::<TractResult<_>>()?)
+            }
+            ATTR_ATTRS => Attr::Attrs(self.attrs()?),
+            code => bail!("Invalid attribute code {}", code),
+        })
+    }
+
+    fn attrs(&mut self) -> TractResult<Attrs> {
+        let len = self.count()?;
+        let attrs = (0..len)
+            .map(|_| Ok((self.str()?, self.attr()?)))
+            .collect::<TractResult<Vec<(String, Attr)>>>()?;
+        Ok(Attrs(attrs))
+    }
+
+    pub fn model(&mut self) -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let node_count = self.count()?;
+        let mut edges = vec![];
+        for id in 0..node_count {
+            let name = self.str()?;
+            let op_name = self.str()?;
+            let attrs = self.attrs()?;
+            let op = self
+                .registry
+                .load_op(&op_name, &attrs)
+                .chain_err(|| format!("Loading node {}", name))?;
+            let inputs = self.outlets()?;
+            let output_count = self.count()?;
+            let facts = (0..output_count).map(|_| self.fact()).collect::<TractResult<TVec<_>>>()?;
+            model.add_node(name, op, facts)?;
+            for (ix, outlet) in inputs.into_iter().enumerate() {
+                edges.push((outlet, InletId::new(id, ix)));
+            }
+        }
+        for (outlet, inlet) in edges {
+            check_outlet(&model, outlet)?;
+            model.add_edge(outlet, inlet)?;
+        }
+        let inputs = self.outlets()?;
+        for &input in &inputs {
+            check_outlet(&model, input)?;
+            if !model.node(input.node).op_is::<crate::ops::source::TypedSource>() {
+                bail!("Invalid model input {:?}: not a source", input)
+            }
+        }
+        model.inputs = inputs;
+        let outputs = self.outlets()?;
+        for &output in &outputs {
+            check_outlet(&model, output)?;
+        }
+        model.outputs = outputs;
+        let label_count = self.count()?;
+        for _ in 0..label_count {
+            let outlet = self.outlet()?;
+            check_outlet(&model, outlet)?;
+            let label = self.str()?;
+            model.set_outlet_label(outlet, label)?;
+        }
+        Ok(model)
+    }
+}
+
+fn check_outlet(model: &TypedModel, outlet: OutletId) -> TractResult<()> {
+    if model.nodes().get(outlet.node).map(|n| outlet.slot < n.outputs.len()) != Some(true) {
+        bail!("Invalid outlet {:?}", outlet)
+    }
+    Ok(())
+}
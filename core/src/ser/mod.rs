@@ -0,0 +1,380 @@
+//! Native on-disk format for TypedModel.
+//!
+//! This allows to save a decluttered `TypedModel` and reload it later without
+//! going through a framework (ONNX, TensorFlow, ...) parser, type inference
+//! and decluttering again. Ops and facts are stored as they are, so the
+//! reloaded model is the same as the one that was saved.
+//!
+//! A file is made of a header, a table of tensors (all the weights and
+//! constants, each of them stored only once), and a graph section. Each node
+//! of the graph is stored with its name, its inputs, its output facts, and an
+//! op, as an op name and a set of named attributes (see `Attrs`).
+//!
+//! Translation between ops and attributes is performed by a `Registry`.
+//! `registry()` returns a registry knowing about the core decluttered ops.
+//! Crates implementing their own TypedOp can register them in a registry
+//! before saving or loading a model.
+//!
+//! Codegen'd (lir) ops are not supported: the intended workflow is to save a
+//! decluttered model, then call `optimize()` after loading it.
+//!
+//! ```
+//! # extern crate tract_core;
+//! # fn main() {
+//! use tract_core::internal::*;
+//!
+//! let mut model = TypedModel::default();
+//! let source = model.add_source("input", TypedFact::dt_shape(f32::datum_type(), [3].as_ref()).unwrap()).unwrap();
+//! let exp = model.wire_node("exp", tract_core::ops::math::exp(), &[source]).unwrap();
+//! model.set_output_outlets(&exp).unwrap();
+//!
+//! let registry = tract_core::ser::registry();
+//! let mut buffer = vec![];
+//! registry.write_model(&model, &mut buffer).unwrap();
+//! let reloaded = registry.read_model(&*buffer).unwrap();
+//! assert_eq!(model.nodes().len(), reloaded.nodes().len());
+//! # }
+//! ```
+use crate::internal::*;
+use crate::ops::binary::BinMiniOp;
+use std::any::TypeId;
+use std::io::{Read, Write};
+use std::path::Path;
+
+mod codec;
+mod ops;
+
+/// Magic bytes at the beginning of every file.
+pub const MAGIC: &[u8; 8] = b"TRACTMDL";
+/// Current format version.
+///
+/// * 1: initial format
+/// * 2: symbolic dimensions are encoded by name instead of as a char
+pub const VERSION: u32 = 2;
+
+/// A serializable op attribute value.
+#[derive(Clone, Debug)]
+pub enum Attr {
+    Bool(bool),
+    Int(i64),
+    Ints(TVec<i64>),
+    Float(f32),
+    String(String),
+    DatumType(DatumType),
+    Dim(TDim),
+    Dims(TVec<TDim>),
+    Tensor(Arc<Tensor>),
+    Model(TypedModel),
+    List(Vec<Attr>),
+    Attrs(Attrs),
+}
+
+macro_rules! attr_from {
+    ($t: ty, $v: ident => $e: expr) => {
+        impl From<$t> for Attr {
+            fn from($v: $t) -> Attr {
+                $e
+            }
+        }
+    };
+}
+
+attr_from!(bool, v => Attr::Bool(v));
+attr_from!(usize, v => Attr::Int(v as i64));
+attr_from!(isize, v => Attr::Int(v as i64));
+attr_from!(i32, v => Attr::Int(v as i64));
+attr_from!(i64, v => Attr::Int(v));
+attr_from!(f32, v => Attr::Float(v));
+attr_from!(String, v => Attr::String(v));
+attr_from!(&str, v => Attr::String(v.to_string()));
+attr_from!(DatumType, v => Attr::DatumType(v));
+attr_from!(TDim, v => Attr::Dim(v));
+attr_from!(Arc<Tensor>, v => Attr::Tensor(v));
+attr_from!(TypedModel, v => Attr::Model(v));
+attr_from!(Vec<Attr>, v => Attr::List(v));
+attr_from!(Attrs, v => Attr::Attrs(v));
+attr_from!(&[usize], v => Attr::Ints(v.iter().map(|&i| i as i64).collect()));
+attr_from!(&[TDim], v => Attr::Dims(v.into()));
+
+impl Attr {
+    pub fn as_bool(&self) -> TractResult<bool> {
+        match self {
+            Attr::Bool(b) => Ok(*b),
+            _ => bail!("Expected a bool, got {:?}", self),
+        }
+    }
+
+    pub fn as_i64(&self) -> TractResult<i64> {
+        match self {
+            Attr::Int(i) => Ok(*i),
+            _ => bail!("Expected an integer, got {:?}", self),
+        }
+    }
+
+    pub fn as_usize(&self) -> TractResult<usize> {
+        let i = self.as_i64()?;
+        if i < 0 {
+            bail!("Expected a positive integer, got {}", i)
+        }
+        Ok(i as usize)
+    }
+
+    pub fn as_i64s(&self) -> TractResult<&[i64]> {
+        match self {
+            Attr::Ints(i) => Ok(i),
+            _ => bail!("Expected a list of integers, got {:?}", self),
+        }
+    }
+
+    pub fn as_usizes(&self) -> TractResult<TVec<usize>> {
+        self.as_i64s()?
+            .iter()
+            .map(|&i| {
+                if i < 0 {
+                    bail!("Expected a positive integer, got {}", i)
+                }
+                Ok(i as usize)
+            })
+            .collect()
+    }
+
+    pub fn as_f32(&self) -> TractResult<f32> {
+        match self {
+            Attr::Float(f) => Ok(*f),
+            _ => bail!("Expected a float, got {:?}", self),
+        }
+    }
+
+    pub fn as_str(&self) -> TractResult<&str> {
+        match self {
+            Attr::String(s) => Ok(s),
+            _ => bail!("Expected a string, got {:?}", self),
+        }
+    }
+
+    pub fn as_datum_type(&self) -> TractResult<DatumType> {
+        match self {
+            Attr::DatumType(dt) => Ok(*dt),
+            _ => bail!("Expected a datum type, got {:?}", self),
+        }
+    }
+
+    pub fn as_dim(&self) -> TractResult<&TDim> {
+        match self {
+            Attr::Dim(d) => Ok(d),
+            _ => bail!("Expected a dimension, got {:?}", self),
+        }
+    }
+
+    pub fn as_dims(&self) -> TractResult<&[TDim]> {
+        match self {
+            Attr::Dims(d) => Ok(d),
+            _ => bail!("Expected a list of dimensions, got {:?}", self),
+        }
+    }
+
+    pub fn as_tensor(&self) -> TractResult<&Arc<Tensor>> {
+        match self {
+            Attr::Tensor(t) => Ok(t),
+            _ => bail!("Expected a tensor, got {:?}", self),
+        }
+    }
+
+    pub fn as_model(&self) -> TractResult<&TypedModel> {
+        match self {
+            Attr::Model(m) => Ok(m),
+            _ => bail!("Expected a model, got {:?}", self),
+        }
+    }
+
+    pub fn as_list(&self) -> TractResult<&[Attr]> {
+        match self {
+            Attr::List(l) => Ok(l),
+            _ => bail!("Expected a list, got {:?}", self),
+        }
+    }
+
+    pub fn as_attrs(&self) -> TractResult<&Attrs> {
+        match self {
+            Attr::Attrs(a) => Ok(a),
+            _ => bail!("Expected an attribute set, got {:?}", self),
+        }
+    }
+}
+
+/// Ordered set of named attributes describing an op.
+#[derive(Clone, Debug, Default)]
+pub struct Attrs(pub Vec<(String, Attr)>);
+
+impl Attrs {
+    pub fn with(mut self, name: &str, attr: impl Into<Attr>) -> Attrs {
+        self.0.push((name.to_string(), attr.into()));
+        self
+    }
+
+    pub fn with_opt<A: Into<Attr>>(self, name: &str, attr: Option<A>) -> Attrs {
+        if let Some(attr) = attr {
+            self.with(name, attr)
+        } else {
+            self
+        }
+    }
+
+    pub fn get_opt(&self, name: &str) -> Option<&Attr> {
+        self.0.iter().find(|(n, _)| n == name).map(|(_, a)| a)
+    }
+
+    pub fn get(&self, name: &str) -> TractResult<&Attr> {
+        self.get_opt(name).ok_or_else(|| format!("Missing attribute {}", name).into())
+    }
+}
+
+type OpDumper = Box<dyn Fn(&Registry, &dyn TypedOp) -> TractResult<Attrs> + Send + Sync>;
+type OpLoader = fn(&Registry, &Attrs) -> TractResult<Box<dyn TypedOp>>;
+type ElementWiseDumper = Box<dyn Fn(&dyn ElementWiseMiniOp) -> TractResult<Attrs> + Send + Sync>;
+type ElementWiseLoader = fn(&Attrs) -> TractResult<Box<dyn ElementWiseMiniOp>>;
+
+/// Translates ops to and from their serialized form.
+#[derive(Default)]
+pub struct Registry {
+    op_dumpers: HashMap<TypeId, (String, OpDumper)>,
+    op_loaders: HashMap<String, OpLoader>,
+    bin_mini_ops: HashMap<String, fn() -> Box<dyn BinMiniOp>>,
+    element_wise_dumpers: HashMap<TypeId, (String, ElementWiseDumper)>,
+    element_wise_loaders: HashMap<String, ElementWiseLoader>,
+}
+
+impl Registry {
+    /// Register an op type, under a name that must be unique in the registry.
+    pub fn register_op<O: TypedOp>(
+        &mut self,
+        name: &str,
+        dump: fn(&Registry, &O) -> TractResult<Attrs>,
+        load: OpLoader,
+    ) {
+        let dumper: OpDumper =
+            Box::new(move |registry, op| dump(registry, op.as_op().downcast_ref::<O>().unwrap()));
+        self.op_dumpers.insert(TypeId::of::<O>(), (name.to_string(), dumper));
+        self.op_loaders.insert(name.to_string(), load);
+    }
+
+    /// Register a binary mini op (as found in TypedBinOp, UnaryOp, ...).
+    pub fn register_bin_mini_op(&mut self, name: &str, ctor: fn() -> Box<dyn BinMiniOp>) {
+        self.bin_mini_ops.insert(name.to_string(), ctor);
+    }
+
+    /// Register an element wise mini op (as found in ElementWiseOp).
+    pub fn register_element_wise<M: ElementWiseMiniOp>(
+        &mut self,
+        name: &str,
+        dump: fn(&M) -> TractResult<Attrs>,
+        load: ElementWiseLoader,
+    ) {
+        let dumper: ElementWiseDumper =
+            Box::new(move |op| dump(op.as_any().downcast_ref::<M>().unwrap()));
+        self.element_wise_dumpers.insert(TypeId::of::<M>(), (name.to_string(), dumper));
+        self.element_wise_loaders.insert(name.to_string(), load);
+    }
+
+    pub fn dump_op(&self, op: &dyn TypedOp) -> TractResult<(String, Attrs)> {
+        let (name, dumper) = self
+            .op_dumpers
+            .get(&op.as_op().as_any().type_id())
+            .ok_or_else(|| format!("No serializer registered for {:?}", op))?;
+        Ok((name.clone(), dumper(self, op)?))
+    }
+
+    pub fn load_op(&self, name: &str, attrs: &Attrs) -> TractResult<Box<dyn TypedOp>> {
+        let loader = self
+            .op_loaders
+            .get(name)
+            .ok_or_else(|| format!("No loader registered for {}", name))?;
+        loader(self, attrs).chain_err(|| format!("Loading {} op", name))
+    }
+
+    pub fn dump_bin_mini_op(&self, op: &dyn BinMiniOp) -> TractResult<Attr> {
+        if !self.bin_mini_ops.contains_key(op.name()) {
+            bail!("No serializer registered for {:?}", op)
+        }
+        Ok(op.name().into())
+    }
+
+    pub fn load_bin_mini_op(&self, attr: &Attr) -> TractResult<Box<dyn BinMiniOp>> {
+        let name = attr.as_str()?;
+        let ctor = self
+            .bin_mini_ops
+            .get(name)
+            .ok_or_else(|| format!("No loader registered for {}", name))?;
+        Ok(ctor())
+    }
+
+    pub fn dump_element_wise(&self, op: &dyn ElementWiseMiniOp) -> TractResult<Attr> {
+        let (name, dumper) = self
+            .element_wise_dumpers
+            .get(&op.as_any().type_id())
+            .ok_or_else(|| format!("No serializer registered for {:?}", op))?;
+        Ok(Attrs::default().with("name", &**name).with("attrs", dumper(op)?).into())
+    }
+
+    pub fn load_element_wise(&self, attr: &Attr) -> TractResult<Box<dyn ElementWiseMiniOp>> {
+        let attr = attr.as_attrs()?;
+        let name = attr.get("name")?.as_str()?;
+        let loader = self
+            .element_wise_loaders
+            .get(name)
+            .ok_or_else(|| format!("No loader registered for {}", name))?;
+        loader(attr.get("attrs")?.as_attrs()?)
+    }
+
+    /// Serialize a model to a writer.
+    pub fn write_model<W: Write>(&self, model: &TypedModel, mut w: W) -> TractResult<()> {
+        let mut encoder = codec::Encoder::new(self);
+        encoder.model(model)?;
+        let (graph, tensors) = encoder.finish();
+        w.write_all(MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        codec::write_tensors(&mut w, &tensors)?;
+        w.write_all(&graph)?;
+        Ok(())
+    }
+
+    /// Deserialize a model from a reader.
+    pub fn read_model<R: Read>(&self, mut r: R) -> TractResult<TypedModel> {
+        let mut buffer = vec![];
+        r.read_to_end(&mut buffer)?;
+        if buffer.len() < 12 || &buffer[0..8] != MAGIC {
+            bail!("Not a tract model file")
+        }
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&buffer[8..12]);
+        let version = u32::from_le_bytes(version);
+        if version != VERSION {
+            bail!("Unsupported tract model file version {} (expected {})", version, VERSION)
+        }
+        let mut decoder = codec::Decoder::new(self, &buffer[12..]);
+        decoder.tensors()?;
+        decoder.model()
+    }
+
+    /// Save a model to a file. Nothing is written if the model can not be
+    /// serialized.
+    pub fn save_model(&self, model: &TypedModel, path: impl AsRef<Path>) -> TractResult<()> {
+        let mut buffer = vec![];
+        self.write_model(model, &mut buffer)?;
+        std::fs::write(path, buffer)?;
+        Ok(())
+    }
+
+    /// Load a model from a file.
+    pub fn load_model(&self, path: impl AsRef<Path>) -> TractResult<TypedModel> {
+        let file = std::fs::File::open(path)?;
+        self.read_model(file)
+    }
+}
+
+/// A registry knowing about all the core decluttered ops.
+pub fn registry() -> Registry {
+    let mut registry = Registry::default();
+    ops::register_core_ops(&mut registry);
+    registry
+}
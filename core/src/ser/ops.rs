@@ -0,0 +1,1117 @@
+use super::{Attr, Attrs, Registry};
+use crate::ops;
+use crate::ops::array::*;
+use crate::ops::binary::{MergeOp, MergeOpUnicast, TypedBinOp, UnaryOp};
+use crate::ops::cnn::*;
+use crate::ops::control_flow::{If, Loop};
+use crate::ops::einsum::{Einsum, Expr};
+use crate::ops::element_wise::ElementWiseOp;
+use crate::ops::matmul::{MatMul, MatMulUnary};
+use crate::ops::nn::*;
+use crate::ops::quant::*;
+use crate::ops::resize::*;
+use crate::ops::scan::*;
+use crate::ops::source::TypedSource;
+
+macro_rules! bin_mini_ops {
+    ($registry: expr, $module: ident, $($op: ident),*) => {
+        $( $registry.register_bin_mini_op(stringify!($op), || Box::new(ops::$module::$op)); )*
+    }
+}
+
+macro_rules! element_wise_mini_ops {
+    ($registry: expr, $module: ident, $($op: ident),*) => {
+        $(
+            $registry.register_element_wise::<ops::$module::$op>(
+                stringify!($op),
+                |_| Ok(Attrs::default()),
+                |_| Ok(Box::new(ops::$module::$op {}))
+            );
+        )*
+    }
+}
+
+pub fn register_core_ops(reg: &mut Registry) {
+    bin_mini_ops!(reg, math, Add, Sub, Mul, Div, Rem, Min, Max, Pow, FlippedPow);
+    bin_mini_ops!(reg, math, ShiftLeft, ShiftRight, FlippedShiftLeft, FlippedShiftRight);
+    bin_mini_ops!(reg, logic, And, Or, Xor, Equals, Lesser, LesserEqual, Greater, GreaterEqual);
+
+    element_wise_mini_ops!(reg, math, Abs, Exp, Ln, Square, Sqrt, Recip, Rsqrt);
+    element_wise_mini_ops!(reg, math, Ceil, Floor, Round, RoundHalfToEven, Neg, Sign);
+    element_wise_mini_ops!(reg, math, Cos, Sin, Tan, Acos, Asin, Atan);
+    element_wise_mini_ops!(reg, math, Cosh, Sinh, Tanh, Acosh, Asinh, Atanh);
+    element_wise_mini_ops!(reg, logic, Not);
+    element_wise_mini_ops!(reg, nn, Sigmoid);
+    reg.register_element_wise::<ops::cast::Cast>(
+        "Cast",
+        |op| Ok(Attrs::default().with("to", op.to)),
+        |attrs| Ok(Box::new(ops::cast::Cast::new(attrs.get("to")?.as_datum_type()?))),
+    );
+    reg.register_element_wise::<QuantizeLinearU8>(
+        "QuantizeLinearU8",
+        |op| Ok(Attrs::default().with("scale", op.scale).with("zero_point", op.zero_point as i64)),
+        |attrs| {
+            Ok(Box::new(QuantizeLinearU8 {
+                scale: attrs.get("scale")?.as_f32()?,
+                zero_point: attrs.get("zero_point")?.as_i64()? as u8,
+            }))
+        },
+    );
+    reg.register_element_wise::<QuantizeLinearI8>(
+        "QuantizeLinearI8",
+        |op| Ok(Attrs::default().with("scale", op.scale).with("zero_point", op.zero_point as i64)),
+        |attrs| {
+            Ok(Box::new(QuantizeLinearI8 {
+                scale: attrs.get("scale")?.as_f32()?,
+                zero_point: attrs.get("zero_point")?.as_i64()? as i8,
+            }))
+        },
+    );
+    reg.register_element_wise::<LookupTable>(
+        "LookupTable",
+        |op| Ok(Attrs::default().with("table", rctensor1(op.table.table()))),
+        |attrs| {
+            let table = attrs.get("table")?.as_tensor()?.as_slice::<u8>()?;
+            if table.len() != 256 {
+                bail!("Invalid lookup table length {}", table.len())
+            }
+            Ok(Box::new(LookupTable { table: (tract_linalg::ops().lut_u8)(table) }))
+        },
+    );
+
+    reg.register_op::<TypedSource>(
+        "Source",
+        |_, op| Ok(Attrs::default().with("fact", fact(&op.fact))),
+        |_, attrs| Ok(Box::new(TypedSource::new(load_fact(attrs.get("fact")?)?))),
+    );
+    reg.register_op::<ops::konst::Const>(
+        "Const",
+        |_, op| Ok(Attrs::default().with("value", op.0.clone())),
+        |_, attrs| Ok(Box::new(ops::konst::Const::new(attrs.get("value")?.as_tensor()?.clone()))),
+    );
+    reg.register_op::<ops::identity::Identity>(
+        "Identity",
+        |_, _| Ok(Attrs::default()),
+        |_, _| Ok(Box::new(ops::identity::Identity)),
+    );
+    reg.register_op::<ops::logic::Iff>(
+        "Iff",
+        |_, _| Ok(Attrs::default()),
+        |_, _| Ok(Box::new(ops::logic::Iff)),
+    );
+
+    reg.register_op::<TypedBinOp>(
+        "TypedBinOp",
+        |reg, op| Ok(Attrs::default().with("mini_op", reg.dump_bin_mini_op(&*op.0)?)),
+        |reg, attrs| Ok(Box::new(TypedBinOp(reg.load_bin_mini_op(attrs.get("mini_op")?)?))),
+    );
+    reg.register_op::<UnaryOp>(
+        "UnaryOp",
+        |reg, op| {
+            Ok(Attrs::default()
+                .with("mini_op", reg.dump_bin_mini_op(&*op.mini_op)?)
+                .with("a", op.a.clone()))
+        },
+        |reg, attrs| {
+            Ok(Box::new(UnaryOp::new(
+                reg.load_bin_mini_op(attrs.get("mini_op")?)?,
+                attrs.get("a")?.as_tensor()?.clone(),
+            )))
+        },
+    );
+    reg.register_op::<MergeOp>(
+        "MergeOp",
+        |reg, op| Ok(Attrs::default().with("mini_op", reg.dump_bin_mini_op(&*op.0)?)),
+        |reg, attrs| Ok(Box::new(MergeOp(reg.load_bin_mini_op(attrs.get("mini_op")?)?))),
+    );
+    reg.register_op::<MergeOpUnicast>(
+        "MergeOpUnicast",
+        |reg, op| Ok(Attrs::default().with("mini_op", reg.dump_bin_mini_op(&*op.0)?)),
+        |reg, attrs| Ok(Box::new(MergeOpUnicast(reg.load_bin_mini_op(attrs.get("mini_op")?)?))),
+    );
+    reg.register_op::<ElementWiseOp>(
+        "ElementWiseOp",
+        |reg, op| Ok(Attrs::default().with("mini_op", reg.dump_element_wise(&*op.0)?)),
+        |reg, attrs| Ok(Box::new(ElementWiseOp(reg.load_element_wise(attrs.get("mini_op")?)?))),
+    );
+    reg.register_op::<DequantizeLinearF32>(
+        "DequantizeLinearF32",
+        |_, op| Ok(Attrs::default().with("scale", op.scale).with("zero_point", op.zero_point)),
+        |_, attrs| {
+            Ok(Box::new(DequantizeLinearF32::new(
+                attrs.get("scale")?.as_f32()?,
+                attrs.get("zero_point")?.as_i64()? as i32,
+            )))
+        },
+    );
+
+    reg.register_op::<AxisOp>("AxisOp", dump_axis_op, load_axis_op);
+    reg.register_op::<Slice<TDim>>(
+        "Slice",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("axis", op.axis)
+                .with("start", op.start.clone())
+                .with("end", op.end.clone()))
+        },
+        |_, attrs| {
+            Ok(Box::new(Slice::new(
+                attrs.get("axis")?.as_usize()?,
+                attrs.get("start")?.as_dim()?.clone(),
+                attrs.get("end")?.as_dim()?.clone(),
+            )))
+        },
+    );
+    reg.register_op::<Slice<usize>>(
+        "SliceFinite",
+        |_, op| {
+            Ok(Attrs::default().with("axis", op.axis).with("start", op.start).with("end", op.end))
+        },
+        |_, attrs| {
+            Ok(Box::new(Slice::new(
+                attrs.get("axis")?.as_usize()?,
+                attrs.get("start")?.as_usize()?,
+                attrs.get("end")?.as_usize()?,
+            )))
+        },
+    );
+    reg.register_op::<TypedConcat>(
+        "Concat",
+        |_, op| {
+            let slices = op
+                .slices
+                .iter()
+                .map(|s| match s {
+                    ConcatSlice::Const(t) => Attr::Tensor(t.clone()),
+                    ConcatSlice::Var => Attr::Bool(false),
+                })
+                .collect::<Vec<_>>();
+            Ok(Attrs::default().with("axis", op.axis).with("slices", slices))
+        },
+        |_, attrs| {
+            let slices = attrs
+                .get("slices")?
+                .as_list()?
+                .iter()
+                .map(|s| match s {
+                    Attr::Tensor(t) => ConcatSlice::Const(t.clone()),
+                    _ => ConcatSlice::Var,
+                })
+                .collect();
+            Ok(Box::new(TypedConcat::new(attrs.get("axis")?.as_usize()?, slices)))
+        },
+    );
+    reg.register_op::<Gather>(
+        "Gather",
+        |_, op| Ok(Attrs::default().with("axis", op.axis)),
+        |_, attrs| Ok(Box::new(Gather::new(attrs.get("axis")?.as_usize()?))),
+    );
+    reg.register_op::<GatherNd>(
+        "GatherNd",
+        |_, op| Ok(Attrs::default().with("batch_dims", op.batch_dims)),
+        |_, attrs| Ok(Box::new(GatherNd::new(attrs.get("batch_dims")?.as_usize()?))),
+    );
+    reg.register_op::<GatherElements>(
+        "GatherElements",
+        |_, op| Ok(Attrs::default().with("axis", op.axis)),
+        |_, attrs| Ok(Box::new(GatherElements::new(attrs.get("axis")?.as_usize()?))),
+    );
+    reg.register_op::<ScatterNd>(
+        "ScatterNd",
+        |_, op| Ok(Attrs::default().with("reduction", dump_scatter_reduction(op.reduction))),
+        |_, attrs| Ok(Box::new(ScatterNd::new(load_scatter_reduction(attrs.get("reduction")?)?))),
+    );
+    reg.register_op::<ScatterElements>(
+        "ScatterElements",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("axis", op.axis)
+                .with("reduction", dump_scatter_reduction(op.reduction)))
+        },
+        |_, attrs| {
+            Ok(Box::new(ScatterElements::new(
+                attrs.get("axis")?.as_usize()?,
+                load_scatter_reduction(attrs.get("reduction")?)?,
+            )))
+        },
+    );
+    reg.register_op::<OneHot>(
+        "OneHot",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("axis", op.axis)
+                .with("dim", op.dim)
+                .with("off", op.off.clone())
+                .with("on", op.on.clone()))
+        },
+        |_, attrs| {
+            Ok(Box::new(OneHot::new(
+                attrs.get("axis")?.as_usize()?,
+                attrs.get("dim")?.as_usize()?,
+                attrs.get("off")?.as_tensor()?.clone(),
+                attrs.get("on")?.as_tensor()?.clone(),
+            )))
+        },
+    );
+    reg.register_op::<TopK>(
+        "TopK",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("axis", op.axis)
+                .with("largest", op.largest)
+                .with("fallback_k", op.fallback_k.clone()))
+        },
+        |_, attrs| {
+            Ok(Box::new(TopK::new(
+                attrs.get("axis")?.as_usize()?,
+                attrs.get("largest")?.as_bool()?,
+                attrs.get("fallback_k")?.as_dim()?.clone(),
+            )))
+        },
+    );
+    reg.register_op::<NonZero>(
+        "NonZero",
+        |_, op| Ok(Attrs::default().with("count", op.count.clone())),
+        |_, attrs| Ok(Box::new(NonZero::new(attrs.get("count")?.as_dim()?.clone()))),
+    );
+    reg.register_op::<Unique>(
+        "Unique",
+        |_, op| {
+            Ok(Attrs::default()
+                .with_opt("axis", op.axis)
+                .with("sorted", op.sorted)
+                .with("count", op.count.clone()))
+        },
+        |_, attrs| {
+            Ok(Box::new(Unique::new(
+                attrs.get_opt("axis").map(|a| a.as_usize()).transpose()?,
+                attrs.get("sorted")?.as_bool()?,
+                attrs.get("count")?.as_dim()?.clone(),
+            )))
+        },
+    );
+    reg.register_op::<Tile>(
+        "Tile",
+        |_, op| Ok(Attrs::default().with("multipliers", &*op.multipliers)),
+        |_, attrs| Ok(Box::new(Tile::new(attrs.get("multipliers")?.as_usizes()?))),
+    );
+    reg.register_op::<MultiBroadcastTo>(
+        "MultiBroadcastTo",
+        |_, op| Ok(Attrs::default().with("shape", &*op.shape)),
+        |_, attrs| Ok(Box::new(MultiBroadcastTo::new(attrs.get("shape")?.as_dims()?.into()))),
+    );
+    reg.register_op::<FiniteReshape>(
+        "FiniteReshape",
+        |_, op| Ok(Attrs::default().with("shape", &*op.shape)),
+        |_, attrs| Ok(Box::new(FiniteReshape::new(attrs.get("shape")?.as_usizes()?))),
+    );
+    reg.register_op::<Pad>("Pad", dump_pad, load_pad);
+    reg.register_op::<ops::Downsample>(
+        "Downsample",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("axis", op.axis)
+                .with("stride", op.stride)
+                .with("modulo", op.modulo))
+        },
+        |_, attrs| {
+            Ok(Box::new(ops::Downsample::new(
+                attrs.get("axis")?.as_usize()?,
+                attrs.get("stride")?.as_i64()? as isize,
+                attrs.get("modulo")?.as_usize()?,
+            )))
+        },
+    );
+
+    reg.register_op::<Reduce>(
+        "Reduce",
+        |_, op| {
+            let reducer = match op.reducer {
+                Reducer::Max => "max",
+                Reducer::Min => "min",
+                Reducer::Prod => "prod",
+                Reducer::Sum => "sum",
+            };
+            Ok(Attrs::default().with("axes", &*op.axes).with("reducer", reducer))
+        },
+        |_, attrs| {
+            let reducer = match attrs.get("reducer")?.as_str()? {
+                "max" => Reducer::Max,
+                "min" => Reducer::Min,
+                "prod" => Reducer::Prod,
+                "sum" => Reducer::Sum,
+                r => bail!("Unknown reducer {}", r),
+            };
+            Ok(Box::new(Reduce::new(attrs.get("axes")?.as_usizes()?, reducer)))
+        },
+    );
+    reg.register_op::<ArgMaxMin>(
+        "ArgMaxMin",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("max", op.max)
+                .with("axis", op.axis)
+                .with("keepdims", op.keepdims))
+        },
+        |_, attrs| {
+            Ok(Box::new(ArgMaxMin::new(
+                attrs.get("max")?.as_bool()?,
+                attrs.get("axis")?.as_usize()?,
+                attrs.get("keepdims")?.as_bool()?,
+            )))
+        },
+    );
+
+    reg.register_op::<MeanVarNorm>(
+        "MeanVarNorm",
+        |_, op| Ok(Attrs::default().with("axis", op.axis).with("epsilon", op.epsilon)),
+        |_, attrs| {
+            Ok(Box::new(MeanVarNorm::new(
+                attrs.get("axis")?.as_usize()?,
+                attrs.get("epsilon")?.as_f32()?,
+            )))
+        },
+    );
+    reg.register_op::<Einsum>("Einsum", dump_einsum, load_einsum);
+    reg.register_op::<MatMul>(
+        "MatMul",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("a_trans", op.a_trans)
+                .with("b_trans", op.b_trans)
+                .with("c_trans", op.c_trans)
+                .with_opt("q_params", op.q_params.as_ref().map(dump_q_params)))
+        },
+        |_, attrs| {
+            Ok(Box::new(MatMul {
+                a_trans: attrs.get("a_trans")?.as_bool()?,
+                b_trans: attrs.get("b_trans")?.as_bool()?,
+                c_trans: attrs.get("c_trans")?.as_bool()?,
+                q_params: attrs.get_opt("q_params").map(load_q_params).transpose()?,
+            }))
+        },
+    );
+    reg.register_op::<MatMulUnary>(
+        "MatMulUnary",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("a", op.a.clone())
+                .with("a_trans", op.a_trans)
+                .with("b_trans", op.b_trans)
+                .with("c_trans", op.c_trans)
+                .with_opt("q_params", op.q_params.as_ref().map(dump_q_params)))
+        },
+        |_, attrs| {
+            Ok(Box::new(MatMulUnary::new(
+                attrs.get("a")?.as_tensor()?.clone(),
+                attrs.get("a_trans")?.as_bool()?,
+                attrs.get("b_trans")?.as_bool()?,
+                attrs.get("c_trans")?.as_bool()?,
+                attrs.get_opt("q_params").map(load_q_params).transpose()?,
+            )))
+        },
+    );
+    reg.register_op::<ConvUnary>(
+        "ConvUnary",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("pool_spec", dump_pool_spec(&op.pool_spec))
+                .with("kernel_fmt", dump_kernel_fmt(op.kernel_fmt))
+                .with("kernel", op.kernel.clone())
+                .with("group", op.group)
+                .with_opt("bias", op.bias.clone())
+                .with_opt("q_params", op.q_params.as_ref().map(dump_q_params)))
+        },
+        |_, attrs| {
+            Ok(Box::new(ConvUnary::new(
+                load_pool_spec(attrs.get("pool_spec")?)?,
+                load_kernel_fmt(attrs.get("kernel_fmt")?)?,
+                attrs.get("kernel")?.as_tensor()?.clone(),
+                attrs.get("group")?.as_usize()?,
+                attrs.get_opt("bias").map(|b| b.as_tensor().map(|t| t.clone())).transpose()?,
+                attrs.get_opt("q_params").map(load_q_params).transpose()?,
+            )))
+        },
+    );
+    reg.register_op::<DeconvUnary>(
+        "DeconvUnary",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("pool_spec", dump_pool_spec(&op.pool_spec))
+                .with("kernel_fmt", dump_kernel_fmt(op.kernel_fmt))
+                .with("kernel", op.kernel.clone())
+                .with_opt("bias", op.bias.clone())
+                .with("adjustments", &*op.adjustments)
+                .with("group", op.group))
+        },
+        |_, attrs| {
+            Ok(Box::new(DeconvUnary::new(
+                load_pool_spec(attrs.get("pool_spec")?)?,
+                load_kernel_fmt(attrs.get("kernel_fmt")?)?,
+                attrs.get("kernel")?.as_tensor()?.clone(),
+                attrs.get_opt("bias").map(|b| b.as_tensor().map(|t| t.clone())).transpose()?,
+                attrs.get("adjustments")?.as_usizes()?,
+                attrs.get("group")?.as_usize()?,
+            )))
+        },
+    );
+    reg.register_op::<MaxPool>(
+        "MaxPool",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("pool_spec", dump_pool_spec(&op.pool_spec))
+                .with_opt("with_index_outputs", op.with_index_outputs))
+        },
+        |_, attrs| {
+            Ok(Box::new(MaxPool::new(
+                load_pool_spec(attrs.get("pool_spec")?)?,
+                attrs.get_opt("with_index_outputs").map(|a| a.as_datum_type()).transpose()?,
+            )))
+        },
+    );
+    reg.register_op::<AvgPool>(
+        "AvgPool",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("pool_spec", dump_pool_spec(&op.pool_spec))
+                .with("count_include_pad", op.count_include_pad))
+        },
+        |_, attrs| {
+            Ok(Box::new(AvgPool::new(
+                load_pool_spec(attrs.get("pool_spec")?)?,
+                attrs.get("count_include_pad")?.as_bool()?,
+            )))
+        },
+    );
+    reg.register_op::<Scan>("Scan", dump_scan, load_scan);
+    reg.register_op::<If>(
+        "If",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("then_body", op.then_body.clone())
+                .with("then_input_mapping", &*op.then_input_mapping)
+                .with("else_body", op.else_body.clone())
+                .with("else_input_mapping", &*op.else_input_mapping)
+                .with("symbol_prefix", &*op.symbol_prefix))
+        },
+        |_, attrs| {
+            Ok(Box::new(If::new(
+                attrs.get("then_body")?.as_model()?.clone(),
+                attrs.get("then_input_mapping")?.as_usizes()?.into_vec(),
+                attrs.get("else_body")?.as_model()?.clone(),
+                attrs.get("else_input_mapping")?.as_usizes()?.into_vec(),
+                attrs.get("symbol_prefix")?.as_str()?.to_string(),
+            )))
+        },
+    );
+    reg.register_op::<Loop>(
+        "Loop",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("body", op.body.clone())
+                .with("carried", op.carried)
+                .with("iters", op.iters.clone()))
+        },
+        |_, attrs| {
+            Ok(Box::new(Loop::new(
+                attrs.get("body")?.as_model()?.clone(),
+                attrs.get("carried")?.as_usize()?,
+                attrs.get("iters")?.as_dim()?.clone(),
+            )))
+        },
+    );
+    reg.register_op::<Resize>("Resize", dump_resize, load_resize);
+}
+
+fn dump_einsum(_: &Registry, op: &Einsum) -> TractResult<Attrs> {
+    let inputs: Vec<Attr> =
+        op.expr.inputs.iter().map(|i| i.iter().collect::<String>().into()).collect();
+    Ok(Attrs::default()
+        .with("inputs", inputs)
+        .with("output", op.expr.output.iter().collect::<String>()))
+}
+
+fn load_einsum(_: &Registry, attrs: &Attrs) -> TractResult<Box<dyn TypedOp>> {
+    let inputs = attrs
+        .get("inputs")?
+        .as_list()?
+        .iter()
+        .map(|i| Ok(i.as_str()?.chars().collect()))
+        .collect::<TractResult<_>>()?;
+    let output = attrs.get("output")?.as_str()?.chars().collect();
+    Ok(Box::new(Einsum::new(Expr { inputs, output })))
+}
+
+fn fact(fact: &TypedFact) -> Attrs {
+    Attrs::default()
+        .with("datum_type", fact.datum_type)
+        .with("shape", &*fact.shape.to_tvec())
+        .with_opt("konst", fact.konst.clone())
+}
+
+fn load_fact(attr: &Attr) -> TractResult<TypedFact> {
+    let attrs = attr.as_attrs()?;
+    let mut fact = TypedFact::dt_shape(
+        attrs.get("datum_type")?.as_datum_type()?,
+        attrs.get("shape")?.as_dims()?,
+    )?;
+    fact.konst = attrs.get_opt("konst").map(|k| k.as_tensor().map(|t| t.clone())).transpose()?;
+    Ok(fact)
+}
+
+fn dump_q_params(q: &QParams) -> Attrs {
+    Attrs::default()
+        .with("c_datum_type", q.c_datum_type)
+        .with_opt("zero_point_a", q.zero_point_a.clone())
+        .with_opt("zero_point_b", q.zero_point_b.clone())
+        .with_opt("zero_point_c", q.zero_point_c.clone())
+        .with_opt("scale_factor", q.scale_factor)
+}
+
+fn load_q_params(attr: &Attr) -> TractResult<QParams> {
+    let attrs = attr.as_attrs()?;
+    let tensor = |name: &str| -> TractResult<Option<Arc<Tensor>>> {
+        attrs.get_opt(name).map(|t| t.as_tensor().map(|t| t.clone())).transpose()
+    };
+    Ok(QParams {
+        c_datum_type: attrs.get("c_datum_type")?.as_datum_type()?,
+        zero_point_a: tensor("zero_point_a")?,
+        zero_point_b: tensor("zero_point_b")?,
+        zero_point_c: tensor("zero_point_c")?,
+        scale_factor: attrs.get_opt("scale_factor").map(|s| s.as_f32()).transpose()?,
+    })
+}
+
+fn dump_kernel_fmt(kernel_fmt: KernelFormat) -> &'static str {
+    match kernel_fmt {
+        KernelFormat::OIHW => "OIHW",
+        KernelFormat::HWIO => "HWIO",
+    }
+}
+
+fn load_kernel_fmt(attr: &Attr) -> TractResult<KernelFormat> {
+    match attr.as_str()? {
+        "OIHW" => Ok(KernelFormat::OIHW),
+        "HWIO" => Ok(KernelFormat::HWIO),
+        f => bail!("Unknown kernel format {}", f),
+    }
+}
+
+fn dump_pool_spec(spec: &PoolSpec) -> Attrs {
+    let data_format = match spec.data_format {
+        DataFormat::NCHW => "NCHW",
+        DataFormat::NHWC => "NHWC",
+        DataFormat::CHW => "CHW",
+        DataFormat::HWC => "HWC",
+    };
+    let padding = match &spec.padding {
+        PaddingSpec::Explicit(before, after, ceil_mode) => Attrs::default()
+            .with("mode", "explicit")
+            .with("before", &**before)
+            .with("after", &**after)
+            .with("ceil_mode", *ceil_mode),
+        PaddingSpec::Valid => Attrs::default().with("mode", "valid"),
+        PaddingSpec::SameUpper => Attrs::default().with("mode", "same_upper"),
+        PaddingSpec::SameLower => Attrs::default().with("mode", "same_lower"),
+    };
+    Attrs::default()
+        .with("data_format", data_format)
+        .with("kernel_shape", &*spec.kernel_shape)
+        .with("padding", padding)
+        .with_opt("dilations", spec.dilations.as_deref())
+        .with_opt("strides", spec.strides.as_deref())
+        .with_opt("output_channel_override", spec.output_channel_override)
+}
+
+fn load_pool_spec(attr: &Attr) -> TractResult<PoolSpec> {
+    let attrs = attr.as_attrs()?;
+    let data_format = match attrs.get("data_format")?.as_str()? {
+        "NCHW" => DataFormat::NCHW,
+        "NHWC" => DataFormat::NHWC,
+        "CHW" => DataFormat::CHW,
+        "HWC" => DataFormat::HWC,
+        f => bail!("Unknown data format {}", f),
+    };
+    let padding = attrs.get("padding")?.as_attrs()?;
+    let padding = match padding.get("mode")?.as_str()? {
+        "explicit" => PaddingSpec::Explicit(
+            padding.get("before")?.as_usizes()?,
+            padding.get("after")?.as_usizes()?,
+            padding.get("ceil_mode")?.as_bool()?,
+        ),
+        "valid" => PaddingSpec::Valid,
+        "same_upper" => PaddingSpec::SameUpper,
+        "same_lower" => PaddingSpec::SameLower,
+        p => bail!("Unknown padding {}", p),
+    };
+    Ok(PoolSpec {
+        data_format,
+        kernel_shape: attrs.get("kernel_shape")?.as_usizes()?,
+        padding,
+        dilations: attrs.get_opt("dilations").map(|a| a.as_usizes()).transpose()?,
+        strides: attrs.get_opt("strides").map(|a| a.as_usizes()).transpose()?,
+        output_channel_override: attrs
+            .get_opt("output_channel_override")
+            .map(|a| a.as_usize())
+            .transpose()?,
+    })
+}
+
+fn dump_axis_op(_: &Registry, op: &AxisOp) -> TractResult<Attrs> {
+    Ok(match op {
+        AxisOp::Add(axis) => Attrs::default().with("op", "add").with("axis", *axis),
+        AxisOp::Rm(axis) => Attrs::default().with("op", "rm").with("axis", *axis),
+        AxisOp::Move(from, to) => {
+            Attrs::default().with("op", "move").with("from", *from).with("to", *to)
+        }
+        AxisOp::Reshape(at, from, to) => Attrs::default()
+            .with("op", "reshape")
+            .with("at", *at)
+            .with("from", &**from)
+            .with("to", &**to),
+    })
+}
+
+fn load_axis_op(_: &Registry, attrs: &Attrs) -> TractResult<Box<dyn TypedOp>> {
+    let op = match attrs.get("op")?.as_str()? {
+        "add" => AxisOp::Add(attrs.get("axis")?.as_usize()?),
+        "rm" => AxisOp::Rm(attrs.get("axis")?.as_usize()?),
+        "move" => AxisOp::Move(attrs.get("from")?.as_usize()?, attrs.get("to")?.as_usize()?),
+        "reshape" => AxisOp::Reshape(
+            attrs.get("at")?.as_usize()?,
+            attrs.get("from")?.as_dims()?.into(),
+            attrs.get("to")?.as_dims()?.into(),
+        ),
+        op => bail!("Unknown axis op {}", op),
+    };
+    Ok(Box::new(op))
+}
+
+fn dump_scatter_reduction(reduction: ScatterReduction) -> &'static str {
+    match reduction {
+        ScatterReduction::None => "none",
+        ScatterReduction::Add => "add",
+        ScatterReduction::Mul => "mul",
+        ScatterReduction::Max => "max",
+        ScatterReduction::Min => "min",
+    }
+}
+
+fn load_scatter_reduction(attr: &Attr) -> TractResult<ScatterReduction> {
+    Ok(match attr.as_str()? {
+        "none" => ScatterReduction::None,
+        "add" => ScatterReduction::Add,
+        "mul" => ScatterReduction::Mul,
+        "max" => ScatterReduction::Max,
+        "min" => ScatterReduction::Min,
+        r => bail!("Unknown scatter reduction {}", r),
+    })
+}
+
+fn dump_pad(_: &Registry, op: &Pad) -> TractResult<Attrs> {
+    let pads: Vec<Attr> = op.pads.iter().map(|&(a, b)| (&[a, b][..]).into()).collect();
+    let attrs = Attrs::default().with("pads", pads);
+    Ok(match &op.mode {
+        PadMode::Constant(t) => attrs.with("mode", "constant").with("value", t.clone()),
+        PadMode::Reflect => attrs.with("mode", "reflect"),
+        PadMode::Symmetric => attrs.with("mode", "symmetric"),
+        PadMode::Edge => attrs.with("mode", "edge"),
+    })
+}
+
+fn load_pad(_: &Registry, attrs: &Attrs) -> TractResult<Box<dyn TypedOp>> {
+    let pads = attrs
+        .get("pads")?
+        .as_list()?
+        .iter()
+        .map(|p| {
+            let p = p.as_usizes()?;
+            if p.len() != 2 {
+                bail!("Expected a pair of pads")
+            }
+            Ok((p[0], p[1]))
+        })
+        .collect::<TractResult<Vec<_>>>()?;
+    let mode = match attrs.get("mode")?.as_str()? {
+        "constant" => PadMode::Constant(attrs.get("value")?.as_tensor()?.clone()),
+        "reflect" => PadMode::Reflect,
+        "symmetric" => PadMode::Symmetric,
+        "edge" => PadMode::Edge,
+        m => bail!("Unknown pad mode {}", m),
+    };
+    Ok(Box::new(Pad::new(pads, mode)))
+}
+
+fn dump_scan(_: &Registry, op: &Scan) -> TractResult<Attrs> {
+    let input_mapping = op
+        .input_mapping
+        .iter()
+        .map(|im| {
+            match im {
+                InputMapping::Full { slot } => {
+                    Attrs::default().with("kind", "full").with("slot", *slot)
+                }
+                InputMapping::State { initializer: StateInitializer::FromInput(slot) } => {
+                    Attrs::default().with("kind", "state").with("slot", *slot)
+                }
+                InputMapping::State { initializer: StateInitializer::Value(t) } => {
+                    Attrs::default().with("kind", "state").with("value", t.clone())
+                }
+                InputMapping::Scan { slot, axis, chunk } => Attrs::default()
+                    .with("kind", "scan")
+                    .with("slot", *slot)
+                    .with("axis", *axis)
+                    .with("chunk", chunk.clone()),
+            }
+            .into()
+        })
+        .collect::<Vec<Attr>>();
+    let output_mapping = op
+        .output_mapping
+        .iter()
+        .map(|om| {
+            Attrs::default()
+                .with_opt("full_slot", om.full_slot)
+                .with("axis", om.axis)
+                .with("chunk", om.chunk.clone())
+                .with_opt("full_dim_hint", om.full_dim_hint.clone())
+                .with_opt("last_value_slot", om.last_value_slot)
+                .with("state", om.state)
+                .into()
+        })
+        .collect::<Vec<Attr>>();
+    Ok(Attrs::default()
+        .with("skip", op.skip)
+        .with("body", op.body.clone())
+        .with_opt("seq_length_input_slot", op.seq_length_input_slot)
+        .with("input_mapping", input_mapping)
+        .with("output_mapping", output_mapping)
+        .with("backward", op.backward))
+}
+
+fn load_scan(_: &Registry, attrs: &Attrs) -> TractResult<Box<dyn TypedOp>> {
+    let opt_usize = |attrs: &Attrs, name: &str| -> TractResult<Option<usize>> {
+        attrs.get_opt(name).map(|a| a.as_usize()).transpose()
+    };
+    let input_mapping = attrs
+        .get("input_mapping")?
+        .as_list()?
+        .iter()
+        .map(|im| {
+            let im = im.as_attrs()?;
+            Ok(match im.get("kind")?.as_str()? {
+                "full" => InputMapping::Full { slot: im.get("slot")?.as_usize()? },
+                "state" => {
+                    let initializer = if let Some(t) = im.get_opt("value") {
+                        StateInitializer::Value(t.as_tensor()?.clone())
+                    } else {
+                        StateInitializer::FromInput(im.get("slot")?.as_usize()?)
+                    };
+                    InputMapping::State { initializer }
+                }
+                "scan" => InputMapping::Scan {
+                    slot: im.get("slot")?.as_usize()?,
+                    axis: im.get("axis")?.as_usize()?,
+                    chunk: im.get("chunk")?.as_dim()?.clone(),
+                },
+                k => bail!("Unknown input mapping {}", k),
+            })
+        })
+        .collect::<TractResult<Vec<_>>>()?;
+    let output_mapping = attrs
+        .get("output_mapping")?
+        .as_list()?
+        .iter()
+        .map(|om| {
+            let om = om.as_attrs()?;
+            Ok(OutputMapping {
+                full_slot: opt_usize(om, "full_slot")?,
+                axis: om.get("axis")?.as_usize()?,
+                chunk: om.get("chunk")?.as_dim()?.clone(),
+                full_dim_hint: om
+                    .get_opt("full_dim_hint")
+                    .map(|d| d.as_dim().map(|d| d.clone()))
+                    .transpose()?,
+                last_value_slot: opt_usize(om, "last_value_slot")?,
+                state: om.get("state")?.as_bool()?,
+            })
+        })
+        .collect::<TractResult<Vec<_>>>()?;
+    let mut scan = Scan::new(
+        attrs.get("body")?.as_model()?.clone(),
+        input_mapping,
+        output_mapping,
+        opt_usize(attrs, "seq_length_input_slot")?,
+        attrs.get("backward")?.as_bool()?,
+    )?;
+    scan.skip = attrs.get("skip")?.as_usize()?;
+    Ok(Box::new(scan))
+}
+
+fn dump_resize(_: &Registry, op: &Resize) -> TractResult<Attrs> {
+    let coord_transformer = match op.coord_transformer {
+        CoordTransformer::HalfPixel => "half_pixel",
+        CoordTransformer::AlignCorners => "align_corners",
+        CoordTransformer::Asymmetric => "asymmetric",
+        CoordTransformer::TfHalfPixelForNn => "tf_half_pixel_for_nn",
+        CoordTransformer::PytorchHalfPixel => "pytorch_half_pixel",
+        CoordTransformer::TfCropAndResize => "tf_crop_and_resize",
+    };
+    let interpolator = match op.interpolator {
+        Interpolator::Nearest => "nearest",
+        Interpolator::Linear => "linear",
+        Interpolator::Cubic => "cubic",
+    };
+    let nearest = match op.nearest {
+        Nearest::RoundPreferFloor => "round_prefer_floor",
+        Nearest::RoundPreferCeil => "round_prefer_ceil",
+        Nearest::Floor => "floor",
+        Nearest::Ceil => "ceil",
+    };
+    Ok(Attrs::default()
+        .with("coord_transformer", coord_transformer)
+        .with("interpolator", interpolator)
+        .with("nearest", nearest)
+        .with("cubic_coeff_a", op.cubic_coeff_a)
+        .with("exclude_outside", op.exclude_outside)
+        .with("extrapolation_value", op.extrapolation_value)
+        .with_opt("roi_input", op.optional_roi_input)
+        .with_opt("scales_input", op.optional_scales_input)
+        .with_opt("sizes_input", op.optional_sizes_input))
+}
+
+fn load_resize(_: &Registry, attrs: &Attrs) -> TractResult<Box<dyn TypedOp>> {
+    let coord_transformer = match attrs.get("coord_transformer")?.as_str()? {
+        "half_pixel" => CoordTransformer::HalfPixel,
+        "align_corners" => CoordTransformer::AlignCorners,
+        "asymmetric" => CoordTransformer::Asymmetric,
+        "tf_half_pixel_for_nn" => CoordTransformer::TfHalfPixelForNn,
+        "pytorch_half_pixel" => CoordTransformer::PytorchHalfPixel,
+        "tf_crop_and_resize" => CoordTransformer::TfCropAndResize,
+        c => bail!("Unknown coordinate transformer {}", c),
+    };
+    let interpolator = match attrs.get("interpolator")?.as_str()? {
+        "nearest" => Interpolator::Nearest,
+        "linear" => Interpolator::Linear,
+        "cubic" => Interpolator::Cubic,
+        i => bail!("Unknown interpolator {}", i),
+    };
+    let nearest = match attrs.get("nearest")?.as_str()? {
+        "round_prefer_floor" => Nearest::RoundPreferFloor,
+        "round_prefer_ceil" => Nearest::RoundPreferCeil,
+        "floor" => Nearest::Floor,
+        "ceil" => Nearest::Ceil,
+        n => bail!("Unknown nearest mode {}", n),
+    };
+    let input = |name: &str| attrs.get_opt(name).map(|a| a.as_usize()).transpose();
+    Ok(Box::new(Resize::new(
+        coord_transformer,
+        interpolator,
+        nearest,
+        attrs.get("cubic_coeff_a")?.as_f32()?,
+        attrs.get("exclude_outside")?.as_bool()?,
+        attrs.get("extrapolation_value")?.as_f32()?,
+        input("roi_input")?,
+        input("scales_input")?,
+        input("sizes_input")?,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::registry;
+    use crate::internal::*;
+    use crate::ops;
+
+    fn round_trip(model: &TypedModel) -> TypedModel {
+        let mut buffer = vec![];
+        registry().write_model(model, &mut buffer).unwrap();
+        registry().read_model(&*buffer).unwrap()
+    }
+
+    #[test]
+    fn round_trip_conv_and_bin() {
+        let mut model = TypedModel::default();
+        let source = model
+            .add_source(
+                "input",
+                TypedFact::dt_shape(f32::datum_type(), [1, 2, 5, 5].as_ref()).unwrap(),
+            )
+            .unwrap();
+        let kernel = tensor4(&[[[[1f32]], [[2f32]]], [[[3f32]], [[4f32]]]]).into_arc_tensor();
+        let conv = ops::cnn::ConvUnary::new(
+            ops::cnn::PoolSpec::new(
+                ops::nn::DataFormat::NCHW,
+                tvec!(1, 1),
+                ops::cnn::PaddingSpec::Valid,
+                None,
+                None,
+                Some(2),
+            ),
+            ops::cnn::KernelFormat::OIHW,
+            kernel,
+            1,
+            None,
+            None,
+        );
+        let conv = model.wire_node("conv", conv, &[source]).unwrap();
+        let bias = rctensor4(&[[[[1f32]], [[2f32]]]]);
+        let add = model.wire_node("add", ops::math::add::unary(bias), &conv).unwrap();
+        let exp = model.wire_node("exp", ops::math::exp(), &add).unwrap();
+        model.set_output_outlets(&exp).unwrap();
+        model.set_outlet_label(exp[0], "output".to_string()).unwrap();
+
+        let reloaded = round_trip(&model);
+        assert_eq!(model.nodes().len(), reloaded.nodes().len());
+        for (a, b) in model.nodes().iter().zip(reloaded.nodes().iter()) {
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.inputs, b.inputs);
+            assert_eq!(format!("{:?}", a.op), format!("{:?}", b.op));
+            assert_eq!(a.outputs[0].fact, b.outputs[0].fact);
+        }
+        assert_eq!(reloaded.outlet_label(exp[0]), Some("output"));
+
+        let input = Tensor::from(tract_ndarray::Array4::<f32>::from_shape_fn(
+            (1, 2, 5, 5),
+            |(_, c, h, w)| (c + h * w) as f32 / 10.0,
+        ));
+        let expected = SimplePlan::new(&model).unwrap().run(tvec!(input.clone())).unwrap();
+        let found = SimplePlan::new(&reloaded).unwrap().run(tvec!(input)).unwrap();
+        assert_eq!(expected, found);
+    }
+
+    #[test]
+    fn round_trip_streaming_fact_and_scan() {
+        let mut body = TypedModel::default();
+        let x = body
+            .add_source("x", TypedFact::dt_shape(f32::datum_type(), [1, 2].as_ref()).unwrap())
+            .unwrap();
+        let h = body
+            .add_source("h", TypedFact::dt_shape(f32::datum_type(), [1, 2].as_ref()).unwrap())
+            .unwrap();
+        let sum = body.wire_node("sum", ops::math::add::bin_typed(), &[x, h]).unwrap();
+        body.set_output_outlets(&sum).unwrap();
+        let scan = ops::scan::Scan::new(
+            body,
+            vec![
+                ops::scan::InputMapping::Scan { slot: 0, axis: 0, chunk: 1.to_dim() },
+                ops::scan::InputMapping::State {
+                    initializer: ops::scan::StateInitializer::Value(rctensor2(&[[0f32, 0f32]])),
+                },
+            ],
+            vec![ops::scan::OutputMapping {
+                full_slot: Some(0),
+                axis: 0,
+                chunk: 1.to_dim(),
+                full_dim_hint: Some(TDim::s()),
+                last_value_slot: None,
+                state: true,
+            }],
+            None,
+            false,
+        )
+        .unwrap();
+        let mut model = TypedModel::default();
+        let source = model
+            .add_source(
+                "input",
+                TypedFact::dt_shape(f32::datum_type(), [TDim::s(), 2.to_dim()].as_ref()).unwrap(),
+            )
+            .unwrap();
+        let scan = model.wire_node("scan", scan, &[source]).unwrap();
+        model.set_output_outlets(&scan).unwrap();
+
+        let reloaded = round_trip(&model);
+        assert_eq!(reloaded.outlet_fact(scan[0]).unwrap(), model.outlet_fact(scan[0]).unwrap());
+        let reloaded_scan = reloaded.node(scan[0].node).op_as::<ops::scan::Scan>().unwrap();
+        assert_eq!(reloaded_scan.body.nodes().len(), 3);
+
+        let input = rctensor2(&[[1f32, 2.0], [3.0, 4.0], [5.0, 6.0]]).into_tensor();
+        let model = model.concretize_stream_dim(3).unwrap();
+        let reloaded = reloaded.concretize_stream_dim(3).unwrap();
+        let expected = SimplePlan::new(&model).unwrap().run(tvec!(input.clone())).unwrap();
+        let found = SimplePlan::new(&reloaded).unwrap().run(tvec!(input)).unwrap();
+        assert_eq!(expected, found);
+    }
+
+    #[test]
+    fn round_trip_array_and_nn_ops() {
+        let registry = registry();
+        let ops: Vec<Box<dyn TypedOp>> = vec![
+            Box::new(ops::array::OneHot::new(1, 4, rctensor0(0f32), rctensor0(1f32))),
+            Box::new(ops::array::GatherNd::new(1)),
+            Box::new(ops::array::GatherElements::new(2)),
+            Box::new(ops::array::ScatterNd::new(ops::array::ScatterReduction::None)),
+            Box::new(ops::array::ScatterNd::new(ops::array::ScatterReduction::Mul)),
+            Box::new(ops::array::ScatterElements::new(1, ops::array::ScatterReduction::Add)),
+            Box::new(ops::array::ScatterElements::new(0, ops::array::ScatterReduction::Max)),
+            Box::new(ops::array::ScatterElements::new(0, ops::array::ScatterReduction::Min)),
+            Box::new(ops::array::TopK::new(1, false, 3.to_dim())),
+            Box::new(ops::array::NonZero::new(TDim::s())),
+            Box::new(ops::array::Unique::new(Some(0), true, TDim::s())),
+            Box::new(ops::array::Unique::new(None, false, 5.to_dim())),
+            Box::new(ops::nn::MeanVarNorm::new(2, 1e-5)),
+            Box::new(ops::element_wise::ElementWiseOp(Box::new(ops::quant::LookupTable {
+                table: (tract_linalg::ops().lut_u8)(&(0..=255u8).rev().collect::<Vec<_>>()),
+            }))),
+            Box::new(ops::einsum::Einsum::new(
+                ops::einsum::Expr::parse("bij,bjk->bik", &[3, 3]).unwrap(),
+            )),
+        ];
+        for op in ops {
+            let (name, attrs) = registry.dump_op(&*op).unwrap();
+            let reloaded = registry.load_op(&name, &attrs).unwrap();
+            assert_eq!(format!("{:?}", op), format!("{:?}", reloaded));
+        }
+    }
+
+    fn exp_model() -> TypedModel {
+        let mut model = TypedModel::default();
+        let source = model
+            .add_source("input", TypedFact::dt_shape(f32::datum_type(), [3].as_ref()).unwrap())
+            .unwrap();
+        let exp = model.wire_node("exp", ops::math::exp(), &[source]).unwrap();
+        model.set_output_outlets(&exp).unwrap();
+        model
+    }
+
+    #[test]
+    fn reject_truncated_file() {
+        let mut buffer = vec![];
+        registry().write_model(&exp_model(), &mut buffer).unwrap();
+        for len in 0..buffer.len() {
+            assert!(registry().read_model(&buffer[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn reject_huge_counts() {
+        let mut buffer = super::super::MAGIC.to_vec();
+        buffer.extend_from_slice(&super::super::VERSION.to_le_bytes());
+        buffer.extend_from_slice(&u64::max_value().to_le_bytes());
+        assert!(registry().read_model(&*buffer).is_err());
+    }
+
+    #[test]
+    fn reject_corrupt_bytes() {
+        let mut buffer = vec![];
+        registry().write_model(&exp_model(), &mut buffer).unwrap();
+        for pos in 12..buffer.len() {
+            for &byte in &[1u8, 2, 0x80, 0xff] {
+                let mut corrupt = buffer.clone();
+                corrupt[pos] = byte;
+                // must not panic, whether or not it is rejected
+                let _ = registry().read_model(&*corrupt);
+            }
+        }
+    }
+
+    #[test]
+    fn save_and_load_file() {
+        let path = std::env::temp_dir().join(format!("tract-ser-{}.bin", std::process::id()));
+        registry().save_model(&exp_model(), &path).unwrap();
+        let reloaded = registry().load_model(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(reloaded.unwrap().nodes().len(), 2);
+    }
+}
@@ -1,6 +1,7 @@
 //! `Tensor`, tract main data object of interest.
 use crate::dim::TDim;
 use crate::internal::*;
+use crate::model::memory::ArenaSlice;
 use ndarray::prelude::*;
 use std::alloc;
 use std::fmt;
@@ -20,6 +21,9 @@ pub struct Tensor {
     shape: TVec<usize>,
     layout: alloc::Layout,
     data: *mut u8,
+    /// Set when the data lives in a state arena: the tensor keeps the slice
+    /// busy, and does not deallocate it.
+    arena: Option<Arc<ArenaSlice>>,
 }
 
 unsafe impl Send for Tensor {}
@@ -91,7 +95,7 @@ impl Drop for Tensor {
                     .for_each(|s| std::ptr::drop_in_place(s as *mut TDim));
             }
         }
-        if !self.data.is_null() && self.layout.size() > 0 {
+        if !self.data.is_null() && self.layout.size() > 0 && self.arena.is_none() {
             unsafe { alloc::dealloc(self.data, self.layout) }
         }
     }
@@ -136,7 +140,19 @@ impl Tensor {
             assert!(!ptr.is_null());
             ptr
         } as *mut u8;
-        Ok(Tensor { layout, dt, shape: shape.into(), data })
+        Ok(Tensor { layout, dt, shape: shape.into(), data, arena: None })
+    }
+
+    /// Create an uninitialized tensor in an arena slice.
+    pub(crate) unsafe fn uninitialized_in_arena(
+        dt: DatumType,
+        shape: &[usize],
+        data: *mut u8,
+        slice: Arc<ArenaSlice>,
+    ) -> TractResult<Tensor> {
+        let bytes = shape.iter().cloned().product::<usize>() * dt.size_of();
+        let layout = alloc::Layout::from_size_align(bytes, dt.alignment())?;
+        Ok(Tensor { layout, dt, shape: shape.into(), data, arena: Some(slice) })
     }
 
     pub fn stack_tensors(
@@ -198,7 +214,7 @@ impl Tensor {
         let layout = alloc::Layout::from_size_align(bytes, dt.alignment())?;
         let data = alloc::alloc(layout);
         content.as_ptr().copy_to_nonoverlapping(data, bytes);
-        Ok(Tensor { dt, shape: shape.into(), data, layout })
+        Ok(Tensor { dt, shape: shape.into(), data, layout, arena: None })
     }
 
     /// Get the number of dimensions (or axes) of the tensor.
@@ -266,6 +282,11 @@ impl Tensor {
         self.dt
     }
 
+    /// Whether the tensor data lives in the arena of a running state.
+    pub fn is_in_arena(&self) -> bool {
+        self.arena.is_some()
+    }
+
     /// Set the datum type of the tensor.
     pub unsafe fn set_datum_type(&mut self, dt: DatumType) {
         self.dt = dt
@@ -579,25 +600,33 @@ impl Tensor {
         let layout =
             alloc::Layout::from_size_align(vec.len() * size_of::<T>(), align_of::<T>()).unwrap();
         let data = Box::into_raw(vec) as *mut u8;
-        Tensor { dt: T::datum_type(), shape, layout, data }
+        Tensor { dt: T::datum_type(), shape, layout, data, arena: None }
     }
 
     pub fn deep_clone(&self) -> Tensor {
         if self.dt == DatumType::String {
             let data: Vec<String> = self.as_slice::<String>().unwrap().to_vec();
-            let t = Tensor { data: data.as_ptr() as *mut u8, shape: self.shape.clone(), ..*self };
+            let data_ptr = data.as_ptr() as *mut u8;
+            let t = Tensor { data: data_ptr, shape: self.shape.clone(), arena: None, ..*self };
+            std::mem::forget(data);
+            t
+        } else if self.dt == DatumType::Blob {
+            let data: Vec<Blob> = self.as_slice::<Blob>().unwrap().to_vec();
+            let data_ptr = data.as_ptr() as *mut u8;
+            let t = Tensor { data: data_ptr, shape: self.shape.clone(), arena: None, ..*self };
             std::mem::forget(data);
             t
         } else if self.dt == DatumType::TDim {
             let data: Vec<TDim> = self.as_slice::<TDim>().unwrap().to_vec();
-            let t = Tensor { data: data.as_ptr() as *mut u8, shape: self.shape.clone(), ..*self };
+            let data_ptr = data.as_ptr() as *mut u8;
+            let t = Tensor { data: data_ptr, shape: self.shape.clone(), arena: None, ..*self };
             std::mem::forget(data);
             t
         } else {
             unsafe {
                 let data = alloc::alloc(self.layout) as *mut u8;
                 self.data.copy_to_nonoverlapping(data, self.layout.size());
-                Tensor { data, shape: self.shape.clone(), ..*self }
+                Tensor { data, shape: self.shape.clone(), arena: None, ..*self }
             }
         }
     }
@@ -10,7 +10,7 @@ fi
 mkdir -p $CACHEDIR/onnx
 cd $CACHEDIR/onnx
 
-for version in 1.4.1 1.5.0
+for version in 1.4.1 1.5.0 1.6.0 1.7.0
 do
     if [ ! -e onnx-$version/onnx/backend/test/data ]
     then
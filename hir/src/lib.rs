@@ -10,6 +10,7 @@ pub mod macros;
 pub mod framework;
 
 pub mod infer;
+pub mod ser;
 
 pub extern crate tract_core;
 
@@ -24,6 +25,7 @@ pub mod ops {
     pub mod cnn;
     pub mod downsample;
     pub mod dummy;
+    pub mod einsum;
     pub mod element_wise;
     pub mod expandable;
     pub mod identity;
@@ -6,7 +6,7 @@ use tract_num_traits::{AsPrimitive, One, Zero};
 #[educe(Hash)]
 pub struct ConstantLike {
     #[educe(Hash(method = "hash_f32"))]
-    value: f32,
+    pub value: f32,
 }
 
 tract_linalg::impl_dyn_hash!(ConstantLike);
@@ -76,8 +76,8 @@ impl TypedOp for ConstantLike {
 
 #[derive(Debug, Clone, new, Default, Hash)]
 pub struct EyeLike {
-    dt: Option<DatumType>,
-    k: isize,
+    pub dt: Option<DatumType>,
+    pub k: isize,
 }
 
 tract_linalg::impl_dyn_hash!(EyeLike);
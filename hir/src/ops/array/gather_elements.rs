@@ -0,0 +1,45 @@
+use crate::infer::*;
+use crate::internal::*;
+
+use tract_core::ops::array::GatherElements as TypedGatherElements;
+
+/// GatherElements, with an axis that may count from the end.
+#[derive(Debug, Clone, new, Hash)]
+pub struct GatherElements {
+    pub axis: isize,
+}
+
+tract_linalg::impl_dyn_hash!(GatherElements);
+
+impl Expansion for GatherElements {
+    fn name(&self) -> Cow<str> {
+        "GatherElements".into()
+    }
+
+    op_hir!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 2)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, &inputs[0].datum_type)?;
+        s.equals(&inputs[0].rank, &inputs[1].rank)?;
+        s.equals(&outputs[0].shape, &inputs[1].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let rank = model.outlet_fact(inputs[0])?.rank() as isize;
+        let axis = if self.axis < 0 { self.axis + rank } else { self.axis } as usize;
+        model.wire_node(prefix, TypedGatherElements::new(axis), inputs)
+    }
+}
@@ -0,0 +1,58 @@
+use crate::infer::*;
+use crate::internal::*;
+
+pub use tract_core::ops::array::GatherNd;
+
+impl InferenceRulesOp for GatherNd {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 2)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, &inputs[0].datum_type)?;
+        for i in 0..self.batch_dims {
+            s.equals(&inputs[0].shape[i], &inputs[1].shape[i])?;
+        }
+        s.given(&inputs[1].rank, move |s, indices_rank| {
+            let indices_rank = indices_rank as usize;
+            if indices_rank == 0 {
+                bail!("GatherNd: indices must have at least one axis")
+            }
+            for i in 0..(indices_rank - 1) {
+                s.equals(&outputs[0].shape[i], &inputs[1].shape[i])?;
+            }
+            s.given_2(
+                &inputs[1].shape[indices_rank - 1],
+                &inputs[0].rank,
+                move |s, n, data_rank| {
+                    if let Ok(n) = n.to_integer() {
+                        let data_rank = data_rank as usize;
+                        let kept = self.batch_dims + n as usize;
+                        if kept > data_rank {
+                            bail!(
+                                "GatherNd: {} batch axes and {} coordinates for data of rank {}",
+                                self.batch_dims,
+                                n,
+                                data_rank
+                            )
+                        }
+                        s.equals(&outputs[0].rank, (indices_rank - 1 + data_rank - kept) as i32)?;
+                        for i in 0..(data_rank - kept) {
+                            s.equals(
+                                &outputs[0].shape[indices_rank - 1 + i],
+                                &inputs[0].shape[kept + i],
+                            )?;
+                        }
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+
+    as_op!();
+    to_typed!();
+}
@@ -0,0 +1,49 @@
+use crate::infer::*;
+use crate::internal::*;
+
+use tract_core::ops::array::ScatterElements as TypedScatterElements;
+use tract_core::ops::array::ScatterReduction;
+
+/// ScatterElements, with an axis that may count from the end.
+#[derive(Debug, Clone, new, Hash)]
+pub struct ScatterElements {
+    pub axis: isize,
+    pub reduction: ScatterReduction,
+}
+
+tract_linalg::impl_dyn_hash!(ScatterElements);
+
+impl Expansion for ScatterElements {
+    fn name(&self) -> Cow<str> {
+        "ScatterElements".into()
+    }
+
+    op_hir!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 3)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, &inputs[0].datum_type)?;
+        s.equals(&inputs[2].datum_type, &inputs[0].datum_type)?;
+        s.equals(&inputs[0].rank, &inputs[1].rank)?;
+        s.equals(&inputs[1].shape, &inputs[2].shape)?;
+        s.equals(&outputs[0].shape, &inputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let rank = model.outlet_fact(inputs[0])?.rank() as isize;
+        let axis = if self.axis < 0 { self.axis + rank } else { self.axis } as usize;
+        model.wire_node(prefix, TypedScatterElements::new(axis, self.reduction), inputs)
+    }
+}
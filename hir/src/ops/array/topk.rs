@@ -0,0 +1,95 @@
+use crate::infer::*;
+use crate::internal::*;
+
+use tract_core::ops::array::TopK as TypedTopK;
+
+/// TopK, with k given as an attribute or as a second input.
+///
+/// When k is an input that is not a constant, the output length along the
+/// axis is a symbol named after the node.
+#[derive(Debug, Clone, new, Hash)]
+pub struct TopK {
+    pub axis: isize,
+    pub largest: bool,
+    pub k: Option<usize>,
+    pub index_type: DatumType,
+}
+
+tract_linalg::impl_dyn_hash!(TopK);
+
+impl TopK {
+    fn resolve_axis(&self, rank: usize) -> usize {
+        (if self.axis < 0 { self.axis + rank as isize } else { self.axis }) as usize
+    }
+}
+
+impl Expansion for TopK {
+    fn name(&self) -> Cow<str> {
+        "TopK".into()
+    }
+
+    op_hir!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1 + self.k.is_none() as usize)?;
+        check_output_arity(&outputs, 2)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&outputs[1].datum_type, self.index_type)?;
+        s.equals(&inputs[0].rank, &outputs[0].rank)?;
+        s.equals(&inputs[0].rank, &outputs[1].rank)?;
+        s.equals(&outputs[0].shape, &outputs[1].shape)?;
+        s.given(&inputs[0].rank, move |s, rank| {
+            let axis = self.resolve_axis(rank as usize);
+            for i in 0..rank as usize {
+                if i != axis {
+                    s.equals(&inputs[0].shape[i], &outputs[0].shape[i])?;
+                }
+            }
+            if let Some(k) = self.k {
+                s.equals(&outputs[0].shape[axis], k.to_dim())?;
+            } else {
+                s.given(&inputs[1].value, move |s, k| {
+                    if k.len() != 1 {
+                        bail!("TopK: k must be a scalar or a single-element tensor")
+                    }
+                    let k = k.cast_to::<i64>()?.as_slice::<i64>()?[0];
+                    s.equals(&outputs[0].shape[axis], k.to_dim())
+                })?;
+            }
+            Ok(())
+        })
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(2)
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let axis = self.resolve_axis(model.outlet_fact(inputs[0])?.rank());
+        let k = if let Some(k) = self.k {
+            model.add_const(format!("{}.k", prefix), rctensor0(k as i64))?
+        } else {
+            inputs[1]
+        };
+        let op = TypedTopK::new(axis, self.largest, TDim::sym(format!("{}.k", prefix)));
+        let mut wires = model.wire_node(prefix, op, &[inputs[0], k])?;
+        if self.index_type != i64::datum_type() {
+            wires[1] = model.wire_node(
+                format!("{}.cast_indices", prefix),
+                tract_core::ops::cast::cast(self.index_type),
+                &[wires[1]],
+            )?[0];
+        }
+        Ok(wires)
+    }
+}
@@ -0,0 +1,314 @@
+use crate::infer::*;
+use crate::internal::*;
+
+use tract_core::ops::cnn::deconv::DeconvUnary;
+use tract_core::ops::cnn::{KernelFormat, PaddingSpec, PoolSpec};
+use tract_core::ops::nn::DataFormat;
+
+/// Transposed convolution.
+///
+/// Kernel layouts are the ones of the convolution this op is the transpose
+/// of: OIHW is [Cin, Cout/group, H, W], HWIO is [H, W, Cout/group, Cin].
+///
+/// The output spatial shape can be given, either as an attribute or as a
+/// constant input (taking the full output shape, as in TF). It then overrides
+/// the output shape computed from the padding, stride and adjustments.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct Deconv {
+    pub data_format: DataFormat,
+    pub kernel_fmt: KernelFormat,
+    pub padding: PaddingSpec,
+    pub strides: Option<TVec<usize>>,
+    pub dilations: Option<TVec<usize>>,
+    pub adjustments: Option<TVec<usize>>,
+    pub output_shape: Option<TVec<usize>>,
+    pub group: Option<usize>,
+
+    pub x_input: Option<usize>,
+    pub k_input: Option<usize>,
+    pub bias_input: Option<usize>,
+    pub output_shape_input: Option<usize>,
+}
+
+tract_linalg::impl_dyn_hash!(Deconv);
+
+impl Deconv {
+    pub fn nhwc(self) -> Deconv {
+        Deconv { data_format: DataFormat::NHWC, ..self }
+    }
+
+    pub fn hwio(self) -> Deconv {
+        Deconv { kernel_fmt: KernelFormat::HWIO, ..self }
+    }
+
+    pub fn padding(self, padding: PaddingSpec) -> Deconv {
+        Deconv { padding, ..self }
+    }
+
+    pub fn strides(self, strides: TVec<usize>) -> Deconv {
+        Deconv { strides: Some(strides), ..self }
+    }
+
+    pub fn dilations(self, dilations: TVec<usize>) -> Deconv {
+        Deconv { dilations: Some(dilations), ..self }
+    }
+
+    pub fn adjustments(self, adjustments: TVec<usize>) -> Deconv {
+        Deconv { adjustments: Some(adjustments), ..self }
+    }
+
+    pub fn output_shape(self, output_shape: TVec<usize>) -> Deconv {
+        Deconv { output_shape: Some(output_shape), ..self }
+    }
+
+    pub fn group(self, group: usize) -> Deconv {
+        Deconv { group: Some(group), ..self }
+    }
+
+    pub fn x_input(self, input: usize) -> Deconv {
+        Deconv { x_input: Some(input), ..self }
+    }
+
+    pub fn k_input(self, input: usize) -> Deconv {
+        Deconv { k_input: Some(input), ..self }
+    }
+
+    pub fn bias_input(self, input: usize) -> Deconv {
+        Deconv { bias_input: Some(input), ..self }
+    }
+
+    pub fn output_shape_input(self, input: usize) -> Deconv {
+        Deconv { output_shape_input: Some(input), ..self }
+    }
+
+    fn x_slot(&self) -> usize {
+        self.x_input.unwrap_or(0)
+    }
+
+    fn k_slot(&self) -> usize {
+        self.k_input.unwrap_or(1)
+    }
+
+    /// Pool spec and adjustments of the core op, given input and kernel
+    /// shapes, and an optional spatial output shape.
+    fn pool_spec_and_adjustments<D: DimLike>(
+        &self,
+        x_shape: &[D],
+        kshape: &[usize],
+        output_shape: Option<&[usize]>,
+    ) -> TractResult<(PoolSpec, TVec<usize>)> {
+        let spatial_rank = kshape.len() - 2;
+        let group = self.group.unwrap_or(1);
+        let output_channels = match self.kernel_fmt {
+            KernelFormat::OIHW => kshape[1] * group,
+            KernelFormat::HWIO => kshape[kshape.len() - 2] * group,
+        };
+        let kernel_shape: TVec<usize> = kshape[self.kernel_fmt.h_axis()..][..spatial_rank].into();
+        let mut pool_spec = PoolSpec {
+            data_format: self.data_format,
+            kernel_shape,
+            padding: self.padding.clone(),
+            dilations: self.dilations.clone(),
+            strides: self.strides.clone(),
+            output_channel_override: Some(output_channels),
+        };
+        let mut adjustments = self.adjustments.clone().unwrap_or(tvec!(0; spatial_rank));
+        if let Some(output_shape) = output_shape {
+            // deduce the padding from the output shape as the matching forward
+            // convolution would do, and make up the difference with adjustments
+            let x_shape = self.data_format.shape(x_shape)?;
+            let mut before = tvec!();
+            let mut after = tvec!();
+            for axis in 0..spatial_rank {
+                let padding = self.padding.compute_one(
+                    axis,
+                    &output_shape[axis],
+                    pool_spec.kernel_shape[axis],
+                    pool_spec.dilation(axis),
+                    pool_spec.stride(axis),
+                );
+                let input = x_shape.hw_dims()[axis].to_integer()? as usize;
+                let computed = PaddingSpec::Explicit(
+                    tvec!(padding.pad_before),
+                    tvec!(padding.pad_after),
+                    false,
+                )
+                .compute_one_for_deconv(
+                    0,
+                    &input,
+                    pool_spec.kernel_shape[axis],
+                    pool_spec.dilation(axis),
+                    pool_spec.stride(axis),
+                    0,
+                );
+                if computed.output > output_shape[axis] {
+                    bail!(
+                        "Deconvolution can not produce output shape {:?} for input {:?}",
+                        output_shape,
+                        x_shape
+                    );
+                }
+                adjustments[axis] = output_shape[axis] - computed.output;
+                before.push(padding.pad_before);
+                after.push(padding.pad_after);
+            }
+            pool_spec.padding = PaddingSpec::Explicit(before, after, false);
+        }
+        Ok((pool_spec, adjustments))
+    }
+
+    fn infer_output_shape(
+        &self,
+        xshape: &[TDim],
+        kshape: &[TDim],
+        output_shape: Option<TVec<usize>>,
+    ) -> TractResult<Option<TVec<TDim>>> {
+        if let Ok(kshape) = kshape
+            .iter()
+            .map(|d| d.to_integer().map(|d| d as usize))
+            .collect::<TractResult<TVec<_>>>()
+        {
+            let (pool_spec, adjustments) =
+                self.pool_spec_and_adjustments(xshape, &*kshape, output_shape.as_deref())?;
+            Ok(Some(tract_core::ops::cnn::deconv::output_shape(&pool_spec, xshape, &adjustments)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn spatial_output_shape(
+        &self,
+        output_shape_input: Option<&Tensor>,
+    ) -> TractResult<Option<TVec<usize>>> {
+        if let Some(shape) = output_shape_input {
+            let shape = shape.cast_to::<i64>()?;
+            let shape = self
+                .data_format
+                .shape(shape.as_slice::<i64>()?.iter().map(|&d| d as usize).collect::<TVec<_>>())?;
+            Ok(Some(shape.hw_dims().into()))
+        } else {
+            Ok(self.output_shape.clone())
+        }
+    }
+}
+
+impl Expansion for Deconv {
+    fn name(&self) -> Cow<str> {
+        "Deconv".into()
+    }
+
+    fn validation(&self) -> Validation {
+        Validation::Rounding
+    }
+
+    op_hir!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_output_arity(&outputs, 1)?;
+        let has_n = self.data_format == DataFormat::NHWC || self.data_format == DataFormat::NCHW;
+        let x = &inputs[self.x_slot()];
+        let k = &inputs[self.k_slot()];
+        s.equals(&x.datum_type, &k.datum_type)?;
+        s.equals(&outputs[0].datum_type, &x.datum_type)?;
+        s.equals(&x.rank, k.rank.bex() + (has_n as usize as i32 - 1))?;
+        s.equals(&outputs[0].rank, &x.rank)?;
+        if let Some(bias) = self.bias_input {
+            s.equals(&inputs[bias].rank, 1)?;
+        }
+        if let Some(slot) = self.output_shape_input {
+            s.equals(&inputs[slot].rank, 1)?;
+            s.given_3(&x.shape, &k.shape, &inputs[slot].value, move |s, xshape, kshape, oshape| {
+                let output_shape = self.spatial_output_shape(Some(&*oshape))?;
+                if let Some(shape) = self.infer_output_shape(&xshape, &kshape, output_shape)? {
+                    s.equals(&outputs[0].shape, shape)?;
+                }
+                Ok(())
+            })
+        } else {
+            s.given_2(&x.shape, &k.shape, move |s, xshape, kshape| {
+                let output_shape = self.output_shape.clone();
+                if let Some(shape) = self.infer_output_shape(&xshape, &kshape, output_shape)? {
+                    s.equals(&outputs[0].shape, shape)?;
+                }
+                Ok(())
+            })
+        }
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let x = inputs[self.x_slot()];
+        let kernel = target
+            .outlet_fact(inputs[self.k_slot()])?
+            .konst
+            .clone()
+            .ok_or("Deconvolution kernel must be a constant")?;
+        let bias = if let Some(slot) = self.bias_input {
+            Some(target.outlet_fact(inputs[slot])?.konst.clone().ok_or("Bias must be a constant")?)
+        } else {
+            None
+        };
+        let output_shape = if let Some(slot) = self.output_shape_input {
+            let shape = target
+                .outlet_fact(inputs[slot])?
+                .konst
+                .clone()
+                .ok_or("Deconvolution output shape must be a constant")?;
+            self.spatial_output_shape(Some(&*shape))?
+        } else {
+            self.output_shape.clone()
+        };
+        let x_shape = target.outlet_fact(x)?.shape.to_tvec();
+        let (pool_spec, adjustments) =
+            self.pool_spec_and_adjustments(&*x_shape, kernel.shape(), output_shape.as_deref())?;
+        let op = DeconvUnary::new(
+            pool_spec,
+            self.kernel_fmt,
+            kernel,
+            bias,
+            adjustments,
+            self.group.unwrap_or(1),
+        );
+        target.wire_node(prefix, op, &[x])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn infer_output_shape() {
+        let mut op = expand(Deconv::default().strides(tvec![2]).adjustments(tvec![1]));
+        let ifact = InferenceFact::dt_shape(DatumType::F32, shapefactoid!(1, 2, 5));
+        let kfact = InferenceFact::dt_shape(DatumType::F32, shapefactoid!(2, 3, 3));
+        let ofact = InferenceFact::default();
+        let facts = op.infer_facts(tvec!(&ifact, &kfact), tvec!(&ofact), tvec!()).unwrap();
+        assert_eq!(
+            facts.1,
+            tvec!(InferenceFact::dt_shape(DatumType::F32, shapefactoid!(1, 3, 12)))
+        );
+    }
+
+    #[test]
+    fn same_padding_with_output_shape() {
+        let op = Deconv::default().padding(PaddingSpec::SameUpper).strides(tvec![2]);
+        let (pool_spec, adjustments) =
+            op.pool_spec_and_adjustments(&[1usize, 2, 3], &[2, 3, 3], Some(&[5])).unwrap();
+        assert_eq!(pool_spec.padding, PaddingSpec::Explicit(tvec!(1), tvec!(1), false));
+        assert_eq!(adjustments, tvec!(0));
+        let (pool_spec, adjustments) =
+            op.pool_spec_and_adjustments(&[1usize, 2, 3], &[2, 3, 3], Some(&[6])).unwrap();
+        assert_eq!(pool_spec.padding, PaddingSpec::Explicit(tvec!(0), tvec!(1), false));
+        assert_eq!(adjustments, tvec!(0));
+    }
+}
@@ -1,6 +1,8 @@
 mod conv;
+mod deconv;
 mod pools;
 
 pub use conv::Conv;
+pub use deconv::Deconv;
 pub use pools::{AvgPool, MaxPool};
 pub use tract_core::ops::cnn::{ConvUnary, PaddingSpec, PoolSpec};
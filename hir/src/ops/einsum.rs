@@ -0,0 +1,70 @@
+use crate::infer::*;
+use crate::internal::*;
+
+use tract_core::ops::einsum::Einsum as TypedEinsum;
+pub use tract_core::ops::einsum::Expr;
+
+/// Einsum, from its equation. Ellipsis are resolved once the input ranks are
+/// known.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Einsum {
+    pub equation: String,
+}
+
+tract_linalg::impl_dyn_hash!(Einsum);
+
+impl Einsum {
+    fn input_count(&self) -> usize {
+        self.equation.split("->").next().unwrap().split(',').count()
+    }
+}
+
+impl Expansion for Einsum {
+    fn name(&self) -> Cow<str> {
+        "Einsum".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![self.equation.clone()])
+    }
+
+    op_hir!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, self.input_count())?;
+        check_output_arity(&outputs, 1)?;
+        for input in inputs {
+            s.equals(&input.datum_type, &outputs[0].datum_type)?;
+        }
+        s.given_all(inputs.iter().map(|i| &i.rank), move |s, ranks| {
+            let ranks: TVec<usize> = ranks.iter().map(|&r| r as usize).collect();
+            let expr = Expr::parse(&self.equation, &ranks)?;
+            s.equals(&outputs[0].rank, expr.output.len() as i32)
+        })?;
+        s.given_all(inputs.iter().map(|i| &i.shape), move |s, shapes: Vec<TVec<TDim>>| {
+            let ranks: TVec<usize> = shapes.iter().map(|s| s.len()).collect();
+            let expr = Expr::parse(&self.equation, &ranks)?;
+            let shapes: TVec<&[TDim]> = shapes.iter().map(|s| &**s).collect();
+            s.equals(&outputs[0].shape, expr.output_shape(&shapes)?)
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let ranks = inputs
+            .iter()
+            .map(|i| Ok(model.outlet_fact(*i)?.rank()))
+            .collect::<TractResult<TVec<_>>>()?;
+        let expr = Expr::parse(&self.equation, &ranks)?;
+        model.wire_node(prefix, TypedEinsum::new(expr), inputs)
+    }
+}
@@ -3,9 +3,13 @@ use crate::internal::*;
 
 // TODO tricky to re-express in "core" because of the multiple hot point... do
 // we need one more reduce ?
+/// With `coerce_to_2d` (ONNX before opset 13), the input is seen as a 2D
+/// matrix, all axes from `axis` on being flattened in the second one.
+/// Otherwise, only `axis` is considered.
 #[derive(Debug, Clone, new, Default, Hash)]
 pub struct LayerHardmax {
-    axis: isize,
+    pub axis: isize,
+    pub coerce_to_2d: bool,
 }
 
 tract_linalg::impl_dyn_hash!(LayerHardmax);
@@ -19,10 +23,7 @@ impl LayerHardmax {
         let shape = array.shape().to_vec();
         let axis =
             if self.axis < 0 { shape.len() as isize + self.axis } else { self.axis } as usize;
-        let first_dim: usize = array.shape()[0..axis].iter().product();
-        let second_dim: usize = array.len() / first_dim;
-        let mut array = array.into_shape((first_dim, second_dim))?;
-        array.outer_iter_mut().for_each(|mut layer| {
+        let hot = |mut layer: tract_ndarray::ArrayViewMut1<D>| {
             let max = layer
                 .iter()
                 .enumerate()
@@ -34,8 +35,18 @@ impl LayerHardmax {
                 .iter_mut()
                 .enumerate()
                 .for_each(|(ix, r)| *r = D::from_usize((ix == max) as usize).unwrap());
-        });
-        Ok(tvec!(array.into_shape(shape)?.into_arc_tensor()))
+        };
+        if self.coerce_to_2d {
+            let first_dim: usize = array.shape()[0..axis].iter().product();
+            let second_dim: usize = array.len() / first_dim;
+            let mut array = array.into_shape((first_dim, second_dim))?;
+            array.outer_iter_mut().for_each(hot);
+            Ok(tvec!(array.into_shape(shape)?.into_arc_tensor()))
+        } else {
+            let mut array = array;
+            array.lanes_mut(tract_ndarray::Axis(axis)).into_iter().for_each(hot);
+            Ok(tvec!(array.into_arc_tensor()))
+        }
     }
 }
 
@@ -45,7 +56,7 @@ impl Op for LayerHardmax {
     }
 
     fn info(&self) -> TractResult<Vec<String>> {
-        Ok(vec![format!("axis: {}", self.axis)])
+        Ok(vec![format!("axis: {} coerce_to_2d: {}", self.axis, self.coerce_to_2d)])
     }
 
     op_hir!();
@@ -84,6 +95,7 @@ impl TypedOp for LayerHardmax {
 #[derive(Debug, Clone, new, Default, Hash)]
 pub struct LayerLogSoftmax {
     axis: isize,
+    coerce_to_2d: bool,
 }
 
 tract_linalg::impl_dyn_hash!(LayerLogSoftmax);
@@ -110,7 +122,7 @@ impl Expansion for LayerLogSoftmax {
         target: &mut TypedModel,
         inputs: &[OutletId],
     ) -> TractResult<TVec<OutletId>> {
-        let softmax = LayerSoftmax { axis: self.axis }.wire(name, target, inputs)?;
+        let softmax = LayerSoftmax::new(self.axis, self.coerce_to_2d).wire(name, target, inputs)?;
         target.wire_node(format!("{}.logsoftmax", name), tract_core::ops::math::ln(), &softmax)
     }
 }
@@ -118,6 +130,7 @@ impl Expansion for LayerLogSoftmax {
 #[derive(Debug, Clone, new, Default, Hash)]
 pub struct LayerSoftmax {
     axis: isize,
+    coerce_to_2d: bool,
 }
 
 tract_linalg::impl_dyn_hash!(LayerSoftmax);
@@ -147,7 +160,8 @@ impl Expansion for LayerSoftmax {
         let input = inputs[0];
         let rank = target.outlet_fact(input)?.rank();
         let axis = if self.axis < 0 { rank as isize + self.axis } else { self.axis } as usize;
-        let reducing_axes = (axis..rank).collect::<TVec<usize>>();
+        let reducing_axes =
+            if self.coerce_to_2d { (axis..rank).collect::<TVec<usize>>() } else { tvec!(axis) };
         let maxes = target.wire_node(
             format!("{}.max", name),
             nn::Reduce::new(reducing_axes.clone(), nn::Reducer::Max),
@@ -0,0 +1,82 @@
+use crate::internal::*;
+use crate::ops::nn::{Reduce, Reducer};
+use tract_core::ops::math;
+
+/// Wire (x - mean) / sqrt(var + epsilon), with mean and variance computed
+/// over `axes`.
+///
+/// f32 normalizations over trailing axes use the fused MeanVarNorm op, other
+/// cases are expanded to Reduce and binary ops.
+pub fn wire_mean_var_norm(
+    name: &str,
+    model: &mut TypedModel,
+    x: OutletId,
+    axes: &[usize],
+    epsilon: f32,
+) -> TractResult<OutletId> {
+    let fact = model.outlet_fact(x)?.clone();
+    let rank = fact.rank();
+    let mut sorted = axes.to_vec();
+    sorted.sort();
+    // axes of size 1 do not change the groups, so the fused op applies if all
+    // the other normalized axes come after the other kept axes
+    let wide = |axis: &usize| fact.shape.dim(*axis) != 1.to_dim();
+    let first_normalized = sorted.iter().cloned().filter(wide).next().unwrap_or(rank);
+    let last_kept = (0..rank).filter(|axis| !sorted.contains(axis)).filter(wide).last();
+    if fact.datum_type == f32::datum_type()
+        && last_kept.map(|k| k < first_normalized).unwrap_or(true)
+    {
+        let op = tract_core::ops::nn::MeanVarNorm::new(first_normalized, epsilon);
+        return Ok(model.wire_node(name, op, &[x])?[0]);
+    }
+    let axes: Vec<i64> = sorted.iter().map(|&axis| axis as i64).collect();
+    let mean = Reduce::new(Some(axes.clone()), true, Reducer::Mean).wire(
+        &format!("{}.mean", name),
+        model,
+        &[x],
+    )?[0];
+    let diff = model.wire_node(format!("{}.diff", name), math::sub::bin_typed(), &[x, mean])?[0];
+    let sqr = model.wire_node(format!("{}.sqr", name), math::square(), &[diff])?[0];
+    let var = Reduce::new(Some(axes), true, Reducer::Mean).wire(
+        &format!("{}.variance", name),
+        model,
+        &[sqr],
+    )?[0];
+    let epsilon = tensor0(epsilon).cast_to_dt(fact.datum_type)?.into_owned();
+    let var_sane = model.wire_node(
+        format!("{}.epsilon", name),
+        math::add::unary(epsilon.broadcast_into_rank(rank)?.into_arc_tensor()),
+        &[var],
+    )?[0];
+    let rsqrt = model.wire_node(format!("{}.rsqrt", name), math::rsqrt(), &[var_sane])?[0];
+    Ok(model.wire_node(name, math::mul::bin_typed(), &[diff, rsqrt])?[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(axes: &[usize]) -> TractResult<(TypedModel, Arc<Tensor>)> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", TypedFact::dt_shape(f32::datum_type(), [2, 2].as_ref())?)?;
+        let normed = wire_mean_var_norm("norm", &mut model, x, axes, 1e-5)?;
+        model.set_output_outlets(&[normed])?;
+        let input = tensor2(&[[1f32, 3.], [2., 6.]]);
+        let output = SimplePlan::new(&model)?.run(tvec!(input))?.remove(0);
+        Ok((model, output))
+    }
+
+    #[test]
+    fn mean_var_norm_trailing_axes_are_fused() {
+        let (model, output) = run(&[1]).unwrap();
+        assert!(model.node_by_name("norm").unwrap().op_is::<tract_core::ops::nn::MeanVarNorm>());
+        output.close_enough(&tensor2(&[[-1f32, 1.], [-1., 1.]]), true).unwrap();
+    }
+
+    #[test]
+    fn mean_var_norm_leading_axes_are_expanded() {
+        let (model, output) = run(&[0]).unwrap();
+        assert!(!model.nodes().iter().any(|n| n.op_is::<tract_core::ops::nn::MeanVarNorm>()));
+        output.close_enough(&tensor2(&[[-1f32, -1.], [1., 1.]]), true).unwrap();
+    }
+}
@@ -1,11 +1,13 @@
 mod arg_max_min;
 mod global_pools;
 mod layer_max;
+mod mean_var_norm;
 mod reduce;
 
 pub use arg_max_min::ArgMaxMin;
 pub use global_pools::*;
 pub use layer_max::*;
+pub use mean_var_norm::wire_mean_var_norm;
 pub use reduce::{Reduce, Reducer};
 
 pub use tract_core::ops::nn::{sigmoid, DataFormat};
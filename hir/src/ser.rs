@@ -0,0 +1,63 @@
+//! Serialization of the TypedOps defined in this crate.
+//!
+//! See `tract_core::ser` for the format itself.
+use crate::internal::*;
+use crate::ops::array::{ConstantLike, EyeLike};
+use crate::ops::nn::LayerHardmax;
+use tract_core::ser::{Attrs, Registry};
+
+pub fn register_hir_ops(reg: &mut Registry) {
+    reg.register_op::<LayerHardmax>(
+        "hir.LayerHardmax",
+        |_, op| Ok(Attrs::default().with("axis", op.axis).with("coerce_to_2d", op.coerce_to_2d)),
+        |_, attrs| {
+            Ok(Box::new(LayerHardmax::new(
+                attrs.get("axis")?.as_i64()? as isize,
+                attrs.get("coerce_to_2d")?.as_bool()?,
+            )))
+        },
+    );
+    reg.register_op::<ConstantLike>(
+        "hir.ConstantLike",
+        |_, op| Ok(Attrs::default().with("value", op.value)),
+        |_, attrs| Ok(Box::new(ConstantLike::new(attrs.get("value")?.as_f32()?))),
+    );
+    reg.register_op::<EyeLike>(
+        "hir.EyeLike",
+        |_, op| Ok(Attrs::default().with_opt("dt", op.dt).with("k", op.k)),
+        |_, attrs| {
+            Ok(Box::new(EyeLike::new(
+                attrs.get_opt("dt").map(|dt| dt.as_datum_type()).transpose()?,
+                attrs.get("k")?.as_i64()? as isize,
+            )))
+        },
+    );
+}
+
+/// A registry knowing about the core ops and the ones defined in this crate.
+pub fn registry() -> Registry {
+    let mut registry = tract_core::ser::registry();
+    register_hir_ops(&mut registry);
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_hir_ops() {
+        let registry = registry();
+        let ops: Vec<Box<dyn TypedOp>> = vec![
+            Box::new(LayerHardmax::new(-1, true)),
+            Box::new(ConstantLike::new(0.5)),
+            Box::new(EyeLike::new(Some(f32::datum_type()), 1)),
+            Box::new(EyeLike::new(None, -2)),
+        ];
+        for op in ops {
+            let (name, attrs) = registry.dump_op(&*op).unwrap();
+            let reloaded = registry.load_op(&name, &attrs).unwrap();
+            assert_eq!(format!("{:?}", op), format!("{:?}", reloaded));
+        }
+    }
+}
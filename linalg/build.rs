@@ -9,7 +9,8 @@ fn main() {
     let os = var("CARGO_CFG_TARGET_OS").unwrap();
     let out_dir = path::PathBuf::from(var("OUT_DIR").unwrap());
     if arch == "x86_64" {
-        let files = preprocess_files("x86_64/fma");
+        let mut files = preprocess_files("x86_64/fma");
+        files.extend(preprocess_files("x86_64/avx512"));
         if target == "x86_64-pc-windows-msvc" {
             let mut lib_exe =
                 cc::windows_registry::find(&*target, "lib.exe").expect("Could not find lib.exe");
@@ -73,6 +74,8 @@ fn main() {
     if arch == "aarch64" {
         let files = preprocess_files("arm64/arm64simd");
         cc::Build::new().files(files).static_flag(true).compile("arm64");
+        let files = preprocess_files("arm64/arm64fp16");
+        cc::Build::new().files(files).static_flag(true).compile("arm64fp16");
     }
 }
 
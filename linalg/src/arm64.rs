@@ -1,5 +1,8 @@
+use std::{env, fs};
+mod arm64fp16;
 mod arm64simd;
 
+use crate::f16::f16;
 use crate::Ops;
 
 use crate::frame::MatMatMulImpl;
@@ -7,6 +10,21 @@ use crate::frame::QMatMatMulImpl;
 use crate::frame::SigmoidImpl;
 use crate::frame::TanhImpl;
 
+fn has_fp16_cpuinfo() -> std::io::Result<bool> {
+    let cpu_info = fs::read_to_string("/proc/cpuinfo")?;
+    let fp16 =
+        cpu_info.split("\n").any(|line| line.starts_with("Features") && line.contains("asimdhp"));
+    Ok(fp16)
+}
+
+/// ARMv8.2 half precision vector arithmetic (FEAT_FP16, "asimdhp").
+pub fn has_fp16() -> bool {
+    if let Ok(v) = env::var("TRACT_CPU_AARCH64_FP16") {
+        return v == "true";
+    }
+    has_fp16_cpuinfo().unwrap_or(false)
+}
+
 pub fn plug(ops: &mut Ops) {
     log::info!("arm64simd activated for smmm");
     ops.mmm_f32 = Box::new(|m, k, n| {
@@ -28,4 +46,25 @@ pub fn plug(ops: &mut Ops) {
     });
     ops.sigmoid_f32 = Box::new(|| Box::new(SigmoidImpl::<arm64simd::SigmoidF32x4n, f32>::new()));
     ops.tanh_f32 = Box::new(|| Box::new(TanhImpl::<arm64simd::TanhF32x4n, f32>::new()));
+    if has_fp16() {
+        log::info!("arm64fp16 activated for mmm_f16");
+        ops.mmm_f16 = Box::new(|m, k, n| {
+            Box::new(MatMatMulImpl::<arm64fp16::MatMatMulF16x16x8, f16, f16, f16, f16>::new(
+                m, k, n,
+            ))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn may_have_fp16() {
+        println!("Has fp16 ? {:?}", has_fp16());
+        if let Ok(fp16) = env::var("TRACT_CPU_EXPECT_AARCH64_FP16") {
+            assert_eq!(fp16 == "true", has_fp16());
+        }
+    }
 }
@@ -0,0 +1,41 @@
+use crate::f16::f16;
+use crate::frame::mmm::*;
+
+extern "C" {
+    #[no_mangle]
+    fn arm64fp16_mmm_f16_16x8(op: *const MatMatMulKerSpec<f16, f16, f16, f16>) -> isize;
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct MatMatMulF16x16x8;
+
+impl MatMatMulKer<f16, f16, f16, f16> for MatMatMulF16x16x8 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "arm64fp16"
+    }
+    #[inline(always)]
+    fn mr() -> usize {
+        16
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        8
+    }
+    fn alignment_bytes_packed_a() -> usize {
+        16
+    }
+    fn alignment_bytes_packed_b() -> usize {
+        16
+    }
+    #[inline(never)]
+    fn kernel(op: &MatMatMulKerSpec<f16, f16, f16, f16>) -> isize {
+        unsafe { arm64fp16_mmm_f16_16x8(op) }
+    }
+}
+
+test_mmm_kernel_f16!(
+    crate::arm64::arm64fp16::MatMatMulF16x16x8,
+    test_MatMatMulF16x16x8,
+    crate::arm64::has_fp16()
+);
@@ -137,6 +137,21 @@ impl num_traits::ToPrimitive for f16 {
     }
 }
 
+impl num_traits::FromPrimitive for f16 {
+    fn from_i64(n: i64) -> Option<Self> {
+        Some(f16(half::f16::from_f32(n as f32)))
+    }
+    fn from_u64(n: u64) -> Option<Self> {
+        Some(f16(half::f16::from_f32(n as f32)))
+    }
+    fn from_f32(n: f32) -> Option<Self> {
+        Some(f16(half::f16::from_f32(n)))
+    }
+    fn from_f64(n: f64) -> Option<Self> {
+        Some(f16(half::f16::from_f64(n)))
+    }
+}
+
 impl num_traits::AsPrimitive<f32> for f16 {
     fn as_(self) -> f32 {
         self.0.to_f32()
@@ -302,6 +317,12 @@ impl ops::Mul<&f16> for f16 {
     }
 }
 
+impl ops::MulAssign<f16> for f16 {
+    fn mul_assign(&mut self, other: f16) {
+        *self = *self * other
+    }
+}
+
 impl ops::Div<f16> for f16 {
     type Output = f16;
     fn div(self, other: f16) -> f16 {
@@ -360,3 +381,13 @@ impl std::str::FromStr for f16 {
         s.parse::<f32>().map(|f| f.into())
     }
 }
+
+#[cfg(test)]
+impl proptest::arbitrary::Arbitrary for f16 {
+    type Parameters = ();
+    type Strategy = proptest::strategy::Map<std::ops::Range<f32>, fn(f32) -> f16>;
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        use proptest::strategy::Strategy;
+        (-65504f32..65504f32).prop_map(f16::from)
+    }
+}
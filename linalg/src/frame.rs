@@ -1,4 +1,6 @@
 #[macro_use]
+pub mod exp;
+#[macro_use]
 pub mod lut;
 #[macro_use]
 pub mod mmm;
@@ -14,5 +16,6 @@ pub use pack_b::PackB;
 
 pub use self::mmm::{MatMatMul, MatMatMulImpl, QMatMatMul, QMatMatMulImpl};
 
+pub use self::exp::ExpImpl;
 pub use self::sigmoid::SigmoidImpl;
 pub use self::tanh::TanhImpl;
@@ -0,0 +1,172 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+pub trait ExpFunc {
+    fn exp(self) -> Self;
+}
+
+impl ExpFunc for f32 {
+    fn exp(self) -> f32 {
+        crate::generic::exp::sexp(self)
+    }
+}
+
+pub trait Exp<T>: Send + Sync + Debug + dyn_clone::DynClone
+where
+    T: Copy + Debug + PartialEq + Send + Sync + ExpFunc,
+{
+    fn run(&self, vec: &mut [T]);
+}
+
+dyn_clone::clone_trait_object!(<T> Exp<T> where T: Copy);
+
+#[derive(Debug, Clone, new)]
+pub struct ExpImpl<K, T>
+where
+    T: Copy + Debug + PartialEq + Send + Sync + ExpFunc,
+    K: ExpKer<T> + Clone,
+{
+    phantom: PhantomData<(K, T)>,
+}
+
+impl<K, T> Exp<T> for ExpImpl<K, T>
+where
+    T: Copy + Debug + PartialEq + Send + Sync + ExpFunc,
+    K: ExpKer<T> + Clone,
+{
+    fn run(&self, vec: &mut [T]) {
+        if vec.len() == 0 {
+            return;
+        }
+        let alignment = K::alignment_bytes();
+        let mut offset = 0;
+        unsafe {
+            while offset < vec.len() && &vec[offset] as *const T as usize % alignment != 0 {
+                *vec.get_unchecked_mut(offset) = vec.get_unchecked(offset).exp();
+                offset += 1;
+            }
+            let len = (vec.len() - offset) / K::nr() * K::nr();
+            if len > 0 {
+                K::run(&mut vec[offset..][..len]);
+            }
+            for i in (len + offset)..vec.len() {
+                *vec.get_unchecked_mut(i) = vec.get_unchecked(i).exp();
+            }
+        }
+    }
+}
+
+pub trait ExpKer<T>: Send + Sync + Debug + dyn_clone::DynClone + Clone
+where
+    T: Copy + Debug + PartialEq + Send + Sync,
+{
+    fn name() -> &'static str;
+    fn alignment_bytes() -> usize;
+    fn nr() -> usize;
+    fn run(vec: &mut [T]);
+}
+
+#[cfg(test)]
+#[macro_use]
+pub mod test {
+    use super::ExpKer;
+    use proptest::test_runner::TestCaseResult;
+
+    #[macro_export]
+    macro_rules! exp_frame_tests {
+        ($cond:expr, $ker:ty) => {
+            proptest::proptest! {
+                #[test]
+                fn exp(xs in proptest::collection::vec(-120f32..120.0, 0..100)) {
+                    if $cond {
+                        crate::frame::exp::test::test_exp::<$ker>(&*xs).unwrap()
+                    }
+                }
+            }
+
+            #[test]
+            fn exp_4_magic() {
+                if $cond {
+                    crate::frame::exp::test::test_exp::<$ker>(&[0f32, -20.0, 20.0, 0.0]).unwrap()
+                }
+            }
+
+            #[test]
+            fn exp_4zeros() {
+                if $cond {
+                    crate::frame::exp::test::test_exp::<$ker>(&[0.0; 4]).unwrap();
+                }
+            }
+
+            #[test]
+            fn exp_4_extremes() {
+                if $cond {
+                    crate::frame::exp::test::test_exp::<$ker>(&[
+                        std::f32::NEG_INFINITY,
+                        -100.0,
+                        -87.0,
+                        88.0,
+                    ])
+                    .unwrap()
+                }
+            }
+
+            #[test]
+            fn exp_4_overflows() {
+                if $cond {
+                    crate::frame::exp::test::test_exp::<$ker>(&[
+                        88.7,
+                        88.8,
+                        100.0,
+                        std::f32::INFINITY,
+                    ])
+                    .unwrap()
+                }
+            }
+
+            #[test]
+            fn exp_4_nans() {
+                if $cond {
+                    crate::frame::exp::test::test_exp::<$ker>(&[
+                        std::f32::NAN,
+                        0.0,
+                        -std::f32::NAN,
+                        1.0,
+                    ])
+                    .unwrap()
+                }
+            }
+
+            #[test]
+            fn exp_20_ones() {
+                if $cond {
+                    crate::frame::exp::test::test_exp::<$ker>(&[1.0; 20]).unwrap();
+                }
+            }
+
+            #[test]
+            fn exp_18_zeros() {
+                if $cond {
+                    crate::frame::exp::test::test_exp::<$ker>(&[0.0; 18]).unwrap();
+                }
+            }
+        };
+    }
+
+    pub fn test_exp<K: ExpKer<f32>>(values: &[f32]) -> TestCaseResult {
+        use crate::frame::exp::Exp;
+        let op = crate::frame::exp::ExpImpl::<K, f32>::new();
+        let mut found = values.to_vec();
+        op.run(&mut found);
+        let expected = values.iter().map(|x| x.exp()).collect::<Vec<_>>();
+        proptest::prop_assert!(
+            found.iter().zip(expected.iter()).all(|(f, e)| f == e
+                || f.is_nan() && e.is_nan()
+                || (f - e).abs() <= e.abs() * 1e-5 + 1e-30),
+            "found: {:?} expected: {:?}",
+            found,
+            expected
+        );
+        Ok(())
+    }
+}
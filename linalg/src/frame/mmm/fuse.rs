@@ -233,6 +233,7 @@ pub mod test {
             mod fuse {
                 #[allow(unused_imports)]
                 use crate::frame::mmm::fuse::test;
+                use num_traits::AsPrimitive;
                 use proptest::prelude::*;
 
                 #[test]
@@ -254,7 +255,7 @@ pub mod test {
                     fn return_c_prop(pb in any::<test::ReturnCProblem<$ker, $ta, $tb, $tc, $ti>>()) {
                         if $cond {
                             let got = pb.run();
-                            prop_assert!(got.iter().zip(pb.c.iter()).all(|(g,e)| (*g as f32 - *e as f32).abs() < 1e-7),
+                            prop_assert!(got.iter().zip(pb.c.iter()).all(|(g,e)| (AsPrimitive::<f64>::as_(*g) - AsPrimitive::<f64>::as_(*e)).abs() < 1e-7),
                             "got: {:?}\nexpected: {:?}", pb.run(), pb.c)
                         }
                     }
@@ -297,18 +298,32 @@ pub mod test {
 
                 #[test]
                 fn return_c_max() {
-                    if $cond {
+                    if $cond && test::tile_indices_fit_c::<$ker, $ta, $tb, $tc, $ti>() {
                         test::return_c_max::<$ker, $ta, $tb, $tc, $ti>()
                     }
                 }
 
                 #[test]
                 fn return_c_min() {
-                    if $cond {
+                    if $cond && test::tile_indices_fit_c::<$ker, $ta, $tb, $tc, $ti>() {
                         test::return_c_min::<$ker, $ta, $tb, $tc, $ti>()
                     }
                 }
 
+                #[test]
+                fn return_c_max_wrapped() {
+                    if $cond {
+                        test::return_c_max_wrapped::<$ker, $ta, $tb, $tc, $ti>()
+                    }
+                }
+
+                #[test]
+                fn return_c_min_wrapped() {
+                    if $cond {
+                        test::return_c_min_wrapped::<$ker, $ta, $tb, $tc, $ti>()
+                    }
+                }
+
                 #[test]
                 fn return_c_scalar_mul() {
                     if $cond {
@@ -673,6 +688,78 @@ pub mod test {
         }));
     }
 
+    /// Whether the indices of a tile survive a conversion to C, as
+    /// `return_c_max` and `return_c_min` expect.
+    pub fn tile_indices_fit_c<K, TA, TB, TC, TI>() -> bool
+    where
+        K: MatMatMulKer<TA, TB, TC, TI>,
+        TA: Copy,
+        TB: Copy,
+        TC: Copy + 'static + AsPrimitive<TI>,
+        TI: Copy + Debug + PartialEq + 'static,
+        usize: AsPrimitive<TC> + AsPrimitive<TI>,
+    {
+        let last = K::mr() * K::nr() - 1;
+        let c: TC = last.as_();
+        AsPrimitive::<TI>::as_(c) == last.as_()
+    }
+
+    pub fn return_c_max_wrapped<K, TA, TB, TC, TI>()
+    where
+        K: MatMatMulKer<TA, TB, TC, TI>,
+        TA: Copy,
+        TB: Copy,
+        TC: Copy + PartialEq + 'static + AsPrimitive<TI>,
+        TI: Copy
+            + Add
+            + Mul<Output = TI>
+            + std::cmp::PartialOrd
+            + Zero
+            + Debug
+            + fmt::Display
+            + PartialEq
+            + 'static
+            + AsPrimitive<TC>,
+        usize: AsPrimitive<TC> + AsPrimitive<TI>,
+    {
+        // tile indices wrap around in narrow C types, going negative in i8
+        let len = K::mr() * K::nr();
+        let v: Vec<TC> = (0..len).map(|f| f.as_()).collect();
+        let found = fused_ops::<K, TA, TB, TC, TI>(&*v, &[FusedKerSpec::Max(5.as_())]);
+        assert!(found.iter().zip(v.iter()).all(|(&a, &c)| {
+            let c: TI = c.as_();
+            a == if c > 5.as_() { c.as_() } else { 5.as_() }
+        }));
+    }
+
+    pub fn return_c_min_wrapped<K, TA, TB, TC, TI>()
+    where
+        K: MatMatMulKer<TA, TB, TC, TI>,
+        TA: Copy,
+        TB: Copy,
+        TC: Copy + PartialEq + 'static + AsPrimitive<TI>,
+        TI: Copy
+            + Add
+            + Mul<Output = TI>
+            + std::cmp::PartialOrd
+            + Zero
+            + Debug
+            + fmt::Display
+            + PartialEq
+            + 'static
+            + AsPrimitive<TC>,
+        usize: AsPrimitive<TC> + AsPrimitive<TI>,
+    {
+        // tile indices wrap around in narrow C types, going negative in i8
+        let len = K::mr() * K::nr();
+        let v: Vec<TC> = (0..len).map(|f| f.as_()).collect();
+        let found = fused_ops::<K, TA, TB, TC, TI>(&*v, &[FusedKerSpec::Min(5.as_())]);
+        assert!(found.iter().zip(v.iter()).all(|(&a, &c)| {
+            let c: TI = c.as_();
+            a == if c < 5.as_() { c.as_() } else { 5.as_() }
+        }));
+    }
+
     pub fn return_c_scalar_add<K, TA, TB, TC, TI>()
     where
         K: MatMatMulKer<TA, TB, TC, TI>,
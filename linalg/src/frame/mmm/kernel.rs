@@ -44,6 +44,11 @@ where
     fn nr() -> usize;
     fn alignment_bytes_packed_a() -> usize;
     fn alignment_bytes_packed_b() -> usize;
+    /// Number of consecutive k values packed together for each row of A and
+    /// each column of B.
+    fn packed_k_interleave() -> usize {
+        1
+    }
 }
 
 #[macro_export]
@@ -60,6 +65,63 @@ macro_rules! test_mmm_kernel_f32 {
     };
 }
 
+#[macro_export]
+macro_rules! test_mmm_kernel_f64 {
+    ($k: ty, $id: ident, $cond: expr) => {
+        #[cfg(test)]
+        #[allow(non_snake_case)]
+        mod $id {
+            mmm_kernel_tests!($cond, $k, f64, f64, f64, f64);
+            mmm_frame_tests!($cond, $k, f64, f64, f64, f64);
+            mmm_kernel_fuse_tests!($cond, $k, f64, f64, f64, f64);
+            mmm_s_frame_tests!($cond, $k, f64, f64, f64, f64);
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! test_mmm_kernel_f16 {
+    ($k: ty, $id: ident, $cond: expr) => {
+        #[cfg(test)]
+        #[allow(non_snake_case)]
+        mod $id {
+            mmm_kernel_tests!(
+                $cond,
+                $k,
+                $crate::f16::f16,
+                $crate::f16::f16,
+                $crate::f16::f16,
+                $crate::f16::f16,
+                packed_offsets_f16
+            );
+            mmm_frame_tests!(
+                $cond,
+                $k,
+                $crate::f16::f16,
+                $crate::f16::f16,
+                $crate::f16::f16,
+                $crate::f16::f16
+            );
+            mmm_kernel_fuse_tests!(
+                $cond,
+                $k,
+                $crate::f16::f16,
+                $crate::f16::f16,
+                $crate::f16::f16,
+                $crate::f16::f16
+            );
+            mmm_s_frame_tests!(
+                $cond,
+                $k,
+                $crate::f16::f16,
+                $crate::f16::f16,
+                $crate::f16::f16,
+                $crate::f16::f16
+            );
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! test_mmm_kernel_i8 {
     ($k: ty, $id: ident, $cond: expr) => {
@@ -107,11 +169,40 @@ macro_rules! test_mmm_kernel_u8 {
     };
 }
 
+#[macro_export]
+macro_rules! test_mmm_kernel_u8_i32 {
+    ($k: ty, $id: ident, $cond: expr) => {
+        #[cfg(test)]
+        #[allow(non_snake_case)]
+        mod $id {
+            mmm_kernel_tests!($cond, $k, u8, u8, i32, i32);
+            mmm_kernel_fuse_tests!($cond, $k, u8, u8, i32, i32);
+            qmmm_kernel_fuse_tests!($cond, $k, u8, u8, i32, i32);
+            qmmm_frame_tests!($cond, $k, u8, u8, i32, i32);
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! test_mmm_kernel_u8_i8 {
+    ($k: ty, $id: ident, $cond: expr) => {
+        #[cfg(test)]
+        #[allow(non_snake_case)]
+        mod $id {
+            mmm_kernel_tests!($cond, $k, u8, i8, i32, i32);
+            mmm_kernel_fuse_tests!($cond, $k, u8, i8, i32, i32);
+            qmmm_kernel_fuse_tests!($cond, $k, u8, i8, i32, i32);
+            qmmm_frame_tests!($cond, $k, u8, i8, i32, i32);
+        }
+    };
+}
+
 #[cfg(test)]
 #[macro_use]
 pub mod test {
     use super::*;
     use crate::align::Buffer;
+    use crate::frame::{PackA, PackB};
     use num_traits::{AsPrimitive, One, Zero};
     use proptest::collection::vec;
     use proptest::prelude::*;
@@ -130,6 +221,9 @@ pub mod test {
     #[macro_export]
     macro_rules! mmm_kernel_tests {
         ($cond:expr, $ker:ty, $ta:ty, $tb:ty, $tc:ty, $ti: ty) => {
+            mmm_kernel_tests!($cond, $ker, $ta, $tb, $tc, $ti, packed_offsets);
+        };
+        ($cond:expr, $ker:ty, $ta:ty, $tb:ty, $tc:ty, $ti: ty, $packed_offsets: ident) => {
             mod kernel {
                 use num_traits::Zero;
                 use proptest::prelude::*;
@@ -204,21 +298,21 @@ pub mod test {
                 #[test]
                 fn packed_offsets_k1() {
                     if $cond {
-                        test::packed_offsets::<$ker, $ta, $tb, $tc, $ti>(1, <$ker>::nr())
+                        test::$packed_offsets::<$ker, $ta, $tb, $tc, $ti>(1, <$ker>::nr())
                     }
                 }
 
                 #[test]
                 fn packed_offsets_k2() {
                     if $cond {
-                        test::packed_offsets::<$ker, $ta, $tb, $tc, $ti>(2, <$ker>::nr())
+                        test::$packed_offsets::<$ker, $ta, $tb, $tc, $ti>(2, <$ker>::nr())
                     }
                 }
 
                 #[test]
                 fn packed_offsets_k13() {
                     if $cond {
-                        test::packed_offsets::<$ker, $ta, $tb, $tc, $ti>(13, <$ker>::nr())
+                        test::$packed_offsets::<$ker, $ta, $tb, $tc, $ti>(13, <$ker>::nr())
                     }
                 }
 
@@ -246,7 +340,7 @@ pub mod test {
                 #[test]
                 fn packed_offsets_with_row_stride() {
                     if $cond {
-                        test::packed_offsets::<$ker, $ta, $tb, $tc, $ti>(2, <$ker>::nr() + 5)
+                        test::$packed_offsets::<$ker, $ta, $tb, $tc, $ti>(2, <$ker>::nr() + 5)
                     }
                 }
             }
@@ -307,8 +401,8 @@ pub mod test {
     impl<K, TA, TB, TC, TI> PackedPackedProblem<K, TA, TB, TC, TI>
     where
         K: MatMatMulKer<TA, TB, TC, TI>,
-        TA: 'static + Debug + AsPrimitive<TI>,
-        TB: 'static + Debug + AsPrimitive<TI>,
+        TA: 'static + Debug + AsPrimitive<TI> + Zero,
+        TB: 'static + Debug + AsPrimitive<TI> + Zero,
         TC: Copy + Zero + PartialEq + 'static + Debug,
         TI: Copy + Add + Mul<Output = TI> + Zero + One + Debug + fmt::Display + AsPrimitive<TC>,
         usize: AsPrimitive<TA> + AsPrimitive<TB>,
@@ -332,8 +426,8 @@ pub mod test {
         }
 
         pub fn run(&self) -> Vec<TC> {
-            let pa = Buffer::realign_data(&self.a, K::alignment_bytes_packed_a());
-            let pb = Buffer::realign_data(&self.b, K::alignment_bytes_packed_b());
+            let pa = packed_a::<K, TA, TB, TC, TI>(&self.a, self.k);
+            let pb = packed_b::<K, TA, TB, TC, TI>(&self.b, self.k);
             let mut v = vec![TC::zero(); K::mr() * K::nr()];
             let mut c = if self.trans_c {
                 mmm_stride_storage(&mut v, 1, K::mr())
@@ -416,8 +510,8 @@ pub mod test {
     impl<K, TA, TB, TC, TI> PackedOffsetsProblem<K, TA, TB, TC, TI>
     where
         K: MatMatMulKer<TA, TB, TC, TI>,
-        TA: 'static + Debug + AsPrimitive<TI>,
-        TB: 'static + Debug + AsPrimitive<TI>,
+        TA: 'static + Debug + AsPrimitive<TI> + Zero,
+        TB: 'static + Debug + AsPrimitive<TI> + Zero,
         TC: Copy + Zero + PartialEq + 'static + Debug,
         TI: Copy + Add + Mul<Output = TI> + Zero + One + Debug + fmt::Display + AsPrimitive<TC>,
         usize: AsPrimitive<TA> + AsPrimitive<TB>,
@@ -441,7 +535,7 @@ pub mod test {
         }
 
         pub fn run(&self) -> Vec<TC> {
-            let pa = Buffer::realign_data(&self.a, K::alignment_bytes_packed_a());
+            let pa = packed_a::<K, TA, TB, TC, TI>(&self.a, self.rows_offsets.len());
             let rows_offset: Vec<isize> = self
                 .rows_offsets
                 .iter()
@@ -472,15 +566,15 @@ pub mod test {
     pub fn packed_packed<K, TA, TB, TC, TI>(k: usize)
     where
         K: MatMatMulKer<TA, TB, TC, TI>,
-        TA: Copy + One,
-        TB: Copy + One,
+        TA: Copy + One + Zero + Debug,
+        TB: Copy + One + Zero + Debug,
         TC: Copy + PartialEq + Zero + 'static + Debug,
         TI: Copy + Add + Mul + Zero + Debug + fmt::Display,
         usize: AsPrimitive<TC>,
     {
         let len = K::mr() * K::nr();
-        let pa = Buffer::realign_data(&vec![TA::one(); K::mr() * k], K::alignment_bytes_packed_a());
-        let pb = Buffer::realign_data(&vec![TB::one(); K::nr() * k], K::alignment_bytes_packed_b());
+        let pa = packed_a::<K, TA, TB, TC, TI>(&vec![TA::one(); K::mr() * k], k);
+        let pb = packed_b::<K, TA, TB, TC, TI>(&vec![TB::one(); K::nr() * k], k);
         let mut v: Vec<TC> = vec![TC::zero(); len];
         let mut c = mmm_stride_storage(&mut v, K::nr(), 1);
         let err = K::kernel(&MatMatMulKerSpec {
@@ -495,6 +589,48 @@ pub mod test {
         assert_eq!(v, expected);
     }
 
+    /// Packs one panel of A, stored as `a[m + mr * k]`, for the kernel.
+    pub fn packed_a<K, TA, TB, TC, TI>(a: &[TA], k: usize) -> Buffer<TA>
+    where
+        K: MatMatMulKer<TA, TB, TC, TI>,
+        TA: Copy + Zero + Debug,
+        TB: Copy,
+        TC: Copy,
+        TI: Copy + Debug,
+    {
+        let pack = PackA::new(
+            k,
+            K::mr(),
+            K::mr(),
+            K::alignment_bytes_packed_a(),
+            K::packed_k_interleave(),
+        );
+        let mut pa = Buffer::uninitialized(pack.len(), pack.alignment());
+        pack.pack(pa.as_mut_ptr(), a.as_ptr(), 1, K::mr() as isize);
+        pa
+    }
+
+    /// Packs one panel of B, stored as `b[n + nr * k]`, for the kernel.
+    pub fn packed_b<K, TA, TB, TC, TI>(b: &[TB], k: usize) -> Buffer<TB>
+    where
+        K: MatMatMulKer<TA, TB, TC, TI>,
+        TA: Copy,
+        TB: Copy + Zero + Debug,
+        TC: Copy,
+        TI: Copy + Debug,
+    {
+        let pack = PackB::new(
+            k,
+            K::nr(),
+            K::nr(),
+            K::alignment_bytes_packed_b(),
+            K::packed_k_interleave(),
+        );
+        let mut pb = Buffer::uninitialized(pack.len(), pack.alignment());
+        pack.pack(pb.as_mut_ptr(), b.as_ptr(), K::nr() as isize, 1);
+        pb
+    }
+
     pub fn mmm_stride_storage<T: Copy>(v: &mut [T], rsc: usize, csc: usize) -> PanelStore<T> {
         PanelStore::Strides {
             ptr: v.as_mut_ptr(),
@@ -507,15 +643,41 @@ pub mod test {
     pub fn packed_offsets<K, TA, TB, TC, TI>(k: usize, t: usize)
     where
         K: MatMatMulKer<TA, TB, TC, TI>,
-        TA: Copy + One + AsPrimitive<TI>,
+        TA: Copy + One + Zero + AsPrimitive<TI> + Debug,
         TB: Copy + One + AsPrimitive<TI>,
         TC: Copy + PartialEq + Zero + 'static + Debug,
         TI: Copy + Add + Zero + Mul<Output = TI> + Debug + fmt::Display + 'static + AsPrimitive<TC>,
         usize: AsPrimitive<TA> + AsPrimitive<TB>,
     {
         let a: Vec<TA> = (1..=(k * K::mr())).map(|x| x.as_()).collect();
-        let pa = Buffer::realign_data(&a, K::alignment_bytes_packed_a());
         let b: Vec<TB> = (0..(k * t)).map(|x| x.as_()).collect();
+        packed_offsets_with::<K, TA, TB, TC, TI>(k, t, &a, &b)
+    }
+
+    /// packed_offsets, with operands small enough for the f16 sums to stay exact.
+    pub fn packed_offsets_f16<K, TA, TB, TC, TI>(k: usize, t: usize)
+    where
+        K: MatMatMulKer<TA, TB, TC, TI>,
+        TA: Copy + One + Zero + AsPrimitive<TI> + Debug,
+        TB: Copy + One + AsPrimitive<TI>,
+        TC: Copy + PartialEq + Zero + 'static + Debug,
+        TI: Copy + Add + Zero + Mul<Output = TI> + Debug + fmt::Display + 'static + AsPrimitive<TC>,
+        usize: AsPrimitive<TA> + AsPrimitive<TB>,
+    {
+        let a: Vec<TA> = (1..=(k * K::mr())).map(|x| (x % 11).as_()).collect();
+        let b: Vec<TB> = (0..(k * t)).map(|x| (x % 11).as_()).collect();
+        packed_offsets_with::<K, TA, TB, TC, TI>(k, t, &a, &b)
+    }
+
+    fn packed_offsets_with<K, TA, TB, TC, TI>(k: usize, t: usize, a: &[TA], b: &[TB])
+    where
+        K: MatMatMulKer<TA, TB, TC, TI>,
+        TA: Copy + One + Zero + AsPrimitive<TI> + Debug,
+        TB: Copy + One + AsPrimitive<TI>,
+        TC: Copy + PartialEq + Zero + 'static + Debug,
+        TI: Copy + Add + Zero + Mul<Output = TI> + Debug + fmt::Display + 'static + AsPrimitive<TC>,
+    {
+        let pa = packed_a::<K, TA, TB, TC, TI>(a, k);
         let len = K::mr() * K::nr();
         let mut v: Vec<TC> = vec![TC::zero(); len];
         let mut c = mmm_stride_storage(&mut v, K::nr(), 1);
@@ -538,7 +700,7 @@ pub mod test {
                 let row = ix / K::nr();
                 let col = ix % K::nr();
                 (0..k)
-                    .map(|i| pa[K::mr() * i + row].as_() * b[t * i + col].as_())
+                    .map(|i| a[K::mr() * i + row].as_() * b[t * i + col].as_())
                     .fold(TI::zero(), |s, a| s + a)
                     .as_()
             })
@@ -549,13 +711,13 @@ pub mod test {
     pub fn packed_vec<K, TA, TB, TC, TI>(k: usize)
     where
         K: MatMatMulKer<TA, TB, TC, TI>,
-        TA: Copy + One + AsPrimitive<TI> + Debug,
+        TA: Copy + One + Zero + AsPrimitive<TI> + Debug,
         TB: Copy + One + AsPrimitive<TI> + Debug,
         TC: Copy + PartialEq + Zero + 'static + Debug,
         TI: Copy + Add + Zero + Mul<Output = TI> + Debug + fmt::Display + 'static + AsPrimitive<TC>,
         usize: AsPrimitive<TC>,
     {
-        let pa = Buffer::realign_data(&vec![TA::one(); K::mr() * k], K::alignment_bytes_packed_a());
+        let pa = packed_a::<K, TA, TB, TC, TI>(&vec![TA::one(); K::mr() * k], k);
         let b = vec![TB::one(); k];
         let c: Vec<TC> = vec![TC::zero(); K::mr()];
         let err = K::kernel(&MatMatMulKerSpec {
@@ -6,6 +6,7 @@ use std::ops::{Add, Mul};
 use num_traits::Zero;
 
 use crate::frame::{PackA, PackB};
+use crate::multithread::{current_tract_executor, split, Executor, Shared};
 
 use super::fuse::ScratchSpaceFusedNonLinear;
 use super::*;
@@ -111,12 +112,14 @@ where
     K: MatMatMulKer<TA, TB, TC, TI> + 'static,
 {
     pub fn new(m: usize, k: usize, n: usize) -> MatMatMulImpl<K, TA, TB, TC, TI> {
+        let ki = K::packed_k_interleave();
+        let k_padded = (k + ki - 1) / ki * ki;
         MatMatMulImpl {
             m,
             k,
             n,
-            a_storage: MatrixStoreSpec::Packed { panel_len: (k * K::mr()) },
-            b_storage: MatrixStoreSpec::Packed { panel_len: (k * K::nr()) },
+            a_storage: MatrixStoreSpec::Packed { panel_len: (k_padded * K::mr()) },
+            b_storage: MatrixStoreSpec::Packed { panel_len: (k_padded * K::nr()) },
             c_storage: MatrixStoreSpec::Strides {
                 row_byte_stride: (n * std::mem::size_of::<TC>()) as isize,
                 col_byte_stride: (std::mem::size_of::<TC>()) as isize,
@@ -130,18 +133,18 @@ where
 
 impl<K, TA, TB, TC, TI> MatMatMul<TA, TB, TC, TI> for MatMatMulImpl<K, TA, TB, TC, TI>
 where
-    TA: Copy + Zero + Debug + 'static,
-    TB: Copy + Zero + Debug + 'static,
-    TC: Copy + Debug + 'static,
-    TI: Copy + Add + Mul + Zero + Debug + 'static,
+    TA: Copy + Zero + Debug + Send + Sync + 'static,
+    TB: Copy + Zero + Debug + Send + Sync + 'static,
+    TC: Copy + Debug + Send + Sync + 'static,
+    TI: Copy + Add + Mul + Zero + Debug + Send + Sync + 'static,
     K: MatMatMulKer<TA, TB, TC, TI> + 'static,
 {
     fn a_pack(&self) -> PackA<TA> {
-        PackA::new(self.k, self.m, K::mr(), K::alignment_bytes_packed_a())
+        PackA::new(self.k, self.m, K::mr(), K::alignment_bytes_packed_a(), K::packed_k_interleave())
     }
 
     fn b_pack(&self) -> PackB<TB> {
-        PackB::new(self.k, self.n, K::nr(), K::alignment_bytes_packed_b())
+        PackB::new(self.k, self.n, K::nr(), K::alignment_bytes_packed_b(), K::packed_k_interleave())
     }
 
     fn m(&self) -> usize {
@@ -225,6 +228,62 @@ where
     }
 
     unsafe fn run(&self, a: *const TA, b: *const TB, c: *mut TC, non_linear: &[FusedSpec<TI>]) {
+        let row_panels = (self.m + K::mr() - 1) / K::mr();
+        let col_panels = (self.n + K::nr() - 1) / K::nr();
+        let pool = match current_tract_executor() {
+            Executor::MultiThread(pool)
+                if row_panels * col_panels > 1 && self.m * self.k * self.n >= MIN_PARALLEL_WORK =>
+            {
+                pool
+            }
+            _ => return self.run_tiles(a, b, c, non_linear, 0..row_panels, 0..col_panels),
+        };
+        // split along the rows if there are enough row panels to go around,
+        // along the columns otherwise
+        let chunks = 4 * pool.current_num_threads();
+        let (a, b, c) = (Shared(a), Shared(b), Shared(c));
+        pool.scope(|s| {
+            if row_panels >= pool.current_num_threads() {
+                for rows in split(row_panels, chunks) {
+                    s.spawn(move |_| {
+                        self.run_tiles(a.0, b.0, c.0, non_linear, rows, 0..col_panels)
+                    });
+                }
+            } else {
+                for cols in split(col_panels, chunks) {
+                    s.spawn(move |_| {
+                        self.run_tiles(a.0, b.0, c.0, non_linear, 0..row_panels, cols)
+                    });
+                }
+            }
+        })
+    }
+}
+
+/// Below this many multiply-accumulates, products are computed on one thread.
+const MIN_PARALLEL_WORK: usize = 64 * 1024;
+
+impl<K, TA, TB, TC, TI> MatMatMulImpl<K, TA, TB, TC, TI>
+where
+    TA: Copy + Zero + Debug + Send + Sync + 'static,
+    TB: Copy + Zero + Debug + Send + Sync + 'static,
+    TC: Copy + Debug + Send + Sync + 'static,
+    TI: Copy + Add + Mul + Zero + Debug + Send + Sync + 'static,
+    K: MatMatMulKer<TA, TB, TC, TI> + 'static,
+{
+    /// Computes the C tiles in the `rows` row panels and `cols` column panels.
+    ///
+    /// Full tiles are computed in place, partial ones (on the right and bottom
+    /// edges) go through a temporary tile.
+    unsafe fn run_tiles(
+        &self,
+        a: *const TA,
+        b: *const TB,
+        c: *mut TC,
+        non_linear: &[FusedSpec<TI>],
+        rows: std::ops::Range<usize>,
+        cols: std::ops::Range<usize>,
+    ) {
         let mr = K::mr();
         let nr = K::nr();
         let m = self.m;
@@ -238,69 +297,40 @@ where
             mr,
             nr,
         };
-        let ref mut tmp_tile = tmp_c_storage.wrap(tmpc.as_ptr());
+        let ref tmp_tile = tmp_c_storage.wrap(tmpc.as_ptr());
         let a = self.a_storage.wrap(a);
         let b = self.b_storage.wrap(b);
         let mut c = self.c_storage.wrap(c);
         let ref linear = LinearSpec::k(self.k);
-        for ia in 0..m / mr {
+        for ia in rows {
             let ref a = a.panel_a(ia);
-            for ib in 0..n / nr {
-                let ref b = b.panel_b(nr, ib, nr);
-                let ref direct_c = c.tile_c(ia, ib);
+            let height = mr.min(m - ia * mr);
+            for ib in cols.clone() {
+                let width = nr.min(n - ib * nr);
+                let ref b = b.panel_b(nr, ib, width, K::packed_k_interleave());
                 let non_linear = scratch.for_tile::<TA, TB, TC, K>(non_linear, ia, ib);
-                let err = K::kernel(&MatMatMulKerSpec {
-                    a: a as _,
-                    b: b as _,
-                    c: direct_c as _,
-                    linear,
-                    non_linear,
-                });
-                debug_assert_eq!(err, 0, "Kernel return error {}", err);
-            }
-            if n % nr != 0 {
-                let ref b = b.panel_b(nr, n / nr, n % nr);
-                let ref tmp_tile_c = tmp_tile.tile_c(0, 0);
-                let non_linear = scratch.for_tile::<TA, TB, TC, K>(non_linear, ia, n / nr);
-                let err = K::kernel(&MatMatMulKerSpec {
-                    a: a as _,
-                    b: b as _,
-                    c: tmp_tile_c as _,
-                    linear,
-                    non_linear,
-                });
-                debug_assert_eq!(err, 0, "Kernel return error {}", err);
-                c.set_from_tile(ia, n / nr, mr, n % nr, &*tmpc);
-            }
-        }
-        if m % mr != 0 {
-            let ref panel_a = a.panel_a(m / mr);
-            let ref tmp_tile_c = tmp_tile.tile_c(0, 0);
-            for ib in 0..n / nr {
-                let ref b = b.panel_b(nr, ib, nr);
-                let non_linear = scratch.for_tile::<TA, TB, TC, K>(non_linear, m / mr, ib);
-                let err = K::kernel(&MatMatMulKerSpec {
-                    a: panel_a as _,
-                    b: b as _,
-                    c: tmp_tile_c as _,
-                    linear,
-                    non_linear,
-                });
-                debug_assert_eq!(err, 0, "Kernel return error {}", err);
-                c.set_from_tile(m / mr, ib, m % mr, nr, &*tmpc);
-            }
-            if n % nr != 0 {
-                let ref b = b.panel_b(nr, n / nr, n % nr);
-                let non_linear = scratch.for_tile::<TA, TB, TC, K>(non_linear, m / mr, n / nr);
-                let err = K::kernel(&MatMatMulKerSpec {
-                    a: panel_a as _,
-                    b: b as _,
-                    c: tmp_tile_c as _,
-                    linear,
-                    non_linear,
-                });
-                debug_assert_eq!(err, 0, "Kernel return error {}", err);
-                c.set_from_tile(m / mr, n / nr, m % mr, n % nr, &*tmpc);
+                if height == mr && width == nr {
+                    let ref direct_c = c.tile_c(ia, ib);
+                    let err = K::kernel(&MatMatMulKerSpec {
+                        a: a as _,
+                        b: b as _,
+                        c: direct_c as _,
+                        linear,
+                        non_linear,
+                    });
+                    debug_assert_eq!(err, 0, "Kernel return error {}", err);
+                } else {
+                    let ref tmp_tile_c = tmp_tile.tile_c(0, 0);
+                    let err = K::kernel(&MatMatMulKerSpec {
+                        a: a as _,
+                        b: b as _,
+                        c: tmp_tile_c as _,
+                        linear,
+                        non_linear,
+                    });
+                    debug_assert_eq!(err, 0, "Kernel return error {}", err);
+                    c.set_from_tile(ia, ib, height, width, &*tmpc);
+                }
             }
         }
     }
@@ -410,6 +440,24 @@ pub mod test {
                     }
                 }
 
+                #[test]
+                fn mat_mul_multithread() {
+                    if $cond {
+                        let executor = $crate::multithread::Executor::multithread(3).unwrap();
+                        // enough row panels to split the rows, then a single row panel
+                        for &(m, k, n) in &[(67, 64, 33), (3, 64, 400)] {
+                            let a: Vec<$ta> =
+                                (0..m * k).map(|i| ((i % 3 == 0) as isize).as_()).collect();
+                            let b: Vec<$tb> =
+                                (0..k * n).map(|i| ((i % 2) as isize).as_()).collect();
+                            $crate::multithread::multithread_tract_scope(executor.clone(), || {
+                                test_mat_mat_mul_prep::<$ker, $ta, $tb, $tc, $ti>(m, k, n, &*a, &*b)
+                                    .unwrap()
+                            })
+                        }
+                    }
+                }
+
                 #[test]
                 fn mat_mul_1_2_1() {
                     if $cond {
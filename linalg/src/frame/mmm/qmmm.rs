@@ -93,28 +93,19 @@ where
     TI: Copy + Add + Mul + Zero + Debug + SloppyHash + 'static,
     K: MatMatMulKer<TA, TB, TC, TI> + 'static,
 {
-    fn sum_a_over_k(&self, mut a: *const TA) -> Vec<TI> {
+    fn sum_a_over_k(&self, a: *const TA) -> Vec<TI> {
         match &self.mmm.a_storage {
-            MatrixStoreSpec::Packed { .. } => {
+            MatrixStoreSpec::Packed { panel_len } => {
                 let mr = K::mr();
+                let ki = K::packed_k_interleave();
                 let mut result = vec![TI::zero(); self.m];
                 unsafe {
-                    for p in 0..(self.m / mr) {
-                        for _k in 0..self.k {
-                            for row in 0..mr {
-                                result[p * mr + row] = result[p * mr + row] + (*a).as_();
-                                a = a.offset(1);
-                            }
-                        }
-                    }
-                    if self.m % mr != 0 {
-                        let p = self.m / mr;
-                        for _k in 0..self.k {
-                            for row in 0..mr {
-                                if row < self.m % mr {
-                                    result[p * mr + row] = result[p * mr + row] + (*a).as_();
-                                }
-                                a = a.offset(1);
+                    for p in 0..(self.m + mr - 1) / mr {
+                        let panel = a.add(p * panel_len);
+                        for row in 0..mr.min(self.m - p * mr) {
+                            for k in 0..self.k {
+                                let item = *panel.add((k / ki * mr + row) * ki + k % ki);
+                                result[p * mr + row] = result[p * mr + row] + item.as_();
                             }
                         }
                     }
@@ -125,27 +116,18 @@ where
         }
     }
 
-    fn sum_b_over_k(&self, mut b: *const TB) -> Vec<TI> {
+    fn sum_b_over_k(&self, b: *const TB) -> Vec<TI> {
         let mut result = vec![TI::zero(); self.n];
         match &self.mmm.b_storage {
-            MatrixStoreSpec::Packed { .. } => unsafe {
+            MatrixStoreSpec::Packed { panel_len } => unsafe {
                 let nr = K::nr();
-                for p in 0..(self.n / nr) {
-                    for _k in 0..self.k {
-                        for col in 0..nr {
-                            result[p * nr + col] = result[p * nr + col] + (*b).as_();
-                            b = b.offset(1);
-                        }
-                    }
-                }
-                if self.n % nr != 0 {
-                    let p = self.n / nr;
-                    for _k in 0..self.k {
-                        for col in 0..nr {
-                            if col < self.n % nr {
-                                result[p * nr + col] = result[p * nr + col] + (*b).as_();
-                            }
-                            b = b.offset(1);
+                let ki = K::packed_k_interleave();
+                for p in 0..(self.n + nr - 1) / nr {
+                    let panel = b.add(p * panel_len);
+                    for col in 0..nr.min(self.n - p * nr) {
+                        for k in 0..self.k {
+                            let item = *panel.add((k / ki * nr + col) * ki + k % ki);
+                            result[p * nr + col] = result[p * nr + col] + item.as_();
                         }
                     }
                 }
@@ -220,10 +202,19 @@ where
 
 impl<K, TA, TB, TC, TI> QMatMatMul<TA, TB, TC, TI> for QMatMatMulImpl<K, TA, TB, TC, TI>
 where
-    TA: Copy + Zero + Debug + SloppyHash + AsPrimitive<TI> + 'static,
-    TB: Copy + Zero + Debug + SloppyHash + AsPrimitive<TI> + 'static,
-    TC: Copy + Debug + Bounded + AsPrimitive<TI> + SloppyHash + 'static,
-    TI: Copy + Add + Mul<Output = TI> + Zero + Neg<Output = TI> + Debug + SloppyHash + 'static,
+    TA: Copy + Zero + Debug + SloppyHash + AsPrimitive<TI> + Send + Sync + 'static,
+    TB: Copy + Zero + Debug + SloppyHash + AsPrimitive<TI> + Send + Sync + 'static,
+    TC: Copy + Debug + Bounded + AsPrimitive<TI> + SloppyHash + Send + Sync + 'static,
+    TI: Copy
+        + Add
+        + Mul<Output = TI>
+        + Zero
+        + Neg<Output = TI>
+        + Debug
+        + SloppyHash
+        + Send
+        + Sync
+        + 'static,
     K: MatMatMulKer<TA, TB, TC, TI> + 'static,
     usize: AsPrimitive<TI>,
     i32: AsPrimitive<TI>,
@@ -520,7 +511,13 @@ pub mod test {
                 .collect()
         }
 
-        pub fn run<K: MatMatMulKer<TA, TB, TC, TI>>(&self) -> Vec<TC> {
+        pub fn run<K: MatMatMulKer<TA, TB, TC, TI>>(&self) -> Vec<TC>
+        where
+            TA: Send + Sync,
+            TB: Send + Sync,
+            TC: Send + Sync,
+            TI: Send + Sync,
+        {
             unsafe {
                 let mut c = vec![TC::zero(); self.m * self.n];
                 let mut mmm = QMatMatMulImpl::from(MatMatMulImpl::<K, TA, TB, TC, TI>::new(
@@ -65,10 +65,17 @@ impl<'a, T: Copy> MatrixStore<'a, T> {
         }
     }
 
-    pub(super) unsafe fn panel_b(&self, nr: usize, i: usize, n: usize) -> PanelStore<T> {
+    pub(super) unsafe fn panel_b(
+        &self,
+        nr: usize,
+        i: usize,
+        n: usize,
+        k_interleave: usize,
+    ) -> PanelStore<T> {
         match self {
             MatrixStore::Packed { ptr, panel_len } => {
-                if nr * i + 1 == n {
+                // an interleaved single column is not evenly strided
+                if nr * i + 1 == n && k_interleave == 1 {
                     PanelStore::VecStride {
                         ptr: ptr.offset((panel_len * i) as isize),
                         byte_stride: (nr * std::mem::size_of::<T>()) as isize,
@@ -9,34 +9,45 @@ pub struct PackA<T: Copy + Zero> {
     m: usize,
     mr: usize,
     alignment: usize,
+    k_interleave: usize,
     _boo: PhantomData<T>,
 }
 
 impl<T: Copy + Zero + Debug> PackA<T> {
-    pub fn new(k: usize, m: usize, mr: usize, alignment: usize) -> PackA<T> {
-        PackA { k, m, mr, alignment, _boo: PhantomData }
+    pub fn new(k: usize, m: usize, mr: usize, alignment: usize, k_interleave: usize) -> PackA<T> {
+        PackA { k, m, mr, alignment, k_interleave, _boo: PhantomData }
     }
     pub fn alignment(&self) -> usize {
         self.alignment
     }
 
+    /// k, padded with zeros to a multiple of the interleave.
+    pub fn k_padded(&self) -> usize {
+        (self.k + self.k_interleave - 1) / self.k_interleave * self.k_interleave
+    }
+
     pub fn len(&self) -> usize {
-        (self.m + self.mr - 1) / self.mr * self.mr * self.k
+        (self.m + self.mr - 1) / self.mr * self.mr * self.k_padded()
     }
 
     fn pack_panel_a(&self, pa: *mut T, a: *const T, rsa: isize, csa: isize, rows: usize) {
         let mr = self.mr;
-        for i in 0..self.k {
+        let ki = self.k_interleave;
+        for i in 0..self.k_padded() {
+            let offset = |j: usize| ((i / ki * mr + j) * ki + i % ki) as isize;
             for j in 0..rows {
                 unsafe {
-                    *pa.offset((i * mr + j) as isize) =
+                    *pa.offset(offset(j)) = if i < self.k {
                         *a.offset(i as isize * csa + j as isize * rsa)
+                    } else {
+                        T::zero()
+                    }
                 }
             }
             #[cfg(debug_assertions)]
             for j in rows..mr {
                 unsafe {
-                    *pa.offset((i * mr + j) as isize) = T::zero();
+                    *pa.offset(offset(j)) = T::zero();
                 }
             }
         }
@@ -44,11 +55,12 @@ impl<T: Copy + Zero + Debug> PackA<T> {
 
     pub fn pack(&self, pa: *mut T, a: *const T, rsa: isize, csa: isize) {
         let mr = self.mr;
+        let k = self.k_padded();
         assert!(pa as usize % self.alignment == 0);
         unsafe {
             for p in 0..(self.m / mr) {
                 self.pack_panel_a(
-                    pa.offset((p * mr * self.k) as isize),
+                    pa.offset((p * mr * k) as isize),
                     a.offset((p * mr) as isize * rsa),
                     rsa,
                     csa,
@@ -57,7 +69,7 @@ impl<T: Copy + Zero + Debug> PackA<T> {
             }
             if self.m % mr != 0 {
                 self.pack_panel_a(
-                    pa.offset((self.m / mr * mr * self.k) as isize),
+                    pa.offset((self.m / mr * mr * k) as isize),
                     a.offset((self.m / mr * mr) as isize * rsa),
                     rsa,
                     csa,
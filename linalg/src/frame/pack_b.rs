@@ -9,29 +9,36 @@ pub struct PackB<T: Copy + Zero> {
     n: usize,
     nr: usize,
     alignment: usize,
+    k_interleave: usize,
     _boo: PhantomData<T>,
 }
 
 impl<T: Copy + Zero + Debug> PackB<T> {
-    pub fn new(k: usize, n: usize, nr: usize, alignment: usize) -> PackB<T> {
-        PackB { k, n, nr, alignment, _boo: PhantomData }
+    pub fn new(k: usize, n: usize, nr: usize, alignment: usize, k_interleave: usize) -> PackB<T> {
+        PackB { k, n, nr, alignment, k_interleave, _boo: PhantomData }
     }
 
     pub fn alignment(&self) -> usize {
         self.alignment
     }
 
+    /// k, padded with zeros to a multiple of the interleave.
+    pub fn k_padded(&self) -> usize {
+        (self.k + self.k_interleave - 1) / self.k_interleave * self.k_interleave
+    }
+
     pub fn len(&self) -> usize {
-        (self.n + self.nr - 1) / self.nr * self.nr * self.k
+        (self.n + self.nr - 1) / self.nr * self.nr * self.k_padded()
     }
 
     pub fn pack(&self, pb: *mut T, b: *const T, rsb: isize, csb: isize) {
         let nr = self.nr;
+        let k = self.k_padded();
         assert!(pb as usize % self.alignment == 0);
         unsafe {
             for p in 0..(self.n / nr) {
                 self.pack_panel_b(
-                    pb.offset((p * nr * self.k) as isize),
+                    pb.offset((p * nr * k) as isize),
                     b.offset((p * nr) as isize * csb),
                     rsb,
                     csb,
@@ -40,7 +47,7 @@ impl<T: Copy + Zero + Debug> PackB<T> {
             }
             if self.n % nr != 0 {
                 self.pack_panel_b(
-                    pb.offset((self.n / nr * nr * self.k) as isize),
+                    pb.offset((self.n / nr * nr * k) as isize),
                     b.offset((self.n / nr * nr) as isize * csb),
                     rsb,
                     csb,
@@ -52,24 +59,49 @@ impl<T: Copy + Zero + Debug> PackB<T> {
 
     fn pack_panel_b(&self, pb: *mut T, b: *const T, rsb: isize, csb: isize, cols: usize) {
         let nr = self.nr;
-        for i in 0..self.k {
+        let ki = self.k_interleave;
+        for i in 0..self.k_padded() {
+            let offset = |j: usize| ((i / ki * nr + j) * ki + i % ki) as isize;
             for j in 0..cols {
                 unsafe {
-                    *pb.offset((i * nr + j) as isize) =
+                    *pb.offset(offset(j)) = if i < self.k {
                         *b.offset(j as isize * csb + i as isize * rsb)
+                    } else {
+                        T::zero()
+                    }
                 }
             }
             #[cfg(debug_assertions)]
             for j in cols..nr {
                 unsafe {
-                    *pb.offset((i * nr + j) as isize) = T::zero();
+                    *pb.offset(offset(j)) = T::zero();
                 }
             }
         }
     }
 
     pub fn write_packed_by_rows<'p>(&self, pb: &'p mut [T]) -> PackedWriter<'p, T> {
-        PackedWriter::new(pb, self.nr, self.n, self.k)
+        PackedWriter::new(pb, self.nr, self.n, self.k, self.k_interleave)
+    }
+
+    /// Writer for the rows of a packed buffer, starting at `first_row`.
+    ///
+    /// Writers over disjoint row ranges of the same buffer touch disjoint
+    /// items, so they can be used concurrently.
+    pub unsafe fn write_packed_by_rows_from<'p>(
+        &self,
+        pb: *mut T,
+        first_row: usize,
+    ) -> PackedWriter<'p, T> {
+        let ki = self.k_interleave;
+        PackedWriter::from_ptr(
+            pb.add(first_row / ki * ki * self.nr + first_row % ki),
+            self.nr,
+            self.n,
+            self.k,
+            ki,
+            first_row % ki,
+        )
     }
 }
 
@@ -84,8 +116,11 @@ where
     last_panel_width: usize,
     remain: usize,
     current_panel: usize,
+    k_interleave: usize,
+    current_lane: usize,
     next_panel: isize,
     next_lane: isize,
+    next_group: isize,
     _phantom: PhantomData<&'p T>,
 }
 
@@ -93,19 +128,41 @@ impl<'p, T> PackedWriter<'p, T>
 where
     T: Copy + Debug,
 {
-    pub fn new(data: &'p mut [T], panel_width: usize, mn: usize, k: usize) -> PackedWriter<'p, T> {
+    pub fn new(
+        data: &'p mut [T],
+        panel_width: usize,
+        mn: usize,
+        k: usize,
+        k_interleave: usize,
+    ) -> PackedWriter<'p, T> {
+        unsafe { Self::from_ptr(data.as_mut_ptr(), panel_width, mn, k, k_interleave, 0) }
+    }
+
+    unsafe fn from_ptr(
+        ptr: *mut T,
+        panel_width: usize,
+        mn: usize,
+        k: usize,
+        k_interleave: usize,
+        current_lane: usize,
+    ) -> PackedWriter<'p, T> {
         let panels = (mn + panel_width - 1) / panel_width;
         let last_panel_width = mn - (panels - 1) * panel_width;
+        let k = (k + k_interleave - 1) / k_interleave * k_interleave;
+        // from the end of a row in the last panel to the start of a row in the first one
+        let row_end = ((panels - 1) * panel_width * k + last_panel_width * k_interleave) as isize;
         PackedWriter {
-            ptr: data.as_mut_ptr(),
+            ptr,
             panels,
             panel_width,
             last_panel_width,
             remain: if panels > 1 { panel_width } else { last_panel_width },
             current_panel: 0,
-            next_panel: ((k - 1) * panel_width) as isize,
-            next_lane: panel_width as isize
-                - ((last_panel_width + (panels - 1) * panel_width * k) as isize),
+            k_interleave,
+            current_lane,
+            next_panel: ((k - k_interleave) * panel_width) as isize,
+            next_lane: 1 - row_end,
+            next_group: (panel_width * k_interleave - (k_interleave - 1)) as isize - row_end,
             _phantom: PhantomData,
         }
     }
@@ -115,11 +172,17 @@ where
         unsafe {
             *self.ptr = t;
             self.remain -= 1;
-            self.ptr = self.ptr.offset(1);
+            self.ptr = self.ptr.add(self.k_interleave);
             if self.remain == 0 {
                 self.current_panel += 1;
                 if self.current_panel == self.panels {
-                    self.ptr = self.ptr.offset(self.next_lane);
+                    self.current_lane += 1;
+                    if self.current_lane == self.k_interleave {
+                        self.ptr = self.ptr.offset(self.next_group);
+                        self.current_lane = 0;
+                    } else {
+                        self.ptr = self.ptr.offset(self.next_lane);
+                    }
                     self.current_panel = 0;
                 } else {
                     self.ptr = self.ptr.offset(self.next_panel);
@@ -133,3 +196,37 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::align::Buffer;
+
+    fn writer_matches_pack(k: usize, n: usize, nr: usize, k_interleave: usize) {
+        let b: Vec<u32> = (0..(k * n) as u32).collect();
+        let pack = PackB::new(k, n, nr, 4, k_interleave);
+        let mut packed = Buffer::uninitialized(pack.len(), 4);
+        packed.iter_mut().for_each(|x| *x = 0);
+        pack.pack(packed.as_mut_ptr(), b.as_ptr(), n as isize, 1);
+        let mut written = Buffer::uninitialized(pack.len(), 4);
+        written.iter_mut().for_each(|x| *x = 0);
+        let half = k / 2;
+        let mut writer = pack.write_packed_by_rows(&mut written);
+        b[..half * n].iter().for_each(|&x| writer.write(x));
+        let mut writer = unsafe { pack.write_packed_by_rows_from(written.as_mut_ptr(), half) };
+        b[half * n..].iter().for_each(|&x| writer.write(x));
+        assert_eq!(&*written, &*packed);
+    }
+
+    #[test]
+    fn writer() {
+        writer_matches_pack(7, 11, 4, 1);
+    }
+
+    #[test]
+    fn writer_interleaved() {
+        writer_matches_pack(7, 11, 4, 4);
+        writer_matches_pack(9, 3, 8, 4);
+        writer_matches_pack(5, 8, 8, 2);
+    }
+}
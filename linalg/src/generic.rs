@@ -1,8 +1,10 @@
+pub mod exp;
 pub mod lut;
 pub mod mmm;
 pub mod sigmoid;
 pub mod tanh;
 
+pub use self::exp::SExp4;
 pub use self::lut::GenericLut8;
 pub use self::mmm::GenericMmm4x4;
 pub use self::sigmoid::SSigmoid4;
@@ -0,0 +1,74 @@
+use crate::frame::exp::ExpKer;
+
+use std::f32::consts::LOG2_E;
+
+const LOW: f32 = -104.0;
+// above ln(f32::MAX), so p.2^n overflows to inf as expected
+const HIGH: f32 = 89.0;
+const C1: f32 = 0.693359375;
+const C2: f32 = -2.12194440e-4;
+const P0: f32 = 1.9875691500E-4;
+const P1: f32 = 1.3981999507E-3;
+const P2: f32 = 8.3334519073E-3;
+const P3: f32 = 4.1665795894E-2;
+const P4: f32 = 1.6666665459E-1;
+const P5: f32 = 5.0000001201E-1;
+
+pub fn sexp(x: f32) -> f32 {
+    // comparisons, not max and min, so that NaN passes through
+    let x = if x < LOW {
+        LOW
+    } else if x > HIGH {
+        HIGH
+    } else {
+        x
+    };
+
+    // x = n.ln(2) + r, with |r| <= ln(2)/2
+    let n = (x * LOG2_E).round();
+    let r = x - n * C1;
+    let r = r - n * C2;
+
+    let p = P0;
+    let p = p * r + P1;
+    let p = p * r + P2;
+    let p = p * r + P3;
+    let p = p * r + P4;
+    let p = p * r + P5;
+    let p = p * r * r + r + 1.0;
+
+    // 2^n is built in two halves so that n down to -150 does not underflow the exponent
+    let n = n as i32;
+    let n1 = n >> 1;
+    let n2 = n - n1;
+    p * f32::from_bits(((n1 + 127) as u32) << 23) * f32::from_bits(((n2 + 127) as u32) << 23)
+}
+
+#[derive(Clone, Debug)]
+pub struct SExp4;
+
+impl ExpKer<f32> for SExp4 {
+    fn name() -> &'static str {
+        "generic"
+    }
+
+    fn alignment_bytes() -> usize {
+        16
+    }
+
+    fn nr() -> usize {
+        4
+    }
+
+    fn run(x: &mut [f32]) {
+        debug_assert!(x.len() % Self::nr() == 0);
+        debug_assert!(x.as_ptr() as usize % Self::alignment_bytes() == 0);
+        x.iter_mut().for_each(|px| *px = sexp(*px))
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+pub mod test {
+    exp_frame_tests!(true, crate::generic::exp::SExp4);
+}
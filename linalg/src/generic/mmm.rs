@@ -40,6 +40,24 @@ impl PseudoRightShift for f32 {
     }
 }
 
+impl PseudoRightShift for f64 {
+    fn q_even(self, mult: Self, shift: usize) -> Self {
+        self * mult * 2f64.powi(-(shift as i32))
+    }
+    fn q_to_plus_inf(self, mult: Self, shift: usize) -> Self {
+        self * mult * 2f64.powi(-(shift as i32))
+    }
+}
+
+impl PseudoRightShift for crate::f16::f16 {
+    fn q_even(self, mult: Self, shift: usize) -> Self {
+        self * mult * crate::f16::f16::from(2f32.powi(-(shift as i32)))
+    }
+    fn q_to_plus_inf(self, mult: Self, shift: usize) -> Self {
+        self * mult * crate::f16::f16::from(2f32.powi(-(shift as i32)))
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct GenericMmm4x4<TA, TB, TC, TI>(PhantomData<(TA, TB, TC, TI)>)
 where
@@ -626,9 +644,12 @@ where
 }
 
 test_mmm_kernel_f32!(crate::generic::mmm::GenericMmm4x4<f32, f32, f32, f32>, test_GenericMmm4x4_f32, true);
+test_mmm_kernel_f64!(crate::generic::mmm::GenericMmm4x4<f64, f64, f64, f64>, test_GenericMmm4x4_f64, true);
+test_mmm_kernel_f16!(crate::generic::mmm::GenericMmm4x4<crate::f16::f16, crate::f16::f16, crate::f16::f16, crate::f16::f16>, test_GenericMmm4x4_f16, true);
 test_mmm_kernel_i8!(crate::generic::mmm::GenericMmm4x4<i8, i8, i8, i32>, test_GenericMmm4x4_i8, true);
 test_mmm_kernel_u8!(crate::generic::mmm::GenericMmm4x4<u8, u8, u8, i32>, test_GenericMmm4x4_u8, true);
 test_mmm_kernel_i8_i32!(crate::generic::mmm::GenericMmm4x4<i8, i8, i32, i32>, test_GenericMmm4x4_i8_i32, true);
+test_mmm_kernel_u8_i8!(crate::generic::mmm::GenericMmm4x4<u8, i8, i32, i32>, test_GenericMmm4x4_u8_i8, true);
 
 test_mmm_kernel_f32!(crate::generic::mmm::GenericMmmTest3x2<f32, f32, f32, f32>, test_GenericMmmTest3x2_f32, true);
 test_mmm_kernel_i8!(crate::generic::mmm::GenericMmmTest3x2<i8, i8, i8, i32>, test_GenericMmmTest3x2_i8, true);
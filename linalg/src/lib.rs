@@ -6,16 +6,20 @@ extern crate lazy_static;
 extern crate libc;
 extern crate log;
 extern crate num_traits;
+extern crate rayon;
 #[cfg(test)]
 extern crate proptest;
 
 pub mod align;
 pub mod f16;
 pub mod hash;
+pub mod multithread;
 #[macro_use]
 pub mod frame;
 mod generic;
 
+#[cfg(target_arch = "x86_64")]
+pub mod x86_64_avx512;
 #[cfg(target_arch = "x86_64")]
 pub mod x86_64_fma;
 
@@ -25,6 +29,7 @@ pub mod arm64;
 #[cfg(any(target_arch = "arm", target_arch = "armv7"))]
 pub mod arm32;
 
+pub use self::frame::exp;
 pub use self::frame::lut;
 pub use self::frame::mmm;
 pub use self::frame::sigmoid;
@@ -34,6 +39,18 @@ pub struct Ops {
     pub mmm_f32: Box<
         dyn Fn(usize, usize, usize) -> Box<dyn mmm::MatMatMul<f32, f32, f32, f32>> + Send + Sync,
     >,
+    pub mmm_f64: Box<
+        dyn Fn(usize, usize, usize) -> Box<dyn mmm::MatMatMul<f64, f64, f64, f64>> + Send + Sync,
+    >,
+    pub mmm_f16: Box<
+        dyn Fn(
+                usize,
+                usize,
+                usize,
+            ) -> Box<dyn mmm::MatMatMul<f16::f16, f16::f16, f16::f16, f16::f16>>
+            + Send
+            + Sync,
+    >,
     pub qmmm_i8_i32: Box<
         dyn Fn(usize, usize, usize) -> Box<dyn mmm::QMatMatMul<i8, i8, i32, i32>> + Send + Sync,
     >,
@@ -44,8 +61,13 @@ pub struct Ops {
         Box<dyn Fn(usize, usize, usize) -> Box<dyn mmm::QMatMatMul<u8, u8, u8, i32>> + Send + Sync>,
     pub qmmm_i8_i8:
         Box<dyn Fn(usize, usize, usize) -> Box<dyn mmm::QMatMatMul<i8, i8, i8, i32>> + Send + Sync>,
+    /// u8 A (typically activations) times i8 B (typically weights), to i32
+    pub qmmm_u8_i8: Box<
+        dyn Fn(usize, usize, usize) -> Box<dyn mmm::QMatMatMul<u8, i8, i32, i32>> + Send + Sync,
+    >,
     pub sigmoid_f32: Box<dyn Fn() -> Box<dyn sigmoid::Sigmoid<f32>> + Send + Sync>,
     pub tanh_f32: Box<dyn Fn() -> Box<dyn tanh::Tanh<f32>> + Send + Sync>,
+    pub exp_f32: Box<dyn Fn() -> Box<dyn exp::Exp<f32>> + Send + Sync>,
     pub lut_u8: Box<dyn Fn(&[u8]) -> Box<dyn lut::Lut> + Send + Sync>,
 }
 
@@ -60,6 +82,24 @@ pub fn generic() -> Ops {
                 f32,
             >::new(m, k, n))
         }),
+        mmm_f64: Box::new(|m, k, n| {
+            Box::new(mmm::MatMatMulImpl::<
+                generic::GenericMmm4x4<f64, f64, f64, f64>,
+                f64,
+                f64,
+                f64,
+                f64,
+            >::new(m, k, n))
+        }),
+        mmm_f16: Box::new(|m, k, n| {
+            Box::new(mmm::MatMatMulImpl::<
+                generic::GenericMmm4x4<f16::f16, f16::f16, f16::f16, f16::f16>,
+                f16::f16,
+                f16::f16,
+                f16::f16,
+                f16::f16,
+            >::new(m, k, n))
+        }),
         qmmm_i8_i32: Box::new(|m, k, n| {
             Box::new(mmm::QMatMatMulImpl::from(mmm::MatMatMulImpl::<
                 generic::GenericMmm4x4<i8, i8, i32, i32>,
@@ -96,8 +136,18 @@ pub fn generic() -> Ops {
                 i32,
             >::new(m, k, n)))
         }),
+        qmmm_u8_i8: Box::new(|m, k, n| {
+            Box::new(mmm::QMatMatMulImpl::from(mmm::MatMatMulImpl::<
+                generic::GenericMmm4x4<u8, i8, i32, i32>,
+                u8,
+                i8,
+                i32,
+                i32,
+            >::new(m, k, n)))
+        }),
         sigmoid_f32: Box::new(|| Box::new(sigmoid::SigmoidImpl::<generic::SSigmoid4, f32>::new())),
         tanh_f32: Box::new(|| Box::new(tanh::TanhImpl::<generic::STanh4, f32>::new())),
+        exp_f32: Box::new(|| Box::new(exp::ExpImpl::<generic::SExp4, f32>::new())),
         lut_u8: Box::new(|table: &[u8]| Box::new(lut::LutImpl::<generic::GenericLut8>::new(table))),
     }
 }
@@ -115,7 +165,29 @@ pub fn best() -> Ops {
                     ),
                 )
             });
-            log::info!("mmm_f32 x86_64/fma activated");
+            ops.mmm_f64 = Box::new(|m, k, n| {
+                Box::new(
+                    mmm::MatMatMulImpl::<x86_64_fma::mmm::MatMatMulF64x8x6, f64, f64, f64, f64>::new(
+                        m, k, n,
+                    ),
+                )
+            });
+            log::info!("mmm_f32 and mmm_f64 x86_64/fma activated");
+        }
+        if is_x86_feature_detected!("avx2")
+            && is_x86_feature_detected!("fma")
+            && is_x86_feature_detected!("f16c")
+        {
+            ops.mmm_f16 = Box::new(|m, k, n| {
+                Box::new(mmm::MatMatMulImpl::<
+                    x86_64_fma::mmm::MatMatMulF16x16x6,
+                    f16::f16,
+                    f16::f16,
+                    f16::f16,
+                    f16::f16,
+                >::new(m, k, n))
+            });
+            log::info!("mmm_f16 x86_64/fma (f32 accumulation) activated");
         }
         if is_x86_feature_detected!("avx2") {
             ops.qmmm_i8_i8 = Box::new(|m, k, n| {
@@ -136,7 +208,109 @@ pub fn best() -> Ops {
                     i32,
                 >::new(m, k, n)))
             });
-            log::info!("mmm_i8_i8 and mmm_i8_i32 x86_64/fma activated");
+            ops.qmmm_u8_u8 = Box::new(|m, k, n| {
+                Box::new(mmm::QMatMatMulImpl::from(mmm::MatMatMulImpl::<
+                    x86_64_fma::mmm::MatMatMulU8x8x8,
+                    u8,
+                    u8,
+                    u8,
+                    i32,
+                >::new(m, k, n)))
+            });
+            ops.qmmm_u8_i32 = Box::new(|m, k, n| {
+                Box::new(mmm::QMatMatMulImpl::from(mmm::MatMatMulImpl::<
+                    x86_64_fma::mmm::MatMatMulU8xI32x8x8,
+                    u8,
+                    u8,
+                    i32,
+                    i32,
+                >::new(m, k, n)))
+            });
+            ops.qmmm_u8_i8 = Box::new(|m, k, n| {
+                Box::new(mmm::QMatMatMulImpl::from(mmm::MatMatMulImpl::<
+                    x86_64_fma::mmm::MatMatMulU8xI8xI32x8x8,
+                    u8,
+                    i8,
+                    i32,
+                    i32,
+                >::new(m, k, n)))
+            });
+            log::info!(
+                "mmm_i8_i8, mmm_i8_i32, mmm_u8_u8, mmm_u8_i32 and mmm_u8_i8 x86_64/fma activated"
+            );
+        }
+        if is_x86_feature_detected!("avx512f") {
+            ops.mmm_f32 = Box::new(|m, k, n| {
+                Box::new(mmm::MatMatMulImpl::<
+                    x86_64_avx512::mmm::MatMatMulF32x32x8,
+                    f32,
+                    f32,
+                    f32,
+                    f32,
+                >::new(m, k, n))
+            });
+            log::info!("mmm_f32 x86_64/avx512 activated");
+        }
+        if is_x86_feature_detected!("avx512bw") && is_x86_feature_detected!("avx512vnni") {
+            ops.qmmm_i8_i8 = Box::new(|m, k, n| {
+                Box::new(mmm::QMatMatMulImpl::from(mmm::MatMatMulImpl::<
+                    x86_64_avx512::mmm::MatMatMulI8x32x8,
+                    i8,
+                    i8,
+                    i8,
+                    i32,
+                >::new(m, k, n)))
+            });
+            ops.qmmm_i8_i32 = Box::new(|m, k, n| {
+                Box::new(mmm::QMatMatMulImpl::from(mmm::MatMatMulImpl::<
+                    x86_64_avx512::mmm::MatMatMulI8xI32x32x8,
+                    i8,
+                    i8,
+                    i32,
+                    i32,
+                >::new(m, k, n)))
+            });
+            ops.qmmm_u8_u8 = Box::new(|m, k, n| {
+                Box::new(mmm::QMatMatMulImpl::from(mmm::MatMatMulImpl::<
+                    x86_64_avx512::mmm::MatMatMulU8x32x8,
+                    u8,
+                    u8,
+                    u8,
+                    i32,
+                >::new(m, k, n)))
+            });
+            ops.qmmm_u8_i32 = Box::new(|m, k, n| {
+                Box::new(mmm::QMatMatMulImpl::from(mmm::MatMatMulImpl::<
+                    x86_64_avx512::mmm::MatMatMulU8xI32x32x8,
+                    u8,
+                    u8,
+                    i32,
+                    i32,
+                >::new(m, k, n)))
+            });
+            log::info!(
+                "qmmm_i8_i8, qmmm_i8_i32, qmmm_u8_u8 and qmmm_u8_i32 x86_64/avx512vnni activated"
+            );
+        }
+        if is_x86_feature_detected!("avx512f") {
+            ops.sigmoid_f32 = Box::new(|| {
+                Box::new(sigmoid::SigmoidImpl::<x86_64_avx512::sigmoid::SigmoidF32x16n, f32>::new())
+            });
+            ops.tanh_f32 = Box::new(|| {
+                Box::new(tanh::TanhImpl::<x86_64_avx512::tanh::TanhF32x16n, f32>::new())
+            });
+            ops.exp_f32 =
+                Box::new(|| Box::new(exp::ExpImpl::<x86_64_avx512::exp::ExpF32x16n, f32>::new()));
+            log::info!("sigmoid_f32, tanh_f32 and exp_f32 x86_64/avx512 activated");
+        } else if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+            ops.sigmoid_f32 = Box::new(|| {
+                Box::new(sigmoid::SigmoidImpl::<x86_64_fma::sigmoid::SigmoidF32x8n, f32>::new())
+            });
+            ops.tanh_f32 =
+                Box::new(|| Box::new(tanh::TanhImpl::<x86_64_fma::tanh::TanhF32x8n, f32>::new()));
+            ops.exp_f32 =
+                Box::new(|| Box::new(exp::ExpImpl::<x86_64_fma::exp::ExpF32x8n, f32>::new()));
+            log::info!("sigmoid_f32, tanh_f32 and exp_f32 x86_64/fma activated");
         }
     }
     #[cfg(any(target_arch = "arm", target_arch = "armv7"))]
@@ -178,6 +352,8 @@ mod test {
         + MulAssign
         + PartialOrd
         + Bounded
+        + Send
+        + Sync
     {
         fn strat() -> BoxedStrategy<Self>;
         fn close(&self, other: &Self) -> bool;
@@ -192,6 +368,28 @@ mod test {
         }
     }
 
+    impl Datum for f64 {
+        fn strat() -> BoxedStrategy<Self> {
+            (-1000isize..1000).prop_map(|i| i as f64 / 1000.0).boxed()
+        }
+        fn close(&self, other: &Self) -> bool {
+            (self - other).abs() < 0.001
+        }
+    }
+
+    impl Datum for crate::f16::f16 {
+        fn strat() -> BoxedStrategy<Self> {
+            (-512isize..513).prop_map(|i| (i as f32 / 512.0).as_()).boxed()
+        }
+        fn close(&self, other: &Self) -> bool {
+            // products and partial sums round in f16, and kernels accumulate
+            // in their own order (or in f32): over a few hundred terms, the
+            // results drift apart by a few percent
+            let (a, b) = (self.0.to_f32(), other.0.to_f32());
+            (a - b).abs() <= 0.1 * a.abs().max(b.abs()).max(1.0)
+        }
+    }
+
     impl Datum for i8 {
         fn strat() -> BoxedStrategy<Self> {
             any::<i8>().boxed()
@@ -0,0 +1,149 @@
+//! Intra-op parallelism settings.
+//!
+//! Big matrix products and im2col packing can split their work across a
+//! thread pool. The executor they use is the one set for the current thread
+//! by `multithread_tract_scope`, or the process-wide default otherwise (single
+//! threaded until `set_default_executor` is called).
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+
+#[derive(Debug, Clone)]
+pub enum Executor {
+    SingleThread,
+    MultiThread(Arc<ThreadPool>),
+}
+
+impl Executor {
+    /// An executor backed by a new pool of `threads` threads.
+    pub fn multithread(threads: usize) -> Result<Executor, ThreadPoolBuildError> {
+        if threads <= 1 {
+            return Ok(Executor::SingleThread);
+        }
+        let pool = ThreadPoolBuilder::new().num_threads(threads).build()?;
+        Ok(Executor::MultiThread(Arc::new(pool)))
+    }
+
+    pub fn threads(&self) -> usize {
+        match self {
+            Executor::SingleThread => 1,
+            Executor::MultiThread(pool) => pool.current_num_threads(),
+        }
+    }
+}
+
+impl Default for Executor {
+    fn default() -> Executor {
+        Executor::SingleThread
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref DEFAULT_EXECUTOR: Mutex<Executor> = Mutex::new(Executor::SingleThread);
+}
+
+/// Bumped each time the default executor changes. Threads keep a copy of the
+/// default executor, and only lock it again when this has moved.
+static DEFAULT_EXECUTOR_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static TLS_EXECUTOR_OVERRIDE: RefCell<Option<Executor>> = RefCell::new(None);
+    static TLS_DEFAULT_EXECUTOR: RefCell<(usize, Executor)> =
+        RefCell::new((0, Executor::SingleThread));
+}
+
+/// Sets the executor used by threads with no scoped override.
+pub fn set_default_executor(executor: Executor) {
+    let mut default = DEFAULT_EXECUTOR.lock().unwrap();
+    *default = executor;
+    DEFAULT_EXECUTOR_GENERATION.fetch_add(1, Ordering::Release);
+}
+
+/// The executor for the current thread.
+pub fn current_tract_executor() -> Executor {
+    if let Some(executor) = TLS_EXECUTOR_OVERRIDE.with(|e| e.borrow().clone()) {
+        return executor;
+    }
+    let generation = DEFAULT_EXECUTOR_GENERATION.load(Ordering::Acquire);
+    TLS_DEFAULT_EXECUTOR.with(|cached| {
+        let mut cached = cached.borrow_mut();
+        if cached.0 != generation {
+            *cached = (generation, DEFAULT_EXECUTOR.lock().unwrap().clone());
+        }
+        cached.1.clone()
+    })
+}
+
+/// Runs `f` with `executor` as the current thread executor.
+pub fn multithread_tract_scope<R, F: FnOnce() -> R>(executor: Executor, f: F) -> R {
+    let previous = TLS_EXECUTOR_OVERRIDE.with(|e| e.replace(Some(executor)));
+    struct Restore(Option<Executor>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            TLS_EXECUTOR_OVERRIDE.with(|e| *e.borrow_mut() = previous);
+        }
+    }
+    let _restore = Restore(previous);
+    f()
+}
+
+/// Splits `0..len` in at most `chunks` contiguous, non-empty ranges.
+pub fn split(len: usize, chunks: usize) -> impl Iterator<Item = std::ops::Range<usize>> {
+    let chunks = chunks.max(1).min(len.max(1));
+    (0..chunks).map(move |c| (c * len / chunks)..((c + 1) * len / chunks)).filter(|r| r.len() > 0)
+}
+
+/// A raw pointer that can be moved to the pool threads.
+///
+/// Users are responsible for making concurrent accesses through it disjoint
+/// or read-only.
+#[derive(Copy, Clone, Debug)]
+pub struct Shared<P>(pub P);
+
+// SAFETY: a `*const T` is only used for reading, like a `&T`, which can be
+// sent to and shared with other threads if `T: Sync`.
+unsafe impl<T: Sync> Send for Shared<*const T> {}
+unsafe impl<T: Sync> Sync for Shared<*const T> {}
+
+// SAFETY: threads write to disjoint parts of the pointee through a
+// `*mut T`, like through a split `&mut T`, which can be sent to another
+// thread if `T: Send`. Sharing it only allows copying the pointer, so it
+// needs the same bound.
+unsafe impl<T: Send> Send for Shared<*mut T> {}
+unsafe impl<T: Send> Sync for Shared<*mut T> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_covers_range() {
+        assert_eq!(split(10, 3).collect::<Vec<_>>(), vec!(0..3, 3..6, 6..10));
+        assert_eq!(split(2, 4).collect::<Vec<_>>(), vec!(0..1, 1..2));
+        assert_eq!(split(0, 4).count(), 0);
+    }
+
+    #[test]
+    fn scope_overrides_default() {
+        let executor = Executor::multithread(2).unwrap();
+        assert_eq!(current_tract_executor().threads(), 1);
+        multithread_tract_scope(executor, || {
+            assert_eq!(current_tract_executor().threads(), 2);
+        });
+        assert_eq!(current_tract_executor().threads(), 1);
+        // default changes are seen by threads that already cached it
+        set_default_executor(Executor::multithread(3).unwrap());
+        assert_eq!(current_tract_executor().threads(), 3);
+        let other = std::thread::spawn(|| current_tract_executor().threads());
+        assert_eq!(other.join().unwrap(), 3);
+        multithread_tract_scope(Executor::SingleThread, || {
+            assert_eq!(current_tract_executor().threads(), 1);
+        });
+        set_default_executor(Executor::SingleThread);
+        assert_eq!(current_tract_executor().threads(), 1);
+    }
+}
@@ -0,0 +1,33 @@
+use crate::frame::exp::ExpKer;
+
+extern "C" {
+    #[no_mangle]
+    fn avx512_exp_f32_16n(ptr: *mut f32, count: usize);
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ExpF32x16n;
+
+impl ExpKer<f32> for ExpF32x16n {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "avx512"
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        16
+    }
+    #[inline(always)]
+    fn alignment_bytes() -> usize {
+        64
+    }
+    #[inline(never)]
+    fn run(buf: &mut [f32]) {
+        unsafe { avx512_exp_f32_16n(buf.as_mut_ptr(), buf.len()) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    exp_frame_tests!(is_x86_feature_detected!("avx512f"), crate::x86_64_avx512::exp::ExpF32x16n);
+}
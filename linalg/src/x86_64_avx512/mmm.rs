@@ -0,0 +1,192 @@
+use crate::frame::mmm::*;
+
+extern "C" {
+    #[no_mangle]
+    fn avx512_mmm_f32_32x8(op: *const MatMatMulKerSpec<f32, f32, f32, f32>) -> isize;
+    #[no_mangle]
+    fn avx512_mmm_i8_32x8(op: *const MatMatMulKerSpec<i8, i8, i8, i32>) -> isize;
+    #[no_mangle]
+    fn avx512_mmm_u8_32x8(op: *const MatMatMulKerSpec<u8, u8, u8, i32>) -> isize;
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct MatMatMulF32x32x8;
+
+impl MatMatMulKer<f32, f32, f32, f32> for MatMatMulF32x32x8 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "avx512"
+    }
+    #[inline(always)]
+    fn mr() -> usize {
+        32
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        8
+    }
+    fn alignment_bytes_packed_a() -> usize {
+        64
+    }
+    fn alignment_bytes_packed_b() -> usize {
+        4
+    }
+    #[inline(never)]
+    fn kernel(spec: &MatMatMulKerSpec<f32, f32, f32, f32>) -> isize {
+        unsafe { avx512_mmm_f32_32x8(spec) }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct MatMatMulI8x32x8;
+
+impl MatMatMulKer<i8, i8, i8, i32> for MatMatMulI8x32x8 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "avx512vnni"
+    }
+    #[inline(always)]
+    fn mr() -> usize {
+        32
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        8
+    }
+    fn alignment_bytes_packed_a() -> usize {
+        64
+    }
+    fn alignment_bytes_packed_b() -> usize {
+        4
+    }
+    fn packed_k_interleave() -> usize {
+        4
+    }
+    #[inline(never)]
+    fn kernel(spec: &MatMatMulKerSpec<i8, i8, i8, i32>) -> isize {
+        unsafe { avx512_mmm_i8_32x8(spec) }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct MatMatMulI8xI32x32x8;
+
+impl MatMatMulKer<i8, i8, i32, i32> for MatMatMulI8xI32x32x8 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "avx512vnni"
+    }
+    #[inline(always)]
+    fn mr() -> usize {
+        32
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        8
+    }
+    fn alignment_bytes_packed_a() -> usize {
+        64
+    }
+    fn alignment_bytes_packed_b() -> usize {
+        4
+    }
+    fn packed_k_interleave() -> usize {
+        4
+    }
+    #[inline(never)]
+    fn kernel(spec: &MatMatMulKerSpec<i8, i8, i32, i32>) -> isize {
+        unsafe { avx512_mmm_i8_32x8(spec as *const _ as _) }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct MatMatMulU8x32x8;
+
+impl MatMatMulKer<u8, u8, u8, i32> for MatMatMulU8x32x8 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "avx512vnni"
+    }
+    #[inline(always)]
+    fn mr() -> usize {
+        32
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        8
+    }
+    fn alignment_bytes_packed_a() -> usize {
+        64
+    }
+    fn alignment_bytes_packed_b() -> usize {
+        4
+    }
+    fn packed_k_interleave() -> usize {
+        4
+    }
+    #[inline(never)]
+    fn kernel(spec: &MatMatMulKerSpec<u8, u8, u8, i32>) -> isize {
+        unsafe { avx512_mmm_u8_32x8(spec) }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct MatMatMulU8xI32x32x8;
+
+impl MatMatMulKer<u8, u8, i32, i32> for MatMatMulU8xI32x32x8 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "avx512vnni"
+    }
+    #[inline(always)]
+    fn mr() -> usize {
+        32
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        8
+    }
+    fn alignment_bytes_packed_a() -> usize {
+        64
+    }
+    fn alignment_bytes_packed_b() -> usize {
+        4
+    }
+    fn packed_k_interleave() -> usize {
+        4
+    }
+    #[inline(never)]
+    fn kernel(spec: &MatMatMulKerSpec<u8, u8, i32, i32>) -> isize {
+        unsafe { avx512_mmm_u8_32x8(spec as *const _ as _) }
+    }
+}
+
+test_mmm_kernel_f32!(
+    crate::x86_64_avx512::mmm::MatMatMulF32x32x8,
+    test_MatMatMulF32x32x8,
+    is_x86_feature_detected!("avx512f")
+);
+
+test_mmm_kernel_i8!(
+    crate::x86_64_avx512::mmm::MatMatMulI8x32x8,
+    test_MatMatMulI8x32x8,
+    is_x86_feature_detected!("avx512bw") && is_x86_feature_detected!("avx512vnni")
+);
+
+test_mmm_kernel_i8_i32!(
+    crate::x86_64_avx512::mmm::MatMatMulI8xI32x32x8,
+    test_MatMatMulI8xI32x32x8,
+    is_x86_feature_detected!("avx512bw") && is_x86_feature_detected!("avx512vnni")
+);
+
+test_mmm_kernel_u8!(
+    crate::x86_64_avx512::mmm::MatMatMulU8x32x8,
+    test_MatMatMulU8x32x8,
+    is_x86_feature_detected!("avx512bw") && is_x86_feature_detected!("avx512vnni")
+);
+
+test_mmm_kernel_u8_i32!(
+    crate::x86_64_avx512::mmm::MatMatMulU8xI32x32x8,
+    test_MatMatMulU8xI32x32x8,
+    is_x86_feature_detected!("avx512bw") && is_x86_feature_detected!("avx512vnni")
+);
@@ -0,0 +1,36 @@
+use crate::frame::sigmoid::SigmoidKer;
+
+extern "C" {
+    #[no_mangle]
+    fn avx512_sigmoid_f32_16n(ptr: *mut f32, count: usize);
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct SigmoidF32x16n;
+
+impl SigmoidKer<f32> for SigmoidF32x16n {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "avx512"
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        16
+    }
+    #[inline(always)]
+    fn alignment_bytes() -> usize {
+        64
+    }
+    #[inline(never)]
+    fn run(buf: &mut [f32]) {
+        unsafe { avx512_sigmoid_f32_16n(buf.as_mut_ptr(), buf.len()) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    sigmoid_frame_tests!(
+        is_x86_feature_detected!("avx512f"),
+        crate::x86_64_avx512::sigmoid::SigmoidF32x16n
+    );
+}
@@ -1 +1,4 @@
+pub mod exp;
 pub mod mmm;
+pub mod sigmoid;
+pub mod tanh;
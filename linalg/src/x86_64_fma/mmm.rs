@@ -1,10 +1,19 @@
+use crate::f16::f16;
 use crate::frame::mmm::*;
 
 extern "C" {
     #[no_mangle]
     fn fma_mmm_f32_16x6(op: *const MatMatMulKerSpec<f32, f32, f32, f32>) -> isize;
     #[no_mangle]
+    fn fma_mmm_f64_8x6(op: *const MatMatMulKerSpec<f64, f64, f64, f64>) -> isize;
+    #[no_mangle]
+    fn fma_mmm_f16_16x6(op: *const MatMatMulKerSpec<f16, f16, f16, f16>) -> isize;
+    #[no_mangle]
     fn fma_mmm_i8_8x8(op: *const MatMatMulKerSpec<i8, i8, i8, i32>) -> isize;
+    #[no_mangle]
+    fn fma_mmm_u8_8x8(op: *const MatMatMulKerSpec<u8, u8, u8, i32>) -> isize;
+    #[no_mangle]
+    fn fma_mmm_u8_i8_8x8(op: *const MatMatMulKerSpec<u8, i8, i32, i32>) -> isize;
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -35,6 +44,63 @@ impl MatMatMulKer<f32, f32, f32, f32> for MatMatMulF32x16x6 {
     }
 }
 
+#[derive(Copy, Clone, Debug)]
+pub struct MatMatMulF64x8x6;
+
+impl MatMatMulKer<f64, f64, f64, f64> for MatMatMulF64x8x6 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "fma"
+    }
+    #[inline(always)]
+    fn mr() -> usize {
+        8
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        6
+    }
+    fn alignment_bytes_packed_a() -> usize {
+        32
+    }
+    fn alignment_bytes_packed_b() -> usize {
+        8
+    }
+    #[inline(never)]
+    fn kernel(spec: &MatMatMulKerSpec<f64, f64, f64, f64>) -> isize {
+        unsafe { fma_mmm_f64_8x6(spec) }
+    }
+}
+
+/// f16 operands, accumulated in f32.
+#[derive(Copy, Clone, Debug)]
+pub struct MatMatMulF16x16x6;
+
+impl MatMatMulKer<f16, f16, f16, f16> for MatMatMulF16x16x6 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "fma"
+    }
+    #[inline(always)]
+    fn mr() -> usize {
+        16
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        6
+    }
+    fn alignment_bytes_packed_a() -> usize {
+        32
+    }
+    fn alignment_bytes_packed_b() -> usize {
+        2
+    }
+    #[inline(never)]
+    fn kernel(spec: &MatMatMulKerSpec<f16, f16, f16, f16>) -> isize {
+        unsafe { fma_mmm_f16_16x6(spec) }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct MatMatMulI8x8x8;
 
@@ -91,12 +157,113 @@ impl MatMatMulKer<i8, i8, i32, i32> for MatMatMulI8xI32x8x8 {
     }
 }
 
+#[derive(Copy, Clone, Debug)]
+pub struct MatMatMulU8x8x8;
+
+impl MatMatMulKer<u8, u8, u8, i32> for MatMatMulU8x8x8 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "avx2"
+    }
+    #[inline(always)]
+    fn mr() -> usize {
+        8
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        8
+    }
+    fn alignment_bytes_packed_a() -> usize {
+        32
+    }
+    fn alignment_bytes_packed_b() -> usize {
+        4
+    }
+    #[inline(never)]
+    fn kernel(spec: &MatMatMulKerSpec<u8, u8, u8, i32>) -> isize {
+        unsafe { fma_mmm_u8_8x8(spec) }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct MatMatMulU8xI32x8x8;
+
+impl MatMatMulKer<u8, u8, i32, i32> for MatMatMulU8xI32x8x8 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "avx2"
+    }
+    #[inline(always)]
+    fn mr() -> usize {
+        8
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        8
+    }
+    fn alignment_bytes_packed_a() -> usize {
+        32
+    }
+    fn alignment_bytes_packed_b() -> usize {
+        4
+    }
+    #[inline(never)]
+    fn kernel(spec: &MatMatMulKerSpec<u8, u8, i32, i32>) -> isize {
+        unsafe { fma_mmm_u8_8x8(spec as *const _ as _) }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct MatMatMulU8xI8xI32x8x8;
+
+impl MatMatMulKer<u8, i8, i32, i32> for MatMatMulU8xI8xI32x8x8 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "avx2"
+    }
+    #[inline(always)]
+    fn mr() -> usize {
+        8
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        8
+    }
+    fn alignment_bytes_packed_a() -> usize {
+        32
+    }
+    fn alignment_bytes_packed_b() -> usize {
+        16
+    }
+    fn packed_k_interleave() -> usize {
+        2
+    }
+    #[inline(never)]
+    fn kernel(spec: &MatMatMulKerSpec<u8, i8, i32, i32>) -> isize {
+        unsafe { fma_mmm_u8_i8_8x8(spec) }
+    }
+}
+
 test_mmm_kernel_f32!(
     crate::x86_64_fma::mmm::MatMatMulF32x16x6,
     test_MatMatMulF32x16x6,
     is_x86_feature_detected!("fma")
 );
 
+test_mmm_kernel_f64!(
+    crate::x86_64_fma::mmm::MatMatMulF64x8x6,
+    test_MatMatMulF64x8x6,
+    is_x86_feature_detected!("fma")
+);
+
+test_mmm_kernel_f16!(
+    crate::x86_64_fma::mmm::MatMatMulF16x16x6,
+    test_MatMatMulF16x16x6,
+    is_x86_feature_detected!("avx2")
+        && is_x86_feature_detected!("fma")
+        && is_x86_feature_detected!("f16c")
+);
+
 test_mmm_kernel_i8!(
     crate::x86_64_fma::mmm::MatMatMulI8x8x8,
     test_MatMatMulI8x8x8,
@@ -108,3 +275,21 @@ test_mmm_kernel_i8_i32!(
     test_MatMatMulI8xI32x8x8,
     is_x86_feature_detected!("avx2")
 );
+
+test_mmm_kernel_u8!(
+    crate::x86_64_fma::mmm::MatMatMulU8x8x8,
+    test_MatMatMulU8x8x8,
+    is_x86_feature_detected!("avx2")
+);
+
+test_mmm_kernel_u8_i32!(
+    crate::x86_64_fma::mmm::MatMatMulU8xI32x8x8,
+    test_MatMatMulU8xI32x8x8,
+    is_x86_feature_detected!("avx2")
+);
+
+test_mmm_kernel_u8_i8!(
+    crate::x86_64_fma::mmm::MatMatMulU8xI8xI32x8x8,
+    test_MatMatMulU8xI8xI32x8x8,
+    is_x86_feature_detected!("avx2")
+);
@@ -0,0 +1,36 @@
+use crate::frame::sigmoid::SigmoidKer;
+
+extern "C" {
+    #[no_mangle]
+    fn fma_sigmoid_f32_8n(ptr: *mut f32, count: usize);
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct SigmoidF32x8n;
+
+impl SigmoidKer<f32> for SigmoidF32x8n {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "fma"
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        8
+    }
+    #[inline(always)]
+    fn alignment_bytes() -> usize {
+        32
+    }
+    #[inline(never)]
+    fn run(buf: &mut [f32]) {
+        unsafe { fma_sigmoid_f32_8n(buf.as_mut_ptr(), buf.len()) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    sigmoid_frame_tests!(
+        is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma"),
+        crate::x86_64_fma::sigmoid::SigmoidF32x8n
+    );
+}
@@ -0,0 +1,430 @@
+//! Export of a decluttered TypedModel to ONNX.
+//!
+//! The exporter walks the TypedModel in evaluation order and translates each
+//! node to one or several ONNX nodes, using the exporter function registered
+//! for the op type in the `OnnxExportRegister`. Constants become
+//! initializers, sources become graph inputs. The resulting graph targets
+//! ONNX operator set `OPSET`.
+//!
+//! Streaming dimensions are exported as the symbolic "S" dimension
+//! parameter. Dimensions that are an expression of S are only supported on
+//! tensor shapes, not as op attributes, so it is usually a good idea to
+//! call `concretize_stream_dim` before exporting a streaming model.
+use std::any::TypeId;
+use std::collections::HashSet;
+use std::convert::TryInto;
+
+use crate::pb::attribute_proto::AttributeType;
+use crate::pb::tensor_proto::DataType;
+use crate::pb::*;
+use crate::Onnx;
+use prost::Message;
+use tract_hir::internal::*;
+
+mod ops;
+
+/// ONNX operator set version of the exported models.
+pub const OPSET: i64 = 11;
+
+pub type OnnxExporter = fn(&mut GraphExporter, &TypedNode) -> TractResult<()>;
+
+#[derive(Clone, Default)]
+pub struct OnnxExportRegister(pub HashMap<TypeId, OnnxExporter>);
+
+impl OnnxExportRegister {
+    pub fn insert<O: TypedOp>(&mut self, exporter: OnnxExporter) {
+        self.0.insert(TypeId::of::<O>(), exporter);
+    }
+}
+
+pub fn register_all_exporters(reg: &mut OnnxExportRegister) {
+    ops::register_all_exporters(reg);
+}
+
+/// Builds the ONNX graph for a TypedModel.
+pub struct GraphExporter<'a> {
+    pub framework: &'a Onnx,
+    pub model: &'a TypedModel,
+    pub graph: GraphProto,
+    prefix: String,
+    names: HashMap<OutletId, String>,
+    closures: HashSet<usize>,
+    temp_counter: usize,
+}
+
+impl<'a> GraphExporter<'a> {
+    pub fn new(framework: &'a Onnx, model: &'a TypedModel) -> GraphExporter<'a> {
+        GraphExporter {
+            framework,
+            model,
+            graph: GraphProto::default(),
+            prefix: String::new(),
+            names: HashMap::new(),
+            closures: HashSet::new(),
+            temp_counter: 0,
+        }
+    }
+
+    /// Prefix all names generated by this exporter (useful for subgraphs).
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> GraphExporter<'a> {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Make a source node of the model refer to a value of an enclosing
+    /// graph instead of being a graph input.
+    pub fn with_closure(mut self, source: usize, outer_name: impl Into<String>) -> Self {
+        self.names.insert(OutletId::new(source, 0), outer_name.into());
+        self.closures.insert(source);
+        self
+    }
+
+    /// ONNX node name for a model node.
+    pub fn name(&self, node: &TypedNode) -> String {
+        format!("{}{}", self.prefix, node.name)
+    }
+
+    /// ONNX value name for a model outlet.
+    pub fn outlet_name(&self, outlet: OutletId) -> String {
+        if let Some(name) = self.names.get(&outlet) {
+            return name.clone();
+        }
+        let name = if let Some(label) = self.model.outlet_label(outlet) {
+            label.to_string()
+        } else if outlet.slot == 0 {
+            self.model.node(outlet.node).name.clone()
+        } else {
+            format!("{}.{}", self.model.node(outlet.node).name, outlet.slot)
+        };
+        format!("{}{}", self.prefix, name)
+    }
+
+    /// ONNX value name for the ix-th input of a node.
+    pub fn input(&self, node: &TypedNode, ix: usize) -> String {
+        self.outlet_name(node.inputs[ix])
+    }
+
+    /// ONNX value name for the ix-th output of a node.
+    pub fn output(&self, node: &TypedNode, ix: usize) -> String {
+        self.outlet_name(OutletId::new(node.id, ix))
+    }
+
+    /// A fresh name for an intermediate value.
+    pub fn temp(&mut self, node: &TypedNode, hint: &str) -> String {
+        self.temp_counter += 1;
+        format!("{}{}.{}-{}", self.prefix, node.name, hint, self.temp_counter)
+    }
+
+    /// Add an initializer to the graph, returning its name.
+    pub fn initializer(&mut self, name: impl Into<String>, tensor: &Tensor) -> TractResult<String> {
+        let mut proto: TensorProto = tensor.try_into()?;
+        proto.name = name.into();
+        let name = proto.name.clone();
+        self.graph.initializer.push(proto);
+        Ok(name)
+    }
+
+    /// Add an initializer for a constant attached to a node.
+    pub fn konst(&mut self, node: &TypedNode, hint: &str, tensor: &Tensor) -> TractResult<String> {
+        let name = self.temp(node, hint);
+        self.initializer(name, tensor)
+    }
+
+    /// Add a node to the graph.
+    pub fn node(
+        &mut self,
+        name: impl Into<String>,
+        op_type: &str,
+        inputs: Vec<String>,
+        outputs: Vec<String>,
+    ) -> &mut NodeProto {
+        self.graph.node.push(NodeProto {
+            name: name.into(),
+            op_type: op_type.to_string(),
+            input: inputs,
+            output: outputs,
+            ..NodeProto::default()
+        });
+        self.graph.node.last_mut().unwrap()
+    }
+
+    /// Add a node computing an intermediate value, returning its name.
+    pub fn temp_node(
+        &mut self,
+        node: &TypedNode,
+        op_type: &str,
+        inputs: Vec<String>,
+        attributes: Vec<AttributeProto>,
+    ) -> String {
+        let output = self.temp(node, &op_type.to_lowercase());
+        let name = output.clone();
+        self.node(name, op_type, inputs, vec![output.clone()]).attribute = attributes;
+        output
+    }
+
+    pub fn value_info(&self, name: String, fact: &TypedFact) -> TractResult<ValueInfoProto> {
+        let elem_type: DataType = fact.datum_type.try_into()?;
+        let dim = fact
+            .shape
+            .iter()
+            .map(|d| {
+                use tensor_shape_proto::dimension::Value;
+                let value = if let Ok(d) = d.to_integer() {
+                    Value::DimValue(d as i64)
+                } else {
+                    Value::DimParam(d.to_string())
+                };
+                tensor_shape_proto::Dimension { value: Some(value), ..Default::default() }
+            })
+            .collect();
+        let tensor = type_proto::Tensor {
+            elem_type: elem_type as i32,
+            shape: Some(TensorShapeProto { dim }),
+        };
+        Ok(ValueInfoProto {
+            name,
+            r#type: Some(TypeProto {
+                value: Some(type_proto::Value::TensorType(tensor)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    /// Translate all the nodes of the model, leaving graph inputs and
+    /// outputs to the caller.
+    pub fn export_nodes(&mut self) -> TractResult<()> {
+        let model = self.model;
+        let register = &self.framework.export_register;
+        for &id in &model.eval_order()? {
+            if self.closures.contains(&id) {
+                continue;
+            }
+            let node = model.node(id);
+            let exporter = register
+                .0
+                .get(&node.op().as_any().type_id())
+                .ok_or_else(|| format!("No ONNX exporter for {}", node))?;
+            exporter(self, node).chain_err(|| format!("Exporting {}", node))?;
+        }
+        Ok(())
+    }
+
+    /// Export the whole model, returning the graph.
+    pub fn export(mut self) -> TractResult<GraphProto> {
+        self.graph.name = self.prefix.trim_end_matches('.').to_string();
+        self.export_nodes()?;
+        for &input in self.model.input_outlets()? {
+            if self.closures.contains(&input.node) {
+                continue;
+            }
+            let vi = self.value_info(self.outlet_name(input), self.model.outlet_fact(input)?)?;
+            self.graph.input.push(vi);
+        }
+        for &output in self.model.output_outlets()? {
+            let vi = self.value_info(self.outlet_name(output), self.model.outlet_fact(output)?)?;
+            self.graph.output.push(vi);
+        }
+        Ok(self.graph)
+    }
+}
+
+pub fn attr_int(name: &str, i: i64) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        r#type: AttributeType::Int as i32,
+        i,
+        ..AttributeProto::default()
+    }
+}
+
+pub fn attr_ints(name: &str, ints: impl IntoIterator<Item = i64>) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        r#type: AttributeType::Ints as i32,
+        ints: ints.into_iter().collect(),
+        ..AttributeProto::default()
+    }
+}
+
+pub fn attr_float(name: &str, f: f32) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        r#type: AttributeType::Float as i32,
+        f,
+        ..AttributeProto::default()
+    }
+}
+
+pub fn attr_string(name: &str, s: &str) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        r#type: AttributeType::String as i32,
+        s: s.as_bytes().to_vec(),
+        ..AttributeProto::default()
+    }
+}
+
+pub fn attr_tensor(name: &str, t: &Tensor) -> TractResult<AttributeProto> {
+    Ok(AttributeProto {
+        name: name.to_string(),
+        r#type: AttributeType::Tensor as i32,
+        t: Some(t.try_into()?),
+        ..AttributeProto::default()
+    })
+}
+
+pub fn attr_graph(name: &str, g: GraphProto) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        r#type: AttributeType::Graph as i32,
+        g: Some(g),
+        ..AttributeProto::default()
+    }
+}
+
+impl Onnx {
+    /// Translate a decluttered TypedModel to an ONNX ModelProto.
+    pub fn export(&self, model: &TypedModel) -> TractResult<ModelProto> {
+        let graph = GraphExporter::new(self, model).export()?;
+        Ok(ModelProto {
+            ir_version: 6,
+            opset_import: vec![OperatorSetIdProto { domain: String::new(), version: OPSET }],
+            producer_name: "tract".to_string(),
+            producer_version: env!("CARGO_PKG_VERSION").to_string(),
+            graph: Some(graph),
+            ..ModelProto::default()
+        })
+    }
+
+    /// Write a decluttered TypedModel in the ONNX protobuf format.
+    pub fn write_model(&self, model: &TypedModel, w: &mut dyn std::io::Write) -> TractResult<()> {
+        let proto = self.export(model)?;
+        let mut buf = vec![];
+        proto.encode(&mut buf).map_err(|e| format!("{:?}", e))?;
+        w.write_all(&buf)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tract_hir::tract_core::ops;
+    use tract_hir::tract_core::ops::array::{Slice, TypedConcat};
+    use tract_hir::tract_core::ops::cnn::*;
+    use tract_hir::tract_core::ops::matmul::MatMulUnary;
+    use tract_hir::tract_core::ops::nn::{DataFormat, Reduce, Reducer};
+    use tract_ndarray::{Array2, Array4};
+
+    fn input(shape: &[usize]) -> Tensor {
+        let len = shape.iter().product::<usize>();
+        let data = (0..len).map(|i| ((i * 37) % 11) as f32 / 4.0 - 1.25).collect::<Vec<_>>();
+        tract_ndarray::ArrayD::from_shape_vec(shape, data).unwrap().into()
+    }
+
+    fn round_trip(model: TypedModel) {
+        let input_shape = model
+            .input_fact(0)
+            .unwrap()
+            .shape
+            .as_finite()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect::<TVec<_>>();
+        let input = input(&input_shape);
+        let expected = SimplePlan::new(model.clone()).unwrap().run(tvec!(input.clone())).unwrap();
+        let mut buffer = vec![];
+        crate::onnx().write_model(&model, &mut buffer).unwrap();
+        let reloaded = crate::onnx().model_for_read(&mut &*buffer).unwrap();
+        let found =
+            SimplePlan::new(reloaded.into_optimized().unwrap()).unwrap().run(tvec!(input)).unwrap();
+        assert_eq!(expected.len(), found.len());
+        for (e, f) in expected.iter().zip(found.iter()) {
+            e.close_enough(f, true).unwrap();
+        }
+    }
+
+    #[test]
+    fn conv_pool_matmul() {
+        let mut model = TypedModel::default();
+        let x = model
+            .add_source("x", TypedFact::dt_shape(f32::datum_type(), [1, 3, 8, 8].as_ref()).unwrap())
+            .unwrap();
+        let kernel = Array4::from_shape_fn((4, 3, 3, 3), |(o, i, y, x)| {
+            ((o * 7 + i * 5 + y * 3 + x) % 5) as f32 / 4.0 - 0.5
+        });
+        let conv = ConvUnary {
+            pool_spec: PoolSpec::new(
+                DataFormat::NCHW,
+                tvec!(3, 3),
+                PaddingSpec::SameUpper,
+                None,
+                None,
+                Some(4),
+            ),
+            kernel_fmt: KernelFormat::OIHW,
+            kernel: Tensor::from(kernel).into_arc_tensor(),
+            group: 1,
+            bias: Some(rctensor1(&[0.5f32, -0.5, 1.0, 0.0])),
+            q_params: None,
+        };
+        let wire = model.wire_node("conv", conv, &[x]).unwrap();
+        let relu = ops::math::max::unary(tensor4(&[[[[0f32]]]]).into_arc_tensor());
+        let wire = model.wire_node("relu", relu, &wire).unwrap();
+        let pool = MaxPool {
+            pool_spec: PoolSpec::new(
+                DataFormat::NCHW,
+                tvec!(2, 2),
+                PaddingSpec::Valid,
+                None,
+                Some(tvec!(2, 2)),
+                None,
+            ),
+            with_index_outputs: None,
+        };
+        let wire = model.wire_node("pool", pool, &wire).unwrap();
+        let reshape = AxisOp::Reshape(2, tvec!(4.to_dim(), 4.to_dim()), tvec!(16.to_dim()));
+        let wire = model.wire_node("reshape", reshape, &wire).unwrap();
+        let a = Array2::from_shape_fn((5, 4), |(m, k)| ((m * 3 + k) % 3) as f32 - 1.0);
+        let mm = MatMulUnary::new(Tensor::from(a).into_arc_tensor(), false, false, false, None);
+        let wire = model.wire_node("mm", mm, &wire).unwrap();
+        model.set_output_outlets(&wire).unwrap();
+        round_trip(model);
+    }
+
+    #[test]
+    fn element_wise_and_reductions() {
+        let mut model = TypedModel::default();
+        let x = model
+            .add_source("x", TypedFact::dt_shape(f32::datum_type(), [2, 3, 4].as_ref()).unwrap())
+            .unwrap();
+        let exp = model.wire_node("exp", ops::math::exp(), &[x]).unwrap();
+        let sigmoid = model.wire_node("sigmoid", ops::nn::sigmoid(), &[x]).unwrap();
+        let mul = model.wire_node("mul", ops::math::mul::bin_typed(), &[exp[0], sigmoid[0]]);
+        let sub = model.wire_node("sub", ops::math::sub::bin_typed(), &[mul.unwrap()[0], x]);
+        let concat = TypedConcat::concat_vars(1, 2);
+        let concat = model.wire_node("concat", concat, &[sub.unwrap()[0], x]).unwrap();
+        let permute = model.wire_node("permute", AxisOp::Move(2, 0), &concat).unwrap();
+        let reduce = Reduce::new(tvec!(2), Reducer::Sum);
+        let reduce = model.wire_node("reduce", reduce, &permute).unwrap();
+        model.set_output_outlets(&reduce).unwrap();
+        round_trip(model);
+    }
+
+    #[test]
+    fn slices() {
+        let mut model = TypedModel::default();
+        let x = model
+            .add_source("x", TypedFact::dt_shape(f32::datum_type(), [2, 6].as_ref()).unwrap())
+            .unwrap();
+        let head = model.wire_node("head", Slice::new(1, 1, 4), &[x]).unwrap();
+        let tail = Slice::new(1, 2.to_dim(), 6.to_dim());
+        let tail = model.wire_node("tail", tail, &[x]).unwrap();
+        let every_other = ops::Downsample::new(1, 2, 1);
+        let every_other = model.wire_node("every_other", every_other, &[x]).unwrap();
+        model.set_output_outlets(&[head[0], tail[0], every_other[0]]).unwrap();
+        round_trip(model);
+    }
+}
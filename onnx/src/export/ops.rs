@@ -0,0 +1,770 @@
+use super::{
+    attr_float, attr_graph, attr_int, attr_ints, attr_string, GraphExporter, OnnxExportRegister,
+};
+use crate::pb::tensor_proto::DataType;
+use crate::pb::AttributeProto;
+use std::convert::TryInto;
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops;
+use tract_hir::tract_core::ops::array::{
+    ConcatSlice, FiniteReshape, Gather, MultiBroadcastTo, Pad, PadMode, Slice, Tile, TypedConcat,
+};
+use tract_hir::tract_core::ops::binary::{BinMiniOp, MergeOp, MergeOpUnicast, TypedBinOp, UnaryOp};
+use tract_hir::tract_core::ops::cnn::{
+    AvgPool, ConvUnary, DeconvUnary, KernelFormat, MaxPool, PaddingSpec, PoolSpec,
+};
+use tract_hir::tract_core::ops::control_flow::{If, Loop};
+use tract_hir::tract_core::ops::element_wise::ElementWiseOp;
+use tract_hir::tract_core::ops::matmul::{MatMul, MatMulUnary};
+use tract_hir::tract_core::ops::nn::{ArgMaxMin, DataFormat, Reduce, Reducer};
+use tract_hir::tract_core::ops::quant::{
+    DequantizeLinearF32, QParams, QuantizeLinearI8, QuantizeLinearU8,
+};
+use tract_hir::tract_core::ops::resize::{CoordTransformer, Interpolator, Nearest, Resize};
+use tract_hir::tract_core::ops::scan::{InputMapping, Scan, StateInitializer};
+use tract_hir::tract_core::ops::source::TypedSource;
+
+pub fn register_all_exporters(reg: &mut OnnxExportRegister) {
+    reg.insert::<TypedSource>(|_, _| Ok(()));
+    reg.insert::<ops::konst::Const>(konst);
+    reg.insert::<ops::identity::Identity>(|e, node| {
+        simple(e, node, "Identity", vec![e.input(node, 0)], vec![])
+    });
+    reg.insert::<ops::logic::Iff>(|e, node| {
+        let inputs = (0..3).map(|ix| e.input(node, ix)).collect();
+        simple(e, node, "Where", inputs, vec![])
+    });
+
+    reg.insert::<TypedBinOp>(|e, node| {
+        let op = node.op_as::<TypedBinOp>().unwrap();
+        bin(e, node, &*op.0, e.input(node, 0), e.input(node, 1))
+    });
+    reg.insert::<MergeOp>(|e, node| {
+        let op = node.op_as::<MergeOp>().unwrap();
+        bin(e, node, &*op.0, e.input(node, 0), e.input(node, 1))
+    });
+    reg.insert::<MergeOpUnicast>(|e, node| {
+        let op = node.op_as::<MergeOpUnicast>().unwrap();
+        bin(e, node, &*op.0, e.input(node, 0), e.input(node, 1))
+    });
+    reg.insert::<UnaryOp>(|e, node| {
+        let op = node.op_as::<UnaryOp>().unwrap();
+        let a = e.konst(node, "a", &op.a)?;
+        bin(e, node, &*op.mini_op, a, e.input(node, 0))
+    });
+    reg.insert::<ElementWiseOp>(element_wise);
+    reg.insert::<DequantizeLinearF32>(dequantize_linear);
+
+    reg.insert::<ops::change_axes::AxisOp>(axis_op);
+    reg.insert::<Slice<TDim>>(|e, node| {
+        let op = node.op_as::<Slice<TDim>>().unwrap();
+        let start = op.start.to_integer()? as i64;
+        let end = if let Ok(end) = op.end.to_integer() {
+            end as i64
+        } else if op.end == e.model.outlet_fact(node.inputs[0])?.shape.dim(op.axis) {
+            std::i64::MAX
+        } else {
+            bail!("Can not export slice end {}", op.end)
+        };
+        slice(e, node, op.axis, start, end, None)
+    });
+    reg.insert::<Slice<usize>>(|e, node| {
+        let op = node.op_as::<Slice<usize>>().unwrap();
+        slice(e, node, op.axis, op.start as i64, op.end as i64, None)
+    });
+    reg.insert::<ops::Downsample>(|e, node| {
+        let op = node.op_as::<ops::Downsample>().unwrap();
+        if op.stride <= 0 {
+            bail!("Can not export downsampling with negative stride")
+        }
+        slice(e, node, op.axis, op.modulo as i64, std::i64::MAX, Some(op.stride as i64))
+    });
+    reg.insert::<TypedConcat>(concat);
+    reg.insert::<Gather>(|e, node| {
+        let op = node.op_as::<Gather>().unwrap();
+        let inputs = vec![e.input(node, 0), e.input(node, 1)];
+        simple(e, node, "Gather", inputs, vec![attr_int("axis", op.axis as i64)])
+    });
+    reg.insert::<Tile>(|e, node| {
+        let op = node.op_as::<Tile>().unwrap();
+        let repeats = op.multipliers.iter().map(|&m| m as i64).collect::<Vec<_>>();
+        let repeats = e.konst(node, "repeats", &tensor1(&repeats))?;
+        simple(e, node, "Tile", vec![e.input(node, 0), repeats], vec![])
+    });
+    reg.insert::<MultiBroadcastTo>(multi_broadcast_to);
+    reg.insert::<FiniteReshape>(|e, node| {
+        let op = node.op_as::<FiniteReshape>().unwrap();
+        let shape = op.shape.iter().map(|&d| d as i64).collect::<Vec<_>>();
+        let shape = e.konst(node, "shape", &tensor1(&shape))?;
+        simple(e, node, "Reshape", vec![e.input(node, 0), shape], vec![])
+    });
+    reg.insert::<Pad>(pad);
+
+    reg.insert::<Reduce>(|e, node| {
+        let op = node.op_as::<Reduce>().unwrap();
+        let op_type = match op.reducer {
+            Reducer::Max => "ReduceMax",
+            Reducer::Min => "ReduceMin",
+            Reducer::Prod => "ReduceProd",
+            Reducer::Sum => "ReduceSum",
+        };
+        let attrs =
+            vec![attr_ints("axes", op.axes.iter().map(|&a| a as i64)), attr_int("keepdims", 1)];
+        simple(e, node, op_type, vec![e.input(node, 0)], attrs)
+    });
+    reg.insert::<ArgMaxMin>(|e, node| {
+        let op = node.op_as::<ArgMaxMin>().unwrap();
+        let op_type = if op.max { "ArgMax" } else { "ArgMin" };
+        let attrs =
+            vec![attr_int("axis", op.axis as i64), attr_int("keepdims", op.keepdims as i64)];
+        simple(e, node, op_type, vec![e.input(node, 0)], attrs)
+    });
+
+    reg.insert::<MatMul>(|e, node| {
+        let op = node.op_as::<MatMul>().unwrap();
+        let a = (e.input(node, 0), e.model.outlet_fact(node.inputs[0])?.rank(), op.a_trans);
+        let b = (e.input(node, 1), e.model.outlet_fact(node.inputs[1])?.rank(), op.b_trans);
+        matmul(e, node, a, b, op.c_trans, op.q_params.as_ref())
+    });
+    reg.insert::<MatMulUnary>(|e, node| {
+        let op = node.op_as::<MatMulUnary>().unwrap();
+        let a = (e.konst(node, "a", &op.a)?, op.a.rank(), op.a_trans);
+        let b = (e.input(node, 0), e.model.outlet_fact(node.inputs[0])?.rank(), op.b_trans);
+        matmul(e, node, a, b, op.c_trans, op.q_params.as_ref())
+    });
+    reg.insert::<ConvUnary>(conv);
+    reg.insert::<DeconvUnary>(deconv);
+    reg.insert::<MaxPool>(|e, node| {
+        let op = node.op_as::<MaxPool>().unwrap();
+        if op.with_index_outputs.is_some() {
+            bail!("Can not export max pooling with index outputs")
+        }
+        let attrs = pool_attrs(&op.pool_spec, false)?;
+        nchw_wrapped(e, node, op.pool_spec.data_format, "MaxPool", vec![], attrs)
+    });
+    reg.insert::<AvgPool>(|e, node| {
+        let op = node.op_as::<AvgPool>().unwrap();
+        if op.pool_spec.dilations().iter().any(|&d| d != 1) {
+            bail!("Can not export dilated average pooling")
+        }
+        let mut attrs = pool_attrs(&op.pool_spec, false)?;
+        attrs.push(attr_int("count_include_pad", op.count_include_pad as i64));
+        nchw_wrapped(e, node, op.pool_spec.data_format, "AveragePool", vec![], attrs)
+    });
+    reg.insert::<Scan>(scan);
+    reg.insert::<If>(if_);
+    reg.insert::<Loop>(loop_);
+    reg.insert::<Resize>(resize);
+}
+
+/// Emit a single ONNX node computing the first output of `node`.
+fn simple(
+    e: &mut GraphExporter,
+    node: &TypedNode,
+    op_type: &str,
+    inputs: Vec<String>,
+    attributes: Vec<AttributeProto>,
+) -> TractResult<()> {
+    e.node(e.name(node), op_type, inputs, vec![e.output(node, 0)]).attribute = attributes;
+    Ok(())
+}
+
+fn konst(e: &mut GraphExporter, node: &TypedNode) -> TractResult<()> {
+    let op = node.op_as::<ops::konst::Const>().unwrap();
+    e.initializer(e.output(node, 0), &op.0)?;
+    Ok(())
+}
+
+fn bin(
+    e: &mut GraphExporter,
+    node: &TypedNode,
+    mini: &dyn BinMiniOp,
+    a: String,
+    b: String,
+) -> TractResult<()> {
+    let (op_type, inputs, attrs) = match mini.name() {
+        "Add" | "Sub" | "Mul" | "Div" | "Pow" | "Min" | "Max" => (mini.name(), vec![a, b], vec![]),
+        "And" | "Or" | "Xor" | "Greater" => (mini.name(), vec![a, b], vec![]),
+        "Equals" => ("Equal", vec![a, b], vec![]),
+        "Lesser" => ("Less", vec![a, b], vec![]),
+        "LesserEqual" => ("Not", vec![e.temp_node(node, "Greater", vec![a, b], vec![])], vec![]),
+        "GreaterEqual" => ("Not", vec![e.temp_node(node, "Less", vec![a, b], vec![])], vec![]),
+        "Rem" => ("Mod", vec![a, b], vec![attr_int("fmod", 1)]),
+        "FlippedPow" => ("Pow", vec![b, a], vec![]),
+        "ShiftLeft" => ("BitShift", vec![a, b], vec![attr_string("direction", "LEFT")]),
+        "ShiftRight" => ("BitShift", vec![a, b], vec![attr_string("direction", "RIGHT")]),
+        "FlippedShiftLeft" => ("BitShift", vec![b, a], vec![attr_string("direction", "LEFT")]),
+        "FlippedShiftRight" => ("BitShift", vec![b, a], vec![attr_string("direction", "RIGHT")]),
+        name => bail!("No ONNX equivalent for {}", name),
+    };
+    simple(e, node, op_type, inputs, attrs)
+}
+
+fn element_wise(e: &mut GraphExporter, node: &TypedNode) -> TractResult<()> {
+    let mini = &*node.op_as::<ElementWiseOp>().unwrap().0;
+    let input = e.input(node, 0);
+    let name = mini.name();
+    let (op_type, inputs, attrs) = match &*name {
+        "Abs" | "Exp" | "Sqrt" | "Ceil" | "Floor" | "Neg" | "Sign" | "Not" | "Sigmoid" => {
+            (&*name, vec![input], vec![])
+        }
+        "Cos" | "Sin" | "Tan" | "Acos" | "Asin" | "Atan" => (&*name, vec![input], vec![]),
+        "Cosh" | "Sinh" | "Tanh" | "Acosh" | "Asinh" | "Atanh" => (&*name, vec![input], vec![]),
+        "Ln" => ("Log", vec![input], vec![]),
+        "Recip" => ("Reciprocal", vec![input], vec![]),
+        "RoundHalfToEven" => ("Round", vec![input], vec![]),
+        "Rsqrt" => ("Reciprocal", vec![e.temp_node(node, "Sqrt", vec![input], vec![])], vec![]),
+        "Square" => ("Mul", vec![input.clone(), input], vec![]),
+        "Cast" => {
+            let to = mini.downcast_ref::<ops::cast::Cast>().unwrap().to;
+            let to: DataType = to.try_into()?;
+            ("Cast", vec![input], vec![attr_int("to", to as i64)])
+        }
+        "QuantizeLinearU8" => {
+            let op = mini.downcast_ref::<QuantizeLinearU8>().unwrap();
+            let scale = e.konst(node, "scale", &tensor0(op.scale.recip()))?;
+            let zero_point = e.konst(node, "zero_point", &tensor0(op.zero_point))?;
+            ("QuantizeLinear", vec![input, scale, zero_point], vec![])
+        }
+        "QuantizeLinearI8" => {
+            let op = mini.downcast_ref::<QuantizeLinearI8>().unwrap();
+            let scale = e.konst(node, "scale", &tensor0(op.scale.recip()))?;
+            let zero_point = e.konst(node, "zero_point", &tensor0(op.zero_point))?;
+            ("QuantizeLinear", vec![input, scale, zero_point], vec![])
+        }
+        name => bail!("No ONNX equivalent for {}", name),
+    };
+    simple(e, node, op_type, inputs, attrs)
+}
+
+fn dequantize_linear(e: &mut GraphExporter, node: &TypedNode) -> TractResult<()> {
+    let op = node.op_as::<DequantizeLinearF32>().unwrap();
+    let dt = e.model.outlet_fact(node.inputs[0])?.datum_type;
+    let scale = e.konst(node, "scale", &tensor0(op.scale))?;
+    let zero_point = tensor0(op.zero_point).cast_to_dt(dt)?.into_owned();
+    let zero_point = e.konst(node, "zero_point", &zero_point)?;
+    simple(e, node, "DequantizeLinear", vec![e.input(node, 0), scale, zero_point], vec![])
+}
+
+fn axis_op(e: &mut GraphExporter, node: &TypedNode) -> TractResult<()> {
+    use ops::change_axes::AxisOp;
+    let op = node.op_as::<AxisOp>().unwrap();
+    let input = e.input(node, 0);
+    match op {
+        AxisOp::Add(axis) => {
+            simple(e, node, "Unsqueeze", vec![input], vec![attr_ints("axes", vec![*axis as i64])])
+        }
+        AxisOp::Rm(axis) => {
+            simple(e, node, "Squeeze", vec![input], vec![attr_ints("axes", vec![*axis as i64])])
+        }
+        AxisOp::Move(from, to) => {
+            let rank = e.model.outlet_fact(node.inputs[0])?.rank();
+            let mut perm: Vec<i64> = (0..rank as i64).collect();
+            let axis = perm.remove(*from);
+            perm.insert(*to, axis);
+            simple(e, node, "Transpose", vec![input], vec![attr_ints("perm", perm)])
+        }
+        AxisOp::Reshape(at, _, _) => {
+            let output = e.model.outlet_fact(OutletId::new(node.id, 0))?;
+            let mut shape = vec![];
+            let mut wildcard = false;
+            for (ix, d) in output.shape.iter().enumerate() {
+                if let Ok(d) = d.to_integer() {
+                    shape.push(d as i64)
+                } else if ix < *at {
+                    shape.push(0)
+                } else if !wildcard {
+                    wildcard = true;
+                    shape.push(-1)
+                } else {
+                    bail!("Can not export reshape to {:?}", output.shape)
+                }
+            }
+            let shape = e.konst(node, "shape", &tensor1(&shape))?;
+            simple(e, node, "Reshape", vec![input, shape], vec![])
+        }
+    }
+}
+
+fn slice(
+    e: &mut GraphExporter,
+    node: &TypedNode,
+    axis: usize,
+    start: i64,
+    end: i64,
+    step: Option<i64>,
+) -> TractResult<()> {
+    // spell out all axes instead of using the optional axes input
+    let input = e.model.outlet_fact(node.inputs[0])?;
+    let rank = input.rank();
+    let mut starts = vec![0; rank];
+    let mut ends: Vec<i64> = input
+        .shape
+        .iter()
+        .map(|d| d.to_integer().map(|d| d as i64).unwrap_or(std::i64::MAX))
+        .collect();
+    starts[axis] = start;
+    ends[axis] = end.min(ends[axis]);
+    let mut inputs = vec![
+        e.input(node, 0),
+        e.konst(node, "starts", &tensor1(&starts))?,
+        e.konst(node, "ends", &tensor1(&ends))?,
+    ];
+    if let Some(step) = step {
+        let mut steps = vec![1; rank];
+        steps[axis] = step;
+        inputs.push(e.konst(node, "axes", &tensor1(&(0..rank as i64).collect::<Vec<_>>()))?);
+        inputs.push(e.konst(node, "steps", &tensor1(&steps))?);
+    }
+    simple(e, node, "Slice", inputs, vec![])
+}
+
+fn concat(e: &mut GraphExporter, node: &TypedNode) -> TractResult<()> {
+    let op = node.op_as::<TypedConcat>().unwrap();
+    let mut inputs = vec![];
+    let mut var = 0;
+    for slice in &op.slices {
+        match slice {
+            ConcatSlice::Const(t) => inputs.push(e.konst(node, "slice", t)?),
+            ConcatSlice::Var => {
+                inputs.push(e.input(node, var));
+                var += 1;
+            }
+        }
+    }
+    simple(e, node, "Concat", inputs, vec![attr_int("axis", op.axis as i64)])
+}
+
+fn multi_broadcast_to(e: &mut GraphExporter, node: &TypedNode) -> TractResult<()> {
+    let op = node.op_as::<MultiBroadcastTo>().unwrap();
+    let input = e.model.outlet_fact(node.inputs[0])?;
+    let offset = op.shape.len() - input.rank();
+    let shape = op
+        .shape
+        .iter()
+        .enumerate()
+        .map(|(ix, d)| {
+            if let Ok(d) = d.to_integer() {
+                Ok(d as i64)
+            } else if ix >= offset && input.shape.dim(ix - offset) == *d {
+                Ok(1)
+            } else {
+                bail!("Can not export broadcasting to {:?}", op.shape)
+            }
+        })
+        .collect::<TractResult<Vec<_>>>()?;
+    let shape = e.konst(node, "shape", &tensor1(&shape))?;
+    simple(e, node, "Expand", vec![e.input(node, 0), shape], vec![])
+}
+
+fn pad(e: &mut GraphExporter, node: &TypedNode) -> TractResult<()> {
+    let op = node.op_as::<Pad>().unwrap();
+    let pads = op
+        .pads
+        .iter()
+        .map(|p| p.0 as i64)
+        .chain(op.pads.iter().map(|p| p.1 as i64))
+        .collect::<Vec<_>>();
+    let mut inputs = vec![e.input(node, 0), e.konst(node, "pads", &tensor1(&pads))?];
+    let mode = match &op.mode {
+        PadMode::Constant(value) => {
+            inputs.push(e.konst(node, "value", value)?);
+            "constant"
+        }
+        PadMode::Reflect => "reflect",
+        PadMode::Edge => "edge",
+        PadMode::Symmetric => bail!("ONNX Pad has no symmetric mode"),
+    };
+    simple(e, node, "Pad", inputs, vec![attr_string("mode", mode)])
+}
+
+fn matmul(
+    e: &mut GraphExporter,
+    node: &TypedNode,
+    a: (String, usize, bool),
+    b: (String, usize, bool),
+    c_trans: bool,
+    q_params: Option<&QParams>,
+) -> TractResult<()> {
+    if q_params.is_some() {
+        bail!("Can not export quantized matrix multiplication")
+    }
+    // c' = b' . a'
+    let (a, b) = if c_trans { ((b.0, b.1, !b.2), (a.0, a.1, !a.2)) } else { (a, b) };
+    let mut transposed = |(name, rank, trans): (String, usize, bool)| -> TractResult<String> {
+        if !trans {
+            return Ok(name);
+        }
+        if rank < 2 {
+            bail!("Can not transpose a rank {} operand", rank)
+        }
+        let mut perm: Vec<i64> = (0..rank as i64).collect();
+        perm.swap(rank - 2, rank - 1);
+        Ok(e.temp_node(node, "Transpose", vec![name], vec![attr_ints("perm", perm)]))
+    };
+    let inputs = vec![transposed(a)?, transposed(b)?];
+    simple(e, node, "MatMul", inputs, vec![])
+}
+
+fn pool_attrs(spec: &PoolSpec, conv: bool) -> TractResult<Vec<AttributeProto>> {
+    let mut attrs = vec![
+        attr_ints("kernel_shape", spec.kernel_shape.iter().map(|&k| k as i64)),
+        attr_ints("strides", spec.strides().iter().map(|&s| s as i64)),
+    ];
+    if conv || spec.dilations().iter().any(|&d| d != 1) {
+        attrs.push(attr_ints("dilations", spec.dilations().iter().map(|&d| d as i64)));
+    }
+    match &spec.padding {
+        PaddingSpec::Explicit(before, after, ceil_mode) => {
+            let pads = before.iter().chain(after.iter()).map(|&p| p as i64);
+            attrs.push(attr_ints("pads", pads));
+            if *ceil_mode {
+                if conv {
+                    bail!("Can not export convolution with ceil mode")
+                }
+                attrs.push(attr_int("ceil_mode", 1));
+            }
+        }
+        PaddingSpec::Valid => attrs.push(attr_string("auto_pad", "VALID")),
+        PaddingSpec::SameUpper => attrs.push(attr_string("auto_pad", "SAME_UPPER")),
+        PaddingSpec::SameLower => attrs.push(attr_string("auto_pad", "SAME_LOWER")),
+    }
+    Ok(attrs)
+}
+
+/// Emit `op_type` on the first input of `node` converted to NCHW, with
+/// `extra_inputs`, and convert the result back to the node data format.
+fn nchw_wrapped(
+    e: &mut GraphExporter,
+    node: &TypedNode,
+    format: DataFormat,
+    op_type: &str,
+    extra_inputs: Vec<String>,
+    attributes: Vec<AttributeProto>,
+) -> TractResult<()> {
+    let has_n = format == DataFormat::NCHW || format == DataFormat::NHWC;
+    let c_last = format == DataFormat::NHWC || format == DataFormat::HWC;
+    let rank = e.model.outlet_fact(node.inputs[0])?.rank() + !has_n as usize;
+    let mut input = e.input(node, 0);
+    if !has_n {
+        input = e.temp_node(node, "Unsqueeze", vec![input], vec![attr_ints("axes", vec![0])]);
+    }
+    if c_last {
+        let perm = vec![0, rank as i64 - 1].into_iter().chain(1..rank as i64 - 1);
+        input = e.temp_node(node, "Transpose", vec![input], vec![attr_ints("perm", perm)]);
+    }
+    let mut inputs = vec![input];
+    inputs.extend(extra_inputs);
+    if format == DataFormat::NCHW {
+        return simple(e, node, op_type, inputs, attributes);
+    }
+    let mut output = e.temp_node(node, op_type, inputs, attributes);
+    if c_last {
+        let perm = std::iter::once(0).chain(2..rank as i64).chain(std::iter::once(1));
+        let attrs = vec![attr_ints("perm", perm)];
+        if has_n {
+            return simple(e, node, "Transpose", vec![output], attrs);
+        }
+        output = e.temp_node(node, "Transpose", vec![output], attrs);
+    }
+    simple(e, node, "Squeeze", vec![output], vec![attr_ints("axes", vec![0])])
+}
+
+fn hwio_to_oihw<T: Datum>(kernel: &Tensor, group: usize) -> TractResult<Tensor> {
+    let kernel = kernel.to_array_view::<T>()?;
+    let hw_rank = kernel.ndim() - 2;
+    let mut shape = kernel.shape().to_vec();
+    shape.insert(hw_rank + 1, group);
+    shape[hw_rank] /= group;
+    let mut permutation: Vec<usize> = vec![hw_rank + 1, hw_rank + 2, hw_rank];
+    permutation.extend(0..hw_rank);
+    let permuted = kernel.into_shape(shape)?.permuted_axes(permutation);
+    let mut oihw: Vec<usize> = permuted.shape()[1..].to_vec();
+    oihw[0] *= group;
+    let data = permuted.iter().cloned().collect();
+    Ok(tract_ndarray::ArrayD::from_shape_vec(oihw, data)?.into_tensor())
+}
+
+fn conv(e: &mut GraphExporter, node: &TypedNode) -> TractResult<()> {
+    let op = node.op_as::<ConvUnary>().unwrap();
+    if op.q_params.is_some() {
+        bail!("Can not export quantized convolution")
+    }
+    let kernel = match op.kernel_fmt {
+        KernelFormat::OIHW => op.kernel.as_ref().clone(),
+        KernelFormat::HWIO => {
+            dispatch_datum!(hwio_to_oihw(op.kernel.datum_type())(&op.kernel, op.group))?
+        }
+    };
+    let mut inputs = vec![e.konst(node, "kernel", &kernel)?];
+    if let Some(bias) = &op.bias {
+        let co = kernel.shape()[0];
+        if bias.len() != co {
+            bail!("Can not export convolution bias {:?}", bias)
+        }
+        let mut bias = bias.as_ref().clone();
+        bias.set_shape(&[co])?;
+        inputs.push(e.konst(node, "bias", &bias)?);
+    }
+    let mut attrs = pool_attrs(&op.pool_spec, true)?;
+    attrs.push(attr_int("group", op.group as i64));
+    nchw_wrapped(e, node, op.pool_spec.data_format, "Conv", inputs, attrs)
+}
+
+fn deconv(e: &mut GraphExporter, node: &TypedNode) -> TractResult<()> {
+    let op = node.op_as::<DeconvUnary>().unwrap();
+    let spatial_rank = op.pool_spec.kernel_shape.len();
+    let kernel = match op.kernel_fmt {
+        KernelFormat::OIHW => op.kernel.as_ref().clone(),
+        KernelFormat::HWIO => {
+            let mut kernel = op.kernel.as_ref().clone();
+            AxisOp::Move(spatial_rank + 1, 0).change_tensor(&mut kernel)?;
+            AxisOp::Move(spatial_rank + 1, 1).change_tensor(&mut kernel)?;
+            kernel
+        }
+    };
+    let mut inputs = vec![e.konst(node, "kernel", &kernel)?];
+    if let Some(bias) = &op.bias {
+        inputs.push(e.konst(node, "bias", bias)?);
+    }
+    // ONNX and tract disagree on SAME padding for deconvolution: always
+    // export explicit pads
+    let (before, after, output_padding) = match &op.pool_spec.padding {
+        PaddingSpec::Valid => {
+            (tvec!(0; spatial_rank), tvec!(0; spatial_rank), op.adjustments.clone())
+        }
+        PaddingSpec::Explicit(before, after, _) => {
+            (before.clone(), after.clone(), op.adjustments.clone())
+        }
+        padding => {
+            let input_shape = e.model.outlet_fact(node.inputs[0])?.shape.to_tvec();
+            let input_shape = op.pool_spec.data_format.shape(input_shape)?;
+            let mut before = tvec!();
+            let mut after = tvec!();
+            let mut output_padding = tvec!();
+            for axis in 0..spatial_rank {
+                let input = input_shape.hw_dims()[axis].to_integer()? as usize;
+                let kernel_field =
+                    (op.pool_spec.kernel_shape[axis] - 1) * op.pool_spec.dilation(axis) + 1;
+                let computed = padding.compute_one_for_deconv(
+                    axis,
+                    &input,
+                    op.pool_spec.kernel_shape[axis],
+                    op.pool_spec.dilation(axis),
+                    op.pool_spec.stride(axis),
+                    op.adjustments[axis],
+                );
+                let natural = (input - 1) * op.pool_spec.stride(axis) + kernel_field
+                    - computed.pad_before
+                    - computed.pad_after;
+                before.push(computed.pad_before);
+                after.push(computed.pad_after);
+                output_padding.push(computed.output - natural);
+            }
+            (before, after, output_padding)
+        }
+    };
+    let pool_spec =
+        PoolSpec { padding: PaddingSpec::Explicit(before, after, false), ..op.pool_spec.clone() };
+    let mut attrs = pool_attrs(&pool_spec, true)?;
+    attrs.push(attr_int("group", op.group as i64));
+    if output_padding.iter().any(|&p| p != 0) {
+        attrs.push(attr_ints("output_padding", output_padding.iter().map(|&p| p as i64)));
+    }
+    nchw_wrapped(e, node, op.pool_spec.data_format, "ConvTranspose", inputs, attrs)
+}
+
+fn scan(e: &mut GraphExporter, node: &TypedNode) -> TractResult<()> {
+    let op = node.op_as::<Scan>().unwrap();
+    if op.skip > 0 || op.seq_length_input_slot.is_some() {
+        bail!("Can not export scan with skip or sequence length input")
+    }
+    let check_chunk = |chunk: &TDim| -> TractResult<()> {
+        if chunk.to_integer()?.abs() != 1 {
+            bail!("Can not export scan with chunk {}", chunk)
+        }
+        Ok(())
+    };
+    let body_inputs = op.body.input_outlets()?;
+    let body_outputs = op.body.output_outlets()?;
+    let mut body =
+        GraphExporter::new(e.framework, &op.body).with_prefix(format!("{}.", e.name(node)));
+    for (ix, im) in op.input_mapping.iter().enumerate() {
+        if let InputMapping::Full { slot } = im {
+            body = body.with_closure(body_inputs[ix].node, e.input(node, *slot));
+        }
+    }
+
+    let (mut state_inputs, mut body_state_inputs) = (vec![], vec![]);
+    let (mut scan_inputs, mut body_scan_inputs, mut scan_input_axes) = (vec![], vec![], vec![]);
+    for (ix, im) in op.input_mapping.iter().enumerate() {
+        let outlet = body_inputs[ix];
+        let fact = op.body.outlet_fact(outlet)?;
+        match im {
+            InputMapping::Full { .. } => (),
+            InputMapping::State { initializer } => {
+                state_inputs.push(match initializer {
+                    StateInitializer::FromInput(slot) => e.input(node, *slot),
+                    StateInitializer::Value(t) => e.konst(node, "state", t)?,
+                });
+                body_state_inputs.push(body.value_info(body.outlet_name(outlet), fact)?);
+            }
+            InputMapping::Scan { slot, axis, chunk } => {
+                check_chunk(chunk)?;
+                scan_inputs.push(e.input(node, *slot));
+                scan_input_axes.push(*axis as i64);
+                let name = body.outlet_name(outlet);
+                let slice = format!("{}.slice", name);
+                let mut slice_fact = fact.clone();
+                slice_fact.shape.remove_axis(*axis)?;
+                body_scan_inputs.push(body.value_info(slice.clone(), &slice_fact)?);
+                body.node(name.clone(), "Unsqueeze", vec![slice], vec![name]).attribute =
+                    vec![attr_ints("axes", vec![*axis as i64])];
+            }
+        }
+    }
+
+    body.export_nodes()?;
+
+    let (mut state_outputs, mut body_state_outputs) = (vec![], vec![]);
+    let (mut scan_outputs, mut body_scan_outputs, mut scan_output_axes) = (vec![], vec![], vec![]);
+    for (ix, om) in op.output_mapping.iter().enumerate() {
+        let outlet = body_outputs[ix];
+        let fact = op.body.outlet_fact(outlet)?;
+        let name = body.outlet_name(outlet);
+        if om.state {
+            body_state_outputs.push(body.value_info(name.clone(), fact)?);
+            state_outputs.push(match om.last_value_slot {
+                Some(slot) => e.output(node, slot),
+                None => e.temp(node, "state"),
+            });
+        } else if om.last_value_slot.is_some() {
+            bail!("Can not export last value of a scan output")
+        }
+        if let Some(slot) = om.full_slot {
+            check_chunk(&om.chunk)?;
+            let squeezed = format!("{}.squeezed", name);
+            let mut squeezed_fact = fact.clone();
+            squeezed_fact.shape.remove_axis(om.axis)?;
+            body_scan_outputs.push(body.value_info(squeezed.clone(), &squeezed_fact)?);
+            body.node(squeezed.clone(), "Squeeze", vec![name], vec![squeezed]).attribute =
+                vec![attr_ints("axes", vec![om.axis as i64])];
+            scan_outputs.push(e.output(node, slot));
+            scan_output_axes.push(om.axis as i64);
+        }
+    }
+
+    let mut body = body.graph;
+    body.name = e.name(node);
+    body.input = body_state_inputs.into_iter().chain(body_scan_inputs).collect();
+    body.output = body_state_outputs.into_iter().chain(body_scan_outputs).collect();
+    let mut attrs =
+        vec![attr_graph("body", body), attr_int("num_scan_inputs", scan_inputs.len() as i64)];
+    if op.backward {
+        attrs.push(attr_ints("scan_input_directions", vec![1; scan_inputs.len()]));
+        attrs.push(attr_ints("scan_output_directions", vec![1; scan_outputs.len()]));
+    }
+    attrs.push(attr_ints("scan_input_axes", scan_input_axes));
+    attrs.push(attr_ints("scan_output_axes", scan_output_axes));
+    let inputs = state_inputs.into_iter().chain(scan_inputs).collect();
+    let outputs = state_outputs.into_iter().chain(scan_outputs).collect();
+    e.node(e.name(node), "Scan", inputs, outputs).attribute = attrs;
+    Ok(())
+}
+
+/// Export a body as a subgraph, its inputs from `closures` referring to the
+/// outer values feeding the given node input slots.
+fn subgraph(
+    e: &GraphExporter,
+    node: &TypedNode,
+    hint: &str,
+    body: &TypedModel,
+    closures: impl IntoIterator<Item = (usize, usize)>,
+) -> TractResult<crate::pb::GraphProto> {
+    let body_inputs = body.input_outlets()?;
+    let mut exporter =
+        GraphExporter::new(e.framework, body).with_prefix(format!("{}.{}.", e.name(node), hint));
+    for (body_input, slot) in closures {
+        exporter = exporter.with_closure(body_inputs[body_input].node, e.input(node, slot));
+    }
+    exporter.export()
+}
+
+fn if_(e: &mut GraphExporter, node: &TypedNode) -> TractResult<()> {
+    let op = node.op_as::<If>().unwrap();
+    let then_branch = subgraph(
+        e,
+        node,
+        "then",
+        &op.then_body,
+        op.then_input_mapping.iter().cloned().enumerate(),
+    )?;
+    let else_branch = subgraph(
+        e,
+        node,
+        "else",
+        &op.else_body,
+        op.else_input_mapping.iter().cloned().enumerate(),
+    )?;
+    let outputs = (0..node.outputs.len()).map(|ix| e.output(node, ix)).collect();
+    e.node(e.name(node), "If", vec![e.input(node, 0)], outputs).attribute =
+        vec![attr_graph("then_branch", then_branch), attr_graph("else_branch", else_branch)];
+    Ok(())
+}
+
+fn loop_(e: &mut GraphExporter, node: &TypedNode) -> TractResult<()> {
+    let op = node.op_as::<Loop>().unwrap();
+    let first_closure = 2 + op.carried;
+    let closures = (first_closure..node.inputs.len()).map(|ix| (ix, ix));
+    let body = subgraph(e, node, "body", &op.body, closures)?;
+    let inputs = (0..first_closure).map(|ix| e.input(node, ix)).collect();
+    let outputs = (0..node.outputs.len()).map(|ix| e.output(node, ix)).collect();
+    e.node(e.name(node), "Loop", inputs, outputs).attribute = vec![attr_graph("body", body)];
+    Ok(())
+}
+
+fn resize(e: &mut GraphExporter, node: &TypedNode) -> TractResult<()> {
+    let op = node.op_as::<Resize>().unwrap();
+    let coord_transformer = match op.coord_transformer {
+        CoordTransformer::HalfPixel => "half_pixel",
+        CoordTransformer::AlignCorners => "align_corners",
+        CoordTransformer::Asymmetric => "asymmetric",
+        CoordTransformer::TfHalfPixelForNn => "tf_half_pixel_for_nn",
+        CoordTransformer::PytorchHalfPixel => "pytorch_half_pixel",
+        CoordTransformer::TfCropAndResize => "tf_crop_and_resize",
+    };
+    let mode = match op.interpolator {
+        Interpolator::Nearest => "nearest",
+        Interpolator::Linear => "linear",
+        Interpolator::Cubic => "cubic",
+    };
+    let nearest_mode = match op.nearest {
+        Nearest::RoundPreferFloor => "round_prefer_floor",
+        Nearest::RoundPreferCeil => "round_prefer_ceil",
+        Nearest::Floor => "floor",
+        Nearest::Ceil => "ceil",
+    };
+    // Resize-11 wants roi and scales as (possibly empty) tensors
+    let mut inputs = vec![e.input(node, 0)];
+    for (slot, hint) in
+        [(op.optional_roi_input, "roi"), (op.optional_scales_input, "scales")].iter()
+    {
+        if let Some(slot) = slot {
+            inputs.push(e.input(node, *slot));
+        } else {
+            inputs.push(e.konst(node, hint, &Tensor::zero::<f32>(&[0])?)?);
+        }
+    }
+    if let Some(sizes) = op.optional_sizes_input {
+        inputs.push(e.input(node, sizes));
+    }
+    let attrs = vec![
+        attr_string("coordinate_transformation_mode", coord_transformer),
+        attr_string("mode", mode),
+        attr_string("nearest_mode", nearest_mode),
+        attr_float("cubic_coeff_a", op.cubic_coeff_a),
+        attr_int("exclude_outside", op.exclude_outside as i64),
+        attr_float("extrapolation_value", op.extrapolation_value),
+    ];
+    simple(e, node, "Resize", inputs, attrs)
+}
@@ -11,6 +11,7 @@ extern crate num_integer;
 #[macro_use]
 pub extern crate tract_hir;
 
+pub mod export;
 pub mod model;
 pub mod ops;
 
@@ -19,6 +20,7 @@ pub mod pb {
 }
 
 pub mod pb_helpers;
+pub mod ser;
 pub mod tensor;
 
 pub use model::Onnx;
@@ -44,5 +46,7 @@ pub fn for_reader<R: std::io::Read>(mut r: R) -> TractResult<InferenceModel> {
 pub fn onnx() -> Onnx {
     let mut ops = crate::model::OnnxOpRegister::default();
     ops::register_all_ops(&mut ops);
-    Onnx { op_register: ops }
+    let mut exporters = crate::export::OnnxExportRegister::default();
+    export::register_all_exporters(&mut exporters);
+    Onnx { op_register: ops, export_register: exporters }
 }
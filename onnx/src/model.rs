@@ -6,6 +6,7 @@ use std::collections::HashMap;
 use tract_hir::internal::*;
 
 use crate::pb;
+use crate::tensor::ExternalData;
 use prost::Message;
 
 pub fn optional_inputs(pb: &pb::NodeProto) -> impl Iterator<Item = Option<usize>> + '_ {
@@ -37,6 +38,7 @@ pub struct ParsingContext<'a> {
     pub onnx_operator_set_version: i64,
     pub framework: &'a Onnx,
     pub model: &'a pb::ModelProto,
+    pub external_data: Option<&'a ExternalData<'a>>,
     pub parent_graphs: Vec<&'a pb::GraphProto>,
 }
 
@@ -48,6 +50,10 @@ pub struct ParseResult {
 }
 
 impl<'a> ParsingContext<'a> {
+    pub fn load_tensor(&self, proto: &pb::TensorProto) -> TractResult<Tensor> {
+        crate::tensor::load_tensor(proto, self.external_data)
+    }
+
     pub fn parse_graph(&self, graph: &pb::GraphProto) -> TractResult<ParseResult> {
         let mut ctx = self.clone();
         ctx.parent_graphs.push(graph);
@@ -57,7 +63,7 @@ impl<'a> ParsingContext<'a> {
         let mut initializers: HashMap<&str, Tensor> = graph
             .initializer
             .iter()
-            .map(|init| Ok((&*init.name, init.try_into()?)))
+            .map(|init| Ok((&*init.name, self.load_tensor(init)?)))
             .collect::<TractResult<_>>()?;
         for (k, v) in initializers.iter() {
             trace!("Initializer: {} {:?}", k, v);
@@ -105,18 +111,23 @@ impl<'a> ParsingContext<'a> {
                 .map(|_| InferenceFact::default())
                 .collect();
             trace!("  outputs {:?}", pbnode.output);
-            let (op, closures) = match self.framework.op_register.0.get(&pbnode.op_type) {
-                Some(builder) => (builder)(&ctx, pbnode)?,
-                None => (
-                    tract_hir::ops::unimpl::UnimplementedOp::new(
-                        pbnode.output.len(),
-                        &*pbnode.op_type,
-                        format!("{:?}", pbnode),
-                    )
-                    .into(),
-                    vec![],
-                ),
-            };
+            let (op, closures) =
+                match self.framework.op_register.get(&pbnode.domain, &pbnode.op_type) {
+                    Some(builder) => (builder)(&ctx, pbnode)?,
+                    None => (
+                        tract_hir::ops::unimpl::UnimplementedOp::new(
+                            pbnode.output.len(),
+                            if pbnode.domain.is_empty() {
+                                pbnode.op_type.clone()
+                            } else {
+                                format!("{}.{}", pbnode.domain, pbnode.op_type)
+                            },
+                            format!("{:?}", pbnode),
+                        )
+                        .into(),
+                        vec![],
+                    ),
+                };
             let id = model.add_node(name, op, facts)?;
             for (ix, output) in pbnode.output.iter().filter(|s| !s.is_empty()).enumerate() {
                 outlets_by_name.insert(output.to_owned(), OutletId::new(id, ix));
@@ -152,6 +163,12 @@ impl<'a> ParsingContext<'a> {
         for output in graph.output.iter() {
             let fact = output.r#type.as_ref().unwrap().value.as_ref().unwrap();
             let pb::type_proto::Value::TensorType(fact) = fact;
+            if !outlets_by_name.contains_key(&*output.name) {
+                // subgraph output forwarding a value from an outer scope
+                let id = model.add_source(output.name.clone(), InferenceFact::default())?;
+                unresolved_inputs.push(output.name.to_string());
+                outlets_by_name.insert(output.name.to_string(), id);
+            }
             outputs.push(outlets_by_name[&*output.name]);
             model.set_outlet_fact(outlets_by_name[&*output.name], fact.try_into()?)?;
         }
@@ -161,37 +178,52 @@ impl<'a> ParsingContext<'a> {
     }
 }
 
+pub type OnnxOpBuilder =
+    fn(&ParsingContext, node: &pb::NodeProto) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)>;
+
+/// Operator builders, by (domain, op_type). The default ONNX domain is "".
 #[derive(Clone, Default)]
-pub struct OnnxOpRegister(
-    pub  HashMap<
-        String,
-        fn(
-            &ParsingContext,
-            node: &pb::NodeProto,
-        ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)>,
-    >,
-);
+pub struct OnnxOpRegister(pub HashMap<(String, String), OnnxOpBuilder>);
 
 impl OnnxOpRegister {
-    pub fn insert(
-        &mut self,
-        s: &'static str,
-        builder: fn(
-            &ParsingContext,
-            node: &pb::NodeProto,
-        ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)>,
-    ) {
-        self.0.insert(s.into(), builder);
+    /// Register a builder for an operator of the default ONNX domain.
+    pub fn insert(&mut self, s: &'static str, builder: OnnxOpBuilder) {
+        self.insert_in_domain("", s, builder);
+    }
+
+    /// Register a builder for an operator of a custom domain, like
+    /// "com.microsoft".
+    pub fn insert_in_domain(&mut self, domain: &str, op_type: &str, builder: OnnxOpBuilder) {
+        self.0.insert((Self::canonic_domain(domain).to_string(), op_type.to_string()), builder);
+    }
+
+    pub fn get(&self, domain: &str, op_type: &str) -> Option<&OnnxOpBuilder> {
+        self.0.get(&(Self::canonic_domain(domain).to_string(), op_type.to_string()))
+    }
+
+    fn canonic_domain(domain: &str) -> &str {
+        if domain == "ai.onnx" {
+            ""
+        } else {
+            domain
+        }
     }
 }
 
 #[derive(Clone, Default)]
 pub struct Onnx {
     pub op_register: OnnxOpRegister,
+    pub export_register: crate::export::OnnxExportRegister,
 }
 
 impl Onnx {
-    pub fn parse(&self, proto: &pb::ModelProto) -> TractResult<ParseResult> {
+    /// Parse a model. External tensor data is resolved relative to
+    /// `model_dir`, if any.
+    pub fn parse(
+        &self,
+        proto: &pb::ModelProto,
+        model_dir: Option<&path::Path>,
+    ) -> TractResult<ParseResult> {
         let onnx_operator_set_version =
             proto.opset_import.iter().find(|import| import.domain == "").unwrap().version;
         let graph = &proto.graph;
@@ -201,9 +233,11 @@ impl Onnx {
                   operator set 9, 10, 11 and 12 only. Your model may still work so this is not a hard fail.",
                   onnx_operator_set_version);
         }
+        let external_data = model_dir.map(ExternalData::new);
         let ctx = ParsingContext {
             framework: self,
             model: proto,
+            external_data: external_data.as_ref(),
             parent_graphs: vec![],
             onnx_operator_set_version,
         };
@@ -228,7 +262,22 @@ impl Framework<pb::ModelProto> for Onnx {
     }
 
     fn model_for_proto_model(&self, proto: &pb::ModelProto) -> TractResult<InferenceModel> {
-        let ParseResult { model, unresolved_inputs, .. } = self.parse(proto)?;
+        self.model_for_proto_model_with_dir(proto, None)
+    }
+
+    fn model_for_path(&self, p: impl AsRef<path::Path>) -> TractResult<InferenceModel> {
+        let proto = self.proto_model_for_path(p.as_ref())?;
+        self.model_for_proto_model_with_dir(&proto, p.as_ref().parent())
+    }
+}
+
+impl Onnx {
+    fn model_for_proto_model_with_dir(
+        &self,
+        proto: &pb::ModelProto,
+        model_dir: Option<&path::Path>,
+    ) -> TractResult<InferenceModel> {
+        let ParseResult { model, unresolved_inputs, .. } = self.parse(proto, model_dir)?;
         if unresolved_inputs.len() > 0 {
             bail!("Could not resolve inputs at top-level: {:?}", unresolved_inputs)
         }
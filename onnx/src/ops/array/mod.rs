@@ -1,6 +1,12 @@
 mod compress;
+mod non_zero;
 mod pad;
+mod scatter;
+mod shape;
 mod slice;
+mod split;
+mod squeeze;
+mod unique;
 
 use tract_hir::internal::*;
 use tract_hir::ops::array;
@@ -18,16 +24,24 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("EyeLike", eye_like);
     reg.insert("Flatten", flatten);
     reg.insert("Gather", gather);
+    reg.insert("GatherElements", gather_elements);
+    reg.insert("GatherND", gather_nd);
+    reg.insert("NonZero", non_zero::non_zero);
     reg.insert("Pad", pad::pad);
-    reg.insert("Reshape", |_, _| Ok((expand(array::Reshape::default()), vec![])));
-    reg.insert("Shape", |_, _| Ok((expand(array::Shape::new(DatumType::I64)), vec![])));
+    reg.insert("Reshape", reshape);
+    reg.insert("Scatter", scatter::scatter_elements);
+    reg.insert("ScatterElements", scatter::scatter_elements);
+    reg.insert("ScatterND", scatter::scatter_nd);
+    reg.insert("Shape", shape::shape);
     reg.insert("Size", |_, _| Ok((expand(array::Size::new(DatumType::I64)), vec![])));
     reg.insert("Transpose", transpose);
     reg.insert("Tile", |_, _| Ok((expand(array::Tile::default()), vec![])));
+    reg.insert("TopK", topk);
     reg.insert("Slice", slice::slice);
-    reg.insert("Split", split);
-    reg.insert("Squeeze", squeeze);
-    reg.insert("Unsqueeze", unsqueeze);
+    reg.insert("Split", split::split);
+    reg.insert("Squeeze", squeeze::squeeze);
+    reg.insert("Unique", unique::unique);
+    reg.insert("Unsqueeze", squeeze::unsqueeze);
 }
 
 pub fn concat(
@@ -97,21 +111,31 @@ pub fn gather(
     Ok((Box::new(array::Gather::new(axis)), vec![]))
 }
 
-pub fn split(
+pub fn gather_elements(
     _ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
     let axis = node.get_attr_opt("axis")?.unwrap_or(0);
-    let split = node.get_attr_opt_vec("split")?;
-    Ok((expand(array::Split::new(axis, node.output.len(), split)), vec![]))
+    Ok((expand(array::GatherElements::new(axis)), vec![]))
 }
 
-pub fn squeeze(
+pub fn gather_nd(
     _ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    let axes = node.get_attr_opt_vec("axes")?;
-    Ok((expand(array::Squeeze::new(axes)), vec![]))
+    let batch_dims = node.get_attr_opt("batch_dims")?.unwrap_or(0);
+    Ok((Box::new(array::GatherNd::new(batch_dims)), vec![]))
+}
+
+pub fn reshape(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    // Reshape-14: with allowzero, a 0 in the shape is a real zero dimension
+    if node.get_attr_opt("allowzero")?.unwrap_or(0i64) != 0 {
+        bail!("Reshape with allowzero=1 is not supported")
+    }
+    Ok((expand(array::Reshape::default()), vec![]))
 }
 
 pub fn transpose(
@@ -122,10 +146,44 @@ pub fn transpose(
     Ok((expand(array::PermuteAxes::new(perm.map(|t| t.into()))), vec![]))
 }
 
-pub fn unsqueeze(
-    _ctx: &ParsingContext,
+pub fn topk(
+    ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    let axes = node.get_attr_vec::<i64>("axes")?.into_iter().map(|x| x as isize).collect();
-    Ok((expand(array::AddDims::new(axes)), vec![]))
+    let axis = node.get_attr_opt("axis")?.unwrap_or(-1);
+    let largest = node.get_attr_opt("largest")?.unwrap_or(1i64) == 1;
+    if node.get_attr_opt("sorted")?.unwrap_or(1i64) != 1 {
+        bail!("TopK with sorted=0 is not supported")
+    }
+    // TopK-1 has k as an attribute, later versions as an input
+    let k = if ctx.onnx_operator_set_version < 10 { Some(node.get_attr("k")?) } else { None };
+    Ok((expand(array::TopK::new(axis, largest, k, DatumType::I64)), vec![]))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::attr_int;
+    use crate::ops::test_util::*;
+    use tract_hir::internal::*;
+
+    #[test]
+    fn topk1_with_k_attribute() {
+        let node = node("TopK", &["x"], &["values", "indices"], vec![attr_int("k", 2)]);
+        let x = tensor2(&[[1f32, 4., 3.], [0., -1., 2.]]);
+        let outputs = &[("values", DatumType::F32), ("indices", DatumType::I64)];
+        let result = run_node(1, node, &[("x", x)], &[], outputs).unwrap();
+        assert_eq!(result[0], rctensor2(&[[4f32, 3.], [2., 0.]]));
+        assert_eq!(result[1], rctensor2(&[[1i64, 2], [2, 0]]));
+    }
+
+    #[test]
+    fn topk11_smallest_with_k_input() {
+        let attrs = vec![attr_int("axis", 0), attr_int("largest", 0)];
+        let node = node("TopK", &["x", "k"], &["values", "indices"], attrs);
+        let x = tensor2(&[[1f32, 4., 3.], [0., -1., 2.]]);
+        let outputs = &[("values", DatumType::F32), ("indices", DatumType::I64)];
+        let result = run_node(11, node, &[("x", x)], &[("k", tensor1(&[1i64]))], outputs).unwrap();
+        assert_eq!(result[0], rctensor2(&[[0f32, -1., 2.]]));
+        assert_eq!(result[1], rctensor2(&[[1i64, 1, 1]]));
+    }
 }
@@ -0,0 +1,62 @@
+use crate::model::ParsingContext;
+use crate::pb::*;
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::array::NonZero as TypedNonZero;
+
+pub fn non_zero(
+    _ctx: &ParsingContext,
+    _node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    Ok((expand(NonZero), vec![]))
+}
+
+/// The number of non-zero elements is a symbol named after the node.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct NonZero;
+
+tract_linalg::impl_dyn_hash!(NonZero);
+
+impl Expansion for NonZero {
+    fn name(&self) -> Cow<str> {
+        "NonZero".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, DatumType::I64)?;
+        s.equals(&outputs[0].rank, 2)?;
+        s.given(&inputs[0].rank, move |s, rank| s.equals(&outputs[0].shape[0], rank.to_dim()))
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let op = TypedNonZero::new(TDim::sym(format!("{}.count", prefix)));
+        model.wire_node(prefix, op, inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ops::test_util::*;
+    use tract_hir::internal::*;
+
+    #[test]
+    fn non_zero() {
+        let node = node("NonZero", &["x"], &["y"], vec![]);
+        let x = tensor2(&[[0i32, 3], [5, 0]]);
+        let result = run_node(9, node, &[("x", x)], &[], &[("y", DatumType::I64)]).unwrap();
+        assert_eq!(result[0], rctensor2(&[[0i64, 1], [1, 0]]));
+    }
+}
@@ -47,18 +47,44 @@ pub fn pad_11(
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
     let mode = pad_mode(node)?;
-    let op = Pad11::new(mode, if node.input.len() == 3 { Some(2) } else { None });
+    let mut options = crate::model::optional_inputs(node).skip(2);
+    let op = Pad11::new(mode, options.next().unwrap(), options.next().unwrap());
     Ok((expand(op), vec![]))
 }
 
+/// Pad-11 takes pads (and constant value) as inputs, Pad-18 adds an
+/// optional axes input restricting the padded axes.
 #[derive(Debug, Clone, new, Hash)]
 pub struct Pad11 {
     mode: array::PadMode,
     constant_input: Option<usize>,
+    axes_input: Option<usize>,
 }
 
 tract_linalg::impl_dyn_hash!(Pad11);
 
+impl Pad11 {
+    fn pads(rank: usize, pads: &Tensor, axes: Option<&Tensor>) -> TractResult<Vec<(usize, usize)>> {
+        let pads = pads.cast_to::<i64>()?;
+        let pads = pads.as_slice::<i64>()?;
+        let axes: Vec<usize> = if let Some(axes) = axes {
+            let axes = axes.cast_to::<i64>()?;
+            let axes = axes.as_slice::<i64>()?;
+            axes.iter().map(|&a| if a < 0 { a + rank as i64 } else { a } as usize).collect()
+        } else {
+            (0..rank).collect()
+        };
+        if pads.len() != 2 * axes.len() {
+            bail!("Expected {} pads, got {}", 2 * axes.len(), pads.len())
+        }
+        let mut result = vec![(0, 0); rank];
+        for (ix, &axis) in axes.iter().enumerate() {
+            result[axis] = (pads[ix] as usize, pads[ix + axes.len()] as usize);
+        }
+        Ok(result)
+    }
+}
+
 impl Expansion for Pad11 {
     fn name(&self) -> Cow<str> {
         "Pad".into()
@@ -72,7 +98,10 @@ impl Expansion for Pad11 {
         inputs: &'p [TensorProxy],
         outputs: &'p [TensorProxy],
     ) -> InferenceResult {
-        check_input_arity(&inputs, 2 + self.constant_input.is_some() as usize)?;
+        check_input_arity(
+            &inputs,
+            2 + self.constant_input.is_some() as usize + self.axes_input.is_some() as usize,
+        )?;
         check_output_arity(&outputs, 1)?;
         if let Some(input) = self.constant_input {
             s.equals(&inputs[0].datum_type, &inputs[input].datum_type)?;
@@ -80,20 +109,32 @@ impl Expansion for Pad11 {
         }
         s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
         s.equals(&inputs[0].rank, &outputs[0].rank)?;
-        s.given(&inputs[1].value, move |s, pads| {
-            let pads = pads.as_slice::<i64>()?;
-            let rank = pads.len() / 2;
-            let pads: Vec<_> =
-                (0..rank).map(|ax| (pads[ax] as usize, pads[ax + rank] as usize)).collect();
-            for i in 0..rank {
+        let set_shape = move |s: &mut Solver<'r>,
+                              rank: i32,
+                              pads: &Tensor,
+                              axes: Option<&Tensor>|
+              -> InferenceResult {
+            let pads = Self::pads(rank as usize, pads, axes)?;
+            for (i, pad) in pads.iter().enumerate() {
                 s.equals(
                     &outputs[0].shape[i],
-                    inputs[0].shape[i].bex() + pads[i].0.to_dim() + pads[i].1.to_dim(),
+                    inputs[0].shape[i].bex() + pad.0.to_dim() + pad.1.to_dim(),
                 )?;
             }
             Ok(())
-        })?;
-        Ok(())
+        };
+        if let Some(axes) = self.axes_input {
+            s.given_3(
+                &inputs[0].rank,
+                &inputs[1].value,
+                &inputs[axes].value,
+                move |s, rank, pads, axes| set_shape(s, rank, &pads, Some(&axes)),
+            )
+        } else {
+            s.given_2(&inputs[0].rank, &inputs[1].value, move |s, rank, pads| {
+                set_shape(s, rank, &pads, None)
+            })
+        }
     }
 
     fn wire(
@@ -111,15 +152,67 @@ impl Expansion for Pad11 {
         } else {
             self.mode.clone()
         };
-        let pads = model
-            .outlet_fact(inputs[1])?
-            .konst
-            .as_ref()
-            .ok_or("Expect padding to be constant")?
-            .cast_to::<i64>()?;
-        let pads = pads.as_slice::<i64>()?;
-        let rank = pads.len() / 2;
-        let pads = (0..rank).map(|ax| (pads[ax] as usize, pads[ax + rank] as usize)).collect();
+        let pads =
+            model.outlet_fact(inputs[1])?.konst.clone().ok_or("Expect padding to be constant")?;
+        let axes = if let Some(axes) = self.axes_input {
+            Some(
+                model
+                    .outlet_fact(inputs[axes])?
+                    .konst
+                    .clone()
+                    .ok_or("Expect axes to be constant")?,
+            )
+        } else {
+            None
+        };
+        let rank = model.outlet_fact(inputs[0])?.rank();
+        let pads = Self::pads(rank, &pads, axes.as_deref())?;
         model.wire_node(name, array::Pad { mode, pads }, &inputs[0..1])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::ops::test_util::*;
+    use tract_hir::internal::*;
+
+    fn pad(opset: i64, inputs: &[&str], consts: &[(&str, Tensor)]) -> Tensor {
+        let node = node("Pad", inputs, &["y"], vec![]);
+        let x = tensor2(&[[1f32, 2.], [3., 4.]]);
+        let outputs = &[("y", f32::datum_type())];
+        run_node(opset, node, &[("x", x)], consts, outputs).unwrap().remove(0).into_tensor()
+    }
+
+    #[test]
+    fn pad11() {
+        let consts = [("pads", tensor1(&[0i64, 1, 1, 0])), ("value", tensor0(9f32))];
+        assert_eq!(
+            pad(11, &["x", "pads", "value"], &consts),
+            tensor2(&[[9f32, 1., 2.], [9., 3., 4.], [9., 9., 9.]])
+        );
+    }
+
+    #[test]
+    fn pad18_with_axes() {
+        let consts =
+            [("pads", tensor1(&[1i64, 2])), ("value", tensor0(9f32)), ("axes", tensor1(&[-1i64]))];
+        assert_eq!(
+            pad(18, &["x", "pads", "value", "axes"], &consts),
+            tensor2(&[[9f32, 1., 2., 9., 9.], [9., 3., 4., 9., 9.]])
+        );
+        let consts = [("pads", tensor1(&[1i64, 0])), ("axes", tensor1(&[0i64]))];
+        assert_eq!(
+            pad(18, &["x", "pads", "", "axes"], &consts),
+            tensor2(&[[0f32, 0.], [1., 2.], [3., 4.]])
+        );
+    }
+
+    #[test]
+    fn pad18_rejects_pads_not_matching_axes() {
+        let node = node("Pad", &["x", "pads", "", "axes"], &["y"], vec![]);
+        let consts = [("pads", tensor1(&[1i64, 2, 3, 4])), ("axes", tensor1(&[0i64]))];
+        let x = tensor2(&[[1f32, 2.], [3., 4.]]);
+        let outputs = &[("y", f32::datum_type())];
+        assert!(run_node(18, node, &[("x", x)], &consts, outputs).is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use crate::model::ParsingContext;
+use crate::pb::*;
+use tract_hir::internal::*;
+use tract_hir::ops::array::{ScatterElements, ScatterNd, ScatterReduction};
+
+fn reduction(node: &NodeProto) -> TractResult<ScatterReduction> {
+    match node.get_attr_opt("reduction")? {
+        None | Some("none") => Ok(ScatterReduction::None),
+        Some(reduction) => node.check_value(
+            "reduction",
+            match reduction {
+                "add" => Ok(ScatterReduction::Add),
+                "mul" => Ok(ScatterReduction::Mul),
+                "max" => Ok(ScatterReduction::Max),
+                "min" => Ok(ScatterReduction::Min),
+                _ => Err(reduction),
+            },
+        ),
+    }
+}
+
+pub fn scatter_elements(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr_opt("axis")?.unwrap_or(0);
+    Ok((expand(ScatterElements::new(axis, reduction(node)?)), vec![]))
+}
+
+pub fn scatter_nd(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    Ok((Box::new(ScatterNd::new(reduction(node)?)), vec![]))
+}
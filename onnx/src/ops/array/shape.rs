@@ -0,0 +1,120 @@
+use crate::model::ParsingContext;
+use crate::pb::*;
+use tract_hir::internal::*;
+use tract_hir::ops::array;
+
+pub fn shape(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let start = node.get_attr_opt("start")?;
+    let end = node.get_attr_opt("end")?;
+    if start.is_none() && end.is_none() {
+        Ok((expand(array::Shape::new(DatumType::I64)), vec![]))
+    } else {
+        Ok((expand(Shape15::new(start.unwrap_or(0), end)), vec![]))
+    }
+}
+
+/// Shape-15 can return only a slice of the input shape.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Shape15 {
+    start: i64,
+    end: Option<i64>,
+}
+
+tract_linalg::impl_dyn_hash!(Shape15);
+
+impl Shape15 {
+    fn range(&self, rank: usize) -> std::ops::Range<usize> {
+        let resolve = |ix: i64| {
+            let ix = if ix < 0 { ix + rank as i64 } else { ix };
+            ix.max(0).min(rank as i64) as usize
+        };
+        let start = resolve(self.start);
+        let end = resolve(self.end.unwrap_or(rank as i64));
+        start..end.max(start)
+    }
+
+    fn sliced(&self, shape: &[TDim]) -> TractResult<Tensor> {
+        let dims = &shape[self.range(shape.len())];
+        if let Ok(dims) =
+            dims.iter().map(|d| d.to_integer().map(|d| d as i64)).collect::<TractResult<Vec<_>>>()
+        {
+            Ok(tensor1(&dims))
+        } else {
+            Ok(tensor1(dims))
+        }
+    }
+}
+
+impl Expansion for Shape15 {
+    fn name(&self) -> Cow<str> {
+        "Shape15".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].rank, 1)?;
+        s.given(&inputs[0].rank, move |s, rank| {
+            s.equals(&outputs[0].shape[0], self.range(rank as usize).len().to_dim())
+        })?;
+        s.given(&inputs[0].shape, move |s, shape| {
+            let value = self.sliced(&shape)?;
+            s.equals(&outputs[0].datum_type, value.datum_type())?;
+            s.equals(&outputs[0].value, value.into_arc_tensor())
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let shape = model.outlet_fact(inputs[0])?.shape.to_tvec();
+        let wire = model.add_const(prefix, self.sliced(&shape)?)?;
+        Ok(tvec!(wire))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::attr_int;
+    use crate::ops::test_util::*;
+    use crate::pb::AttributeProto;
+    use tract_hir::internal::*;
+
+    fn shape(attrs: Vec<AttributeProto>) -> Tensor {
+        let node = node("Shape", &["x"], &["y"], attrs);
+        let x = Tensor::zero::<f32>(&[2, 3, 4, 5]).unwrap();
+        let outputs = &[("y", i64::datum_type())];
+        run_node(15, node, &[("x", x)], &[], outputs).unwrap().remove(0).into_tensor()
+    }
+
+    #[test]
+    fn shape15() {
+        assert_eq!(shape(vec![]), tensor1(&[2i64, 3, 4, 5]));
+        assert_eq!(shape(vec![attr_int("start", 1)]), tensor1(&[3i64, 4, 5]));
+        assert_eq!(shape(vec![attr_int("start", 1), attr_int("end", 3)]), tensor1(&[3i64, 4]));
+    }
+
+    #[test]
+    fn shape15_negative_and_out_of_range() {
+        assert_eq!(shape(vec![attr_int("start", -2)]), tensor1(&[4i64, 5]));
+        assert_eq!(shape(vec![attr_int("end", -3)]), tensor1(&[2i64]));
+        assert_eq!(
+            shape(vec![attr_int("start", -10), attr_int("end", 10)]),
+            tensor1(&[2i64, 3, 4, 5])
+        );
+        assert_eq!(shape(vec![attr_int("start", 3), attr_int("end", 1)]), tensor1::<i64>(&[]));
+    }
+}
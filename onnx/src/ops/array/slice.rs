@@ -7,12 +7,11 @@ pub fn slice(
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
     let v = ctx.onnx_operator_set_version;
-    if v >= 1 && v < 10 {
+    if v < 10 {
         slice1(ctx, node)
-    } else if v == 10 {
-        slice10(ctx, node)
     } else {
-        bail!("Only Slice-1 and Slice-10 are supported")
+        // Slice-11 only adds support for negative axes, Slice-13 for bfloat16
+        slice10(ctx, node)
     }
 }
 
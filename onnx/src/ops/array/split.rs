@@ -0,0 +1,158 @@
+use crate::model::ParsingContext;
+use crate::pb::*;
+use tract_hir::internal::*;
+use tract_hir::ops::array;
+
+pub fn split(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr_opt("axis")?.unwrap_or(0);
+    if ctx.onnx_operator_set_version < 13 {
+        let split = node.get_attr_opt_vec("split")?;
+        Ok((expand(array::Split::new(axis, node.output.len(), split)), vec![]))
+    } else {
+        let split_input = crate::model::optional_inputs(node).skip(1).next().unwrap();
+        // Split-18: without split input, the last chunk may be smaller
+        let uneven = ctx.onnx_operator_set_version >= 18 && split_input.is_none();
+        Ok((expand(Split13::new(axis, node.output.len(), split_input, uneven)), vec![]))
+    }
+}
+
+/// Split-13 takes the optional split as an input instead of an attribute.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Split13 {
+    axis: isize,
+    outputs: usize,
+    split_input: Option<usize>,
+    uneven: bool,
+}
+
+tract_linalg::impl_dyn_hash!(Split13);
+
+impl Split13 {
+    fn split(&self, dim: &TDim, split: Option<&Tensor>) -> TractResult<Option<Vec<usize>>> {
+        if let Some(split) = split {
+            let split = split.cast_to::<i64>()?;
+            Ok(Some(split.as_slice::<i64>()?.iter().map(|&d| d as usize).collect()))
+        } else if let (true, Ok(dim)) = (self.uneven, dim.to_integer()) {
+            let dim = dim as usize;
+            let chunk = (dim + self.outputs - 1) / self.outputs;
+            Ok(Some(
+                (0..self.outputs).map(|ix| chunk.min(dim.saturating_sub(ix * chunk))).collect(),
+            ))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn axis(&self, rank: usize) -> usize {
+        (if self.axis < 0 { self.axis + rank as isize } else { self.axis }) as usize
+    }
+}
+
+impl Expansion for Split13 {
+    fn name(&self) -> Cow<str> {
+        "Split13".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1 + self.split_input.is_some() as usize)?;
+        check_output_arity(&outputs, self.outputs)?;
+        (0..self.outputs).try_for_each(|i| {
+            s.equals(&inputs[0].datum_type, &outputs[i].datum_type)?;
+            s.equals(&inputs[0].rank, &outputs[i].rank)
+        })?;
+        let set_shapes = move |s: &mut Solver<'r>,
+                               shape: TVec<TDim>,
+                               split: Option<&Tensor>|
+              -> InferenceResult {
+            let axis = self.axis(shape.len());
+            let dims: TVec<TDim> = if let Some(split) = self.split(&shape[axis], split)? {
+                split.into_iter().map(|d| d.to_dim()).collect()
+            } else {
+                tvec!(shape[axis].clone() / self.outputs as u32; self.outputs)
+            };
+            for i in 0..self.outputs {
+                let mut shape = shape.clone();
+                shape[axis] = dims[i].clone();
+                s.equals(&outputs[i].shape, shape)?;
+            }
+            Ok(())
+        };
+        if let Some(split) = self.split_input {
+            s.given_2(&inputs[0].shape, &inputs[split].value, move |s, shape, split| {
+                set_shapes(s, shape, Some(&split))
+            })
+        } else {
+            s.given(&inputs[0].shape, move |s, shape| set_shapes(s, shape, None))
+        }
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.outputs)
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let fact = model.outlet_fact(inputs[0])?.clone();
+        let split = if let Some(split) = self.split_input {
+            if let Some(split) = model.outlet_fact(inputs[split])?.konst.clone() {
+                Some(split)
+            } else {
+                bail!("split input is expected to be a constant")
+            }
+        } else {
+            None
+        };
+        let split = self.split(&fact.shape.dim(self.axis(fact.rank())), split.as_deref())?;
+        array::Split::new(self.axis, self.outputs, split).wire(prefix, model, &inputs[0..1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::attr_int;
+    use crate::ops::test_util::*;
+    use tract_hir::internal::*;
+
+    fn split(opset: i64, inputs: &[&str], x: Tensor, consts: &[(&str, Tensor)]) -> TVec<Tensor> {
+        let node = node("Split", inputs, &["a", "b"], vec![attr_int("axis", -1)]);
+        let outputs = &[("a", x.datum_type()), ("b", x.datum_type())];
+        let result = run_node(opset, node, &[("x", x)], consts, outputs).unwrap();
+        result.into_iter().map(|t| t.into_tensor()).collect()
+    }
+
+    #[test]
+    fn split13_with_split_input() {
+        let x = tensor2(&[[1f32, 2., 3.], [4., 5., 6.]]);
+        let result = split(13, &["x", "split"], x, &[("split", tensor1(&[1i64, 2]))]);
+        assert_eq!(result[0], tensor2(&[[1f32], [4.]]));
+        assert_eq!(result[1], tensor2(&[[2f32, 3.], [5., 6.]]));
+    }
+
+    #[test]
+    fn split13_in_equal_parts() {
+        let result = split(13, &["x"], tensor1(&[1i32, 2, 3, 4]), &[]);
+        assert_eq!(result[0], tensor1(&[1i32, 2]));
+        assert_eq!(result[1], tensor1(&[3i32, 4]));
+    }
+
+    #[test]
+    fn split18_last_part_is_smaller() {
+        let result = split(18, &["x"], tensor1(&[1i32, 2, 3, 4, 5]), &[]);
+        assert_eq!(result[0], tensor1(&[1i32, 2, 3]));
+        assert_eq!(result[1], tensor1(&[4i32, 5]));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::model::ParsingContext;
+use crate::pb::*;
+use tract_hir::internal::*;
+use tract_hir::ops::array;
+
+pub fn squeeze(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    if ctx.onnx_operator_set_version < 13 {
+        let axes = node.get_attr_opt_vec("axes")?;
+        Ok((expand(array::Squeeze::new(axes)), vec![]))
+    } else {
+        Ok((expand(Squeeze13), vec![]))
+    }
+}
+
+pub fn unsqueeze(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    if ctx.onnx_operator_set_version < 13 {
+        let axes = node.get_attr_vec::<i64>("axes")?.into_iter().map(|x| x as isize).collect();
+        Ok((expand(array::AddDims::new(axes)), vec![]))
+    } else {
+        Ok((expand(Unsqueeze13), vec![]))
+    }
+}
+
+fn axes(t: &Tensor) -> TractResult<Vec<isize>> {
+    Ok(t.cast_to::<i64>()?.as_slice::<i64>()?.iter().map(|&a| a as isize).collect())
+}
+
+/// Unsqueeze negative axes count from the end of the output.
+fn unsqueeze_op(axes: Vec<isize>, input_rank: usize) -> array::AddDims {
+    let rank = (input_rank + axes.len()) as isize;
+    array::AddDims::new(axes.into_iter().map(|a| if a < 0 { a + rank } else { a }).collect())
+}
+
+fn const_axes(model: &TypedModel, input: OutletId) -> TractResult<Vec<isize>> {
+    if let Some(t) = &model.outlet_fact(input)?.konst {
+        axes(t)
+    } else {
+        bail!("axes input is expected to be a constant")
+    }
+}
+
+/// Squeeze-13 takes the optional axes as an input instead of an attribute.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct Squeeze13;
+
+tract_linalg::impl_dyn_hash!(Squeeze13);
+
+impl Expansion for Squeeze13 {
+    fn name(&self) -> Cow<str> {
+        "Squeeze13".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, &inputs[0].datum_type)?;
+        if inputs.len() == 1 {
+            return s.given(&inputs[0].shape, move |s, shape| {
+                s.equals(&outputs[0].shape, array::Squeeze::new(None).compute_shape(&shape)?)
+            });
+        }
+        check_input_arity(&inputs, 2)?;
+        s.given_2(&inputs[0].shape, &inputs[1].value, move |s, shape, axes_value| {
+            let op = array::Squeeze::new(Some(axes(&axes_value)?));
+            s.equals(&outputs[0].shape, op.compute_shape(&shape)?)
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let axes = if inputs.len() == 2 { Some(const_axes(model, inputs[1])?) } else { None };
+        array::Squeeze::new(axes).wire(prefix, model, &inputs[0..1])
+    }
+}
+
+/// Unsqueeze-13 takes the axes as an input instead of an attribute.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct Unsqueeze13;
+
+tract_linalg::impl_dyn_hash!(Unsqueeze13);
+
+impl Expansion for Unsqueeze13 {
+    fn name(&self) -> Cow<str> {
+        "Unsqueeze13".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 2)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, &inputs[0].datum_type)?;
+        s.given(&inputs[1].shape[0], move |s, axes_count| {
+            s.equals(&outputs[0].rank, (&inputs[0].rank).bex() + axes_count.to_integer()?)
+        })?;
+        s.given_2(&inputs[0].shape, &inputs[1].value, move |s, shape, axes_value| {
+            let op = unsqueeze_op(axes(&axes_value)?, shape.len());
+            s.equals(&outputs[0].shape, op.compute_shape(&shape))
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let axes = const_axes(model, inputs[1])?;
+        let rank = model.outlet_fact(inputs[0])?.rank();
+        unsqueeze_op(axes, rank).wire(prefix, model, &inputs[0..1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ops::test_util::*;
+    use tract_hir::internal::*;
+
+    fn run(op: &str, x: Tensor, axes: Option<Tensor>) -> Tensor {
+        let (inputs, consts) = if let Some(axes) = axes {
+            (vec!["x", "axes"], vec![("axes", axes)])
+        } else {
+            (vec!["x"], vec![])
+        };
+        let node = node(op, &inputs, &["y"], vec![]);
+        let outputs = &[("y", x.datum_type())];
+        run_node(13, node, &[("x", x)], &consts, outputs).unwrap().remove(0).into_tensor()
+    }
+
+    #[test]
+    fn squeeze13_with_axes_input() {
+        let x = tensor3(&[[[1f32, 2.]]]);
+        assert_eq!(run("Squeeze", x.clone(), Some(tensor1(&[1i64]))), tensor2(&[[1f32, 2.]]));
+        assert_eq!(run("Squeeze", x, Some(tensor1(&[-3i64, -2]))), tensor1(&[1f32, 2.]));
+    }
+
+    #[test]
+    fn squeeze13_without_axes_input() {
+        assert_eq!(run("Squeeze", tensor3(&[[[1f32], [2.]]]), None), tensor1(&[1f32, 2.]));
+    }
+
+    #[test]
+    fn unsqueeze13() {
+        let x = tensor1(&[1f32, 2.]);
+        assert_eq!(run("Unsqueeze", x.clone(), Some(tensor1(&[0i64]))), tensor2(&[[1f32, 2.]]));
+        assert_eq!(run("Unsqueeze", x, Some(tensor1(&[0i64, -1]))), tensor3(&[[[1f32], [2.]]]));
+    }
+}
@@ -0,0 +1,83 @@
+use crate::model::{optional_outputs, ParsingContext};
+use crate::pb::*;
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::array::Unique as TypedUnique;
+
+pub fn unique(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr_opt("axis")?;
+    let sorted = node.get_attr_opt("sorted")?.unwrap_or(1i64) == 1;
+    let outputs = optional_outputs(node).take(4).collect();
+    Ok((expand(Unique::new(axis, sorted, outputs)), vec![]))
+}
+
+/// Unique with optional outputs: `outputs` gives the node output slot, if
+/// any, of the values, indices, inverse indices and counts.
+///
+/// The number of unique values is a symbol named after the node.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Unique {
+    axis: Option<isize>,
+    sorted: bool,
+    outputs: TVec<Option<usize>>,
+}
+
+tract_linalg::impl_dyn_hash!(Unique);
+
+impl Expansion for Unique {
+    fn name(&self) -> Cow<str> {
+        "Unique".into()
+    }
+
+    op_onnx!();
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.outputs.iter().filter(|o| o.is_some()).count())
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, self.nboutputs()?)?;
+        if let Some(values) = self.outputs[0] {
+            s.equals(&outputs[values].datum_type, &inputs[0].datum_type)?;
+            if self.axis.is_some() {
+                s.equals(&outputs[values].rank, &inputs[0].rank)?;
+            } else {
+                s.equals(&outputs[values].rank, 1)?;
+            }
+        }
+        for &slot in self.outputs.iter().skip(1) {
+            if let Some(slot) = slot {
+                s.equals(&outputs[slot].datum_type, DatumType::I64)?;
+                s.equals(&outputs[slot].rank, 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let rank = model.outlet_fact(inputs[0])?.rank() as isize;
+        let axis = self.axis.map(|axis| if axis < 0 { axis + rank } else { axis } as usize);
+        let op = TypedUnique::new(axis, self.sorted, TDim::sym(format!("{}.count", prefix)));
+        let wires = model.wire_node(prefix, op, inputs)?;
+        Ok(self
+            .outputs
+            .iter()
+            .zip(wires.iter())
+            .filter(|(o, _)| o.is_some())
+            .map(|(_, w)| *w)
+            .collect())
+    }
+}
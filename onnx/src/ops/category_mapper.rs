@@ -6,6 +6,7 @@ use tract_hir::tract_core::itertools::Itertools;
 
 pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("CategoryMapper", category_mapper);
+    reg.insert_in_domain("ai.onnx.ml", "CategoryMapper", category_mapper);
 }
 
 fn category_mapper(
@@ -99,3 +100,30 @@ impl<Src: Datum + Hash + Eq + Ord, Dst: Datum + Hash> TypedOp for CategoryMapper
 
     as_op!();
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::export::{attr_int, attr_ints};
+    use crate::ops::test_util::*;
+    use crate::pb::*;
+    use tract_hir::internal::*;
+
+    #[test]
+    fn category_mapper_in_ml_domain() {
+        let cats_strings = AttributeProto {
+            name: "cats_strings".to_string(),
+            r#type: attribute_proto::AttributeType::Strings as i32,
+            strings: vec![b"a".to_vec(), b"b".to_vec()],
+            ..AttributeProto::default()
+        };
+        let attrs =
+            vec![cats_strings, attr_ints("cats_int64s", vec![1, 2]), attr_int("default_int64", -1)];
+        let node = NodeProto {
+            domain: "ai.onnx.ml".to_string(),
+            ..node("CategoryMapper", &["x"], &["y"], attrs)
+        };
+        let x = tensor1(&["a".to_string(), "c".to_string(), "b".to_string()]);
+        let y = run_node(13, node, &[("x", x)], &[], &[("y", i64::datum_type())]).unwrap();
+        assert_eq!(y[0], rctensor1(&[1i64, -1, 2]));
+    }
+}
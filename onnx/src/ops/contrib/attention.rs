@@ -0,0 +1,302 @@
+use super::scalar;
+use crate::model::{optional_inputs, ParsingContext};
+use crate::pb::NodeProto;
+use tract_hir::internal::*;
+use tract_hir::ops;
+use tract_hir::ops::binary::wire_rank_broadcast;
+use tract_hir::ops::math;
+use tract_hir::tract_core::ops::array::Slice;
+
+pub fn attention(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let num_heads = node.get_attr::<usize>("num_heads")?;
+    let unidirectional = node.get_attr_opt("unidirectional")?.unwrap_or(false);
+    let mask_filter_value = node.get_attr_opt("mask_filter_value")?.unwrap_or(-10000.0);
+    let scale = node.get_attr_opt("scale")?;
+    if node.get_attr_opt_vec::<i64>("qkv_hidden_sizes")?.is_some() {
+        bail!("Attention: qkv_hidden_sizes is not supported")
+    }
+    if node.output.iter().skip(1).any(|o| !o.is_empty()) {
+        bail!("Attention: present output is not supported")
+    }
+    let mut optional = optional_inputs(node).skip(3);
+    let mask = optional.next().unwrap();
+    if optional.next().unwrap().is_some() {
+        bail!("Attention: past input is not supported")
+    }
+    let extra_add = optional.next().unwrap();
+    if node.input.iter().skip(6).any(|input| !input.is_empty()) {
+        bail!("Attention: only input, weights, bias, mask_index and extra_add inputs are supported")
+    }
+    let op = Attention::new(num_heads, unidirectional, mask_filter_value, scale, mask, extra_add);
+    Ok((expand(op), vec![]))
+}
+
+/// Multi-head self attention, with packed query, key and value weights.
+///
+/// input is [batch, seq, input_hidden], weights [input_hidden, 3 * hidden],
+/// bias [3 * hidden]. The optional mask is a raw [batch, seq] 0/1 mask, the
+/// optional extra_add is added to the attention scores.
+#[derive(Debug, Clone, new, Educe)]
+#[educe(Hash)]
+pub struct Attention {
+    num_heads: usize,
+    unidirectional: bool,
+    #[educe(Hash(method = "hash_f32"))]
+    mask_filter_value: f32,
+    #[educe(Hash(method = "hash_opt_f32"))]
+    scale: Option<f32>,
+    mask: Option<usize>,
+    extra_add: Option<usize>,
+}
+
+tract_linalg::impl_dyn_hash!(Attention);
+
+fn hash_opt_f32<H: std::hash::Hasher>(s: &Option<f32>, state: &mut H) {
+    s.map(|f| f.to_bits()).hash(state)
+}
+
+impl Expansion for Attention {
+    fn name(&self) -> Cow<str> {
+        "Attention".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("heads: {} unidirectional: {:?}", self.num_heads, self.unidirectional)])
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(
+            inputs,
+            3 + self.mask.is_some() as usize + self.extra_add.is_some() as usize,
+        )?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[1].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[2].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].rank, 3)?;
+        s.equals(&inputs[1].rank, 2)?;
+        s.equals(&inputs[2].rank, 1)?;
+        s.equals(&outputs[0].rank, 3)?;
+        s.equals(&inputs[0].shape[2], &inputs[1].shape[0])?;
+        s.equals(&inputs[1].shape[1], &inputs[2].shape[0])?;
+        s.equals(&inputs[0].shape[0], &outputs[0].shape[0])?;
+        s.equals(&inputs[0].shape[1], &outputs[0].shape[1])?;
+        s.given(&inputs[1].shape[1], move |s, qkv| s.equals(&outputs[0].shape[2], qkv / 3u32))?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let input_fact = model.outlet_fact(inputs[0])?.clone();
+        let qkv_size = model.outlet_fact(inputs[1])?.shape.dim(1).to_integer()? as usize;
+        let hidden = qkv_size / 3;
+        if hidden * 3 != qkv_size || hidden % self.num_heads != 0 {
+            bail!(
+                "Attention: weights of width {} do not split in 3 times {} heads",
+                qkv_size,
+                self.num_heads
+            )
+        }
+        let head_size = hidden / self.num_heads;
+
+        let qkv = model.wire_node(
+            format!("{}.qkv", name),
+            ops::matmul::MatMul::default(),
+            &inputs[0..2],
+        )?[0];
+        let wires = wire_rank_broadcast(&format!("{}.qkv_bias", name), model, &[qkv, inputs[2]])?;
+        let qkv =
+            model.wire_node(format!("{}.qkv_biased", name), math::add::bin_typed(), &wires)?[0];
+
+        // [batch, seq, hidden] -> [batch, heads, seq, head_size]
+        let mut heads = tvec!();
+        for (ix, part) in ["q", "k", "v"].iter().enumerate() {
+            let wire = model.wire_node(
+                format!("{}.{}", name, part),
+                Slice::new(2, ix * hidden, (ix + 1) * hidden),
+                &[qkv],
+            )?[0];
+            let wire = model.wire_node(
+                format!("{}.{}_split_heads", name, part),
+                AxisOp::Reshape(
+                    2,
+                    tvec!(hidden.to_dim()),
+                    tvec!(self.num_heads.to_dim(), head_size.to_dim()),
+                ),
+                &[wire],
+            )?[0];
+            let wire = model.wire_node(
+                format!("{}.{}_heads_first", name, part),
+                AxisOp::Move(2, 1),
+                &[wire],
+            )?[0];
+            heads.push(wire);
+        }
+
+        let scores = model.wire_node(
+            format!("{}.qk", name),
+            ops::matmul::MatMul::default().with_b_trans(true),
+            &heads[0..2],
+        )?[0];
+        let scale = self.scale.unwrap_or(1.0 / (head_size as f32).sqrt());
+        let mut scores = model.wire_node(
+            format!("{}.qk_scaled", name),
+            math::mul::unary(scalar(model, scores, scale)?),
+            &[scores],
+        )?[0];
+
+        if let Some(mask) = self.mask {
+            let mask = inputs[mask];
+            if model.outlet_fact(mask)?.rank() != 2 {
+                bail!("Attention: only [batch, seq] raw masks are supported")
+            }
+            let mask = model.wire_node(
+                format!("{}.mask_as_float", name),
+                tract_hir::tract_core::ops::cast::cast(input_fact.datum_type),
+                &[mask],
+            )?[0];
+            // (1 - mask) * mask_filter_value
+            let mask = model.wire_node(
+                format!("{}.mask_filter", name),
+                math::mul::unary(scalar(model, mask, -self.mask_filter_value)?),
+                &[mask],
+            )?[0];
+            let mut mask = model.wire_node(
+                format!("{}.mask_offset", name),
+                math::add::unary(scalar(model, mask, self.mask_filter_value)?),
+                &[mask],
+            )?[0];
+            for axis in 1..3 {
+                mask = model.wire_node(
+                    format!("{}.mask_add_axis_{}", name, axis),
+                    AxisOp::Add(1),
+                    &[mask],
+                )?[0];
+            }
+            scores = model.wire_node(
+                format!("{}.masked", name),
+                math::add::bin_typed(),
+                &[scores, mask],
+            )?[0];
+        }
+
+        if self.unidirectional {
+            let seq = input_fact.shape.dim(1).to_integer().map_err(|_| {
+                format!("Attention: unidirectional attention requires a known sequence length")
+            })? as usize;
+            let causal = tract_ndarray::Array4::from_shape_fn((1, 1, seq, seq), |(_, _, i, j)| {
+                if j > i {
+                    self.mask_filter_value
+                } else {
+                    0.0f32
+                }
+            });
+            let causal = model.add_const(format!("{}.causal_mask", name), Tensor::from(causal))?;
+            scores = model.wire_node(
+                format!("{}.causal", name),
+                math::add::bin_typed(),
+                &[scores, causal],
+            )?[0];
+        }
+
+        if let Some(extra_add) = self.extra_add {
+            scores = model.wire_node(
+                format!("{}.extra_add", name),
+                math::add::bin_typed(),
+                &[scores, inputs[extra_add]],
+            )?[0];
+        }
+
+        let probs = ops::nn::LayerSoftmax::new(3, false).wire(
+            &format!("{}.softmax", name),
+            model,
+            &[scores],
+        )?;
+        let context = model.wire_node(
+            format!("{}.context", name),
+            ops::matmul::MatMul::default(),
+            &[probs[0], heads[2]],
+        )?[0];
+        let context =
+            model.wire_node(format!("{}.seq_first", name), AxisOp::Move(1, 2), &[context])?[0];
+        model.wire_node(
+            name,
+            AxisOp::Reshape(
+                2,
+                tvec!(self.num_heads.to_dim(), head_size.to_dim()),
+                tvec!(hidden.to_dim()),
+            ),
+            &[context],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::attr_int;
+    use crate::ops::test_util::*;
+    use crate::pb::AttributeProto;
+    use tract_hir::internal::*;
+
+    // with one head, identity weights and no bias, q = k = v = x, and the
+    // scores of [[1, 0], [0, 1]] are [[a, 0], [0, a]] with a = 1/sqrt(2)
+    const P: f32 = 0.669_762;
+
+    fn attention(attrs: Vec<AttributeProto>, mask: Option<Tensor>) -> TractResult<Tensor> {
+        let weights = tensor2(&[[1f32, 0., 1., 0., 1., 0.], [0., 1., 0., 1., 0., 1.]]);
+        let mut consts = vec![("w", weights), ("b", tensor1(&[0f32; 6]))];
+        let mut inputs = vec!["x", "w", "b"];
+        if let Some(mask) = mask {
+            consts.push(("mask", mask));
+            inputs.push("mask");
+        }
+        let mut attrs = attrs;
+        attrs.push(attr_int("num_heads", 1));
+        let node = ms_node("Attention", &inputs, &["y"], attrs);
+        let x = tensor3(&[[[1f32, 0.], [0., 1.]]]);
+        let outputs = [("y", f32::datum_type())];
+        Ok(run_node(11, node, &[("x", x)], &consts, &outputs)?.remove(0).into_tensor())
+    }
+
+    #[test]
+    fn self_attention() {
+        let y = attention(vec![], None).unwrap();
+        y.close_enough(&tensor3(&[[[P, 1. - P], [1. - P, P]]]), true).unwrap();
+    }
+
+    #[test]
+    fn unidirectional_attention() {
+        let y = attention(vec![attr_int("unidirectional", 1)], None).unwrap();
+        y.close_enough(&tensor3(&[[[1f32, 0.], [1. - P, P]]]), true).unwrap();
+    }
+
+    #[test]
+    fn masked_attention() {
+        let y = attention(vec![], Some(tensor2(&[[1i32, 0]]))).unwrap();
+        y.close_enough(&tensor3(&[[[1f32, 0.], [1., 0.]]]), true).unwrap();
+    }
+
+    #[test]
+    fn heads_must_split_hidden_size() {
+        let attrs = vec![attr_int("num_heads", 4)];
+        let weights = tensor2(&[[1f32, 0., 1., 0., 1., 0.], [0., 1., 0., 1., 0., 1.]]);
+        let consts = [("w", weights), ("b", tensor1(&[0f32; 6]))];
+        let node = ms_node("Attention", &["x", "w", "b"], &["y"], attrs);
+        let x = tensor3(&[[[1f32, 0.], [0., 1.]]]);
+        assert!(run_node(11, node, &[("x", x)], &consts, &[("y", f32::datum_type())]).is_err());
+    }
+}
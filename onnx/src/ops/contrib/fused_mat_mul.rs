@@ -0,0 +1,103 @@
+use super::scalar;
+use crate::model::ParsingContext;
+use crate::pb::NodeProto;
+use tract_hir::internal::*;
+use tract_hir::ops;
+
+pub fn fused_mat_mul(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let alpha = node.get_attr_opt("alpha")?.unwrap_or(1.);
+    let trans_a = node.get_attr_opt("transA")?.unwrap_or(false);
+    let trans_b = node.get_attr_opt("transB")?.unwrap_or(false);
+    if node.get_attr_opt("transBatchA")?.unwrap_or(false)
+        || node.get_attr_opt("transBatchB")?.unwrap_or(false)
+    {
+        bail!("FusedMatMul: transBatchA and transBatchB are not supported")
+    }
+    Ok((expand(FusedMatMul::new(alpha, trans_a, trans_b)), vec![]))
+}
+
+/// MatMul with optionally transposed operands, scaled by alpha.
+#[derive(Debug, Clone, new, Educe)]
+#[educe(Hash)]
+pub struct FusedMatMul {
+    #[educe(Hash(method = "hash_f32"))]
+    alpha: f32,
+    trans_a: bool,
+    trans_b: bool,
+}
+
+tract_linalg::impl_dyn_hash!(FusedMatMul);
+
+impl Expansion for FusedMatMul {
+    fn name(&self) -> Cow<str> {
+        "FusedMatMul".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 2)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[1].datum_type, &outputs[0].datum_type)?;
+        s.given_2(&inputs[0].shape, &inputs[1].shape, move |s, ashape, bshape| {
+            let (_, _, _, cshape) =
+                ops::matmul::compute_shapes(ashape, bshape, self.trans_a, self.trans_b, false)?;
+            s.equals(&outputs[0].shape, cshape)
+        })
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let op =
+            ops::matmul::MatMul::default().with_a_trans(self.trans_a).with_b_trans(self.trans_b);
+        if self.alpha == 1.0 {
+            return model.wire_node(name, op, inputs);
+        }
+        let wire = model.wire_node(format!("{}.ab", name), op, inputs)?[0];
+        model.wire_node(name, ops::math::mul::unary(scalar(model, wire, self.alpha)?), &[wire])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::{attr_float, attr_int};
+    use crate::ops::test_util::*;
+    use crate::pb::AttributeProto;
+    use tract_hir::internal::*;
+
+    fn run(attrs: Vec<AttributeProto>, a: Tensor, b: Tensor) -> Tensor {
+        let node = ms_node("FusedMatMul", &["a", "b"], &["c"], attrs);
+        let outputs = [("c", f32::datum_type())];
+        run_node(11, node, &[("a", a)], &[("b", b)], &outputs).unwrap().remove(0).into_tensor()
+    }
+
+    #[test]
+    fn fused_mat_mul() {
+        let a = tensor2(&[[1f32, 2., 3.], [4., 5., 6.]]);
+        let b = tensor2(&[[1f32, 0.], [0., 1.], [1., 1.]]);
+        assert_eq!(run(vec![], a.clone(), b.clone()), tensor2(&[[4f32, 5.], [10., 11.]]));
+        let attrs = vec![attr_float("alpha", 0.5)];
+        assert_eq!(run(attrs, a, b), tensor2(&[[2f32, 2.5], [5., 5.5]]));
+    }
+
+    #[test]
+    fn fused_mat_mul_transposed() {
+        let a = tensor2(&[[1f32, 4.], [2., 5.], [3., 6.]]);
+        let b = tensor2(&[[1f32, 0., 1.], [0., 1., 1.]]);
+        let attrs = vec![attr_int("transA", 1), attr_int("transB", 1), attr_float("alpha", 2.)];
+        assert_eq!(run(attrs, a, b), tensor2(&[[8f32, 10.], [20., 22.]]));
+    }
+}
@@ -0,0 +1,156 @@
+use super::scalar;
+use crate::model::ParsingContext;
+use crate::pb::NodeProto;
+use tract_hir::internal::*;
+use tract_hir::ops::binary::wire_rank_broadcast;
+use tract_hir::ops::math;
+
+pub fn gelu(
+    _ctx: &ParsingContext,
+    _node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    Ok((expand(Gelu::new(false, false)), vec![]))
+}
+
+pub fn bias_gelu(
+    _ctx: &ParsingContext,
+    _node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    Ok((expand(Gelu::new(false, true)), vec![]))
+}
+
+pub fn fast_gelu(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let bias = crate::model::optional_inputs(node).skip(1).next().unwrap().is_some();
+    Ok((expand(Gelu::new(true, bias)), vec![]))
+}
+
+/// Gaussian error linear unit, with the optional bias added to the input
+/// first. The fast variant uses the tanh approximation.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Gelu {
+    fast: bool,
+    bias: bool,
+}
+
+tract_linalg::impl_dyn_hash!(Gelu);
+
+impl Expansion for Gelu {
+    fn name(&self) -> Cow<str> {
+        if self.fast {
+            "FastGelu".into()
+        } else {
+            "Gelu".into()
+        }
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("bias: {:?}", self.bias)])
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1 + self.bias as usize)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        if self.bias {
+            s.equals(&inputs[1].datum_type, &outputs[0].datum_type)?;
+        }
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let mut x = inputs[0];
+        if self.bias {
+            let wires = wire_rank_broadcast(&format!("{}.bias", name), model, inputs)?;
+            x = model.wire_node(format!("{}.biased", name), math::add::bin_typed(), &wires)?[0];
+        }
+        let inner = if self.fast {
+            // sqrt(2/pi) * (x + 0.044715 * x^3)
+            let sqr = model.wire_node(format!("{}.sqr", name), math::square(), &[x])?[0];
+            let cube =
+                model.wire_node(format!("{}.cube", name), math::mul::bin_typed(), &[sqr, x])?[0];
+            let cube = model.wire_node(
+                format!("{}.cube_coef", name),
+                math::mul::unary(scalar(model, x, 0.044715)?),
+                &[cube],
+            )?[0];
+            let sum =
+                model.wire_node(format!("{}.sum", name), math::add::bin_typed(), &[x, cube])?[0];
+            let scaled = model.wire_node(
+                format!("{}.scaled", name),
+                math::mul::unary(scalar(model, x, (2.0 / std::f32::consts::PI).sqrt())?),
+                &[sum],
+            )?[0];
+            model.wire_node(format!("{}.tanh", name), math::tanh(), &[scaled])?[0]
+        } else {
+            // erf(x / sqrt(2))
+            let scaled = model.wire_node(
+                format!("{}.scaled", name),
+                math::mul::unary(scalar(model, x, std::f32::consts::FRAC_1_SQRT_2)?),
+                &[x],
+            )?[0];
+            model.wire_node(format!("{}.erf", name), crate::ops::math::erf(), &[scaled])?[0]
+        };
+        let one_plus = model.wire_node(
+            format!("{}.one_plus", name),
+            math::add::unary(scalar(model, x, 1.0)?),
+            &[inner],
+        )?[0];
+        let half = model.wire_node(
+            format!("{}.half", name),
+            math::mul::unary(scalar(model, x, 0.5)?),
+            &[one_plus],
+        )?[0];
+        model.wire_node(name, math::mul::bin_typed(), &[x, half])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ops::test_util::*;
+    use tract_hir::internal::*;
+
+    fn run(op: &str, bias: Option<Tensor>) -> Tensor {
+        let x = tensor1(&[-1f32, 0., 1., 2.]);
+        let (inputs, consts) =
+            if let Some(b) = bias { (vec!["x", "b"], vec![("b", b)]) } else { (vec!["x"], vec![]) };
+        let node = ms_node(op, &inputs, &["y"], vec![]);
+        let outputs = [("y", f32::datum_type())];
+        run_node(11, node, &[("x", x)], &consts, &outputs).unwrap().remove(0).into_tensor()
+    }
+
+    #[test]
+    fn gelu() {
+        let expected = tensor1(&[-0.158655f32, 0., 0.841345, 1.954500]);
+        run("Gelu", None).close_enough(&expected, true).unwrap();
+    }
+
+    #[test]
+    fn bias_gelu() {
+        let expected = tensor1(&[0f32, 0.841345, 1.954500, 2.995950]);
+        run("BiasGelu", Some(tensor1(&[1f32]))).close_enough(&expected, true).unwrap();
+    }
+
+    #[test]
+    fn fast_gelu() {
+        let expected = tensor1(&[-0.158808f32, 0., 0.841192, 1.954598]);
+        run("FastGelu", None).close_enough(&expected, true).unwrap();
+        let expected = tensor1(&[0f32, 0.841192, 1.954598, 2.996363]);
+        run("FastGelu", Some(tensor1(&[1f32]))).close_enough(&expected, true).unwrap();
+    }
+}
@@ -0,0 +1,53 @@
+use crate::model::OnnxOpRegister;
+use tract_hir::internal::*;
+
+mod attention;
+mod fused_mat_mul;
+mod gelu;
+mod skip_layer_norm;
+
+/// Operators from onnxruntime "com.microsoft" domain commonly found in
+/// transformer exports.
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    let ms = "com.microsoft";
+    reg.insert_in_domain(ms, "Attention", attention::attention);
+    reg.insert_in_domain(ms, "BiasGelu", gelu::bias_gelu);
+    reg.insert_in_domain(ms, "FastGelu", gelu::fast_gelu);
+    reg.insert_in_domain(ms, "FusedMatMul", fused_mat_mul::fused_mat_mul);
+    reg.insert_in_domain(ms, "Gelu", gelu::gelu);
+    reg.insert_in_domain(ms, "LayerNormalization", super::nn::layer_norm::layer_normalization);
+    reg.insert_in_domain(ms, "SkipLayerNormalization", skip_layer_norm::skip_layer_normalization);
+}
+
+/// A scalar with the rank of `wire`, to combine with it in a unary op.
+fn scalar(model: &TypedModel, wire: OutletId, v: f32) -> TractResult<Arc<Tensor>> {
+    Ok(tensor0(v).broadcast_into_rank(model.outlet_fact(wire)?.rank())?.into_arc_tensor())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ops::test_util::*;
+    use tract_hir::internal::*;
+
+    #[test]
+    fn contrib_ops_are_in_microsoft_domain() {
+        let reg = &crate::onnx().op_register;
+        assert!(reg.get("com.microsoft", "Gelu").is_some());
+        assert!(reg.get("com.microsoft", "LayerNormalization").is_some());
+        assert!(reg.get("", "Gelu").is_none());
+        assert!(reg.get("ai.onnx", "Gelu").is_none());
+        // ai.onnx is the default domain
+        assert!(reg.get("ai.onnx", "Relu").is_some());
+        assert!(reg.get("com.microsoft", "Relu").is_none());
+    }
+
+    #[test]
+    fn contrib_op_in_default_domain_is_not_resolved() {
+        let x = tensor1(&[1f32]);
+        let outputs = [("y", f32::datum_type())];
+        let mut gelu = node("Gelu", &["x"], &["y"], vec![]);
+        assert!(run_node(11, gelu.clone(), &[("x", x.clone())], &[], &outputs).is_err());
+        gelu.domain = "com.microsoft".to_string();
+        assert!(run_node(11, gelu, &[("x", x)], &[], &outputs).is_ok());
+    }
+}
@@ -0,0 +1,106 @@
+use crate::model::{optional_inputs, ParsingContext};
+use crate::ops::nn::layer_norm::wire_layer_norm;
+use crate::pb::NodeProto;
+use tract_hir::internal::*;
+use tract_hir::ops::binary::wire_rank_broadcast;
+use tract_hir::ops::math;
+
+pub fn skip_layer_normalization(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let epsilon = node.get_attr_opt("epsilon")?.unwrap_or(1e-12);
+    if node.output.iter().skip(1).any(|o| !o.is_empty()) {
+        bail!("SkipLayerNormalization: only the normalized output is supported")
+    }
+    let mut optional = optional_inputs(node).skip(3);
+    let beta = optional.next().unwrap();
+    let bias = optional.next().unwrap();
+    Ok((expand(SkipLayerNorm::new(epsilon, beta, bias)), vec![]))
+}
+
+/// Layer normalization over the last axis of input + skip (+ bias).
+#[derive(Debug, Clone, new, Educe)]
+#[educe(Hash)]
+pub struct SkipLayerNorm {
+    #[educe(Hash(method = "hash_f32"))]
+    epsilon: f32,
+    beta: Option<usize>,
+    bias: Option<usize>,
+}
+
+tract_linalg::impl_dyn_hash!(SkipLayerNorm);
+
+impl Expansion for SkipLayerNorm {
+    fn name(&self) -> Cow<str> {
+        "SkipLayerNorm".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 3 + self.beta.is_some() as usize + self.bias.is_some() as usize)?;
+        check_output_arity(outputs, 1)?;
+        for input in inputs {
+            s.equals(&input.datum_type, &outputs[0].datum_type)?;
+        }
+        s.equals(&inputs[0].shape, &inputs[1].shape)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let mut x = model.wire_node(
+            format!("{}.skip", name),
+            math::add::bin_typed(),
+            &[inputs[0], inputs[1]],
+        )?[0];
+        if let Some(bias) = self.bias {
+            let wires = wire_rank_broadcast(&format!("{}.bias", name), model, &[x, inputs[bias]])?;
+            x = model.wire_node(format!("{}.biased", name), math::add::bin_typed(), &wires)?[0];
+        }
+        let beta = self.beta.map(|beta| inputs[beta]);
+        wire_layer_norm(name, model, x, inputs[2], beta, -1, self.epsilon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ops::test_util::*;
+    use tract_hir::internal::*;
+
+    #[test]
+    fn skip_layer_norm() {
+        // x + skip + bias is [2, 6], normalized to [-1, 1]
+        let consts = [
+            ("skip", tensor3(&[[[1f32, 1.]]])),
+            ("gamma", tensor1(&[2f32, 3.])),
+            ("beta", tensor1(&[1f32, 1.])),
+            ("bias", tensor1(&[0f32, 2.])),
+        ];
+        let node = ms_node(
+            "SkipLayerNormalization",
+            &["x", "skip", "gamma", "beta", "bias"],
+            &["y"],
+            vec![],
+        );
+        let x = tensor3(&[[[1f32, 3.]]]);
+        let outputs = [("y", f32::datum_type())];
+        let y = run_node(11, node, &[("x", x.clone())], &consts, &outputs).unwrap();
+        y[0].close_enough(&tensor3(&[[[-1f32, 4.]]]), true).unwrap();
+        // without beta nor bias, [2, 4] is normalized to [-1, 1]
+        let node = ms_node("SkipLayerNormalization", &["x", "skip", "gamma"], &["y"], vec![]);
+        let y = run_node(11, node, &[("x", x)], &consts[0..2], &outputs).unwrap();
+        y[0].close_enough(&tensor3(&[[[-2f32, 3.]]]), true).unwrap();
+    }
+}
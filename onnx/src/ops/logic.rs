@@ -1,6 +1,9 @@
-use crate::model::OnnxOpRegister;
+use crate::model::{OnnxOpRegister, ParseResult, ParsingContext};
+use crate::pb::*;
+use tract_hir::infer::*;
 use tract_hir::internal::*;
 use tract_hir::ops;
+use tract_hir::tract_core::ops::control_flow;
 
 pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("Not", |_, _| Ok((Box::new(ops::logic::not()), vec![])));
@@ -15,4 +18,224 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("GreaterOrEqual", |_, _| Ok((ops::logic::GreaterEqual.into_hir(), vec![])));
 
     reg.insert("Where", |_, _| Ok((Box::new(ops::logic::Iff::default()), vec![])));
+
+    reg.insert("If", if_);
+}
+
+pub fn if_(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let graph_then: &GraphProto = node.get_attr("then_branch")?;
+    let graph_else: &GraphProto = node.get_attr("else_branch")?;
+    let ParseResult { model: then_body, unresolved_inputs: unresolved_then, .. } =
+        ctx.parse_graph(graph_then)?;
+    let ParseResult { model: else_body, unresolved_inputs: unresolved_else, .. } =
+        ctx.parse_graph(graph_else)?;
+    // branches have no formal inputs: they only close on outer values, which
+    // are wired as inputs of the op after the condition.
+    let mut closures = unresolved_then.clone();
+    for name in &unresolved_else {
+        if !closures.contains(name) {
+            closures.push(name.clone());
+        }
+    }
+    let mapping = |unresolved: &[String]| -> Vec<usize> {
+        unresolved.iter().map(|name| 1 + closures.iter().position(|c| c == name).unwrap()).collect()
+    };
+    let then_input_mapping = mapping(&unresolved_then);
+    let else_input_mapping = mapping(&unresolved_else);
+    Ok((Box::new(If::new(then_body, then_input_mapping, else_body, else_input_mapping)), closures))
+}
+
+/// ONNX If.
+///
+/// When the condition is known at translation time, only the selected branch
+/// is wired in the typed model. Otherwise, it translates to a core If, which
+/// the decluttering will also resolve if constant propagation manages to
+/// determine the condition.
+#[derive(Debug, Clone, new, Hash)]
+pub struct If {
+    pub then_body: InferenceModel,
+    then_input_mapping: Vec<usize>,
+    pub else_body: InferenceModel,
+    else_input_mapping: Vec<usize>,
+}
+
+tract_linalg::impl_dyn_hash!(If);
+
+impl If {
+    fn branch(&self, cond: &Tensor) -> TractResult<(&InferenceModel, &[usize])> {
+        if cond.cast_to_scalar::<bool>()? {
+            Ok((&self.then_body, &self.then_input_mapping))
+        } else {
+            Ok((&self.else_body, &self.else_input_mapping))
+        }
+    }
+}
+
+impl Op for If {
+    fn name(&self) -> Cow<str> {
+        "If".into()
+    }
+
+    op_onnx!();
+    not_a_typed_op!();
+}
+
+impl StatelessOp for If {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (body, mapping) = self.branch(&inputs[0])?;
+        let body_inputs = mapping.iter().map(|&slot| inputs[slot].clone().into_tensor()).collect();
+        SimplePlan::new(body)?.run(body_inputs)
+    }
+}
+
+impl InferenceOp for If {
+    fn infer_facts(
+        &mut self,
+        inputs: TVec<&InferenceFact>,
+        outputs: TVec<&InferenceFact>,
+        _observed: TVec<&InferenceFact>,
+    ) -> TractResult<(TVec<InferenceFact>, TVec<InferenceFact>, TVec<InferenceFact>)> {
+        let mut inputs: TVec<InferenceFact> = inputs.into_iter().cloned().collect();
+        let mut outputs: TVec<InferenceFact> = outputs.into_iter().cloned().collect();
+        inputs[0].datum_type.unify_with(&bool::datum_type().into())?;
+        loop {
+            let mut changed = false;
+            for (body, mapping) in &mut [
+                (&mut self.then_body, &self.then_input_mapping),
+                (&mut self.else_body, &self.else_input_mapping),
+            ] {
+                for (ix, &slot) in mapping.iter().enumerate() {
+                    if inputs[slot].unify_with_mut(body.input_fact_mut(ix)?)? {
+                        changed = true;
+                    }
+                }
+                if body.analyse(false)? {
+                    changed = true;
+                }
+            }
+            for (ix, output) in outputs.iter_mut().enumerate() {
+                if let Some(cond) = inputs[0].value.concretize() {
+                    let (body, _) = self.branch(&cond)?;
+                    if output.unify_with(body.output_fact(ix)?)? {
+                        changed = true;
+                    }
+                } else {
+                    let then_fact = self.then_body.output_fact(ix)?;
+                    let else_fact = self.else_body.output_fact(ix)?;
+                    if output.datum_type.unify_with(&then_fact.datum_type)?
+                        || output.datum_type.unify_with(&else_fact.datum_type)?
+                    {
+                        changed = true;
+                    }
+                    if then_fact.shape == else_fact.shape {
+                        if output.shape.unify_with(&then_fact.shape)? {
+                            changed = true;
+                        }
+                    } else if let (Some(then_shape), Some(else_shape)) =
+                        (then_fact.shape.concretize(), else_fact.shape.concretize())
+                    {
+                        // only keep the rank and the dimensions both branches agree on
+                        if then_shape.len() == else_shape.len() {
+                            let dims = then_shape
+                                .iter()
+                                .zip(else_shape.iter())
+                                .map(|(t, e)| {
+                                    if t == e {
+                                        GenericFactoid::Only(t.clone())
+                                    } else {
+                                        GenericFactoid::Any
+                                    }
+                                })
+                                .collect();
+                            if output.shape.unify_with(&ShapeFactoid::closed(dims))? {
+                                changed = true;
+                            }
+                        }
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        Ok((inputs, outputs, tvec!()))
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let inputs: TVec<OutletId> = node.inputs.iter().map(|i| mapping[i]).collect();
+        if let Some(cond) = target.outlet_fact(inputs[0])?.konst.clone() {
+            let (body, mapping) = self.branch(&cond)?;
+            let body = body.clone().into_typed()?;
+            let inputs: TVec<OutletId> = mapping.iter().map(|&slot| inputs[slot]).collect();
+            control_flow::wire_body(&node.name, &body, target, &inputs)
+        } else {
+            let op = control_flow::If::new(
+                self.then_body.clone().into_typed()?,
+                self.then_input_mapping.clone(),
+                self.else_body.clone().into_typed()?,
+                self.else_input_mapping.clone(),
+                node.name.clone(),
+            );
+            target.wire_node(&*node.name, op, &inputs)
+        }
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.then_body.output_outlets()?.len())
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::attr_graph;
+    use crate::ops::test_util::*;
+    use tract_hir::internal::*;
+
+    fn if_graph() -> crate::pb::GraphProto {
+        let f32_dt = f32::datum_type();
+        let then_branch = graph(
+            vec![node("Identity", &["x"], &["then_y"], vec![])],
+            &[],
+            &[],
+            &[("then_y", f32_dt)],
+        );
+        let zeros = tensor1(&[0f32, 0., 0.]);
+        let else_branch = graph(
+            vec![node("Identity", &["zeros"], &["else_y"], vec![])],
+            &[],
+            &[("zeros", zeros)],
+            &[("else_y", f32_dt)],
+        );
+        let if_ = node(
+            "If",
+            &["cond"],
+            &["y"],
+            vec![attr_graph("then_branch", then_branch), attr_graph("else_branch", else_branch)],
+        );
+        let cond = tensor0(true);
+        let x = tensor1(&[1f32, 2.]);
+        graph(vec![if_], &[("cond", &cond), ("x", &x)], &[], &[("y", f32_dt)])
+    }
+
+    #[test]
+    fn if_with_different_branch_shapes() {
+        for &(cond, ref expected) in
+            &[(true, tensor1(&[1f32, 2.])), (false, tensor1(&[0f32, 0., 0.]))]
+        {
+            let inputs = tvec!(tensor0(cond), tensor1(&[1f32, 2.]));
+            let outputs = run_graph(11, if_graph(), inputs).unwrap();
+            assert_eq!(&*outputs[0], expected);
+        }
+    }
 }
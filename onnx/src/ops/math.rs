@@ -4,6 +4,7 @@ use crate::pb::*;
 use tract_hir::internal::*;
 use tract_hir::ops;
 use tract_hir::ops::binary::Nary;
+use tract_hir::tract_core::ser::{Attrs, Registry};
 
 mod clip;
 mod gemm;
@@ -61,6 +62,42 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("MatMulInteger", mat_mul_integer::mat_mul_integer);
     reg.insert("QLinearMatMul", mat_mul_integer::q_linear_mat_mul);
     reg.insert("Gemm", gemm::gemm);
+    reg.insert("Einsum", einsum);
+}
+
+pub fn register_ser_ops(reg: &mut Registry) {
+    reg.register_element_wise::<Erf>(
+        "onnx.Erf",
+        |_| Ok(Attrs::default()),
+        |_| Ok(Box::new(Erf {})),
+    );
+    reg.register_element_wise::<IsNan>(
+        "onnx.IsNan",
+        |_| Ok(Attrs::default()),
+        |_| Ok(Box::new(IsNan {})),
+    );
+    reg.register_element_wise::<IsInf>(
+        "onnx.IsInf",
+        |op| {
+            Ok(Attrs::default()
+                .with("detect_positive", op.detect_positive)
+                .with("detect_negative", op.detect_negative))
+        },
+        |attrs| {
+            Ok(Box::new(IsInf {
+                detect_positive: attrs.get("detect_positive")?.as_bool()?,
+                detect_negative: attrs.get("detect_negative")?.as_bool()?,
+            }))
+        },
+    );
+}
+
+pub fn einsum(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let equation = node.get_attr::<&str>("equation")?;
+    Ok((expand(ops::einsum::Einsum::new(equation.to_string())), vec![]))
 }
 
 element_wise!(erf, Erf,
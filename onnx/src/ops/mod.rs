@@ -2,6 +2,7 @@ use crate::model::{OnnxOpRegister, ParsingContext};
 use crate::pb::*;
 use tract_hir::internal::*;
 use tract_hir::ops;
+use tract_hir::tract_core::ser::{Attrs, Registry};
 
 macro_rules! op_onnx {
     () => {
@@ -14,11 +15,16 @@ macro_rules! op_onnx {
 mod array;
 mod cast;
 mod category_mapper;
+mod contrib;
 mod logic;
 mod math;
 mod nn;
 mod quant;
 pub mod rec;
+mod resize;
+
+#[cfg(test)]
+pub(crate) mod test_util;
 
 pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("Cast", cast::cast);
@@ -26,17 +32,48 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("Identity", |_, _| Ok((Box::new(ops::identity::Identity::default()), vec![])));
     array::register_all_ops(reg);
     category_mapper::register_all_ops(reg);
+    contrib::register_all_ops(reg);
     logic::register_all_ops(reg);
     math::register_all_ops(reg);
     nn::register_all_ops(reg);
     quant::register_all_ops(reg);
     rec::register_all_ops(reg);
+    resize::register_all_ops(reg);
+}
+
+/// Register the TypedOps of this crate in a serialization registry.
+pub fn register_ser_ops(reg: &mut Registry) {
+    reg.register_element_wise::<cast::Cast>(
+        "onnx.Cast",
+        |op| Ok(Attrs::default().with("to", op.to)),
+        |attrs| Ok(Box::new(cast::Cast::new(attrs.get("to")?.as_datum_type()?))),
+    );
+    math::register_ser_ops(reg);
+    nn::register_ser_ops(reg);
 }
 
 fn konst(
-    _ctx: &ParsingContext,
+    ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    let v = node.get_attr::<Tensor>("value")?;
+    // Constant-12 adds value_* attributes for scalars and lists
+    let value = node.attribute.iter().find(|a| a.name == "value").and_then(|a| a.t.as_ref());
+    let v = if let Some(t) = value {
+        ctx.load_tensor(t)?
+    } else if let Some(v) = node.get_attr_opt::<f32>("value_float")? {
+        tensor0(v)
+    } else if let Some(v) = node.get_attr_opt_vec::<f32>("value_floats")? {
+        tensor1(&v)
+    } else if let Some(v) = node.get_attr_opt::<i64>("value_int")? {
+        tensor0(v)
+    } else if let Some(v) = node.get_attr_opt_vec::<i64>("value_ints")? {
+        tensor1(&v)
+    } else if let Some(v) = node.get_attr_opt::<String>("value_string")? {
+        tensor0(v)
+    } else if let Some(v) = node.get_attr_opt_vec::<String>("value_strings")? {
+        tensor1(&v)
+    } else {
+        bail!("Constant: no value attribute (sparse_value is not supported)")
+    };
     Ok((Box::new(tract_hir::ops::konst::Const(v.into())), vec![]))
 }
@@ -11,7 +11,7 @@ pub fn dropout(
 
 #[derive(Debug, Clone, new, Default, Hash)]
 pub struct Dropout {
-    output_mask: bool,
+    pub output_mask: bool,
 }
 
 tract_linalg::impl_dyn_hash!(Dropout);
@@ -28,12 +28,13 @@ impl Op for Dropout {
 impl StatelessOp for Dropout {
     /// Evaluates the operation given the input tensors.
     fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        // Dropout-12 ratio and training_mode inputs are ignored: inference only
+        let input = inputs.swap_remove(0);
         if self.output_mask {
-            let input = args_1!(inputs);
             let mask = tract_ndarray::ArrayD::from_elem(input.shape(), true);
             Ok(tvec!(input, mask.into_arc_tensor()))
         } else {
-            Ok(inputs)
+            Ok(tvec!(input))
         }
     }
 }
@@ -45,7 +46,9 @@ impl InferenceRulesOp for Dropout {
         inputs: &'p [TensorProxy],
         outputs: &'p [TensorProxy],
     ) -> InferenceResult {
-        check_input_arity(&inputs, 1)?;
+        if inputs.len() < 1 || inputs.len() > 3 {
+            bail!("Dropout expects 1 to 3 inputs, got {}", inputs.len())
+        }
         check_output_arity(&outputs, 1 + self.output_mask as usize)?;
         s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
         s.equals(&inputs[0].shape, &outputs[0].shape)?;
@@ -0,0 +1,167 @@
+use crate::model::ParsingContext;
+use crate::pb::NodeProto;
+use tract_hir::internal::*;
+use tract_hir::ops::math;
+use tract_hir::ops::nn::wire_mean_var_norm;
+
+pub fn group_normalization(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let groups = node.get_attr("num_groups")?;
+    let epsilon = node.get_attr_opt("epsilon")?.unwrap_or(1e-5);
+    Ok((expand(GroupNorm::new(groups, epsilon)), vec![]))
+}
+
+/// Split the channels in `groups` groups, normalize each group of each
+/// instance, then scale and shift. Scale and bias hold one value per group
+/// (operator set 18) or one per channel (operator set 21).
+#[derive(Debug, Clone, new, Default, Educe)]
+#[educe(Hash)]
+pub struct GroupNorm {
+    groups: usize,
+    #[educe(Hash(method = "hash_f32"))]
+    epsilon: f32,
+}
+
+tract_linalg::impl_dyn_hash!(GroupNorm);
+
+impl Expansion for GroupNorm {
+    fn name(&self) -> Cow<str> {
+        "GroupNorm".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 3)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].datum_type, &inputs[1].datum_type)?;
+        s.equals(&inputs[0].datum_type, &inputs[2].datum_type)?;
+        s.equals(&inputs[1].shape, &inputs[2].shape)?;
+        s.equals(&inputs[1].rank, 1)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let fact = model.outlet_fact(inputs[0])?.clone();
+        let rank = fact.rank();
+        if rank < 2 {
+            bail!("GroupNormalization expects an input of rank 2 or more, got {:?}", fact)
+        }
+        let channels = fact.shape.dim(1).to_integer()? as usize;
+        if self.groups == 0 || channels % self.groups != 0 {
+            bail!(
+                "GroupNormalization: {} channels can not be split in {} groups",
+                channels,
+                self.groups
+            )
+        }
+        let per_group = match model.outlet_fact(inputs[1])?.shape.dim(0).to_integer()? as usize {
+            n if n == channels => false,
+            n if n == self.groups => true,
+            n => bail!(
+                "GroupNormalization: scale of length {} for {} channels in {} groups",
+                n,
+                channels,
+                self.groups
+            ),
+        };
+        let split = AxisOp::Reshape(
+            1,
+            tvec!(channels.to_dim()),
+            tvec!(self.groups.to_dim(), (channels / self.groups).to_dim()),
+        );
+        let grouped =
+            model.wire_node(format!("{}.split-groups", name), split.clone(), &inputs[0..1])?;
+        let axes: TVec<usize> = (2..rank + 1).collect();
+        let normed = wire_mean_var_norm(
+            &format!("{}.normed", name),
+            model,
+            grouped[0],
+            &axes,
+            self.epsilon,
+        )?;
+        let merge = split.recip();
+        if per_group {
+            let scale = wire_on_axis_1(&format!("{}.scale", name), model, inputs[1], rank + 1)?;
+            let bias = wire_on_axis_1(&format!("{}.bias", name), model, inputs[2], rank + 1)?;
+            let scaled = model.wire_node(
+                format!("{}.scaled", name),
+                math::mul::bin_typed(),
+                &[normed, scale],
+            )?;
+            let shifted = model.wire_node(
+                format!("{}.shifted", name),
+                math::add::bin_typed(),
+                &[scaled[0], bias],
+            )?;
+            model.wire_node(name, merge, &shifted)
+        } else {
+            let merged = model.wire_node(format!("{}.merge-groups", name), merge, &[normed])?;
+            let scale = wire_on_axis_1(&format!("{}.scale", name), model, inputs[1], rank)?;
+            let bias = wire_on_axis_1(&format!("{}.bias", name), model, inputs[2], rank)?;
+            let scaled = model.wire_node(
+                format!("{}.scaled", name),
+                math::mul::bin_typed(),
+                &[merged[0], scale],
+            )?;
+            model.wire_node(name, math::add::bin_typed(), &[scaled[0], bias])
+        }
+    }
+}
+
+/// Reshape a vector to broadcast along the axis 1 of a tensor of rank `rank`.
+fn wire_on_axis_1(
+    name: &str,
+    model: &mut TypedModel,
+    x: OutletId,
+    rank: usize,
+) -> TractResult<OutletId> {
+    let mut wire = model.wire_node(format!("{}.add-axis-n", name), AxisOp::Add(0), &[x])?;
+    for i in 2..rank {
+        wire = model.wire_node(format!("{}.add-axis-{}", name, i), AxisOp::Add(2), &wire)?;
+    }
+    Ok(wire[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::{attr_float, attr_int};
+    use crate::ops::test_util::*;
+    use tract_hir::internal::*;
+
+    fn run(opset: i64, scale: Tensor, bias: Tensor) -> Tensor {
+        let attrs = vec![attr_int("num_groups", 2), attr_float("epsilon", 0.0)];
+        let node = node("GroupNormalization", &["x", "scale", "bias"], &["y"], attrs);
+        // both groups normalize to [-1, 1, -1, 1]
+        let x = tensor3(&[[[-1f32, 1.], [-1., 1.], [2., 4.], [2., 4.]]]);
+        let consts = &[("scale", scale), ("bias", bias)];
+        let outputs = &[("y", f32::datum_type())];
+        run_node(opset, node, &[("x", x)], consts, outputs).unwrap().remove(0).into_tensor()
+    }
+
+    #[test]
+    fn group_norm_with_scale_per_channel_or_per_group() {
+        let found = run(21, tensor1(&[1f32, 2., 3., 4.]), tensor1(&[0f32, 0., 1., 1.]));
+        found
+            .close_enough(&tensor3(&[[[-1f32, 1.], [-2., 2.], [-2., 4.], [-3., 5.]]]), true)
+            .unwrap();
+        let found = run(18, tensor1(&[1f32, 2.]), tensor1(&[0f32, 1.]));
+        found
+            .close_enough(&tensor3(&[[[-1f32, 1.], [-1., 1.], [-1., 3.], [-1., 3.]]]), true)
+            .unwrap();
+    }
+}
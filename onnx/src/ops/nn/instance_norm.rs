@@ -50,42 +50,13 @@ impl Expansion for InstanceNorm {
         inputs: &[OutletId],
     ) -> TractResult<TVec<OutletId>> {
         let rank = model.outlet_fact(inputs[0])?.rank();
-        let axes: Vec<_> = (0..rank as i64).filter(|&axis| axis != 1).collect();
-        let mean = tract_hir::ops::nn::Reduce::new(
-            Some(axes.clone()),
-            true,
-            tract_hir::ops::nn::Reducer::Mean,
-        )
-        .wire(&format!("{}.mean", name), model, &inputs[0..1])?[0];
-        let diff = model.wire_node(
-            format!("{}.diff", name),
-            tract_hir::ops::math::sub::bin_typed(),
-            &[inputs[0], mean],
-        )?;
-        let sqr_diff =
-            model.wire_node(format!("{}.sqr", name), tract_hir::ops::math::square(), &diff)?;
-        let vari = tract_hir::ops::nn::Reduce::new(
-            Some(axes.clone()),
-            true,
-            tract_hir::ops::nn::Reducer::Mean,
-        )
-        .wire(&format!("{}.variance", name), model, &sqr_diff)?[0];
-        let vari_sane = model.wire_node(
-            format!("{}.epsilon", name),
-            tract_hir::ops::math::add::unary(
-                tensor0(self.epsilon).broadcast_into_rank(rank)?.into_arc_tensor(),
-            ),
-            &[vari],
-        )?;
-        let div = model.wire_node(
-            format!("{}.rsqrt", name),
-            tract_hir::ops::math::rsqrt(),
-            &vari_sane,
-        )?;
-        let divised = model.wire_node(
-            format!("{}.div", name),
-            tract_hir::ops::math::mul::bin_typed(),
-            &[diff[0], div[0]],
+        let axes: TVec<usize> = (2..rank).collect();
+        let normed = tract_hir::ops::nn::wire_mean_var_norm(
+            &format!("{}.normed", name),
+            model,
+            inputs[0],
+            &axes,
+            self.epsilon,
         )?;
         let mut scale =
             model.wire_node(format!("{}.add-scale-axis-n", name), AxisOp::Add(0), &inputs[1..2])?;
@@ -99,16 +70,13 @@ impl Expansion for InstanceNorm {
         let scaled = model.wire_node(
             format!("{}.scaled", name),
             tract_hir::ops::math::mul::bin_typed(),
-            &[divised[0], scale[0]],
+            &[normed, scale[0]],
         )?;
         let mut bias =
             model.wire_node(format!("{}.add-bias-axis-n", name), AxisOp::Add(0), &inputs[2..3])?;
         for i in 2..rank {
-            bias = model.wire_node(
-                format!("{}.add-bias-axis-{}", name, i),
-                AxisOp::Add(2),
-                &bias,
-            )?;
+            bias =
+                model.wire_node(format!("{}.add-bias-axis-{}", name, i), AxisOp::Add(2), &bias)?;
         }
         model.wire_node(name, tract_hir::ops::math::add::bin_typed(), &[scaled[0], bias[0]])
     }
@@ -0,0 +1,110 @@
+use crate::model::ParsingContext;
+use crate::pb::NodeProto;
+use tract_hir::internal::*;
+use tract_hir::ops::binary::wire_rank_broadcast;
+use tract_hir::ops::math;
+use tract_hir::ops::nn::wire_mean_var_norm;
+
+pub fn layer_normalization(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr_opt("axis")?.unwrap_or(-1);
+    let epsilon = node.get_attr_opt("epsilon")?.unwrap_or(1e-5);
+    if node.output.iter().skip(1).any(|o| !o.is_empty()) {
+        bail!("LayerNormalization: only the normalized output is supported (no Mean or InvStdDev)")
+    }
+    let bias = crate::model::optional_inputs(node).skip(2).next().unwrap().is_some();
+    Ok((expand(LayerNorm::new(axis, epsilon, bias)), vec![]))
+}
+
+/// Normalize over the axes from `axis` to the last one, then scale and
+/// optionally shift.
+#[derive(Debug, Clone, new, Default, Educe)]
+#[educe(Hash)]
+pub struct LayerNorm {
+    axis: isize,
+    #[educe(Hash(method = "hash_f32"))]
+    epsilon: f32,
+    bias: bool,
+}
+
+tract_linalg::impl_dyn_hash!(LayerNorm);
+
+impl Expansion for LayerNorm {
+    fn name(&self) -> Cow<str> {
+        "LayerNorm".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 2 + self.bias as usize)?;
+        check_output_arity(outputs, 1)?;
+        for input in inputs {
+            s.equals(&input.datum_type, &outputs[0].datum_type)?;
+        }
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let bias = if self.bias { Some(inputs[2]) } else { None };
+        wire_layer_norm(name, model, inputs[0], inputs[1], bias, self.axis, self.epsilon)
+    }
+}
+
+/// (x - mean) / sqrt(var + epsilon) * scale + bias, with mean and variance
+/// computed over the axes from `axis` to the last one.
+pub(crate) fn wire_layer_norm(
+    name: &str,
+    model: &mut TypedModel,
+    x: OutletId,
+    scale: OutletId,
+    bias: Option<OutletId>,
+    axis: isize,
+    epsilon: f32,
+) -> TractResult<TVec<OutletId>> {
+    let rank = model.outlet_fact(x)?.rank();
+    let axis = if axis < 0 { axis + rank as isize } else { axis } as usize;
+    let axes: TVec<usize> = (axis..rank).collect();
+    let normed = wire_mean_var_norm(&format!("{}.normed", name), model, x, &axes, epsilon)?;
+    let wires = wire_rank_broadcast(&format!("{}.scale", name), model, &[normed, scale])?;
+    let scaled = model.wire_node(
+        if bias.is_some() { format!("{}.scaled", name) } else { name.to_string() },
+        math::mul::bin_typed(),
+        &wires,
+    )?;
+    if let Some(bias) = bias {
+        let wires = wire_rank_broadcast(&format!("{}.bias", name), model, &[scaled[0], bias])?;
+        model.wire_node(name, math::add::bin_typed(), &wires)
+    } else {
+        Ok(scaled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ops::test_util::*;
+    use tract_hir::internal::*;
+
+    #[test]
+    fn layer_norm_in_default_domain() {
+        let node = node("LayerNormalization", &["x", "scale", "bias"], &["y"], vec![]);
+        let x = tensor2(&[[1f32, 3.], [2., 6.]]);
+        let consts = &[("scale", tensor1(&[1f32, 2.])), ("bias", tensor1(&[0f32, 1.]))];
+        let outputs = &[("y", f32::datum_type())];
+        let found = run_node(17, node, &[("x", x)], consts, outputs).unwrap();
+        found[0].close_enough(&tensor2(&[[-1f32, 3.], [-1., 3.]]), true).unwrap();
+    }
+}
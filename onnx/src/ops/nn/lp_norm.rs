@@ -0,0 +1,89 @@
+use crate::model::ParsingContext;
+use crate::pb::NodeProto;
+use tract_hir::internal::*;
+use tract_hir::ops::math;
+use tract_hir::ops::nn::{Reduce, Reducer};
+
+pub fn lp_normalization(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr_opt("axis")?.unwrap_or(-1);
+    let p = node.get_attr_opt("p")?.unwrap_or(2);
+    let reducer = node.check_value(
+        "p",
+        match p {
+            1 => Ok(Reducer::L1),
+            2 => Ok(Reducer::L2),
+            p => Err(p),
+        },
+    )?;
+    Ok((expand(LpNorm::new(axis, reducer)), vec![]))
+}
+
+/// Divide by the L1 or L2 norm computed along `axis`.
+#[derive(Debug, Clone, new, Hash)]
+pub struct LpNorm {
+    axis: i64,
+    reducer: Reducer,
+}
+
+tract_linalg::impl_dyn_hash!(LpNorm);
+
+impl Expansion for LpNorm {
+    fn name(&self) -> Cow<str> {
+        "LpNorm".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let norm = Reduce::new(Some(vec![self.axis]), true, self.reducer).wire(
+            &format!("{}.norm", name),
+            model,
+            inputs,
+        )?;
+        model.wire_node(name, math::div::bin_typed(), &[inputs[0], norm[0]])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::attr_int;
+    use crate::ops::test_util::*;
+    use crate::pb::AttributeProto;
+    use tract_hir::internal::*;
+
+    fn run(attrs: Vec<AttributeProto>) -> Tensor {
+        let node = node("LpNormalization", &["x"], &["y"], attrs);
+        let x = tensor2(&[[3f32, 4.], [6., 8.]]);
+        let outputs = &[("y", f32::datum_type())];
+        run_node(1, node, &[("x", x)], &[], outputs).unwrap().remove(0).into_tensor()
+    }
+
+    #[test]
+    fn lp_norm() {
+        run(vec![]).close_enough(&tensor2(&[[0.6f32, 0.8], [0.6, 0.8]]), true).unwrap();
+        run(vec![attr_int("axis", 0), attr_int("p", 1)])
+            .close_enough(&tensor2(&[[1f32 / 3., 1. / 3.], [2. / 3., 2. / 3.]]), true)
+            .unwrap();
+    }
+}
@@ -0,0 +1,87 @@
+use crate::model::ParsingContext;
+use crate::pb::NodeProto;
+use tract_hir::internal::*;
+
+pub fn mean_variance_normalization(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axes = node.get_attr_opt_tvec("axes")?.unwrap_or(tvec!(0, 2, 3));
+    Ok((expand(MeanVarianceNorm::new(axes)), vec![]))
+}
+
+/// Normalize to zero mean and unit variance over `axes`.
+#[derive(Debug, Clone, new, Default, Hash)]
+pub struct MeanVarianceNorm {
+    axes: TVec<isize>,
+}
+
+tract_linalg::impl_dyn_hash!(MeanVarianceNorm);
+
+impl Expansion for MeanVarianceNorm {
+    fn name(&self) -> Cow<str> {
+        "MeanVarianceNorm".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let rank = model.outlet_fact(inputs[0])?.rank() as isize;
+        let axes = self
+            .axes
+            .iter()
+            .map(|&axis| {
+                let axis = if axis < 0 { axis + rank } else { axis };
+                if axis < 0 || axis >= rank {
+                    bail!("MeanVarianceNormalization: invalid axis {} for rank {}", axis, rank)
+                }
+                Ok(axis as usize)
+            })
+            .collect::<TractResult<TVec<usize>>>()?;
+        let normed = tract_hir::ops::nn::wire_mean_var_norm(name, model, inputs[0], &axes, 1e-9)?;
+        Ok(tvec!(normed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::attr_ints;
+    use crate::ops::test_util::*;
+    use crate::pb::AttributeProto;
+    use tract_hir::internal::*;
+
+    fn run(attrs: Vec<AttributeProto>, x: Tensor) -> Tensor {
+        let node = node("MeanVarianceNormalization", &["x"], &["y"], attrs);
+        let outputs = &[("y", f32::datum_type())];
+        run_node(13, node, &[("x", x)], &[], outputs).unwrap().remove(0).into_tensor()
+    }
+
+    #[test]
+    fn mean_variance_norm() {
+        // the default axes normalize each channel
+        let x = tensor4(&[[[[1f32, 3.]], [[2., 6.]]]]);
+        run(vec![], x).close_enough(&tensor4(&[[[[-1f32, 1.]], [[-1., 1.]]]]), true).unwrap();
+        let x = tensor2(&[[1f32, 3.], [2., 6.]]);
+        run(vec![attr_ints("axes", vec![0])], x)
+            .close_enough(&tensor2(&[[-1f32, -1.], [1., 1.]]), true)
+            .unwrap();
+    }
+}
@@ -5,22 +5,21 @@ use tract_hir::ops::{cnn, nn};
 use crate::model::{OnnxOpRegister, ParsingContext};
 use crate::pb::NodeProto;
 use crate::pb_helpers::OptionExt;
+use tract_hir::tract_core::ser::{Attrs, Registry};
 
 use tract_num_traits::AsPrimitive;
 
 mod batch_norm;
 mod dropout;
+mod group_norm;
 mod instance_norm;
+pub(crate) mod layer_norm;
+mod lp_norm;
 mod lrn;
+mod mean_variance_norm;
+mod reduce;
 
-fn reduce(
-    node: &NodeProto,
-    reducer: nn::Reducer,
-) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    let axes = node.get_attr_opt_vec("axes")?;
-    let keep_dims = node.get_attr_opt("keepdims")?.unwrap_or(1i64) == 1;
-    Ok((expand(ops::nn::Reduce::new(axes, keep_dims, reducer)), vec![]))
-}
+use reduce::reduce;
 
 pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("ArgMax", arg_max_min);
@@ -29,31 +28,36 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("BatchNormalization", batch_normalization);
     reg.insert("Conv", conv);
     reg.insert("ConvInteger", conv_integer);
+    reg.insert("ConvTranspose", conv_transpose);
     reg.insert("Dropout", dropout::dropout);
     reg.insert("Elu", elu);
     reg.insert("GlobalAveragePool", |_, _| Ok((expand(ops::nn::GlobalAvgPool), vec![])));
     reg.insert("GlobalLpPool", global_lp_pool);
     reg.insert("GlobalMaxPool", |_, _| Ok((expand(ops::nn::GlobalMaxPool), vec![])));
+    reg.insert("GroupNormalization", group_norm::group_normalization);
     reg.insert("Hardmax", layer_hard_max);
     reg.insert("HardSigmoid", hard_sigmoid);
     reg.insert("InstanceNormalization", instance_norm::instance_normalization);
+    reg.insert("LayerNormalization", layer_norm::layer_normalization);
     reg.insert("LeakyRelu", leaky_relu);
     reg.insert("LogSoftmax", layer_log_soft_max);
+    reg.insert("LpNormalization", lp_norm::lp_normalization);
     reg.insert("LRN", lrn);
     reg.insert("MaxPool", max_pool);
+    reg.insert("MeanVarianceNormalization", mean_variance_norm::mean_variance_normalization);
     reg.insert("ParametricSoftplus", parametric_softplus);
     reg.insert("QLinearConv", conv_qlinear);
     reg.insert("PRelu", |_, _| Ok((expand(Prelu), vec![])));
-    reg.insert("ReduceL1", |_, node| reduce(node, nn::Reducer::L1));
-    reg.insert("ReduceL2", |_, node| reduce(node, nn::Reducer::L2));
-    reg.insert("ReduceLogSum", |_, node| reduce(node, nn::Reducer::LogSum));
-    reg.insert("ReduceLogSumExp", |_, node| reduce(node, nn::Reducer::LogSumExp));
-    reg.insert("ReduceMax", |_, node| reduce(node, nn::Reducer::Max));
-    reg.insert("ReduceMean", |_, node| reduce(node, nn::Reducer::Mean));
-    reg.insert("ReduceMin", |_, node| reduce(node, nn::Reducer::Min));
-    reg.insert("ReduceProd", |_, node| reduce(node, nn::Reducer::Prod));
-    reg.insert("ReduceSum", |_, node| reduce(node, nn::Reducer::Sum));
-    reg.insert("ReduceSumSquare", |_, node| reduce(node, nn::Reducer::SumSquare));
+    reg.insert("ReduceL1", |ctx, node| reduce(ctx, node, nn::Reducer::L1, 18));
+    reg.insert("ReduceL2", |ctx, node| reduce(ctx, node, nn::Reducer::L2, 18));
+    reg.insert("ReduceLogSum", |ctx, node| reduce(ctx, node, nn::Reducer::LogSum, 18));
+    reg.insert("ReduceLogSumExp", |ctx, node| reduce(ctx, node, nn::Reducer::LogSumExp, 18));
+    reg.insert("ReduceMax", |ctx, node| reduce(ctx, node, nn::Reducer::Max, 18));
+    reg.insert("ReduceMean", |ctx, node| reduce(ctx, node, nn::Reducer::Mean, 18));
+    reg.insert("ReduceMin", |ctx, node| reduce(ctx, node, nn::Reducer::Min, 18));
+    reg.insert("ReduceProd", |ctx, node| reduce(ctx, node, nn::Reducer::Prod, 18));
+    reg.insert("ReduceSum", |ctx, node| reduce(ctx, node, nn::Reducer::Sum, 13));
+    reg.insert("ReduceSumSquare", |ctx, node| reduce(ctx, node, nn::Reducer::SumSquare, 18));
     reg.insert("Relu", |_, _| Ok((expand(ops::activations::Clip::new(Some(0.0), None)), vec![])));
     reg.insert("ScaledTanh", scaled_tanh);
     reg.insert("Shrink", shrink);
@@ -65,6 +69,42 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("Softsign", |_, _| Ok((expand(ops::activations::Softsign), vec![])));
 }
 
+pub fn register_ser_ops(reg: &mut Registry) {
+    reg.register_op::<lrn::Lrn>(
+        "onnx.Lrn",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("alpha", op.alpha)
+                .with("beta", op.beta)
+                .with("bias", op.bias)
+                .with("size", op.size))
+        },
+        |_, attrs| {
+            Ok(Box::new(lrn::Lrn::new(
+                attrs.get("alpha")?.as_f32()?,
+                attrs.get("beta")?.as_f32()?,
+                attrs.get("bias")?.as_f32()?,
+                attrs.get("size")?.as_usize()?,
+            )))
+        },
+    );
+    reg.register_op::<dropout::Dropout>(
+        "onnx.Dropout",
+        |_, op| Ok(Attrs::default().with("output_mask", op.output_mask)),
+        |_, attrs| Ok(Box::new(dropout::Dropout::new(attrs.get("output_mask")?.as_bool()?))),
+    );
+    reg.register_element_wise::<Shrink>(
+        "onnx.Shrink",
+        |op| Ok(Attrs::default().with("bias", op.bias).with("lambd", op.lambd)),
+        |attrs| {
+            Ok(Box::new(Shrink {
+                bias: attrs.get("bias")?.as_f32()?,
+                lambd: attrs.get("lambd")?.as_f32()?,
+            }))
+        },
+    );
+}
+
 fn pad(node: &NodeProto) -> TractResult<cnn::PaddingSpec> {
     let ceil_mode = node.get_attr_opt::<isize>("ceil_mode")?.unwrap_or(0) == 1;
     let default = match node.get_attr_opt_vec::<isize>("kernel_shape")? {
@@ -125,6 +165,9 @@ pub fn batch_normalization(
     if spatial != 0 {
         bail!("BatchNormalization: attribute 'spatial' is not supported (deprecated by ONNX operator set 9)")
     }
+    if node.get_attr_opt("training_mode")?.unwrap_or(0i64) != 0 {
+        bail!("BatchNormalization: attribute 'training_mode' is not supported (operator set 14)")
+    }
     Ok((expand(batch_norm::BatchNorm::new(nn::DataFormat::NCHW, epsilon, spatial != 0)), vec![]))
 }
 
@@ -190,6 +233,45 @@ pub fn conv_qlinear(
     Ok((expand(op), vec![]))
 }
 
+pub fn conv_transpose(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let mut op = cnn::Deconv::default().padding(pad(node)?);
+    if let Some(group) = node.get_attr_opt("group")? {
+        op = op.group(group);
+    }
+    if let Some(v) = dilations(node)? {
+        op = op.dilations(v);
+    }
+    if let Some(v) = strides(node)? {
+        op = op.strides(v);
+    }
+    if let Some(v) = node.get_attr_opt_tvec("output_padding")? {
+        op = op.adjustments(v);
+    }
+    if let Some(mut output_shape) = node.get_attr_opt_tvec::<usize>("output_shape")? {
+        // output_shape may be given with or without the N and C axes
+        if let Some(kernel_shape) = node.get_attr_opt_tvec::<usize>("kernel_shape")? {
+            if output_shape.len() > kernel_shape.len() {
+                output_shape = output_shape[output_shape.len() - kernel_shape.len()..].into();
+            }
+        }
+        // pads are then computed from the output shape, with the extra
+        // padding at the beginning unless auto_pad is SAME_UPPER
+        let padding = if node.get_attr_opt("auto_pad")? == Some("SAME_UPPER") {
+            cnn::PaddingSpec::SameUpper
+        } else {
+            cnn::PaddingSpec::SameLower
+        };
+        op = op.padding(padding).output_shape(output_shape);
+    }
+    if node.input.len() == 3 {
+        op = op.bias_input(2);
+    }
+    Ok((expand(op), vec![]))
+}
+
 pub fn average_pool(
     _ctx: &ParsingContext,
     node: &NodeProto,
@@ -232,28 +314,38 @@ pub fn hard_sigmoid(
     Ok((expand(ops::activations::HardSigmoid(alpha, beta)), vec![]))
 }
 
+/// Before opset 13, Softmax, LogSoftmax and Hardmax coerce their input to 2D
+/// around `axis` (default 1). From 13 on, they work on `axis` only (default -1).
+fn layer_max_axis(ctx: &ParsingContext, node: &NodeProto) -> TractResult<(isize, bool)> {
+    if ctx.onnx_operator_set_version < 13 {
+        Ok((node.get_attr_opt("axis")?.unwrap_or(1), true))
+    } else {
+        Ok((node.get_attr_opt("axis")?.unwrap_or(-1), false))
+    }
+}
+
 pub fn layer_hard_max(
-    _ctx: &ParsingContext,
+    ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    let axis = node.get_attr_opt("axis")?.unwrap_or(1);
-    Ok((Box::new(ops::nn::LayerHardmax::new(axis)), vec![]))
+    let (axis, coerce_to_2d) = layer_max_axis(ctx, node)?;
+    Ok((Box::new(ops::nn::LayerHardmax::new(axis, coerce_to_2d)), vec![]))
 }
 
 pub fn layer_log_soft_max(
-    _ctx: &ParsingContext,
+    ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    let axis = node.get_attr_opt("axis")?.unwrap_or(1);
-    Ok((expand(ops::nn::LayerLogSoftmax::new(axis)), vec![]))
+    let (axis, coerce_to_2d) = layer_max_axis(ctx, node)?;
+    Ok((expand(ops::nn::LayerLogSoftmax::new(axis, coerce_to_2d)), vec![]))
 }
 
 pub fn layer_soft_max(
-    _ctx: &ParsingContext,
+    ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    let axis = node.get_attr_opt("axis")?.unwrap_or(1);
-    Ok((expand(ops::nn::LayerSoftmax::new(axis)), vec![]))
+    let (axis, coerce_to_2d) = layer_max_axis(ctx, node)?;
+    Ok((expand(ops::nn::LayerSoftmax::new(axis, coerce_to_2d)), vec![]))
 }
 
 pub fn leaky_relu(
@@ -418,3 +510,34 @@ pub fn thresholded_relu(
     let alpha = node.get_attr_opt("alpha")?.unwrap_or(1.);
     Ok((expand(ops::activations::ThresholdRelu(alpha)), vec![]))
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::ops::test_util::*;
+    use tract_hir::internal::*;
+
+    fn run(opset: i64, op: &str, x: Tensor) -> Tensor {
+        let node = node(op, &["x"], &["y"], vec![]);
+        let outputs = &[("y", f32::datum_type())];
+        run_node(opset, node, &[("x", x)], &[], outputs).unwrap().remove(0).into_tensor()
+    }
+
+    #[test]
+    fn softmax13_works_on_last_axis() {
+        let x = tensor3(&[[[0f32, 3f32.ln()], [0., 3f32.ln()]]]);
+        run(13, "Softmax", x.clone())
+            .close_enough(&tensor3(&[[[0.25f32, 0.75], [0.25, 0.75]]]), true)
+            .unwrap();
+        // before opset 13, the input is coerced to 2D around axis 1
+        run(12, "Softmax", x)
+            .close_enough(&tensor3(&[[[0.125f32, 0.375], [0.125, 0.375]]]), true)
+            .unwrap();
+    }
+
+    #[test]
+    fn hardmax13_works_on_last_axis() {
+        let x = tensor3(&[[[1f32, 3.], [4., 2.]]]);
+        assert_eq!(run(13, "Hardmax", x.clone()), tensor3(&[[[0f32, 1.], [1., 0.]]]));
+        assert_eq!(run(12, "Hardmax", x), tensor3(&[[[0f32, 0.], [1., 0.]]]));
+    }
+}
@@ -0,0 +1,161 @@
+use crate::model::ParsingContext;
+use crate::pb::*;
+use tract_hir::internal::*;
+use tract_hir::ops::nn;
+
+pub fn reduce(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+    reducer: nn::Reducer,
+    axes_as_input_since: i64,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let keep_dims = node.get_attr_opt("keepdims")?.unwrap_or(1i64) == 1;
+    if ctx.onnx_operator_set_version < axes_as_input_since {
+        let axes = node.get_attr_opt_vec("axes")?;
+        Ok((expand(nn::Reduce::new(axes, keep_dims, reducer)), vec![]))
+    } else {
+        let noop_with_empty_axes = node.get_attr_opt("noop_with_empty_axes")?.unwrap_or(0i64) == 1;
+        Ok((expand(Reduce13::new(keep_dims, noop_with_empty_axes, reducer)), vec![]))
+    }
+}
+
+/// ReduceSum-13 and the other reductions from opset 18 on take the optional
+/// axes as an input instead of an attribute.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Reduce13 {
+    keep_dims: bool,
+    noop_with_empty_axes: bool,
+    reducer: nn::Reducer,
+}
+
+tract_linalg::impl_dyn_hash!(Reduce13);
+
+impl Reduce13 {
+    /// None when the operator is a no-op.
+    fn reduce(&self, axes: Option<&Tensor>) -> TractResult<Option<nn::Reduce>> {
+        let axes = if let Some(axes) = axes.filter(|axes| axes.len() > 0) {
+            Some(axes.cast_to::<i64>()?.as_slice::<i64>()?.to_vec())
+        } else {
+            None
+        };
+        if axes.is_none() && self.noop_with_empty_axes {
+            Ok(None)
+        } else {
+            Ok(Some(nn::Reduce::new(axes, self.keep_dims, self.reducer)))
+        }
+    }
+}
+
+impl Expansion for Reduce13 {
+    fn name(&self) -> Cow<str> {
+        format!("Reduce13<{:?}>", self.reducer).into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        let set_shape = move |s: &mut Solver<'r>,
+                              shape: TVec<TDim>,
+                              axes: Option<&Tensor>|
+              -> InferenceResult {
+            if let Some(op) = self.reduce(axes)? {
+                s.equals(&outputs[0].shape, op.output_shape(&shape))
+            } else {
+                s.equals(&outputs[0].shape, shape)
+            }
+        };
+        if inputs.len() == 1 {
+            s.given(&inputs[0].shape, move |s, shape| set_shape(s, shape, None))
+        } else {
+            check_input_arity(&inputs, 2)?;
+            s.given_2(&inputs[0].shape, &inputs[1].value, move |s, shape, axes| {
+                set_shape(s, shape, Some(&axes))
+            })
+        }
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let axes = if inputs.len() == 2 {
+            if let Some(axes) = model.outlet_fact(inputs[1])?.konst.clone() {
+                Some(axes)
+            } else {
+                bail!("axes input is expected to be a constant")
+            }
+        } else {
+            None
+        };
+        if let Some(op) = self.reduce(axes.as_deref())? {
+            op.wire(name, model, &inputs[0..1])
+        } else {
+            Ok(tvec!(inputs[0]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::attr_int;
+    use crate::ops::test_util::*;
+    use crate::pb::AttributeProto;
+    use tract_hir::internal::*;
+
+    fn run(
+        opset: i64,
+        op: &str,
+        attrs: Vec<AttributeProto>,
+        x: Tensor,
+        axes: Option<Tensor>,
+    ) -> Tensor {
+        let (inputs, consts) = if let Some(axes) = axes {
+            (vec!["x", "axes"], vec![("axes", axes)])
+        } else {
+            (vec!["x"], vec![])
+        };
+        let node = node(op, &inputs, &["y"], attrs);
+        let outputs = &[("y", x.datum_type())];
+        run_node(opset, node, &[("x", x)], &consts, outputs).unwrap().remove(0).into_tensor()
+    }
+
+    #[test]
+    fn reduce_sum13_with_axes_input() {
+        let x = tensor2(&[[1f32, 2.], [3., 4.]]);
+        let attrs = vec![attr_int("keepdims", 0)];
+        assert_eq!(
+            run(13, "ReduceSum", attrs, x.clone(), Some(tensor1(&[1i64]))),
+            tensor1(&[3f32, 7.])
+        );
+        assert_eq!(run(13, "ReduceSum", vec![], x, None), tensor2(&[[10f32]]));
+    }
+
+    #[test]
+    fn reduce_sum13_noop_with_empty_axes() {
+        let x = tensor2(&[[1f32, 2.], [3., 4.]]);
+        let attrs = vec![attr_int("noop_with_empty_axes", 1)];
+        assert_eq!(run(13, "ReduceSum", attrs.clone(), x.clone(), None), x);
+        assert_eq!(run(13, "ReduceSum", attrs, x.clone(), Some(tensor1::<i64>(&[]))), x);
+    }
+
+    #[test]
+    fn reduce_axes_as_input_from_opset_18() {
+        let x = tensor2(&[[1f32, 2.], [3., 4.]]);
+        assert_eq!(
+            run(18, "ReduceMax", vec![], x.clone(), Some(tensor1(&[-1i64]))),
+            tensor2(&[[2f32], [4.]])
+        );
+        // before opset 18, axes are an attribute
+        let attrs = vec![crate::export::attr_ints("axes", vec![0])];
+        assert_eq!(run(17, "ReduceMax", attrs, x, None), tensor2(&[[3f32, 4.]]));
+    }
+}
@@ -7,6 +7,9 @@ pub fn gru(
     _ctx: &ParsingContext,
     pb: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    if pb.get_attr_opt("layout")?.unwrap_or(0i64) != 0 {
+        bail!("GRU: attribute 'layout' is not supported (batch-first layout, operator set 14)")
+    }
     let mut gru = GRU::default();
 
     let mut options = crate::model::optional_inputs(pb).skip(3);
@@ -0,0 +1,414 @@
+use crate::model::{optional_inputs, ParseResult, ParsingContext};
+use crate::pb::*;
+use tract_hir::infer::*;
+use tract_hir::internal::*;
+use tract_hir::ops;
+use tract_hir::tract_core::ops::control_flow;
+
+pub fn loop_(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let graph: &GraphProto = node.get_attr("body")?;
+    let ParseResult { model, unresolved_inputs, .. } = ctx.parse_graph(graph)?;
+    let mut options = optional_inputs(node);
+    let trip_count_input = options.next().unwrap();
+    let cond_input = options.next().unwrap();
+    let carried = node.input.len().saturating_sub(2);
+    Ok((Box::new(Loop::new(model, trip_count_input, cond_input, carried)), unresolved_inputs))
+}
+
+/// ONNX Loop.
+///
+/// Loop-carried values must keep the same type and shape across iterations.
+///
+/// When the trip count is known and the condition can never become false,
+/// the loop translates to a Scan over the iteration numbers. Otherwise, it
+/// translates to a core Loop.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Loop {
+    body: InferenceModel,
+    trip_count_input: Option<usize>,
+    cond_input: Option<usize>,
+    carried: usize,
+}
+
+tract_linalg::impl_dyn_hash!(Loop);
+
+impl Loop {
+    /// Op input slot of the first loop-carried value.
+    fn first_carried_slot(&self) -> usize {
+        self.trip_count_input.is_some() as usize + self.cond_input.is_some() as usize
+    }
+
+    fn scan_outputs(&self) -> TractResult<usize> {
+        Ok(self.body.output_outlets()?.len() - 1 - self.carried)
+    }
+
+    /// Static trip count, if the condition can not stop the loop earlier.
+    fn static_trip_count(
+        &self,
+        trip_count: Option<&Tensor>,
+        cond: Option<&Tensor>,
+        body_cond_is_true: bool,
+    ) -> TractResult<Option<usize>> {
+        if let Some(trip_count) = trip_count {
+            let cond = if let Some(cond) = cond { cond.cast_to_scalar::<bool>()? } else { true };
+            if cond && body_cond_is_true {
+                return Ok(Some(trip_count.cast_to_scalar::<i64>()?.max(0) as usize));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Check if the condition computed by the body is always true, or just
+    /// forwards the incoming one.
+    fn inference_body_cond_is_true(&self) -> TractResult<bool> {
+        let cond_in = self.body.input_outlets()?[1];
+        let mut cond_out = self.body.output_outlets()?[0];
+        while self.body.node(cond_out.node).op_is::<ops::identity::Identity>() {
+            cond_out = self.body.node(cond_out.node).inputs[0];
+        }
+        Ok(cond_out == cond_in
+            || self
+                .body
+                .outlet_fact(cond_out)?
+                .value
+                .concretize()
+                .map(|v| v.cast_to_scalar::<bool>())
+                .transpose()?
+                == Some(true))
+    }
+
+    fn typed_body_cond_is_true(body: &TypedModel) -> TractResult<bool> {
+        let cond_out = body.output_outlets()?[0];
+        Ok(cond_out == body.input_outlets()?[1]
+            || body
+                .outlet_fact(cond_out)?
+                .konst
+                .as_ref()
+                .map(|v| v.cast_to_scalar::<bool>())
+                .transpose()?
+                == Some(true))
+    }
+
+    /// Core Loop for evaluation, the body input facts being set from the
+    /// actual core Loop inputs.
+    fn to_core_loop(&self, inputs: &[Arc<Tensor>]) -> TractResult<control_flow::Loop> {
+        let mut body = self.body.clone();
+        body.set_input_fact(0, InferenceFact::dt_shape(i64::datum_type(), shapefactoid!()))?;
+        body.set_input_fact(1, InferenceFact::dt_shape(bool::datum_type(), shapefactoid!()))?;
+        for (ix, input) in inputs.iter().enumerate().skip(2) {
+            body.set_input_fact(ix, InferenceFact::dt_shape_from_tensor(input))?;
+        }
+        Ok(control_flow::Loop::new(body.into_typed()?, self.carried, TDim::sym("iters")))
+    }
+
+    /// Core Loop inputs, filling the missing trip count and condition with
+    /// constants built by `konst`.
+    fn core_loop_inputs<T: Clone>(
+        &self,
+        inputs: &[T],
+        mut konst: impl FnMut(&str, Tensor) -> TractResult<T>,
+    ) -> TractResult<TVec<T>> {
+        let mut loop_inputs = tvec!();
+        loop_inputs.push(match self.trip_count_input {
+            Some(slot) => inputs[slot].clone(),
+            None => konst("trip_count", tensor0(i64::max_value()))?,
+        });
+        loop_inputs.push(match self.cond_input {
+            Some(slot) => inputs[slot].clone(),
+            None => konst("cond", tensor0(true))?,
+        });
+        loop_inputs.extend(inputs[self.first_carried_slot()..].iter().cloned());
+        Ok(loop_inputs)
+    }
+
+    /// Wire the loop as a Scan over a constant tensor of the iteration numbers.
+    fn wire_as_scan(
+        &self,
+        name: &str,
+        body: &TypedModel,
+        trip_count: usize,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let carried = self.carried;
+        let closures = body.input_outlets()?.len() - 2 - carried;
+        let mut scan_body = TypedModel::default();
+        let mut states = tvec!();
+        for ix in 0..carried {
+            let fact = body.input_fact(2 + ix)?;
+            let fact = TypedFact::dt_shape(fact.datum_type, fact.shape.clone())?;
+            states.push(scan_body.add_source(format!("{}.state-{}", name, ix), fact)?);
+        }
+        let iter = scan_body.add_source(
+            format!("{}.iter", name),
+            TypedFact::dt_shape(i64::datum_type(), [1].as_ref())?,
+        )?;
+        let iter = scan_body.wire_node(format!("{}.iter.rm", name), AxisOp::Rm(0), &[iter])?[0];
+        let cond = scan_body.add_const(format!("{}.cond", name), tensor0(true))?;
+        let mut body_inputs = tvec!(iter, cond);
+        body_inputs.extend(states.iter().cloned());
+        for ix in 0..closures {
+            let fact = body.input_fact(2 + carried + ix)?;
+            let fact = TypedFact::dt_shape(fact.datum_type, fact.shape.clone())?;
+            body_inputs.push(scan_body.add_source(format!("{}.closure-{}", name, ix), fact)?);
+        }
+        let body_outputs = control_flow::wire_body(name, body, &mut scan_body, &body_inputs)?;
+        let mut scan_body_outputs: TVec<OutletId> = body_outputs[1..][..carried].into();
+        for (ix, output) in body_outputs[1 + carried..].iter().enumerate() {
+            scan_body_outputs.push(
+                scan_body.wire_node(
+                    format!("{}.scan-output-{}.add", name, ix),
+                    AxisOp::Add(0),
+                    &[*output],
+                )?[0],
+            );
+        }
+        scan_body.set_output_outlets(&scan_body_outputs)?;
+
+        let mut input_mapping = vec![];
+        let mut output_mapping = vec![];
+        for ix in 0..carried {
+            input_mapping.push(ops::scan::InputMapping::State {
+                initializer: ops::scan::StateInitializer::FromInput(ix),
+            });
+            output_mapping.push(ops::scan::OutputMapping {
+                state: true,
+                last_value_slot: Some(ix),
+                full_slot: None,
+                axis: 0,
+                chunk: 1.to_dim(),
+                full_dim_hint: None,
+            });
+        }
+        input_mapping.push(ops::scan::InputMapping::Scan {
+            slot: carried,
+            axis: 0,
+            chunk: 1.to_dim(),
+        });
+        for ix in 0..closures {
+            input_mapping.push(ops::scan::InputMapping::Full { slot: carried + 1 + ix });
+        }
+        for ix in 0..self.scan_outputs()? {
+            output_mapping.push(ops::scan::OutputMapping {
+                state: false,
+                last_value_slot: None,
+                full_slot: Some(carried + ix),
+                axis: 0,
+                chunk: 1.to_dim(),
+                full_dim_hint: Some(trip_count.to_dim()),
+            });
+        }
+
+        let first_carried = self.first_carried_slot();
+        let mut scan_inputs: TVec<OutletId> = inputs[first_carried..][..carried].into();
+        let iters = tensor1(&(0..trip_count as i64).collect::<Vec<_>>());
+        scan_inputs.push(target.add_const(format!("{}.iters", name), iters)?);
+        scan_inputs.extend(inputs[first_carried + carried..].iter().cloned());
+        let op = ops::scan::Scan::new(scan_body, input_mapping, output_mapping, None, false)?;
+        target.wire_node(name, op, &scan_inputs)
+    }
+}
+
+impl Op for Loop {
+    fn name(&self) -> Cow<str> {
+        "Loop".into()
+    }
+
+    op_onnx!();
+    not_a_typed_op!();
+}
+
+impl StatelessOp for Loop {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let inputs = self.core_loop_inputs(&inputs, |_, t| Ok(t.into_arc_tensor()))?;
+        self.to_core_loop(&inputs)?.eval(inputs)
+    }
+}
+
+impl InferenceOp for Loop {
+    fn infer_facts(
+        &mut self,
+        inputs: TVec<&InferenceFact>,
+        outputs: TVec<&InferenceFact>,
+        _observed: TVec<&InferenceFact>,
+    ) -> TractResult<(TVec<InferenceFact>, TVec<InferenceFact>, TVec<InferenceFact>)> {
+        let mut inputs: TVec<InferenceFact> = inputs.into_iter().cloned().collect();
+        let mut outputs: TVec<InferenceFact> = outputs.into_iter().cloned().collect();
+        let first_carried = self.first_carried_slot();
+        let carried = self.carried;
+        if outputs.len() != self.body.output_outlets()?.len() - 1 {
+            bail!(
+                "Loop has {} outputs, body expects {}",
+                outputs.len(),
+                self.body.output_outlets()?.len() - 1
+            )
+        }
+        let scalar = |dt: DatumType| InferenceFact::dt_shape(dt, shapefactoid!());
+        self.body.input_fact_mut(0)?.unify_with(&scalar(i64::datum_type()))?;
+        self.body.input_fact_mut(1)?.unify_with(&scalar(bool::datum_type()))?;
+        loop {
+            let mut changed = false;
+            for ix in 0..carried {
+                let body_input = self.body.input_outlets()?[2 + ix];
+                let body_output = self.body.output_outlets()?[1 + ix];
+                let mut facts = if body_input == body_output {
+                    tvec!(self.body.outlet_fact_mut(body_input)?)
+                } else {
+                    self.body.outlets_fact_mut(&[body_input, body_output])?
+                };
+                facts.push(&mut inputs[first_carried + ix]);
+                facts.push(&mut outputs[ix]);
+                if Factoid::unify_all(
+                    &mut *facts.iter_mut().map(|f| &mut f.datum_type).collect::<TVec<_>>(),
+                )? {
+                    changed = true;
+                }
+                if Factoid::unify_all(
+                    &mut *facts.iter_mut().map(|f| &mut f.shape).collect::<TVec<_>>(),
+                )? {
+                    changed = true;
+                }
+            }
+            for (ix, input) in inputs[first_carried + carried..].iter_mut().enumerate() {
+                if input.unify_with_mut(self.body.input_fact_mut(2 + carried + ix)?)? {
+                    changed = true;
+                }
+            }
+            let trip_count = self.static_trip_count(
+                self.trip_count_input.and_then(|slot| inputs[slot].value.concretize()).as_deref(),
+                self.cond_input.and_then(|slot| inputs[slot].value.concretize()).as_deref(),
+                self.inference_body_cond_is_true()?,
+            )?;
+            for ix in 0..self.scan_outputs()? {
+                let fact = self.body.output_fact(1 + carried + ix)?;
+                let output = &mut outputs[carried + ix];
+                if output.datum_type.unify_with(&fact.datum_type)? {
+                    changed = true;
+                }
+                if let Some(shape) = fact.shape.concretize() {
+                    let mut dims = tvec!(trip_count
+                        .map(|n| GenericFactoid::Only(n.to_dim()))
+                        .unwrap_or(GenericFactoid::Any));
+                    dims.extend(shape.into_iter().map(GenericFactoid::Only));
+                    if output.shape.unify_with(&ShapeFactoid::closed(dims))? {
+                        changed = true;
+                    }
+                }
+            }
+            if self.body.analyse(false)? {
+                changed = true;
+            }
+            if !changed {
+                break;
+            }
+        }
+        Ok((inputs, outputs, tvec!()))
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let inputs: TVec<OutletId> = node.inputs.iter().map(|i| mapping[i]).collect();
+        let body = self.body.clone().into_typed()?.declutter()?;
+        let konst = |slot: Option<usize>| -> TractResult<Option<Arc<Tensor>>> {
+            Ok(match slot {
+                Some(slot) => target.outlet_fact(inputs[slot])?.konst.clone(),
+                None => None,
+            })
+        };
+        let cond = konst(self.cond_input)?;
+        if self.cond_input.is_none() || cond.is_some() {
+            let trip_count = self.static_trip_count(
+                konst(self.trip_count_input)?.as_deref(),
+                cond.as_deref(),
+                Self::typed_body_cond_is_true(&body)?,
+            )?;
+            if let Some(trip_count) = trip_count.filter(|&n| n > 0) {
+                return self.wire_as_scan(&node.name, &body, trip_count, target, &inputs);
+            }
+        }
+        let loop_inputs = self.core_loop_inputs(&inputs, |name, t| {
+            target.add_const(format!("{}.{}", node.name, name), t)
+        })?;
+        let iters = TDim::sym(format!("{}.iters", node.name));
+        let op = control_flow::Loop::new(body, self.carried, iters);
+        target.wire_node(&*node.name, op, &loop_inputs)
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.body.output_outlets()?.len() - 1)
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::attr_graph;
+    use crate::ops::test_util::*;
+    use tract_hir::internal::*;
+
+    // body: (iter, cond, acc) -> (iter < 2, acc + 1, acc)
+    fn loop_node() -> crate::pb::NodeProto {
+        let iter = tensor0(0i64);
+        let cond = tensor0(true);
+        let acc = tensor1(&[0f32, 10.]);
+        let body = graph(
+            vec![
+                node("Less", &["iter", "two"], &["cond_out"], vec![]),
+                node("Add", &["acc", "one"], &["acc_out"], vec![]),
+                node("Identity", &["acc"], &["scan_out"], vec![]),
+            ],
+            &[("iter", &iter), ("cond", &cond), ("acc", &acc)],
+            &[("two", tensor0(2i64)), ("one", tensor1(&[1f32, 1.]))],
+            &[
+                ("cond_out", bool::datum_type()),
+                ("acc_out", f32::datum_type()),
+                ("scan_out", f32::datum_type()),
+            ],
+        );
+        node(
+            "Loop",
+            &["max", "cond", "acc"],
+            &["acc_final", "scans"],
+            vec![attr_graph("body", body)],
+        )
+    }
+
+    fn outputs() -> [(&'static str, DatumType); 2] {
+        [("acc_final", f32::datum_type()), ("scans", f32::datum_type())]
+    }
+
+    #[test]
+    fn loop_with_dynamic_trip_count_and_scan_output() {
+        let inputs = [("max", tensor0(10i64)), ("acc", tensor1(&[0f32, 10.]))];
+        let consts = [("cond", tensor0(true))];
+        let result = run_node(11, loop_node(), &inputs, &consts, &outputs()).unwrap();
+        assert_eq!(*result[0], tensor1(&[3f32, 13.]));
+        assert_eq!(*result[1], tensor2(&[[0f32, 10.], [1., 11.], [2., 12.]]));
+        let inputs = [("max", tensor0(2i64)), ("acc", tensor1(&[0f32, 10.]))];
+        let result = run_node(11, loop_node(), &inputs, &consts, &outputs()).unwrap();
+        assert_eq!(*result[0], tensor1(&[2f32, 12.]));
+        assert_eq!(*result[1], tensor2(&[[0f32, 10.], [1., 11.]]));
+    }
+
+    #[test]
+    fn loop_with_static_trip_count_and_scan_output() {
+        let mut node = loop_node();
+        // the condition is always true, so this runs as a Scan
+        node.attribute[0].g.as_mut().unwrap().node[0] =
+            crate::ops::test_util::node("Identity", &["cond"], &["cond_out"], vec![]);
+        let inputs = [("acc", tensor1(&[0f32, 10.]))];
+        let consts = [("max", tensor0(2i64)), ("cond", tensor0(true))];
+        let result = run_node(11, node, &inputs, &consts, &outputs()).unwrap();
+        assert_eq!(*result[0], tensor1(&[2f32, 12.]));
+        assert_eq!(*result[1], tensor2(&[[0f32, 10.], [1., 11.]]));
+    }
+}
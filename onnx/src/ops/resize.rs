@@ -0,0 +1,332 @@
+use crate::model::{optional_inputs, OnnxOpRegister, ParsingContext};
+use crate::pb::*;
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::resize::{self, CoordTransformer, Interpolator, Nearest};
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert("Resize", resize);
+    reg.insert("Upsample", upsample);
+}
+
+fn interpolator(node: &NodeProto) -> TractResult<Interpolator> {
+    let mode = node.get_attr_opt("mode")?.unwrap_or("nearest");
+    node.check_value(
+        "mode",
+        match mode {
+            "nearest" => Ok(Interpolator::Nearest),
+            "linear" | "bilinear" => Ok(Interpolator::Linear),
+            "cubic" => Ok(Interpolator::Cubic),
+            _ => Err(mode),
+        },
+    )
+}
+
+fn resize(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    if ctx.onnx_operator_set_version < 11 {
+        // Resize-10 has the same semantics as Upsample-9
+        return upsample(ctx, node);
+    }
+    if node.get_attr_opt("antialias")?.unwrap_or(0i64) != 0 {
+        bail!("Resize: attribute 'antialias' is not supported (operator set 18)")
+    }
+    if node.get_attr_opt_vec::<i64>("axes")?.is_some() {
+        bail!("Resize: attribute 'axes' is not supported (operator set 18)")
+    }
+    if node.get_attr_opt("keep_aspect_ratio_policy")?.unwrap_or("stretch") != "stretch" {
+        bail!("Resize: attribute 'keep_aspect_ratio_policy' is not supported (operator set 18)")
+    }
+    let coord_transformer =
+        node.get_attr_opt("coordinate_transformation_mode")?.unwrap_or("half_pixel");
+    let coord_transformer = node.check_value(
+        "coordinate_transformation_mode",
+        match coord_transformer {
+            "half_pixel" => Ok(CoordTransformer::HalfPixel),
+            "align_corners" => Ok(CoordTransformer::AlignCorners),
+            "asymmetric" => Ok(CoordTransformer::Asymmetric),
+            "tf_half_pixel_for_nn" => Ok(CoordTransformer::TfHalfPixelForNn),
+            "pytorch_half_pixel" => Ok(CoordTransformer::PytorchHalfPixel),
+            "tf_crop_and_resize" => Ok(CoordTransformer::TfCropAndResize),
+            _ => Err(coord_transformer),
+        },
+    )?;
+    let nearest = node.get_attr_opt("nearest_mode")?.unwrap_or("round_prefer_floor");
+    let nearest = node.check_value(
+        "nearest_mode",
+        match nearest {
+            "round_prefer_floor" => Ok(Nearest::RoundPreferFloor),
+            "round_prefer_ceil" => Ok(Nearest::RoundPreferCeil),
+            "floor" => Ok(Nearest::Floor),
+            "ceil" => Ok(Nearest::Ceil),
+            _ => Err(nearest),
+        },
+    )?;
+    let mut options = optional_inputs(node).skip(1);
+    let op = resize::Resize::new(
+        coord_transformer,
+        interpolator(node)?,
+        nearest,
+        node.get_attr_opt("cubic_coeff_a")?.unwrap_or(-0.75),
+        node.get_attr_opt("exclude_outside")?.unwrap_or(false),
+        node.get_attr_opt("extrapolation_value")?.unwrap_or(0.0),
+        options.next().unwrap(),
+        options.next().unwrap(),
+        options.next().unwrap(),
+    );
+    Ok((expand(Resize::new(op, None)), vec![]))
+}
+
+fn upsample(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let static_scales = if ctx.onnx_operator_set_version < 9 {
+        Some(rctensor1(&node.get_attr_vec::<f32>("scales")?))
+    } else {
+        None
+    };
+    let op = resize::Resize::new(
+        CoordTransformer::Asymmetric,
+        interpolator(node)?,
+        Nearest::Floor,
+        -0.75,
+        false,
+        0.0,
+        None,
+        Some(1),
+        None,
+    );
+    Ok((expand(Resize::new(op, static_scales)), vec![]))
+}
+
+/// ONNX Resize and Upsample.
+///
+/// Upsample-7 takes its scales as an attribute: they are kept in
+/// `static_scales` and wired as a constant input of the core op.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Resize {
+    op: resize::Resize,
+    static_scales: Option<Arc<Tensor>>,
+}
+
+tract_linalg::impl_dyn_hash!(Resize);
+
+impl Resize {
+    fn output_shape(
+        input_shape: &[TDim],
+        scales: Option<&Tensor>,
+        sizes: Option<&Tensor>,
+    ) -> TractResult<TVec<TDim>> {
+        if let Some(sizes) = sizes.filter(|s| s.len() > 0) {
+            let sizes = sizes.cast_to::<i64>()?;
+            if sizes.len() != input_shape.len() {
+                bail!("Resize expects {} sizes, got {:?}", input_shape.len(), sizes)
+            }
+            sizes
+                .as_slice::<i64>()?
+                .iter()
+                .map(|&s| {
+                    if s < 0 {
+                        bail!("Resize sizes can not be negative, got {:?}", sizes)
+                    }
+                    Ok((s as usize).to_dim())
+                })
+                .collect()
+        } else if let Some(scales) = scales.filter(|s| s.len() > 0) {
+            let scales = scales.cast_to::<f32>()?;
+            if scales.len() != input_shape.len() {
+                bail!("Resize expects {} scales, got {:?}", input_shape.len(), scales)
+            }
+            input_shape
+                .iter()
+                .zip(scales.as_slice::<f32>()?.iter())
+                .map(|(d, &s)| {
+                    if s == 1.0 {
+                        Ok(d.clone())
+                    } else {
+                        Ok(((d.to_integer()? as f32 * s).floor() as usize).to_dim())
+                    }
+                })
+                .collect()
+        } else {
+            bail!("Resize needs either scales or sizes")
+        }
+    }
+}
+
+impl Expansion for Resize {
+    fn name(&self) -> Cow<str> {
+        "Resize".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].rank, &outputs[0].rank)?;
+        let set_shape = move |s: &mut Solver<'r>, shape: TVec<TDim>| -> InferenceResult {
+            for (ix, d) in shape.into_iter().enumerate() {
+                s.equals(&outputs[0].shape[ix], d)?;
+            }
+            Ok(())
+        };
+        if let Some(scales) = &self.static_scales {
+            s.given(&inputs[0].shape, move |s, shape| {
+                set_shape(s, Self::output_shape(&shape, Some(scales), None)?)
+            })?;
+        } else if let Some(sizes) = self.op.optional_sizes_input {
+            if let Some(scales) = self.op.optional_scales_input {
+                s.given_3(
+                    &inputs[0].shape,
+                    &inputs[scales].value,
+                    &inputs[sizes].value,
+                    move |s, shape, scales, sizes| {
+                        set_shape(s, Self::output_shape(&shape, Some(&scales), Some(&sizes))?)
+                    },
+                )?;
+            } else {
+                s.given_2(&inputs[0].shape, &inputs[sizes].value, move |s, shape, sizes| {
+                    set_shape(s, Self::output_shape(&shape, None, Some(&sizes))?)
+                })?;
+            }
+        } else if let Some(scales) = self.op.optional_scales_input {
+            s.given_2(&inputs[0].shape, &inputs[scales].value, move |s, shape, scales| {
+                set_shape(s, Self::output_shape(&shape, Some(&scales), None)?)
+            })?;
+        }
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let mut inputs: TVec<OutletId> = inputs.into();
+        if let Some(scales) = &self.static_scales {
+            inputs.push(model.add_const(format!("{}.scales", prefix), scales.clone())?);
+        }
+        model.wire_node(prefix, self.op.clone(), &inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{attr_float, attr_string};
+    use crate::ops::test_util::*;
+
+    fn resize(
+        attrs: Vec<AttributeProto>,
+        input: Tensor,
+        roi: Option<Tensor>,
+        scales: Option<Tensor>,
+        sizes: Option<Tensor>,
+    ) -> TractResult<Tensor> {
+        let mut consts = vec![];
+        let mut names = vec!["x"];
+        for (name, t) in vec![("roi", roi), ("scales", scales), ("sizes", sizes)] {
+            if let Some(t) = t {
+                consts.push((name, t));
+                names.push(name);
+            } else {
+                names.push("");
+            }
+        }
+        let node = node("Resize", &names, &["y"], attrs);
+        let mut outputs =
+            run_node(11, node, &[("x", input)], &consts, &[("y", f32::datum_type())])?;
+        Ok(outputs.remove(0).into_tensor())
+    }
+
+    #[test]
+    fn nearest_modes() {
+        let input = tensor2(&[[1f32, 2.]]);
+        let scales = tensor1(&[1f32, 2.]);
+        for &(mode, expected) in
+            &[("round_prefer_floor", [1f32, 1., 2., 2.]), ("round_prefer_ceil", [1., 2., 2., 2.])]
+        {
+            let attrs = vec![
+                attr_string("coordinate_transformation_mode", "asymmetric"),
+                attr_string("nearest_mode", mode),
+            ];
+            // exporters usually give an empty roi
+            let roi = tensor1::<f32>(&[]);
+            let output =
+                resize(attrs, input.clone(), Some(roi), Some(scales.clone()), None).unwrap();
+            assert_eq!(output, tensor2(&[expected]));
+        }
+    }
+
+    #[test]
+    fn linear_align_corners_with_sizes() {
+        let attrs = vec![
+            attr_string("mode", "linear"),
+            attr_string("coordinate_transformation_mode", "align_corners"),
+        ];
+        let sizes = tensor1(&[1i64, 4]);
+        let output = resize(attrs, tensor2(&[[0f32, 3.]]), None, None, Some(sizes)).unwrap();
+        output.close_enough(&tensor2(&[[0f32, 1., 2., 3.]]), true).unwrap();
+    }
+
+    #[test]
+    fn tf_crop_and_resize_extrapolates() {
+        let attrs = vec![
+            attr_string("mode", "linear"),
+            attr_string("coordinate_transformation_mode", "tf_crop_and_resize"),
+            attr_float("extrapolation_value", 10.0),
+        ];
+        let input = tensor2(&[[0f32, 1., 2., 3.]]);
+        let roi = tensor1(&[0f32, 0., 1., 1.5]);
+        let sizes = tensor1(&[1i64, 3]);
+        let output = resize(attrs, input, Some(roi), None, Some(sizes)).unwrap();
+        output.close_enough(&tensor2(&[[0f32, 2.25, 10.]]), true).unwrap();
+    }
+
+    #[test]
+    fn upsample() {
+        use crate::pb::attribute_proto::AttributeType;
+        let input = tensor2(&[[1f32, 2.]]);
+        let scales = AttributeProto {
+            name: "scales".to_string(),
+            r#type: AttributeType::Floats as i32,
+            floats: vec![2., 1.],
+            ..AttributeProto::default()
+        };
+        let upsample7 = node("Upsample", &["x"], &["y"], vec![scales]);
+        let output =
+            run_node(7, upsample7, &[("x", input.clone())], &[], &[("y", f32::datum_type())]);
+        assert_eq!(*output.unwrap()[0], tensor2(&[[1f32, 2.], [1., 2.]]));
+        let upsample9 = node("Upsample", &["x", "scales"], &["y"], vec![]);
+        let scales = tensor1(&[1f32, 2.]);
+        let output = run_node(
+            9,
+            upsample9,
+            &[("x", input)],
+            &[("scales", scales)],
+            &[("y", f32::datum_type())],
+        );
+        assert_eq!(*output.unwrap()[0], tensor2(&[[1f32, 1., 2., 2.]]));
+    }
+
+    #[test]
+    fn reject_scales_of_wrong_length() {
+        let input = tensor2(&[[1f32, 2.]]);
+        assert!(resize(vec![], input, None, Some(tensor1(&[2f32])), None).is_err());
+    }
+
+    #[test]
+    fn reject_negative_sizes() {
+        let input = tensor2(&[[1f32, 2.]]);
+        assert!(resize(vec![], input, None, None, Some(tensor1(&[1i64, -2]))).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! Helpers building and running single-node ONNX models in unit tests.
+use crate::pb::tensor_proto::DataType;
+use crate::pb::*;
+use std::convert::TryInto;
+use tract_hir::internal::*;
+
+pub fn node(
+    op_type: &str,
+    inputs: &[&str],
+    outputs: &[&str],
+    attribute: Vec<AttributeProto>,
+) -> NodeProto {
+    NodeProto {
+        op_type: op_type.to_string(),
+        input: inputs.iter().map(|s| s.to_string()).collect(),
+        output: outputs.iter().map(|s| s.to_string()).collect(),
+        attribute,
+        ..NodeProto::default()
+    }
+}
+
+/// A node of the onnxruntime "com.microsoft" domain.
+pub fn ms_node(
+    op_type: &str,
+    inputs: &[&str],
+    outputs: &[&str],
+    attribute: Vec<AttributeProto>,
+) -> NodeProto {
+    NodeProto { domain: "com.microsoft".to_string(), ..node(op_type, inputs, outputs, attribute) }
+}
+
+pub fn value_info(name: &str, dt: DatumType, shape: Option<&[usize]>) -> ValueInfoProto {
+    let elem_type: DataType = dt.try_into().unwrap();
+    let shape = shape.map(|shape| TensorShapeProto {
+        dim: shape
+            .iter()
+            .map(|&d| tensor_shape_proto::Dimension {
+                value: Some(tensor_shape_proto::dimension::Value::DimValue(d as i64)),
+                ..Default::default()
+            })
+            .collect(),
+    });
+    ValueInfoProto {
+        name: name.to_string(),
+        r#type: Some(TypeProto {
+            value: Some(type_proto::Value::TensorType(type_proto::Tensor {
+                elem_type: elem_type as i32,
+                shape,
+            })),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+pub fn initializer(name: &str, t: &Tensor) -> TensorProto {
+    let mut proto: TensorProto = t.try_into().unwrap();
+    proto.name = name.to_string();
+    proto
+}
+
+/// A graph made of `nodes`, with `inputs` as sources, `consts` as
+/// initializers and untyped `outputs` of the given datum types.
+pub fn graph(
+    nodes: Vec<NodeProto>,
+    inputs: &[(&str, &Tensor)],
+    consts: &[(&str, Tensor)],
+    outputs: &[(&str, DatumType)],
+) -> GraphProto {
+    GraphProto {
+        node: nodes,
+        input: inputs
+            .iter()
+            .map(|(name, t)| value_info(name, t.datum_type(), Some(t.shape())))
+            .collect(),
+        initializer: consts.iter().map(|(name, t)| initializer(name, t)).collect(),
+        output: outputs.iter().map(|(name, dt)| value_info(name, *dt, None)).collect(),
+        ..GraphProto::default()
+    }
+}
+
+/// Load, optimize and run a graph with the given operator set.
+pub fn run_graph(
+    opset: i64,
+    graph: GraphProto,
+    inputs: TVec<Tensor>,
+) -> TractResult<TVec<Arc<Tensor>>> {
+    let proto = ModelProto {
+        ir_version: 6,
+        opset_import: vec![OperatorSetIdProto { domain: String::new(), version: opset }],
+        graph: Some(graph),
+        ..ModelProto::default()
+    };
+    let model = crate::onnx().model_for_proto_model(&proto)?.into_optimized()?;
+    SimplePlan::new(model)?.run(inputs)
+}
+
+/// Run a single node taking `inputs` and `consts`.
+pub fn run_node(
+    opset: i64,
+    node: NodeProto,
+    inputs: &[(&str, Tensor)],
+    consts: &[(&str, Tensor)],
+    outputs: &[(&str, DatumType)],
+) -> TractResult<TVec<Arc<Tensor>>> {
+    let sources = inputs.iter().map(|(name, t)| (*name, t)).collect::<Vec<_>>();
+    let graph = graph(vec![node], &sources, consts, outputs);
+    run_graph(opset, graph, inputs.iter().map(|(_, t)| t.clone()).collect())
+}
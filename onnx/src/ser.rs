@@ -0,0 +1,58 @@
+//! Serialization of the TypedOps defined in this crate.
+//!
+//! See `tract_core::ser` for the format itself.
+use tract_hir::tract_core::ser::Registry;
+
+pub fn register_onnx_ops(reg: &mut Registry) {
+    crate::ops::register_ser_ops(reg);
+}
+
+/// A registry knowing about the core ops, and the ones defined in tract-hir
+/// and in this crate.
+pub fn registry() -> Registry {
+    let mut registry = tract_hir::ser::registry();
+    register_onnx_ops(&mut registry);
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{attr_float, attr_int};
+    use crate::ops::test_util::*;
+    use crate::pb::*;
+    use tract_hir::internal::*;
+
+    #[test]
+    fn round_trip_onnx_element_wise_ops() {
+        let x = tensor1(&[-1f32, 0.2, 0.7, std::f32::INFINITY]);
+        let nodes = vec![
+            node("Erf", &["x"], &["erf"], vec![]),
+            node("Shrink", &["erf"], &["shrunk"], vec![attr_float("lambd", 0.5)]),
+            node("Cast", &["shrunk"], &["y"], vec![attr_int("to", 11)]),
+            node("IsInf", &["x"], &["inf"], vec![attr_int("detect_negative", 0)]),
+        ];
+        let graph = graph(
+            nodes,
+            &[("x", &x)],
+            &[],
+            &[("y", f64::datum_type()), ("inf", bool::datum_type())],
+        );
+        let proto = ModelProto {
+            ir_version: 6,
+            opset_import: vec![OperatorSetIdProto { domain: String::new(), version: 13 }],
+            graph: Some(graph),
+            ..ModelProto::default()
+        };
+        let model = crate::onnx().model_for_proto_model(&proto).unwrap().into_typed().unwrap();
+        let model = model.declutter().unwrap();
+        let mut buffer = vec![];
+        assert!(tract_hir::ser::registry().write_model(&model, &mut buffer).is_err());
+        buffer.clear();
+        registry().write_model(&model, &mut buffer).unwrap();
+        let reloaded = registry().read_model(&*buffer).unwrap();
+        let expected = SimplePlan::new(&model).unwrap().run(tvec!(x.clone())).unwrap();
+        let found = SimplePlan::new(&reloaded).unwrap().run(tvec!(x)).unwrap();
+        assert_eq!(expected, found);
+    }
+}
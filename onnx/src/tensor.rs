@@ -1,7 +1,10 @@
 use crate::pb::tensor_proto::DataType;
 use crate::pb::*;
 use prost::Message;
+use std::cell::RefCell;
 use std::convert::{TryFrom, TryInto};
+use std::rc::Rc;
+use std::{fmt, fs, path};
 use tract_hir::internal::*;
 
 impl TryFrom<DataType> for DatumType {
@@ -26,6 +29,28 @@ impl TryFrom<DataType> for DatumType {
     }
 }
 
+impl TryFrom<DatumType> for DataType {
+    type Error = TractError;
+    fn try_from(t: DatumType) -> TractResult<DataType> {
+        match t {
+            DatumType::Bool => Ok(DataType::Bool),
+            DatumType::U8 => Ok(DataType::Uint8),
+            DatumType::U16 => Ok(DataType::Uint16),
+            DatumType::U32 => Ok(DataType::Uint32),
+            DatumType::U64 => Ok(DataType::Uint64),
+            DatumType::I8 => Ok(DataType::Int8),
+            DatumType::I16 => Ok(DataType::Int16),
+            DatumType::I32 => Ok(DataType::Int32),
+            DatumType::I64 => Ok(DataType::Int64),
+            DatumType::F16 => Ok(DataType::Float16),
+            DatumType::F32 => Ok(DataType::Float),
+            DatumType::F64 => Ok(DataType::Double),
+            DatumType::String => Ok(DataType::String),
+            _ => Err(format!("No ONNX equivalent for {:?}", t))?,
+        }
+    }
+}
+
 impl<'a> TryFrom<&'a type_proto::Tensor> for InferenceFact {
     type Error = TractError;
     fn try_from(t: &'a type_proto::Tensor) -> TractResult<InferenceFact> {
@@ -61,82 +86,225 @@ impl TryFrom<type_proto::Tensor> for InferenceFact {
 impl<'a> TryFrom<&'a TensorProto> for Tensor {
     type Error = TractError;
     fn try_from(t: &TensorProto) -> TractResult<Tensor> {
-        let dt = DataType::from_i32(t.data_type).unwrap().try_into()?;
-        let shape: Vec<usize> = t.dims.iter().map(|&i| i as usize).collect();
-        if t.raw_data.len() > 0 {
-            unsafe {
-                match dt {
-                    DatumType::U8 => Tensor::from_raw::<u8>(&*shape, &*t.raw_data),
-                    DatumType::U16 => Tensor::from_raw::<u16>(&*shape, &*t.raw_data),
-                    DatumType::U32 => Tensor::from_raw::<u32>(&*shape, &*t.raw_data),
-                    DatumType::U64 => Tensor::from_raw::<u64>(&*shape, &*t.raw_data),
-                    DatumType::I8 => Tensor::from_raw::<i8>(&*shape, &*t.raw_data),
-                    DatumType::I16 => Tensor::from_raw::<i16>(&*shape, &*t.raw_data),
-                    DatumType::I32 => Tensor::from_raw::<i32>(&*shape, &*t.raw_data),
-                    DatumType::I64 => Tensor::from_raw::<i64>(&*shape, &*t.raw_data),
-                    DatumType::F16 => Tensor::from_raw::<f16>(&*shape, &*t.raw_data),
-                    DatumType::F32 => Tensor::from_raw::<f32>(&*shape, &*t.raw_data),
-                    DatumType::F64 => Tensor::from_raw::<f64>(&*shape, &*t.raw_data),
-                    DatumType::Bool => Ok(Tensor::from_raw::<u8>(&*shape, &*t.raw_data)?
-                        .into_array::<u8>()?
-                        .mapv(|x| x != 0)
-                        .into()),
-                    _ => unimplemented!("FIXME, raw tensor loading"),
-                }
+        load_tensor(t, None)
+    }
+}
+
+fn from_raw(dt: DatumType, shape: &[usize], raw: &[u8]) -> TractResult<Tensor> {
+    let expected = shape.iter().product::<usize>() * dt.size_of();
+    if raw.len() != expected {
+        bail!(
+            "Raw data for {:?} tensor of shape {:?} should be {} bytes, found {}",
+            dt,
+            shape,
+            expected,
+            raw.len()
+        )
+    }
+    unsafe {
+        match dt {
+            DatumType::U8 => Tensor::from_raw::<u8>(shape, raw),
+            DatumType::U16 => Tensor::from_raw::<u16>(shape, raw),
+            DatumType::U32 => Tensor::from_raw::<u32>(shape, raw),
+            DatumType::U64 => Tensor::from_raw::<u64>(shape, raw),
+            DatumType::I8 => Tensor::from_raw::<i8>(shape, raw),
+            DatumType::I16 => Tensor::from_raw::<i16>(shape, raw),
+            DatumType::I32 => Tensor::from_raw::<i32>(shape, raw),
+            DatumType::I64 => Tensor::from_raw::<i64>(shape, raw),
+            DatumType::F16 => Tensor::from_raw::<f16>(shape, raw),
+            DatumType::F32 => Tensor::from_raw::<f32>(shape, raw),
+            DatumType::F64 => Tensor::from_raw::<f64>(shape, raw),
+            DatumType::Bool => {
+                Ok(Tensor::from_raw::<u8>(shape, raw)?.into_array::<u8>()?.mapv(|x| x != 0).into())
             }
-        } else {
-            use tract_ndarray::Array;
-            let it = match dt {
-                DatumType::Bool => {
-                    Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x != 0).collect())?
-                        .into()
-                }
-                DatumType::U8 => {
-                    Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x as u8).collect())?
-                        .into()
-                }
-                DatumType::U16 => Array::from_shape_vec(
-                    &*shape,
-                    t.int32_data.iter().map(|&x| x as u16).collect(),
-                )?
-                .into(),
-                DatumType::U32 => Array::from_shape_vec(
-                    &*shape,
-                    t.int32_data.iter().map(|&x| x).collect(),
-                )?
-                .into(),
-                DatumType::U64 => Array::from_shape_vec(
-                    &*shape,
-                    t.int64_data.iter().map(|&x| x).collect(),
-                )?
-                .into(),
-                DatumType::I8 => {
-                    Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x as i8).collect())?
-                        .into()
-                }
-                DatumType::I16 => Array::from_shape_vec(
-                    &*shape,
-                    t.int32_data.iter().map(|&x| x as i16).collect(),
-                )?
-                .into(),
-                DatumType::I32 => Array::from_shape_vec(&*shape, t.int32_data.to_vec())?.into(),
-                DatumType::I64 => Array::from_shape_vec(&*shape, t.int64_data.to_vec())?.into(),
-                DatumType::F32 => Array::from_shape_vec(&*shape, t.float_data.to_vec())?.into(),
-                DatumType::F64 => Array::from_shape_vec(&*shape, t.double_data.to_vec())?.into(),
-                DatumType::String => {
-                    let strings = t
-                        .string_data
-                        .iter()
-                        .cloned()
-                        .map(String::from_utf8)
-                        .collect::<Result<Vec<String>, _>>()
-                        .map_err(|_| format!("Invalid UTF8 buffer"))?;
-                    Array::from_shape_vec(&*shape, strings)?.into()
+            _ => unimplemented!("FIXME, raw tensor loading"),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type ExternalFile = memmap::Mmap;
+#[cfg(target_arch = "wasm32")]
+type ExternalFile = Vec<u8>;
+
+/// The external data files of a model, found relative to the model
+/// directory. Each file is mapped once, however many tensors it holds.
+pub struct ExternalData<'a> {
+    model_dir: &'a path::Path,
+    files: RefCell<HashMap<String, Rc<ExternalFile>>>,
+}
+
+impl<'a> ExternalData<'a> {
+    pub fn new(model_dir: &'a path::Path) -> ExternalData<'a> {
+        ExternalData { model_dir, files: RefCell::new(HashMap::new()) }
+    }
+
+    fn file(&self, location: &str) -> TractResult<Rc<ExternalFile>> {
+        if let Some(file) = self.files.borrow().get(location) {
+            return Ok(file.clone());
+        }
+        let relative = path::Path::new(location);
+        if !relative
+            .components()
+            .all(|c| matches!(c, path::Component::Normal(_) | path::Component::CurDir))
+        {
+            bail!("External data location {:?} is not a path inside the model directory", location)
+        }
+        let path = self.model_dir.join(relative);
+        let file = fs::File::open(&path)
+            .map_err(|e| format!("Could not open external data {:?}: {}", path, e))?;
+        #[cfg(not(target_arch = "wasm32"))]
+        let map = unsafe { memmap::Mmap::map(&file)? };
+        #[cfg(target_arch = "wasm32")]
+        let map = {
+            let mut v = vec![];
+            std::io::Read::read_to_end(&mut &file, &mut v)?;
+            v
+        };
+        let map = Rc::new(map);
+        self.files.borrow_mut().insert(location.to_string(), map.clone());
+        Ok(map)
+    }
+}
+
+impl<'a> fmt::Debug for ExternalData<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ExternalData({:?})", self.model_dir)
+    }
+}
+
+fn load_external(
+    dt: DatumType,
+    shape: &[usize],
+    t: &TensorProto,
+    external_data: Option<&ExternalData>,
+) -> TractResult<Tensor> {
+    let mut location = None;
+    let mut offset = 0usize;
+    let mut length = None;
+    for entry in &t.external_data {
+        match &*entry.key {
+            "location" => location = Some(&*entry.value),
+            "offset" => offset = entry.value.parse()?,
+            "length" => length = Some(entry.value.parse::<usize>()?),
+            _ => (),
+        }
+    }
+    let location =
+        location.ok_or_else(|| format!("Tensor {}: external data has no location", t.name))?;
+    let external_data = external_data.ok_or_else(|| {
+        format!(
+            "Tensor {}: external data can only be resolved for models loaded from a path",
+            t.name
+        )
+    })?;
+    let map = external_data.file(location)?;
+    let end = if let Some(length) = length { offset.checked_add(length) } else { Some(map.len()) };
+    match end {
+        Some(end) if offset <= end && end <= map.len() => from_raw(dt, shape, &map[offset..end]),
+        _ => bail!(
+            "Tensor {}: external data range at {} (length {:?}) is out of {:?} ({} bytes)",
+            t.name,
+            offset,
+            length,
+            location,
+            map.len()
+        ),
+    }
+}
+
+/// Convert a TensorProto to a Tensor, resolving external data in the
+/// directory containing the model.
+pub fn load_tensor(t: &TensorProto, external_data: Option<&ExternalData>) -> TractResult<Tensor> {
+    let dt = DataType::from_i32(t.data_type).unwrap().try_into()?;
+    let shape: Vec<usize> = t.dims.iter().map(|&i| i as usize).collect();
+    if t.data_location == tensor_proto::DataLocation::External as i32 {
+        load_external(dt, &shape, t, external_data)
+    } else if t.raw_data.len() > 0 {
+        from_raw(dt, &shape, &t.raw_data)
+    } else {
+        use tract_ndarray::Array;
+        let it = match dt {
+            DatumType::Bool => {
+                Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x != 0).collect())?
+                    .into()
+            }
+            DatumType::U8 => {
+                Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x as u8).collect())?
+                    .into()
+            }
+            DatumType::U16 => Array::from_shape_vec(
+                &*shape,
+                t.int32_data.iter().map(|&x| x as u16).collect(),
+            )?
+            .into(),
+            DatumType::U32 => Array::from_shape_vec(
+                &*shape,
+                t.int32_data.iter().map(|&x| x).collect(),
+            )?
+            .into(),
+            DatumType::U64 => Array::from_shape_vec(
+                &*shape,
+                t.int64_data.iter().map(|&x| x).collect(),
+            )?
+            .into(),
+            DatumType::I8 => {
+                Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x as i8).collect())?
+                    .into()
+            }
+            DatumType::I16 => Array::from_shape_vec(
+                &*shape,
+                t.int32_data.iter().map(|&x| x as i16).collect(),
+            )?
+            .into(),
+            DatumType::I32 => Array::from_shape_vec(&*shape, t.int32_data.to_vec())?.into(),
+            DatumType::I64 => Array::from_shape_vec(&*shape, t.int64_data.to_vec())?.into(),
+            DatumType::F32 => Array::from_shape_vec(&*shape, t.float_data.to_vec())?.into(),
+            DatumType::F64 => Array::from_shape_vec(&*shape, t.double_data.to_vec())?.into(),
+            DatumType::String => {
+                let strings = t
+                    .string_data
+                    .iter()
+                    .cloned()
+                    .map(String::from_utf8)
+                    .collect::<Result<Vec<String>, _>>()
+                    .map_err(|_| format!("Invalid UTF8 buffer"))?;
+                Array::from_shape_vec(&*shape, strings)?.into()
+            }
+            _ => unimplemented!("FIXME, struct tensor loading"),
+        };
+        Ok(it)
+    }
+}
+
+impl<'a> TryFrom<&'a Tensor> for TensorProto {
+    type Error = TractError;
+    fn try_from(t: &Tensor) -> TractResult<TensorProto> {
+        let data_type: DataType = t.datum_type().try_into()?;
+        let mut proto = TensorProto {
+            dims: t.shape().iter().map(|&d| d as i64).collect(),
+            data_type: data_type as i32,
+            ..TensorProto::default()
+        };
+        match t.datum_type() {
+            DatumType::String => {
+                proto.string_data =
+                    t.as_slice::<String>()?.iter().map(|s| s.as_bytes().to_vec()).collect()
+            }
+            DatumType::Bool => {
+                proto.raw_data = t.as_slice::<bool>()?.iter().map(|&b| b as u8).collect()
+            }
+            dt => {
+                fn raw<T: Datum>(t: &Tensor) -> TractResult<Vec<u8>> {
+                    let slice = t.as_slice::<T>()?;
+                    let len = slice.len() * std::mem::size_of::<T>();
+                    Ok(unsafe { std::slice::from_raw_parts(slice.as_ptr() as *const u8, len) }
+                        .to_vec())
                 }
-                _ => unimplemented!("FIXME, struct tensor loading"),
-            };
-            Ok(it)
+                proto.raw_data = dispatch_copy!(raw(dt)(t))?;
+            }
         }
+        Ok(proto)
     }
 }
 
@@ -157,3 +325,109 @@ pub fn proto_from_reader<R: ::std::io::Read>(mut r: R) -> TractResult<TensorProt
 pub fn from_reader<R: ::std::io::Read>(r: R) -> TractResult<Tensor> {
     proto_from_reader(r)?.try_into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::test_util::*;
+
+    fn external(name: &str, shape: &[i64], entries: &[(&str, String)]) -> TensorProto {
+        TensorProto {
+            name: name.to_string(),
+            dims: shape.to_vec(),
+            data_type: DataType::Float as i32,
+            data_location: tensor_proto::DataLocation::External as i32,
+            external_data: entries
+                .iter()
+                .map(|(k, v)| StringStringEntryProto { key: k.to_string(), value: v.clone() })
+                .collect(),
+            ..TensorProto::default()
+        }
+    }
+
+    /// A model computing (x + a, x * b), with a and b stored in `location`.
+    fn load(dir: &path::Path, location: &str) -> TractResult<TVec<Arc<Tensor>>> {
+        let x = tensor1(&[1f32, 2., 3.]);
+        let nodes = vec![
+            node("Add", &["x", "a"], &["sum"], vec![]),
+            node("Mul", &["x", "b"], &["prod"], vec![]),
+        ];
+        let outputs = [("sum", f32::datum_type()), ("prod", f32::datum_type())];
+        let mut graph = graph(nodes, &[("x", &x)], &[], &outputs);
+        let location = location.to_string();
+        // a is in the middle of the file, b at its end
+        graph.initializer.push(external(
+            "a",
+            &[3],
+            &[("location", location.clone()), ("offset", "8".into()), ("length", "12".into())],
+        ));
+        graph.initializer.push(external(
+            "b",
+            &[3],
+            &[("location", location), ("offset", "20".into())],
+        ));
+        let proto = ModelProto {
+            ir_version: 6,
+            opset_import: vec![OperatorSetIdProto { domain: String::new(), version: 11 }],
+            graph: Some(graph),
+            ..ModelProto::default()
+        };
+        let mut buffer = vec![];
+        proto.encode(&mut buffer).unwrap();
+        fs::write(dir.join("model.onnx"), buffer)?;
+        let model = crate::onnx().model_for_path(dir.join("model.onnx"))?;
+        SimplePlan::new(model.into_optimized()?)?.run(tvec!(x))
+    }
+
+    fn fixture_dir(name: &str) -> path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("tract-onnx-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let data: Vec<u8> = [0f32, 0., 10., 20., 30., 2., 3., 4.]
+            .iter()
+            .flat_map(|f| f.to_le_bytes().to_vec())
+            .collect();
+        fs::write(dir.join("weights.bin"), data).unwrap();
+        dir
+    }
+
+    #[test]
+    fn external_data_with_offset_and_length() {
+        let dir = fixture_dir("external");
+        let result = load(&dir, "weights.bin");
+        fs::remove_dir_all(&dir).unwrap();
+        let result = result.unwrap();
+        assert_eq!(result[0], rctensor1(&[11f32, 22., 33.]));
+        assert_eq!(result[1], rctensor1(&[2f32, 6., 12.]));
+    }
+
+    #[test]
+    fn external_data_errors() {
+        let dir = fixture_dir("external-errors");
+        let missing = load(&dir, "missing.bin");
+        let absolute = load(&dir, &*dir.join("weights.bin").to_string_lossy());
+        let parent = load(&dir, "../weights.bin");
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(missing.is_err());
+        assert!(absolute.is_err());
+        assert!(parent.is_err());
+    }
+
+    #[test]
+    fn external_data_out_of_range() {
+        let dir = fixture_dir("external-range");
+        let data = ExternalData::new(&dir);
+        let mut t = external("t", &[3], &[("location", "weights.bin".into())]);
+        let too_long = load_tensor(&t, Some(&data));
+        t.external_data.push(StringStringEntryProto {
+            key: "offset".into(),
+            value: std::usize::MAX.to_string(),
+        });
+        t.external_data.push(StringStringEntryProto { key: "length".into(), value: "12".into() });
+        let overflow = load_tensor(&t, Some(&data));
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(too_long.is_err());
+        assert!(overflow.is_err());
+        // both tensors used the same mapping
+        assert_eq!(data.files.borrow().len(), 1);
+    }
+}
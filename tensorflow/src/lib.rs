@@ -56,7 +56,10 @@ pub mod conform;
 
 pub mod model;
 pub mod ops;
+pub mod saved_model;
+pub mod ser;
 pub mod tensor;
+pub mod tensor_bundle;
 pub mod tfpb;
 
 pub use model::Tensorflow;
@@ -1,13 +1,132 @@
-use crate::tfpb::tensorflow::{GraphDef, NodeDef, SavedModel};
+use crate::tfpb::tensorflow::{
+    DataType, FunctionDef, FunctionDefLibrary, GraphDef, NodeDef, SavedModel,
+};
 use prost::Message;
+use std::convert::TryFrom;
 use std::{fs, path};
 use tract_hir::internal::*;
 
-#[derive(Default)]
-pub struct ParsingContext {
+pub struct ParsingContext<'a> {
+    pub framework: &'a Tensorflow,
+    pub library: Option<&'a FunctionDefLibrary>,
     pub node_output_arities: HashMap<String, usize>,
 }
 
+impl<'a> ParsingContext<'a> {
+    pub fn function(&self, name: &str) -> Option<&'a FunctionDef> {
+        self.library?.function.iter().find(|f| f.signature.as_ref().map(|s| &*s.name) == Some(name))
+    }
+
+    /// Parse a function of the library as a model with one input per
+    /// argument and one output per return value, in signature order.
+    pub fn parse_function(&self, name: &str) -> TractResult<InferenceModel> {
+        let func = self.function(name).ok_or_else(|| format!("Function {} not found", name))?;
+        let signature = func.signature.as_ref().unwrap();
+        let mut graph = crate::tfpb::graph();
+        for arg in &signature.input_arg {
+            let mut node = crate::tfpb::node().name(&arg.name).op("Placeholder");
+            // resource handles and generic arguments are left untyped
+            if let Some(dt) = DataType::from_i32(arg.r#type) {
+                if DatumType::try_from(dt).is_ok() {
+                    node = node.attr("dtype", dt);
+                }
+            }
+            graph = graph.node(node);
+        }
+        for node in &func.node_def {
+            let mut node = node.clone();
+            for input in &mut node.input {
+                *input = self.function_input(func, input)?;
+            }
+            graph = graph.node(node);
+        }
+        let outputs = signature
+            .output_arg
+            .iter()
+            .map(|arg| {
+                let ret = func
+                    .ret
+                    .get(&arg.name)
+                    .ok_or_else(|| format!("Function {} does not return {}", name, arg.name))?;
+                self.function_input(func, ret)
+            })
+            .collect::<TractResult<Vec<_>>>()?;
+        let mut context = ParsingContext {
+            framework: self.framework,
+            library: self.library,
+            node_output_arities: HashMap::new(),
+        };
+        for output in &outputs {
+            let (node, slot) = Tensorflow::parse_input(output)?;
+            let arity = context.node_output_arities.entry(node.to_string()).or_insert(1);
+            *arity = (*arity).max(slot + 1);
+        }
+        let TfModelAndExtensions(mut model, _) =
+            self.framework.parse_graph_with_context(&graph, context)?;
+        let outlets = outputs
+            .iter()
+            .map(|output| {
+                let (node, slot) = Tensorflow::parse_input(output)?;
+                Ok(OutletId::new(model.node_id_by_name(node)?, slot))
+            })
+            .collect::<TractResult<Vec<_>>>()?;
+        model.set_output_outlets(&outlets)?;
+        Ok(model)
+    }
+
+    // Function bodies refer to node outputs as "node:output_arg:index",
+    // translate them to the graph "node:slot" format.
+    fn function_input(&self, func: &FunctionDef, input: &str) -> TractResult<String> {
+        if input.starts_with("^") {
+            return Ok(input.to_string());
+        }
+        let splits: Vec<&str> = input.split(':').collect();
+        let node = splits[0];
+        let slot = if splits.len() == 1 {
+            0
+        } else if let Some(pbnode) = func.node_def.iter().find(|n| n.name == node) {
+            let index = if splits.len() > 2 { splits[2].parse::<usize>()? } else { 0 };
+            self.output_arg_slot(&pbnode.op, splits[1])? + index
+        } else {
+            // function argument, "arg:index"
+            splits[1].parse::<usize>()?
+        };
+        Ok(if slot == 0 { node.to_string() } else { format!("{}:{}", node, slot) })
+    }
+
+    // First slot of a named output argument. Only ops with several output
+    // arguments need to be known here, as these arguments are assumed to be
+    // single tensors. Other ops are assumed to have a single output argument.
+    fn output_arg_slot(&self, op: &str, arg: &str) -> TractResult<usize> {
+        let args: Vec<&str> = match op {
+            "FusedBatchNorm" | "FusedBatchNormV2" | "FusedBatchNormV3" => vec![
+                "y",
+                "batch_mean",
+                "batch_variance",
+                "reserve_space_1",
+                "reserve_space_2",
+                "reserve_space_3",
+            ],
+            "Merge" => vec!["output", "value_index"],
+            "Switch" => vec!["output_false", "output_true"],
+            "TopKV2" => vec!["values", "indices"],
+            "Unique" => vec!["y", "idx"],
+            _ => self
+                .function(op)
+                .and_then(|f| f.signature.as_ref())
+                .map(|s| s.output_arg.iter().map(|a| &*a.name).collect())
+                .unwrap_or_default(),
+        };
+        if args.len() == 0 {
+            return Ok(0);
+        }
+        Ok(args
+            .iter()
+            .position(|a| *a == arg)
+            .ok_or_else(|| format!("{} has no output argument named {}", op, arg))?)
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct TfOpRegister(
     pub HashMap<String, fn(&ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>>>,
@@ -62,7 +181,7 @@ impl Tensorflow {
     // "src_output" indicating which output tensor to use from "node". If
     // "src_output" is 0 the ":0" suffix can be omitted. Regular inputs may
     // optionally be followed by control inputs that have the format "^node".
-    fn parse_input(i: &str) -> TractResult<(&str, usize)> {
+    pub(crate) fn parse_input(i: &str) -> TractResult<(&str, usize)> {
         let pair = if i.starts_with("^") {
             (&i[1..], 0)
         } else {
@@ -109,18 +228,30 @@ impl Tensorflow {
     }
 
     /// Convenience method: will read the first model in the saved model
-    /// container. Use open_avec_model for more control.
+    /// container. Use open_saved_model_dir for more control.
     pub fn read_saved_model(&self, r: &mut dyn std::io::Read) -> TractResult<GraphDef> {
         let mut saved = self.open_saved_model(r)?;
         Ok(saved.meta_graphs.remove(0).graph_def.unwrap())
     }
 
     pub fn parse_graph(&self, graph: &GraphDef) -> TractResult<TfModelAndExtensions> {
+        let context = ParsingContext {
+            framework: self,
+            library: graph.library.as_ref(),
+            node_output_arities: HashMap::new(),
+        };
+        self.parse_graph_with_context(graph, context)
+    }
+
+    fn parse_graph_with_context(
+        &self,
+        graph: &GraphDef,
+        mut context: ParsingContext,
+    ) -> TractResult<TfModelAndExtensions> {
         use crate::ops::control_flow as cf;
 
         let mut model = InferenceModel::default();
         let mut inputs = tvec!();
-        let mut context = ParsingContext::default();
         let mut control_inputs = vec![];
 
         // compute min output arity for all nodes
@@ -146,6 +277,9 @@ impl Tensorflow {
 
             let op = match self.op_register.0.get(&pbnode.op) {
                 Some(builder) => (builder)(&context, pbnode)?,
+                None if context.function(&pbnode.op).is_some() => {
+                    crate::ops::functional::call(&context, &pbnode.op)?
+                }
                 None => tract_hir::ops::unimpl::UnimplementedOp::new(
                     context.node_output_arities.get(name).cloned().unwrap_or(1),
                     &pbnode.op,
@@ -160,8 +294,10 @@ impl Tensorflow {
 
             let node_id = model.add_node(name.clone(), op, facts)?;
             if pbnode.op == "Placeholder" {
-                let dt = pbnode.get_attr_datum_type("dtype")?;
-                let mut fact = InferenceFact::dt(dt);
+                let mut fact = InferenceFact::default();
+                if let Some(dt) = pbnode.get_attr_opt_datum_type("dtype")? {
+                    fact = fact.with_datum_type(dt);
+                }
                 if let Some(shape) = pbnode.get_attr_opt_shape("shape")? {
                     let shape_factoid = ShapeFactoid::closed(
                         shape
@@ -249,4 +385,31 @@ impl Framework<GraphDef> for Tensorflow {
     fn model_for_proto_model(&self, graph: &GraphDef) -> TractResult<InferenceModel> {
         Ok(self.parse_graph(graph)?.0)
     }
+
+    /// A directory is loaded as a SavedModel, using the "serve" meta graph
+    /// and the default signature.
+    fn model_for_path(&self, p: impl AsRef<path::Path>) -> TractResult<InferenceModel> {
+        if p.as_ref().is_dir() {
+            let saved = self.open_saved_model_dir(p, &["serve"])?;
+            Ok(self.model_for_saved_model(&saved, None)?.0)
+        } else {
+            self.model_for_proto_model(&self.proto_model_for_path(p)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_arg_slots() {
+        let tf = crate::tensorflow();
+        let ctx =
+            ParsingContext { framework: &tf, library: None, node_output_arities: HashMap::new() };
+        assert_eq!(ctx.output_arg_slot("TopKV2", "indices").unwrap(), 1);
+        assert_eq!(ctx.output_arg_slot("AddV2", "z").unwrap(), 0);
+        assert!(ctx.output_arg_slot("TopKV2", "idx").is_err());
+        assert_eq!(ctx.function_input(&FunctionDef::default(), "x:1").unwrap(), "x:1");
+    }
 }
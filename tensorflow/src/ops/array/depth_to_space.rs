@@ -0,0 +1,82 @@
+use tract_hir::internal::*;
+use tract_hir::ops::nn::DataFormat;
+
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn depth_to_space(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let block_size = pb.get_attr_int("block_size")?;
+    if crate::ops::nn::data_format(pb)? != DataFormat::NHWC {
+        bail!("DepthToSpace is only supported for NHWC data format")
+    }
+    Ok(expand(DepthToSpace::new(block_size)))
+}
+
+/// Move blocks of channels to blocks of space: the channel axis of a NHWC
+/// tensor is split in (row in block, column in block, channel) and the
+/// blocks are interleaved with the rows and columns.
+#[derive(Debug, Clone, new, Hash)]
+pub struct DepthToSpace {
+    block_size: usize,
+}
+
+tract_linalg::impl_dyn_hash!(DepthToSpace);
+
+impl Expansion for DepthToSpace {
+    fn name(&self) -> Cow<str> {
+        "DepthToSpace".into()
+    }
+
+    op_tf!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].rank, 4)?;
+        s.equals(&outputs[0].rank, 4)?;
+        let b = self.block_size;
+        s.equals(&outputs[0].shape[0], &inputs[0].shape[0])?;
+        s.equals(&outputs[0].shape[1], b as i32 * inputs[0].shape[1].bex())?;
+        s.equals(&outputs[0].shape[2], b as i32 * inputs[0].shape[2].bex())?;
+        s.given(&inputs[0].shape[3], move |s, c| s.equals(&outputs[0].shape[3], c / (b * b)))
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let shape = target.outlet_fact(inputs[0])?.shape.to_tvec();
+        let b = self.block_size.to_dim();
+        let c = shape[3].to_integer()? as usize;
+        if c % (self.block_size * self.block_size) != 0 {
+            bail!("DepthToSpace: {} channels for blocks of size {}", c, self.block_size)
+        }
+        let split = AxisOp::Reshape(
+            3,
+            tvec!(c.to_dim()),
+            tvec!(b.clone(), b.clone(), (c / (self.block_size * self.block_size)).to_dim()),
+        );
+        let mut wire = target.wire_node(format!("{}.split-channels", prefix), split, inputs)?;
+        wire = target.wire_node(format!("{}.interleave", prefix), AxisOp::Move(3, 2), &wire)?;
+        let merge_w = AxisOp::Reshape(
+            3,
+            tvec!(shape[2].clone(), b.clone()),
+            tvec!(shape[2].clone() * self.block_size),
+        );
+        wire = target.wire_node(format!("{}.merge-w", prefix), merge_w, &wire)?;
+        let merge_h = AxisOp::Reshape(
+            1,
+            tvec!(shape[1].clone(), b.clone()),
+            tvec!(shape[1].clone() * self.block_size),
+        );
+        target.wire_node(prefix, merge_h, &wire)
+    }
+}
@@ -1,36 +1,59 @@
 use crate::model::TfOpRegister;
 use tract_hir::internal::*;
+use tract_hir::ops::array::ScatterReduction;
 
 use crate::model::ParsingContext;
 use crate::tfpb::tensorflow::NodeDef;
 
 mod concatv2;
+mod depth_to_space;
 mod expand_dims;
 mod fill;
-mod gather;
 mod gather_v2;
+mod mirror_pad;
+mod one_hot;
 mod pack;
 mod pad;
 mod range;
+mod scatter_nd;
+mod split;
 mod squeeze;
+mod topk;
 mod transpose;
+mod unpack;
 
 pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("ConcatV2", concatv2::build);
+    reg.insert("DepthToSpace", depth_to_space::depth_to_space);
     reg.insert("ExpandDims", expand_dims::build);
     reg.insert("Fill", fill::fill);
-    reg.insert("GatherNd", gather::gather_nd);
+    reg.insert("GatherNd", |_, _| Ok(Box::new(tract_hir::ops::array::GatherNd::new(0))));
     reg.insert("GatherV2", gather_v2::gather_v2);
+    reg.insert("MirrorPad", mirror_pad::mirror_pad);
+    reg.insert("OneHot", one_hot::one_hot);
     reg.insert("Pack", pack::pack);
     reg.insert("Pad", pad::pad);
     reg.insert("Range", range::range);
     reg.insert("Reshape", |_, _| Ok(expand(tract_hir::ops::array::Reshape::new())));
+    reg.insert("ScatterNd", scatter_nd::scatter_nd);
     reg.insert("Shape", |_, _| Ok(expand(tract_hir::ops::array::Shape::new(DatumType::I32))));
     reg.insert("Slice", slice);
+    reg.insert("Split", split::split);
+    reg.insert("SplitV", split::split_v);
     reg.insert("Squeeze", squeeze::squeeze);
     reg.insert("StridedSlice", strided_slice);
+    reg.insert("TensorScatterAdd", |_, _| Ok(tensor_scatter(ScatterReduction::Add)));
+    reg.insert("TensorScatterMax", |_, _| Ok(tensor_scatter(ScatterReduction::Max)));
+    reg.insert("TensorScatterMin", |_, _| Ok(tensor_scatter(ScatterReduction::Min)));
+    reg.insert("TensorScatterUpdate", |_, _| Ok(tensor_scatter(ScatterReduction::None)));
     reg.insert("Tile", |_, _| Ok(expand(::tract_hir::ops::array::Tile)));
+    reg.insert("TopKV2", topk::topk_v2);
     reg.insert("Transpose", transpose::transpose);
+    reg.insert("Unpack", unpack::unpack);
+}
+
+fn tensor_scatter(reduction: ScatterReduction) -> Box<dyn InferenceOp> {
+    Box::new(tract_hir::ops::array::ScatterNd::new(reduction))
 }
 
 fn strided_slice(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
@@ -0,0 +1,78 @@
+use tract_hir::internal::*;
+
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn one_hot(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let axis = pb.get_attr_opt_int("axis")?.unwrap_or(-1);
+    Ok(expand(OneHot::new(axis)))
+}
+
+/// One-hot encoding. Inputs are the indices, the depth, then the on and off
+/// values, all but the indices expected to be constants.
+#[derive(Debug, Clone, new, Hash)]
+pub struct OneHot {
+    axis: i64,
+}
+
+tract_linalg::impl_dyn_hash!(OneHot);
+
+impl OneHot {
+    fn resolve_axis(&self, indices_rank: usize) -> usize {
+        if self.axis < 0 {
+            (self.axis + indices_rank as i64 + 1) as usize
+        } else {
+            self.axis as usize
+        }
+    }
+}
+
+impl Expansion for OneHot {
+    fn name(&self) -> Cow<str> {
+        "OneHot".into()
+    }
+
+    op_tf!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 4)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[2].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[3].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[1].rank, 0)?;
+        s.equals(&inputs[2].rank, 0)?;
+        s.equals(&inputs[3].rank, 0)?;
+        s.equals(inputs[0].rank.bex() + 1, &outputs[0].rank)?;
+        s.given_2(&inputs[0].shape, &inputs[1].value, move |s, shape, depth| {
+            let depth = *depth.cast_to::<i64>()?.to_scalar::<i64>()? as usize;
+            let mut shape = shape.clone();
+            shape.insert(self.resolve_axis(shape.len()), depth.to_dim());
+            s.equals(&outputs[0].shape, shape)
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let konst = |ix: usize| -> TractResult<Arc<Tensor>> {
+            target
+                .outlet_fact(inputs[ix])?
+                .konst
+                .clone()
+                .ok_or_else(|| format!("OneHot expects input {} to be const", ix).into())
+        };
+        let depth = *konst(1)?.cast_to::<i64>()?.to_scalar::<i64>()? as usize;
+        let (on, off) = (konst(2)?, konst(3)?);
+        let axis = self.resolve_axis(target.outlet_fact(inputs[0])?.rank());
+        let op = tract_hir::tract_core::ops::array::OneHot::new(axis, depth, off, on);
+        target.wire_node(prefix, op, &inputs[0..1])
+    }
+}
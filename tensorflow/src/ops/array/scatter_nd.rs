@@ -0,0 +1,66 @@
+use tract_hir::internal::*;
+use tract_hir::ops::array::{ScatterNd as TypedScatterNd, ScatterReduction};
+
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn scatter_nd(_ctx: &ParsingContext, _pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    Ok(expand(ScatterNd))
+}
+
+/// Scatter updates in a zero tensor of the given shape, summing the updates
+/// for duplicate indices.
+#[derive(Debug, Clone, Hash)]
+pub struct ScatterNd;
+
+tract_linalg::impl_dyn_hash!(ScatterNd);
+
+impl Expansion for ScatterNd {
+    fn name(&self) -> Cow<str> {
+        "ScatterNd".into()
+    }
+
+    op_tf!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 3)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, &inputs[1].datum_type)?;
+        s.equals(&inputs[2].rank, 1)?;
+        s.equals(outputs[0].rank.bex().to_dim(), &inputs[2].shape[0])?;
+        s.given(&inputs[2].value, move |s, shape| {
+            let shape = shape.cast_to::<TDim>()?;
+            s.equals(&outputs[0].shape, ShapeFactoid::from(shape.as_slice::<TDim>()?.to_vec()))
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let shape = if let Some(shape) = &model.outlet_fact(inputs[2])?.konst {
+            shape
+                .cast_to::<i64>()?
+                .as_slice::<i64>()?
+                .iter()
+                .map(|&d| d as usize)
+                .collect::<Vec<_>>()
+        } else {
+            bail!("ScatterNd: shape input must be a constant")
+        };
+        let dt = model.outlet_fact(inputs[1])?.datum_type;
+        let zeros = model.add_const(format!("{}.zeros", prefix), Tensor::zero_dt(dt, &shape)?)?;
+        model.wire_node(
+            prefix,
+            TypedScatterNd::new(ScatterReduction::Add),
+            &[zeros, inputs[0], inputs[1]],
+        )
+    }
+}
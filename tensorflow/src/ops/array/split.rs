@@ -0,0 +1,197 @@
+use tract_hir::internal::*;
+
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn split(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let num_split = pb.get_attr_int("num_split")?;
+    Ok(expand(Split::new(num_split)))
+}
+
+pub fn split_v(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let num_split = pb.get_attr_int("num_split")?;
+    Ok(expand(SplitV::new(num_split)))
+}
+
+fn resolve_axis(axis: &Tensor, rank: usize) -> TractResult<usize> {
+    let axis = *axis.cast_to::<i64>()?.to_scalar::<i64>()?;
+    let axis = if axis < 0 { axis + rank as i64 } else { axis };
+    if axis < 0 || axis >= rank as i64 {
+        bail!("Invalid split axis {} for rank {}", axis, rank)
+    }
+    Ok(axis as usize)
+}
+
+/// Split in `num_split` even parts. Inputs are the axis, then the tensor.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Split {
+    num_split: usize,
+}
+
+tract_linalg::impl_dyn_hash!(Split);
+
+impl Expansion for Split {
+    fn name(&self) -> Cow<str> {
+        "Split".into()
+    }
+
+    op_tf!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 2)?;
+        check_output_arity(&outputs, self.num_split)?;
+        for output in outputs {
+            s.equals(&inputs[1].datum_type, &output.datum_type)?;
+            s.equals(&inputs[1].rank, &output.rank)?;
+        }
+        s.equals(&inputs[0].rank, 0)?;
+        s.given_2(&inputs[1].shape, &inputs[0].value, move |s, shape, axis| {
+            let axis = resolve_axis(&axis, shape.len())?;
+            for output in outputs {
+                let mut shape = shape.clone();
+                shape[axis] = shape[axis].clone() / self.num_split;
+                s.equals(&output.shape, shape)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.num_split)
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        if let Some(ref axis) = target.outlet_fact(inputs[0])?.konst {
+            let axis = resolve_axis(axis, target.outlet_fact(inputs[1])?.rank())?;
+            let op = tract_hir::ops::array::Split::new(axis as isize, self.num_split, None);
+            op.wire(prefix, target, &inputs[1..2])
+        } else {
+            bail!("Need split axis to be const")
+        }
+    }
+}
+
+/// Split in `num_split` parts of the given sizes, one of which may be -1.
+/// Inputs are the tensor, the sizes, then the axis.
+#[derive(Debug, Clone, new, Hash)]
+pub struct SplitV {
+    num_split: usize,
+}
+
+tract_linalg::impl_dyn_hash!(SplitV);
+
+impl SplitV {
+    fn sizes(&self, dim: &TDim, sizes: &Tensor) -> TractResult<Vec<usize>> {
+        let sizes = sizes.cast_to::<i64>()?;
+        let sizes = sizes.as_slice::<i64>()?;
+        if sizes.len() != self.num_split {
+            bail!("SplitV: {} sizes for {} outputs", sizes.len(), self.num_split)
+        }
+        if sizes.iter().filter(|&&s| s < 0).count() > 1 {
+            bail!("SplitV: only one size can be -1, got {:?}", sizes)
+        }
+        let known: i64 = sizes.iter().filter(|&&s| s >= 0).sum();
+        let dim = dim.to_integer()? as i64;
+        let rest = dim - known;
+        if rest < 0 || (rest > 0 && sizes.iter().all(|&s| s >= 0)) {
+            bail!("SplitV: sizes {:?} do not split an axis of length {}", sizes, dim)
+        }
+        Ok(sizes.iter().map(|&s| if s < 0 { rest } else { s } as usize).collect())
+    }
+}
+
+impl Expansion for SplitV {
+    fn name(&self) -> Cow<str> {
+        "SplitV".into()
+    }
+
+    op_tf!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 3)?;
+        check_output_arity(&outputs, self.num_split)?;
+        for output in outputs {
+            s.equals(&inputs[0].datum_type, &output.datum_type)?;
+            s.equals(&inputs[0].rank, &output.rank)?;
+        }
+        s.equals(&inputs[1].rank, 1)?;
+        s.equals(&inputs[2].rank, 0)?;
+        s.given_3(
+            &inputs[0].shape,
+            &inputs[1].value,
+            &inputs[2].value,
+            move |s, shape, sizes, axis| {
+                let axis = resolve_axis(&axis, shape.len())?;
+                if let Ok(sizes) = self.sizes(&shape[axis], &sizes) {
+                    for (output, size) in outputs.iter().zip(sizes.into_iter()) {
+                        let mut shape = shape.clone();
+                        shape[axis] = size.to_dim();
+                        s.equals(&output.shape, shape)?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.num_split)
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let input = target.outlet_fact(inputs[0])?.clone();
+        if let (Some(sizes), Some(axis)) =
+            (&target.outlet_fact(inputs[1])?.konst, &target.outlet_fact(inputs[2])?.konst)
+        {
+            let axis = resolve_axis(axis, input.rank())?;
+            let sizes = self.sizes(&input.shape.dim(axis), sizes)?;
+            let op = tract_hir::ops::array::Split::new(axis as isize, self.num_split, Some(sizes));
+            op.wire(prefix, target, &inputs[0..1])
+        } else {
+            bail!("Need split sizes and axis to be const")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sizes(dim: usize, sizes: &[i64]) -> TractResult<Vec<usize>> {
+        SplitV::new(sizes.len()).sizes(&dim.to_dim(), &tensor1(sizes))
+    }
+
+    #[test]
+    fn split_v_sizes() {
+        assert_eq!(sizes(5, &[2, 3]).unwrap(), vec![2, 3]);
+        assert_eq!(sizes(5, &[1, -1, 1]).unwrap(), vec![1, 3, 1]);
+        assert_eq!(sizes(5, &[5, -1]).unwrap(), vec![5, 0]);
+    }
+
+    #[test]
+    fn split_v_invalid_sizes() {
+        assert!(sizes(5, &[4, 3, -1]).is_err());
+        assert!(sizes(5, &[-1, -1]).is_err());
+        assert!(sizes(5, &[2, 2]).is_err());
+    }
+}
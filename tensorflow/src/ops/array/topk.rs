@@ -0,0 +1,51 @@
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+use tract_hir::internal::*;
+use tract_hir::ops::array::TopK;
+
+pub fn topk_v2(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    if !pb.get_attr_opt_bool("sorted")?.unwrap_or(true) {
+        bail!("TopKV2 {}: sorted=false is not supported", pb.name)
+    }
+    Ok(expand(TopK::new(-1, true, None, DatumType::I32)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tfpb;
+    use crate::tfpb::tensorflow::DataType::{DtFloat, DtInt32};
+    use crate::tfpb::tensorflow::{GraphDef, TensorProto};
+    use std::convert::TryInto;
+    use tract_hir::internal::*;
+
+    fn graph(sorted: Option<bool>) -> GraphDef {
+        let k: TensorProto = (&tensor0(2i32)).try_into().unwrap();
+        let mut topk = tfpb::node().name("topk").op("TopKV2").input("x").input("k");
+        if let Some(sorted) = sorted {
+            topk = topk.attr("sorted", sorted);
+        }
+        tfpb::graph()
+            .node(tfpb::node().name("x").op("Placeholder").attr("dtype", DtFloat))
+            .node(tfpb::node().name("k").op("Const").attr("dtype", DtInt32).attr("value", k))
+            .node(topk)
+    }
+
+    #[test]
+    fn topk_v2_on_last_axis() {
+        let mut model = crate::tensorflow().parse_graph(&graph(Some(true))).unwrap().0;
+        let topk = model.node_id_by_name("topk").unwrap();
+        model.set_output_outlets(&[OutletId::new(topk, 0), OutletId::new(topk, 1)]).unwrap();
+        let input = tensor2(&[[1f32, 4., 3.], [0., -1., 2.]]);
+        model.set_input_fact(0, InferenceFact::dt_shape_from_tensor(&input)).unwrap();
+        let result =
+            SimplePlan::new(model.into_optimized().unwrap()).unwrap().run(tvec!(input)).unwrap();
+        assert_eq!(result[0], rctensor2(&[[4f32, 3.], [2., 0.]]));
+        assert_eq!(result[1], rctensor2(&[[1i32, 2], [2, 0]]));
+    }
+
+    #[test]
+    fn topk_v2_unsorted_is_rejected() {
+        assert!(crate::tensorflow().parse_graph(&graph(None)).is_ok());
+        assert!(crate::tensorflow().parse_graph(&graph(Some(false))).is_err());
+    }
+}
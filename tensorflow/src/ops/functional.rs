@@ -0,0 +1,748 @@
+use std::collections::HashSet;
+use tract_hir::internal::*;
+use tract_hir::ops;
+use tract_hir::tract_core::ops::control_flow;
+
+use crate::model::ParsingContext;
+use crate::model::TfOpRegister;
+use crate::tfpb::tensorflow::NodeDef;
+
+/// Maximum number of iterations of a While dry run at translation time.
+const MAX_STATIC_TRIP_COUNT: usize = 1 << 16;
+
+pub fn register_all_ops(reg: &mut TfOpRegister) {
+    reg.insert("If", if_);
+    reg.insert("PartitionedCall", partitioned_call);
+    reg.insert("StatefulPartitionedCall", partitioned_call);
+    reg.insert("StatelessIf", if_);
+    reg.insert("StatelessWhile", while_);
+    reg.insert("While", while_);
+}
+
+/// Call to a function of the library.
+pub fn call(ctx: &ParsingContext, name: &str) -> TractResult<Box<dyn InferenceOp>> {
+    Ok(Box::new(PartitionedCall::new(ctx.parse_function(name)?)))
+}
+
+fn partitioned_call(ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    call(ctx, pb.get_attr_func_name("f")?)
+}
+
+fn if_(ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let then_body = ctx.parse_function(pb.get_attr_func_name("then_branch")?)?;
+    let else_body = ctx.parse_function(pb.get_attr_func_name("else_branch")?)?;
+    Ok(Box::new(If::new(then_body, else_body)))
+}
+
+fn while_(ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let cond = ctx.parse_function(pb.get_attr_func_name("cond")?)?;
+    let body = ctx.parse_function(pb.get_attr_func_name("body")?)?;
+    Ok(Box::new(While::new(cond, body)))
+}
+
+/// Unify the facts of the op inputs and outputs with the ones of a body
+/// taking the same inputs and producing the same outputs.
+fn unify_with_body(
+    body: &mut InferenceModel,
+    inputs: &mut [InferenceFact],
+    outputs: &mut [InferenceFact],
+) -> TractResult<bool> {
+    let mut changed = false;
+    for (ix, input) in inputs.iter_mut().enumerate() {
+        changed |= input.unify_with_mut(body.input_fact_mut(ix)?)?;
+    }
+    changed |= body.analyse(false)?;
+    for (ix, output) in outputs.iter_mut().enumerate() {
+        changed |= output.unify_with_mut(body.output_fact_mut(ix)?)?;
+    }
+    Ok(changed)
+}
+
+/// Typed version of a body, its input facts being set from the actual
+/// inputs in the typed model.
+fn typed_body(
+    body: &InferenceModel,
+    target: &TypedModel,
+    inputs: &[OutletId],
+) -> TractResult<TypedModel> {
+    let mut body = body.clone();
+    for (ix, input) in inputs.iter().enumerate() {
+        body.set_input_fact(ix, target.outlet_fact(*input)?.into())?;
+    }
+    body.into_typed()
+}
+
+/// TensorFlow PartitionedCall and StatefulPartitionedCall, as well as
+/// direct calls to library functions.
+///
+/// The function body is inlined in the typed model.
+#[derive(Debug, Clone, new, Hash)]
+pub struct PartitionedCall {
+    pub body: InferenceModel,
+}
+
+tract_linalg::impl_dyn_hash!(PartitionedCall);
+
+impl Op for PartitionedCall {
+    fn name(&self) -> Cow<str> {
+        "PartitionedCall".into()
+    }
+
+    op_tf!();
+    not_a_typed_op!();
+}
+
+impl StatelessOp for PartitionedCall {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let inputs = inputs.into_iter().map(|t| t.into_tensor()).collect();
+        SimplePlan::new(&self.body)?.run(inputs)
+    }
+}
+
+impl InferenceOp for PartitionedCall {
+    fn infer_facts(
+        &mut self,
+        inputs: TVec<&InferenceFact>,
+        outputs: TVec<&InferenceFact>,
+        _observed: TVec<&InferenceFact>,
+    ) -> TractResult<(TVec<InferenceFact>, TVec<InferenceFact>, TVec<InferenceFact>)> {
+        let mut inputs: TVec<InferenceFact> = inputs.into_iter().cloned().collect();
+        let mut outputs: TVec<InferenceFact> = outputs.into_iter().cloned().collect();
+        if inputs.len() != self.body.input_outlets()?.len() {
+            bail!(
+                "Function expects {} inputs, got {}",
+                self.body.input_outlets()?.len(),
+                inputs.len()
+            )
+        }
+        while unify_with_body(&mut self.body, &mut inputs, &mut outputs)? {}
+        Ok((inputs, outputs, tvec!()))
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let inputs: TVec<OutletId> = node.inputs.iter().map(|i| mapping[i]).collect();
+        let body = typed_body(&self.body, target, &inputs)?;
+        control_flow::wire_body(&node.name, &body, target, &inputs)
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.body.output_outlets()?.len())
+    }
+
+    as_op!();
+}
+
+/// TensorFlow If and StatelessIf.
+///
+/// Input 0 is the condition, both branches take all the other inputs. When
+/// the condition is known at translation time, only the selected branch is
+/// wired in the typed model. Otherwise, it translates to a core If.
+#[derive(Debug, Clone, new, Hash)]
+pub struct If {
+    pub then_body: InferenceModel,
+    pub else_body: InferenceModel,
+}
+
+tract_linalg::impl_dyn_hash!(If);
+
+impl If {
+    fn branch(&self, cond: &Tensor) -> TractResult<&InferenceModel> {
+        if cond.cast_to_scalar::<bool>()? {
+            Ok(&self.then_body)
+        } else {
+            Ok(&self.else_body)
+        }
+    }
+}
+
+impl Op for If {
+    fn name(&self) -> Cow<str> {
+        "If".into()
+    }
+
+    op_tf!();
+    not_a_typed_op!();
+}
+
+impl StatelessOp for If {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let body = self.branch(&inputs[0])?;
+        let body_inputs = inputs[1..].iter().map(|t| t.clone().into_tensor()).collect();
+        SimplePlan::new(body)?.run(body_inputs)
+    }
+}
+
+impl InferenceOp for If {
+    fn infer_facts(
+        &mut self,
+        inputs: TVec<&InferenceFact>,
+        outputs: TVec<&InferenceFact>,
+        _observed: TVec<&InferenceFact>,
+    ) -> TractResult<(TVec<InferenceFact>, TVec<InferenceFact>, TVec<InferenceFact>)> {
+        let mut inputs: TVec<InferenceFact> = inputs.into_iter().cloned().collect();
+        let mut outputs: TVec<InferenceFact> = outputs.into_iter().cloned().collect();
+        loop {
+            let mut changed = false;
+            for body in &mut [&mut self.then_body, &mut self.else_body] {
+                for (ix, input) in inputs[1..].iter_mut().enumerate() {
+                    changed |= input.unify_with_mut(body.input_fact_mut(ix)?)?;
+                }
+                changed |= body.analyse(false)?;
+            }
+            for (ix, output) in outputs.iter_mut().enumerate() {
+                if let Some(cond) = inputs[0].value.concretize() {
+                    changed |= output.unify_with(self.branch(&cond)?.output_fact(ix)?)?;
+                } else {
+                    let then_fact = self.then_body.output_fact(ix)?;
+                    let else_fact = self.else_body.output_fact(ix)?;
+                    changed |= output.datum_type.unify_with(&then_fact.datum_type)?;
+                    changed |= output.datum_type.unify_with(&else_fact.datum_type)?;
+                    if then_fact.shape == else_fact.shape {
+                        changed |= output.shape.unify_with(&then_fact.shape)?;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        Ok((inputs, outputs, tvec!()))
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let inputs: TVec<OutletId> = node.inputs.iter().map(|i| mapping[i]).collect();
+        if let Some(cond) = target.outlet_fact(inputs[0])?.konst.clone() {
+            let body = typed_body(self.branch(&cond)?, target, &inputs[1..])?;
+            control_flow::wire_body(&node.name, &body, target, &inputs[1..])
+        } else {
+            let input_mapping: Vec<usize> = (1..inputs.len()).collect();
+            let op = control_flow::If::new(
+                typed_body(&self.then_body, target, &inputs[1..])?,
+                input_mapping.clone(),
+                typed_body(&self.else_body, target, &inputs[1..])?,
+                input_mapping,
+                node.name.clone(),
+            );
+            target.wire_node(&*node.name, op, &inputs)
+        }
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.then_body.output_outlets()?.len())
+    }
+
+    as_op!();
+}
+
+/// TensorFlow While and StatelessWhile.
+///
+/// The `cond` function computes the condition from the loop variables, the
+/// `body` function their next values. Loop variables must keep the same type
+/// and shape across iterations.
+///
+/// When the condition only depends on loop variables with constant initial
+/// values whose updates only depend on each other, the trip count is computed
+/// by a dry run at translation time and the loop translates to a Scan.
+/// Otherwise, it translates to a core Loop.
+#[derive(Debug, Clone, new, Hash)]
+pub struct While {
+    pub cond: InferenceModel,
+    pub body: InferenceModel,
+}
+
+tract_linalg::impl_dyn_hash!(While);
+
+impl While {
+    /// Typed versions of cond and body for the given loop variables.
+    fn typed_bodies(
+        &self,
+        target: &TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<(TypedModel, TypedModel)> {
+        let mut cond = self.cond.clone();
+        let mut body = self.body.clone();
+        for (ix, input) in inputs.iter().enumerate() {
+            // values change across iterations, only keep type and shape
+            let fact = target.outlet_fact(*input)?;
+            let fact = InferenceFact::dt_shape(fact.datum_type, fact.shape.iter());
+            cond.set_input_fact(ix, fact.clone())?;
+            body.set_input_fact(ix, fact)?;
+        }
+        Ok((cond.into_typed()?.declutter()?, body.into_typed()?.declutter()?))
+    }
+
+    fn static_trip_count(
+        cond: &TypedModel,
+        body: &TypedModel,
+        initial: &[Option<Arc<Tensor>>],
+    ) -> TractResult<Option<usize>> {
+        let cond_output = cond.output_outlets()?[0];
+        let body_outputs = body.output_outlets()?;
+        let mut vars = input_dependencies(cond, cond_output)?;
+        let mut ix = 0;
+        while ix < vars.len() {
+            for dep in input_dependencies(body, body_outputs[vars[ix]])? {
+                if !vars.contains(&dep) {
+                    vars.push(dep);
+                }
+            }
+            ix += 1;
+        }
+        let mut values = if let Some(values) =
+            vars.iter().map(|&v| initial[v].clone()).collect::<Option<Vec<_>>>()
+        {
+            values
+        } else {
+            return Ok(None);
+        };
+        let mut cond_state = SimpleState::new(SimplePlan::new(cond)?)?;
+        let mut body_state = SimpleState::new(SimplePlan::new(body)?)?;
+        for trip_count in 0..MAX_STATIC_TRIP_COUNT {
+            for (&v, value) in vars.iter().zip(values.iter()) {
+                cond_state.set_input(v, value.clone().into_tensor())?;
+            }
+            let running = cond_state.compute_recursively(cond_output.node)?[cond_output.slot]
+                .cast_to_scalar::<bool>()?;
+            cond_state.reset_wires()?;
+            if !running {
+                return Ok(Some(trip_count));
+            }
+            for (&v, value) in vars.iter().zip(values.iter()) {
+                body_state.set_input(v, value.clone().into_tensor())?;
+            }
+            values = vars
+                .iter()
+                .map(|&v| {
+                    let outlet = body_outputs[v];
+                    Ok(body_state.compute_recursively(outlet.node)?[outlet.slot].clone())
+                })
+                .collect::<TractResult<_>>()?;
+            body_state.reset_wires()?;
+        }
+        Ok(None)
+    }
+
+    /// Wire the cond function, casting its output to a boolean.
+    fn wire_cond(
+        prefix: &str,
+        cond: &TypedModel,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<OutletId> {
+        let wire = control_flow::wire_body(prefix, cond, target, inputs)?[0];
+        if target.outlet_fact(wire)?.datum_type == bool::datum_type() {
+            Ok(wire)
+        } else {
+            Ok(target.wire_node(
+                format!("{}.cast", prefix),
+                tract_hir::ops::cast(bool::datum_type()),
+                &[wire],
+            )?[0])
+        }
+    }
+
+    /// Wire the loop as a Scan over a constant tensor of the iteration numbers.
+    fn wire_as_scan(
+        name: &str,
+        body: &TypedModel,
+        trip_count: usize,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let mut scan_body = TypedModel::default();
+        let mut states = tvec!();
+        for ix in 0..inputs.len() {
+            let fact = body.input_fact(ix)?;
+            let fact = TypedFact::dt_shape(fact.datum_type, fact.shape.clone())?;
+            states.push(scan_body.add_source(format!("{}.state-{}", name, ix), fact)?);
+        }
+        scan_body.add_source(
+            format!("{}.iter", name),
+            TypedFact::dt_shape(i64::datum_type(), [1].as_ref())?,
+        )?;
+        let outputs = control_flow::wire_body(name, body, &mut scan_body, &states)?;
+        scan_body.set_output_outlets(&outputs)?;
+
+        let mut input_mapping = vec![];
+        let mut output_mapping = vec![];
+        for ix in 0..inputs.len() {
+            input_mapping.push(ops::scan::InputMapping::State {
+                initializer: ops::scan::StateInitializer::FromInput(ix),
+            });
+            output_mapping.push(ops::scan::OutputMapping {
+                state: true,
+                last_value_slot: Some(ix),
+                full_slot: None,
+                axis: 0,
+                chunk: 1.to_dim(),
+                full_dim_hint: None,
+            });
+        }
+        input_mapping.push(ops::scan::InputMapping::Scan {
+            slot: inputs.len(),
+            axis: 0,
+            chunk: 1.to_dim(),
+        });
+        let mut scan_inputs: TVec<OutletId> = inputs.into();
+        let iters = tensor1(&(0..trip_count as i64).collect::<Vec<_>>());
+        scan_inputs.push(target.add_const(format!("{}.iters", name), iters)?);
+        let op = ops::scan::Scan::new(scan_body, input_mapping, output_mapping, None, false)?;
+        target.wire_node(name, op, &scan_inputs)
+    }
+
+    /// Wire the loop as a core Loop, the body computing the next condition
+    /// from the next values of the loop variables.
+    fn wire_as_loop(
+        name: &str,
+        cond: &TypedModel,
+        body: &TypedModel,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let mut loop_body = TypedModel::default();
+        loop_body
+            .add_source(format!("{}.iter", name), TypedFact::dt_shape(i64::datum_type(), ())?)?;
+        loop_body
+            .add_source(format!("{}.cond", name), TypedFact::dt_shape(bool::datum_type(), ())?)?;
+        let mut vars = tvec!();
+        for ix in 0..inputs.len() {
+            let fact = body.input_fact(ix)?;
+            let fact = TypedFact::dt_shape(fact.datum_type, fact.shape.clone())?;
+            vars.push(loop_body.add_source(format!("{}.var-{}", name, ix), fact)?);
+        }
+        let next = control_flow::wire_body(&format!("{}.body", name), body, &mut loop_body, &vars)?;
+        let next_cond = Self::wire_cond(&format!("{}.cond", name), cond, &mut loop_body, &next)?;
+        let mut outputs = tvec!(next_cond);
+        outputs.extend(next.iter().cloned());
+        loop_body.set_output_outlets(&outputs)?;
+
+        let max_trip_count =
+            target.add_const(format!("{}.max_trip_count", name), tensor0(i64::max_value()))?;
+        let initial_cond =
+            Self::wire_cond(&format!("{}.initial_cond", name), cond, target, inputs)?;
+        let mut loop_inputs = tvec!(max_trip_count, initial_cond);
+        loop_inputs.extend(inputs.iter().cloned());
+        let iters = format!("{}.iters", name);
+        let op = control_flow::Loop::new(loop_body, inputs.len(), TDim::sym(iters));
+        target.wire_node(name, op, &loop_inputs)
+    }
+}
+
+/// Indices of the model inputs `outlet` depends on.
+fn input_dependencies(model: &TypedModel, outlet: OutletId) -> TractResult<Vec<usize>> {
+    let inputs = model.input_outlets()?;
+    let mut deps = vec![];
+    let mut visited = HashSet::new();
+    let mut todo = vec![outlet.node];
+    while let Some(node) = todo.pop() {
+        if !visited.insert(node) {
+            continue;
+        }
+        if let Some(ix) = inputs.iter().position(|i| i.node == node) {
+            deps.push(ix);
+        }
+        todo.extend(model.node(node).inputs.iter().map(|i| i.node));
+    }
+    deps.sort();
+    Ok(deps)
+}
+
+impl Op for While {
+    fn name(&self) -> Cow<str> {
+        "While".into()
+    }
+
+    op_tf!();
+    not_a_typed_op!();
+}
+
+impl StatelessOp for While {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let cond = SimplePlan::new(&self.cond)?;
+        let body = SimplePlan::new(&self.body)?;
+        let mut vars = inputs;
+        loop {
+            let cond_inputs = vars.iter().map(|t| t.clone().into_tensor()).collect();
+            if !cond.run(cond_inputs)?[0].cast_to_scalar::<bool>()? {
+                return Ok(vars);
+            }
+            vars = body.run(vars.into_iter().map(|t| t.into_tensor()).collect())?;
+        }
+    }
+}
+
+impl InferenceOp for While {
+    fn infer_facts(
+        &mut self,
+        inputs: TVec<&InferenceFact>,
+        outputs: TVec<&InferenceFact>,
+        _observed: TVec<&InferenceFact>,
+    ) -> TractResult<(TVec<InferenceFact>, TVec<InferenceFact>, TVec<InferenceFact>)> {
+        let mut inputs: TVec<InferenceFact> = inputs.into_iter().cloned().collect();
+        let mut outputs: TVec<InferenceFact> = outputs.into_iter().cloned().collect();
+        if inputs.len() != self.body.input_outlets()?.len()
+            || inputs.len() != self.cond.input_outlets()?.len()
+        {
+            bail!("While cond and body must take the {} loop variables", inputs.len())
+        }
+        loop {
+            let mut changed = false;
+            for ix in 0..inputs.len() {
+                let body_input = self.body.input_outlets()?[ix];
+                let body_output = self.body.output_outlets()?[ix];
+                let mut facts = if body_input == body_output {
+                    tvec!(self.body.outlet_fact_mut(body_input)?)
+                } else {
+                    self.body.outlets_fact_mut(&[body_input, body_output])?
+                };
+                facts.push(self.cond.input_fact_mut(ix)?);
+                facts.push(&mut inputs[ix]);
+                facts.push(&mut outputs[ix]);
+                changed |= Factoid::unify_all(
+                    &mut *facts.iter_mut().map(|f| &mut f.datum_type).collect::<TVec<_>>(),
+                )?;
+                changed |= Factoid::unify_all(
+                    &mut *facts.iter_mut().map(|f| &mut f.shape).collect::<TVec<_>>(),
+                )?;
+            }
+            changed |= self.cond.analyse(false)?;
+            changed |= self.body.analyse(false)?;
+            if !changed {
+                break;
+            }
+        }
+        Ok((inputs, outputs, tvec!()))
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let inputs: TVec<OutletId> = node.inputs.iter().map(|i| mapping[i]).collect();
+        let (cond, body) = self.typed_bodies(target, &inputs)?;
+        let initial = inputs
+            .iter()
+            .map(|i| Ok(target.outlet_fact(*i)?.konst.clone()))
+            .collect::<TractResult<Vec<_>>>()?;
+        match Self::static_trip_count(&cond, &body, &initial)? {
+            Some(0) => Ok(inputs),
+            Some(trip_count) => Self::wire_as_scan(&node.name, &body, trip_count, target, &inputs),
+            None => Self::wire_as_loop(&node.name, &cond, &body, target, &inputs),
+        }
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.body.output_outlets()?.len())
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tfpb;
+    use crate::tfpb::tensorflow::attr_value::Value;
+    use crate::tfpb::tensorflow::op_def::ArgDef;
+    use crate::tfpb::tensorflow::DataType::{self, DtBool, DtFloat, DtInt32};
+    use crate::tfpb::tensorflow::{
+        AttrValue, FunctionDef, FunctionDefLibrary, GraphDef, NameAttrList, OpDef, TensorProto,
+    };
+    use std::convert::TryInto;
+    use tract_hir::tract_core::ops::scan::Scan;
+
+    fn func(name: &str) -> AttrValue {
+        AttrValue {
+            value: Some(Value::Func(NameAttrList { name: name.to_string(), attr: HashMap::new() })),
+        }
+    }
+
+    fn konst(name: &str, t: Tensor, dt: DataType) -> NodeDef {
+        let value: TensorProto = (&t).try_into().unwrap();
+        tfpb::node().name(name).op("Const").attr("dtype", dt).attr("value", value)
+    }
+
+    fn binary(name: &str, op: &str, a: &str, b: &str) -> NodeDef {
+        tfpb::node().name(name).op(op).input(a).input(b)
+    }
+
+    fn function(
+        name: &str,
+        inputs: &[(&str, DataType)],
+        outputs: &[(&str, DataType, &str)],
+        node_def: Vec<NodeDef>,
+    ) -> FunctionDef {
+        let arg = |name: &str, dt: DataType| ArgDef {
+            name: name.to_string(),
+            r#type: dt as i32,
+            ..ArgDef::default()
+        };
+        FunctionDef {
+            signature: Some(OpDef {
+                name: name.to_string(),
+                input_arg: inputs.iter().map(|&(name, dt)| arg(name, dt)).collect(),
+                output_arg: outputs.iter().map(|&(name, dt, _)| arg(name, dt)).collect(),
+                ..OpDef::default()
+            }),
+            node_def,
+            ret: outputs
+                .iter()
+                .map(|&(name, _, ret)| (name.to_string(), ret.to_string()))
+                .collect(),
+            ..FunctionDef::default()
+        }
+    }
+
+    fn library() -> FunctionDefLibrary {
+        let function = vec![
+            function(
+                "double",
+                &[("x", DtFloat)],
+                &[("y", DtFloat, "add:z:0")],
+                vec![binary("add", "AddV2", "x", "x")],
+            ),
+            function(
+                "plus_one",
+                &[("x", DtFloat)],
+                &[("y", DtFloat, "add:z:0")],
+                vec![
+                    konst("one", tensor1(&[1f32, 1.]), DtFloat),
+                    binary("add", "AddV2", "x", "one:output:0"),
+                ],
+            ),
+            function("identity", &[("x", DtFloat)], &[("y", DtFloat, "x")], vec![]),
+            function(
+                "less_than_3",
+                &[("i", DtInt32), ("acc", DtFloat)],
+                &[("c", DtBool, "less:z:0")],
+                vec![konst("three", tensor0(3i32), DtInt32), binary("less", "Less", "i", "three")],
+            ),
+            // (i, acc) -> (i + 1, double(acc))
+            function(
+                "step",
+                &[("i", DtInt32), ("acc", DtFloat)],
+                &[("i_next", DtInt32, "inc:z:0"), ("acc_next", DtFloat, "dbl:output:0")],
+                vec![
+                    konst("one", tensor0(1i32), DtInt32),
+                    binary("inc", "AddV2", "i", "one:output:0"),
+                    tfpb::node()
+                        .name("dbl")
+                        .op("StatefulPartitionedCall")
+                        .input("acc")
+                        .attr("f", func("double")),
+                ],
+            ),
+        ];
+        FunctionDefLibrary { function, gradient: vec![] }
+    }
+
+    fn run(graph: GraphDef, output: &str, inputs: TVec<Tensor>) -> TractResult<TVec<Arc<Tensor>>> {
+        let mut graph = graph;
+        graph.library = Some(library());
+        let mut model = crate::tensorflow().parse_graph(&graph)?.0;
+        let (node, slot) = crate::Tensorflow::parse_input(output)?;
+        model.set_output_outlets(&[OutletId::new(model.node_id_by_name(node)?, slot)])?;
+        for (ix, input) in inputs.iter().enumerate() {
+            model.set_input_fact(ix, InferenceFact::dt_shape_from_tensor(input))?;
+        }
+        SimplePlan::new(model.into_optimized()?)?.run(inputs)
+    }
+
+    fn placeholder(name: &str, dt: DataType) -> NodeDef {
+        tfpb::node().name(name).op("Placeholder").attr("dtype", dt)
+    }
+
+    #[test]
+    fn partitioned_call() {
+        let graph = tfpb::graph().node(placeholder("x", DtFloat)).node(
+            tfpb::node().name("call").op("PartitionedCall").input("x").attr("f", func("double")),
+        );
+        let result = run(graph, "call", tvec!(tensor1(&[1f32, 2.]))).unwrap();
+        assert_eq!(result[0], rctensor1(&[2f32, 4.]));
+    }
+
+    #[test]
+    fn direct_function_call() {
+        let graph = tfpb::graph()
+            .node(placeholder("x", DtFloat))
+            .node(tfpb::node().name("call").op("double").input("x"));
+        let result = run(graph, "call", tvec!(tensor1(&[1f32, 2.]))).unwrap();
+        assert_eq!(result[0], rctensor1(&[2f32, 4.]));
+    }
+
+    #[test]
+    fn stateless_if() {
+        let graph =
+            tfpb::graph().node(placeholder("cond", DtBool)).node(placeholder("x", DtFloat)).node(
+                tfpb::node()
+                    .name("if")
+                    .op("StatelessIf")
+                    .input("cond")
+                    .input("x")
+                    .attr("then_branch", func("plus_one"))
+                    .attr("else_branch", func("identity")),
+            );
+        let result = run(graph.clone(), "if", tvec!(tensor0(true), tensor1(&[1f32, 2.]))).unwrap();
+        assert_eq!(result[0], rctensor1(&[2f32, 3.]));
+        let result = run(graph, "if", tvec!(tensor0(false), tensor1(&[1f32, 2.]))).unwrap();
+        assert_eq!(result[0], rctensor1(&[1f32, 2.]));
+    }
+
+    fn while_graph(i: NodeDef) -> GraphDef {
+        tfpb::graph().node(i).node(placeholder("acc", DtFloat)).node(
+            tfpb::node()
+                .name("while")
+                .op("StatelessWhile")
+                .input("i")
+                .input("acc")
+                .attr("cond", func("less_than_3"))
+                .attr("body", func("step")),
+        )
+    }
+
+    #[test]
+    fn while_with_static_trip_count() {
+        let mut graph = while_graph(konst("i", tensor0(0i32), DtInt32));
+        graph.library = Some(library());
+        let mut model = crate::tensorflow().parse_graph(&graph).unwrap().0;
+        model.set_output_names(&["while"]).unwrap();
+        model.set_input_fact(0, InferenceFact::dt_shape(f32::datum_type(), tvec!(2))).unwrap();
+        let model = model.into_typed().unwrap();
+        assert!(model.nodes().iter().any(|n| n.op_is::<Scan>()));
+        let result = run(
+            while_graph(konst("i", tensor0(0i32), DtInt32)),
+            "while:1",
+            tvec!(tensor1(&[1f32, 2.])),
+        )
+        .unwrap();
+        assert_eq!(result[0], rctensor1(&[8f32, 16.]));
+    }
+
+    #[test]
+    fn while_with_dynamic_trip_count() {
+        for &(i, factor) in &[(0i32, 8f32), (2, 2.), (5, 1.)] {
+            let result = run(
+                while_graph(placeholder("i", DtInt32)),
+                "while:1",
+                tvec!(tensor0(i), tensor1(&[1f32, 2.])),
+            )
+            .unwrap();
+            assert_eq!(result[0], rctensor1(&[factor, 2. * factor]));
+        }
+    }
+}
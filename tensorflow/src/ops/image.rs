@@ -0,0 +1,123 @@
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::resize::{CoordTransformer, Interpolator, Nearest, Resize};
+
+use crate::model::ParsingContext;
+use crate::model::TfOpRegister;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn register_all_ops(reg: &mut TfOpRegister) {
+    reg.insert("ResizeBilinear", |_, pb| resize(pb, Interpolator::Linear));
+    reg.insert("ResizeNearestNeighbor", |_, pb| resize(pb, Interpolator::Nearest));
+}
+
+fn resize(pb: &NodeDef, interpolator: Interpolator) -> TractResult<Box<dyn InferenceOp>> {
+    let align_corners = pb.get_attr_opt_bool("align_corners")?.unwrap_or(false);
+    let half_pixel_centers = pb.get_attr_opt_bool("half_pixel_centers")?.unwrap_or(false);
+    if align_corners && half_pixel_centers {
+        bail!("align_corners and half_pixel_centers can not be both set")
+    }
+    Ok(expand(ResizeImage::new(interpolator, align_corners, half_pixel_centers)))
+}
+
+/// Resize the spatial axes of a NHWC image to the size given as second
+/// input, which is expected to be const.
+#[derive(Debug, Clone, new, Hash)]
+pub struct ResizeImage {
+    interpolator: Interpolator,
+    align_corners: bool,
+    half_pixel_centers: bool,
+}
+
+tract_linalg::impl_dyn_hash!(ResizeImage);
+
+impl ResizeImage {
+    fn core_op(&self) -> Resize {
+        let linear = self.interpolator == Interpolator::Linear;
+        let (coord_transformer, nearest) = if self.align_corners {
+            (CoordTransformer::AlignCorners, Nearest::RoundPreferCeil)
+        } else if self.half_pixel_centers && linear {
+            (CoordTransformer::HalfPixel, Nearest::Floor)
+        } else if self.half_pixel_centers {
+            (CoordTransformer::TfHalfPixelForNn, Nearest::Floor)
+        } else {
+            (CoordTransformer::Asymmetric, Nearest::Floor)
+        };
+        Resize::new(
+            coord_transformer,
+            self.interpolator,
+            nearest,
+            -0.75,
+            false,
+            0.0,
+            None,
+            None,
+            Some(1),
+        )
+    }
+}
+
+impl Expansion for ResizeImage {
+    fn name(&self) -> Cow<str> {
+        "ResizeImage".into()
+    }
+
+    op_tf!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 2)?;
+        check_output_arity(&outputs, 1)?;
+        if self.interpolator == Interpolator::Nearest {
+            s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        } else {
+            s.equals(&outputs[0].datum_type, f32::datum_type())?;
+        }
+        s.equals(&inputs[0].rank, 4)?;
+        s.equals(&outputs[0].rank, 4)?;
+        s.equals(&inputs[1].rank, 1)?;
+        s.equals(&inputs[1].shape[0], 2.to_dim())?;
+        s.equals(&inputs[0].shape[0], &outputs[0].shape[0])?;
+        s.equals(&inputs[0].shape[3], &outputs[0].shape[3])?;
+        s.given(&inputs[1].value, move |s, size| {
+            let size = size.cast_to::<i64>()?;
+            let size = size.as_slice::<i64>()?;
+            s.equals(&outputs[0].shape[1], size[0].to_dim())?;
+            s.equals(&outputs[0].shape[2], size[1].to_dim())
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let fact = target.outlet_fact(inputs[0])?.clone();
+        let size = if let Some(size) = &target.outlet_fact(inputs[1])?.konst {
+            size.cast_to::<i64>()?.into_owned()
+        } else {
+            bail!("Need resize size to be const")
+        };
+        let size = size.as_slice::<i64>()?;
+        let sizes = tensor1(&[
+            fact.shape.dim(0).to_integer()? as i64,
+            size[0],
+            size[1],
+            fact.shape.dim(3).to_integer()? as i64,
+        ]);
+        let sizes = target.add_const(format!("{}.sizes", prefix), sizes)?;
+        let mut wire = inputs[0];
+        if self.interpolator == Interpolator::Linear && fact.datum_type != f32::datum_type() {
+            wire = target.wire_node(
+                format!("{}.cast", prefix),
+                tract_hir::ops::cast(f32::datum_type()),
+                &[wire],
+            )?[0];
+        }
+        target.wire_node(prefix, self.core_op(), &[wire, sizes])
+    }
+}
@@ -1,5 +1,6 @@
 use tract_hir::internal::*;
 use tract_hir::ops;
+use tract_hir::tract_core::ser::{Attrs, Registry};
 
 use crate::model::ParsingContext;
 use crate::model::TfOpRegister;
@@ -15,9 +16,71 @@ pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("LogicalAnd", |_, _| Ok(ops::logic::And.into_hir()));
     reg.insert("LogicalOr", |_, _| Ok(ops::logic::Or.into_hir()));
     reg.insert("Merge", merge);
+    reg.insert("Select", |_, _| Ok(expand(Select)));
+    reg.insert("SelectV2", |_, _| Ok(Box::new(ops::logic::Iff::default())));
     reg.insert("Switch", |_, _| Ok(Box::new(Switch)));
 }
 
+/// Select picks whole rows of t or e when cond is a vector and they are not,
+/// and elements otherwise. SelectV2 broadcasts like other binary ops.
+#[derive(Debug, Clone, Hash)]
+pub struct Select;
+
+tract_linalg::impl_dyn_hash!(Select);
+
+impl Expansion for Select {
+    fn name(&self) -> Cow<str> {
+        "Select".into()
+    }
+
+    op_tf!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 3)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, bool::datum_type())?;
+        s.equals(&inputs[1].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[2].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[1].shape, &outputs[0].shape)?;
+        s.equals(&inputs[2].shape, &outputs[0].shape)?;
+        s.given_2(&inputs[0].rank, &outputs[0].rank, move |s, cond_rank, rank| {
+            if cond_rank == rank {
+                s.equals(&inputs[0].shape, &outputs[0].shape)
+            } else if cond_rank == 1 {
+                s.equals(&inputs[0].shape[0], &outputs[0].shape[0])
+            } else {
+                bail!("Select condition must be a vector or have the shape of the values")
+            }
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let cond_rank = target.outlet_fact(inputs[0])?.rank();
+        let rank = target.outlet_fact(inputs[1])?.rank();
+        let mut cond = inputs[0];
+        if cond_rank == 1 {
+            for axis in 1..rank {
+                cond = target.wire_node(
+                    format!("{}.cond-add-axis-{}", prefix, axis),
+                    AxisOp::Add(axis),
+                    &[cond],
+                )?[0];
+            }
+        }
+        target.wire_node(prefix, ops::logic::Iff::default(), &[cond, inputs[1], inputs[2]])
+    }
+}
+
 #[derive(Debug, Clone, new, Hash)]
 pub struct Switch;
 
@@ -168,3 +231,11 @@ impl TypedOp for Merge {
         ))
     }
 }
+
+pub fn register_ser_ops(reg: &mut Registry) {
+    reg.register_op::<Merge>(
+        "tf.Merge",
+        |_, op| Ok(Attrs::default().with("n", op.n)),
+        |_, attrs| Ok(Box::new(Merge::new(attrs.get("n")?.as_usize()?))),
+    );
+}
@@ -1,10 +1,12 @@
 use tract_hir::internal::*;
 use tract_hir::ops;
+use tract_hir::tract_core::ops::binary::BinMiniOp;
 
 use crate::model::ParsingContext;
 use crate::model::TfOpRegister;
 use crate::tfpb::tensorflow::NodeDef;
 
+mod arg_max;
 mod reduce;
 
 pub fn register_all_ops(reg: &mut TfOpRegister) {
@@ -12,9 +14,15 @@ pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("Add", |_, _| Ok(ops::math::Add.into_hir()));
     reg.insert("AddN", add_n);
     reg.insert("AddV2", |_, _| Ok(ops::math::Add.into_hir()));
+    reg.insert("ArgMax", |_, pb| arg_max::arg_max_min(pb, true));
+    reg.insert("ArgMin", |_, pb| arg_max::arg_max_min(pb, false));
+    reg.insert("BatchMatMul", batch_mat_mul);
+    reg.insert("BatchMatMulV2", batch_mat_mul);
     reg.insert("BiasAdd", |_, _| Ok(ops::math::Add.into_hir()));
     reg.insert("Ceil", |_, _| Ok(Box::new(ops::math::ceil())));
     reg.insert("Div", |_, _| Ok(ops::math::Div.into_hir()));
+    reg.insert("Einsum", einsum);
+    reg.insert("Exp", |_, _| Ok(Box::new(ops::math::exp())));
     reg.insert("FloorMod", |_, _| Ok(ops::math::Rem.into_hir()));
     reg.insert("MatMul", mat_mul);
     reg.insert("Max", reduce::max);
@@ -31,16 +39,66 @@ pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("Neg", |_, _| Ok(Box::new(ops::math::neg())));
     reg.insert("RealDiv", |_, _| Ok(ops::math::Div.into_hir()));
     reg.insert("Rsqrt", |_, _| Ok(Box::new(ops::math::rsqrt())));
+    reg.insert("Sqrt", |_, _| Ok(Box::new(ops::math::sqrt())));
+    reg.insert("Square", |_, _| Ok(Box::new(ops::math::square())));
+    reg.insert("SquaredDifference", |_, _| Ok(expand(SquaredDifference)));
     reg.insert("Sub", |_, _| Ok(ops::math::Sub.into_hir()));
     reg.insert("Tanh", |_, _| Ok(Box::new(ops::math::tanh())));
 }
 
+pub fn einsum(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    Ok(expand(ops::einsum::Einsum::new(pb.get_attr_str("equation")?)))
+}
+
 pub fn add_n(_ctx: &ParsingContext, _pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
     Ok(Box::new(ops::binary::Nary(Box::new(ops::math::Add), false)))
 }
 
+pub fn batch_mat_mul(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let adj_x = pb.get_attr_opt_bool("adj_x")?.unwrap_or(false);
+    let adj_y = pb.get_attr_opt_bool("adj_y")?.unwrap_or(false);
+    Ok(Box::new(ops::matmul::MatMul::default().with_a_trans(adj_x).with_b_trans(adj_y)))
+}
+
 pub fn mat_mul(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
     let trans_a = pb.get_attr_bool("transpose_a")?;
     let trans_b = pb.get_attr_bool("transpose_b")?;
     Ok(Box::new(ops::matmul::MatMul::default().with_a_trans(trans_a).with_b_trans(trans_b)))
 }
+
+/// (a - b)², as emitted by tf.nn.moments for variances.
+#[derive(Debug, Clone, Hash)]
+struct SquaredDifference;
+
+tract_linalg::impl_dyn_hash!(SquaredDifference);
+
+impl Expansion for SquaredDifference {
+    fn name(&self) -> Cow<str> {
+        "SquaredDifference".into()
+    }
+
+    op_tf!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        ops::binary::rules(s, inputs, outputs, |a, b| ops::math::Sub.result_datum_type(a, b))
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let diff = ops::binary::InferenceBinOp(Box::new(ops::math::Sub)).wire(
+            &format!("{}.diff", prefix),
+            target,
+            inputs,
+        )?;
+        target.wire_node(prefix, ops::math::square(), &diff)
+    }
+}
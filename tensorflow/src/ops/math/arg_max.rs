@@ -0,0 +1,73 @@
+use tract_hir::internal::*;
+use tract_hir::ops::nn::ArgMaxMin;
+
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn arg_max_min(pb: &NodeDef, max: bool) -> TractResult<Box<dyn InferenceOp>> {
+    let output_type = pb.get_attr_opt_datum_type("output_type")?.unwrap_or(DatumType::I64);
+    Ok(expand(ArgMax::new(max, output_type)))
+}
+
+/// ArgMax or ArgMin along the axis given as second input, which is expected
+/// to be const.
+#[derive(Debug, Clone, new, Hash)]
+pub struct ArgMax {
+    max: bool,
+    output_type: DatumType,
+}
+
+tract_linalg::impl_dyn_hash!(ArgMax);
+
+fn resolve_axis(axis: &Tensor, rank: usize) -> TractResult<usize> {
+    let axis = *axis.cast_to::<i64>()?.to_scalar::<i64>()?;
+    let axis = if axis < 0 { axis + rank as i64 } else { axis };
+    if axis < 0 || axis >= rank as i64 {
+        bail!("Invalid axis {} for rank {}", axis, rank)
+    }
+    Ok(axis as usize)
+}
+
+impl Expansion for ArgMax {
+    fn name(&self) -> Cow<str> {
+        if self.max { "ArgMax" } else { "ArgMin" }.into()
+    }
+
+    op_tf!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 2)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, self.output_type)?;
+        s.equals(inputs[0].rank.bex() - 1, &outputs[0].rank)?;
+        s.given_2(&inputs[0].shape, &inputs[1].value, move |s, shape, axis| {
+            let axis = resolve_axis(&axis, shape.len())?;
+            let mut shape = shape.clone();
+            shape.remove(axis);
+            s.equals(&outputs[0].shape, shape)
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let axis = if let Some(axis) = &target.outlet_fact(inputs[1])?.konst {
+            resolve_axis(axis, target.outlet_fact(inputs[0])?.rank())?
+        } else {
+            bail!("Need axis to be const")
+        };
+        let op = ArgMaxMin::new(self.max, axis, false);
+        if self.output_type == DatumType::I64 {
+            return target.wire_node(prefix, op, &inputs[0..1]);
+        }
+        let wire = target.wire_node(format!("{}.arg", prefix), op, &inputs[0..1])?;
+        target.wire_node(prefix, tract_hir::ops::cast(self.output_type), &wire)
+    }
+}
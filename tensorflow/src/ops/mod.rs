@@ -1,4 +1,5 @@
 use tract_hir::internal::*;
+use tract_hir::tract_core::ser::{Attrs, Registry};
 
 use crate::model::ParsingContext;
 use crate::model::TfOpRegister;
@@ -14,6 +15,8 @@ macro_rules! op_tf {
 
 pub mod array;
 pub mod control_flow;
+pub mod functional;
+pub mod image;
 pub mod logic;
 pub mod math;
 pub mod nn;
@@ -25,6 +28,8 @@ pub mod vars;
 pub fn register_all_ops(reg: &mut TfOpRegister) {
     array::register_all_ops(reg);
     control_flow::register_all_ops(reg);
+    functional::register_all_ops(reg);
+    image::register_all_ops(reg);
     logic::register_all_ops(reg);
     math::register_all_ops(reg);
     nn::register_all_ops(reg);
@@ -37,6 +42,16 @@ pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("Identity", |_, _| Ok(Box::new(tract_hir::ops::identity::Identity)));
     reg.insert("NoOp", |_, _| Ok(Box::new(Noop)));
     reg.insert("Placeholder", |_, _| Ok(Box::new(tract_hir::ops::source::Source::new())));
+    reg.insert("StopGradient", |_, _| Ok(Box::new(tract_hir::ops::identity::Identity)));
+}
+
+/// Register the TypedOps of this crate in a serialization registry.
+pub fn register_ser_ops(reg: &mut Registry) {
+    logic::register_ser_ops(reg);
+    nn::s2b::unary::register_ser_ops(reg);
+    random::register_ser_ops(reg);
+    vars::register_ser_ops(reg);
+    reg.register_op::<Noop>("tf.Noop", |_, _| Ok(Attrs::default()), |_, _| Ok(Box::new(Noop)));
 }
 
 fn cast(_ctx: &ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
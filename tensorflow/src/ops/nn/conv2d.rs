@@ -15,6 +15,29 @@ pub fn conv2d(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn Infere
     Ok(expand(op))
 }
 
+pub fn conv2d_backprop_input(
+    _ctx: &ParsingContext,
+    pb: &NodeDef,
+) -> TractResult<Box<dyn InferenceOp>> {
+    let data_format = super::data_format(pb)?;
+    let geo = if data_format == DataFormat::NHWC { 1..3 } else { 2..4 };
+    let strides = super::strides(pb)?;
+    let mut op = cnn::Deconv::default()
+        .hwio()
+        .padding(super::padding(pb)?)
+        .strides(strides[geo.clone()].into())
+        .output_shape_input(0)
+        .k_input(1)
+        .x_input(2);
+    if let Some(dilations) = pb.get_attr_opt_list_int::<usize>("dilations")? {
+        op = op.dilations(dilations[geo].into());
+    }
+    if data_format == DataFormat::NHWC {
+        op = op.nhwc()
+    }
+    Ok(expand(op))
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(non_snake_case)]
@@ -170,6 +193,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn backprop_input_stride_2() {
+        let op = expand(
+            cnn::Deconv::default()
+                .nhwc()
+                .hwio()
+                .strides(tvec!(2, 2))
+                .output_shape_input(0)
+                .k_input(1)
+                .x_input(2),
+        );
+        let result = op
+            .as_stateless()
+            .unwrap()
+            .eval(tvec![
+                rctensor1(&[1i32, 4, 4, 1]),
+                Tensor::from(ArrayD::<f32>::ones(vec![2, 2, 1, 1])).into(),
+                mk(&[1, 2, 2, 1]).into(),
+            ])
+            .unwrap()
+            .remove(0);
+        let expected = tensor4(&[[
+            [[1.0f32], [1.0], [2.0], [2.0]],
+            [[1.0], [1.0], [2.0], [2.0]],
+            [[3.0], [3.0], [4.0], [4.0]],
+            [[3.0], [3.0], [4.0], [4.0]],
+        ]]);
+        assert_eq!(*result, expected);
+    }
+
     #[test]
     fn inference_2() {
         let mut op = make_conv(1, 1, PaddingSpec::SameUpper);
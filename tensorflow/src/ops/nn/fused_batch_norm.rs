@@ -1,4 +1,5 @@
 use tract_hir::internal::*;
+use tract_hir::ops::nn::DataFormat;
 use tract_hir::tract_core::itertools::izip;
 
 use crate::model::ParsingContext;
@@ -6,14 +7,33 @@ use crate::tfpb::tensorflow::NodeDef;
 
 pub fn fused_batch_norm(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
     let epsilon = pb.get_attr_float::<f32>("epsilon")?;
-    Ok(expand(FusedBatchNorm::new(epsilon)))
+    let data_format = super::data_format(pb)?;
+    // TF defaults to training mode, and strips default-valued attributes
+    // from exported graphs: a missing attribute means true.
+    let is_training = pb.get_attr_opt_bool("is_training")?.unwrap_or(true);
+    Ok(expand(FusedBatchNorm::new(epsilon, data_format, is_training)))
 }
 
+/// In training mode (TF's default), mean and variance are computed from the input over all
+/// axes but the channel one instead of being read from the inputs 3 and 4.
+/// This is how Keras implements its LayerNormalization.
 #[derive(Debug, Clone, new, Educe)]
 #[educe(Hash)]
 struct FusedBatchNorm {
     #[educe(Hash(method = "hash_f32"))]
     epsilon: f32,
+    data_format: DataFormat,
+    is_training: bool,
+}
+
+impl FusedBatchNorm {
+    fn c_axis(&self) -> usize {
+        if self.data_format == DataFormat::NHWC {
+            3
+        } else {
+            1
+        }
+    }
 }
 
 tract_linalg::impl_dyn_hash!(FusedBatchNorm);
@@ -51,10 +71,13 @@ impl Expansion for FusedBatchNorm {
         s.equals(&inputs[3].rank, 1)?;
         s.equals(&inputs[4].rank, 1)?;
         s.equals(&inputs[0].shape, &outputs[0].shape)?;
-        s.equals(&inputs[1].shape[0], &inputs[0].shape[3])?;
-        s.equals(&inputs[2].shape[0], &inputs[0].shape[3])?;
-        s.equals(&inputs[3].shape[0], &inputs[0].shape[3])?;
-        s.equals(&inputs[4].shape[0], &inputs[0].shape[3])?;
+        let c_axis = self.c_axis();
+        s.equals(&inputs[1].shape[0], &inputs[0].shape[c_axis])?;
+        s.equals(&inputs[2].shape[0], &inputs[0].shape[c_axis])?;
+        if !self.is_training {
+            s.equals(&inputs[3].shape[0], &inputs[0].shape[c_axis])?;
+            s.equals(&inputs[4].shape[0], &inputs[0].shape[c_axis])?;
+        }
         Ok(())
     }
 
@@ -64,6 +87,35 @@ impl Expansion for FusedBatchNorm {
         target: &mut TypedModel,
         inputs: &[OutletId],
     ) -> TractResult<TVec<OutletId>> {
+        let mut shape = tvec!(1, 1, 1, 1);
+        if self.is_training {
+            if let (Some(scale), Some(offset)) =
+                (&target.outlet_fact(inputs[1])?.konst, &target.outlet_fact(inputs[2])?.konst)
+            {
+                shape[self.c_axis()] = scale.len();
+                let scale = scale.clone().into_tensor().into_shape(&shape)?;
+                let offset = offset.clone().into_tensor().into_shape(&shape)?;
+                let axes: TVec<usize> = (0..4).filter(|&axis| axis != self.c_axis()).collect();
+                let normed = tract_hir::ops::nn::wire_mean_var_norm(
+                    &format!("{}.normed", prefix),
+                    target,
+                    inputs[0],
+                    &axes,
+                    self.epsilon,
+                )?;
+                let wire = target.wire_node(
+                    format!("{}.mul", prefix),
+                    tract_hir::ops::math::mul::unary(scale.into_arc_tensor()),
+                    &[normed],
+                )?;
+                return target.wire_node(
+                    format!("{}.add", prefix),
+                    tract_hir::ops::math::add::unary(offset.into_arc_tensor()),
+                    &wire,
+                );
+            }
+            bail!("Batch norm scale and offset expected to be known")
+        }
         let scale = target.outlet_fact(inputs[1])?;
         let offset = target.outlet_fact(inputs[2])?;
         let mean = target.outlet_fact(inputs[3])?;
@@ -78,7 +130,7 @@ impl Expansion for FusedBatchNorm {
             let slope: Vec<f32> =
                 izip!(variance, scale).map(|(v, s)| s / (v + self.epsilon).sqrt()).collect();
             let inter: Vec<f32> = izip!(offset, mean, &slope).map(|(o, m, s)| o - m * s).collect();
-            let shape = tvec!(1, 1, 1, scale.len());
+            shape[self.c_axis()] = scale.len();
             let slope = tensor1(&slope).into_shape(&shape)?;
             let inter = tensor1(&inter).into_shape(&shape)?;
             let wire = target.wire_node(
@@ -95,3 +147,60 @@ impl Expansion for FusedBatchNorm {
         bail!("Batch norm parameters expected to be known")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::tfpb;
+    use crate::tfpb::tensorflow::DataType::DtFloat;
+    use crate::tfpb::tensorflow::{GraphDef, TensorProto};
+    use std::convert::TryInto;
+    use tract_hir::internal::*;
+
+    fn graph(is_training: Option<bool>) -> GraphDef {
+        let mut bn = tfpb::node()
+            .name("bn")
+            .op("FusedBatchNormV3")
+            .input("x")
+            .input("scale")
+            .input("offset")
+            .input("mean")
+            .input("variance")
+            .attr("epsilon", 0f32);
+        if let Some(is_training) = is_training {
+            bn = bn.attr("is_training", is_training);
+        }
+        let konst = |name: &str, v: [f32; 2]| {
+            let v: TensorProto = (&tensor1(&v)).try_into().unwrap();
+            tfpb::node().name(name).op("Const").attr("dtype", DtFloat).attr("value", v)
+        };
+        tfpb::graph()
+            .node(tfpb::node().name("x").op("Placeholder").attr("dtype", DtFloat))
+            .node(konst("scale", [1., 1.]))
+            .node(konst("offset", [0., 0.]))
+            .node(konst("mean", [0., 0.]))
+            .node(konst("variance", [1., 1.]))
+            .node(bn)
+    }
+
+    fn run(is_training: Option<bool>) -> Arc<Tensor> {
+        let mut model = crate::tensorflow().parse_graph(&graph(is_training)).unwrap().0;
+        let bn = model.node_id_by_name("bn").unwrap();
+        model.set_output_outlets(&[OutletId::new(bn, 0)]).unwrap();
+        let input = tensor4(&[[[[1f32, 2.]], [[3., 6.]]]]);
+        model.set_input_fact(0, InferenceFact::dt_shape_from_tensor(&input)).unwrap();
+        let plan = SimplePlan::new(model.into_optimized().unwrap()).unwrap();
+        plan.run(tvec!(input)).unwrap().remove(0)
+    }
+
+    #[test]
+    fn fused_batch_norm_defaults_to_training() {
+        let normed = rctensor4(&[[[[-1f32, -1.]], [[1., 1.]]]]);
+        assert_eq!(run(None), normed);
+        assert_eq!(run(Some(true)), normed);
+    }
+
+    #[test]
+    fn fused_batch_norm_inference() {
+        assert_eq!(run(Some(false)), rctensor4(&[[[[1f32, 2.]], [[3., 6.]]]]));
+    }
+}
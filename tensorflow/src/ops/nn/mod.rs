@@ -2,6 +2,7 @@ use tract_hir::internal::*;
 use tract_hir::ops::cnn::PaddingSpec;
 use tract_hir::ops::nn::{DataFormat, LayerSoftmax};
 
+use crate::model::ParsingContext;
 use crate::model::TfOpRegister;
 use crate::tfpb::tensorflow::NodeDef;
 
@@ -14,19 +15,29 @@ pub mod s2b;
 pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("AvgPool", pools::avgpool);
     reg.insert("Conv2D", conv2d::conv2d);
+    reg.insert("Conv2DBackpropInput", conv2d::conv2d_backprop_input);
     reg.insert("DepthwiseConv2dNative", dw_conv2d::depthwise_conv2d);
+    reg.insert("Elu", |_, _| Ok(expand(tract_hir::ops::activations::Elu(1.0))));
     reg.insert("FusedBatchNorm", fused_batch_norm::fused_batch_norm);
+    reg.insert("FusedBatchNormV2", fused_batch_norm::fused_batch_norm);
+    reg.insert("FusedBatchNormV3", fused_batch_norm::fused_batch_norm);
+    reg.insert("LeakyRelu", leaky_relu);
     reg.insert("MaxPool", pools::maxpool);
     reg.insert("Relu", |_, _| Ok(expand(tract_hir::ops::activations::Clip::new(Some(0.0), None))));
     reg.insert("Relu6", |_, _| {
         Ok(expand(tract_hir::ops::activations::Clip::new(Some(0.0), Some(6.0))))
     });
     reg.insert("Sigmoid", |_, _| Ok(Box::new(tract_hir::ops::nn::sigmoid())));
-    reg.insert("Softmax", |_, _| Ok(expand(LayerSoftmax::new(1))));
+    reg.insert("Softmax", |_, _| Ok(expand(LayerSoftmax::new(1, true))));
     reg.insert("SpaceToBatchND", s2b::space_to_batch_nd);
     reg.insert("BatchToSpaceND", s2b::batch_to_space_nd);
 }
 
+fn leaky_relu(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let alpha = pb.get_attr_opt_float("alpha")?.unwrap_or(0.2);
+    Ok(expand(tract_hir::ops::activations::LeakyRelu(alpha)))
+}
+
 pub fn strides(pb: &NodeDef) -> TractResult<Vec<usize>> {
     let strides: Vec<usize> = pb.get_attr_list_int("strides")?;
     if strides.len() != 4 || strides[0] != 1 && strides[3] != 1 {
@@ -2,6 +2,7 @@ use tract_hir::internal::*;
 use tract_ndarray::prelude::*;
 
 use tract_hir::ops::cnn::{ConvUnary, PoolSpec};
+use tract_hir::tract_core::ser::{Attr, Attrs, Registry};
 
 #[derive(Debug, Copy, Clone, Hash)]
 pub enum PaddingStrat {
@@ -151,3 +152,79 @@ impl TypedOp for BatchToSpaceUnary {
         Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &*self.space_shape)?))
     }
 }
+
+fn dump_pad(pad: &[PaddingStrat]) -> Attr {
+    pad.iter()
+        .map(|strat| {
+            let ints: &[usize] = match *strat {
+                PaddingStrat::FlexFixed(f) => &[0, f, 0],
+                PaddingStrat::FixedFlex(f) => &[1, f, 0],
+                PaddingStrat::FixedFixed(a, b) => &[2, a, b],
+            };
+            Attr::from(ints)
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn load_pad(attr: &Attr) -> TractResult<Vec<PaddingStrat>> {
+    attr.as_list()?
+        .iter()
+        .map(|strat| {
+            Ok(match &*strat.as_usizes()? {
+                &[0, f, 0] => PaddingStrat::FlexFixed(f),
+                &[1, f, 0] => PaddingStrat::FixedFlex(f),
+                &[2, a, b] => PaddingStrat::FixedFixed(a, b),
+                other => bail!("Invalid padding strategy {:?}", other),
+            })
+        })
+        .collect()
+}
+
+fn load_block_shape(attrs: &Attrs) -> TractResult<Array1<i32>> {
+    let block_shape = attrs.get("block_shape")?.as_tensor()?;
+    Ok(block_shape.to_array_view::<i32>()?.into_dimensionality::<Ix1>()?.to_owned())
+}
+
+pub fn register_ser_ops(reg: &mut Registry) {
+    reg.register_op::<SpaceToBatchUnary>(
+        "tf.SpaceToBatchUnary",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("datum_type", op.datum_type)
+                .with("space_shape", &*op.space_shape)
+                .with("batch_shape", &*op.batch_shape)
+                .with("block_shape", Tensor::from(op.block_shape.clone()).into_arc_tensor())
+                .with("pad", dump_pad(&op.pad)))
+        },
+        |_, attrs| {
+            Ok(Box::new(SpaceToBatchUnary::new(
+                attrs.get("datum_type")?.as_datum_type()?,
+                attrs.get("space_shape")?.as_dims()?.into(),
+                attrs.get("batch_shape")?.as_dims()?.into(),
+                load_block_shape(attrs)?,
+                load_pad(attrs.get("pad")?)?.into(),
+            )))
+        },
+    );
+    reg.register_op::<BatchToSpaceUnary>(
+        "tf.BatchToSpaceUnary",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("datum_type", op.datum_type)
+                .with("batch_shape", &*op.batch_shape)
+                .with("space_shape", &*op.space_shape)
+                .with("block_shape", Tensor::from(op.block_shape.clone()).into_arc_tensor())
+                .with("pad", dump_pad(&op.pad)))
+        },
+        |_, attrs| {
+            Ok(Box::new(BatchToSpaceUnary::new(
+                attrs.get("datum_type")?.as_datum_type()?,
+                attrs.get("batch_shape")?.as_dims()?.into(),
+                attrs.get("space_shape")?.as_dims()?.into(),
+                load_block_shape(attrs)?,
+                load_pad(attrs.get("pad")?)?,
+            )))
+        },
+    );
+}
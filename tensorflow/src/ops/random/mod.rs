@@ -2,8 +2,13 @@ mod philox;
 mod random_uniform;
 
 use crate::model::TfOpRegister;
+use tract_hir::tract_core::ser::Registry;
 
 pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("RandomUniform", random_uniform::random_uniform);
     reg.insert("RandomUniformInt", random_uniform::random_uniform_int);
 }
+
+pub fn register_ser_ops(reg: &mut Registry) {
+    random_uniform::register_ser_ops(reg);
+}
@@ -1,6 +1,7 @@
 use crate::model::ParsingContext;
 use crate::tfpb::tensorflow::NodeDef;
 use tract_hir::internal::*;
+use tract_hir::tract_core::ser::{Attrs, Registry};
 
 use super::philox::Philox4x32x10;
 
@@ -251,3 +252,24 @@ impl InferenceRulesOp for RandomUniformInt {
 
     as_op!();
 }
+
+pub fn register_ser_ops(reg: &mut Registry) {
+    reg.register_op::<TypedRandomUniform>(
+        "tf.RandomUniform",
+        |_, op| {
+            Ok(Attrs::default()
+                .with("t", op.t)
+                .with("seed1", op.seed1 as i64)
+                .with("seed2", op.seed2 as i64)
+                .with("shape", &*op.shape))
+        },
+        |_, attrs| {
+            Ok(Box::new(TypedRandomUniform::new(
+                attrs.get("t")?.as_datum_type()?,
+                attrs.get("seed1")?.as_i64()? as u64,
+                attrs.get("seed2")?.as_i64()? as u64,
+                attrs.get("shape")?.as_dims()?.into(),
+            )))
+        },
+    );
+}
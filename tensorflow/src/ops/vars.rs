@@ -1,18 +1,24 @@
 use tract_hir::internal::*;
+use tract_hir::tract_core::ser::{Attrs, Registry};
 
 use crate::model::{ParsingContext, TfOpRegister};
 use crate::tfpb::tensorflow::NodeDef;
 
 pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("Assign", |_, _| Ok(Box::new(Assign::default())));
+    reg.insert("AssignVariableOp", |_, _| Ok(Box::new(Assign::default())));
+    reg.insert("ReadVariableOp", |_, _| Ok(Box::new(tract_hir::ops::identity::Identity)));
+    reg.insert("VarHandleOp", variable_v2);
     reg.insert("VariableV2", variable_v2);
 }
 
+// Resource variables are handled as ref variables: VarHandleOp outputs the
+// variable value, ReadVariableOp passes it through and AssignVariableOp
+// updates the session state like Assign.
+
 fn variable_v2(_ctx: &ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
-    let shared_name = node.get_attr_str("shared_name")?;
-    let shared_name = if shared_name != "" { Some(shared_name) } else { None };
-    let container = node.get_attr_str("container")?;
-    let container = if container != "" { Some(container) } else { None };
+    let shared_name = node.get_attr_opt_str("shared_name")?.filter(|s| s != "");
+    let container = node.get_attr_opt_str("container")?.filter(|s| s != "");
     let name = node.name.to_string();
     let id = format!("{:?}#{:?}#{}", container, shared_name, name);
     let shape = node.get_attr_shape("shape")?;
@@ -216,3 +222,41 @@ impl TypedOp for Assign {
         Ok(tvec!(inputs[0].clone()))
     }
 }
+
+pub fn register_ser_ops(reg: &mut Registry) {
+    reg.register_op::<VariableV2>(
+        "tf.VariableV2",
+        |_, op| {
+            Ok(Attrs::default()
+                .with_opt("container", op.container.clone())
+                .with_opt("shared_name", op.shared_name.clone())
+                .with("name", &*op.name)
+                .with("id", &*op.id)
+                .with("shape", &*op.shape)
+                .with("dt", op.dt)
+                .with_opt("initializer", op.initializer.clone()))
+        },
+        |_, attrs| {
+            let string =
+                |name| attrs.get_opt(name).map(|a| a.as_str().map(|s| s.to_string())).transpose();
+            Ok(Box::new(VariableV2::new(
+                string("container")?,
+                string("shared_name")?,
+                attrs.get("name")?.as_str()?.to_string(),
+                attrs.get("id")?.as_str()?.to_string(),
+                attrs.get("shape")?.as_usizes()?,
+                attrs.get("dt")?.as_datum_type()?,
+                attrs.get_opt("initializer").map(|a| a.as_tensor().map(Arc::clone)).transpose()?,
+            )))
+        },
+    );
+    reg.register_op::<Assign>(
+        "tf.Assign",
+        |_, op| Ok(Attrs::default().with_opt("var_id", op.var_id.clone())),
+        |_, attrs| {
+            let var_id =
+                attrs.get_opt("var_id").map(|a| a.as_str().map(|s| s.to_string())).transpose()?;
+            Ok(Box::new(Assign::new(var_id)))
+        },
+    );
+}
@@ -0,0 +1,385 @@
+//! SavedModel directories: `saved_model.pb` holds one or more meta graphs,
+//! identified by tags, each with named signatures. Variable values live in
+//! the `variables/variables` tensor bundle.
+
+use crate::model::{Tensorflow, TfModelAndExtensions};
+use crate::ops::vars::VariableV2;
+use crate::tensor_bundle::TensorBundle;
+use crate::tfpb::tensorflow::saved_object::Kind;
+use crate::tfpb::tensorflow::tensor_info::Encoding;
+use crate::tfpb::tensorflow::{
+    GraphDef, MetaGraphDef, NodeDef, SignatureDef, TensorInfo, TrackableObjectGraph,
+};
+use prost::Message;
+use std::{fs, path};
+use tract_hir::internal::*;
+
+const OBJECT_GRAPH_KEY: &str = "_CHECKPOINTABLE_OBJECT_GRAPH";
+const DEFAULT_SIGNATURE: &str = "serving_default";
+
+/// A meta graph from a SavedModel, with its variables if it has any.
+pub struct TfSavedModel {
+    pub meta_graph: MetaGraphDef,
+    pub variables: Option<TensorBundle>,
+}
+
+impl TfSavedModel {
+    pub fn graph_def(&self) -> TractResult<&GraphDef> {
+        Ok(self.meta_graph.graph_def.as_ref().ok_or_else(|| format!("Meta graph has no graph"))?)
+    }
+
+    /// Names of the meta graph signatures, sorted.
+    pub fn signature_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.meta_graph.signature_def.keys().map(|s| &**s).collect();
+        names.sort();
+        names
+    }
+
+    /// The named signature, or the default one: "serving_default" if
+    /// present, or the only signature of the meta graph.
+    pub fn signature(&self, name: Option<&str>) -> TractResult<Option<&SignatureDef>> {
+        let signatures = &self.meta_graph.signature_def;
+        if let Some(name) = name {
+            let sig = signatures.get(name).ok_or_else(|| {
+                format!("No signature {} in meta graph (found {:?})", name, self.signature_names())
+            })?;
+            Ok(Some(sig))
+        } else if let Some(sig) = signatures.get(DEFAULT_SIGNATURE) {
+            Ok(Some(sig))
+        } else if signatures.len() == 1 {
+            Ok(signatures.values().next())
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Find the checkpoint key of each variable node, by order of preference:
+    /// * from the restore ops of a TF1 saver (Assign or AssignVariableOp fed
+    ///   by RestoreV2),
+    /// * from the object graph of a TF2 SavedModel, matched with the
+    ///   checkpointed trackable object graph,
+    /// * the variable name (or shared name) itself.
+    fn checkpoint_keys(&self, bundle: &TensorBundle) -> TractResult<HashMap<String, String>> {
+        let graph = self.graph_def()?;
+        let nodes: HashMap<&str, &NodeDef> = graph.node.iter().map(|n| (&*n.name, n)).collect();
+        let source = |input: &str| -> TractResult<(&NodeDef, usize)> {
+            let (mut name, mut slot) = Tensorflow::parse_input(input)?;
+            loop {
+                let node = nodes.get(name).ok_or_else(|| format!("No node {}", name))?;
+                if node.op == "Identity" && node.input.len() > 0 {
+                    let (n, s) = Tensorflow::parse_input(&node.input[0])?;
+                    name = n;
+                    slot = s;
+                } else {
+                    return Ok((node, slot));
+                }
+            }
+        };
+        let variables: Vec<&NodeDef> =
+            graph.node.iter().filter(|n| n.op == "VariableV2" || n.op == "VarHandleOp").collect();
+        let mut keys = HashMap::new();
+
+        for assign in graph.node.iter() {
+            if (assign.op != "Assign" && assign.op != "AssignVariableOp") || assign.input.len() < 2
+            {
+                continue;
+            }
+            let (restore, slot) = source(&assign.input[1])?;
+            if restore.op != "RestoreV2" || restore.input.len() < 2 {
+                continue;
+            }
+            let (names, _) = source(&restore.input[1])?;
+            if names.op != "Const" {
+                continue;
+            }
+            let names = names.get_attr_tensor("value")?;
+            let key = &names.as_slice::<Blob>()?[slot].0;
+            let (var, _) = source(&assign.input[0])?;
+            keys.insert(var.name.clone(), String::from_utf8_lossy(key).into_owned());
+        }
+
+        if let (Some(saved_objects), true) =
+            (&self.meta_graph.object_graph_def, bundle.contains(OBJECT_GRAPH_KEY))
+        {
+            let trackables = bundle.tensor(OBJECT_GRAPH_KEY)?;
+            let trackables = TrackableObjectGraph::decode(&*trackables.to_scalar::<Blob>()?.0)
+                .map_err(|e| format!("Invalid checkpointed object graph: {:?}", e))?;
+            for (saved, trackable) in saved_objects.nodes.iter().zip(trackables.nodes.iter()) {
+                let saved = if let Some(Kind::Variable(v)) = &saved.kind { v } else { continue };
+                let key = if let Some(attr) =
+                    trackable.attributes.iter().find(|a| a.name == "VARIABLE_VALUE")
+                {
+                    &attr.checkpoint_key
+                } else {
+                    continue;
+                };
+                for var in &variables {
+                    if var.name == saved.name || shared_name(var)? == Some(&*saved.name) {
+                        keys.entry(var.name.clone()).or_insert_with(|| key.clone());
+                    }
+                }
+            }
+        }
+
+        for var in &variables {
+            if keys.contains_key(&var.name) {
+                continue;
+            }
+            if let Some(key) =
+                Some(&*var.name).into_iter().chain(shared_name(var)?).find(|k| bundle.contains(k))
+            {
+                keys.insert(var.name.clone(), key.to_string());
+            }
+        }
+        Ok(keys)
+    }
+}
+
+fn shared_name(node: &NodeDef) -> TractResult<Option<&str>> {
+    Ok(node
+        .get_attr_opt_raw_str("shared_name")?
+        .map(|s| std::str::from_utf8(s))
+        .transpose()?
+        .filter(|s| s.len() > 0))
+}
+
+impl Tensorflow {
+    /// Open a SavedModel directory, picking the meta graph bearing all the
+    /// given tags (usually "serve").
+    pub fn open_saved_model_dir(
+        &self,
+        dir: impl AsRef<path::Path>,
+        tags: &[&str],
+    ) -> TractResult<TfSavedModel> {
+        let dir = dir.as_ref();
+        let pb = dir.join("saved_model.pb");
+        let saved = self.open_saved_model(
+            &mut fs::File::open(&pb).map_err(|e| format!("Could not open {:?}: {}", pb, e))?,
+        )?;
+        let found_tags: Vec<Vec<String>> = saved
+            .meta_graphs
+            .iter()
+            .map(|mg| mg.meta_info_def.as_ref().map(|info| info.tags.clone()).unwrap_or(vec![]))
+            .collect();
+        let meta_graph = saved
+            .meta_graphs
+            .into_iter()
+            .find(|mg| {
+                let found = mg.meta_info_def.as_ref().map(|info| &*info.tags).unwrap_or(&[]);
+                tags.iter().all(|t| found.iter().any(|f| f == t))
+            })
+            .ok_or_else(|| {
+                format!("No meta graph tagged {:?} in {:?} (found {:?})", tags, dir, found_tags)
+            })?;
+        let variables = if dir.join("variables").join("variables.index").exists() {
+            Some(TensorBundle::open(dir.join("variables").join("variables"))?)
+        } else {
+            None
+        };
+        Ok(TfSavedModel { meta_graph, variables })
+    }
+
+    /// Build a model from a SavedModel meta graph. Variables are initialized
+    /// from the checkpoint. If a signature is selected (see
+    /// TfSavedModel::signature), it sets the model inputs and outputs, sorted
+    /// by signature name, and labels their outlets with these names.
+    pub fn model_for_saved_model(
+        &self,
+        saved: &TfSavedModel,
+        signature: Option<&str>,
+    ) -> TractResult<TfModelAndExtensions> {
+        let mut model_and_ext = self.parse_graph(saved.graph_def()?)?;
+        let model = &mut model_and_ext.0;
+        if let Some(bundle) = &saved.variables {
+            let keys = saved.checkpoint_keys(bundle)?;
+            for node in &mut model.nodes {
+                let key = keys.get(&node.name);
+                if let Some(var) = node.op_as_mut::<VariableV2>() {
+                    if let Some(key) = key {
+                        var.initializer = Some(bundle.tensor(key)?.into_arc_tensor());
+                    } else {
+                        warn!("No checkpointed value for variable {}", node.name);
+                    }
+                }
+            }
+        }
+        if let Some(signature) = saved.signature(signature)? {
+            let inputs = Self::signature_outlets(model, &signature.inputs)?;
+            model.set_input_outlets(&inputs)?;
+            let outputs = Self::signature_outlets(model, &signature.outputs)?;
+            model.set_output_outlets(&outputs)?;
+        }
+        Ok(model_and_ext)
+    }
+
+    fn signature_outlets(
+        model: &mut InferenceModel,
+        tensors: &HashMap<String, TensorInfo>,
+    ) -> TractResult<Vec<OutletId>> {
+        let mut tensors: Vec<(&String, &TensorInfo)> = tensors.iter().collect();
+        tensors.sort_by_key(|pair| pair.0);
+        tensors
+            .into_iter()
+            .map(|(name, info)| {
+                let tensor = if let Some(Encoding::Name(tensor)) = &info.encoding {
+                    tensor
+                } else {
+                    bail!("Signature tensor {} is not a dense tensor", name)
+                };
+                let (node, slot) = Self::parse_input(tensor)?;
+                let outlet = OutletId::new(model.node_id_by_name(node)?, slot);
+                model.set_outlet_label(outlet, name.clone())?;
+                Ok(outlet)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tensor_bundle::tests::bundle;
+    use crate::tfpb;
+    use crate::tfpb::tensorflow::meta_graph_def::MetaInfoDef;
+    use crate::tfpb::tensorflow::tensor_shape_proto::Dim;
+    use crate::tfpb::tensorflow::trackable_object_graph::trackable_object::SerializedTensor;
+    use crate::tfpb::tensorflow::trackable_object_graph::TrackableObject;
+    use crate::tfpb::tensorflow::DataType::{DtFloat, DtString};
+    use crate::tfpb::tensorflow::{
+        SavedObject, SavedObjectGraph, SavedVariable, TensorProto, TensorShapeProto,
+    };
+
+    fn shape(dims: &[usize]) -> TensorShapeProto {
+        TensorShapeProto {
+            dim: dims.iter().map(|&d| Dim { size: d as i64, name: String::new() }).collect(),
+            unknown_rank: false,
+        }
+    }
+
+    fn tensor_info(name: &str) -> TensorInfo {
+        TensorInfo {
+            dtype: DtFloat as i32,
+            tensor_shape: None,
+            encoding: Some(Encoding::Name(name.to_string())),
+        }
+    }
+
+    fn string_const(name: &str, strings: Vec<Vec<u8>>) -> NodeDef {
+        let value = TensorProto {
+            dtype: DtString as i32,
+            tensor_shape: Some(shape(&[strings.len()])),
+            string_val: strings,
+            ..TensorProto::default()
+        };
+        tfpb::node().name(name).op("Const").attr("dtype", DtString).attr("value", value)
+    }
+
+    fn var(name: &str, op: &str, dims: &[usize]) -> NodeDef {
+        tfpb::node()
+            .name(name)
+            .op(op)
+            .attr("dtype", DtFloat)
+            .attr("shape", shape(dims))
+            .attr("shared_name", "")
+    }
+
+    // x * w + b, w being a ref variable restored by a TF1 saver under
+    // another name, b a resource variable
+    fn saved_model() -> TfSavedModel {
+        let graph = tfpb::graph()
+            .node(tfpb::node().name("x").op("Placeholder").attr("dtype", DtFloat))
+            .node(var("w", "VariableV2", &[2]))
+            .node(var("b", "VarHandleOp", &[2]))
+            .node(tfpb::node().name("b/read").op("ReadVariableOp").input("b"))
+            .node(tfpb::node().name("mul").op("Mul").input("x").input("w").attr("T", DtFloat))
+            .node(
+                tfpb::node()
+                    .name("add")
+                    .op("AddV2")
+                    .input("mul")
+                    .input("b/read")
+                    .attr("T", DtFloat),
+            )
+            .node(string_const("save/Const", vec![b"model".to_vec()]))
+            .node(string_const("save/tensor_names", vec![b"weights".to_vec()]))
+            .node(string_const("save/shape_and_slices", vec![vec![]]))
+            .node(
+                tfpb::node()
+                    .name("save/RestoreV2")
+                    .op("RestoreV2")
+                    .input("save/Const")
+                    .input("save/tensor_names")
+                    .input("save/shape_and_slices"),
+            )
+            .node(tfpb::node().name("save/Assign").op("Assign").input("w").input("save/RestoreV2"));
+        let mut meta_graph = MetaGraphDef::default();
+        meta_graph.graph_def = Some(graph);
+        meta_graph.meta_info_def =
+            Some(MetaInfoDef { tags: vec!["serve".to_string()], ..MetaInfoDef::default() });
+        let mut signature = SignatureDef::default();
+        signature.inputs.insert("input".to_string(), tensor_info("x:0"));
+        signature.outputs.insert("output".to_string(), tensor_info("add:0"));
+        meta_graph.signature_def.insert(DEFAULT_SIGNATURE.to_string(), signature);
+        let variables = bundle(&[("weights", tensor1(&[2f32, 3.])), ("b", tensor1(&[10f32, 20.]))]);
+        TfSavedModel { meta_graph, variables: Some(variables) }
+    }
+
+    #[test]
+    fn restore_and_run_signature() {
+        let saved = saved_model();
+        let mut model = crate::tensorflow().model_for_saved_model(&saved, None).unwrap().0;
+        let input = model.find_outlet_label("input").unwrap();
+        let output = model.find_outlet_label("output").unwrap();
+        assert_eq!(model.input_outlets().unwrap(), &[input]);
+        assert_eq!(model.output_outlets().unwrap(), &[output]);
+        model.set_input_fact(0, InferenceFact::dt_shape(f32::datum_type(), tvec!(2))).unwrap();
+        let model = model.into_optimized().unwrap();
+        let result = SimplePlan::new(&model).unwrap().run(tvec!(tensor1(&[1f32, 2.]))).unwrap();
+        assert_eq!(result[0], rctensor1(&[12f32, 26.]));
+    }
+
+    #[test]
+    fn restore_from_object_graph_key() {
+        const BIAS_KEY: &str = "layer/bias/.ATTRIBUTES/VARIABLE_VALUE";
+        let mut saved = saved_model();
+        let variable = SavedVariable { name: "b".to_string(), ..SavedVariable::default() };
+        saved.meta_graph.object_graph_def = Some(SavedObjectGraph {
+            nodes: vec![
+                SavedObject::default(),
+                SavedObject { kind: Some(Kind::Variable(variable)), ..SavedObject::default() },
+            ],
+            ..SavedObjectGraph::default()
+        });
+        let attribute = SerializedTensor {
+            name: "VARIABLE_VALUE".to_string(),
+            checkpoint_key: BIAS_KEY.to_string(),
+            ..SerializedTensor::default()
+        };
+        let trackables = TrackableObjectGraph {
+            nodes: vec![
+                TrackableObject::default(),
+                TrackableObject { attributes: vec![attribute], ..TrackableObject::default() },
+            ],
+        };
+        let mut trackables_pb = vec![];
+        trackables.encode(&mut trackables_pb).unwrap();
+        // "b" is a decoy: the key found through the object graph wins
+        saved.variables = Some(bundle(&[
+            ("weights", tensor1(&[2f32, 3.])),
+            ("b", tensor1(&[0f32, 0.])),
+            (BIAS_KEY, tensor1(&[10f32, 20.])),
+            (OBJECT_GRAPH_KEY, tensor0(Blob(trackables_pb))),
+        ]));
+        let mut model = crate::tensorflow().model_for_saved_model(&saved, None).unwrap().0;
+        model.set_input_fact(0, InferenceFact::dt_shape(f32::datum_type(), tvec!(2))).unwrap();
+        let model = model.into_optimized().unwrap();
+        let result = SimplePlan::new(&model).unwrap().run(tvec!(tensor1(&[1f32, 2.]))).unwrap();
+        assert_eq!(result[0], rctensor1(&[12f32, 26.]));
+    }
+
+    #[test]
+    fn unknown_signature() {
+        let saved = saved_model();
+        assert!(crate::tensorflow().model_for_saved_model(&saved, Some("predict")).is_err());
+    }
+}
@@ -0,0 +1,50 @@
+//! Serialization of the TypedOps defined in this crate.
+//!
+//! See `tract_core::ser` for the format itself.
+use tract_hir::tract_core::ser::Registry;
+
+pub fn register_tensorflow_ops(reg: &mut Registry) {
+    crate::ops::register_ser_ops(reg);
+}
+
+/// A registry knowing about the core ops, and the ones defined in tract-hir
+/// and in this crate.
+pub fn registry() -> Registry {
+    let mut registry = tract_hir::ser::registry();
+    register_tensorflow_ops(&mut registry);
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::nn::s2b::unary::*;
+    use tract_hir::internal::*;
+
+    #[test]
+    fn round_trip_tensorflow_ops() {
+        let registry = registry();
+        let ops: Vec<Box<dyn TypedOp>> = vec![
+            Box::new(SpaceToBatchUnary::new(
+                f32::datum_type(),
+                tvec!(1.to_dim(), 5.to_dim(), 5.to_dim(), 3.to_dim()),
+                tvec!(4.to_dim(), 3.to_dim(), 3.to_dim(), 3.to_dim()),
+                tract_ndarray::arr1(&[2, 2]),
+                tvec!(PaddingStrat::FlexFixed(1), PaddingStrat::FixedFixed(0, 1)),
+            )),
+            Box::new(BatchToSpaceUnary::new(
+                f32::datum_type(),
+                tvec!(4.to_dim(), 3.to_dim(), 3.to_dim(), 3.to_dim()),
+                tvec!(1.to_dim(), 5.to_dim(), 5.to_dim(), 3.to_dim()),
+                tract_ndarray::arr1(&[2, 2]),
+                vec![PaddingStrat::FixedFlex(1), PaddingStrat::FixedFixed(1, 0)],
+            )),
+            Box::new(crate::ops::vars::Assign::new(Some("var".to_string()))),
+        ];
+        for op in ops {
+            let (name, attrs) = registry.dump_op(&*op).unwrap();
+            let reloaded = registry.load_op(&name, &attrs).unwrap();
+            assert_eq!(format!("{:?}", op), format!("{:?}", reloaded));
+        }
+    }
+}
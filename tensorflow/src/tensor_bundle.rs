@@ -0,0 +1,401 @@
+//! Reader for TensorFlow tensor bundles, the checkpoint format used for the
+//! variables of a SavedModel.
+//!
+//! A bundle with prefix `p` is made of an index file `p.index`, mapping
+//! tensor names to their location, and data files `p.data-?????-of-?????`
+//! holding the raw tensor content. The index is a LevelDB-style sorted table
+//! of serialized protobuf entries, the empty key holding a bundle header.
+
+use crate::tfpb::tensorflow::bundle_header_proto::Endianness;
+use crate::tfpb::tensorflow::{BundleEntryProto, BundleHeaderProto, DataType};
+use prost::Message;
+use std::convert::TryFrom;
+use std::{fs, path};
+use tract_hir::internal::*;
+
+const TABLE_MAGIC: u64 = 0xdb4775248b80fb57;
+const FOOTER_LEN: usize = 48;
+const BLOCK_TRAILER_LEN: usize = 5;
+
+pub struct TensorBundle {
+    entries: HashMap<String, BundleEntryProto>,
+    shards: Vec<Box<dyn AsRef<[u8]>>>,
+}
+
+impl TensorBundle {
+    /// Open the bundle with the given prefix, for instance
+    /// `variables/variables` in a SavedModel directory.
+    pub fn open(prefix: impl AsRef<path::Path>) -> TractResult<TensorBundle> {
+        let prefix = prefix.as_ref().to_string_lossy().into_owned();
+        let index = fs::read(format!("{}.index", prefix))
+            .map_err(|e| format!("Could not open {}.index: {}", prefix, e))?;
+        let header = Self::header(&read_table(&index)?)?;
+        let shards = (0..header.num_shards)
+            .map(|shard| {
+                let path = format!("{}.data-{:05}-of-{:05}", prefix, shard, header.num_shards);
+                let file =
+                    fs::File::open(&path).map_err(|e| format!("Could not open {}: {}", path, e))?;
+                #[cfg(not(target_arch = "wasm32"))]
+                let data: Box<dyn AsRef<[u8]>> = Box::new(unsafe { memmap::Mmap::map(&file)? });
+                #[cfg(target_arch = "wasm32")]
+                let data: Box<dyn AsRef<[u8]>> = {
+                    use std::io::Read;
+                    let mut v = vec![];
+                    (&file).read_to_end(&mut v)?;
+                    Box::new(v)
+                };
+                Ok(data)
+            })
+            .collect::<TractResult<Vec<_>>>()?;
+        Self::from_parts(&index, shards)
+    }
+
+    /// Build a bundle from the index file content and the data shards.
+    pub fn from_parts(
+        index: &[u8],
+        shards: Vec<Box<dyn AsRef<[u8]>>>,
+    ) -> TractResult<TensorBundle> {
+        let table = read_table(index)?;
+        let header = Self::header(&table)?;
+        if header.endianness != Endianness::Little as i32 {
+            bail!("Big endian tensor bundles are not supported")
+        }
+        if header.num_shards as usize != shards.len() {
+            bail!("Tensor bundle expects {} shards, got {}", header.num_shards, shards.len())
+        }
+        let entries = table
+            .into_iter()
+            .filter(|(k, _)| k.len() > 0)
+            .map(|(k, v)| {
+                let key = String::from_utf8_lossy(&k).into_owned();
+                let entry = BundleEntryProto::decode(&*v)
+                    .map_err(|e| format!("Invalid bundle entry for {}: {:?}", key, e))?;
+                Ok((key, entry))
+            })
+            .collect::<TractResult<HashMap<_, _>>>()?;
+        Ok(TensorBundle { entries, shards })
+    }
+
+    fn header(table: &[(Vec<u8>, Vec<u8>)]) -> TractResult<BundleHeaderProto> {
+        let header = table
+            .iter()
+            .find(|(k, _)| k.len() == 0)
+            .ok_or_else(|| format!("Tensor bundle index has no header"))?;
+        Ok(BundleHeaderProto::decode(&*header.1)
+            .map_err(|e| format!("Invalid bundle header: {:?}", e))?)
+    }
+
+    /// Names of the tensors in the bundle.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(|k| &**k)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Read a tensor from the bundle.
+    pub fn tensor(&self, key: &str) -> TractResult<Tensor> {
+        let entry = self.entries.get(key).ok_or_else(|| format!("No tensor {} in bundle", key))?;
+        if entry.slices.len() > 0 {
+            bail!("Tensor {} is partitioned, partitioned variables are not supported", key)
+        }
+        let dtype = DataType::from_i32(entry.dtype)
+            .ok_or_else(|| format!("Invalid data type {} for {}", entry.dtype, key))?;
+        let shape: TVec<usize> = match &entry.shape {
+            Some(shape) => TVec::try_from(shape)?,
+            None => tvec!(),
+        };
+        let shard = self
+            .shards
+            .get(entry.shard_id as usize)
+            .ok_or_else(|| format!("Tensor {} refers to missing shard {}", key, entry.shard_id))?;
+        let shard = (**shard).as_ref();
+        let (offset, size) = match (usize::try_from(entry.offset), usize::try_from(entry.size)) {
+            (Ok(offset), Ok(size)) => (offset, size),
+            _ => bail!("Tensor {} has invalid offset {} or size {}", key, entry.offset, entry.size),
+        };
+        let end = offset
+            .checked_add(size)
+            .filter(|&end| end <= shard.len())
+            .ok_or_else(|| format!("Tensor {} overflows its data shard", key))?;
+        let data = &shard[offset..end];
+        if dtype == DataType::DtString {
+            read_strings(&shape, data)
+        } else {
+            let dt = DatumType::try_from(dtype)?;
+            if shape.iter().product::<usize>() * dt.size_of() != size {
+                bail!("Tensor {} has {} bytes for a shape {:?} of {:?}", key, size, shape, dt)
+            }
+            unsafe { Tensor::from_raw_dt(dt, &shape, data) }
+        }
+    }
+}
+
+/// String tensors are stored as the varint64 lengths of all elements, a
+/// 32-bit checksum, then the concatenated bytes.
+fn read_strings(shape: &[usize], mut data: &[u8]) -> TractResult<Tensor> {
+    let len = shape.iter().product::<usize>();
+    // each element takes at least one byte for its length
+    if len > data.len() {
+        bail!("Truncated string tensor")
+    }
+    let lengths = (0..len).map(|_| read_varint(&mut data)).collect::<TractResult<Vec<u64>>>()?;
+    if data.len() < 4 {
+        bail!("Truncated string tensor")
+    }
+    data = &data[4..];
+    let mut strings = Vec::with_capacity(len);
+    for l in lengths {
+        let l = l as usize;
+        if data.len() < l {
+            bail!("Truncated string tensor")
+        }
+        strings.push(Blob(data[..l].to_vec()));
+        data = &data[l..];
+    }
+    Ok(tract_ndarray::ArrayD::from_shape_vec(shape, strings)?.into())
+}
+
+fn read_varint(data: &mut &[u8]) -> TractResult<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = data.split_first().ok_or_else(|| format!("Truncated varint"))?;
+        *data = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("Invalid varint")
+}
+
+fn read_block_handle(data: &mut &[u8]) -> TractResult<(usize, usize)> {
+    Ok((read_varint(data)? as usize, read_varint(data)? as usize))
+}
+
+/// Read all the key/value pairs of a sorted table.
+fn read_table(data: &[u8]) -> TractResult<Vec<(Vec<u8>, Vec<u8>)>> {
+    if data.len() < FOOTER_LEN {
+        bail!("Tensor bundle index is too short")
+    }
+    let footer = &data[data.len() - FOOTER_LEN..];
+    let mut magic = [0u8; 8];
+    magic.copy_from_slice(&footer[FOOTER_LEN - 8..]);
+    if u64::from_le_bytes(magic) != TABLE_MAGIC {
+        bail!("Tensor bundle index is not a table (wrong magic number)")
+    }
+    let mut handles = footer;
+    let _metaindex = read_block_handle(&mut handles)?;
+    let index = read_block_handle(&mut handles)?;
+    let mut entries = vec![];
+    for (_, handle) in read_block(data, index)? {
+        let handle = read_block_handle(&mut &*handle)?;
+        entries.extend(read_block(data, handle)?);
+    }
+    Ok(entries)
+}
+
+/// Read the entries of a block. Keys share a prefix with their predecessor,
+/// and the block ends with the offsets of its restart points and their count.
+fn read_block(data: &[u8], (offset, size): (usize, usize)) -> TractResult<Vec<(Vec<u8>, Vec<u8>)>> {
+    let end = offset
+        .checked_add(size)
+        .and_then(|end| end.checked_add(BLOCK_TRAILER_LEN))
+        .ok_or_else(|| format!("Table block overflows the index file"))?;
+    if end > data.len() {
+        bail!("Table block overflows the index file")
+    }
+    if data[offset + size] != 0 {
+        bail!("Compressed tensor bundle index are not supported")
+    }
+    let block = &data[offset..offset + size];
+    if block.len() < 4 {
+        bail!("Table block is too short")
+    }
+    let mut restarts = [0u8; 4];
+    restarts.copy_from_slice(&block[block.len() - 4..]);
+    let restarts = u32::from_le_bytes(restarts) as usize;
+    let end = block
+        .len()
+        .checked_sub(4 * (restarts + 1))
+        .ok_or_else(|| format!("Invalid table block restart count"))?;
+    let mut cursor = &block[..end];
+    let mut entries: Vec<(Vec<u8>, Vec<u8>)> = vec![];
+    while cursor.len() > 0 {
+        let shared = read_varint(&mut cursor)? as usize;
+        let non_shared = read_varint(&mut cursor)? as usize;
+        let value_len = read_varint(&mut cursor)? as usize;
+        if cursor.len() < non_shared + value_len {
+            bail!("Truncated table block entry")
+        }
+        let mut key = match entries.last() {
+            Some((previous, _)) if shared <= previous.len() => previous[..shared].to_vec(),
+            None if shared == 0 => vec![],
+            _ => bail!("Invalid shared key prefix in table block"),
+        };
+        key.extend_from_slice(&cursor[..non_shared]);
+        let value = cursor[non_shared..non_shared + value_len].to_vec();
+        cursor = &cursor[non_shared + value_len..];
+        entries.push((key, value));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::tfpb::tensorflow::tensor_shape_proto::Dim;
+    use crate::tfpb::tensorflow::TensorShapeProto;
+
+    fn write_varint(buf: &mut Vec<u8>, mut v: u64) {
+        while v >= 0x80 {
+            buf.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        buf.push(v as u8);
+    }
+
+    // one block, no prefix sharing, a single restart point
+    fn write_block(out: &mut Vec<u8>, entries: &[(Vec<u8>, Vec<u8>)]) -> (usize, usize) {
+        let offset = out.len();
+        for (k, v) in entries {
+            write_varint(out, 0);
+            write_varint(out, k.len() as u64);
+            write_varint(out, v.len() as u64);
+            out.extend_from_slice(k);
+            out.extend_from_slice(v);
+        }
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes());
+        let size = out.len() - offset;
+        out.extend_from_slice(&[0; BLOCK_TRAILER_LEN]);
+        (offset, size)
+    }
+
+    fn encode<M: Message>(m: &M) -> Vec<u8> {
+        let mut v = vec![];
+        m.encode(&mut v).unwrap();
+        v
+    }
+
+    /// Build a single shard bundle, returning the index and data files.
+    pub(crate) fn write_bundle(tensors: &[(&str, Tensor)]) -> (Vec<u8>, Vec<u8>) {
+        let mut data = vec![];
+        let mut entries = vec![(
+            vec![],
+            encode(&BundleHeaderProto { num_shards: 1, endianness: 0, version: None }),
+        )];
+        let mut tensors = tensors.to_vec();
+        tensors.sort_by_key(|pair| pair.0);
+        for (name, t) in tensors {
+            let offset = data.len();
+            if t.datum_type() == DatumType::Blob {
+                let blobs = t.as_slice::<Blob>().unwrap();
+                for b in blobs {
+                    write_varint(&mut data, b.0.len() as u64);
+                }
+                data.extend_from_slice(&[0; 4]);
+                for b in blobs {
+                    data.extend_from_slice(&b.0);
+                }
+            } else {
+                match t.datum_type() {
+                    DatumType::F32 => t
+                        .as_slice::<f32>()
+                        .unwrap()
+                        .iter()
+                        .for_each(|x| data.extend_from_slice(&x.to_le_bytes())),
+                    DatumType::I32 => t
+                        .as_slice::<i32>()
+                        .unwrap()
+                        .iter()
+                        .for_each(|x| data.extend_from_slice(&x.to_le_bytes())),
+                    DatumType::I64 => t
+                        .as_slice::<i64>()
+                        .unwrap()
+                        .iter()
+                        .for_each(|x| data.extend_from_slice(&x.to_le_bytes())),
+                    dt => panic!("No test bundle support for {:?}", dt),
+                }
+            }
+            let entry = BundleEntryProto {
+                dtype: DataType::try_from(t.datum_type()).unwrap() as i32,
+                shape: Some(TensorShapeProto {
+                    dim: t
+                        .shape()
+                        .iter()
+                        .map(|&d| Dim { size: d as i64, name: String::new() })
+                        .collect(),
+                    unknown_rank: false,
+                }),
+                shard_id: 0,
+                offset: offset as i64,
+                size: (data.len() - offset) as i64,
+                crc32c: 0,
+                slices: vec![],
+            };
+            entries.push((name.as_bytes().to_vec(), encode(&entry)));
+        }
+        let mut index = vec![];
+        let data_block = write_block(&mut index, &entries);
+        let mut handle = vec![];
+        write_varint(&mut handle, data_block.0 as u64);
+        write_varint(&mut handle, data_block.1 as u64);
+        let last_key = entries.last().unwrap().0.clone();
+        let metaindex = write_block(&mut index, &[]);
+        let index_block = write_block(&mut index, &[(last_key, handle)]);
+        let mut footer = vec![];
+        for (offset, size) in &[metaindex, index_block] {
+            write_varint(&mut footer, *offset as u64);
+            write_varint(&mut footer, *size as u64);
+        }
+        footer.resize(FOOTER_LEN - 8, 0);
+        footer.extend_from_slice(&TABLE_MAGIC.to_le_bytes());
+        index.extend(footer);
+        (index, data)
+    }
+
+    pub(crate) fn bundle(tensors: &[(&str, Tensor)]) -> TensorBundle {
+        let (index, data) = write_bundle(tensors);
+        TensorBundle::from_parts(&index, vec![Box::new(data)]).unwrap()
+    }
+
+    #[test]
+    fn read_numbers() {
+        let b = bundle(&[
+            ("dense/kernel", tensor2(&[[1f32, 2.], [3., 4.]])),
+            ("dense/bias", tensor1(&[5i64, 6])),
+            ("global_step", tensor0(12i32)),
+        ]);
+        let mut keys = b.keys().collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, vec!["dense/bias", "dense/kernel", "global_step"]);
+        assert_eq!(b.tensor("dense/kernel").unwrap(), tensor2(&[[1f32, 2.], [3., 4.]]));
+        assert_eq!(b.tensor("dense/bias").unwrap(), tensor1(&[5i64, 6]));
+        assert_eq!(b.tensor("global_step").unwrap(), tensor0(12i32));
+        assert!(b.tensor("missing").is_err());
+    }
+
+    #[test]
+    fn read_strings() {
+        let strings = tensor1(&[Blob(b"foo".to_vec()), Blob(vec![]), Blob(b"barbaz".to_vec())]);
+        let b = bundle(&[("s", strings.clone())]);
+        assert_eq!(b.tensor("s").unwrap(), strings);
+    }
+
+    #[test]
+    fn reject_out_of_bounds_entries() {
+        let mut b = bundle(&[("x", tensor1(&[1f32, 2.]))]);
+        let mut entry = b.entries["x"].clone();
+        for &(offset, size) in &[(-1, 8), (0, -8), (i64::MAX, i64::MAX), (4, 8)] {
+            entry.offset = offset;
+            entry.size = size;
+            b.entries.insert("x".to_string(), entry.clone());
+            assert!(b.tensor("x").is_err());
+        }
+        assert!(read_block(&[0; 64], (usize::MAX - 2, 1)).is_err());
+        assert!(read_block(&[0; 64], (60, 4)).is_err());
+        assert!(super::read_strings(&[usize::MAX / 2, 2], &[3, 0, 0, 0, 0]).is_err());
+    }
+}
@@ -164,6 +164,21 @@ impl NodeDef {
         Ok(None)
     }
 
+    pub fn get_attr_func_name(&self, name: &str) -> TractResult<&str> {
+        Ok(self.get_attr_opt_func_name(name)?.ok_or_else(|| {
+            format!("Node {} ({}) expected function attribute '{}'", self.name, self.op, name)
+        })?)
+    }
+
+    pub fn get_attr_opt_func_name(&self, name: &str) -> TractResult<Option<&str>> {
+        if let Some(a) = self.attr.get(name) {
+            if let Value::Func(f) = a.value.as_ref().unwrap() {
+                return Ok(Some(&f.name));
+            }
+        };
+        Ok(None)
+    }
+
     pub fn get_attr_int<T: tract_num_traits::FromPrimitive>(&self, name: &str) -> TractResult<T> {
         Ok(self.get_attr_opt_int(name)?.ok_or_else(|| {
             format!("Node {} ({}) expected int attribute '{}'", self.name, self.op, name)
@@ -234,6 +249,12 @@ impl<'a> From<&'a str> for AttrValue {
     }
 }
 
+impl From<bool> for AttrValue {
+    fn from(t: bool) -> AttrValue {
+        AttrValue { value: Some(Value::B(t)) }
+    }
+}
+
 impl From<i32> for AttrValue {
     fn from(t: i32) -> AttrValue {
         AttrValue::from(t as i64)
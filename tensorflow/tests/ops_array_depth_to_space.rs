@@ -0,0 +1,57 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+fn depth_to_space(input: Tensor, block_size: usize) -> proptest::test_runner::TestCaseResult {
+    let graph = tfpb::graph().node(placeholder_f32("input")).node(
+        tfpb::node()
+            .name("op")
+            .op("DepthToSpace")
+            .input("input")
+            .attr("T", DtFloat)
+            .attr("block_size", block_size as i64),
+    );
+    let graph = graph.write_to_bytes().unwrap();
+    compare(&graph, vec![("input", input)], "op")
+}
+
+fn strat() -> BoxedStrategy<(Tensor, usize)> {
+    (1usize..3, 1usize..4, 1usize..4, 1usize..3, 2usize..4)
+        .prop_map(|(n, h, w, c, b)| {
+            let dims = vec![n, h, w, c * b * b];
+            let len = dims.iter().product::<usize>();
+            let input =
+                tract_ndarray::Array::from_shape_vec(dims, (0..len).map(|i| i as f32).collect())
+                    .unwrap()
+                    .into();
+            (input, b)
+        })
+        .boxed()
+}
+
+proptest! {
+    #[test]
+    fn proptest_depth_to_space((input, block_size) in strat()) {
+        depth_to_space(input, block_size)?
+    }
+}
+
+#[test]
+fn depth_to_space_2x2() {
+    let input = tensor1(&(0..16).map(|i| i as f32).collect::<Vec<_>>());
+    depth_to_space(input.into_shape(&[1, 2, 2, 4]).unwrap(), 2).unwrap()
+}
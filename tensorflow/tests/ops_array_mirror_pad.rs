@@ -0,0 +1,90 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{DtFloat, DtInt32};
+
+fn mirror_pad(
+    input: Tensor,
+    paddings: Tensor,
+    mode: &str,
+) -> proptest::test_runner::TestCaseResult {
+    let graph =
+        tfpb::graph().node(placeholder_f32("input")).node(const_i32("paddings", &paddings)).node(
+            tfpb::node()
+                .name("op")
+                .op("MirrorPad")
+                .input("input")
+                .input("paddings")
+                .attr("T", DtFloat)
+                .attr("Tpaddings", DtInt32)
+                .attr("mode", mode),
+        );
+    let graph = graph.write_to_bytes().unwrap();
+    compare(&graph, vec![("input", input)], "op")
+}
+
+// input and paddings, which must not exceed the input dims (minus one in
+// reflect mode)
+fn strat(reflect: bool) -> BoxedStrategy<(Tensor, Tensor)> {
+    vec(2usize..5, 1..4)
+        .prop_flat_map(move |dims| {
+            let pads = dims
+                .iter()
+                .map(|&d| {
+                    let max = if reflect { d - 1 } else { d };
+                    (0..max + 1, 0..max + 1)
+                })
+                .collect::<Vec<_>>();
+            (Just(dims), pads)
+        })
+        .prop_map(|(dims, pads)| {
+            let len = dims.iter().product::<usize>();
+            let input = tract_ndarray::Array::from_shape_vec(
+                dims.clone(),
+                (0..len).map(|i| i as f32).collect(),
+            )
+            .unwrap()
+            .into();
+            let pads = pads.iter().flat_map(|p| vec![p.0 as i32, p.1 as i32]).collect::<Vec<_>>();
+            (input, tensor1(&pads).into_shape(&[dims.len(), 2]).unwrap())
+        })
+        .boxed()
+}
+
+proptest! {
+    #[test]
+    fn proptest_reflect((input, paddings) in strat(true)) {
+        mirror_pad(input, paddings, "REFLECT")?
+    }
+
+    #[test]
+    fn proptest_symmetric((input, paddings) in strat(false)) {
+        mirror_pad(input, paddings, "SYMMETRIC")?
+    }
+}
+
+#[test]
+fn reflect_2d() {
+    mirror_pad(tensor2(&[[1f32, 2., 3.], [4., 5., 6.]]), tensor2(&[[1i32, 1], [2, 2]]), "REFLECT")
+        .unwrap()
+}
+
+#[test]
+fn symmetric_2d() {
+    mirror_pad(tensor2(&[[1f32, 2., 3.], [4., 5., 6.]]), tensor2(&[[1i32, 1], [2, 2]]), "SYMMETRIC")
+        .unwrap()
+}
@@ -0,0 +1,71 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{DtFloat, DtInt32};
+
+fn one_hot(indices: Tensor, depth: i32, axis: i64) -> proptest::test_runner::TestCaseResult {
+    let graph = tfpb::graph()
+        .node(placeholder_i32("indices"))
+        .node(const_i32("depth", &tensor0(depth)))
+        .node(const_f32("on", &tensor0(5f32)))
+        .node(const_f32("off", &tensor0(-1f32)))
+        .node(
+            tfpb::node()
+                .name("op")
+                .op("OneHot")
+                .input("indices")
+                .input("depth")
+                .input("on")
+                .input("off")
+                .attr("T", DtFloat)
+                .attr("TI", DtInt32)
+                .attr("axis", axis),
+        );
+    let graph = graph.write_to_bytes().unwrap();
+    compare(&graph, vec![("indices", indices)], "op")
+}
+
+fn strat() -> BoxedStrategy<(Tensor, i32, i64)> {
+    (vec(1usize..4, 0..3), 1i32..5)
+        .prop_flat_map(|(dims, depth)| {
+            let len = dims.iter().product::<usize>();
+            let rank = dims.len() as i64;
+            (Just(dims), vec(-1..depth + 1, len..len + 1), Just(depth), -1..rank + 1)
+        })
+        .prop_map(|(dims, indices, depth, axis)| {
+            let indices = tract_ndarray::Array::from_shape_vec(dims, indices).unwrap().into();
+            (indices, depth, axis)
+        })
+        .boxed()
+}
+
+proptest! {
+    #[test]
+    fn proptest_one_hot((indices, depth, axis) in strat()) {
+        one_hot(indices, depth, axis)?
+    }
+}
+
+#[test]
+fn one_hot_out_of_range() {
+    one_hot(tensor1(&[0i32, 2, -1, 3]), 3, -1).unwrap()
+}
+
+#[test]
+fn one_hot_axis_0() {
+    one_hot(tensor2(&[[0i32, 1], [2, 0]]), 3, 0).unwrap()
+}
@@ -0,0 +1,146 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{DtFloat, DtInt32};
+
+fn input(dims: Vec<usize>) -> Tensor {
+    let len = dims.iter().product::<usize>();
+    tract_ndarray::Array::from_shape_vec(dims, (0..len).map(|i| i as f32).collect()).unwrap().into()
+}
+
+// rank, axis, split count and an input whose axis length is a multiple of it
+fn strat() -> BoxedStrategy<(usize, usize, Tensor)> {
+    (1usize..4)
+        .prop_flat_map(|r| (0..r, 1usize..4, vec(1usize..4, r..r + 1)))
+        .prop_map(|(axis, n, mut dims)| {
+            dims[axis] *= n;
+            (axis, n, input(dims))
+        })
+        .boxed()
+}
+
+fn split(
+    axis: usize,
+    n: usize,
+    input: Tensor,
+    output: usize,
+) -> proptest::test_runner::TestCaseResult {
+    let graph = tfpb::graph()
+        .node(const_i32("axis", &tensor0(axis as i32)))
+        .node(placeholder_f32("input"))
+        .node(
+            tfpb::node()
+                .name("op")
+                .op("Split")
+                .input("axis")
+                .input("input")
+                .attr("T", DtFloat)
+                .attr("num_split", n as i64),
+        )
+        .node(
+            tfpb::node()
+                .name("output")
+                .op("Identity")
+                .input(format!("op:{}", output))
+                .attr("T", DtFloat),
+        );
+    let graph = graph.write_to_bytes().unwrap();
+    compare(&graph, vec![("input", input)], "output")
+}
+
+fn split_v(
+    axis: i32,
+    sizes: &[i32],
+    input: Tensor,
+    output: usize,
+) -> proptest::test_runner::TestCaseResult {
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(const_i32("sizes", &tensor1(sizes)))
+        .node(const_i32("axis", &tensor0(axis)))
+        .node(
+            tfpb::node()
+                .name("op")
+                .op("SplitV")
+                .input("input")
+                .input("sizes")
+                .input("axis")
+                .attr("T", DtFloat)
+                .attr("Tlen", DtInt32)
+                .attr("num_split", sizes.len() as i64),
+        )
+        .node(
+            tfpb::node()
+                .name("output")
+                .op("Identity")
+                .input(format!("op:{}", output))
+                .attr("T", DtFloat),
+        );
+    let graph = graph.write_to_bytes().unwrap();
+    compare(&graph, vec![("input", input)], "output")
+}
+
+fn unpack(axis: usize, input: Tensor, output: usize) -> proptest::test_runner::TestCaseResult {
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(
+            tfpb::node()
+                .name("op")
+                .op("Unpack")
+                .input("input")
+                .attr("T", DtFloat)
+                .attr("num", input.shape()[axis] as i64)
+                .attr("axis", axis as i64),
+        )
+        .node(
+            tfpb::node()
+                .name("output")
+                .op("Identity")
+                .input(format!("op:{}", output))
+                .attr("T", DtFloat),
+        );
+    let graph = graph.write_to_bytes().unwrap();
+    compare(&graph, vec![("input", input)], "output")
+}
+
+proptest! {
+    #[test]
+    fn proptest_split((axis, n, input) in strat(), output in 0usize..4) {
+        split(axis, n, input, output % n)?
+    }
+
+    #[test]
+    fn proptest_unpack((axis, _n, input) in strat(), output in 0usize..12) {
+        let output = output % input.shape()[axis];
+        unpack(axis, input, output)?
+    }
+}
+
+#[test]
+fn split_last_axis() {
+    split(1, 3, input(vec![2, 6]), 2).unwrap()
+}
+
+#[test]
+fn split_v_inferred_size() {
+    split_v(-1, &[1, -1, 2], input(vec![2, 6]), 1).unwrap()
+}
+
+#[test]
+fn unpack_middle_axis() {
+    unpack(1, input(vec![2, 3, 2]), 2).unwrap()
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+fn resize(
+    op: &str,
+    input: Tensor,
+    size: (usize, usize),
+    align_corners: bool,
+    half_pixel_centers: bool,
+) -> proptest::test_runner::TestCaseResult {
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(const_i32("size", &tensor1(&[size.0 as i32, size.1 as i32])))
+        .node(
+            tfpb::node()
+                .name("op")
+                .op(op)
+                .input("input")
+                .input("size")
+                .attr("T", DtFloat)
+                .attr("align_corners", align_corners)
+                .attr("half_pixel_centers", half_pixel_centers),
+        );
+    let graph = graph.write_to_bytes().unwrap();
+    compare(&graph, vec![("input", input)], "op")
+}
+
+// input, output size, and one of the three coordinate modes
+fn strat() -> BoxedStrategy<(Tensor, (usize, usize), bool, bool)> {
+    (1usize..3, 1usize..5, 1usize..5, 1usize..3, 1usize..8, 1usize..8, 0usize..3)
+        .prop_map(|(n, h, w, c, oh, ow, mode)| {
+            let dims = vec![n, h, w, c];
+            let len = dims.iter().product::<usize>();
+            let input =
+                tract_ndarray::Array::from_shape_vec(dims, (0..len).map(|i| i as f32).collect())
+                    .unwrap()
+                    .into();
+            (input, (oh, ow), mode == 1, mode == 2)
+        })
+        .boxed()
+}
+
+proptest! {
+    #[test]
+    fn proptest_bilinear((input, size, ac, hpc) in strat()) {
+        resize("ResizeBilinear", input, size, ac, hpc)?
+    }
+
+    #[test]
+    fn proptest_nearest((input, size, ac, hpc) in strat()) {
+        resize("ResizeNearestNeighbor", input, size, ac, hpc)?
+    }
+}
+
+fn image_2x2() -> Tensor {
+    tensor1(&[1f32, 2., 3., 4.]).into_shape(&[1, 2, 2, 1]).unwrap()
+}
+
+#[test]
+fn bilinear_upsample_half_pixel() {
+    resize("ResizeBilinear", image_2x2(), (4, 4), false, true).unwrap()
+}
+
+#[test]
+fn nearest_upsample_align_corners() {
+    resize("ResizeNearestNeighbor", image_2x2(), (3, 5), true, false).unwrap()
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{DtBool, DtFloat};
+
+fn select(op: &str, cond: Tensor, t: Tensor, e: Tensor) -> proptest::test_runner::TestCaseResult {
+    let graph = tfpb::graph()
+        .node(placeholder("cond", DtBool, None))
+        .node(placeholder_f32("t"))
+        .node(placeholder_f32("e"))
+        .node(
+            tfpb::node().name("op").op(op).input("cond").input("t").input("e").attr("T", DtFloat),
+        );
+    let graph = graph.write_to_bytes().unwrap();
+    compare(&graph, vec![("cond", cond), ("t", t), ("e", e)], "op")
+}
+
+fn values(dims: &[usize], offset: f32) -> Tensor {
+    let len = dims.iter().product::<usize>();
+    tract_ndarray::Array::from_shape_vec(dims, (0..len).map(|i| i as f32 + offset).collect())
+        .unwrap()
+        .into()
+}
+
+// condition shape (either the whole shape or its first dimension) and
+// branch shape
+fn strat() -> BoxedStrategy<(Tensor, Vec<usize>)> {
+    (vec(1usize..4, 1..4), any::<bool>())
+        .prop_flat_map(|(dims, vector)| {
+            let len = if vector { dims[0] } else { dims.iter().product::<usize>() };
+            (Just(dims), Just(vector), vec(any::<bool>(), len..len + 1))
+        })
+        .prop_map(|(dims, vector, cond)| {
+            let cond_shape = if vector { vec![dims[0]] } else { dims.clone() };
+            let cond = tract_ndarray::Array::from_shape_vec(cond_shape, cond).unwrap().into();
+            (cond, dims)
+        })
+        .boxed()
+}
+
+proptest! {
+    #[test]
+    fn proptest_select((cond, dims) in strat()) {
+        select("Select", cond, values(&dims, 0.), values(&dims, 100.))?
+    }
+}
+
+#[test]
+fn select_v2_broadcast() {
+    let cond = tensor1(&[true, false, true]);
+    select("SelectV2", cond.into(), values(&[2, 3], 0.), tensor0(-1f32)).unwrap()
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{DtFloat, DtInt32, DtInt64};
+
+fn arg_max(op: &str, input: Tensor, axis: i32) -> proptest::test_runner::TestCaseResult {
+    let graph =
+        tfpb::graph().node(placeholder_f32("input")).node(const_i32("axis", &tensor0(axis))).node(
+            tfpb::node()
+                .name("op")
+                .op(op)
+                .input("input")
+                .input("axis")
+                .attr("T", DtFloat)
+                .attr("Tidx", DtInt32)
+                .attr("output_type", DtInt64),
+        );
+    let graph = graph.write_to_bytes().unwrap();
+    compare(&graph, vec![("input", input)], "op")
+}
+
+// input and a possibly negative axis, with few distinct values to exercise
+// ties
+fn strat() -> BoxedStrategy<(Tensor, i32)> {
+    vec(1usize..4, 1..4)
+        .prop_flat_map(|dims| {
+            let len = dims.iter().product::<usize>();
+            let rank = dims.len() as i32;
+            (Just(dims), vec(0i8..3, len..len + 1), -rank..rank)
+        })
+        .prop_map(|(dims, values, axis)| {
+            let input = tract_ndarray::Array::from_shape_vec(
+                dims,
+                values.into_iter().map(|x| x as f32).collect(),
+            )
+            .unwrap()
+            .into();
+            (input, axis)
+        })
+        .boxed()
+}
+
+proptest! {
+    #[test]
+    fn proptest_arg_max((input, axis) in strat()) {
+        arg_max("ArgMax", input, axis)?
+    }
+
+    #[test]
+    fn proptest_arg_min((input, axis) in strat()) {
+        arg_max("ArgMin", input, axis)?
+    }
+}
+
+#[test]
+fn arg_max_ties() {
+    arg_max("ArgMax", tensor2(&[[1f32, 3., 3.], [2., 2., 0.]]), 1).unwrap()
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+fn batch_mat_mul(
+    a: Tensor,
+    b: Tensor,
+    adj_x: bool,
+    adj_y: bool,
+) -> proptest::test_runner::TestCaseResult {
+    let graph = tfpb::graph().node(placeholder_f32("a")).node(placeholder_f32("b")).node(
+        tfpb::node()
+            .name("op")
+            .op("BatchMatMulV2")
+            .input("a")
+            .input("b")
+            .attr("T", DtFloat)
+            .attr("adj_x", adj_x)
+            .attr("adj_y", adj_y),
+    );
+    let graph = graph.write_to_bytes().unwrap();
+    compare(&graph, vec![("a", a), ("b", b)], "op")
+}
+
+fn tensor(dims: Vec<usize>) -> BoxedStrategy<Tensor> {
+    let len = dims.iter().product::<usize>();
+    vec(-10i8..10, len..len + 1)
+        .prop_map(move |v| {
+            tract_ndarray::Array::from_shape_vec(
+                dims.clone(),
+                v.into_iter().map(|x| x as f32).collect(),
+            )
+            .unwrap()
+            .into()
+        })
+        .boxed()
+}
+
+// a and b with a common batch shape, possibly transposed
+fn strat() -> BoxedStrategy<(Tensor, Tensor, bool, bool)> {
+    (vec(1usize..3, 0..3), 1usize..4, 1usize..4, 1usize..4, any::<bool>(), any::<bool>())
+        .prop_flat_map(|(batch, m, k, n, adj_x, adj_y)| {
+            let mut a = batch.clone();
+            a.extend(if adj_x { [k, m] } else { [m, k] }.iter().cloned());
+            let mut b = batch;
+            b.extend(if adj_y { [n, k] } else { [k, n] }.iter().cloned());
+            (tensor(a), tensor(b), Just(adj_x), Just(adj_y))
+        })
+        .boxed()
+}
+
+proptest! {
+    #[test]
+    fn proptest((a, b, adj_x, adj_y) in strat()) {
+        batch_mat_mul(a, b, adj_x, adj_y)?
+    }
+}
+
+#[test]
+fn batch_mat_mul_adj_y() {
+    let a = tensor1(&[1f32, 2., 3., 4., 5., 6.]).into_shape(&[2, 1, 3]).unwrap();
+    let b = tensor1(&[1f32, 0., 1., 0., 1., 0.]).into_shape(&[2, 1, 3]).unwrap();
+    batch_mat_mul(a, b, false, true).unwrap()
+}
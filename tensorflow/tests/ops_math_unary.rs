@@ -0,0 +1,54 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+fn unary(op: &str, input: Tensor) -> proptest::test_runner::TestCaseResult {
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(tfpb::node().name("op").op(op).input("input").attr("T", DtFloat));
+    let graph = graph.write_to_bytes().unwrap();
+    compare(&graph, vec![("input", input)], "op")
+}
+
+fn strat(min: i32, max: i32) -> BoxedStrategy<Tensor> {
+    vec(min..max, 1..10)
+        .prop_map(|v| tensor1(&*v.iter().map(|&x| x as f32 / 10.).collect::<Vec<_>>()))
+        .boxed()
+}
+
+proptest! {
+    #[test]
+    fn proptest_exp(input in strat(-50, 50)) {
+        unary("Exp", input)?
+    }
+
+    #[test]
+    fn proptest_sqrt(input in strat(0, 1000)) {
+        unary("Sqrt", input)?
+    }
+
+    #[test]
+    fn proptest_square(input in strat(-100, 100)) {
+        unary("Square", input)?
+    }
+
+    #[test]
+    fn proptest_stop_gradient(input in strat(-100, 100)) {
+        unary("StopGradient", input)?
+    }
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+fn activation(
+    op: &str,
+    input: Tensor,
+    alpha: Option<f32>,
+) -> proptest::test_runner::TestCaseResult {
+    let mut node = tfpb::node().name("op").op(op).input("input").attr("T", DtFloat);
+    if let Some(alpha) = alpha {
+        node = node.attr("alpha", alpha);
+    }
+    let graph = tfpb::graph().node(placeholder_f32("input")).node(node);
+    let graph = graph.write_to_bytes().unwrap();
+    compare(&graph, vec![("input", input)], "op")
+}
+
+fn strat() -> BoxedStrategy<Tensor> {
+    vec(-100i32..100, 1..10)
+        .prop_map(|v| tensor1(&*v.iter().map(|&x| x as f32 / 10.).collect::<Vec<_>>()))
+        .boxed()
+}
+
+proptest! {
+    #[test]
+    fn proptest_leaky_relu(input in strat(), alpha in 0f32..1.) {
+        activation("LeakyRelu", input, Some(alpha))?
+    }
+
+    #[test]
+    fn proptest_elu(input in strat()) {
+        activation("Elu", input, None)?
+    }
+}
+
+#[test]
+fn leaky_relu_default_alpha() {
+    activation("LeakyRelu", tensor1(&[-2f32, -0.5, 0., 1.]), None).unwrap()
+}
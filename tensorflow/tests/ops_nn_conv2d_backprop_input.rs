@@ -0,0 +1,122 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+#[derive(Debug, Clone)]
+struct DeconvProblem {
+    input_sizes: [usize; 4],
+    kernel: Tensor,
+    data: Tensor,
+    stride: usize,
+    valid: bool,
+}
+
+impl DeconvProblem {
+    fn check(&self) -> proptest::test_runner::TestCaseResult {
+        let sizes = tensor1(&self.input_sizes.iter().map(|&d| d as i32).collect::<Vec<_>>());
+        let graph = tfpb::graph()
+            .node(const_i32("input_sizes", &sizes))
+            .node(const_f32("kernel", &self.kernel))
+            .node(placeholder_f32("data"))
+            .node(
+                tfpb::node()
+                    .name("op")
+                    .op("Conv2DBackpropInput")
+                    .input("input_sizes")
+                    .input("kernel")
+                    .input("data")
+                    .attr("T", DtFloat)
+                    .attr("strides", vec![1, self.stride as i64, self.stride as i64, 1])
+                    .attr("padding", if self.valid { "VALID" } else { "SAME" }),
+            );
+        let graph = graph.write_to_bytes().unwrap();
+        compare(&graph, vec![("data", self.data.clone())], "op")
+    }
+}
+
+fn tensor(dims: Vec<usize>) -> BoxedStrategy<Tensor> {
+    let len = dims.iter().product::<usize>();
+    vec(-9i8..9, len..len + 1)
+        .prop_map(move |v| {
+            tract_ndarray::Array::from_shape_vec(
+                dims.clone(),
+                v.into_iter().map(|x| x as f32).collect(),
+            )
+            .unwrap()
+            .into()
+        })
+        .boxed()
+}
+
+impl Arbitrary for DeconvProblem {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<DeconvProblem>;
+
+    // the output of the forward convolution is computed from the input size
+    // so that the problem is always consistent
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        (1usize..3, 1usize..3, 1usize..4, 1usize..4, 1usize..3, any::<bool>())
+            .prop_flat_map(|(kh, kw, ic, oc, stride, valid)| {
+                (1usize..3, kh..kh + 5, kw..kw + 5, Just((kh, kw, ic, oc, stride, valid)))
+            })
+            .prop_flat_map(|(n, h, w, (kh, kw, ic, oc, stride, valid))| {
+                let out = |i: usize, k: usize| {
+                    if valid {
+                        (i - k) / stride + 1
+                    } else {
+                        (i + stride - 1) / stride
+                    }
+                };
+                (
+                    Just([n, h, w, ic]),
+                    tensor(vec![kh, kw, ic, oc]),
+                    tensor(vec![n, out(h, kh), out(w, kw), oc]),
+                    Just(stride),
+                    Just(valid),
+                )
+            })
+            .prop_map(|(input_sizes, kernel, data, stride, valid)| DeconvProblem {
+                input_sizes,
+                kernel,
+                data,
+                stride,
+                valid,
+            })
+            .boxed()
+    }
+}
+
+proptest! {
+    #[test]
+    fn proptest(pb in any::<DeconvProblem>()) {
+        pb.check()?
+    }
+}
+
+#[test]
+fn stride_2_same() {
+    DeconvProblem {
+        input_sizes: [1, 4, 4, 1],
+        kernel: tensor4(&[[[[1f32]], [[1.]]], [[[1.]], [[1.]]]]),
+        data: tensor4(&[[[[1f32], [2.]], [[3.], [4.]]]]),
+        stride: 2,
+        valid: false,
+    }
+    .check()
+    .unwrap()
+}
@@ -0,0 +1,119 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+#[derive(Debug, Clone)]
+struct BatchNormProblem {
+    x: Tensor,
+    scale: Tensor,
+    offset: Tensor,
+    mean: Tensor,
+    variance: Tensor,
+    is_training: bool,
+}
+
+impl BatchNormProblem {
+    fn check(&self) -> proptest::test_runner::TestCaseResult {
+        let graph = tfpb::graph()
+            .node(placeholder_f32("x"))
+            .node(const_f32("scale", &self.scale))
+            .node(const_f32("offset", &self.offset))
+            .node(const_f32("mean", &self.mean))
+            .node(const_f32("variance", &self.variance))
+            .node(
+                tfpb::node()
+                    .name("op")
+                    .op("FusedBatchNormV3")
+                    .input("x")
+                    .input("scale")
+                    .input("offset")
+                    .input("mean")
+                    .input("variance")
+                    .attr("T", DtFloat)
+                    .attr("U", DtFloat)
+                    .attr("epsilon", 0.001f32)
+                    .attr("data_format", "NHWC")
+                    .attr("is_training", self.is_training),
+            );
+        let graph = graph.write_to_bytes().unwrap();
+        compare(&graph, vec![("x", self.x.clone())], "op")
+    }
+}
+
+fn tensor(dims: Vec<usize>, min: i8, max: i8) -> BoxedStrategy<Tensor> {
+    let len = dims.iter().product::<usize>();
+    vec(min..max, len..len + 1)
+        .prop_map(move |v| {
+            tract_ndarray::Array::from_shape_vec(
+                dims.clone(),
+                v.into_iter().map(|x| x as f32).collect(),
+            )
+            .unwrap()
+            .into()
+        })
+        .boxed()
+}
+
+impl Arbitrary for BatchNormProblem {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<BatchNormProblem>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        (1usize..3, 1usize..4, 1usize..4, 1usize..4, any::<bool>())
+            .prop_flat_map(|(n, h, w, c, is_training)| {
+                (
+                    tensor(vec![n, h, w, c], -10, 10),
+                    tensor(vec![c], 1, 5),
+                    tensor(vec![c], -5, 5),
+                    tensor(vec![c], -5, 5),
+                    tensor(vec![c], 1, 5),
+                    Just(is_training),
+                )
+            })
+            .prop_map(|(x, scale, offset, mean, variance, is_training)| BatchNormProblem {
+                x,
+                scale,
+                offset,
+                mean,
+                variance,
+                is_training,
+            })
+            .boxed()
+    }
+}
+
+proptest! {
+    #[test]
+    fn proptest(pb in any::<BatchNormProblem>()) {
+        pb.check()?
+    }
+}
+
+#[test]
+fn training_on_a_single_pixel() {
+    BatchNormProblem {
+        x: tensor4(&[[[[1f32, 2.]]], [[[3., 6.]]]]),
+        scale: tensor1(&[1f32, 2.]),
+        offset: tensor1(&[0f32, 1.]),
+        mean: tensor1(&[0f32, 0.]),
+        variance: tensor1(&[1f32, 1.]),
+        is_training: true,
+    }
+    .check()
+    .unwrap()
+}